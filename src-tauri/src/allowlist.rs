@@ -0,0 +1,184 @@
+use std::{path::Path, sync::RwLock};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{AppHandle, State};
+
+use crate::{
+  consent::ConsentManager,
+  error::{ErrorCode, OpenWorkError},
+  paths,
+  store::{read_state, write_state},
+};
+
+pub const ALLOWLIST_FILE: &str = "allowlist.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AllowlistSettings {
+  /// When off (the default) any directory can be opened.
+  pub enabled: bool,
+  pub roots: Vec<String>,
+}
+
+// Path checks happen deep inside helpers that have no access to Tauri state,
+// so the active allowlist lives in a process-wide slot loaded at startup.
+static ACTIVE: RwLock<Option<AllowlistSettings>> = RwLock::new(None);
+
+fn active() -> AllowlistSettings {
+  ACTIVE
+    .read()
+    .expect("allowlist lock poisoned")
+    .clone()
+    .unwrap_or_default()
+}
+
+fn set_active(settings: AllowlistSettings) {
+  *ACTIVE.write().expect("allowlist lock poisoned") = Some(settings);
+}
+
+/// Error for paths outside the allowlist; `details.path` lets the UI offer to
+/// approve the folder.
+pub fn needs_approval_error(path: &Path) -> OpenWorkError {
+  OpenWorkError::new(
+    ErrorCode::NeedsApproval,
+    format!("{} is outside your approved folders.", path.display()),
+  )
+  .with_details(json!({ "path": path.to_string_lossy() }))
+}
+
+/// Checks a canonical directory against the allowlist, when enabled.
+pub fn ensure_allowed(path: &Path) -> Result<(), OpenWorkError> {
+  let settings = active();
+  if !settings.enabled {
+    return Ok(());
+  }
+
+  let allowed = settings
+    .roots
+    .iter()
+    .filter_map(|root| paths::canonicalize(Path::new(root)).ok())
+    .any(|root| path.starts_with(root));
+
+  if allowed {
+    Ok(())
+  } else {
+    Err(needs_approval_error(path))
+  }
+}
+
+fn normalize(settings: AllowlistSettings) -> Result<AllowlistSettings, OpenWorkError> {
+  let mut roots: Vec<String> = Vec::new();
+  for root in settings.roots {
+    let root = paths::existing_dir(&root, "root")?.to_string_lossy().to_string();
+    if !roots.contains(&root) {
+      roots.push(root);
+    }
+  }
+  Ok(AllowlistSettings {
+    enabled: settings.enabled,
+    roots,
+  })
+}
+
+/// What `next` opens up compared to `current`, as the consent target and
+/// the question for the user; `None` when it only tightens the allowlist.
+fn loosened(current: &AllowlistSettings, next: &AllowlistSettings) -> Option<(String, String)> {
+  if !current.enabled {
+    return None;
+  }
+  if !next.enabled {
+    return Some(("off".to_string(), "Turn off the folder allowlist?".to_string()));
+  }
+  let added: Vec<&str> = next
+    .roots
+    .iter()
+    .filter(|root| !current.roots.contains(root))
+    .map(String::as_str)
+    .collect();
+  (!added.is_empty()).then(|| {
+    (
+      added.join("\n"),
+      format!("Allow OpenWork to open {}?", added.join(", ")),
+    )
+  })
+}
+
+pub fn init(app: &AppHandle) {
+  match read_state::<AllowlistSettings>(app, ALLOWLIST_FILE) {
+    Ok(settings) => set_active(settings),
+    Err(e) => {
+      // Fail closed: a corrupt file must not silently disable the allowlist.
+      tracing::error!(error = %e, "failed to load allowlist; allowing no roots until it is fixed");
+      set_active(AllowlistSettings {
+        enabled: true,
+        roots: Vec::new(),
+      });
+    }
+  }
+}
+
+#[tauri::command]
+pub fn allowlist_get() -> AllowlistSettings {
+  active()
+}
+
+/// Replaces the allowlist. Approving new folders or turning it off needs
+/// confirmation; removing folders or turning it on doesn't.
+#[tauri::command]
+pub fn allowlist_set(
+  app: AppHandle,
+  consent: State<ConsentManager>,
+  settings: AllowlistSettings,
+  confirmation_id: Option<String>,
+) -> Result<AllowlistSettings, OpenWorkError> {
+  let settings = normalize(settings)?;
+  if let Some((target, description)) = loosened(&active(), &settings) {
+    consent.require(
+      "allowlist_change",
+      &target,
+      &description,
+      confirmation_id.as_deref(),
+    )?;
+  }
+  write_state(&app, ALLOWLIST_FILE, &settings)?;
+  set_active(settings.clone());
+  Ok(settings)
+}
+
+/// Adds a folder to the allowlist once the user confirms it.
+#[tauri::command]
+pub fn allowlist_approve(
+  app: AppHandle,
+  consent: State<ConsentManager>,
+  path: String,
+  confirmation_id: Option<String>,
+) -> Result<AllowlistSettings, OpenWorkError> {
+  let mut settings = active();
+  settings.roots.push(path);
+  allowlist_set(app, consent, settings, confirmation_id)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn settings(enabled: bool, roots: &[&str]) -> AllowlistSettings {
+    AllowlistSettings {
+      enabled,
+      roots: roots.iter().map(|root| root.to_string()).collect(),
+    }
+  }
+
+  #[test]
+  fn confirms_only_loosening_changes() {
+    let current = settings(true, &["/code"]);
+    assert_eq!(loosened(&current, &settings(true, &[])), None);
+    assert_eq!(loosened(&settings(false, &[]), &settings(true, &["/"])), None);
+    assert_eq!(
+      loosened(&current, &settings(true, &["/code", "/"])).unwrap().0,
+      "/"
+    );
+    assert_eq!(loosened(&current, &settings(false, &["/code"])).unwrap().0, "off");
+  }
+}
@@ -0,0 +1,110 @@
+//! Flatpak and Snap packaging on Linux.
+//!
+//! A Flatpak build sees the runtime's `/usr` rather than the host's, and gets
+//! its own `XDG_CONFIG_HOME`/`XDG_DATA_HOME` under `~/.var/app`. Programs the
+//! user installed (opencode, git, package managers) are therefore started on
+//! the host through `flatpak-spawn --host` (see `exec`), and config paths use
+//! the host's XDG directories, which Flatpak exposes as `HOST_XDG_*`.
+//!
+//! A Snap points `HOME` and the XDG directories at `~/snap/<name>/<rev>`; the
+//! real home is in `SNAP_REAL_HOME`.
+
+use std::{env, ffi::OsString, path::PathBuf, process::Command, sync::OnceLock};
+
+use serde::Serialize;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum AppSandbox {
+  None,
+  Flatpak,
+  Snap,
+}
+
+/// The packaging sandbox the app runs in, detected once.
+pub fn current() -> AppSandbox {
+  static SANDBOX: OnceLock<AppSandbox> = OnceLock::new();
+  *SANDBOX.get_or_init(|| {
+    if cfg!(target_os = "linux")
+      && (env::var_os("FLATPAK_ID").is_some() || PathBuf::from("/.flatpak-info").exists())
+    {
+      AppSandbox::Flatpak
+    } else if cfg!(target_os = "linux") && env::var_os("SNAP").is_some() {
+      AppSandbox::Snap
+    } else {
+      AppSandbox::None
+    }
+  })
+}
+
+fn non_empty(value: Option<OsString>) -> Option<PathBuf> {
+  value.filter(|value| !value.is_empty()).map(PathBuf::from)
+}
+
+/// The user's real home directory when the sandbox remaps `HOME`.
+pub fn real_home() -> Option<PathBuf> {
+  match current() {
+    AppSandbox::Snap => non_empty(env::var_os("SNAP_REAL_HOME")),
+    _ => None,
+  }
+}
+
+/// A host XDG base directory (`XDG_CONFIG_HOME`, `XDG_DATA_HOME`, ...), or
+/// `None` to use the default under the home directory.
+pub fn xdg_dir(key: &str) -> Option<PathBuf> {
+  match current() {
+    AppSandbox::None => non_empty(env::var_os(key)),
+    AppSandbox::Flatpak => non_empty(env::var_os(format!("HOST_{key}"))),
+    AppSandbox::Snap => {
+      // Snap's own values point into the snap's private data.
+      let private: Vec<PathBuf> = ["SNAP_USER_DATA", "SNAP_USER_COMMON"]
+        .iter()
+        .filter_map(|var| non_empty(env::var_os(var)))
+        .collect();
+      non_empty(env::var_os(key)).filter(|dir| !private.iter().any(|root| dir.starts_with(root)))
+    }
+  }
+}
+
+/// A scratch directory the host sees at the same path. Flatpak's `/tmp` is
+/// private to the sandbox, but its cache directory lives in the real home.
+pub fn shared_temp_dir() -> PathBuf {
+  match current() {
+    AppSandbox::Flatpak => non_empty(env::var_os("XDG_CACHE_HOME")).unwrap_or_else(env::temp_dir),
+    _ => env::temp_dir(),
+  }
+}
+
+/// Host-side equivalent of `command` when running in Flatpak, `None` otherwise.
+///
+/// Only variables the caller set to something other than this process's own
+/// value are forwarded: the host program starts from the host's environment,
+/// and the sandbox's `PATH` means nothing there.
+pub fn host_command(command: &Command) -> Option<Command> {
+  if current() != AppSandbox::Flatpak {
+    return None;
+  }
+  let mut host = Command::new("flatpak-spawn");
+  // `--watch-bus` ends the host process along with this app.
+  host.arg("--host").arg("--watch-bus");
+  if let Some(dir) = command.get_current_dir() {
+    let mut arg = OsString::from("--directory=");
+    arg.push(dir);
+    host.arg(arg);
+  }
+  for (key, value) in command.get_envs() {
+    let Some(value) = value else {
+      continue;
+    };
+    if key == "PATH" || env::var_os(key).as_deref() == Some(value) {
+      continue;
+    }
+    let mut arg = OsString::from("--env=");
+    arg.push(key);
+    arg.push("=");
+    arg.push(value);
+    host.arg(arg);
+  }
+  host.arg(command.get_program()).args(command.get_args());
+  Some(host)
+}
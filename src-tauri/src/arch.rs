@@ -0,0 +1,214 @@
+//! CPU architecture of the machine and of the opencode binary.
+//!
+//! On Apple Silicon and Windows on ARM an x86_64 opencode still runs, under
+//! Rosetta or Prism, but slower and sometimes with broken native modules. The
+//! doctor compares the binary's architecture (read from its Mach-O, ELF or PE
+//! header) with the machine's and warns when they differ.
+//!
+//! The machine's architecture isn't always `std::env::consts::ARCH`: an x86_64
+//! build of OpenWork running under emulation reports x86_64 there too.
+
+use std::{env, fs::File, io::Read, path::Path, sync::OnceLock};
+
+use serde::Serialize;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Arch {
+  X86_64,
+  Aarch64,
+  X86,
+  Arm,
+}
+
+impl Arch {
+  fn from_target(arch: &str) -> Option<Self> {
+    match arch {
+      "x86_64" => Some(Arch::X86_64),
+      "aarch64" => Some(Arch::Aarch64),
+      "x86" => Some(Arch::X86),
+      "arm" => Some(Arch::Arm),
+      _ => None,
+    }
+  }
+
+  pub fn label(self) -> &'static str {
+    match self {
+      Arch::X86_64 => "x86_64",
+      Arch::Aarch64 => "arm64",
+      Arch::X86 => "x86",
+      Arch::Arm => "arm",
+    }
+  }
+}
+
+const HEADER_BYTES: usize = 4096;
+
+fn u16_at(bytes: &[u8], offset: usize, little_endian: bool) -> Option<u16> {
+  let raw: [u8; 2] = bytes.get(offset..offset + 2)?.try_into().ok()?;
+  Some(if little_endian {
+    u16::from_le_bytes(raw)
+  } else {
+    u16::from_be_bytes(raw)
+  })
+}
+
+fn u32_at(bytes: &[u8], offset: usize, little_endian: bool) -> Option<u32> {
+  let raw: [u8; 4] = bytes.get(offset..offset + 4)?.try_into().ok()?;
+  Some(if little_endian {
+    u32::from_le_bytes(raw)
+  } else {
+    u32::from_be_bytes(raw)
+  })
+}
+
+fn mach_cpu(cpu_type: u32) -> Option<Arch> {
+  match cpu_type {
+    0x0100_0007 => Some(Arch::X86_64),
+    0x0100_000c => Some(Arch::Aarch64),
+    7 => Some(Arch::X86),
+    12 => Some(Arch::Arm),
+    _ => None,
+  }
+}
+
+/// Architectures an executable header declares: one for ELF, PE and thin
+/// Mach-O files, several for universal binaries, none for scripts.
+fn arches_from_header(bytes: &[u8]) -> Vec<Arch> {
+  match bytes.get(..4) {
+    Some([0x7f, b'E', b'L', b'F']) => {
+      let little_endian = bytes.get(5) == Some(&1);
+      let arch = match u16_at(bytes, 18, little_endian) {
+        Some(0x3e) => Some(Arch::X86_64),
+        Some(0xb7) => Some(Arch::Aarch64),
+        Some(0x03) => Some(Arch::X86),
+        Some(0x28) => Some(Arch::Arm),
+        _ => None,
+      };
+      arch.into_iter().collect()
+    }
+    Some([0xcf, 0xfa, 0xed, 0xfe]) | Some([0xce, 0xfa, 0xed, 0xfe]) => {
+      u32_at(bytes, 4, true).and_then(mach_cpu).into_iter().collect()
+    }
+    Some([0xca, 0xfe, 0xba, 0xbe]) | Some([0xca, 0xfe, 0xba, 0xbf]) => {
+      let entry_size = if bytes[3] == 0xbf { 32 } else { 20 };
+      // Java class files share the magic; their version field is far larger.
+      let count = u32_at(bytes, 4, false)
+        .filter(|&count| count > 0 && count < 32)
+        .unwrap_or(0);
+      (0..count as usize)
+        .filter_map(|index| u32_at(bytes, 8 + index * entry_size, false))
+        .filter_map(mach_cpu)
+        .collect()
+    }
+    Some([b'M', b'Z', _, _]) => {
+      let arch = u32_at(bytes, 0x3c, true)
+        .map(|offset| offset as usize)
+        .filter(|&offset| bytes.get(offset..offset + 4) == Some(b"PE\0\0"))
+        .and_then(|offset| match u16_at(bytes, offset + 4, true) {
+          Some(0x8664) => Some(Arch::X86_64),
+          Some(0xaa64) => Some(Arch::Aarch64),
+          Some(0x014c) => Some(Arch::X86),
+          Some(0x01c4) => Some(Arch::Arm),
+          _ => None,
+        });
+      arch.into_iter().collect()
+    }
+    _ => Vec::new(),
+  }
+}
+
+/// Architectures `path` is built for; empty for launcher scripts and unknown
+/// formats.
+pub fn binary_arches(path: &Path) -> Vec<Arch> {
+  let mut header = Vec::with_capacity(HEADER_BYTES);
+  let read = File::open(path).and_then(|file| file.take(HEADER_BYTES as u64).read_to_end(&mut header));
+  match read {
+    Ok(_) => arches_from_header(&header),
+    Err(_) => Vec::new(),
+  }
+}
+
+/// Whether this process runs under Rosetta.
+#[cfg(target_os = "macos")]
+pub fn translated() -> bool {
+  static TRANSLATED: OnceLock<bool> = OnceLock::new();
+  *TRANSLATED.get_or_init(|| {
+    crate::exec::output(crate::exec::command("sysctl").args(["-n", "sysctl.proc_translated"]))
+      .map(|output| String::from_utf8_lossy(&output.stdout).trim() == "1")
+      .unwrap_or(false)
+  })
+}
+
+/// The machine's architecture, seen through any emulation of this process.
+pub fn host() -> Option<Arch> {
+  static HOST: OnceLock<Option<Arch>> = OnceLock::new();
+  *HOST.get_or_init(|| {
+    let native = Arch::from_target(env::consts::ARCH);
+    #[cfg(target_os = "macos")]
+    if native == Some(Arch::X86_64) && translated() {
+      return Some(Arch::Aarch64);
+    }
+    #[cfg(windows)]
+    if native != Some(Arch::Aarch64)
+      && env::var("PROCESSOR_IDENTIFIER").is_ok_and(|id| id.trim_start().starts_with("ARM"))
+    {
+      // Emulated processes see an x86 PROCESSOR_ARCHITECTURE, but the
+      // identifier still names the real CPU.
+      return Some(Arch::Aarch64);
+    }
+    native
+  })
+}
+
+/// A warning when `binary` can't run natively on this machine.
+pub fn mismatch_note(binary: &[Arch]) -> Option<String> {
+  let host = host()?;
+  if binary.is_empty() || binary.contains(&host) {
+    return None;
+  }
+  let built_for: Vec<&str> = binary.iter().map(|arch| arch.label()).collect();
+  Some(format!(
+    "opencode is built for {} but this machine is {}; it runs under emulation. Reinstall the {} build.",
+    built_for.join("/"),
+    host.label(),
+    host.label()
+  ))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn reads_elf_and_mach_o_headers() {
+    let mut elf = vec![0x7f, b'E', b'L', b'F', 2, 1];
+    elf.resize(20, 0);
+    elf[18] = 0xb7;
+    assert_eq!(arches_from_header(&elf), vec![Arch::Aarch64]);
+
+    let thin = [0xcf, 0xfa, 0xed, 0xfe, 0x07, 0x00, 0x00, 0x01];
+    assert_eq!(arches_from_header(&thin), vec![Arch::X86_64]);
+
+    let mut fat = vec![0xca, 0xfe, 0xba, 0xbe, 0, 0, 0, 2];
+    fat.extend([0x01, 0x00, 0x00, 0x07]);
+    fat.extend([0; 16]);
+    fat.extend([0x01, 0x00, 0x00, 0x0c]);
+    fat.extend([0; 16]);
+    assert_eq!(arches_from_header(&fat), vec![Arch::X86_64, Arch::Aarch64]);
+  }
+
+  #[test]
+  fn reads_pe_headers_and_ignores_scripts() {
+    let mut pe = vec![0u8; 0x90];
+    pe[..2].copy_from_slice(b"MZ");
+    pe[0x3c] = 0x80;
+    pe[0x80..0x84].copy_from_slice(b"PE\0\0");
+    pe[0x84..0x86].copy_from_slice(&0xaa64u16.to_le_bytes());
+    assert_eq!(arches_from_header(&pe), vec![Arch::Aarch64]);
+
+    assert!(arches_from_header(b"#!/usr/bin/env node\n").is_empty());
+    // A Java class file: same magic as a universal binary.
+    assert!(arches_from_header(&[0xca, 0xfe, 0xba, 0xbe, 0, 0, 0, 52]).is_empty());
+  }
+}
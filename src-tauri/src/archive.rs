@@ -0,0 +1,503 @@
+//! Zip and tar archives: atomic zip writing for exports and bundles, and
+//! streaming extraction for imports.
+//!
+//! Extraction never trusts the archive. Entry names that are absolute, climb
+//! out with `..` or use drive letters are rejected, links are skipped, and the
+//! entry count and the bytes actually written are capped so a small archive
+//! can't expand into a full disk. Everything lands in a staging folder that is
+//! renamed into place only once the whole archive has been read.
+
+use std::{
+  ffi::OsString,
+  fs::{self, File},
+  io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+  path::{Path, PathBuf},
+  time::{Duration, Instant},
+};
+
+use flate2::read::GzDecoder;
+use serde::Serialize;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::{disk_space, error::OpenWorkError, paths};
+
+pub const ARCHIVE_PROGRESS_EVENT: &str = "archive://progress";
+
+/// Minimum gap between extraction progress reports.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveResult {
+  pub path: String,
+  pub files: u64,
+  pub bytes: u64,
+}
+
+/// Writes `entries` (absolute path, archive name) into a zip at `dest`.
+///
+/// The archive is written next to `dest` first and renamed into place, so a
+/// failed export never leaves a truncated zip behind.
+pub fn write_zip(dest: &Path, entries: &[(PathBuf, String)]) -> Result<ArchiveResult, String> {
+  write_atomically(dest, |zip, options| {
+    let mut files = 0u64;
+    let mut bytes = 0u64;
+    for (path, name) in entries {
+      let mut source =
+        File::open(path).map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+      zip
+        .start_file(name.as_str(), options)
+        .map_err(|e| format!("Failed to add {name} to archive: {e}"))?;
+      bytes += io::copy(&mut source, zip).map_err(|e| format!("Failed to add {name} to archive: {e}"))?;
+      files += 1;
+    }
+    Ok((files, bytes))
+  })
+}
+
+/// Like [`write_zip`], for content generated in memory (archive name, bytes).
+pub fn write_zip_bytes(dest: &Path, entries: &[(String, Vec<u8>)]) -> Result<ArchiveResult, String> {
+  write_atomically(dest, |zip, options| {
+    let mut bytes = 0u64;
+    for (name, content) in entries {
+      zip
+        .start_file(name.as_str(), options)
+        .map_err(|e| format!("Failed to add {name} to archive: {e}"))?;
+      zip
+        .write_all(content)
+        .map_err(|e| format!("Failed to add {name} to archive: {e}"))?;
+      bytes += content.len() as u64;
+    }
+    Ok((entries.len() as u64, bytes))
+  })
+}
+
+type Zip = ZipWriter<BufWriter<File>>;
+
+fn write_atomically(
+  dest: &Path,
+  write_entries: impl FnOnce(&mut Zip, SimpleFileOptions) -> Result<(u64, u64), String>,
+) -> Result<ArchiveResult, String> {
+  if let Some(parent) = dest.parent() {
+    fs::create_dir_all(parent)
+      .map_err(|e| format!("Failed to create dir {}: {e}", parent.display()))?;
+  }
+
+  let tmp = dest.with_extension("zip.partial");
+  let file = File::create(&tmp).map_err(|e| format!("Failed to create {}: {e}", tmp.display()))?;
+
+  let mut zip = ZipWriter::new(BufWriter::new(file));
+  let options = SimpleFileOptions::default()
+    .compression_method(CompressionMethod::Deflated)
+    .large_file(true);
+
+  let result = write_entries(&mut zip, options).and_then(|counts| {
+    zip
+      .finish()
+      .map_err(|e| format!("Failed to finish archive: {e}"))?;
+    Ok(counts)
+  });
+  let (files, bytes) = match result {
+    Ok(counts) => counts,
+    Err(e) => {
+      let _ = fs::remove_file(&tmp);
+      return Err(e);
+    }
+  };
+
+  fs::rename(&tmp, dest).map_err(|e| format!("Failed to write {}: {e}", dest.display()))?;
+
+  Ok(ArchiveResult {
+    path: dest.to_string_lossy().to_string(),
+    files,
+    bytes,
+  })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+  Zip,
+  Tar,
+  TarGz,
+}
+
+impl ArchiveFormat {
+  /// Format from the file name; `None` for anything that can't be read.
+  pub fn detect(path: &Path) -> Option<Self> {
+    let name = path.file_name()?.to_string_lossy().to_lowercase();
+    if name.ends_with(".zip") {
+      Some(Self::Zip)
+    } else if name.ends_with(".tar") {
+      Some(Self::Tar)
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+      Some(Self::TarGz)
+    } else {
+      None
+    }
+  }
+}
+
+/// The archive's file name without its archive suffix, e.g. `review` for
+/// `review.tar.gz`.
+pub fn strip_archive_suffix(path: &Path) -> String {
+  let name = path
+    .file_name()
+    .map(|name| name.to_string_lossy().to_string())
+    .unwrap_or_default();
+  [".tar.gz", ".tgz", ".tar", ".zip"]
+    .iter()
+    .find_map(|suffix| {
+      let split = name.len().checked_sub(suffix.len())?;
+      name
+        .get(split..)
+        .filter(|tail| tail.eq_ignore_ascii_case(suffix))
+        .map(|_| name[..split].to_string())
+    })
+    .unwrap_or(name)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ArchiveLimits {
+  pub max_entries: u64,
+  /// Bytes written across all entries.
+  pub max_total_bytes: u64,
+  pub max_entry_bytes: u64,
+}
+
+impl Default for ArchiveLimits {
+  fn default() -> Self {
+    Self {
+      max_entries: 10_000,
+      max_total_bytes: 512 * 1024 * 1024,
+      max_entry_bytes: 256 * 1024 * 1024,
+    }
+  }
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveProgress {
+  pub archive: String,
+  pub entries: u64,
+  /// Known up front for zips only; tars are read as a stream.
+  pub entries_total: Option<u64>,
+  pub bytes: u64,
+}
+
+/// Turns an archive entry name into a relative path, or `None` when it is
+/// absolute, climbs out with `..`, or has a segment no platform can create.
+pub fn safe_entry_path(name: &str) -> Option<PathBuf> {
+  if name.starts_with(['/', '\\']) {
+    return None;
+  }
+  let mut path = PathBuf::new();
+  for part in name.split(['/', '\\']) {
+    if part.is_empty() || part == "." {
+      continue;
+    }
+    // Rejects `..`, drive letters (`C:`) and device names.
+    paths::file_name_segment(part, "archive entry").ok()?;
+    path.push(part);
+  }
+  (!path.as_os_str().is_empty()).then_some(path)
+}
+
+enum EntryKind {
+  Dir,
+  File { mode: Option<u32> },
+  /// Links and special files, which are skipped.
+  Other,
+}
+
+struct Extractor<'a, F: FnMut(&ArchiveProgress)> {
+  root: PathBuf,
+  limits: &'a ArchiveLimits,
+  progress: ArchiveProgress,
+  last_report: Instant,
+  on_progress: F,
+}
+
+impl<F: FnMut(&ArchiveProgress)> Extractor<'_, F> {
+  fn add(&mut self, name: &str, kind: EntryKind, reader: &mut dyn Read) -> Result<(), String> {
+    let relative = safe_entry_path(name).ok_or_else(|| format!("Unsafe path in archive: {name}"))?;
+    self.progress.entries += 1;
+    if self.progress.entries > self.limits.max_entries {
+      return Err(format!("Archive has more than {} entries", self.limits.max_entries));
+    }
+
+    let target = self.root.join(&relative);
+    match kind {
+      EntryKind::Dir => fs::create_dir_all(&target)
+        .map_err(|e| format!("Failed to create dir {}: {e}", paths::display(&target)))?,
+      EntryKind::File { mode } => {
+        if let Some(parent) = target.parent() {
+          fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create dir {}: {e}", paths::display(parent)))?;
+        }
+        let budget = self
+          .limits
+          .max_entry_bytes
+          .min(self.limits.max_total_bytes - self.progress.bytes);
+        let mut file = File::create(&target)
+          .map_err(|e| format!("Failed to create {}: {e}", paths::display(&target)))?;
+        // Count what is actually written; declared sizes can lie.
+        let written = io::copy(&mut reader.take(budget + 1), &mut file)
+          .map_err(|e| format!("Failed to extract {name}: {e}"))?;
+        if written > budget {
+          return Err(format!("{name} is larger than this archive is allowed to unpack"));
+        }
+        self.progress.bytes += written;
+        set_mode(&target, mode);
+      }
+      EntryKind::Other => tracing::debug!(entry = name, "skipping link or special file in archive"),
+    }
+
+    if self.last_report.elapsed() >= PROGRESS_INTERVAL {
+      self.last_report = Instant::now();
+      (self.on_progress)(&self.progress);
+    }
+    Ok(())
+  }
+}
+
+/// Keeps the executable bits archived scripts need, never setuid/setgid.
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: Option<u32>) {
+  use std::os::unix::fs::PermissionsExt;
+  if let Some(mode) = mode.filter(|mode| mode & 0o111 != 0) {
+    let _ = fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o755));
+  }
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: Option<u32>) {}
+
+fn open_archive(path: &Path) -> Result<(ArchiveFormat, BufReader<File>), String> {
+  let format = ArchiveFormat::detect(path)
+    .ok_or_else(|| format!("Unsupported archive format: {}", path.display()))?;
+  let file = File::open(path).map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+  Ok((format, BufReader::new(file)))
+}
+
+fn read_zip<F: FnMut(&ArchiveProgress)>(
+  reader: BufReader<File>,
+  extractor: &mut Extractor<'_, F>,
+) -> Result<(), String> {
+  let mut zip = ZipArchive::new(reader).map_err(|e| format!("Failed to read archive: {e}"))?;
+  extractor.progress.entries_total = Some(zip.len() as u64);
+  for index in 0..zip.len() {
+    let mut entry = zip
+      .by_index(index)
+      .map_err(|e| format!("Failed to read archive: {e}"))?;
+    let name = entry.name().to_string();
+    let kind = if entry.is_dir() {
+      EntryKind::Dir
+    } else if entry.is_symlink() {
+      EntryKind::Other
+    } else {
+      EntryKind::File {
+        mode: entry.unix_mode(),
+      }
+    };
+    extractor.add(&name, kind, &mut entry)?;
+  }
+  Ok(())
+}
+
+fn read_tar<R: Read, F: FnMut(&ArchiveProgress)>(
+  reader: R,
+  extractor: &mut Extractor<'_, F>,
+) -> Result<(), String> {
+  let mut tar = tar::Archive::new(reader);
+  let entries = tar.entries().map_err(|e| format!("Failed to read archive: {e}"))?;
+  for entry in entries {
+    let mut entry = entry.map_err(|e| format!("Failed to read archive: {e}"))?;
+    let name = entry
+      .path()
+      .map_err(|e| format!("Failed to read archive: {e}"))?
+      .to_string_lossy()
+      .to_string();
+    let header = entry.header();
+    let kind = match header.entry_type() {
+      tar::EntryType::Directory => EntryKind::Dir,
+      tar::EntryType::Regular | tar::EntryType::Continuous => EntryKind::File {
+        mode: header.mode().ok(),
+      },
+      _ => EntryKind::Other,
+    };
+    extractor.add(&name, kind, &mut entry)?;
+  }
+  Ok(())
+}
+
+/// The most unpacking `archive` can write: the sizes a zip declares, a tar's
+/// own size, or the size a gzip trailer records for the tar inside, capped
+/// at `limits`. The gzip trailer wraps past 4 GB, far beyond the limits.
+fn unpacked_size(archive: &Path, limits: &ArchiveLimits) -> Result<u64, String> {
+  let (format, mut reader) = open_archive(archive)?;
+  let read_error = |e: &dyn std::fmt::Display| format!("Failed to read archive: {e}");
+  let size = match format {
+    ArchiveFormat::Zip => {
+      let mut zip = ZipArchive::new(reader).map_err(|e| read_error(&e))?;
+      let mut total = 0u64;
+      for index in 0..zip.len() {
+        total = total.saturating_add(zip.by_index_raw(index).map_err(|e| read_error(&e))?.size());
+      }
+      total
+    }
+    ArchiveFormat::Tar => reader.get_ref().metadata().map_err(|e| read_error(&e))?.len(),
+    ArchiveFormat::TarGz => {
+      let len = reader.get_ref().metadata().map_err(|e| read_error(&e))?.len();
+      let mut trailer = [0u8; 4];
+      reader.seek(SeekFrom::End(-4)).map_err(|e| read_error(&e))?;
+      reader.read_exact(&mut trailer).map_err(|e| read_error(&e))?;
+      u64::from(u32::from_le_bytes(trailer)).max(len)
+    }
+  };
+  Ok(size.min(limits.max_total_bytes))
+}
+
+/// Unpacks `archive` into `dest`, which must not exist yet. `on_progress` is
+/// called as entries are written. Fails with `INSUFFICIENT_SPACE` up front
+/// when `dest`'s volume can't hold what the archive may expand to.
+pub fn extract(
+  archive: &Path,
+  dest: &Path,
+  limits: &ArchiveLimits,
+  on_progress: impl FnMut(&ArchiveProgress),
+) -> Result<ArchiveResult, OpenWorkError> {
+  if dest.exists() {
+    return Err(format!("{} already exists", paths::display(dest)).into());
+  }
+  disk_space::ensure(dest, unpacked_size(archive, limits)?)?;
+  let (format, reader) = open_archive(archive)?;
+
+  // Entries can nest deeper than the folder itself; see `paths::extended`.
+  let dest = &paths::extended(dest);
+  let mut staging = OsString::from(dest.as_os_str());
+  staging.push(".partial");
+  let staging = PathBuf::from(staging);
+  let _ = fs::remove_dir_all(&staging);
+  fs::create_dir_all(&staging)
+    .map_err(|e| format!("Failed to create dir {}: {e}", paths::display(&staging)))?;
+
+  let mut extractor = Extractor {
+    root: staging.clone(),
+    limits,
+    progress: ArchiveProgress {
+      archive: archive.to_string_lossy().to_string(),
+      ..ArchiveProgress::default()
+    },
+    last_report: Instant::now(),
+    on_progress,
+  };
+  let result = match format {
+    ArchiveFormat::Zip => read_zip(reader, &mut extractor),
+    ArchiveFormat::Tar => read_tar(reader, &mut extractor),
+    ArchiveFormat::TarGz => read_tar(GzDecoder::new(reader), &mut extractor),
+  }
+  .and_then(|()| {
+    fs::rename(&staging, dest).map_err(|e| format!("Failed to write {}: {e}", paths::display(dest)))
+  });
+  if let Err(e) = result {
+    let _ = fs::remove_dir_all(&staging);
+    return Err(e.into());
+  }
+
+  (extractor.on_progress)(&extractor.progress);
+  Ok(ArchiveResult {
+    path: paths::display(dest),
+    files: extractor.progress.entries,
+    bytes: extractor.progress.bytes,
+  })
+}
+
+/// Entry names in `archive` without unpacking it, up to `limit` entries.
+pub fn entry_names(archive: &Path, limit: usize) -> Result<Vec<String>, String> {
+  let (format, reader) = open_archive(archive)?;
+  let read_error = |e: &dyn std::fmt::Display| format!("Failed to read archive: {e}");
+  match format {
+    ArchiveFormat::Zip => {
+      let zip = ZipArchive::new(reader).map_err(|e| read_error(&e))?;
+      Ok(zip.file_names().take(limit).map(str::to_string).collect())
+    }
+    ArchiveFormat::Tar | ArchiveFormat::TarGz => {
+      let reader: Box<dyn Read> = match format {
+        ArchiveFormat::TarGz => Box::new(GzDecoder::new(reader)),
+        _ => Box::new(reader),
+      };
+      let mut tar = tar::Archive::new(reader);
+      let mut names = Vec::new();
+      for entry in tar.entries().map_err(|e| read_error(&e))?.take(limit) {
+        let entry = entry.map_err(|e| read_error(&e))?;
+        names.push(entry.path().map_err(|e| read_error(&e))?.to_string_lossy().to_string());
+      }
+      Ok(names)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("openwork-archive-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  #[test]
+  fn rejects_unsafe_entry_names() {
+    assert_eq!(safe_entry_path("skill/SKILL.md"), Some(PathBuf::from("skill/SKILL.md")));
+    assert_eq!(safe_entry_path("./skill//run.sh"), Some(PathBuf::from("skill/run.sh")));
+    assert_eq!(safe_entry_path("../evil"), None);
+    assert_eq!(safe_entry_path("skill/../../evil"), None);
+    assert_eq!(safe_entry_path("/etc/passwd"), None);
+    assert_eq!(safe_entry_path("\\windows\\evil"), None);
+    assert_eq!(safe_entry_path("C:\\evil"), None);
+    assert_eq!(safe_entry_path("./"), None);
+  }
+
+  #[test]
+  fn round_trips_and_limits_zips() {
+    let dir = temp_dir("zip");
+    let zip = dir.join("skill.zip");
+    write_zip_bytes(
+      &zip,
+      &[
+        ("review/SKILL.md".to_string(), b"# Review".to_vec()),
+        ("review/notes.txt".to_string(), vec![b'x'; 4096]),
+      ],
+    )
+    .unwrap();
+    assert_eq!(entry_names(&zip, 10).unwrap().len(), 2);
+    assert_eq!(unpacked_size(&zip, &ArchiveLimits::default()).unwrap(), 4096 + 8);
+
+    let out = dir.join("out");
+    let mut reports = 0;
+    let result = extract(&zip, &out, &ArchiveLimits::default(), |_| reports += 1).unwrap();
+    assert_eq!(result.bytes, 4096 + 8);
+    assert!(reports >= 1);
+    assert_eq!(fs::read_to_string(out.join("review/SKILL.md")).unwrap(), "# Review");
+
+    let small = ArchiveLimits {
+      max_entry_bytes: 1024,
+      ..ArchiveLimits::default()
+    };
+    assert!(extract(&zip, &dir.join("limited"), &small, |_| {}).is_err());
+    assert!(!dir.join("limited").exists());
+    assert!(!dir.join("limited.partial").exists());
+  }
+
+  #[test]
+  fn refuses_traversal_in_zips() {
+    let dir = temp_dir("slip");
+    let zip = dir.join("evil.zip");
+    write_zip_bytes(&zip, &[("../../escaped.txt".to_string(), b"boom".to_vec())]).unwrap();
+    assert!(extract(&zip, &dir.join("out"), &ArchiveLimits::default(), |_| {}).is_err());
+    assert!(!dir.join("escaped.txt").exists());
+    assert!(!dir.join("out").exists());
+  }
+}
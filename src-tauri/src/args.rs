@@ -0,0 +1,200 @@
+//! Validation for free-form strings that end up as arguments to external
+//! programs (package managers, git).
+//!
+//! Commands never rely on the shell, but a value starting with `-` or carrying
+//! an unexpected transport (`ext::`, `file://`) can still change what the
+//! program does, so anything outside a conservative character set is refused.
+
+use crate::error::OpenWorkError;
+
+const MAX_PACKAGE_SPEC_LEN: usize = 256;
+const MAX_PACKAGE_NAME_LEN: usize = 214;
+const MAX_VERSION_LEN: usize = 64;
+const MAX_GIT_URL_LEN: usize = 512;
+const MAX_REF_NAME_LEN: usize = 255;
+
+fn check_common(value: &str, label: &str, max_len: usize) -> Result<(), OpenWorkError> {
+  if value.is_empty() {
+    return Err(OpenWorkError::invalid_argument(format!("{label} is required")));
+  }
+  if value.len() > max_len {
+    return Err(OpenWorkError::invalid_argument(format!("{label} is too long (max {max_len} characters)")));
+  }
+  if value.starts_with('-') {
+    return Err(OpenWorkError::invalid_argument(format!("{label} must not start with '-': {value}")));
+  }
+  if value.chars().any(|c| c.is_whitespace() || c.is_control()) {
+    return Err(OpenWorkError::invalid_argument(format!(
+      "{label} must not contain whitespace or control characters"
+    )));
+  }
+  Ok(())
+}
+
+fn is_name_char(c: char) -> bool {
+  c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '~')
+}
+
+fn check_name_part(part: &str, label: &str, raw: &str) -> Result<(), OpenWorkError> {
+  if part.is_empty() || part.starts_with('.') || !part.chars().all(is_name_char) {
+    return Err(OpenWorkError::invalid_argument(format!("Invalid {label}: {raw}")));
+  }
+  Ok(())
+}
+
+/// An npm-style package name, optionally scoped (`@scope/name`).
+pub fn package_name(raw: &str, label: &str) -> Result<String, OpenWorkError> {
+  let name = raw.trim();
+  check_common(name, label, MAX_PACKAGE_NAME_LEN)?;
+
+  match name.strip_prefix('@') {
+    Some(scoped) => {
+      let (scope, rest) = scoped
+        .split_once('/')
+        .ok_or_else(|| OpenWorkError::invalid_argument(format!("Invalid {label}: {name}")))?;
+      check_name_part(scope, label, name)?;
+      check_name_part(rest, label, name)?;
+    }
+    None => check_name_part(name, label, name)?,
+  }
+
+  Ok(name.to_string())
+}
+
+/// A version, tag or semver range such as `1.2.3`, `^2.0.0`, `>=1.0.0-beta.1` or `latest`.
+pub fn version(raw: &str, label: &str) -> Result<String, OpenWorkError> {
+  let version = raw.trim();
+  check_common(version, label, MAX_VERSION_LEN)?;
+
+  let allowed =
+    |c: char| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+' | '_' | '^' | '~' | '<' | '>' | '=' | '*');
+  if !version.chars().all(allowed) {
+    return Err(OpenWorkError::invalid_argument(format!("Invalid {label}: {version}")));
+  }
+  Ok(version.to_string())
+}
+
+/// A remote git repository: `https://`, `ssh://`, `git+https://`, scp-style
+/// `git@host:owner/repo` or the `github:owner/repo` shorthand.
+pub fn git_url(raw: &str, label: &str) -> Result<String, OpenWorkError> {
+  let url = raw.trim();
+  check_common(url, label, MAX_GIT_URL_LEN)?;
+
+  let allowed = |c: char| {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '~' | '/' | ':' | '@' | '#' | '+' | '%' | '=')
+  };
+  if !url.chars().all(allowed) {
+    return Err(OpenWorkError::invalid_argument(format!("Invalid {label}: {url}")));
+  }
+
+  let rest = ["git+https://", "git+ssh://", "https://", "ssh://", "github:", "gitlab:", "bitbucket:"]
+    .iter()
+    .find_map(|prefix| url.strip_prefix(prefix));
+  let rest = match rest {
+    Some(rest) => rest,
+    // scp-style `user@host:path`; a `::` would select a git remote helper.
+    None if url.contains('@') && url.contains(':') && !url.contains("::") => url,
+    None => {
+      return Err(OpenWorkError::invalid_argument(format!(
+        "Unsupported {label} (expected https, ssh or a host shorthand): {url}"
+      )))
+    }
+  };
+  if rest.is_empty() || rest.starts_with('-') || rest.contains("..") {
+    return Err(OpenWorkError::invalid_argument(format!("Invalid {label}: {url}")));
+  }
+
+  Ok(url.to_string())
+}
+
+/// Whether a package spec should be treated as a git source rather than a registry name.
+fn looks_like_git(spec: &str) -> bool {
+  spec.contains("://")
+    || spec.starts_with("git@")
+    || spec.starts_with("github:")
+    || spec.starts_with("gitlab:")
+    || spec.starts_with("bitbucket:")
+    || spec.ends_with(".git")
+}
+
+/// A package argument for an installer: `name`, `@scope/name`, either with an
+/// optional `@version`, or a git URL.
+pub fn package_spec(raw: &str, label: &str) -> Result<String, OpenWorkError> {
+  let spec = raw.trim();
+  check_common(spec, label, MAX_PACKAGE_SPEC_LEN)?;
+
+  if looks_like_git(spec) {
+    return git_url(spec, label);
+  }
+
+  // The version separator is the last `@` that isn't the scope marker.
+  let body = spec.strip_prefix('@').unwrap_or(spec);
+  let (name, version_part) = match body.rfind('@') {
+    Some(index) => (&spec[..spec.len() - body.len() + index], Some(&body[index + 1..])),
+    None => (spec, None),
+  };
+
+  package_name(name, label)?;
+  if let Some(version_part) = version_part {
+    version(version_part, label)?;
+  }
+
+  Ok(spec.to_string())
+}
+
+/// Cheap pre-checks for a git ref name before it reaches `git check-ref-format`.
+pub fn ref_name(raw: &str, label: &str) -> Result<String, OpenWorkError> {
+  let name = raw.trim();
+  check_common(name, label, MAX_REF_NAME_LEN)?;
+  Ok(name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn accepts_registry_specs() {
+    assert!(package_spec("opkg-skills", "package").is_ok());
+    assert!(package_spec("@scope/name", "package").is_ok());
+    assert!(package_spec("@scope/name@^1.2.0", "package").is_ok());
+    assert!(package_spec("name@latest", "package").is_ok());
+  }
+
+  #[test]
+  fn rejects_suspicious_specs() {
+    assert!(package_spec("--registry=http://evil", "package").is_err());
+    assert!(package_spec("name; rm -rf /", "package").is_err());
+    assert!(package_spec("name$(whoami)", "package").is_err());
+    assert!(package_spec("@scope", "package").is_err());
+    assert!(package_spec("name@", "package").is_err());
+    assert!(package_spec(&"a".repeat(300), "package").is_err());
+  }
+
+  #[test]
+  fn handles_short_and_non_ascii_specs() {
+    assert!(package_spec("a", "package").is_ok());
+    assert!(package_spec("@", "package").is_err());
+    assert!(package_spec("é", "package").is_err());
+    assert!(package_spec("é@1", "package").is_err());
+    assert!(package_spec("@é/name@1", "package").is_err());
+    assert!(package_spec("name@é", "package").is_err());
+  }
+
+  #[test]
+  fn validates_git_urls() {
+    assert!(package_spec("https://github.com/acme/skills.git", "package").is_ok());
+    assert!(package_spec("git@github.com:acme/skills.git", "package").is_ok());
+    assert!(package_spec("github:acme/skills#v1.0.0", "package").is_ok());
+    assert!(git_url("ext::sh -c touch% /tmp/pwned", "url").is_err());
+    assert!(git_url("file:///etc", "url").is_err());
+    assert!(git_url("https://-oProxyCommand=x", "url").is_err());
+  }
+
+  #[test]
+  fn validates_versions() {
+    assert!(version(">=1.0.0-beta.1", "version").is_ok());
+    assert!(version("1.0 || 2.0", "version").is_err());
+    assert!(version("-1", "version").is_err());
+  }
+}
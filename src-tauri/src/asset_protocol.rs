@@ -0,0 +1,214 @@
+//! `project-asset://` serves files from the calling window's project to the
+//! webview, so generated images and HTML previews load without a dev server.
+//!
+//! `project-asset://localhost/reports/chart.png` maps to `<project>/reports/chart.png`
+//! (on Windows the webview spells it `http://project-asset.localhost/...`).
+//! Paths are resolved with the same containment checks as every other project
+//! path, and HTML is sandboxed so a preview can't reach the app.
+
+use std::{
+  fs::File,
+  io::{Read, Seek, SeekFrom},
+  path::Path,
+  thread,
+};
+
+use tauri::{
+  http::{header, Request, Response, StatusCode},
+  AppHandle, Manager, UriSchemeContext, UriSchemeResponder,
+};
+
+use crate::{paths, EngineManager};
+
+pub const SCHEME: &str = "project-asset";
+
+/// Largest single response; bigger files are served in ranges.
+const MAX_CHUNK_BYTES: u64 = 8 * 1024 * 1024;
+
+fn content_type(path: &Path) -> &'static str {
+  let ext = path
+    .extension()
+    .map(|ext| ext.to_string_lossy().to_lowercase())
+    .unwrap_or_default();
+  match ext.as_str() {
+    "png" => "image/png",
+    "jpg" | "jpeg" => "image/jpeg",
+    "gif" => "image/gif",
+    "webp" => "image/webp",
+    "svg" => "image/svg+xml",
+    "ico" => "image/x-icon",
+    "avif" => "image/avif",
+    "pdf" => "application/pdf",
+    "html" | "htm" => "text/html; charset=utf-8",
+    "css" => "text/css; charset=utf-8",
+    "js" | "mjs" => "text/javascript; charset=utf-8",
+    "json" | "map" => "application/json",
+    "wasm" => "application/wasm",
+    "csv" => "text/csv; charset=utf-8",
+    "md" | "markdown" => "text/markdown; charset=utf-8",
+    "txt" | "log" => "text/plain; charset=utf-8",
+    "mp4" => "video/mp4",
+    "webm" => "video/webm",
+    "mp3" => "audio/mpeg",
+    "wav" => "audio/wav",
+    "woff" => "font/woff",
+    "woff2" => "font/woff2",
+    "ttf" => "font/ttf",
+    _ => "application/octet-stream",
+  }
+}
+
+/// Decodes `%XX` escapes; `None` for malformed escapes or non-UTF-8 results.
+fn percent_decode(raw: &str) -> Option<String> {
+  let bytes = raw.as_bytes();
+  let mut out = Vec::with_capacity(bytes.len());
+  let mut i = 0;
+  while i < bytes.len() {
+    if bytes[i] == b'%' {
+      let hex = raw.get(i + 1..i + 3)?;
+      out.push(u8::from_str_radix(hex, 16).ok()?);
+      i += 3;
+    } else {
+      out.push(bytes[i]);
+      i += 1;
+    }
+  }
+  String::from_utf8(out).ok()
+}
+
+/// Parses a single `bytes=start-end` range against a file of `len` bytes.
+/// Returns the inclusive byte span, or `None` when it can't be satisfied.
+fn parse_range(header: &str, len: u64) -> Option<(u64, u64)> {
+  let spec = header.trim().strip_prefix("bytes=")?;
+  if spec.contains(',') || len == 0 {
+    return None;
+  }
+  let (start, end) = spec.split_once('-')?;
+  let (start, end) = match (start.trim(), end.trim()) {
+    ("", suffix) => {
+      let suffix: u64 = suffix.parse().ok()?;
+      (len.saturating_sub(suffix), len - 1)
+    }
+    (start, "") => (start.parse().ok()?, len - 1),
+    (start, end) => (start.parse().ok()?, end.parse::<u64>().ok()?.min(len - 1)),
+  };
+  (start <= end && start < len).then_some((start, end))
+}
+
+fn project_dir(app: &AppHandle, window: &str) -> Option<String> {
+  app
+    .state::<EngineManager>()
+    .with_window(window, |state| state.project_dir.clone())
+}
+
+fn text_response(status: StatusCode, message: &str) -> Response<Vec<u8>> {
+  Response::builder()
+    .status(status)
+    .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
+    .body(message.as_bytes().to_vec())
+    .expect("static response is valid")
+}
+
+fn serve(app: &AppHandle, window: &str, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+  let Some(project_dir) = project_dir(app, window) else {
+    return text_response(StatusCode::NOT_FOUND, "No project is open in this window.");
+  };
+  let Some(relative) = percent_decode(request.uri().path().trim_start_matches('/')) else {
+    return text_response(StatusCode::BAD_REQUEST, "Malformed path.");
+  };
+
+  let path = match paths::resolve_within(Path::new(&project_dir), &relative) {
+    Ok(path) if path.is_file() => path,
+    Ok(_) => return text_response(StatusCode::NOT_FOUND, "Not found."),
+    Err(e) => {
+      tracing::warn!(window, path = %relative, error = %e, "refused project asset");
+      return text_response(StatusCode::FORBIDDEN, "Path is outside the project.");
+    }
+  };
+
+  let Ok(mut file) = File::open(&path) else {
+    return text_response(StatusCode::NOT_FOUND, "Not found.");
+  };
+  let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+
+  let range = request
+    .headers()
+    .get(header::RANGE)
+    .and_then(|value| value.to_str().ok())
+    .map(|value| parse_range(value, len));
+  let (status, start, end) = match range {
+    Some(None) => {
+      return Response::builder()
+        .status(StatusCode::RANGE_NOT_SATISFIABLE)
+        .header(header::CONTENT_RANGE, format!("bytes */{len}"))
+        .body(Vec::new())
+        .expect("static response is valid");
+    }
+    Some(Some((start, end))) => (StatusCode::PARTIAL_CONTENT, start, end.min(start + MAX_CHUNK_BYTES - 1)),
+    None if len > MAX_CHUNK_BYTES => (StatusCode::PARTIAL_CONTENT, 0, MAX_CHUNK_BYTES - 1),
+    None => (StatusCode::OK, 0, len.saturating_sub(1)),
+  };
+
+  let mut body = Vec::new();
+  if len > 0 {
+    let read = file
+      .seek(SeekFrom::Start(start))
+      .and_then(|_| file.by_ref().take(end - start + 1).read_to_end(&mut body));
+    if let Err(e) = read {
+      tracing::warn!(path = %path.display(), error = %e, "failed to read project asset");
+      return text_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to read file.");
+    }
+  }
+
+  let content_type = content_type(&path);
+  let mut response = Response::builder()
+    .status(status)
+    .header(header::CONTENT_TYPE, content_type)
+    .header(header::ACCEPT_RANGES, "bytes")
+    .header(header::CACHE_CONTROL, "no-cache")
+    .header(header::X_CONTENT_TYPE_OPTIONS, "nosniff");
+  if status == StatusCode::PARTIAL_CONTENT {
+    response = response.header(header::CONTENT_RANGE, format!("bytes {start}-{end}/{len}"));
+  }
+  if content_type.starts_with("text/html") || content_type.starts_with("image/svg") {
+    // Opaque origin: scripts in a preview can run but can't reach the app.
+    response = response.header(header::CONTENT_SECURITY_POLICY, "sandbox allow-scripts");
+  }
+  response.body(body).expect("asset response is valid")
+}
+
+/// Handler for `register_asynchronous_uri_scheme_protocol`; reads happen off
+/// the webview's thread.
+pub fn handle(
+  ctx: UriSchemeContext<'_, tauri::Wry>,
+  request: Request<Vec<u8>>,
+  responder: UriSchemeResponder,
+) {
+  let app = ctx.app_handle().clone();
+  let window = ctx.webview_label().to_string();
+  thread::spawn(move || responder.respond(serve(&app, &window, &request)));
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn decodes_paths() {
+    assert_eq!(percent_decode("out/my%20chart.png").as_deref(), Some("out/my chart.png"));
+    assert_eq!(percent_decode("caf%C3%A9.html").as_deref(), Some("café.html"));
+    assert_eq!(percent_decode("bad%2"), None);
+    assert_eq!(percent_decode("bad%zz"), None);
+  }
+
+  #[test]
+  fn parses_ranges() {
+    assert_eq!(parse_range("bytes=0-99", 1000), Some((0, 99)));
+    assert_eq!(parse_range("bytes=900-", 1000), Some((900, 999)));
+    assert_eq!(parse_range("bytes=-100", 1000), Some((900, 999)));
+    assert_eq!(parse_range("bytes=0-5000", 1000), Some((0, 999)));
+    assert_eq!(parse_range("bytes=1000-", 1000), None);
+    assert_eq!(parse_range("bytes=0-1,5-9", 1000), None);
+    assert_eq!(parse_range("items=0-1", 1000), None);
+  }
+}
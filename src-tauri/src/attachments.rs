@@ -0,0 +1,165 @@
+use std::{
+  fs,
+  path::{Path, PathBuf},
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager};
+
+use crate::{error::OpenWorkError, paths, project::project_root};
+
+const MAX_ATTACHMENT_BYTES: u64 = 20 * 1024 * 1024;
+const UNSESSIONED: &str = "_pending";
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StagedAttachment {
+  pub path: String,
+  pub filename: String,
+  pub mime: String,
+  pub bytes: u64,
+  /// A ready-to-send `file` part for the engine's message API.
+  pub part: Value,
+}
+
+fn mime_for(path: &Path) -> Option<&'static str> {
+  let ext = path.extension()?.to_string_lossy().to_lowercase();
+  let mime = match ext.as_str() {
+    "png" => "image/png",
+    "jpg" | "jpeg" => "image/jpeg",
+    "gif" => "image/gif",
+    "webp" => "image/webp",
+    "pdf" => "application/pdf",
+    "json" => "application/json",
+    "csv" => "text/csv",
+    "md" | "markdown" => "text/markdown",
+    "html" | "htm" => "text/html",
+    "txt" | "log" | "yaml" | "yml" | "toml" | "xml" | "ini" | "rs" | "ts" | "tsx" | "js" | "jsx"
+    | "mjs" | "py" | "go" | "java" | "kt" | "swift" | "c" | "h" | "cpp" | "hpp" | "cs" | "rb"
+    | "php" | "sh" | "css" | "scss" | "sql" => "text/plain",
+    _ => return None,
+  };
+  Some(mime)
+}
+
+fn staging_root(app: &AppHandle) -> Result<PathBuf, String> {
+  if let Some(dir) = crate::portable::dir("cache") {
+    return Ok(dir.join("attachments"));
+  }
+  let dir = app
+    .path()
+    .app_cache_dir()
+    .map_err(|e| format!("Failed to resolve app cache dir: {e}"))?;
+  Ok(dir.join("attachments"))
+}
+
+fn session_dir(app: &AppHandle, session_id: Option<&str>) -> Result<PathBuf, String> {
+  let session = session_id.map(str::trim).filter(|s| !s.is_empty()).unwrap_or(UNSESSIONED);
+  if !session.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+    return Err(format!("Invalid session id: {session}"));
+  }
+  Ok(staging_root(app)?.join(session))
+}
+
+fn file_url(path: &Path) -> String {
+  let display = path.to_string_lossy().replace('\\', "/");
+  let encoded: String = display
+    .chars()
+    .map(|c| match c {
+      ' ' => "%20".to_string(),
+      '#' => "%23".to_string(),
+      '?' => "%3F".to_string(),
+      '%' => "%25".to_string(),
+      c => c.to_string(),
+    })
+    .collect();
+  if encoded.starts_with('/') {
+    format!("file://{encoded}")
+  } else {
+    format!("file:///{encoded}")
+  }
+}
+
+/// Copies `source_path` into the staging area for `session_id` after checking
+/// size and type. Relative sources resolve against the project directory.
+#[tauri::command]
+pub fn attachment_stage(
+  app: AppHandle,
+  project_dir: String,
+  source_path: String,
+  session_id: Option<String>,
+) -> Result<StagedAttachment, OpenWorkError> {
+  let root = project_root(&project_dir)?;
+
+  // Dropped files may come from anywhere; relative paths must stay in the project.
+  let source_input = paths::validate_input(&source_path, "sourcePath")?;
+  let source = if source_input.is_absolute() {
+    paths::canonicalize(&source_input)?
+  } else {
+    paths::resolve_within(&root, &source_path)?
+  };
+
+  let metadata =
+    fs::metadata(&source).map_err(|e| format!("Failed to read {}: {e}", source.display()))?;
+  if !metadata.is_file() {
+    return Err(OpenWorkError::invalid_argument(format!("Not a file: {}", source.display())));
+  }
+  if metadata.len() > MAX_ATTACHMENT_BYTES {
+    return Err(OpenWorkError::invalid_argument(format!(
+      "{} is too large ({} MB). Attachments are limited to {} MB.",
+      source.display(),
+      metadata.len() / (1024 * 1024),
+      MAX_ATTACHMENT_BYTES / (1024 * 1024)
+    )));
+  }
+
+  let mime = mime_for(&source).ok_or_else(|| {
+    OpenWorkError::invalid_argument(format!("Unsupported attachment type: {}", source.display()))
+  })?;
+
+  let filename = source
+    .file_name()
+    .map(|n| n.to_string_lossy().to_string())
+    .ok_or_else(|| format!("Invalid file name: {}", source.display()))?;
+
+  // A per-copy prefix keeps same-named files from different folders apart.
+  let stamp = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_nanos())
+    .unwrap_or_default();
+  let dir = session_dir(&app, session_id.as_deref())?;
+  fs::create_dir_all(&dir).map_err(|e| format!("Failed to create dir {}: {e}", dir.display()))?;
+  let dest = dir.join(format!("{stamp:x}-{filename}"));
+
+  fs::copy(&source, &dest)
+    .map_err(|e| format!("Failed to copy {} -> {}: {e}", source.display(), dest.display()))?;
+
+  Ok(StagedAttachment {
+    path: dest.to_string_lossy().to_string(),
+    part: json!({
+      "type": "file",
+      "mime": mime,
+      "filename": filename,
+      "url": file_url(&dest),
+    }),
+    filename,
+    mime: mime.to_string(),
+    bytes: metadata.len(),
+  })
+}
+
+/// Removes staged files for a session (or all sessions when `session_id` is empty).
+#[tauri::command]
+pub fn attachment_clear(app: AppHandle, session_id: Option<String>) -> Result<(), OpenWorkError> {
+  let dir = match session_id.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+    Some(session) => session_dir(&app, Some(session))?,
+    None => staging_root(&app)?,
+  };
+
+  if dir.exists() {
+    fs::remove_dir_all(&dir).map_err(|e| format!("Failed to remove {}: {e}", dir.display()))?;
+  }
+  Ok(())
+}
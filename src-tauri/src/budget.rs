@@ -0,0 +1,280 @@
+use std::{
+  collections::{HashMap, HashSet},
+  sync::Mutex,
+  thread,
+  time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::{
+  error::{ErrorCode, OpenWorkError},
+  history::HistoryDb,
+  relay::{EventRelay, RelayedEvent},
+  store::{read_state, write_state},
+  usage::{now_ms, start_of_month, summarize},
+  webhooks::{self, WebhookEvent},
+};
+
+pub const BUDGET_FILE: &str = "budgets.json";
+
+pub const BUDGET_WARNING_EVENT: &str = "budget://warning";
+pub const BUDGET_EXCEEDED_EVENT: &str = "budget://exceeded";
+
+/// Costed messages arrive in bursts while a session streams; one check per
+/// burst is enough.
+const CHECK_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BudgetSettings {
+  /// Monthly limit across all projects, in the engine's cost unit (USD).
+  pub monthly: Option<f64>,
+  /// Monthly limits keyed by project directory.
+  pub projects: HashMap<String, f64>,
+  /// Fraction of a budget at which a warning is emitted.
+  pub warn_at: f64,
+  /// Refuse to start the engine for a project that is over budget.
+  pub block_engine_start: bool,
+}
+
+impl Default for BudgetSettings {
+  fn default() -> Self {
+    Self {
+      monthly: None,
+      projects: HashMap::new(),
+      warn_at: 0.8,
+      block_engine_start: false,
+    }
+  }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetStatus {
+  /// `None` for the global budget.
+  pub project_dir: Option<String>,
+  pub limit: f64,
+  pub spent: f64,
+  pub level: BudgetLevel,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub enum BudgetLevel {
+  Ok,
+  Warning,
+  Exceeded,
+}
+
+#[derive(Default)]
+pub struct BudgetManager {
+  settings: Mutex<Option<BudgetSettings>>,
+  /// Last level alerted per scope with the month it was in, so each
+  /// threshold fires once per crossing and again in the next month.
+  alerted: Mutex<HashMap<Option<String>, (i64, BudgetLevel)>>,
+  /// Scopes with a check already scheduled.
+  pending: Mutex<HashSet<Option<String>>>,
+}
+
+impl BudgetManager {
+  fn settings(&self, app: &AppHandle) -> BudgetSettings {
+    let mut settings = self.settings.lock().expect("budget mutex poisoned");
+    settings
+      .get_or_insert_with(|| read_state(app, BUDGET_FILE).unwrap_or_default())
+      .clone()
+  }
+}
+
+fn level(spent: f64, limit: f64, warn_at: f64) -> BudgetLevel {
+  if limit <= 0.0 || spent >= limit {
+    BudgetLevel::Exceeded
+  } else if spent >= limit * warn_at {
+    BudgetLevel::Warning
+  } else {
+    BudgetLevel::Ok
+  }
+}
+
+fn scope_status(
+  db: &HistoryDb,
+  settings: &BudgetSettings,
+  project_dir: Option<&str>,
+  limit: f64,
+) -> Result<BudgetStatus, String> {
+  let spent = summarize(db, "month", project_dir)?.totals.cost;
+  Ok(BudgetStatus {
+    project_dir: project_dir.map(str::to_string),
+    limit,
+    spent,
+    level: level(spent, limit, settings.warn_at),
+  })
+}
+
+/// Month-to-date status for the global budget and, if given, one project.
+fn statuses(app: &AppHandle, project_dir: Option<&str>) -> Result<Vec<BudgetStatus>, String> {
+  let settings = app.state::<BudgetManager>().settings(app);
+  let db = app.state::<HistoryDb>();
+
+  let mut statuses = Vec::new();
+  if let Some(limit) = settings.monthly {
+    statuses.push(scope_status(&db, &settings, None, limit)?);
+  }
+  if let Some(project_dir) = project_dir {
+    if let Some(limit) = settings.projects.get(project_dir) {
+      statuses.push(scope_status(&db, &settings, Some(project_dir), *limit)?);
+    }
+  }
+  Ok(statuses)
+}
+
+pub fn check_and_alert(app: &AppHandle, project_dir: Option<&str>) {
+  let statuses = match statuses(app, project_dir) {
+    Ok(statuses) => statuses,
+    Err(e) => {
+      tracing::warn!(error = %e, "failed to compute budget status");
+      return;
+    }
+  };
+
+  let month = start_of_month(now_ms());
+  let manager = app.state::<BudgetManager>();
+  let mut alerted = manager.alerted.lock().expect("budget mutex poisoned");
+
+  for status in statuses {
+    let previous = alerted
+      .insert(status.project_dir.clone(), (month, status.level))
+      .filter(|(alerted_month, _)| *alerted_month == month)
+      .map_or(BudgetLevel::Ok, |(_, level)| level);
+    if status.level <= previous {
+      continue;
+    }
+    let event = match status.level {
+      BudgetLevel::Warning => BUDGET_WARNING_EVENT,
+      BudgetLevel::Exceeded => BUDGET_EXCEEDED_EVENT,
+      BudgetLevel::Ok => continue,
+    };
+    if status.level == BudgetLevel::Exceeded {
+      let scope = status.project_dir.as_deref().unwrap_or("all projects");
+      webhooks::send(
+        WebhookEvent::BudgetExceeded,
+        &format!(
+          "OpenWork budget exceeded for {scope}: ${:.2} of ${:.2} this month.",
+          status.spent, status.limit
+        ),
+        serde_json::json!(status),
+      );
+    }
+    let _ = app.emit(event, status);
+  }
+}
+
+fn handle_event(app: &AppHandle, relayed: &RelayedEvent) {
+  if relayed.event.get("type").and_then(|t| t.as_str()) != Some("message.updated") {
+    return;
+  }
+  let has_cost = relayed
+    .event
+    .pointer("/properties/info/cost")
+    .and_then(|c| c.as_f64())
+    .is_some_and(|c| c > 0.0);
+  if has_cost {
+    schedule_check(app, relayed.project_dir.clone());
+  }
+}
+
+/// Runs `check_and_alert` for `project_dir` after `CHECK_DELAY`, unless a
+/// check for it is already scheduled.
+fn schedule_check(app: &AppHandle, project_dir: Option<String>) {
+  let manager = app.state::<BudgetManager>();
+  if !manager
+    .pending
+    .lock()
+    .expect("budget mutex poisoned")
+    .insert(project_dir.clone())
+  {
+    return;
+  }
+
+  let app = app.clone();
+  thread::spawn(move || {
+    thread::sleep(CHECK_DELAY);
+    app
+      .state::<BudgetManager>()
+      .pending
+      .lock()
+      .expect("budget mutex poisoned")
+      .remove(&project_dir);
+    check_and_alert(&app, project_dir.as_deref());
+  });
+}
+
+/// Called before an engine starts; errors when the user asked to block
+/// over-budget projects.
+pub fn ensure_engine_start_allowed(app: &AppHandle, project_dir: &str) -> Result<(), OpenWorkError> {
+  let settings = app.state::<BudgetManager>().settings(app);
+  if !settings.block_engine_start {
+    return Ok(());
+  }
+
+  let exceeded = statuses(app, Some(project_dir))?
+    .into_iter()
+    .find(|status| status.level == BudgetLevel::Exceeded);
+
+  match exceeded {
+    Some(status) => {
+      let message = format!(
+        "Budget exceeded: ${:.2} spent of ${:.2} this month{}. Raise the budget or disable blocking to start the engine.",
+        status.spent,
+        status.limit,
+        status
+          .project_dir
+          .as_ref()
+          .map(|dir| format!(" for {dir}"))
+          .unwrap_or_default()
+      );
+      Err(OpenWorkError::new(ErrorCode::BudgetExceeded, message).with_details(serde_json::json!(status)))
+    }
+    None => Ok(()),
+  }
+}
+
+pub fn init(app: &AppHandle) {
+  app.manage(BudgetManager::default());
+  app.state::<EventRelay>().subscribe(handle_event);
+}
+
+#[tauri::command]
+pub fn budget_get(app: AppHandle, manager: State<BudgetManager>) -> BudgetSettings {
+  manager.settings(&app)
+}
+
+#[tauri::command]
+pub fn budget_set(
+  app: AppHandle,
+  manager: State<BudgetManager>,
+  settings: BudgetSettings,
+) -> Result<BudgetSettings, OpenWorkError> {
+  if !(0.0..=1.0).contains(&settings.warn_at) {
+    return Err(OpenWorkError::invalid_argument("warnAt must be between 0 and 1"));
+  }
+  if settings.monthly.is_some_and(|m| m < 0.0) || settings.projects.values().any(|v| *v < 0.0) {
+    return Err(OpenWorkError::invalid_argument("budgets must not be negative"));
+  }
+
+  write_state(&app, BUDGET_FILE, &settings)?;
+  *manager.settings.lock().expect("budget mutex poisoned") = Some(settings.clone());
+  // Re-evaluate against the new limits.
+  manager.alerted.lock().expect("budget mutex poisoned").clear();
+  Ok(settings)
+}
+
+#[tauri::command]
+pub fn budget_status(
+  app: AppHandle,
+  project_dir: Option<String>,
+) -> Result<Vec<BudgetStatus>, OpenWorkError> {
+  let project_dir = project_dir.map(|p| p.trim().to_string()).filter(|p| !p.is_empty());
+  Ok(statuses(&app, project_dir.as_deref())?)
+}
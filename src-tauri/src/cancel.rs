@@ -0,0 +1,189 @@
+//! Cancellation for long operations that run external programs.
+//!
+//! A `CancelToken` is shared between the command doing the work and the one
+//! that cancels it; the work checks it between steps and fails with
+//! `CANCELLED`, which the frontend tells apart from a failure. Programs run
+//! through `output` get their own process group on unix, so cancelling stops
+//! everything they started (an install script's `curl` and `tar` too), not
+//! just the shell: the group gets SIGTERM, which bwrap, firejail and
+//! flatpak-spawn pass on to their sandbox, then SIGKILL after a grace period.
+
+use std::{
+  io::Read,
+  process::{Child, Command, Output, Stdio},
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+  },
+  thread,
+  time::{Duration, Instant},
+};
+
+use crate::{
+  error::{ErrorCode, OpenWorkError},
+  exec,
+};
+
+/// How often a running program is checked for exit or cancellation.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// Between SIGTERM and SIGKILL.
+const KILL_GRACE: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+  pub fn cancel(&self) {
+    self.0.store(true, Ordering::SeqCst);
+  }
+
+  pub fn is_cancelled(&self) -> bool {
+    self.0.load(Ordering::SeqCst)
+  }
+
+  /// `CANCELLED` once `cancel` has been called.
+  pub fn check(&self, what: &str) -> Result<(), OpenWorkError> {
+    if self.is_cancelled() {
+      return Err(cancelled(what));
+    }
+    Ok(())
+  }
+
+  /// Sleeps for `duration`, waking early when cancelled; `false` then.
+  pub fn sleep(&self, duration: Duration) -> bool {
+    let until = Instant::now() + duration;
+    while !self.is_cancelled() {
+      let left = until.saturating_duration_since(Instant::now());
+      if left.is_zero() {
+        return true;
+      }
+      thread::sleep(left.min(POLL_INTERVAL));
+    }
+    false
+  }
+}
+
+pub fn cancelled(what: &str) -> OpenWorkError {
+  OpenWorkError::new(ErrorCode::Cancelled, format!("{what} was cancelled"))
+}
+
+fn collect(mut stream: impl Read + Send + 'static) -> thread::JoinHandle<Vec<u8>> {
+  thread::spawn(move || {
+    let mut buffer = Vec::new();
+    let _ = stream.read_to_end(&mut buffer);
+    buffer
+  })
+}
+
+/// Signals the process group `child` leads.
+#[cfg(unix)]
+fn signal_group(child: &Child, signal: i32) {
+  unsafe {
+    libc::killpg(child.id() as libc::pid_t, signal);
+  }
+}
+
+/// Ends `child` and everything it started.
+fn kill_tree(child: &mut Child) {
+  #[cfg(unix)]
+  {
+    // `child` itself too, for when it isn't a group leader: under Flatpak
+    // the group is set up on the host side.
+    signal_group(child, libc::SIGTERM);
+    unsafe {
+      libc::kill(child.id() as libc::pid_t, libc::SIGTERM);
+    }
+    let deadline = Instant::now() + KILL_GRACE;
+    while Instant::now() < deadline {
+      if matches!(child.try_wait(), Ok(Some(_))) {
+        break;
+      }
+      thread::sleep(POLL_INTERVAL);
+    }
+    signal_group(child, libc::SIGKILL);
+  }
+  let _ = child.kill();
+  let _ = child.wait();
+}
+
+/// Runs `command` to completion like `exec::output`, or until `token` is
+/// cancelled, which kills its process tree and fails with `CANCELLED`.
+/// `what` names the operation for messages, e.g. "Installing OpenCode".
+pub fn output(command: &mut Command, token: &CancelToken, what: &str) -> Result<Output, OpenWorkError> {
+  token.check(what)?;
+  #[cfg(unix)]
+  std::os::unix::process::CommandExt::process_group(command, 0);
+  command.stdout(Stdio::piped()).stderr(Stdio::piped());
+  let mut child = exec::spawn(command).map_err(|e| {
+    let program = command.get_program().to_string_lossy().to_string();
+    OpenWorkError::new(ErrorCode::Io, format!("Failed to run {program}: {e}"))
+  })?;
+  let stdout = child.stdout.take().map(collect);
+  let stderr = child.stderr.take().map(collect);
+  let join = |reader: Option<thread::JoinHandle<Vec<u8>>>| {
+    reader.and_then(|reader| reader.join().ok()).unwrap_or_default()
+  };
+
+  let status = loop {
+    if token.is_cancelled() {
+      kill_tree(&mut child);
+      tracing::info!(pid = child.id(), "cancelled program stopped");
+      return Err(cancelled(what));
+    }
+    match child.try_wait() {
+      Ok(Some(status)) => break status,
+      Ok(None) => thread::sleep(POLL_INTERVAL),
+      Err(e) => {
+        kill_tree(&mut child);
+        return Err(OpenWorkError::new(
+          ErrorCode::Io,
+          format!("Failed to wait for the program: {e}"),
+        ));
+      }
+    }
+  };
+  Ok(Output {
+    status,
+    stdout: join(stdout),
+    stderr: join(stderr),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn sleep_wakes_when_cancelled() {
+    let token = CancelToken::default();
+    assert!(token.sleep(Duration::from_millis(1)));
+    assert!(token.check("Install").is_ok());
+
+    let other = token.clone();
+    thread::spawn(move || {
+      thread::sleep(Duration::from_millis(50));
+      other.cancel();
+    });
+    let started = Instant::now();
+    assert!(!token.sleep(Duration::from_secs(30)));
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_eq!(token.check("Install").unwrap_err().code, ErrorCode::Cancelled);
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn cancelling_stops_the_process_tree() {
+    let token = CancelToken::default();
+    let canceller = token.clone();
+    thread::spawn(move || {
+      thread::sleep(Duration::from_millis(200));
+      canceller.cancel();
+    });
+    let started = Instant::now();
+    let mut command = Command::new("sh");
+    command.args(["-c", "sleep 30 & sleep 30"]);
+    let error = output(&mut command, &token, "Test").unwrap_err();
+    assert_eq!(error.code, ErrorCode::Cancelled);
+    assert!(started.elapsed() < Duration::from_secs(10));
+  }
+}
@@ -0,0 +1,347 @@
+//! `openwork-cli`: what the app does, from a terminal or a script.
+//!
+//! `doctor` and `config` run the app's own code in this process. Engine and
+//! skill commands need the app's state (the engine it supervises, consent
+//! dialogs), so they go through the control server of the running app,
+//! which is launched with `--headless` if it isn't running. Results are
+//! printed as JSON; errors go to stderr with a non-zero exit.
+
+use std::{
+  env, fs,
+  io::{self, Read},
+  path::{Path, PathBuf},
+  process::{ExitCode, Stdio},
+  thread,
+  time::{Duration, Instant},
+};
+
+use serde_json::{json, Value};
+
+use crate::{
+  control,
+  engine_cache::EngineCache,
+  error::{ErrorCode, OpenWorkError},
+  exec, paths,
+};
+
+const USAGE: &str = "Usage: openwork-cli <command>
+
+Commands:
+  doctor [--force]                         Check the opencode installation
+  engine start [DIR] [--attach]            Start the engine for DIR (default: current directory),
+                                           or use the one another OpenWork runs for it
+  engine stop                              Stop the engine
+  engine info                              Show the running engine
+  skill import SOURCE [--project DIR] [--name NAME] [--overwrite] [--install-dependencies]
+                                           Copy a skill folder into the project, and
+                                           install the opkg packages it needs
+  config get [--scope global|project] [--project DIR]
+  config set [--scope global|project] [--project DIR] [--file FILE] [--force]
+                                           Write the config from FILE or stdin
+  config edit [--scope global|project] [--project DIR]
+                                           Open the config in $VISUAL or $EDITOR
+
+Engine and skill commands use the running app, starting it in the
+background when needed.";
+
+/// Flags that take a value; every other flag is a switch.
+const VALUE_FLAGS: &[&str] = &["--project", "--scope", "--name", "--file"];
+const SWITCHES: &[&str] = &["--force", "--overwrite", "--attach", "--install-dependencies", "--help", "-h"];
+/// How long a background launch has to start its control server.
+const LAUNCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Default, PartialEq)]
+struct Args {
+  positional: Vec<String>,
+  values: Vec<(String, String)>,
+  switches: Vec<String>,
+}
+
+impl Args {
+  fn value(&self, flag: &str) -> Option<&str> {
+    self
+      .values
+      .iter()
+      .rev()
+      .find(|(name, _)| name == flag)
+      .map(|(_, value)| value.as_str())
+  }
+
+  fn switch(&self, flag: &str) -> bool {
+    self.switches.iter().any(|name| name == flag)
+  }
+
+  fn positional(&self, index: usize) -> Option<&str> {
+    self.positional.get(index).map(String::as_str)
+  }
+}
+
+fn parse_args(raw: &[String]) -> Result<Args, OpenWorkError> {
+  let mut args = Args::default();
+  let mut raw = raw.iter();
+  while let Some(arg) = raw.next() {
+    if let Some((flag, value)) = arg.split_once('=').filter(|(flag, _)| VALUE_FLAGS.contains(flag)) {
+      args.values.push((flag.to_string(), value.to_string()));
+    } else if VALUE_FLAGS.contains(&arg.as_str()) {
+      let value = raw
+        .next()
+        .ok_or_else(|| OpenWorkError::invalid_argument(format!("{arg} needs a value")))?;
+      args.values.push((arg.clone(), value.clone()));
+    } else if SWITCHES.contains(&arg.as_str()) {
+      args.switches.push(arg.clone());
+    } else if arg.starts_with('-') && arg.len() > 1 {
+      return Err(OpenWorkError::invalid_argument(format!("Unknown option {arg}")));
+    } else {
+      args.positional.push(arg.clone());
+    }
+  }
+  Ok(args)
+}
+
+pub fn main() -> ExitCode {
+  let raw: Vec<String> = env::args().skip(1).collect();
+  let result = parse_args(&raw).and_then(|args| {
+    if args.positional.is_empty() || args.switch("--help") || args.switch("-h") {
+      println!("{USAGE}");
+      return Ok(None);
+    }
+    run(&args).map(Some)
+  });
+  match result {
+    Ok(Some(value)) => {
+      println!("{}", serde_json::to_string_pretty(&value).unwrap_or_default());
+      ExitCode::SUCCESS
+    }
+    Ok(None) => ExitCode::SUCCESS,
+    Err(e) => {
+      let code = serde_json::to_value(e.code).unwrap_or_default();
+      eprintln!("error [{}]: {}", code.as_str().unwrap_or("INTERNAL"), e.message);
+      ExitCode::FAILURE
+    }
+  }
+}
+
+fn run(args: &Args) -> Result<Value, OpenWorkError> {
+  match (args.positional(0), args.positional(1)) {
+    (Some("doctor"), None) => to_value(crate::doctor_report(
+      &EngineCache::default(),
+      args.switch("--force"),
+    )),
+    (Some("engine"), Some("start")) => {
+      let dir = args.positional(2).unwrap_or(".");
+      let project_dir = paths::existing_dir(&absolute(dir)?, "project")?;
+      app_call(
+        "engine.start",
+        json!({ "projectDir": project_dir.to_string_lossy(), "attach": args.switch("--attach") }),
+      )
+    }
+    (Some("engine"), Some("stop")) => app_call("engine.stop", Value::Null),
+    (Some("engine"), Some("info")) => app_call("engine.info", Value::Null),
+    (Some("skill"), Some("import")) => {
+      let source = args
+        .positional(2)
+        .ok_or_else(|| OpenWorkError::invalid_argument("skill import needs a SOURCE folder"))?;
+      let params = json!({
+        "projectDir": project_dir(args)?,
+        "sourceDir": paths::existing_dir(&absolute(source)?, "sourceDir")?.to_string_lossy(),
+        "overwrite": args.switch("--overwrite"),
+        "name": args.value("--name"),
+        "installDependencies": args.switch("--install-dependencies"),
+      });
+      confirmed_call("skill.import", params)
+    }
+    (Some("config"), Some(action)) => config(args, action),
+    _ => Err(OpenWorkError::invalid_argument(format!(
+      "Unknown command: {}\n\n{USAGE}",
+      args.positional.join(" ")
+    ))),
+  }
+}
+
+fn to_value<T: serde::Serialize>(value: T) -> Result<Value, OpenWorkError> {
+  serde_json::to_value(value).map_err(|e| OpenWorkError::new(ErrorCode::Internal, e.to_string()))
+}
+
+/// `raw` against the current directory.
+fn absolute(raw: &str) -> Result<String, OpenWorkError> {
+  let cwd = env::current_dir().map_err(|e| format!("Failed to read the current directory: {e}"))?;
+  Ok(cwd.join(raw).to_string_lossy().to_string())
+}
+
+fn project_dir(args: &Args) -> Result<String, OpenWorkError> {
+  let dir = paths::existing_dir(&absolute(args.value("--project").unwrap_or("."))?, "project")?;
+  Ok(dir.to_string_lossy().to_string())
+}
+
+fn config(args: &Args, action: &str) -> Result<Value, OpenWorkError> {
+  let scope = args.value("--scope").unwrap_or("project");
+  let project_dir = match scope {
+    "project" => project_dir(args)?,
+    _ => String::new(),
+  };
+  let path = crate::resolve_opencode_config_path(scope, &project_dir)?;
+  match action {
+    "get" => to_value(crate::read_opencode_config(scope.to_string(), project_dir)?),
+    "set" => {
+      let content = match args.value("--file") {
+        Some(file) => fs::read_to_string(file).map_err(|e| format!("Failed to read {file}: {e}"))?,
+        None => {
+          let mut content = String::new();
+          io::stdin()
+            .read_to_string(&mut content)
+            .map_err(|e| format!("Failed to read stdin: {e}"))?;
+          content
+        }
+      };
+      let existing = fs::read_to_string(paths::extended(&path)).ok();
+      if existing.is_some_and(|existing| existing != content) && !args.switch("--force") {
+        return Err(
+          OpenWorkError::new(
+            ErrorCode::AlreadyExists,
+            format!("{} already exists. Pass --force to overwrite it.", path.display()),
+          )
+          .with_details(json!({ "path": path.to_string_lossy() })),
+        );
+      }
+      to_value(crate::write_config_file(&path, &content)?)
+    }
+    "edit" => {
+      let editor = ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| if cfg!(windows) { "notepad" } else { "vi" }.to_string());
+      let mut words = editor.split_whitespace();
+      let program = words.next().unwrap_or("vi");
+      let status = exec::command(program)
+        .args(words)
+        .arg(&path)
+        .status()
+        .map_err(|e| OpenWorkError::new(ErrorCode::ToolNotFound, format!("Failed to run {program}: {e}")))?;
+      if !status.success() {
+        return Err(OpenWorkError::new(
+          ErrorCode::Cancelled,
+          format!("{program} exited with {status}"),
+        ));
+      }
+      to_value(crate::read_opencode_config(scope.to_string(), project_dir)?)
+    }
+    _ => Err(OpenWorkError::invalid_argument(format!(
+      "Unknown config command {action}; expected get, set or edit"
+    ))),
+  }
+}
+
+fn endpoint_file() -> Result<PathBuf, OpenWorkError> {
+  control::endpoint_file()
+    .ok_or_else(|| OpenWorkError::new(ErrorCode::NotFound, "Unable to resolve the app data directory"))
+}
+
+/// Calls the running app, launching it in the background first if nothing
+/// answers.
+fn app_call(method: &str, params: Value) -> Result<Value, OpenWorkError> {
+  let file = endpoint_file()?;
+  match control::call(&file, method, params.clone()) {
+    Err(e) if e.code == ErrorCode::NotFound && control::call(&file, "ping", Value::Null).is_err() => {
+      launch_app(&file)?;
+      control::call(&file, method, params)
+    }
+    result => result,
+  }
+}
+
+/// Like `app_call`, but answers a `PENDING_CONFIRMATION` by showing the
+/// app's dialog and retrying once with the grant.
+fn confirmed_call(method: &str, mut params: Value) -> Result<Value, OpenWorkError> {
+  match app_call(method, params.clone()) {
+    Err(e) if e.code == ErrorCode::PendingConfirmation => {
+      let token = e
+        .details
+        .as_ref()
+        .and_then(|details| details.get("token"))
+        .cloned()
+        .unwrap_or_default();
+      let grant = app_call("confirm", json!({ "token": token }))?;
+      params["confirmationId"] = grant.get("confirmationId").cloned().unwrap_or_default();
+      app_call(method, params)
+    }
+    result => result,
+  }
+}
+
+/// Starts the app next to this binary with `--headless` and waits for its
+/// control server.
+fn launch_app(file: &Path) -> Result<(), OpenWorkError> {
+  let exe = env::current_exe().map_err(|e| format!("Failed to locate openwork-cli: {e}"))?;
+  let app = ["openwork", "OpenWork"]
+    .iter()
+    .map(|name| exe.with_file_name(format!("{name}{}", env::consts::EXE_SUFFIX)))
+    .find(|path| path.is_file())
+    .ok_or_else(|| {
+      OpenWorkError::new(
+        ErrorCode::NotFound,
+        format!(
+          "OpenWork is not running and was not found next to {}",
+          exe.display()
+        ),
+      )
+    })?;
+  let mut command = exec::command(&app);
+  command
+    .arg(control::HEADLESS_ARG)
+    .stdout(Stdio::null())
+    .stderr(Stdio::null());
+  exec::spawn(&mut command)
+    .map_err(|e| OpenWorkError::new(ErrorCode::Io, format!("Failed to start {}: {e}", app.display())))?;
+
+  let deadline = Instant::now() + LAUNCH_TIMEOUT;
+  while Instant::now() < deadline {
+    if control::call(file, "ping", Value::Null).is_ok() {
+      return Ok(());
+    }
+    thread::sleep(Duration::from_millis(250));
+  }
+  Err(
+    OpenWorkError::new(
+      ErrorCode::Io,
+      format!(
+        "OpenWork did not start its control server within {}s",
+        LAUNCH_TIMEOUT.as_secs()
+      ),
+    )
+    .retryable(),
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn strings(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+  }
+
+  #[test]
+  fn parses_arguments() {
+    let args = parse_args(&strings(&[
+      "skill",
+      "import",
+      "./skills/a",
+      "--project",
+      "/code/app",
+      "--name=b",
+      "--overwrite",
+    ]))
+    .expect("valid arguments");
+    assert_eq!(args.positional, strings(&["skill", "import", "./skills/a"]));
+    assert_eq!(args.value("--project"), Some("/code/app"));
+    assert_eq!(args.value("--name"), Some("b"));
+    assert!(args.switch("--overwrite"));
+    assert!(!args.switch("--force"));
+
+    let error = parse_args(&strings(&["config", "get", "--scope"])).expect_err("missing value");
+    assert_eq!(error.code, ErrorCode::InvalidArgument);
+    let error = parse_args(&strings(&["doctor", "--verbose"])).expect_err("unknown option");
+    assert_eq!(error.code, ErrorCode::InvalidArgument);
+  }
+}
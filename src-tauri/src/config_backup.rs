@@ -0,0 +1,479 @@
+//! Backups of OpenWork's settings and the opencode configs it manages.
+//!
+//! The scheduler's config backup job calls [`run`]. It copies OpenWork's
+//! settings files, the global opencode config and `AGENTS.md`, and the
+//! `opencode.json`/`opencode.jsonc` and `AGENTS.md` of each recent project
+//! into a new folder under `backups/`, unless nothing changed since the last
+//! one. A `manifest.json` there records where each file came from, so
+//! `backup_restore` can put it back. Folders written before the manifest
+//! existed hold only the settings files and the global `opencode.json`.
+//!
+//! Retention keeps the newest `keepLatest` backups plus the newest one of
+//! each day for `keepDays` days. A restore backs up the current files first,
+//! so it can be undone like any other change.
+
+use std::{
+  collections::BTreeSet,
+  fs,
+  path::{Path, PathBuf},
+  sync::Mutex,
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+use crate::{
+  consent::ConsentManager,
+  debug_bundle,
+  error::{ErrorCode, OpenWorkError},
+  paths, recent, resolve_opencode_config_path,
+  store::{app_state_path, read_state, write_state},
+};
+
+pub const BACKUP_SETTINGS_FILE: &str = "backup-settings.json";
+
+const BACKUP_DIR: &str = "backups";
+const MANIFEST_FILE: &str = "manifest.json";
+/// Files backed up from each project, relative to it.
+const PROJECT_FILES: &[&str] = &[
+  "opencode.json",
+  "opencode.jsonc",
+  ".opencode/opencode.json",
+  ".opencode/opencode.jsonc",
+  "AGENTS.md",
+];
+/// Files backed up from the global opencode config folder.
+const GLOBAL_FILES: &[&str] = &["opencode.json", "opencode.jsonc", "AGENTS.md"];
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+const MAX_KEEP: usize = 1000;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BackupSettings {
+  /// The newest backups always kept.
+  pub keep_latest: usize,
+  /// Also keeps the newest backup of each of the last this many days.
+  pub keep_days: usize,
+}
+
+impl Default for BackupSettings {
+  fn default() -> Self {
+    Self {
+      keep_latest: 10,
+      keep_days: 14,
+    }
+  }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum BackupScope {
+  /// OpenWork's own settings; restored files apply after a restart.
+  App,
+  Global,
+  Project,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupFile {
+  /// Path inside the backup folder, `/`-separated.
+  pub name: String,
+  pub scope: BackupScope,
+  /// Where the file is restored to.
+  pub source: String,
+  pub project_dir: Option<String>,
+  pub bytes: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct Manifest {
+  files: Vec<BackupFile>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupInfo {
+  pub id: String,
+  pub created_at: u64,
+  pub files: Vec<BackupFile>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupRestore {
+  pub restored: Vec<String>,
+  /// A backup of the files as they were; `None` when the newest backup
+  /// already matched them.
+  pub previous: Option<BackupInfo>,
+  /// OpenWork settings were restored and apply after a restart.
+  pub restart_needed: bool,
+}
+
+/// Serializes writing and pruning backups.
+static BACKUP_LOCK: Mutex<()> = Mutex::new(());
+
+fn now_ms() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_millis() as u64)
+    .unwrap_or(0)
+}
+
+/// The creation time a backup folder is named after.
+fn created_at(id: &str) -> Option<u64> {
+  (id.len() == 16).then(|| id.parse().ok()).flatten()
+}
+
+/// Backup ids (times) to keep under `settings`: the newest `keep_latest`,
+/// and the newest of each of the last `keep_days` days.
+fn retained(ids: &[u64], settings: &BackupSettings, now: u64) -> BTreeSet<u64> {
+  let mut newest_first = ids.to_vec();
+  newest_first.sort_unstable_by(|a, b| b.cmp(a));
+  let mut keep: BTreeSet<u64> = newest_first.iter().take(settings.keep_latest).copied().collect();
+  let today = now / DAY_MS;
+  let mut days = BTreeSet::new();
+  for id in newest_first {
+    let day = id / DAY_MS;
+    if today.saturating_sub(day) < settings.keep_days as u64 && days.insert(day) {
+      keep.insert(id);
+    }
+  }
+  keep
+}
+
+fn settings(app: &AppHandle) -> BackupSettings {
+  read_state(app, BACKUP_SETTINGS_FILE).unwrap_or_default()
+}
+
+fn global_dir() -> Option<PathBuf> {
+  resolve_opencode_config_path("global", "")
+    .ok()
+    .and_then(|path| path.parent().map(Path::to_path_buf))
+}
+
+/// The files to back up, with their content.
+fn sources(app: &AppHandle) -> Vec<(BackupFile, Vec<u8>)> {
+  let mut files = Vec::new();
+  let mut add = |name: String, scope, source: PathBuf, project_dir: Option<String>| {
+    if let Ok(content) = fs::read(paths::extended(&source)) {
+      let file = BackupFile {
+        name,
+        scope,
+        source: source.to_string_lossy().to_string(),
+        project_dir,
+        bytes: content.len() as u64,
+      };
+      files.push((file, content));
+    }
+  };
+  for name in debug_bundle::SETTINGS_FILES {
+    if let Ok(path) = app_state_path(app, name) {
+      add(format!("openwork/{name}"), BackupScope::App, path, None);
+    }
+  }
+  if let Some(dir) = global_dir() {
+    for name in GLOBAL_FILES {
+      add(
+        format!("global/{name}"),
+        BackupScope::Global,
+        dir.join(name),
+        None,
+      );
+    }
+  }
+  for (index, project_dir) in recent::list(app).into_iter().enumerate() {
+    let root = PathBuf::from(&project_dir);
+    if !root.is_dir() {
+      continue;
+    }
+    for name in PROJECT_FILES {
+      add(
+        format!("projects/{index}/{name}"),
+        BackupScope::Project,
+        root.join(name),
+        Some(project_dir.clone()),
+      );
+    }
+  }
+  files
+}
+
+/// The manifest of the backup in `dir`, or one made up for the flat folders
+/// written before manifests.
+fn manifest(app: &AppHandle, dir: &Path) -> Manifest {
+  if let Ok(text) = fs::read_to_string(dir.join(MANIFEST_FILE)) {
+    return serde_json::from_str(&text).unwrap_or_default();
+  }
+  let mut files = Vec::new();
+  for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+    let name = entry.file_name().to_string_lossy().to_string();
+    let bytes = entry.metadata().map(|meta| meta.len()).unwrap_or(0);
+    let (scope, source) = if debug_bundle::SETTINGS_FILES.contains(&name.as_str()) {
+      (BackupScope::App, app_state_path(app, &name).ok())
+    } else if name == "opencode.json" {
+      (BackupScope::Global, global_dir().map(|dir| dir.join(&name)))
+    } else {
+      continue;
+    };
+    if let Some(source) = source {
+      files.push(BackupFile {
+        name,
+        scope,
+        source: source.to_string_lossy().to_string(),
+        project_dir: None,
+        bytes,
+      });
+    }
+  }
+  files.sort_by(|a, b| a.name.cmp(&b.name));
+  Manifest { files }
+}
+
+/// Backup folders, oldest first.
+fn backup_dirs(app: &AppHandle) -> Result<Vec<(String, PathBuf)>, String> {
+  let root = app_state_path(app, BACKUP_DIR)?;
+  let mut dirs: Vec<(String, PathBuf)> = fs::read_dir(&root)
+    .into_iter()
+    .flatten()
+    .flatten()
+    .map(|entry| (entry.file_name().to_string_lossy().to_string(), entry.path()))
+    .filter(|(id, path)| created_at(id).is_some() && path.is_dir())
+    .collect();
+  dirs.sort();
+  Ok(dirs)
+}
+
+/// Whether the backup in `dir` holds exactly `files`.
+fn unchanged(app: &AppHandle, dir: &Path, files: &[(BackupFile, Vec<u8>)]) -> bool {
+  let saved = manifest(app, dir).files;
+  saved.len() == files.len()
+    && files.iter().all(|(file, content)| {
+      saved
+        .iter()
+        .find(|old| old.source == file.source)
+        .is_some_and(|old| fs::read(dir.join(&old.name)).is_ok_and(|previous| &previous == content))
+    })
+}
+
+/// Writes a new backup unless nothing changed since the newest, then applies
+/// the retention settings. Returns the new backup. Call with `BACKUP_LOCK`
+/// held.
+fn backup_locked(app: &AppHandle) -> Result<Option<BackupInfo>, String> {
+  let files = sources(app);
+  let mut dirs = backup_dirs(app)?;
+  if files.is_empty()
+    || dirs
+      .last()
+      .is_some_and(|(_, latest)| unchanged(app, latest, &files))
+  {
+    return Ok(None);
+  }
+
+  let created_at = now_ms().max(dirs.last().and_then(|(id, _)| self::created_at(id)).unwrap_or(0) + 1);
+  // Zero-padded millis sort in creation order.
+  let id = format!("{created_at:016}");
+  let dir = app_state_path(app, BACKUP_DIR)?.join(&id);
+  let staging = dir.with_extension("partial");
+  let _ = fs::remove_dir_all(&staging);
+  let write = || -> Result<(), String> {
+    for (file, content) in &files {
+      let path = staging.join(&file.name);
+      if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create dir {}: {e}", parent.display()))?;
+      }
+      fs::write(&path, content).map_err(|e| format!("Failed to write backup {}: {e}", file.name))?;
+    }
+    let manifest = Manifest {
+      files: files.iter().map(|(file, _)| file.clone()).collect(),
+    };
+    let text = serde_json::to_string_pretty(&manifest).map_err(|e| e.to_string())?;
+    fs::write(staging.join(MANIFEST_FILE), text)
+      .map_err(|e| format!("Failed to write backup manifest: {e}"))?;
+    fs::rename(&staging, &dir).map_err(|e| format!("Failed to write {}: {e}", dir.display()))
+  };
+  if let Err(e) = write() {
+    let _ = fs::remove_dir_all(&staging);
+    return Err(e);
+  }
+  dirs.push((id.clone(), dir));
+
+  let ids: Vec<u64> = dirs.iter().filter_map(|(id, _)| self::created_at(id)).collect();
+  let keep = retained(&ids, &settings(app), created_at);
+  for (old, path) in &dirs {
+    if self::created_at(old).is_some_and(|time| !keep.contains(&time)) {
+      let _ = fs::remove_dir_all(path);
+    }
+  }
+  tracing::info!(files = files.len(), "configs backed up");
+  Ok(Some(BackupInfo {
+    id,
+    created_at,
+    files: files.into_iter().map(|(file, _)| file).collect(),
+  }))
+}
+
+/// The scheduler's config backup job.
+pub fn run(app: &AppHandle) -> Result<(), String> {
+  let _guard = BACKUP_LOCK.lock().expect("backup lock poisoned");
+  backup_locked(app).map(|_| ())
+}
+
+/// Backups, newest first.
+#[tauri::command]
+pub fn backups_list(app: AppHandle) -> Result<Vec<BackupInfo>, OpenWorkError> {
+  let mut backups: Vec<BackupInfo> = backup_dirs(&app)?
+    .into_iter()
+    .filter_map(|(id, dir)| {
+      Some(BackupInfo {
+        created_at: created_at(&id)?,
+        files: manifest(&app, &dir).files,
+        id,
+      })
+    })
+    .collect();
+  backups.reverse();
+  Ok(backups)
+}
+
+/// Puts files from a backup back in place: those named in `files`, or all
+/// of them. The current files are backed up first.
+#[tauri::command]
+pub fn backup_restore(
+  app: AppHandle,
+  consent: State<ConsentManager>,
+  backup_id: String,
+  files: Option<Vec<String>>,
+  confirmation_id: Option<String>,
+) -> Result<BackupRestore, OpenWorkError> {
+  let _guard = BACKUP_LOCK.lock().expect("backup lock poisoned");
+  let (_, dir) = backup_dirs(&app)?
+    .into_iter()
+    .find(|(id, _)| *id == backup_id)
+    .ok_or_else(|| OpenWorkError::new(ErrorCode::NotFound, format!("No backup {backup_id}")))?;
+  let mut selected = manifest(&app, &dir).files;
+  if let Some(names) = &files {
+    if let Some(missing) = names
+      .iter()
+      .find(|name| !selected.iter().any(|file| &file.name == *name))
+    {
+      return Err(OpenWorkError::new(
+        ErrorCode::NotFound,
+        format!("Backup {backup_id} has no {missing}"),
+      ));
+    }
+    selected.retain(|file| names.contains(&file.name));
+  }
+  if selected.is_empty() {
+    return Err(OpenWorkError::invalid_argument("Nothing to restore"));
+  }
+  // Projects may have moved or been removed from the allowed folders since.
+  for file in &selected {
+    if let Some(project_dir) = &file.project_dir {
+      paths::allowed_dir(project_dir, "projectDir")?;
+    }
+  }
+
+  let sources: Vec<&str> = selected.iter().map(|file| file.source.as_str()).collect();
+  consent.require(
+    "backup_restore",
+    &backup_id,
+    &format!("Restore these files from the backup?\n{}", sources.join("\n")),
+    confirmation_id.as_deref(),
+  )?;
+
+  let previous = backup_locked(&app)?;
+  let mut restored = Vec::new();
+  for file in &selected {
+    let content =
+      fs::read(dir.join(&file.name)).map_err(|e| format!("Failed to read backup {}: {e}", file.name))?;
+    let target = paths::extended(Path::new(&file.source));
+    if let Some(parent) = target.parent() {
+      fs::create_dir_all(parent)
+        .map_err(|e| format!("Failed to create dir {}: {e}", paths::display(parent)))?;
+    }
+    let tmp = target.with_extension("openwork-restore");
+    fs::write(&tmp, &content)
+      .and_then(|()| fs::rename(&tmp, &target))
+      .map_err(|e| {
+        let _ = fs::remove_file(&tmp);
+        format!("Failed to restore {}: {e}", file.source)
+      })?;
+    restored.push(file.source.clone());
+  }
+  crate::telemetry::record("feature.backup_restore");
+  tracing::info!(backup = %backup_id, files = restored.len(), "restored backup");
+  Ok(BackupRestore {
+    restored,
+    previous,
+    restart_needed: selected.iter().any(|file| file.scope == BackupScope::App),
+  })
+}
+
+#[tauri::command]
+pub fn backup_settings_get(app: AppHandle) -> BackupSettings {
+  settings(&app)
+}
+
+#[tauri::command]
+pub fn backup_settings_set(
+  app: AppHandle,
+  settings: BackupSettings,
+) -> Result<BackupSettings, OpenWorkError> {
+  if settings.keep_latest == 0 {
+    return Err(OpenWorkError::invalid_argument("keepLatest must be at least 1"));
+  }
+  if settings.keep_latest > MAX_KEEP || settings.keep_days > MAX_KEEP {
+    return Err(OpenWorkError::invalid_argument(format!(
+      "Backups are kept for at most {MAX_KEEP} entries or days"
+    )));
+  }
+  write_state(&app, BACKUP_SETTINGS_FILE, &settings)?;
+  Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn keeps_latest_and_one_per_day() {
+    let now = 100 * DAY_MS + 12;
+    let settings = BackupSettings {
+      keep_latest: 2,
+      keep_days: 3,
+    };
+    let ids = [
+      100 * DAY_MS + 10,
+      100 * DAY_MS + 5,
+      100 * DAY_MS + 1,
+      99 * DAY_MS + 7,
+      99 * DAY_MS + 3,
+      98 * DAY_MS,
+      97 * DAY_MS + 9,
+    ];
+    let keep: Vec<u64> = retained(&ids, &settings, now).into_iter().collect();
+    assert_eq!(
+      keep,
+      [98 * DAY_MS, 99 * DAY_MS + 7, 100 * DAY_MS + 5, 100 * DAY_MS + 10]
+    );
+
+    let latest_only = BackupSettings {
+      keep_latest: 1,
+      keep_days: 0,
+    };
+    assert_eq!(
+      retained(&ids, &latest_only, now).into_iter().collect::<Vec<_>>(),
+      [100 * DAY_MS + 10]
+    );
+  }
+
+  #[test]
+  fn reads_backup_ids() {
+    assert_eq!(created_at("0001700000000000"), Some(1_700_000_000_000));
+    assert_eq!(created_at("1700000000000"), None);
+    assert_eq!(created_at("0001700000000000.partial"), None);
+  }
+}
@@ -0,0 +1,768 @@
+//! The opencode config JSON schema, for the config editor.
+//!
+//! The schema is fetched from opencode.ai and cached for a day, so the editor
+//! keeps working offline with the last copy. The editor doesn't interpret it
+//! itself: `config_schema_describe` answers what a path means and which keys
+//! can go under it, and `config_schema_validate` checks a whole file. Only
+//! the parts of JSON Schema that opencode's schema uses are understood:
+//! local `$ref`s, `type`, `enum`/`const`, object and array members,
+//! `allOf`/`anyOf`/`oneOf`, numeric bounds, `pattern` and `deprecated`.
+
+use std::{
+  fs,
+  path::PathBuf,
+  sync::RwLock,
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+use crate::{
+  download,
+  error::{ErrorCode, OpenWorkError},
+  jsonc,
+};
+
+pub const SCHEMA_URL: &str = "https://opencode.ai/config.json";
+
+const CACHE_FILE: &str = "config-schema.json";
+const CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+const FETCH_TIMEOUT: Duration = Duration::from_secs(15);
+/// Bounds `$ref` chains and combinator nesting, which may be cyclic.
+const MAX_DEPTH: usize = 64;
+const MAX_DIAGNOSTICS: usize = 200;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigSchema {
+  pub url: String,
+  pub schema: Value,
+  pub fetched_at: u64,
+  /// A cached copy older than a day that couldn't be refreshed.
+  #[serde(default)]
+  pub stale: bool,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaCompletion {
+  pub key: String,
+  pub description: Option<String>,
+  pub types: Vec<String>,
+  pub required: bool,
+  pub deprecated: bool,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaInfo {
+  pub path: Vec<String>,
+  pub description: Option<String>,
+  pub types: Vec<String>,
+  /// Allowed values, from `enum` and `const`.
+  pub values: Vec<Value>,
+  pub default: Option<Value>,
+  pub deprecated: bool,
+  /// Keys the schema names for this object.
+  pub completions: Vec<SchemaCompletion>,
+  /// Whether keys beyond `completions` are accepted, as in `provider`.
+  pub other_keys: bool,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DiagnosticSeverity {
+  Error,
+  Warning,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigDiagnostic {
+  pub path: Vec<String>,
+  pub message: String,
+  pub severity: DiagnosticSeverity,
+  /// Set for syntax errors, 1-based.
+  pub line: Option<usize>,
+  pub column: Option<usize>,
+}
+
+impl ConfigDiagnostic {
+  fn at(path: &[String], message: impl Into<String>) -> Self {
+    Self {
+      path: path.to_vec(),
+      message: message.into(),
+      severity: DiagnosticSeverity::Error,
+      line: None,
+      column: None,
+    }
+  }
+}
+
+static LOADED: RwLock<Option<ConfigSchema>> = RwLock::new(None);
+
+fn now_ms() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_millis() as u64)
+    .unwrap_or(0)
+}
+
+fn is_fresh(schema: &ConfigSchema) -> bool {
+  now_ms().saturating_sub(schema.fetched_at) < CACHE_TTL.as_millis() as u64
+}
+
+fn cache_path(app: &AppHandle) -> Result<PathBuf, String> {
+  if let Some(dir) = crate::portable::dir("cache") {
+    return Ok(dir.join(CACHE_FILE));
+  }
+  let dir = app
+    .path()
+    .app_cache_dir()
+    .map_err(|e| format!("Failed to resolve app cache dir: {e}"))?;
+  Ok(dir.join(CACHE_FILE))
+}
+
+fn read_cache(app: &AppHandle) -> Option<ConfigSchema> {
+  let path = cache_path(app).ok()?;
+  let text = fs::read_to_string(&path).ok()?;
+  serde_json::from_str(&text)
+    .map_err(|e| tracing::warn!(error = %e, path = %path.display(), "ignoring config schema cache"))
+    .ok()
+}
+
+fn write_cache(app: &AppHandle, schema: &ConfigSchema) -> Result<(), String> {
+  let path = cache_path(app)?;
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+  }
+  let text = serde_json::to_string(schema).map_err(|e| format!("Failed to serialize config schema: {e}"))?;
+  fs::write(&path, text).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+fn fetch() -> Result<ConfigSchema, OpenWorkError> {
+  let network = |message: String| OpenWorkError::new(ErrorCode::Network, message).retryable();
+  let schema: Value = download::client()
+    .map_err(network)?
+    .get(SCHEMA_URL)
+    .timeout(FETCH_TIMEOUT)
+    .send()
+    .and_then(|response| response.error_for_status())
+    .and_then(|response| response.json())
+    .map_err(|e| network(format!("Failed to fetch the config schema: {e}")))?;
+  if !schema.is_object() {
+    return Err(network(format!("{SCHEMA_URL} did not return a JSON schema")));
+  }
+  Ok(ConfigSchema {
+    url: SCHEMA_URL.to_string(),
+    schema,
+    fetched_at: now_ms(),
+    stale: false,
+  })
+}
+
+/// The schema from memory, disk or the network, in that order. A copy past
+/// its day is refreshed, but still used when that fails, unless `force`.
+pub fn load(app: &AppHandle, force: bool) -> Result<ConfigSchema, OpenWorkError> {
+  let cached = LOADED
+    .read()
+    .expect("config schema lock poisoned")
+    .clone()
+    .or_else(|| read_cache(app));
+  if let Some(cached) = cached.as_ref().filter(|cached| !force && is_fresh(cached)) {
+    *LOADED.write().expect("config schema lock poisoned") = Some(cached.clone());
+    return Ok(cached.clone());
+  }
+  let schema = match (fetch(), cached) {
+    (Ok(schema), _) => {
+      if let Err(e) = write_cache(app, &schema) {
+        tracing::warn!(error = %e, "failed to cache config schema");
+      }
+      schema
+    }
+    (Err(e), Some(cached)) if !force => {
+      tracing::warn!(error = %e.message, "using stale config schema");
+      ConfigSchema {
+        stale: true,
+        ..cached
+      }
+    }
+    (Err(e), _) => return Err(e),
+  };
+  *LOADED.write().expect("config schema lock poisoned") = Some(schema.clone());
+  Ok(schema)
+}
+
+/// Follows local `$ref`s (`#/...`) from `node`.
+fn resolve<'a>(root: &'a Value, mut node: &'a Value) -> &'a Value {
+  for _ in 0..MAX_DEPTH {
+    let Some(pointer) = node
+      .get("$ref")
+      .and_then(Value::as_str)
+      .and_then(|r| r.strip_prefix('#'))
+    else {
+      break;
+    };
+    match root.pointer(pointer) {
+      Some(target) => node = target,
+      None => break,
+    }
+  }
+  node
+}
+
+/// `node` and everything it combines through `allOf`/`anyOf`/`oneOf`.
+fn variants<'a>(root: &'a Value, node: &'a Value) -> Vec<&'a Value> {
+  let mut out = Vec::new();
+  let mut stack = vec![(resolve(root, node), 0)];
+  while let Some((node, depth)) = stack.pop() {
+    out.push(node);
+    if depth >= MAX_DEPTH {
+      continue;
+    }
+    for key in ["allOf", "anyOf", "oneOf"] {
+      for branch in node.get(key).and_then(Value::as_array).into_iter().flatten() {
+        stack.push((resolve(root, branch), depth + 1));
+      }
+    }
+  }
+  out
+}
+
+fn pattern_matches(pattern: &str, key: &str) -> bool {
+  Regex::new(pattern).is_ok_and(|re| re.is_match(key))
+}
+
+/// Schemas that apply to member `key` of a value matching `node`.
+fn member<'a>(root: &'a Value, node: &'a Value, key: &str) -> Vec<&'a Value> {
+  let mut out = Vec::new();
+  for variant in variants(root, node) {
+    if let Some(schema) = variant.get("properties").and_then(|p| p.get(key)) {
+      out.push(schema);
+      continue;
+    }
+    let patterns: Vec<&Value> = variant
+      .get("patternProperties")
+      .and_then(Value::as_object)
+      .into_iter()
+      .flatten()
+      .filter(|(pattern, _)| pattern_matches(pattern, key))
+      .map(|(_, schema)| schema)
+      .collect();
+    if !patterns.is_empty() {
+      out.extend(patterns);
+    } else if let Some(additional) = variant.get("additionalProperties").filter(|a| a.is_object()) {
+      out.push(additional);
+    } else if let (Some(items), Ok(_)) = (variant.get("items"), key.parse::<usize>()) {
+      out.push(items);
+    }
+  }
+  out
+}
+
+fn schemas_at<'a>(root: &'a Value, path: &[String]) -> Vec<&'a Value> {
+  path.iter().fold(vec![root], |nodes, key| {
+    nodes
+      .into_iter()
+      .flat_map(|node| member(root, node, key))
+      .collect()
+  })
+}
+
+fn description(node: &Value) -> Option<String> {
+  ["description", "markdownDescription"]
+    .iter()
+    .find_map(|key| node.get(*key).and_then(Value::as_str))
+    .filter(|text| !text.trim().is_empty())
+    .map(str::to_string)
+}
+
+fn push_unique<T: PartialEq>(list: &mut Vec<T>, item: T) {
+  if !list.contains(&item) {
+    list.push(item);
+  }
+}
+
+fn type_names(node: &Value) -> Vec<String> {
+  match node.get("type") {
+    Some(Value::String(name)) => vec![name.clone()],
+    Some(Value::Array(names)) => names
+      .iter()
+      .filter_map(Value::as_str)
+      .map(str::to_string)
+      .collect(),
+    _ => Vec::new(),
+  }
+}
+
+fn types_of(variants: &[&Value]) -> Vec<String> {
+  let mut types = Vec::new();
+  for name in variants.iter().flat_map(|variant| type_names(variant)) {
+    push_unique(&mut types, name);
+  }
+  types
+}
+
+fn is_deprecated(node: &Value) -> bool {
+  node.get("deprecated").and_then(Value::as_bool).unwrap_or(false)
+}
+
+/// What the schema says about `path`, or `None` when it doesn't cover it.
+pub fn describe(root: &Value, path: &[String]) -> Option<SchemaInfo> {
+  let nodes = schemas_at(root, path);
+  if nodes.is_empty() {
+    return None;
+  }
+  let variants: Vec<&Value> = nodes.iter().flat_map(|node| variants(root, node)).collect();
+  let mut info = SchemaInfo {
+    path: path.to_vec(),
+    description: variants.iter().find_map(|variant| description(variant)),
+    types: types_of(&variants),
+    values: Vec::new(),
+    default: variants
+      .iter()
+      .find_map(|variant| variant.get("default").cloned()),
+    deprecated: variants.iter().any(|variant| is_deprecated(variant)),
+    completions: Vec::new(),
+    other_keys: false,
+  };
+  for variant in &variants {
+    for value in variant
+      .get("enum")
+      .and_then(Value::as_array)
+      .into_iter()
+      .flatten()
+    {
+      push_unique(&mut info.values, value.clone());
+    }
+    if let Some(value) = variant.get("const") {
+      push_unique(&mut info.values, value.clone());
+    }
+    info.other_keys |= variant.get("patternProperties").is_some()
+      || variant.get("additionalProperties").is_some_and(Value::is_object);
+    let required: Vec<&str> = variant
+      .get("required")
+      .and_then(Value::as_array)
+      .into_iter()
+      .flatten()
+      .filter_map(Value::as_str)
+      .collect();
+    for (key, schema) in variant
+      .get("properties")
+      .and_then(Value::as_object)
+      .into_iter()
+      .flatten()
+    {
+      if info.completions.iter().any(|completion| &completion.key == key) {
+        continue;
+      }
+      let property = variants(root, schema);
+      info.completions.push(SchemaCompletion {
+        key: key.clone(),
+        description: property.iter().find_map(|variant| description(variant)),
+        types: types_of(&property),
+        required: required.contains(&key.as_str()),
+        deprecated: property.iter().any(|variant| is_deprecated(variant)),
+      });
+    }
+  }
+  Some(info)
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+  match name {
+    "null" => value.is_null(),
+    "boolean" => value.is_boolean(),
+    "string" => value.is_string(),
+    "array" => value.is_array(),
+    "object" => value.is_object(),
+    "number" => value.is_number(),
+    "integer" => value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0),
+    _ => true,
+  }
+}
+
+fn expected(names: &[String]) -> String {
+  format!("Expected {}", names.join(" or "))
+}
+
+struct Validator<'a> {
+  root: &'a Value,
+  out: Vec<ConfigDiagnostic>,
+}
+
+impl<'a> Validator<'a> {
+  fn report(&mut self, diagnostic: ConfigDiagnostic) {
+    if self.out.len() < MAX_DIAGNOSTICS {
+      self.out.push(diagnostic);
+    }
+  }
+
+  fn check(&mut self, schema: &'a Value, value: &Value, path: &mut Vec<String>, depth: usize) {
+    if depth >= MAX_DEPTH {
+      return;
+    }
+    let schema = resolve(self.root, schema);
+    if schema == &Value::Bool(false) {
+      self.report(ConfigDiagnostic::at(path, "Not allowed here"));
+      return;
+    }
+    let types = type_names(schema);
+    if !types.is_empty() && !types.iter().any(|name| has_type(value, name)) {
+      self.report(ConfigDiagnostic::at(path, expected(&types)));
+      return;
+    }
+    if is_deprecated(schema) {
+      self.report(ConfigDiagnostic {
+        severity: DiagnosticSeverity::Warning,
+        ..ConfigDiagnostic::at(path, "Deprecated")
+      });
+    }
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+      if !allowed.contains(value) {
+        let names: Vec<String> = allowed.iter().map(Value::to_string).collect();
+        self.report(ConfigDiagnostic::at(
+          path,
+          format!("Expected one of {}", names.join(", ")),
+        ));
+      }
+    }
+    if let Some(constant) = schema.get("const").filter(|constant| *constant != value) {
+      self.report(ConfigDiagnostic::at(path, format!("Expected {constant}")));
+    }
+    self.check_scalar(schema, value, path);
+    match value {
+      Value::Object(members) => {
+        for key in schema
+          .get("required")
+          .and_then(Value::as_array)
+          .into_iter()
+          .flatten()
+        {
+          if let Some(key) = key.as_str().filter(|key| !members.contains_key(*key)) {
+            self.report(ConfigDiagnostic::at(
+              path,
+              format!("Missing required key \"{key}\""),
+            ));
+          }
+        }
+        for (key, member) in members {
+          path.push(key.clone());
+          self.check_member(schema, key, member, path, depth);
+          path.pop();
+        }
+      }
+      Value::Array(items) => {
+        if let Some(items_schema) = schema.get("items").filter(|items| !items.is_array()) {
+          for (index, item) in items.iter().enumerate() {
+            path.push(index.to_string());
+            self.check(items_schema, item, path, depth + 1);
+            path.pop();
+          }
+        }
+      }
+      _ => {}
+    }
+    for branch in schema
+      .get("allOf")
+      .and_then(Value::as_array)
+      .into_iter()
+      .flatten()
+    {
+      self.check(branch, value, path, depth + 1);
+    }
+    for key in ["anyOf", "oneOf"] {
+      if let Some(branches) = schema.get(key).and_then(Value::as_array) {
+        self.check_alternatives(branches, value, path, depth);
+      }
+    }
+  }
+
+  fn check_scalar(&mut self, schema: &Value, value: &Value, path: &[String]) {
+    if let Some(number) = value.as_f64() {
+      if let Some(minimum) = schema
+        .get("minimum")
+        .and_then(Value::as_f64)
+        .filter(|min| number < *min)
+      {
+        self.report(ConfigDiagnostic::at(path, format!("Must be at least {minimum}")));
+      }
+      if let Some(maximum) = schema
+        .get("maximum")
+        .and_then(Value::as_f64)
+        .filter(|max| number > *max)
+      {
+        self.report(ConfigDiagnostic::at(path, format!("Must be at most {maximum}")));
+      }
+    }
+    if let (Some(text), Some(pattern)) = (value.as_str(), schema.get("pattern").and_then(Value::as_str)) {
+      if Regex::new(pattern).is_ok_and(|re| !re.is_match(text)) {
+        self.report(ConfigDiagnostic::at(path, format!("Must match {pattern}")));
+      }
+    }
+  }
+
+  fn check_member(
+    &mut self,
+    schema: &'a Value,
+    key: &str,
+    member: &Value,
+    path: &mut Vec<String>,
+    depth: usize,
+  ) {
+    if let Some(property) = schema.get("properties").and_then(|p| p.get(key)) {
+      self.check(property, member, path, depth + 1);
+      return;
+    }
+    let mut matched = false;
+    for (pattern, property) in schema
+      .get("patternProperties")
+      .and_then(Value::as_object)
+      .into_iter()
+      .flatten()
+    {
+      if pattern_matches(pattern, key) {
+        matched = true;
+        self.check(property, member, path, depth + 1);
+      }
+    }
+    match schema.get("additionalProperties") {
+      _ if matched => {}
+      Some(Value::Bool(false)) => self.report(ConfigDiagnostic::at(path, format!("Unknown key \"{key}\""))),
+      Some(additional) if additional.is_object() => self.check(additional, member, path, depth + 1),
+      _ => {}
+    }
+  }
+
+  /// Passes when one branch does. Otherwise reports the branch that got
+  /// furthest into the value, or the types allowed when none got past the
+  /// value itself.
+  fn check_alternatives(
+    &mut self,
+    branches: &'a [Value],
+    value: &Value,
+    path: &mut Vec<String>,
+    depth: usize,
+  ) {
+    let mut results = Vec::new();
+    for branch in branches {
+      let mut nested = Validator {
+        root: self.root,
+        out: Vec::new(),
+      };
+      nested.check(branch, value, path, depth + 1);
+      let errors: Vec<ConfigDiagnostic> = nested
+        .out
+        .into_iter()
+        .filter(|diagnostic| diagnostic.severity == DiagnosticSeverity::Error)
+        .collect();
+      if errors.is_empty() {
+        return;
+      }
+      results.push(errors);
+    }
+    let Some(best) = results
+      .into_iter()
+      .min_by_key(|errors| (errors.iter().all(|d| d.path.len() == path.len()), errors.len()))
+    else {
+      return;
+    };
+    if best.iter().all(|diagnostic| diagnostic.path.len() == path.len()) {
+      let variants: Vec<&Value> = branches
+        .iter()
+        .flat_map(|branch| variants(self.root, branch))
+        .collect();
+      let types = types_of(&variants);
+      if !types.is_empty() && !types.iter().any(|name| has_type(value, name)) {
+        self.report(ConfigDiagnostic::at(path, expected(&types)));
+        return;
+      }
+    }
+    for diagnostic in best {
+      self.report(diagnostic);
+    }
+  }
+}
+
+/// Problems in `config` against `root`, errors and deprecations alike.
+pub fn validate(root: &Value, config: &Value) -> Vec<ConfigDiagnostic> {
+  let mut validator = Validator {
+    root,
+    out: Vec::new(),
+  };
+  validator.check(root, config, &mut Vec::new(), 0);
+  validator.out
+}
+
+/// Validates config text as the editor holds it, comments and all.
+pub fn validate_text(root: &Value, text: &str) -> Vec<ConfigDiagnostic> {
+  if text.trim().is_empty() {
+    return Vec::new();
+  }
+  match serde_json::from_str::<Value>(&jsonc::strip(text)) {
+    Ok(config) => validate(root, &config),
+    Err(e) => vec![ConfigDiagnostic {
+      line: Some(e.line()),
+      column: Some(e.column()),
+      ..ConfigDiagnostic::at(&[], format!("Invalid JSON: {e}"))
+    }],
+  }
+}
+
+/// The opencode config schema; `force` refetches it.
+#[tauri::command]
+pub fn config_schema_get(app: AppHandle, force: Option<bool>) -> Result<ConfigSchema, OpenWorkError> {
+  load(&app, force.unwrap_or(false))
+}
+
+/// Documentation and completions for one path in the config, as keys from
+/// the root (`["provider", "ollama", "options"]`).
+#[tauri::command]
+pub fn config_schema_describe(
+  app: AppHandle,
+  path: Vec<String>,
+) -> Result<Option<SchemaInfo>, OpenWorkError> {
+  let schema = load(&app, false)?;
+  Ok(describe(&schema.schema, &path))
+}
+
+#[tauri::command]
+pub fn config_schema_validate(app: AppHandle, text: String) -> Result<Vec<ConfigDiagnostic>, OpenWorkError> {
+  let schema = load(&app, false)?;
+  Ok(validate_text(&schema.schema, &text))
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+
+  fn path(keys: &[&str]) -> Vec<String> {
+    keys.iter().map(|key| key.to_string()).collect()
+  }
+
+  fn schema() -> Value {
+    json!({
+      "type": "object",
+      "additionalProperties": false,
+      "properties": {
+        "$schema": { "type": "string" },
+        "theme": { "type": "string", "description": "Theme name" },
+        "autoupdate": { "type": "boolean", "deprecated": true },
+        "share": { "type": "string", "enum": ["manual", "auto", "disabled"], "default": "manual" },
+        "provider": {
+          "type": "object",
+          "additionalProperties": { "$ref": "#/$defs/Provider" }
+        },
+        "instructions": { "type": "array", "items": { "type": "string" } },
+        "tui": {
+          "anyOf": [
+            { "type": "string" },
+            { "type": "object", "properties": { "scroll": { "type": "number", "minimum": 1 } } }
+          ]
+        }
+      },
+      "$defs": {
+        "Provider": {
+          "type": "object",
+          "required": ["npm"],
+          "properties": {
+            "npm": { "type": "string", "description": "Package" },
+            "options": {
+              "type": "object",
+              "properties": { "baseURL": { "type": "string", "pattern": "^https?://" } }
+            }
+          }
+        }
+      }
+    })
+  }
+
+  #[test]
+  fn describes_paths_through_refs() {
+    let schema = schema();
+    let root = describe(&schema, &[]).unwrap();
+    assert!(!root.other_keys);
+    assert!(root
+      .completions
+      .iter()
+      .any(|c| c.key == "theme" && c.description.as_deref() == Some("Theme name")));
+    assert!(root
+      .completions
+      .iter()
+      .any(|c| c.key == "autoupdate" && c.deprecated));
+
+    let share = describe(&schema, &path(&["share"])).unwrap();
+    assert_eq!(
+      share.values,
+      vec![json!("manual"), json!("auto"), json!("disabled")]
+    );
+    assert_eq!(share.default, Some(json!("manual")));
+
+    assert!(describe(&schema, &path(&["provider"])).unwrap().other_keys);
+    let provider = describe(&schema, &path(&["provider", "ollama"])).unwrap();
+    assert!(provider.completions.iter().any(|c| c.key == "npm" && c.required));
+    let base_url = describe(&schema, &path(&["provider", "ollama", "options", "baseURL"])).unwrap();
+    assert_eq!(base_url.types, vec!["string".to_string()]);
+
+    let scroll = describe(&schema, &path(&["tui", "scroll"])).unwrap();
+    assert_eq!(scroll.types, vec!["number".to_string()]);
+    assert_eq!(
+      describe(&schema, &path(&["instructions", "0"])).unwrap().types,
+      vec!["string"]
+    );
+    assert!(describe(&schema, &path(&["nope"])).is_none());
+  }
+
+  #[test]
+  fn validates_configs() {
+    let schema = schema();
+    let valid = json!({
+      "$schema": "https://opencode.ai/config.json",
+      "share": "auto",
+      "provider": { "ollama": { "npm": "x", "options": { "baseURL": "http://localhost" } } },
+      "tui": "compact"
+    });
+    assert!(validate(&schema, &valid).is_empty());
+
+    let invalid = json!({
+      "theme": 3,
+      "share": "never",
+      "extra": true,
+      "autoupdate": false,
+      "provider": { "ollama": { "options": { "baseURL": "localhost" } } },
+      "instructions": ["a", 1],
+      "tui": { "scroll": 0 }
+    });
+    let found: Vec<(String, String, DiagnosticSeverity)> = validate(&schema, &invalid)
+      .into_iter()
+      .map(|d| (d.path.join("."), d.message, d.severity))
+      .collect();
+    let has = |p: &str, message: &str| found.iter().any(|(at, m, _)| at == p && m.contains(message));
+    assert!(has("theme", "Expected string"));
+    assert!(has("share", "Expected one of"));
+    assert!(has("extra", "Unknown key"));
+    assert!(found
+      .iter()
+      .any(|(at, _, s)| at == "autoupdate" && *s == DiagnosticSeverity::Warning));
+    assert!(has("provider.ollama", "Missing required key \"npm\""));
+    assert!(has("provider.ollama.options.baseURL", "Must match"));
+    assert!(has("instructions.1", "Expected string"));
+    assert!(has("tui.scroll", "at least 1"));
+
+    let mistyped = validate(&schema, &json!({ "tui": 5 }));
+    assert_eq!(mistyped[0].message, "Expected string or object");
+  }
+
+  #[test]
+  fn reports_syntax_errors_with_position() {
+    let diagnostics = validate_text(&schema(), "{\n  /* a\n  b */\n  \"theme\": }");
+    assert_eq!(diagnostics[0].line, Some(4));
+    assert!(validate_text(&schema(), "  ").is_empty());
+    assert!(validate_text(&schema(), "{ \"theme\": \"x\", // ok\n}").is_empty());
+  }
+}
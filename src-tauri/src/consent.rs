@@ -0,0 +1,156 @@
+use std::{
+  collections::HashMap,
+  sync::Mutex,
+  time::{Duration, Instant},
+};
+
+use rand::{distributions::Alphanumeric, Rng};
+use serde::Serialize;
+use serde_json::json;
+use tauri::{AppHandle, State};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::error::{ErrorCode, OpenWorkError};
+
+/// How long a pending request or an issued confirmation stays valid.
+const CONSENT_TTL: Duration = Duration::from_secs(120);
+
+#[derive(Clone)]
+struct ConsentAction {
+  action: String,
+  target: String,
+  description: String,
+  created: Instant,
+}
+
+impl ConsentAction {
+  fn expired(&self) -> bool {
+    self.created.elapsed() > CONSENT_TTL
+  }
+}
+
+/// Backend-enforced confirmation for destructive commands.
+///
+/// A destructive command called without a confirmation ID fails with a
+/// `PENDING_CONFIRMATION` error carrying a token. `confirm_request` asks the user through a
+/// native dialog (outside the webview) and, if approved, issues a one-time
+/// confirmation ID bound to that exact action and target.
+#[derive(Default)]
+pub struct ConsentManager {
+  pending: Mutex<HashMap<String, ConsentAction>>,
+  confirmed: Mutex<HashMap<String, ConsentAction>>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfirmationGrant {
+  pub confirmation_id: String,
+}
+
+pub fn random_token(len: usize) -> String {
+  rand::thread_rng()
+    .sample_iter(&Alphanumeric)
+    .take(len)
+    .map(char::from)
+    .collect()
+}
+
+impl ConsentManager {
+  /// Consumes a matching confirmation, or registers a pending request and
+  /// returns the `PENDING_CONFIRMATION` error for the UI.
+  pub fn require(
+    &self,
+    action: &str,
+    target: &str,
+    description: &str,
+    confirmation_id: Option<&str>,
+  ) -> Result<(), OpenWorkError> {
+    if let Some(id) = confirmation_id.map(str::trim).filter(|id| !id.is_empty()) {
+      let mut confirmed = self.confirmed.lock().expect("consent mutex poisoned");
+      confirmed.retain(|_, granted| !granted.expired());
+      if let Some(granted) = confirmed.remove(id) {
+        if granted.action == action && granted.target == target {
+          return Ok(());
+        }
+      }
+      return Err(OpenWorkError::new(
+        ErrorCode::ConfirmationInvalid,
+        "Confirmation is invalid or expired. Please confirm again.",
+      ));
+    }
+
+    let token = random_token(32);
+    {
+      let mut pending = self.pending.lock().expect("consent mutex poisoned");
+      pending.retain(|_, request| !request.expired());
+      pending.insert(
+        token.clone(),
+        ConsentAction {
+          action: action.to_string(),
+          target: target.to_string(),
+          description: description.to_string(),
+          created: Instant::now(),
+        },
+      );
+    }
+
+    Err(
+      OpenWorkError::new(ErrorCode::PendingConfirmation, description).with_details(json!({
+        "token": token,
+        "action": action,
+        "target": target,
+      })),
+    )
+  }
+}
+
+/// Shows a native confirmation dialog for a pending destructive action.
+#[tauri::command]
+pub async fn confirm_request(
+  app: AppHandle,
+  consent: State<'_, ConsentManager>,
+  token: String,
+) -> Result<ConfirmationGrant, OpenWorkError> {
+  let request = consent
+    .pending
+    .lock()
+    .expect("consent mutex poisoned")
+    .remove(token.trim())
+    .filter(|request| !request.expired())
+    .ok_or_else(|| {
+      OpenWorkError::new(
+        ErrorCode::ConfirmationInvalid,
+        "Confirmation request is invalid or expired.",
+      )
+    })?;
+
+  let approved = app
+    .dialog()
+    .message(&request.description)
+    .title("Confirm")
+    .kind(MessageDialogKind::Warning)
+    .buttons(MessageDialogButtons::OkCancelCustom(
+      "Continue".to_string(),
+      "Cancel".to_string(),
+    ))
+    .blocking_show();
+
+  if !approved {
+    return Err(OpenWorkError::new(ErrorCode::Cancelled, "Cancelled"));
+  }
+
+  let confirmation_id = random_token(32);
+  consent
+    .confirmed
+    .lock()
+    .expect("consent mutex poisoned")
+    .insert(
+      confirmation_id.clone(),
+      ConsentAction {
+        created: Instant::now(),
+        ..request
+      },
+    );
+
+  Ok(ConfirmationGrant { confirmation_id })
+}
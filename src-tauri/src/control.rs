@@ -0,0 +1,611 @@
+//! Local control server for driving OpenWork from scripts and CI.
+//!
+//! When enabled (or for a launch with `--headless`, which also keeps the
+//! window hidden) OpenWork listens on a unix socket in its data directory, or
+//! on a random localhost port on Windows, and answers newline-delimited JSON
+//! requests:
+//!
+//! ```text
+//! {"id": 1, "token": "...", "method": "engine.start", "params": {"projectDir": "/code/app"}}
+//! {"id": 1, "result": {...}}   or   {"id": 1, "error": {"code": "...", "message": "..."}}
+//! ```
+//!
+//! Where to connect and the token every request must carry are written to
+//! `control-endpoint.json` (readable only by the user), which is removed when
+//! the server stops. Methods run the same code as the matching commands.
+//! Steps that need the user, like overwriting a skill, fail with
+//! `PENDING_CONFIRMATION` as they do in the app; passing its `details.token`
+//! to `confirm` shows the dialog and returns the `confirmationId` to retry
+//! with. `call` is the client side, used by `openwork-cli`.
+
+use std::{
+  env, fs,
+  io::{self, BufRead, BufReader, Read, Write},
+  path::{Path, PathBuf},
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex, RwLock,
+  },
+  thread,
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager, State};
+
+use crate::{
+  app_sandbox, consent,
+  engine_cache::EngineCache,
+  error::{ErrorCode, OpenWorkError},
+  instance::MAIN_WINDOW,
+  portable,
+  store::{app_state_path, read_state, write_state},
+  EngineManager,
+};
+
+pub const CONTROL_FILE: &str = "control-settings.json";
+pub const ENDPOINT_FILE: &str = "control-endpoint.json";
+
+/// Starts the control server for this launch and keeps the window hidden.
+pub const HEADLESS_ARG: &str = "--headless";
+/// Overrides where clients look for the endpoint file.
+pub const ENDPOINT_ENV: &str = "OPENWORK_CONTROL_ENDPOINT";
+/// The bundle identifier from `tauri.conf.json`, which names the data
+/// directory.
+const APP_IDENTIFIER: &str = "com.differentai.openwork";
+
+#[cfg(unix)]
+const SOCKET_FILE: &str = "control.sock";
+const TOKEN_LEN: usize = 40;
+/// Longest request line; anything longer closes the connection.
+const MAX_LINE: u64 = 1024 * 1024;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ControlSettings {
+  pub enabled: bool,
+}
+
+/// What `control-endpoint.json` holds.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct Endpoint {
+  /// `unix` (`address` is the socket path) or `tcp` (`host:port`).
+  transport: String,
+  address: String,
+  token: String,
+  pid: u32,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ControlStatus {
+  pub enabled: bool,
+  /// Started by `--headless` for this launch.
+  pub headless: bool,
+  pub running: bool,
+  pub transport: Option<String>,
+  pub address: Option<String>,
+  /// The file with the address and token.
+  pub endpoint_file: Option<String>,
+}
+
+struct Running {
+  endpoint: Endpoint,
+  stop: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+pub struct ControlServer {
+  running: Mutex<Option<Running>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Request {
+  #[serde(default)]
+  id: Value,
+  #[serde(default)]
+  token: String,
+  method: String,
+  #[serde(default)]
+  params: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct Response {
+  #[serde(default)]
+  result: Value,
+  error: Option<OpenWorkError>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct DoctorParams {
+  force: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StartParams {
+  project_dir: String,
+  #[serde(default)]
+  attach: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ConfirmParams {
+  token: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportSkillParams {
+  project_dir: String,
+  source_dir: String,
+  #[serde(default)]
+  overwrite: bool,
+  confirmation_id: Option<String>,
+  exclude: Option<Vec<String>>,
+  name: Option<String>,
+  install_dependencies: Option<bool>,
+}
+
+static ACTIVE: RwLock<Option<ControlSettings>> = RwLock::new(None);
+static HEADLESS: AtomicBool = AtomicBool::new(false);
+
+fn active() -> ControlSettings {
+  ACTIVE
+    .read()
+    .expect("control settings lock poisoned")
+    .clone()
+    .unwrap_or_default()
+}
+
+pub fn init(app: &AppHandle) {
+  let settings: ControlSettings = read_state(app, CONTROL_FILE).unwrap_or_else(|e| {
+    tracing::warn!(error = %e, "failed to read control settings");
+    ControlSettings::default()
+  });
+  *ACTIVE.write().expect("control settings lock poisoned") = Some(settings.clone());
+
+  let headless = std::env::args().any(|arg| arg == HEADLESS_ARG);
+  HEADLESS.store(headless, Ordering::Relaxed);
+  if headless {
+    if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+      let _ = window.hide();
+    }
+  }
+  if settings.enabled || headless {
+    if let Err(e) = app.state::<ControlServer>().start(app) {
+      tracing::warn!(error = %e, "failed to start control server");
+    }
+  }
+}
+
+/// Starts the server when a second launch asks for `--headless`, instead of
+/// showing the window. Returns whether it did.
+pub fn on_second_launch(app: &AppHandle, args: &[String]) -> bool {
+  if !args.iter().any(|arg| arg == HEADLESS_ARG) {
+    return false;
+  }
+  HEADLESS.store(true, Ordering::Relaxed);
+  if let Err(e) = app.state::<ControlServer>().start(app) {
+    tracing::warn!(error = %e, "failed to start control server");
+  }
+  true
+}
+
+/// Compares without stopping at the first difference, so response timing
+/// doesn't reveal how much of a guessed token was right.
+fn token_matches(expected: &str, given: &str) -> bool {
+  expected.len() == given.len()
+    && expected
+      .bytes()
+      .zip(given.bytes())
+      .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+      == 0
+}
+
+fn parse_request(line: &str) -> Result<Request, OpenWorkError> {
+  serde_json::from_str(line).map_err(|e| OpenWorkError::invalid_argument(format!("Invalid request: {e}")))
+}
+
+fn params<T: DeserializeOwned>(value: Value) -> Result<T, OpenWorkError> {
+  let value = if value.is_null() { json!({}) } else { value };
+  serde_json::from_value(value).map_err(|e| OpenWorkError::invalid_argument(format!("Invalid params: {e}")))
+}
+
+fn to_value<T: Serialize>(value: T) -> Result<Value, OpenWorkError> {
+  serde_json::to_value(value).map_err(|e| OpenWorkError::new(ErrorCode::Internal, e.to_string()))
+}
+
+fn dispatch(app: &AppHandle, method: &str, params_value: Value) -> Result<Value, OpenWorkError> {
+  let manager = app.state::<EngineManager>();
+  match method {
+    "ping" => Ok(json!({
+      "version": app.package_info().version.to_string(),
+      "pid": std::process::id(),
+    })),
+    "engine.doctor" => {
+      let DoctorParams { force } = params(params_value)?;
+      to_value(crate::engine_doctor(app.state::<EngineCache>(), Some(force)))
+    }
+    "engine.install" => to_value(crate::engine_install(app.clone(), None)?),
+    "engine.info" => to_value(crate::engine_info_for(&manager, MAIN_WINDOW)),
+    "engine.start" => {
+      let StartParams { project_dir, attach } = params(params_value)?;
+      to_value(crate::start_engine(app, &manager, MAIN_WINDOW, &project_dir, attach)?)
+    }
+    "engine.stop" => to_value(crate::stop_engine(app, &manager, MAIN_WINDOW)),
+    "confirm" => {
+      let ConfirmParams { token } = params(params_value)?;
+      let confirm = consent::confirm_request(app.clone(), app.state::<consent::ConsentManager>(), token);
+      to_value(tauri::async_runtime::block_on(confirm)?)
+    }
+    "skill.import" => {
+      let request: ImportSkillParams = params(params_value)?;
+      to_value(crate::import_skill(
+        app.clone(),
+        app.state::<consent::ConsentManager>(),
+        request.project_dir,
+        request.source_dir,
+        request.overwrite,
+        None,
+        request.confirmation_id,
+        request.exclude,
+        None,
+        request.name,
+        request.install_dependencies,
+      )?)
+    }
+    _ => Err(
+      OpenWorkError::new(ErrorCode::NotFound, format!("Unknown method {method}"))
+        .with_details(json!({ "method": method })),
+    ),
+  }
+}
+
+fn respond(app: &AppHandle, token: &str, line: &str) -> Value {
+  let request = match parse_request(line) {
+    Ok(request) => request,
+    Err(e) => return json!({ "id": Value::Null, "error": e }),
+  };
+  if !token_matches(token, &request.token) {
+    let error = OpenWorkError::invalid_argument("Invalid control token");
+    return json!({ "id": request.id, "error": error });
+  }
+  tracing::info!(method = %request.method, "control request");
+  match dispatch(app, &request.method, request.params) {
+    Ok(result) => json!({ "id": request.id, "result": result }),
+    Err(error) => json!({ "id": request.id, "error": error }),
+  }
+}
+
+/// Answers requests on one connection until it closes.
+fn serve<S: Read + Write>(app: &AppHandle, token: &str, reader: S, mut writer: S) -> io::Result<()> {
+  let mut reader = BufReader::new(reader);
+  loop {
+    let mut line = String::new();
+    let read = reader.by_ref().take(MAX_LINE + 1).read_line(&mut line)?;
+    if read == 0 {
+      return Ok(());
+    }
+    if read as u64 > MAX_LINE {
+      let error = OpenWorkError::invalid_argument("Request too large");
+      writeln!(writer, "{}", json!({ "id": Value::Null, "error": error }))?;
+      return Ok(());
+    }
+    if line.trim().is_empty() {
+      continue;
+    }
+    writeln!(writer, "{}", respond(app, token, line.trim()))?;
+    writer.flush()?;
+  }
+}
+
+/// Writes `value` to `path` readable only by the user.
+fn write_private(path: &Path, value: &Endpoint) -> Result<(), String> {
+  let content = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+  let tmp = path.with_extension("json.tmp");
+  let mut options = fs::OpenOptions::new();
+  options.write(true).create(true).truncate(true);
+  #[cfg(unix)]
+  std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+  let mut file = options
+    .open(&tmp)
+    .map_err(|e| format!("Failed to write {}: {e}", tmp.display()))?;
+  file
+    .write_all(content.as_bytes())
+    .map_err(|e| format!("Failed to write {}: {e}", tmp.display()))?;
+  fs::rename(&tmp, path).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+/// Runs `handle` on its own thread for each connection until `stop` is set.
+fn accept_loop<S, I>(app: AppHandle, token: String, stop: Arc<AtomicBool>, incoming: I)
+where
+  S: Read + Write + Send + 'static,
+  I: Iterator<Item = io::Result<(S, S)>>,
+{
+  for connection in incoming {
+    if stop.load(Ordering::SeqCst) {
+      break;
+    }
+    let (reader, writer) = match connection {
+      Ok(pair) => pair,
+      Err(e) => {
+        tracing::warn!(error = %e, "control connection failed");
+        continue;
+      }
+    };
+    let (app, token) = (app.clone(), token.clone());
+    thread::spawn(move || {
+      if let Err(e) = serve(&app, &token, reader, writer) {
+        tracing::debug!(error = %e, "control connection closed");
+      }
+    });
+  }
+}
+
+impl ControlServer {
+  fn start(&self, app: &AppHandle) -> Result<(), OpenWorkError> {
+    let mut running = self.running.lock().expect("control mutex poisoned");
+    if running.is_some() {
+      return Ok(());
+    }
+    let token = consent::random_token(TOKEN_LEN);
+    let stop = Arc::new(AtomicBool::new(false));
+    let endpoint = listen(app, token.clone(), stop.clone())?;
+    let file = app_state_path(app, ENDPOINT_FILE)?;
+    if let Err(e) = write_private(&file, &endpoint) {
+      Self::shut_down(app, &endpoint, &stop);
+      return Err(e.into());
+    }
+    tracing::info!(transport = %endpoint.transport, address = %endpoint.address, "control server started");
+    crate::telemetry::record("feature.control");
+    *running = Some(Running { endpoint, stop });
+    Ok(())
+  }
+
+  fn stop(&self, app: &AppHandle) {
+    if let Some(running) = self.running.lock().expect("control mutex poisoned").take() {
+      Self::shut_down(app, &running.endpoint, &running.stop);
+      tracing::info!("control server stopped");
+    }
+  }
+
+  /// Stops accepting, wakes the accept loop with a connection of its own and
+  /// removes the endpoint file (and socket).
+  fn shut_down(app: &AppHandle, endpoint: &Endpoint, stop: &AtomicBool) {
+    stop.store(true, Ordering::SeqCst);
+    match endpoint.transport.as_str() {
+      #[cfg(unix)]
+      "unix" => {
+        let _ = std::os::unix::net::UnixStream::connect(&endpoint.address);
+        let _ = fs::remove_file(&endpoint.address);
+      }
+      _ => {
+        let _ = std::net::TcpStream::connect(&endpoint.address);
+      }
+    }
+    if let Ok(file) = app_state_path(app, ENDPOINT_FILE) {
+      let _ = fs::remove_file(file);
+    }
+  }
+
+  fn status(&self, app: &AppHandle) -> ControlStatus {
+    let running = self.running.lock().expect("control mutex poisoned");
+    let endpoint = running.as_ref().map(|running| &running.endpoint);
+    ControlStatus {
+      enabled: active().enabled,
+      headless: HEADLESS.load(Ordering::Relaxed),
+      running: endpoint.is_some(),
+      transport: endpoint.map(|endpoint| endpoint.transport.clone()),
+      address: endpoint.map(|endpoint| endpoint.address.clone()),
+      endpoint_file: endpoint
+        .and_then(|_| app_state_path(app, ENDPOINT_FILE).ok())
+        .map(|path| path.to_string_lossy().to_string()),
+    }
+  }
+}
+
+#[cfg(unix)]
+fn listen(app: &AppHandle, token: String, stop: Arc<AtomicBool>) -> Result<Endpoint, OpenWorkError> {
+  use std::os::unix::{fs::PermissionsExt, net::UnixListener};
+
+  let path = app_state_path(app, SOCKET_FILE)?;
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent)
+      .map_err(|e| format!("Failed to create state dir {}: {e}", parent.display()))?;
+  }
+  // Left behind by a launch that didn't stop cleanly.
+  let _ = fs::remove_file(&path);
+  let listener = UnixListener::bind(&path).map_err(|e| {
+    OpenWorkError::new(
+      ErrorCode::Io,
+      format!("Failed to listen on {}: {e}", path.display()),
+    )
+  })?;
+  fs::set_permissions(&path, fs::Permissions::from_mode(0o600))
+    .map_err(|e| format!("Failed to restrict {}: {e}", path.display()))?;
+
+  let app = app.clone();
+  let file_token = token.clone();
+  thread::spawn(move || {
+    let incoming = listener
+      .incoming()
+      .map(|stream| stream.and_then(|stream| Ok((stream.try_clone()?, stream))));
+    accept_loop(app, token, stop, incoming);
+  });
+  Ok(Endpoint {
+    transport: "unix".to_string(),
+    address: path.to_string_lossy().to_string(),
+    token: file_token,
+    pid: std::process::id(),
+  })
+}
+
+#[cfg(not(unix))]
+fn listen(app: &AppHandle, token: String, stop: Arc<AtomicBool>) -> Result<Endpoint, OpenWorkError> {
+  let listener = std::net::TcpListener::bind(("127.0.0.1", 0))
+    .map_err(|e| OpenWorkError::new(ErrorCode::Io, format!("Failed to listen on localhost: {e}")))?;
+  let address = listener
+    .local_addr()
+    .map_err(|e| OpenWorkError::new(ErrorCode::Io, e.to_string()))?
+    .to_string();
+
+  let app = app.clone();
+  let file_token = token.clone();
+  thread::spawn(move || {
+    let incoming = listener
+      .incoming()
+      .map(|stream| stream.and_then(|stream| Ok((stream.try_clone()?, stream))));
+    accept_loop(app, token, stop, incoming);
+  });
+  Ok(Endpoint {
+    transport: "tcp".to_string(),
+    address,
+    token: file_token,
+    pid: std::process::id(),
+  })
+}
+
+/// The endpoint file of the running app, for clients without an
+/// `AppHandle`: `$OPENWORK_CONTROL_ENDPOINT`, or where the app writes it.
+pub fn endpoint_file() -> Option<PathBuf> {
+  if let Some(file) = env::var_os(ENDPOINT_ENV).filter(|file| !file.is_empty()) {
+    return Some(PathBuf::from(file));
+  }
+  if let Some(dir) = portable::dir("data") {
+    return Some(dir.join(ENDPOINT_FILE));
+  }
+  Some(default_data_dir()?.join(ENDPOINT_FILE))
+}
+
+/// Where Tauri puts the app data directory of an installed (not portable)
+/// OpenWork.
+pub fn default_data_dir() -> Option<PathBuf> {
+  let base = if cfg!(windows) {
+    env::var_os("APPDATA").map(PathBuf::from)
+  } else if cfg!(target_os = "macos") {
+    crate::home_dir().map(|home| home.join("Library").join("Application Support"))
+  } else {
+    app_sandbox::xdg_dir("XDG_DATA_HOME").or_else(|| crate::home_dir().map(|home| home.join(".local/share")))
+  };
+  Some(base?.join(APP_IDENTIFIER))
+}
+
+fn not_running(detail: impl std::fmt::Display) -> OpenWorkError {
+  OpenWorkError::new(
+    ErrorCode::NotFound,
+    format!("OpenWork is not running with the control server ({detail})"),
+  )
+}
+
+/// Sends one request to the app whose endpoint file is `file`. Fails with
+/// `NOT_FOUND` when no app is listening.
+pub fn call(file: &Path, method: &str, params: Value) -> Result<Value, OpenWorkError> {
+  let content = fs::read_to_string(file).map_err(|e| not_running(format!("{}: {e}", file.display())))?;
+  let endpoint: Endpoint = serde_json::from_str(&content).map_err(|e| {
+    OpenWorkError::new(
+      ErrorCode::ConfigInvalid,
+      format!("Failed to parse {}: {e}", file.display()),
+    )
+  })?;
+  let request = json!({ "id": 1, "token": endpoint.token, "method": method, "params": params });
+  let line = match endpoint.transport.as_str() {
+    #[cfg(unix)]
+    "unix" => exchange(
+      std::os::unix::net::UnixStream::connect(&endpoint.address),
+      &request,
+    ),
+    "tcp" => exchange(std::net::TcpStream::connect(&endpoint.address), &request),
+    other => {
+      return Err(OpenWorkError::new(
+        ErrorCode::ConfigInvalid,
+        format!("Unsupported control transport {other}"),
+      ))
+    }
+  }?;
+  let response: Response = serde_json::from_str(&line)
+    .map_err(|e| OpenWorkError::new(ErrorCode::Internal, format!("Invalid control response: {e}")))?;
+  match response.error {
+    Some(error) => Err(error),
+    None => Ok(response.result),
+  }
+}
+
+fn exchange<S: Read + Write>(stream: io::Result<S>, request: &Value) -> Result<String, OpenWorkError> {
+  let mut stream = stream.map_err(not_running)?;
+  let io_error = |e: io::Error| OpenWorkError::new(ErrorCode::Io, format!("Control request failed: {e}"));
+  writeln!(stream, "{request}").map_err(io_error)?;
+  stream.flush().map_err(io_error)?;
+  let mut line = String::new();
+  BufReader::new(&mut stream)
+    .read_line(&mut line)
+    .map_err(io_error)?;
+  if line.is_empty() {
+    return Err(OpenWorkError::new(
+      ErrorCode::Io,
+      "The control server closed the connection",
+    ));
+  }
+  Ok(line)
+}
+
+#[tauri::command]
+pub fn control_status(app: AppHandle, server: State<ControlServer>) -> ControlStatus {
+  server.status(&app)
+}
+
+/// Turns the control server on or off for this and later launches. A
+/// `--headless` launch keeps its server regardless.
+#[tauri::command]
+pub fn control_set_enabled(
+  app: AppHandle,
+  server: State<ControlServer>,
+  enabled: bool,
+) -> Result<ControlStatus, OpenWorkError> {
+  let settings = ControlSettings { enabled };
+  write_state(&app, CONTROL_FILE, &settings)?;
+  *ACTIVE.write().expect("control settings lock poisoned") = Some(settings);
+  if enabled {
+    server.start(&app)?;
+  } else if !HEADLESS.load(Ordering::Relaxed) {
+    server.stop(&app);
+  }
+  Ok(server.status(&app))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn checks_tokens() {
+    assert!(token_matches("abc123", "abc123"));
+    assert!(!token_matches("abc123", "abc124"));
+    assert!(!token_matches("abc123", "abc12"));
+    assert!(!token_matches("abc123", ""));
+  }
+
+  #[test]
+  fn parses_requests() {
+    let request =
+      parse_request(r#"{"id":7,"token":"t","method":"engine.start","params":{"projectDir":"/a"}}"#)
+        .expect("valid request");
+    assert_eq!(request.id, json!(7));
+    assert_eq!(request.method, "engine.start");
+    let StartParams { project_dir, attach } = params(request.params).expect("valid params");
+    assert_eq!(project_dir, "/a");
+    assert!(!attach);
+
+    let request = parse_request(r#"{"method":"engine.doctor"}"#).expect("params are optional");
+    let DoctorParams { force } = params(request.params).expect("defaults");
+    assert!(!force);
+
+    let error = parse_request("not json").expect_err("invalid json");
+    assert_eq!(error.code, ErrorCode::InvalidArgument);
+    let error = params::<StartParams>(json!({})).expect_err("projectDir is required");
+    assert_eq!(error.code, ErrorCode::InvalidArgument);
+  }
+}
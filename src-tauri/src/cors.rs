@@ -0,0 +1,198 @@
+//! The origins the engine accepts requests from (`opencode serve --cors`).
+//!
+//! They are read from the app's own webviews when the engine starts, so a dev
+//! server on another port, or a build that serves over `https://`, works
+//! without changes. Anything else (a browser tab on another host, a proxy in
+//! front of the app) has to be listed in `cors.json` explicitly. Changes
+//! apply on the next engine start.
+
+use std::sync::RwLock;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::{
+  error::OpenWorkError,
+  store::{read_state, write_state},
+};
+
+pub const CORS_FILE: &str = "cors.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CorsSettings {
+  /// Origins allowed on top of the app's own, e.g. `https://openwork.example.com`.
+  pub extra_origins: Vec<String>,
+}
+
+static ACTIVE: RwLock<Option<CorsSettings>> = RwLock::new(None);
+
+fn active() -> CorsSettings {
+  ACTIVE
+    .read()
+    .expect("cors settings lock poisoned")
+    .clone()
+    .unwrap_or_default()
+}
+
+/// `scheme://host[:port]` of `url`, lowercased, or `None` when it has no
+/// host (`about:blank`, `data:`).
+fn origin(url: &str) -> Option<String> {
+  let (scheme, rest) = url.trim().split_once("://")?;
+  let valid_scheme = scheme.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+    && scheme
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+  let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+  let host = authority.rsplit('@').next().unwrap_or_default();
+  if !valid_scheme || host.is_empty() || host.starts_with(':') {
+    return None;
+  }
+  Some(format!(
+    "{}://{}",
+    scheme.to_ascii_lowercase(),
+    host.to_ascii_lowercase()
+  ))
+}
+
+/// Where the app's pages come from when no webview is open yet.
+fn configured_origins(app: &AppHandle) -> Vec<String> {
+  if tauri::is_dev() {
+    if let Some(dev_url) = &app.config().build.dev_url {
+      return origin(dev_url.as_str()).into_iter().collect();
+    }
+  }
+  if cfg!(windows) {
+    vec![
+      "http://tauri.localhost".to_string(),
+      "https://tauri.localhost".to_string(),
+    ]
+  } else {
+    vec!["tauri://localhost".to_string()]
+  }
+}
+
+/// `first` followed by `extra`, without duplicates.
+fn merge(first: Vec<String>, extra: &[String]) -> Vec<String> {
+  let mut origins = first;
+  for origin in extra {
+    if !origins.contains(origin) {
+      origins.push(origin.clone());
+    }
+  }
+  origins
+}
+
+/// The origins to pass to the engine: those of the open webviews (or the
+/// configured ones before any is open), then the extra origins from settings.
+pub fn origins(app: &AppHandle) -> Vec<String> {
+  let mut own: Vec<String> = Vec::new();
+  for window in app.webview_windows().values() {
+    match window.url() {
+      Ok(url) => own.extend(origin(url.as_str())),
+      Err(e) => tracing::debug!(window = window.label(), error = %e, "couldn't read webview url"),
+    }
+  }
+  let own = if own.is_empty() {
+    configured_origins(app)
+  } else {
+    merge(Vec::new(), &own)
+  };
+  merge(own, &active().extra_origins)
+}
+
+fn validate(settings: CorsSettings) -> Result<CorsSettings, OpenWorkError> {
+  let mut extra_origins = Vec::new();
+  for raw in settings
+    .extra_origins
+    .iter()
+    .map(|raw| raw.trim())
+    .filter(|raw| !raw.is_empty())
+  {
+    let origin = origin(raw)
+      .filter(|origin| origin.len() == raw.trim_end_matches('/').len())
+      .ok_or_else(|| {
+        OpenWorkError::invalid_argument(format!(
+          "Not an origin: {raw}; use scheme://host[:port] without a path"
+        ))
+      })?;
+    if !extra_origins.contains(&origin) {
+      extra_origins.push(origin);
+    }
+  }
+  Ok(CorsSettings { extra_origins })
+}
+
+pub fn init(app: &AppHandle) {
+  let settings = read_state::<CorsSettings>(app, CORS_FILE).unwrap_or_else(|e| {
+    tracing::warn!(error = %e, "failed to load cors settings");
+    CorsSettings::default()
+  });
+  *ACTIVE.write().expect("cors settings lock poisoned") = Some(settings);
+}
+
+#[tauri::command]
+pub fn cors_settings_get() -> CorsSettings {
+  active()
+}
+
+#[tauri::command]
+pub fn cors_settings_set(app: AppHandle, settings: CorsSettings) -> Result<CorsSettings, OpenWorkError> {
+  let settings = validate(settings)?;
+  write_state(&app, CORS_FILE, &settings)?;
+  *ACTIVE.write().expect("cors settings lock poisoned") = Some(settings.clone());
+  Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn derives_origins_from_urls() {
+    assert_eq!(
+      origin("http://localhost:1420/index.html").as_deref(),
+      Some("http://localhost:1420")
+    );
+    assert_eq!(origin("tauri://localhost").as_deref(), Some("tauri://localhost"));
+    assert_eq!(
+      origin("HTTPS://Tauri.Localhost/?x=1").as_deref(),
+      Some("https://tauri.localhost")
+    );
+    assert_eq!(origin("about:blank"), None);
+    assert_eq!(origin("file:///index.html"), None);
+  }
+
+  #[test]
+  fn validates_extra_origins() {
+    let settings = validate(CorsSettings {
+      extra_origins: vec![
+        " https://app.example.com ".to_string(),
+        "https://app.example.com/".to_string(),
+        String::new(),
+      ],
+    })
+    .unwrap();
+    assert_eq!(settings.extra_origins, ["https://app.example.com"]);
+    assert!(validate(CorsSettings {
+      extra_origins: vec!["https://app.example.com/path".to_string()],
+    })
+    .is_err());
+    assert!(validate(CorsSettings {
+      extra_origins: vec!["localhost:3000".to_string()],
+    })
+    .is_err());
+  }
+
+  #[test]
+  fn keeps_the_first_of_duplicate_origins() {
+    let merged = merge(
+      vec!["tauri://localhost".to_string()],
+      &[
+        "http://localhost:1420".to_string(),
+        "tauri://localhost".to_string(),
+      ],
+    );
+    assert_eq!(merged, ["tauri://localhost", "http://localhost:1420"]);
+  }
+}
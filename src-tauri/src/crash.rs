@@ -0,0 +1,185 @@
+//! Writes a report to `<app data>/crashes` when the backend panics, so "the
+//! app just closed" comes with a backtrace and the log lines leading up to it.
+
+use std::{
+  backtrace::Backtrace,
+  fmt::Write as _,
+  fs,
+  panic,
+  path::{Path, PathBuf},
+  sync::OnceLock,
+  thread,
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::{
+  error::OpenWorkError, log_viewer::recent_raw_lines, logging::log_dir, paths::file_name_segment,
+  redact::redact, store::app_state_path,
+};
+
+const CRASHES_DIR: &str = "crashes";
+const REPORT_PREFIX: &str = "crash-";
+const REPORT_SUFFIX: &str = ".txt";
+const LOG_LINES_IN_REPORT: usize = 100;
+
+struct CrashContext {
+  dir: PathBuf,
+  log_dir: Option<PathBuf>,
+  version: String,
+}
+
+static CONTEXT: OnceLock<CrashContext> = OnceLock::new();
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashReportSummary {
+  pub id: String,
+  /// Milliseconds since the Unix epoch.
+  pub created_ms: u64,
+  pub bytes: u64,
+  pub message: Option<String>,
+}
+
+fn now_ms() -> u128 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_millis())
+    .unwrap_or(0)
+}
+
+fn panic_message(info: &panic::PanicHookInfo<'_>) -> String {
+  let payload = info.payload();
+  if let Some(message) = payload.downcast_ref::<&str>() {
+    return message.to_string();
+  }
+  if let Some(message) = payload.downcast_ref::<String>() {
+    return message.clone();
+  }
+  "unknown panic payload".to_string()
+}
+
+fn render_report(context: &CrashContext, info: &panic::PanicHookInfo<'_>) -> String {
+  let mut report = String::new();
+  let thread = thread::current();
+  let location = info
+    .location()
+    .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()))
+    .unwrap_or_else(|| "unknown".to_string());
+
+  let _ = writeln!(report, "OpenWork crash report");
+  let _ = writeln!(report, "version: {}", context.version);
+  let _ = writeln!(report, "os: {} {}", std::env::consts::OS, std::env::consts::ARCH);
+  let _ = writeln!(report, "time_ms: {}", now_ms());
+  let _ = writeln!(report, "thread: {}", thread.name().unwrap_or("<unnamed>"));
+  let _ = writeln!(report, "message: {}", panic_message(info));
+  let _ = writeln!(report, "location: {location}");
+  let _ = writeln!(report, "\nbacktrace:\n{}", Backtrace::force_capture());
+
+  if let Some(log_dir) = &context.log_dir {
+    let _ = writeln!(report, "\nrecent log lines:");
+    for line in recent_raw_lines(log_dir, LOG_LINES_IN_REPORT) {
+      let _ = writeln!(report, "{line}");
+    }
+  }
+
+  redact(&report)
+}
+
+fn write_report(context: &CrashContext, info: &panic::PanicHookInfo<'_>) -> Result<PathBuf, String> {
+  fs::create_dir_all(&context.dir)
+    .map_err(|e| format!("Failed to create {}: {e}", context.dir.display()))?;
+  let path = context.dir.join(format!("{REPORT_PREFIX}{}{REPORT_SUFFIX}", now_ms()));
+  fs::write(&path, render_report(context, info))
+    .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+  Ok(path)
+}
+
+/// Installs the panic hook. The default hook still runs afterwards, so the
+/// panic is printed to stderr as before.
+pub fn init(app: &AppHandle) {
+  let dir = match app_state_path(app, CRASHES_DIR) {
+    Ok(dir) => dir,
+    Err(e) => {
+      tracing::error!(error = %e, "crash reports disabled");
+      return;
+    }
+  };
+  let _ = CONTEXT.set(CrashContext {
+    dir,
+    log_dir: log_dir(app).ok(),
+    version: app.package_info().version.to_string(),
+  });
+
+  let previous = panic::take_hook();
+  panic::set_hook(Box::new(move |info| {
+    if let Some(context) = CONTEXT.get() {
+      match write_report(context, info) {
+        Ok(path) => tracing::error!(report = %path.display(), "panic: {}", panic_message(info)),
+        Err(e) => eprintln!("{e}"),
+      }
+    }
+    previous(info);
+  }));
+}
+
+fn crashes_dir() -> Result<&'static PathBuf, String> {
+  CONTEXT
+    .get()
+    .map(|context| &context.dir)
+    .ok_or_else(|| "Crash reporting is not initialized".to_string())
+}
+
+fn report_path(id: &str) -> Result<PathBuf, String> {
+  let id = file_name_segment(id, "crash report id")?;
+  if !id.starts_with(REPORT_PREFIX) || !id.ends_with(REPORT_SUFFIX) {
+    return Err(format!("Invalid crash report id: {id}"));
+  }
+  Ok(crashes_dir()?.join(id))
+}
+
+fn summary_message(path: &Path) -> Option<String> {
+  let content = fs::read_to_string(path).ok()?;
+  content
+    .lines()
+    .find_map(|line| line.strip_prefix("message: "))
+    .map(str::to_string)
+}
+
+/// Newest first.
+#[tauri::command]
+pub fn crash_reports_list() -> Result<Vec<CrashReportSummary>, OpenWorkError> {
+  let dir = crashes_dir()?;
+  let Ok(entries) = fs::read_dir(dir) else {
+    return Ok(Vec::new());
+  };
+
+  let mut reports: Vec<CrashReportSummary> = entries
+    .filter_map(Result::ok)
+    .filter_map(|entry| {
+      let id = entry.file_name().to_string_lossy().to_string();
+      let created_ms = id
+        .strip_prefix(REPORT_PREFIX)?
+        .strip_suffix(REPORT_SUFFIX)?
+        .parse()
+        .ok()?;
+      Some(CrashReportSummary {
+        message: summary_message(&entry.path()),
+        bytes: entry.metadata().map(|m| m.len()).unwrap_or(0),
+        created_ms,
+        id,
+      })
+    })
+    .collect();
+
+  reports.sort_by(|a, b| b.created_ms.cmp(&a.created_ms));
+  Ok(reports)
+}
+
+#[tauri::command]
+pub fn crash_report_read(id: String) -> Result<String, OpenWorkError> {
+  let path = report_path(&id)?;
+  Ok(fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?)
+}
@@ -0,0 +1,213 @@
+//! One zip with everything a bug report needs: app logs, the doctor report,
+//! recent engine logs, OpenWork settings and opencode configs, an environment
+//! summary and recent crash reports. Every file is passed through the
+//! redaction layer before it is added.
+
+use std::{
+  collections::BTreeMap,
+  env,
+  fs::{self, File},
+  io::{Read, Seek, SeekFrom},
+  path::{Path, PathBuf},
+  process::Stdio,
+};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager, State};
+
+use crate::{
+  archive::{write_zip_bytes, ArchiveResult},
+  consent::ConsentManager,
+  crash, doctor_report,
+  engine_cache::EngineCache,
+  error::OpenWorkError,
+  exec, logging, opencode_data_dir,
+  paths::export_target,
+  redact::{redact, redact_json},
+  resolve_opencode_config_path,
+  store::app_state_path,
+  EngineManager,
+};
+
+/// Only the end of each log file goes into the bundle.
+const MAX_LOG_BYTES: u64 = 2 * 1024 * 1024;
+const MAX_APP_LOG_FILES: usize = 3;
+const MAX_ENGINE_LOG_FILES: usize = 3;
+const MAX_CRASH_REPORTS: usize = 5;
+
+/// Settings files that describe configuration rather than user content (the
+/// prompt queue and history are left out on purpose).
+pub const SETTINGS_FILES: &[&str] = &[
+  crate::allowlist::ALLOWLIST_FILE,
+  crate::budget::BUDGET_FILE,
+  crate::env_policy::ENV_POLICY_FILE,
+  crate::logging::LOGGING_FILE,
+  crate::notifier::PREFS_FILE,
+  crate::permissions::PERMISSIONS_FILE,
+  crate::workspace::WORKSPACES_FILE,
+];
+
+#[derive(Default)]
+struct Bundle {
+  entries: Vec<(String, Vec<u8>)>,
+}
+
+impl Bundle {
+  fn add_text(&mut self, name: String, text: &str) {
+    self.entries.push((name, redact(text).into_bytes()));
+  }
+
+  fn add_json(&mut self, name: &str, value: &impl Serialize) {
+    let mut value = serde_json::to_value(value).unwrap_or(Value::Null);
+    redact_json(&mut value);
+    let text = serde_json::to_string_pretty(&value).unwrap_or_default();
+    // Key-based patterns (`"apiKey": "..."`) need the serialized form.
+    self.add_text(name.to_string(), &text);
+  }
+}
+
+/// The last `MAX_LOG_BYTES` of a file, starting at a line boundary.
+fn read_tail(path: &Path) -> Option<String> {
+  let mut file = File::open(path).ok()?;
+  let len = file.metadata().ok()?.len();
+  let start = len.saturating_sub(MAX_LOG_BYTES);
+  file.seek(SeekFrom::Start(start)).ok()?;
+  let mut bytes = Vec::new();
+  file.read_to_end(&mut bytes).ok()?;
+
+  let text = String::from_utf8_lossy(&bytes).to_string();
+  if start == 0 {
+    return Some(text);
+  }
+  Some(text.split_once('\n').map(|(_, rest)| rest.to_string()).unwrap_or(text))
+}
+
+/// Newest files in `dir` whose name satisfies `keep`.
+fn newest_files(dir: &Path, limit: usize, keep: impl Fn(&str) -> bool) -> Vec<PathBuf> {
+  let Ok(entries) = fs::read_dir(dir) else {
+    return Vec::new();
+  };
+  let mut files: Vec<(std::time::SystemTime, PathBuf)> = entries
+    .filter_map(Result::ok)
+    .filter(|entry| keep(&entry.file_name().to_string_lossy()))
+    .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+    .collect();
+  files.sort_by(|a, b| b.0.cmp(&a.0));
+  files.into_iter().take(limit).map(|(_, path)| path).collect()
+}
+
+fn add_log_files(bundle: &mut Bundle, prefix: &str, files: Vec<PathBuf>) {
+  for path in files {
+    let Some(name) = path.file_name().map(|n| n.to_string_lossy().to_string()) else {
+      continue;
+    };
+    if let Some(text) = read_tail(&path) {
+      bundle.add_text(format!("{prefix}/{name}"), &text);
+    }
+  }
+}
+
+/// Where opencode keeps its own logs (`$XDG_DATA_HOME/opencode/log`).
+fn engine_log_dir() -> Option<PathBuf> {
+  opencode_data_dir().map(|dir| dir.join("log"))
+}
+
+fn tool_version(program: &str) -> Option<String> {
+  let output = exec::output(exec::command(program).arg("--version").stdin(Stdio::null())).ok()?;
+  let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+  (!text.is_empty()).then_some(text)
+}
+
+fn environment_summary(app: &AppHandle, manager: &EngineManager) -> Value {
+  let engine = {
+    let mut state = manager.main_engine();
+    let mut info = serde_json::to_value(EngineManager::snapshot_locked(&mut state)).unwrap_or(Value::Null);
+    if let Some(info) = info.as_object_mut() {
+      info.remove("authToken");
+    }
+    info
+  };
+
+  // Names only: values routinely hold credentials.
+  let mut variables: Vec<String> = env::vars().map(|(name, _)| name).collect();
+  variables.sort();
+
+  let tools: BTreeMap<&str, Option<String>> = ["git", "node", "npm", "pnpm", "bun", "rg"]
+    .into_iter()
+    .map(|tool| (tool, tool_version(tool)))
+    .collect();
+
+  json!({
+    "appVersion": app.package_info().version.to_string(),
+    "os": env::consts::OS,
+    "arch": env::consts::ARCH,
+    "family": env::consts::FAMILY,
+    "shell": crate::shell::Shell::user().program.display().to_string(),
+    "path": env::var_os("PATH").map(|p| env::split_paths(&p).map(|p| p.display().to_string()).collect::<Vec<_>>()),
+    "environmentVariables": variables,
+    "tools": tools,
+    "engine": engine,
+  })
+}
+
+fn add_config(bundle: &mut Bundle, name: &str, path: &Path) {
+  if let Ok(content) = fs::read_to_string(path) {
+    bundle.add_text(format!("config/{name}"), &content);
+  }
+}
+
+#[tauri::command]
+pub fn debug_bundle_create(
+  app: AppHandle,
+  manager: State<EngineManager>,
+  consent: State<ConsentManager>,
+  dest: String,
+  project_dir: Option<String>,
+  confirmation_id: Option<String>,
+) -> Result<ArchiveResult, OpenWorkError> {
+  let dest = export_target(&dest, "dest", &consent, confirmation_id.as_deref())?;
+  crate::telemetry::record("feature.debug_bundle");
+  let mut bundle = Bundle::default();
+
+  if let Ok(dir) = logging::log_dir(&app) {
+    let files = newest_files(&dir, MAX_APP_LOG_FILES, |name| {
+      name.starts_with(logging::LOG_FILE_PREFIX) && name.ends_with(logging::LOG_FILE_SUFFIX)
+    });
+    add_log_files(&mut bundle, "logs", files);
+  }
+
+  if let Some(dir) = engine_log_dir() {
+    let files = newest_files(&dir, MAX_ENGINE_LOG_FILES, |name| name.ends_with(".log"));
+    add_log_files(&mut bundle, "engine-logs", files);
+  }
+
+  bundle.add_json("doctor.json", &doctor_report(&app.state::<EngineCache>(), true));
+  bundle.add_json("environment.json", &environment_summary(&app, &manager));
+
+  for file in SETTINGS_FILES {
+    if let Ok(path) = app_state_path(&app, file) {
+      add_config(&mut bundle, &format!("openwork/{file}"), &path);
+    }
+  }
+
+  if let Ok(path) = resolve_opencode_config_path("global", "") {
+    add_config(&mut bundle, "opencode-global.json", &path);
+  }
+  if let Some(project_dir) = project_dir {
+    let path = resolve_opencode_config_path("project", &project_dir)?;
+    add_config(&mut bundle, "opencode-project.json", &path);
+  }
+
+  if let Ok(reports) = crash::crash_reports_list() {
+    for report in reports.into_iter().take(MAX_CRASH_REPORTS) {
+      if let Ok(text) = crash::crash_report_read(report.id.clone()) {
+        bundle.add_text(format!("crashes/{}", report.id), &text);
+      }
+    }
+  }
+
+  let result = write_zip_bytes(&dest, &bundle.entries)?;
+  tracing::info!(path = %result.path, files = result.files, "debug bundle created");
+  Ok(result)
+}
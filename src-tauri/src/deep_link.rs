@@ -0,0 +1,193 @@
+//! `openwork://` links, e.g. `openwork://open?project=/path/to/repo` or
+//! `openwork://install-skill?source=github:owner/skills`.
+//!
+//! Any web page can fire one of these, so nothing happens until the user
+//! approves a native dialog describing exactly what the link will do.
+
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State, Url};
+use tauri_plugin_deep_link::DeepLinkExt;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::{args, error::OpenWorkError, instance, paths};
+
+pub const SCHEME: &str = "openwork";
+pub const INSTALL_SKILL_EVENT: &str = "app://install-skill";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeepLink {
+  OpenProject { project_dir: String },
+  InstallSkill { source: String },
+}
+
+/// Approved skill installs, kept until the UI is connected to a project and
+/// takes them.
+#[derive(Default)]
+pub struct DeepLinkRequests {
+  pending_skill: Mutex<Option<String>>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallSkillRequest {
+  pub source: String,
+}
+
+fn query_param(url: &Url, name: &str) -> Result<String, OpenWorkError> {
+  url
+    .query_pairs()
+    .find(|(key, _)| key == name)
+    .map(|(_, value)| value.into_owned())
+    .filter(|value| !value.trim().is_empty())
+    .ok_or_else(|| OpenWorkError::invalid_argument(format!("Link is missing the '{name}' parameter")))
+}
+
+pub fn parse(url: &Url) -> Result<DeepLink, OpenWorkError> {
+  if url.scheme() != SCHEME {
+    return Err(OpenWorkError::invalid_argument(format!(
+      "Unsupported link scheme: {}",
+      url.scheme()
+    )));
+  }
+
+  match url.host_str().unwrap_or_default() {
+    "open" => {
+      let project = query_param(url, "project")?;
+      let project_dir = paths::existing_dir(&project, "project")?;
+      Ok(DeepLink::OpenProject {
+        project_dir: project_dir.to_string_lossy().to_string(),
+      })
+    }
+    "install-skill" => {
+      let source = args::package_spec(&query_param(url, "source")?, "source")?;
+      Ok(DeepLink::InstallSkill { source })
+    }
+    other => Err(OpenWorkError::invalid_argument(format!("Unsupported link action: {other}"))),
+  }
+}
+
+fn describe(link: &DeepLink) -> String {
+  match link {
+    DeepLink::OpenProject { project_dir } => {
+      format!("A link wants to open this folder in OpenWork and start an engine in it:\n\n{project_dir}")
+    }
+    DeepLink::InstallSkill { source } => {
+      format!("A link wants to install a skill into the current project from:\n\n{source}")
+    }
+  }
+}
+
+fn run(app: &AppHandle, link: DeepLink) {
+  match link {
+    DeepLink::OpenProject { project_dir } => instance::request_open(app, project_dir),
+    DeepLink::InstallSkill { source } => {
+      *app
+        .state::<DeepLinkRequests>()
+        .pending_skill
+        .lock()
+        .expect("deep link mutex poisoned") = Some(source.clone());
+      let _ = app.emit(INSTALL_SKILL_EVENT, InstallSkillRequest { source });
+    }
+  }
+}
+
+/// Parses a link and, once the user approves, carries it out.
+pub fn handle(app: &AppHandle, url: &Url) {
+  let link = match parse(url) {
+    Ok(link) => link,
+    Err(e) => {
+      tracing::warn!(url = %url, error = %e, "ignoring deep link");
+      return;
+    }
+  };
+  tracing::info!(url = %url, "deep link received");
+  instance::focus_main_window(app);
+
+  let handle = app.clone();
+  app
+    .dialog()
+    .message(describe(&link))
+    .title("Open link")
+    .kind(MessageDialogKind::Info)
+    .buttons(MessageDialogButtons::OkCancelCustom(
+      "Continue".to_string(),
+      "Cancel".to_string(),
+    ))
+    .show(move |approved| {
+      if approved {
+        run(&handle, link);
+      } else {
+        tracing::info!("deep link declined");
+      }
+    });
+}
+
+/// Handles links that launched the app and listens for later ones.
+pub fn init(app: &AppHandle) {
+  // Installers register the scheme; this covers dev builds and AppImages.
+  #[cfg(any(windows, target_os = "linux"))]
+  if let Err(e) = app.deep_link().register_all() {
+    tracing::warn!(error = %e, "failed to register openwork:// links");
+  }
+
+  if let Ok(Some(urls)) = app.deep_link().get_current() {
+    for url in &urls {
+      handle(app, url);
+    }
+  }
+
+  let handle_app = app.clone();
+  app.deep_link().on_open_url(move |event| {
+    for url in &event.urls() {
+      handle(&handle_app, url);
+    }
+  });
+}
+
+/// Returns the approved skill source, if any, and clears it.
+#[tauri::command]
+pub fn deep_link_skill_take(requests: State<DeepLinkRequests>) -> Option<String> {
+  requests.pending_skill.lock().expect("deep link mutex poisoned").take()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn link(raw: &str) -> Result<DeepLink, OpenWorkError> {
+    parse(&Url::parse(raw).unwrap())
+  }
+
+  #[test]
+  fn parses_supported_actions() {
+    let dir = std::env::temp_dir();
+    let url = Url::parse_with_params("openwork://open", [("project", dir.to_string_lossy())]).unwrap();
+    assert!(matches!(parse(&url), Ok(DeepLink::OpenProject { .. })));
+
+    assert_eq!(
+      link("openwork://install-skill?source=github%3Aowner%2Fskills").unwrap(),
+      DeepLink::InstallSkill {
+        source: "github:owner/skills".to_string()
+      }
+    );
+  }
+
+  #[test]
+  fn rejects_bad_links() {
+    assert!(link("https://open?project=/tmp").is_err());
+    assert!(link("openwork://open").is_err());
+    assert!(link("openwork://open?project=/definitely/not/here").is_err());
+    assert!(link("openwork://install-skill?source=file%3A%2F%2F%2Fetc").is_err());
+    assert!(link("openwork://delete?project=/tmp").is_err());
+  }
+
+  #[test]
+  fn rejects_non_ascii_links_without_panicking() {
+    assert!(link("openwork://install-skill?source=%C3%A9").is_err());
+    assert!(link("openwork://install-skill?source=%C3%A9%401").is_err());
+    assert!(link("openwork://open?project=%C3%A9").is_err());
+    assert!(link("openwork://open?project=%2F%C3%A9-openwork-missing").is_err());
+  }
+}
@@ -0,0 +1,430 @@
+//! Running the engine inside a project's devcontainer.
+//!
+//! A project with `.devcontainer/devcontainer.json` (or `.devcontainer.json`)
+//! can opt in per project. The engine is then started in the container with
+//! the devcontainer CLI (`devcontainer up` + `devcontainer exec`), or with
+//! `docker exec` in an already running container when the CLI isn't
+//! installed, so it uses the container's toolchain. opencode has to be
+//! installed in the image; it uses the container's own config and
+//! credentials.
+//!
+//! The engine listens on the container's address, which the host reaches
+//! directly only on Linux; Docker Desktop doesn't route container addresses.
+//! Paths differ inside the container (`/workspaces/<name>` by default);
+//! `devcontainer_map_path` translates between the two.
+
+use std::{
+  collections::BTreeSet,
+  fs,
+  path::{Component, Path, PathBuf},
+  process::{Command, Stdio},
+  sync::RwLock,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::AppHandle;
+
+use crate::{
+  env_policy,
+  error::{ErrorCode, OpenWorkError},
+  exec, jsonc, paths, resolve_in_path,
+  store::{read_state, write_state},
+};
+
+pub const DEVCONTAINER_FILE: &str = "devcontainer-projects.json";
+
+const CONFIG_PATHS: &[&str] = &[".devcontainer/devcontainer.json", ".devcontainer.json"];
+/// Runs the engine (`"$@"`) until stdin closes. `docker exec` doesn't stop the
+/// process in the container when the client is killed, but the client's exit
+/// closes the exec's stdin.
+const SUPERVISOR: &str = "\"$@\" & pid=$!; (cat >/dev/null; kill $pid 2>/dev/null) & wait $pid";
+
+/// Projects that start their engine in the devcontainer.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DevcontainerSettings {
+  pub projects: BTreeSet<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DevcontainerInfo {
+  pub config_path: String,
+  pub name: Option<String>,
+  /// Where the project is mounted in the container.
+  pub workspace_folder: String,
+  pub enabled: bool,
+  /// The devcontainer CLI, when installed.
+  pub cli: Option<String>,
+  pub docker: bool,
+}
+
+/// The container an engine runs in.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ContainerTarget {
+  pub id: String,
+  pub workspace_folder: String,
+  /// The container's address on the Docker network.
+  pub address: String,
+}
+
+static ACTIVE: RwLock<Option<DevcontainerSettings>> = RwLock::new(None);
+
+fn active() -> DevcontainerSettings {
+  ACTIVE
+    .read()
+    .expect("devcontainer settings lock poisoned")
+    .clone()
+    .unwrap_or_default()
+}
+
+pub fn init(app: &AppHandle) {
+  let settings = read_state::<DevcontainerSettings>(app, DEVCONTAINER_FILE).unwrap_or_else(|e| {
+    tracing::error!(error = %e, "failed to load devcontainer settings");
+    DevcontainerSettings::default()
+  });
+  *ACTIVE.write().expect("devcontainer settings lock poisoned") = Some(settings);
+}
+
+fn config_path(project_dir: &Path) -> Option<PathBuf> {
+  CONFIG_PATHS
+    .iter()
+    .map(|relative| project_dir.join(relative))
+    .find(|path| path.is_file())
+}
+
+fn read_config(path: &Path) -> Option<Value> {
+  let text = fs::read_to_string(path).ok()?;
+  serde_json::from_str(&jsonc::strip(&text)).ok()
+}
+
+/// The in-container project folder: the config's `workspaceFolder` or the
+/// devcontainer default, `/workspaces/<folder name>`.
+fn workspace_folder(config: Option<&Value>, project_dir: &Path) -> String {
+  let basename = project_dir
+    .file_name()
+    .map(|name| name.to_string_lossy().to_string())
+    .unwrap_or_default();
+  config
+    .and_then(|config| config.get("workspaceFolder"))
+    .and_then(Value::as_str)
+    .map(|folder| folder.replace("${localWorkspaceFolderBasename}", &basename))
+    .filter(|folder| folder.starts_with('/'))
+    .unwrap_or_else(|| format!("/workspaces/{basename}"))
+}
+
+/// `path` (inside `host_root`) as seen in the container.
+fn host_to_container(host_root: &Path, container_root: &str, path: &Path) -> Option<String> {
+  let relative = path.strip_prefix(host_root).ok()?;
+  let mut mapped = container_root.trim_end_matches('/').to_string();
+  for component in relative.components() {
+    match component {
+      Component::Normal(part) => {
+        mapped.push('/');
+        mapped.push_str(part.to_str()?);
+      }
+      Component::CurDir => {}
+      _ => return None,
+    }
+  }
+  Some(if mapped.is_empty() {
+    "/".to_string()
+  } else {
+    mapped
+  })
+}
+
+/// A container path under `container_root` as a path inside `host_root`.
+fn container_to_host(host_root: &Path, container_root: &str, path: &str) -> Option<PathBuf> {
+  let root = container_root.trim_end_matches('/');
+  let rest = path.strip_prefix(root)?;
+  if !(rest.is_empty() || rest.starts_with('/')) {
+    return None;
+  }
+  let mut mapped = host_root.to_path_buf();
+  for part in rest.split('/').filter(|part| !part.is_empty() && *part != ".") {
+    if part == ".." {
+      return None;
+    }
+    mapped.push(part);
+  }
+  Some(mapped)
+}
+
+/// Whether `project_dir` starts its engine in the devcontainer.
+pub fn enabled_for(project_dir: &str) -> bool {
+  active().projects.contains(project_dir) && config_path(Path::new(project_dir)).is_some()
+}
+
+fn cli_path() -> Option<PathBuf> {
+  resolve_in_path(if cfg!(windows) {
+    "devcontainer.cmd"
+  } else {
+    "devcontainer"
+  })
+}
+
+fn run(command: &mut Command, what: &str) -> Result<String, OpenWorkError> {
+  env_policy::apply(command, env_policy::EnvTarget::Engine);
+  command
+    .stdin(Stdio::null())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped());
+  let output = exec::output(command).map_err(|e| {
+    let code = if e.kind() == std::io::ErrorKind::NotFound {
+      ErrorCode::ToolNotFound
+    } else {
+      ErrorCode::Io
+    };
+    OpenWorkError::new(code, format!("Failed to run {what}: {e}"))
+  })?;
+  if !output.status.success() {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    return Err(OpenWorkError::new(
+      ErrorCode::EngineStartFailed,
+      format!("{what} failed: {}", crate::redact::redact(stderr.trim())),
+    ));
+  }
+  Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The container's IP address, from `docker inspect`.
+fn container_address(id: &str) -> Result<String, OpenWorkError> {
+  let mut inspect = exec::command("docker");
+  inspect.args([
+    "inspect",
+    "-f",
+    "{{range .NetworkSettings.Networks}}{{.IPAddress}} {{end}}",
+    id,
+  ]);
+  let output = run(&mut inspect, "docker inspect")?;
+  output
+    .split_whitespace()
+    .next()
+    .map(str::to_string)
+    .ok_or_else(|| {
+      OpenWorkError::new(
+        ErrorCode::EngineStartFailed,
+        "The devcontainer has no network address the engine could listen on",
+      )
+    })
+}
+
+/// Starts (or finds) the project's devcontainer.
+pub fn prepare(project_dir: &str) -> Result<ContainerTarget, OpenWorkError> {
+  if !cfg!(target_os = "linux") {
+    return Err(OpenWorkError::new(
+      ErrorCode::EngineStartFailed,
+      "Running the engine in a devcontainer is only supported on Linux, where the host can reach container addresses.",
+    ));
+  }
+  let dir = Path::new(project_dir);
+  let config = config_path(dir).and_then(|path| read_config(&path));
+
+  let (id, workspace_folder) = match cli_path() {
+    Some(cli) => {
+      let mut up = exec::command(&cli);
+      up.args(["up", "--workspace-folder", project_dir]);
+      let output = run(&mut up, "devcontainer up")?;
+      // The result is the last line of JSON on stdout.
+      let result = output
+        .lines()
+        .rev()
+        .find_map(|line| serde_json::from_str::<Value>(line.trim()).ok())
+        .unwrap_or(Value::Null);
+      let id = result.get("containerId").and_then(Value::as_str).ok_or_else(|| {
+        OpenWorkError::new(
+          ErrorCode::EngineStartFailed,
+          "devcontainer up did not report a container",
+        )
+        .with_details(serde_json::json!({ "output": crate::redact::redact(&output) }))
+      })?;
+      let folder = result
+        .get("remoteWorkspaceFolder")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .unwrap_or_else(|| workspace_folder(config.as_ref(), dir));
+      (id.to_string(), folder)
+    }
+    None => {
+      let mut ps = exec::command("docker");
+      ps.args(["ps", "-q", "--filter"])
+        .arg(format!("label=devcontainer.local_folder={project_dir}"));
+      let output = run(&mut ps, "docker ps")?;
+      let id = output.split_whitespace().next().ok_or_else(|| {
+        OpenWorkError::new(
+          ErrorCode::EngineStartFailed,
+          "The devcontainer isn't running. Start it from your editor, or install the devcontainer CLI (npm install -g @devcontainers/cli).",
+        )
+      })?;
+      (id.to_string(), workspace_folder(config.as_ref(), dir))
+    }
+  };
+  let address = container_address(&id)?;
+  tracing::info!(container = %id, address = %address, workspace = %workspace_folder, "devcontainer ready");
+  Ok(ContainerTarget {
+    id,
+    workspace_folder,
+    address,
+  })
+}
+
+/// A command that runs `opencode` with `args` in the container, with `env`
+/// set there. Spawn it with stdin piped and keep stdin open: closing it stops
+/// the engine.
+pub fn engine_command(
+  target: &ContainerTarget,
+  project_dir: &str,
+  args: &[String],
+  env: &[(String, String)],
+) -> Command {
+  let mut command = match cli_path() {
+    Some(cli) => {
+      let mut command = exec::command(cli);
+      env_policy::apply(&mut command, env_policy::EnvTarget::Engine);
+      command.args(["exec", "--workspace-folder", project_dir]);
+      // The CLI only takes values on the command line.
+      for (key, value) in env {
+        command.arg("--remote-env").arg(format!("{key}={value}"));
+      }
+      command
+    }
+    None => {
+      let mut command = exec::command("docker");
+      env_policy::apply(&mut command, env_policy::EnvTarget::Engine);
+      command.args(["exec", "-i", "-w", &target.workspace_folder]);
+      // `-e NAME` copies the value from docker's own environment, keeping it
+      // off the command line.
+      for (key, value) in env {
+        command.arg("-e").arg(key).env(key, value);
+      }
+      command.arg(&target.id);
+      command
+    }
+  };
+  command
+    .args(["sh", "-c", SUPERVISOR, "sh", "opencode"])
+    .args(args)
+    .current_dir(project_dir)
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped());
+  command
+}
+
+#[tauri::command]
+pub fn devcontainer_detect(project_dir: String) -> Result<Option<DevcontainerInfo>, OpenWorkError> {
+  let dir = paths::allowed_dir(&project_dir, "projectDir")?;
+  let Some(path) = config_path(&dir) else {
+    return Ok(None);
+  };
+  let config = read_config(&path);
+  Ok(Some(DevcontainerInfo {
+    config_path: path.to_string_lossy().to_string(),
+    name: config
+      .as_ref()
+      .and_then(|config| config.get("name"))
+      .and_then(Value::as_str)
+      .map(str::to_string),
+    workspace_folder: workspace_folder(config.as_ref(), &dir),
+    enabled: active().projects.contains(dir.to_string_lossy().as_ref()),
+    cli: cli_path().map(|cli| cli.to_string_lossy().to_string()),
+    docker: resolve_in_path(if cfg!(windows) { "docker.exe" } else { "docker" }).is_some(),
+  }))
+}
+
+/// Turns running the project's engine in its devcontainer on or off; applies
+/// on the next engine start.
+#[tauri::command]
+pub fn devcontainer_set_enabled(
+  app: AppHandle,
+  project_dir: String,
+  enabled: bool,
+) -> Result<bool, OpenWorkError> {
+  let dir = paths::allowed_dir(&project_dir, "projectDir")?;
+  if enabled && config_path(&dir).is_none() {
+    return Err(OpenWorkError::new(
+      ErrorCode::NotFound,
+      "This project has no .devcontainer/devcontainer.json",
+    ));
+  }
+  let mut settings = active();
+  let key = dir.to_string_lossy().to_string();
+  if enabled {
+    settings.projects.insert(key);
+  } else {
+    settings.projects.remove(&key);
+  }
+  write_state(&app, DEVCONTAINER_FILE, &settings)?;
+  *ACTIVE.write().expect("devcontainer settings lock poisoned") = Some(settings);
+  Ok(enabled)
+}
+
+/// Translates a path between the host and the devcontainer: host paths inside
+/// the project to container paths when `to_container`, and back otherwise.
+#[tauri::command]
+pub fn devcontainer_map_path(
+  project_dir: String,
+  path: String,
+  to_container: bool,
+  workspace_folder: Option<String>,
+) -> Result<String, OpenWorkError> {
+  let dir = paths::allowed_dir(&project_dir, "projectDir")?;
+  // The running engine's folder, when the caller has it, wins over the config.
+  let container_root = workspace_folder.unwrap_or_else(|| {
+    let config = config_path(&dir).and_then(|path| read_config(&path));
+    workspace_folder(config.as_ref(), &dir)
+  });
+  let mapped = if to_container {
+    host_to_container(&dir, &container_root, Path::new(&path))
+  } else {
+    container_to_host(&dir, &container_root, &path).map(|path| path.to_string_lossy().to_string())
+  };
+  mapped.ok_or_else(|| OpenWorkError::invalid_argument(format!("{path} is outside the project")))
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+
+  #[test]
+  fn resolves_workspace_folder() {
+    let dir = Path::new("/home/ada/app");
+    assert_eq!(workspace_folder(None, dir), "/workspaces/app");
+    let config = json!({ "workspaceFolder": "/src/${localWorkspaceFolderBasename}" });
+    assert_eq!(workspace_folder(Some(&config), dir), "/src/app");
+  }
+
+  #[test]
+  fn maps_paths_both_ways() {
+    let host = Path::new("/home/ada/app");
+    assert_eq!(
+      host_to_container(host, "/workspaces/app", Path::new("/home/ada/app/src/main.rs")).as_deref(),
+      Some("/workspaces/app/src/main.rs")
+    );
+    assert_eq!(
+      host_to_container(host, "/workspaces/app/", host).as_deref(),
+      Some("/workspaces/app")
+    );
+    assert_eq!(
+      host_to_container(host, "/workspaces/app", Path::new("/etc/passwd")),
+      None
+    );
+    assert_eq!(
+      container_to_host(host, "/workspaces/app", "/workspaces/app/src/lib.rs"),
+      Some(PathBuf::from("/home/ada/app/src/lib.rs"))
+    );
+    assert_eq!(
+      container_to_host(host, "/workspaces/app", "/workspaces/application"),
+      None
+    );
+    assert_eq!(
+      container_to_host(host, "/workspaces/app", "/workspaces/app/../x"),
+      None
+    );
+  }
+}
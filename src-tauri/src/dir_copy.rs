@@ -0,0 +1,347 @@
+//! Directory copy used by skill import.
+//!
+//! The tree is walked iteratively, so deep folders can't overflow the stack,
+//! and files are copied by a small worker pool, which matters for skills made
+//! of many small files. Symlinks follow a `SymlinkPolicy`; other non-regular
+//! entries are skipped. On Windows the copy runs on extended-length paths,
+//! so skills nested past `MAX_PATH` copy too.
+
+use std::{
+  collections::HashSet,
+  fs,
+  path::{Path, PathBuf},
+  sync::{
+    atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    Mutex,
+  },
+  thread,
+  time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{disk_space, error::OpenWorkError, paths};
+
+/// Skipped unless the caller passes its own exclusions.
+pub const DEFAULT_EXCLUDES: &[&str] = &["node_modules", ".git"];
+
+const MAX_WORKERS: usize = 8;
+
+/// Minimum gap between progress reports; the final report is always sent.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// What to do with symlinks found in the source tree.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum SymlinkPolicy {
+  /// Leave them out.
+  Skip,
+  /// Copy what they point to. Links back into a folder already being copied
+  /// are skipped, so cycles end.
+  Follow,
+  /// Recreate the link, as long as it resolves inside the source tree; links
+  /// that escape it or dangle are skipped, since the copy would point
+  /// somewhere unexpected.
+  #[default]
+  Preserve,
+}
+
+#[derive(Debug, Clone)]
+pub struct CopyOptions {
+  /// File or folder names to leave out, see `matches_pattern`.
+  pub exclude: Vec<String>,
+  pub symlinks: SymlinkPolicy,
+}
+
+impl Default for CopyOptions {
+  fn default() -> Self {
+    Self {
+      exclude: DEFAULT_EXCLUDES.iter().map(|name| name.to_string()).collect(),
+      symlinks: SymlinkPolicy::default(),
+    }
+  }
+}
+
+#[derive(Debug, Serialize, Clone, Copy, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyProgress {
+  pub files_copied: u64,
+  pub files_total: u64,
+  pub bytes_copied: u64,
+  pub bytes_total: u64,
+}
+
+/// Whether `name` matches `pattern`: an exact file or folder name, or a name
+/// with one `*` wildcard such as `*.log`.
+pub fn matches_pattern(name: &str, pattern: &str) -> bool {
+  match pattern.split_once('*') {
+    Some((prefix, suffix)) => {
+      name.len() >= prefix.len() + suffix.len() && name.starts_with(prefix) && name.ends_with(suffix)
+    }
+    None => name == pattern,
+  }
+}
+
+struct Plan {
+  dirs: Vec<PathBuf>,
+  /// `(from, to, len)` for every regular file.
+  files: Vec<(PathBuf, PathBuf, u64)>,
+  /// `(target, to, target_is_dir)` for every preserved symlink.
+  links: Vec<(PathBuf, PathBuf, bool)>,
+}
+
+/// Link target for `Preserve`, if the link resolves inside `root`.
+fn contained_link(link: &Path, root: &Path) -> Option<(PathBuf, bool)> {
+  let target = fs::read_link(link).ok()?;
+  let resolved = fs::canonicalize(link).ok()?;
+  resolved
+    .starts_with(root)
+    .then(|| (target, resolved.is_dir()))
+}
+
+fn plan(src: &Path, dest: &Path, options: &CopyOptions) -> Result<Plan, String> {
+  let mut plan = Plan {
+    dirs: vec![dest.to_path_buf()],
+    files: Vec::new(),
+    links: Vec::new(),
+  };
+  let root = fs::canonicalize(src).map_err(|e| format!("Failed to resolve {}: {e}", paths::display(src)))?;
+  // Canonical folders already queued; only needed when links are followed.
+  let mut visited = HashSet::from([root.clone()]);
+  let mut pending = vec![(src.to_path_buf(), dest.to_path_buf())];
+
+  while let Some((from_dir, to_dir)) = pending.pop() {
+    let entries = fs::read_dir(&from_dir)
+      .map_err(|e| format!("Failed to read dir {}: {e}", paths::display(&from_dir)))?;
+    for entry in entries {
+      let entry = entry.map_err(|e| e.to_string())?;
+      let name = entry.file_name();
+      if options
+        .exclude
+        .iter()
+        .any(|pattern| matches_pattern(&name.to_string_lossy(), pattern))
+      {
+        continue;
+      }
+
+      let mut file_type = entry.file_type().map_err(|e| e.to_string())?;
+      let from = entry.path();
+      let to = to_dir.join(&name);
+      if file_type.is_symlink() {
+        match options.symlinks {
+          SymlinkPolicy::Skip => {
+            tracing::debug!(path = %from.display(), "skipping symlink");
+            continue;
+          }
+          SymlinkPolicy::Preserve => {
+            match contained_link(&from, &root) {
+              Some((target, is_dir)) => plan.links.push((target, to, is_dir)),
+              None => tracing::warn!(path = %from.display(), "skipping symlink that leaves the folder"),
+            }
+            continue;
+          }
+          SymlinkPolicy::Follow => match fs::metadata(&from) {
+            Ok(metadata) => file_type = metadata.file_type(),
+            Err(_) => {
+              tracing::warn!(path = %from.display(), "skipping dangling symlink");
+              continue;
+            }
+          },
+        }
+      }
+
+      if file_type.is_dir() {
+        if options.symlinks == SymlinkPolicy::Follow {
+          let canonical = fs::canonicalize(&from).map_err(|e| e.to_string())?;
+          if !visited.insert(canonical) {
+            tracing::warn!(path = %from.display(), "skipping symlink cycle");
+            continue;
+          }
+        }
+        plan.dirs.push(to.clone());
+        pending.push((from, to));
+      } else if file_type.is_file() {
+        let len = fs::metadata(&from).map(|m| m.len()).unwrap_or(0);
+        plan.files.push((from, to, len));
+      }
+    }
+  }
+
+  Ok(plan)
+}
+
+#[cfg(unix)]
+fn create_link(target: &Path, to: &Path, _is_dir: bool) -> std::io::Result<()> {
+  std::os::unix::fs::symlink(target, to)
+}
+
+#[cfg(windows)]
+fn create_link(target: &Path, to: &Path, is_dir: bool) -> std::io::Result<()> {
+  if is_dir {
+    std::os::windows::fs::symlink_dir(target, to)
+  } else {
+    std::os::windows::fs::symlink_file(target, to)
+  }
+}
+
+/// Copies `src` into `dest` according to `options`. `on_progress` is called
+/// from the worker threads as files land. Nothing is written when `dest`'s
+/// volume can't hold the files (`INSUFFICIENT_SPACE`).
+pub fn copy_dir(
+  src: &Path,
+  dest: &Path,
+  options: &CopyOptions,
+  on_progress: impl Fn(CopyProgress) + Sync,
+) -> Result<CopyProgress, OpenWorkError> {
+  if !src.is_dir() {
+    return Err(format!("Source is not a directory: {}", paths::display(src)).into());
+  }
+  let (src, dest) = (&paths::extended(src), &paths::extended(dest));
+
+  let plan = plan(src, dest, options)?;
+  disk_space::ensure(dest, plan.files.iter().map(|(_, _, len)| len).sum())?;
+  for dir in &plan.dirs {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create dir {}: {e}", paths::display(dir)))?;
+  }
+  for (target, to, is_dir) in &plan.links {
+    create_link(target, to, *is_dir)
+      .map_err(|e| format!("Failed to create symlink {}: {e}", paths::display(to)))?;
+  }
+
+  let files_total = plan.files.len() as u64;
+  let bytes_total = plan.files.iter().map(|(_, _, len)| len).sum();
+  let next = AtomicUsize::new(0);
+  let files_copied = AtomicU64::new(0);
+  let bytes_copied = AtomicU64::new(0);
+  let failed = AtomicBool::new(false);
+  let error = Mutex::new(None::<String>);
+  let last_report = Mutex::new(Instant::now());
+  let progress = || CopyProgress {
+    files_copied: files_copied.load(Ordering::Relaxed),
+    files_total,
+    bytes_copied: bytes_copied.load(Ordering::Relaxed),
+    bytes_total,
+  };
+
+  let workers = thread::available_parallelism()
+    .map(|n| n.get())
+    .unwrap_or(1)
+    .min(MAX_WORKERS)
+    .min(plan.files.len())
+    .max(1);
+  thread::scope(|scope| {
+    for _ in 0..workers {
+      scope.spawn(|| {
+        while !failed.load(Ordering::Relaxed) {
+          let Some((from, to, _)) = plan.files.get(next.fetch_add(1, Ordering::Relaxed)) else {
+            break;
+          };
+          match fs::copy(from, to) {
+            Ok(copied) => {
+              files_copied.fetch_add(1, Ordering::Relaxed);
+              bytes_copied.fetch_add(copied, Ordering::Relaxed);
+            }
+            Err(e) => {
+              failed.store(true, Ordering::Relaxed);
+              let message =
+                format!("Failed to copy {} -> {}: {e}", paths::display(from), paths::display(to));
+              error.lock().expect("copy error mutex poisoned").get_or_insert(message);
+              break;
+            }
+          }
+
+          let mut last = last_report.lock().expect("copy progress mutex poisoned");
+          if last.elapsed() >= PROGRESS_INTERVAL {
+            *last = Instant::now();
+            on_progress(progress());
+          }
+        }
+      });
+    }
+  });
+
+  if let Some(message) = error.into_inner().expect("copy error mutex poisoned") {
+    return Err(message.into());
+  }
+  let done = progress();
+  on_progress(done);
+  Ok(done)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn matches_names_and_wildcards() {
+    assert!(matches_pattern("node_modules", "node_modules"));
+    assert!(!matches_pattern("node_modules2", "node_modules"));
+    assert!(matches_pattern("debug.log", "*.log"));
+    assert!(matches_pattern(".DS_Store", ".DS_*"));
+    assert!(!matches_pattern("log", "*.log"));
+    assert!(matches_pattern("anything", "*"));
+  }
+
+  #[test]
+  fn copies_nested_trees_with_exclusions() {
+    let root = std::env::temp_dir().join(format!("openwork-dir-copy-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let src = root.join("src");
+    let mut deep = src.clone();
+    for level in 0..200 {
+      deep = deep.join(format!("d{level}"));
+    }
+    fs::create_dir_all(&deep).unwrap();
+    fs::write(deep.join("leaf.txt"), "leaf").unwrap();
+    fs::write(src.join("SKILL.md"), "# Skill").unwrap();
+    fs::create_dir_all(src.join("node_modules/pkg")).unwrap();
+    fs::write(src.join("node_modules/pkg/index.js"), "").unwrap();
+
+    let dest = root.join("dest");
+    let done = copy_dir(&src, &dest, &CopyOptions::default(), |_| {}).unwrap();
+    assert_eq!(done.files_copied, 2);
+    assert_eq!(done.bytes_copied, done.bytes_total);
+    assert!(dest.join("SKILL.md").is_file());
+    assert!(dest.join(deep.strip_prefix(&src).unwrap()).join("leaf.txt").is_file());
+    assert!(!dest.join("node_modules").exists());
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn applies_symlink_policy() {
+    use std::os::unix::fs::symlink;
+
+    let root = std::env::temp_dir().join(format!("openwork-dir-copy-links-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let src = root.join("src");
+    fs::create_dir_all(src.join("scripts")).unwrap();
+    fs::write(src.join("scripts/run.sh"), "echo hi").unwrap();
+    fs::write(root.join("secret.txt"), "outside").unwrap();
+    symlink("scripts/run.sh", src.join("run.sh")).unwrap();
+    symlink("../secret.txt", src.join("secret.txt")).unwrap();
+    symlink("..", src.join("scripts/loop")).unwrap();
+
+    let preserve = CopyOptions::default();
+    copy_dir(&src, &root.join("preserved"), &preserve, |_| {}).unwrap();
+    let link = root.join("preserved/run.sh");
+    assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+    assert_eq!(fs::read_to_string(&link).unwrap(), "echo hi");
+    assert!(fs::symlink_metadata(root.join("preserved/secret.txt")).is_err());
+
+    let follow = CopyOptions {
+      symlinks: SymlinkPolicy::Follow,
+      ..CopyOptions::default()
+    };
+    copy_dir(&src, &root.join("followed"), &follow, |_| {}).unwrap();
+    assert!(!fs::symlink_metadata(root.join("followed/run.sh")).unwrap().file_type().is_symlink());
+    assert_eq!(fs::read_to_string(root.join("followed/secret.txt")).unwrap(), "outside");
+    assert!(!root.join("followed/scripts/loop").exists());
+
+    let skip = CopyOptions {
+      symlinks: SymlinkPolicy::Skip,
+      ..CopyOptions::default()
+    };
+    let done = copy_dir(&src, &root.join("skipped"), &skip, |_| {}).unwrap();
+    assert_eq!(done.files_copied, 1);
+  }
+}
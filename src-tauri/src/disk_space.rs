@@ -0,0 +1,114 @@
+//! Free-space checks before operations that write a lot.
+//!
+//! Installs, skill imports and archive extraction check the volume they
+//! write to first and fail with `INSUFFICIENT_SPACE` instead of stopping
+//! halfway on a full disk. `details` carries the path and the required and
+//! available bytes. When the free space can't be read, the operation goes
+//! ahead as before.
+
+use std::path::{Path, PathBuf};
+
+use serde_json::json;
+
+use crate::{
+  error::{ErrorCode, OpenWorkError},
+  paths,
+};
+
+/// Kept free on top of what an operation needs, so it doesn't leave the
+/// volume completely full.
+const HEADROOM: u64 = 32 * 1024 * 1024;
+
+/// A rough size for package installs, whose size isn't known up front.
+pub const PACKAGE_INSTALL_BYTES: u64 = 256 * 1024 * 1024;
+/// A rough size for the engine install: the binary plus the download.
+pub const ENGINE_INSTALL_BYTES: u64 = 512 * 1024 * 1024;
+
+/// `path`, or its nearest ancestor that exists, since the target of a write
+/// usually doesn't exist yet.
+fn existing_ancestor(path: &Path) -> Option<PathBuf> {
+  path.ancestors().find(|dir| dir.exists()).map(Path::to_path_buf)
+}
+
+/// Bytes as `1.5 GB`, for messages.
+pub fn format_bytes(bytes: u64) -> String {
+  const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+  if bytes < 1024 {
+    return format!("{bytes} B");
+  }
+  let mut value = bytes as f64 / 1024.0;
+  let mut unit = 0;
+  while value >= 1024.0 && unit < UNITS.len() - 1 {
+    value /= 1024.0;
+    unit += 1;
+  }
+  format!("{value:.1} {}", UNITS[unit])
+}
+
+fn check(target: &Path, required: u64, available: u64) -> Result<(), OpenWorkError> {
+  let required = required.saturating_add(HEADROOM);
+  if available >= required {
+    return Ok(());
+  }
+  Err(
+    OpenWorkError::new(
+      ErrorCode::InsufficientSpace,
+      format!(
+        "Not enough disk space for {}: needs {}, {} available",
+        paths::display(target),
+        format_bytes(required),
+        format_bytes(available)
+      ),
+    )
+    .with_details(json!({
+      "path": paths::display(target),
+      "requiredBytes": required,
+      "availableBytes": available,
+    })),
+  )
+}
+
+/// Fails with `INSUFFICIENT_SPACE` unless the volume `target` is on has
+/// `required` bytes free, plus some headroom.
+pub fn ensure(target: &Path, required: u64) -> Result<(), OpenWorkError> {
+  let Some(volume) = existing_ancestor(target) else {
+    return Ok(());
+  };
+  match fs2::available_space(&volume) {
+    Ok(available) => check(target, required, available),
+    Err(e) => {
+      tracing::warn!(path = %paths::display(&volume), error = %e, "couldn't read free disk space");
+      Ok(())
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn formats_bytes() {
+    assert_eq!(format_bytes(512), "512 B");
+    assert_eq!(format_bytes(1536), "1.5 KB");
+    assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.0 GB");
+  }
+
+  #[test]
+  fn finds_the_existing_ancestor() {
+    let dir = std::env::temp_dir();
+    assert_eq!(existing_ancestor(&dir.join("missing/deeper")), Some(dir.clone()));
+    assert_eq!(existing_ancestor(&dir), Some(dir));
+  }
+
+  #[test]
+  fn reports_required_and_available() {
+    let target = Path::new("/tmp/skill");
+    assert!(check(target, 1024, HEADROOM + 1024).is_ok());
+    let error = check(target, 1024, HEADROOM).unwrap_err();
+    assert_eq!(error.code, ErrorCode::InsufficientSpace);
+    let details = error.details.unwrap();
+    assert_eq!(details["requiredBytes"], HEADROOM + 1024);
+    assert_eq!(details["availableBytes"], HEADROOM);
+  }
+}
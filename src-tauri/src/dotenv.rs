@@ -0,0 +1,460 @@
+//! `.env` files in the project root.
+//!
+//! The files can be listed, read with values masked and edited key by key;
+//! edits keep comments and ordering and replace the file atomically. Selected
+//! keys of a file can be injected into the engine's environment, so tools the
+//! agent runs see them. The selection is stored per project and read on each
+//! engine start, like permission profiles.
+
+use std::{
+  collections::{BTreeMap, HashMap},
+  fs,
+  path::{Path, PathBuf},
+  process::Command,
+  sync::RwLock,
+};
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{
+  error::{ErrorCode, OpenWorkError},
+  paths, redact,
+  store::{read_state, write_state},
+};
+
+pub const DOTENV_FILE: &str = "dotenv-injection.json";
+
+const MASK: &str = "********";
+
+/// Keys to inject, by project directory and then `.env` file name.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DotenvSettings {
+  pub projects: HashMap<String, BTreeMap<String, Vec<String>>>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DotenvFile {
+  pub name: String,
+  pub path: String,
+  pub keys: usize,
+  pub injected: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DotenvEntry {
+  pub key: String,
+  pub value: String,
+  pub masked: bool,
+  pub injected: bool,
+  /// 1-based line in the file.
+  pub line: usize,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DotenvContent {
+  pub name: String,
+  pub entries: Vec<DotenvEntry>,
+}
+
+static ACTIVE: RwLock<Option<DotenvSettings>> = RwLock::new(None);
+
+fn active() -> DotenvSettings {
+  ACTIVE
+    .read()
+    .expect("dotenv settings lock poisoned")
+    .clone()
+    .unwrap_or_default()
+}
+
+pub fn init(app: &AppHandle) {
+  let settings = read_state::<DotenvSettings>(app, DOTENV_FILE).unwrap_or_else(|e| {
+    tracing::error!(error = %e, "failed to load dotenv injection settings");
+    DotenvSettings::default()
+  });
+  *ACTIVE.write().expect("dotenv settings lock poisoned") = Some(settings);
+}
+
+/// `.env`, `.env.local`, `.env.production` and the like; nothing with a path.
+fn is_env_file_name(name: &str) -> bool {
+  name == ".env"
+    || name.strip_prefix(".env.").is_some_and(|suffix| {
+      !suffix.is_empty()
+        && suffix
+          .chars()
+          .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+        && !suffix.contains("..")
+    })
+}
+
+pub fn is_valid_key(key: &str) -> bool {
+  key
+    .chars()
+    .next()
+    .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+    && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// The key and value of an assignment line, with or without `export`; `None`
+/// for blank lines, comments and anything else.
+fn parse_line(line: &str) -> Option<(&str, String)> {
+  let line = line.trim_start();
+  if line.starts_with('#') {
+    return None;
+  }
+  let line = line.strip_prefix("export ").map_or(line, str::trim_start);
+  let (key, raw) = line.split_once('=')?;
+  let key = key.trim_end();
+  if !is_valid_key(key) {
+    return None;
+  }
+  let raw = raw.trim();
+  let value = if let Some(rest) = raw.strip_prefix('"') {
+    let mut value = String::new();
+    let mut chars = rest.chars();
+    while let Some(c) = chars.next() {
+      match c {
+        '"' => break,
+        '\\' => match chars.next() {
+          Some('n') => value.push('\n'),
+          Some('r') => value.push('\r'),
+          Some('t') => value.push('\t'),
+          Some(other) => value.push(other),
+          None => value.push('\\'),
+        },
+        _ => value.push(c),
+      }
+    }
+    value
+  } else if let Some(rest) = raw.strip_prefix('\'') {
+    rest.split_once('\'').map_or(rest, |(value, _)| value).to_string()
+  } else {
+    // An unquoted value ends at ` #`.
+    let end = raw.find(" #").unwrap_or(raw.len());
+    raw[..end].trim_end().to_string()
+  };
+  Some((key, value))
+}
+
+/// Every assignment as `(key, value, line)`; a later duplicate wins, as with
+/// most dotenv loaders.
+fn parse(content: &str) -> Vec<(String, String, usize)> {
+  let mut entries: Vec<(String, String, usize)> = Vec::new();
+  for (index, line) in content.lines().enumerate() {
+    let Some((key, value)) = parse_line(line) else {
+      continue;
+    };
+    entries.retain(|(existing, _, _)| existing != key);
+    entries.push((key.to_string(), value, index + 1));
+  }
+  entries.sort_by_key(|(_, _, line)| *line);
+  entries
+}
+
+/// `value` written so `parse_line` reads it back unchanged.
+fn format_value(value: &str) -> String {
+  let plain = value
+    .chars()
+    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/' | ':' | '@' | '+' | ','));
+  if plain {
+    return value.to_string();
+  }
+  if !value.contains(['\'', '\n', '\r']) {
+    return format!("'{value}'");
+  }
+  let mut quoted = String::from("\"");
+  for c in value.chars() {
+    match c {
+      '"' => quoted.push_str("\\\""),
+      '\\' => quoted.push_str("\\\\"),
+      '\n' => quoted.push_str("\\n"),
+      '\r' => quoted.push_str("\\r"),
+      _ => quoted.push(c),
+    }
+  }
+  quoted.push('"');
+  quoted
+}
+
+/// `content` with `key` set to `value` (or removed when `None`). The first
+/// assignment is rewritten in place, keeping `export`; duplicates are dropped.
+fn set_in(content: &str, key: &str, value: Option<&str>) -> String {
+  let mut lines: Vec<String> = Vec::new();
+  let mut written = false;
+  for line in content.lines() {
+    if parse_line(line).is_some_and(|(existing, _)| existing == key) {
+      if let (Some(value), false) = (value, written) {
+        let export = if line.trim_start().starts_with("export ") {
+          "export "
+        } else {
+          ""
+        };
+        lines.push(format!("{export}{key}={}", format_value(value)));
+        written = true;
+      }
+      continue;
+    }
+    lines.push(line.to_string());
+  }
+  if let (Some(value), false) = (value, written) {
+    lines.push(format!("{key}={}", format_value(value)));
+  }
+  let mut updated = lines.join("\n");
+  if !updated.is_empty() {
+    updated.push('\n');
+  }
+  updated
+}
+
+fn env_file(project_dir: &Path, name: &str) -> Result<PathBuf, OpenWorkError> {
+  if !is_env_file_name(name) {
+    return Err(OpenWorkError::invalid_argument(format!(
+      "{name} is not a .env file name"
+    )));
+  }
+  Ok(project_dir.join(name))
+}
+
+fn read_file(path: &Path) -> Result<String, OpenWorkError> {
+  fs::read_to_string(path).map_err(|e| {
+    let code = if e.kind() == std::io::ErrorKind::NotFound {
+      ErrorCode::NotFound
+    } else {
+      ErrorCode::Io
+    };
+    OpenWorkError::new(code, format!("Failed to read {}: {e}", path.display()))
+  })
+}
+
+/// Replaces `path` through a temp file beside it, keeping its permissions.
+pub fn write_atomically(path: &Path, content: &str) -> Result<(), String> {
+  let name = path
+    .file_name()
+    .map(|name| name.to_string_lossy().to_string())
+    .unwrap_or_default();
+  let tmp = path.with_file_name(format!("{name}.openwork-tmp"));
+  fs::write(&tmp, content).map_err(|e| format!("Failed to write {}: {e}", tmp.display()))?;
+  if let Ok(metadata) = fs::metadata(path) {
+    let _ = fs::set_permissions(&tmp, metadata.permissions());
+  }
+  fs::rename(&tmp, path).map_err(|e| {
+    let _ = fs::remove_file(&tmp);
+    format!("Failed to write {}: {e}", path.display())
+  })
+}
+
+fn injected_keys(settings: &DotenvSettings, project_dir: &str, name: &str) -> Vec<String> {
+  settings
+    .projects
+    .get(project_dir)
+    .and_then(|files| files.get(name))
+    .cloned()
+    .unwrap_or_default()
+}
+
+fn content(project_dir: &str, name: &str, text: &str, reveal: bool) -> DotenvContent {
+  let injected = injected_keys(&active(), project_dir, name);
+  let entries = parse(text)
+    .into_iter()
+    .map(|(key, value, line)| DotenvEntry {
+      injected: injected.contains(&key),
+      masked: !reveal && !value.is_empty(),
+      value: if reveal || value.is_empty() {
+        value
+      } else {
+        MASK.to_string()
+      },
+      key,
+      line,
+    })
+    .collect();
+  DotenvContent {
+    name: name.to_string(),
+    entries,
+  }
+}
+
+/// Adds the selected keys of the project's `.env` files to an engine command.
+/// Call after `env_policy::apply`, and before the app's own variables so
+/// those can't be overridden.
+pub fn inject(command: &mut Command, project_dir: &str) {
+  let Some(files) = active().projects.get(project_dir).cloned() else {
+    return;
+  };
+  for (name, keys) in files {
+    let Ok(text) = fs::read_to_string(Path::new(project_dir).join(&name)) else {
+      tracing::warn!(file = %name, "selected .env file is missing");
+      continue;
+    };
+    for (key, value, _) in parse(&text).into_iter().filter(|(key, _, _)| keys.contains(key)) {
+      redact::register_secret(&value);
+      command.env(key, value);
+    }
+    tracing::info!(file = %name, keys = keys.len(), "injected .env variables");
+  }
+}
+
+#[tauri::command]
+pub fn dotenv_files_list(project_dir: String) -> Result<Vec<DotenvFile>, OpenWorkError> {
+  let dir = paths::allowed_dir(&project_dir, "projectDir")?;
+  let key = dir.to_string_lossy().to_string();
+  let settings = active();
+  let entries = fs::read_dir(&dir).map_err(|e| format!("Failed to list {}: {e}", dir.display()))?;
+  let mut files: Vec<DotenvFile> = entries
+    .filter_map(Result::ok)
+    .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_file()))
+    .filter_map(|entry| {
+      let name = entry.file_name().to_str()?.to_string();
+      if !is_env_file_name(&name) {
+        return None;
+      }
+      let text = fs::read_to_string(entry.path()).unwrap_or_default();
+      Some(DotenvFile {
+        path: entry.path().to_string_lossy().to_string(),
+        keys: parse(&text).len(),
+        injected: injected_keys(&settings, &key, &name),
+        name,
+      })
+    })
+    .collect();
+  files.sort_by(|a, b| a.name.cmp(&b.name));
+  Ok(files)
+}
+
+/// The assignments in a `.env` file; values are masked unless `reveal`.
+#[tauri::command]
+pub fn dotenv_read(
+  project_dir: String,
+  name: String,
+  reveal: Option<bool>,
+) -> Result<DotenvContent, OpenWorkError> {
+  let dir = paths::allowed_dir(&project_dir, "projectDir")?;
+  let text = read_file(&env_file(&dir, &name)?)?;
+  Ok(content(
+    &dir.to_string_lossy(),
+    &name,
+    &text,
+    reveal.unwrap_or(false),
+  ))
+}
+
+/// Sets `key` in a `.env` file, creating the file if needed; `value: None`
+/// removes the key.
+#[tauri::command]
+pub fn dotenv_set(
+  project_dir: String,
+  name: String,
+  key: String,
+  value: Option<String>,
+) -> Result<DotenvContent, OpenWorkError> {
+  let dir = paths::allowed_dir(&project_dir, "projectDir")?;
+  let path = env_file(&dir, &name)?;
+  let key = key.trim();
+  if !is_valid_key(key) {
+    return Err(OpenWorkError::invalid_argument(format!(
+      "Invalid variable name: {key}"
+    )));
+  }
+  if value.as_deref().is_some_and(|value| value.contains('\0')) {
+    return Err(OpenWorkError::invalid_argument("value must not contain NUL"));
+  }
+  let current = match read_file(&path) {
+    Ok(text) => text,
+    Err(e) if e.code == ErrorCode::NotFound && value.is_some() => String::new(),
+    Err(e) => return Err(e),
+  };
+  let updated = set_in(&current, key, value.as_deref());
+  write_atomically(&path, &updated)?;
+  tracing::info!(file = %name, key, removed = value.is_none(), "updated .env file");
+  Ok(content(&dir.to_string_lossy(), &name, &updated, false))
+}
+
+/// Chooses which keys of a `.env` file the engine gets; takes effect on the
+/// next engine start. An empty list stops injecting the file.
+#[tauri::command]
+pub fn dotenv_inject_set(
+  app: AppHandle,
+  project_dir: String,
+  name: String,
+  keys: Vec<String>,
+) -> Result<Vec<String>, OpenWorkError> {
+  let dir = paths::allowed_dir(&project_dir, "projectDir")?;
+  env_file(&dir, &name)?;
+  let mut keys: Vec<String> = keys.iter().map(|key| key.trim().to_string()).collect();
+  if let Some(key) = keys.iter().find(|key| !is_valid_key(key)) {
+    return Err(OpenWorkError::invalid_argument(format!(
+      "Invalid variable name: {key}"
+    )));
+  }
+  keys.sort();
+  keys.dedup();
+
+  let mut settings = active();
+  let project = dir.to_string_lossy().to_string();
+  let files = settings.projects.entry(project.clone()).or_default();
+  if keys.is_empty() {
+    files.remove(&name);
+  } else {
+    files.insert(name, keys.clone());
+  }
+  if files.is_empty() {
+    settings.projects.remove(&project);
+  }
+  write_state(&app, DOTENV_FILE, &settings)?;
+  *ACTIVE.write().expect("dotenv settings lock poisoned") = Some(settings);
+  Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_assignments() {
+    let text = "# comment\nexport API_KEY=abc123 # trailing\nURL=\"http://x?a=1#frag\"\nMULTI=\"a\\nb\"\nRAW='$HOME \\n'\nnot a line\nAPI_KEY=override\n";
+    let entries = parse(text);
+    let values: Vec<(&str, &str)> = entries.iter().map(|(k, v, _)| (k.as_str(), v.as_str())).collect();
+    assert_eq!(
+      values,
+      [
+        ("URL", "http://x?a=1#frag"),
+        ("MULTI", "a\nb"),
+        ("RAW", "$HOME \\n"),
+        ("API_KEY", "override")
+      ]
+    );
+    assert_eq!(entries[3].2, 7);
+  }
+
+  #[test]
+  fn edits_in_place() {
+    let text = "# db\nexport DB_URL=old\nOTHER=1\nDB_URL=dup\n";
+    assert_eq!(
+      set_in(text, "DB_URL", Some("postgres://u:p w@h/db")),
+      "# db\nexport DB_URL='postgres://u:p w@h/db'\nOTHER=1\n"
+    );
+    assert_eq!(set_in(text, "DB_URL", None), "# db\nOTHER=1\n");
+    assert_eq!(set_in("", "NEW", Some("it's\nhere")), "NEW=\"it's\\nhere\"\n");
+    for value in ["plain", "with space", "it's \"quoted\"\n", "back\\slash'"] {
+      let line = set_in("", "K", Some(value));
+      assert_eq!(
+        parse_line(line.trim_end()).map(|(_, v)| v).as_deref(),
+        Some(value)
+      );
+    }
+  }
+
+  #[test]
+  fn accepts_only_env_file_names() {
+    assert!(is_env_file_name(".env"));
+    assert!(is_env_file_name(".env.local"));
+    assert!(is_env_file_name(".env.production.local"));
+    assert!(!is_env_file_name(".env."));
+    assert!(!is_env_file_name(".env/../x"));
+    assert!(!is_env_file_name("env"));
+  }
+}
@@ -0,0 +1,325 @@
+//! HTTP downloads: proxy-aware, resumable and verified.
+//!
+//! Files are fetched into `<dest>.partial`. When a connection drops, the next
+//! attempt asks only for the missing bytes with a `Range` request, and starts
+//! over when the server ignores it. The finished file is checked against the
+//! expected SHA-256, when one is given, before it is renamed into place.
+//!
+//! A `cancel` token stops a download between chunks and between attempts;
+//! the partial file is removed.
+//!
+//! A proxy configured in settings applies to every download (including app
+//! updates); without one, the usual `HTTPS_PROXY` / `NO_PROXY` variables apply.
+
+use std::{
+  fs::{self, File, OpenOptions},
+  io::{Read, Write},
+  path::{Path, PathBuf},
+  sync::RwLock,
+  thread,
+  time::{Duration, Instant},
+};
+
+use reqwest::{blocking::Client, header, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::AppHandle;
+
+use crate::{
+  cancel::CancelToken,
+  error::OpenWorkError,
+  store::{read_state, write_state},
+};
+
+pub const PROXY_FILE: &str = "proxy.json";
+pub const DOWNLOAD_PROGRESS_EVENT: &str = "download://progress";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+/// Per attempt; a stalled transfer is retried from where it stopped.
+const ATTEMPT_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+const MAX_ATTEMPTS: u32 = 4;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProxySettings {
+  /// `http://` or `https://` proxy for all downloads; `None` uses the
+  /// environment.
+  pub url: Option<String>,
+}
+
+// Downloads start from helpers without access to Tauri state, like the
+// allowlist, so the proxy lives in a process-wide slot loaded at startup.
+static ACTIVE: RwLock<Option<ProxySettings>> = RwLock::new(None);
+
+/// The configured proxy, if any.
+pub fn proxy_url() -> Option<Url> {
+  let settings = ACTIVE.read().expect("proxy lock poisoned").clone()?;
+  settings.url.and_then(|url| Url::parse(&url).ok())
+}
+
+fn validate_proxy(settings: ProxySettings) -> Result<ProxySettings, OpenWorkError> {
+  let url = settings.url.map(|url| url.trim().to_string()).filter(|url| !url.is_empty());
+  if let Some(url) = &url {
+    let parsed =
+      Url::parse(url).map_err(|e| OpenWorkError::invalid_argument(format!("Invalid proxy URL: {e}")))?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+      return Err(OpenWorkError::invalid_argument("proxy must be an http:// or https:// URL"));
+    }
+  }
+  Ok(ProxySettings { url })
+}
+
+pub fn client() -> Result<Client, String> {
+  let mut builder = Client::builder()
+    .connect_timeout(CONNECT_TIMEOUT)
+    .timeout(ATTEMPT_TIMEOUT);
+  if let Some(proxy) = proxy_url() {
+    let proxy = reqwest::Proxy::all(proxy).map_err(|e| format!("Invalid proxy: {e}"))?;
+    builder = builder.proxy(proxy);
+  }
+  builder.build().map_err(|e| format!("Failed to create HTTP client: {e}"))
+}
+
+pub struct Download<'a> {
+  pub url: &'a str,
+  pub dest: &'a Path,
+  /// Expected SHA-256 as hex.
+  pub sha256: Option<&'a str>,
+  pub max_bytes: Option<u64>,
+  pub cancel: Option<&'a CancelToken>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadProgress {
+  pub url: String,
+  pub downloaded: u64,
+  pub total: Option<u64>,
+  pub attempt: u32,
+}
+
+enum Failure {
+  /// Network trouble; the next attempt resumes.
+  Retry(String),
+  /// The server said no or the file is unacceptable.
+  Fatal(String),
+  Cancelled,
+}
+
+fn partial_path(dest: &Path) -> PathBuf {
+  let mut name = dest.as_os_str().to_os_string();
+  name.push(".partial");
+  PathBuf::from(name)
+}
+
+fn fetch_into(
+  client: &Client,
+  download: &Download,
+  partial: &Path,
+  progress: &mut DownloadProgress,
+  on_progress: &mut impl FnMut(&DownloadProgress),
+) -> Result<(), Failure> {
+  let url = download.url;
+  let offset = fs::metadata(partial).map(|m| m.len()).unwrap_or(0);
+  let mut request = client.get(url);
+  if offset > 0 {
+    request = request.header(header::RANGE, format!("bytes={offset}-"));
+  }
+  let mut response = request
+    .send()
+    .map_err(|e| Failure::Retry(format!("Failed to download {url}: {e}")))?;
+
+  let status = response.status();
+  if status == StatusCode::RANGE_NOT_SATISFIABLE {
+    let _ = fs::remove_file(partial);
+    return Err(Failure::Retry(format!("{url} changed while downloading")));
+  }
+  if status.is_server_error() {
+    return Err(Failure::Retry(format!("Failed to download {url}: {status}")));
+  }
+  if !status.is_success() {
+    return Err(Failure::Fatal(format!("Failed to download {url}: {status}")));
+  }
+
+  let resumed = offset > 0 && status == StatusCode::PARTIAL_CONTENT;
+  let open = if resumed {
+    OpenOptions::new().append(true).open(partial)
+  } else {
+    File::create(partial)
+  };
+  let mut file = open.map_err(|e| Failure::Fatal(format!("Failed to write {}: {e}", partial.display())))?;
+  progress.downloaded = if resumed { offset } else { 0 };
+  progress.total = response.content_length().map(|len| len + progress.downloaded);
+  if let (Some(total), Some(max)) = (progress.total, download.max_bytes) {
+    if total > max {
+      return Err(Failure::Fatal(format!("{url} is larger than expected ({total} bytes)")));
+    }
+  }
+
+  let mut buffer = vec![0u8; 64 * 1024];
+  let mut last_report = Instant::now();
+  loop {
+    if download.cancel.is_some_and(CancelToken::is_cancelled) {
+      return Err(Failure::Cancelled);
+    }
+    let read = response
+      .read(&mut buffer)
+      .map_err(|e| Failure::Retry(format!("Download of {url} was interrupted: {e}")))?;
+    if read == 0 {
+      break;
+    }
+    file
+      .write_all(&buffer[..read])
+      .map_err(|e| Failure::Fatal(format!("Failed to write {}: {e}", partial.display())))?;
+    progress.downloaded += read as u64;
+    if download.max_bytes.is_some_and(|max| progress.downloaded > max) {
+      return Err(Failure::Fatal(format!("{url} is larger than expected")));
+    }
+    if last_report.elapsed() >= PROGRESS_INTERVAL {
+      last_report = Instant::now();
+      on_progress(progress);
+    }
+  }
+
+  if progress.total.is_some_and(|total| progress.downloaded < total) {
+    return Err(Failure::Retry(format!("Download of {url} ended early")));
+  }
+  Ok(())
+}
+
+fn sha256_file(path: &Path) -> Result<String, String> {
+  let mut file = File::open(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+  let mut hasher = Sha256::new();
+  std::io::copy(&mut file, &mut hasher).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+  Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Waits before retrying; `false` when cancelled meanwhile.
+fn backoff(attempt: u32, cancel: Option<&CancelToken>) -> bool {
+  let delay = Duration::from_secs(1 << attempt);
+  match cancel {
+    Some(cancel) => cancel.sleep(delay),
+    None => {
+      thread::sleep(delay);
+      true
+    }
+  }
+}
+
+/// Downloads `download.url` to `download.dest` over HTTPS, retrying dropped
+/// connections. Returns the size of the file.
+#[tracing::instrument(level = "info", skip_all, fields(url = %download.url))]
+pub fn download_to_file(
+  download: &Download,
+  mut on_progress: impl FnMut(&DownloadProgress),
+) -> Result<u64, String> {
+  if !download.url.starts_with("https://") {
+    return Err(format!("Refusing to download over an insecure URL: {}", download.url));
+  }
+  if let Some(parent) = download.dest.parent() {
+    fs::create_dir_all(parent).map_err(|e| format!("Failed to create dir {}: {e}", parent.display()))?;
+  }
+
+  let client = client()?;
+  let partial = partial_path(download.dest);
+  let _ = fs::remove_file(&partial);
+  let mut progress = DownloadProgress {
+    url: download.url.to_string(),
+    downloaded: 0,
+    total: None,
+    attempt: 0,
+  };
+
+  let mut result = Err(String::new());
+  for attempt in 1..=MAX_ATTEMPTS {
+    progress.attempt = attempt;
+    match fetch_into(&client, download, &partial, &mut progress, &mut on_progress) {
+      Ok(()) => {
+        result = Ok(());
+        break;
+      }
+      Err(Failure::Fatal(e)) => {
+        result = Err(e);
+        break;
+      }
+      Err(Failure::Cancelled) => {
+        result = Err(format!("Download of {} was cancelled", download.url));
+        break;
+      }
+      Err(Failure::Retry(e)) => {
+        tracing::warn!(attempt, error = %e, "download attempt failed");
+        result = Err(e);
+        if attempt < MAX_ATTEMPTS && !backoff(attempt, download.cancel) {
+          result = Err(format!("Download of {} was cancelled", download.url));
+          break;
+        }
+      }
+    }
+  }
+  if let Err(e) = result {
+    let _ = fs::remove_file(&partial);
+    return Err(e);
+  }
+  on_progress(&progress);
+
+  if let Some(expected) = download.sha256 {
+    let actual = sha256_file(&partial)?;
+    if !actual.eq_ignore_ascii_case(expected.trim()) {
+      let _ = fs::remove_file(&partial);
+      return Err(format!(
+        "Checksum mismatch for {}: expected {expected}, got {actual}",
+        download.url
+      ));
+    }
+  }
+
+  fs::rename(&partial, download.dest)
+    .map_err(|e| format!("Failed to write {}: {e}", download.dest.display()))?;
+  tracing::info!(bytes = progress.downloaded, "download finished");
+  Ok(progress.downloaded)
+}
+
+pub fn init(app: &AppHandle) {
+  let settings = read_state::<ProxySettings>(app, PROXY_FILE).unwrap_or_else(|e| {
+    tracing::warn!(error = %e, "failed to load proxy settings");
+    ProxySettings::default()
+  });
+  *ACTIVE.write().expect("proxy lock poisoned") = Some(settings);
+}
+
+#[tauri::command]
+pub fn download_proxy_get() -> ProxySettings {
+  ACTIVE.read().expect("proxy lock poisoned").clone().unwrap_or_default()
+}
+
+#[tauri::command]
+pub fn download_proxy_set(app: AppHandle, settings: ProxySettings) -> Result<ProxySettings, OpenWorkError> {
+  let settings = validate_proxy(settings)?;
+  write_state(&app, PROXY_FILE, &settings)?;
+  *ACTIVE.write().expect("proxy lock poisoned") = Some(settings.clone());
+  Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn validates_proxy_urls() {
+    let valid = validate_proxy(ProxySettings {
+      url: Some(" http://proxy.local:3128 ".to_string()),
+    })
+    .unwrap();
+    assert_eq!(valid.url.as_deref(), Some("http://proxy.local:3128"));
+    assert!(validate_proxy(ProxySettings { url: Some(String::new()) }).unwrap().url.is_none());
+    assert!(validate_proxy(ProxySettings {
+      url: Some("socks5://proxy.local".to_string())
+    })
+    .is_err());
+    assert!(validate_proxy(ProxySettings {
+      url: Some("not a url".to_string())
+    })
+    .is_err());
+  }
+}
@@ -0,0 +1,224 @@
+//! Classifies a file or folder dropped onto the window so the UI can route it
+//! to the open-project or import-skill flow without guessing from the name.
+
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use crate::{archive, error::OpenWorkError, paths};
+
+/// Files and folders that mark the root of a project.
+const PROJECT_MARKERS: &[&str] = &[
+  ".git",
+  ".opencode",
+  "opencode.json",
+  "opencode.jsonc",
+  "package.json",
+  "Cargo.toml",
+  "pyproject.toml",
+  "requirements.txt",
+  "go.mod",
+  "pom.xml",
+  "build.gradle",
+  "build.gradle.kts",
+  "Gemfile",
+  "composer.json",
+  "deno.json",
+  "Makefile",
+];
+
+const ARCHIVE_SUFFIXES: &[&str] = &[".zip", ".tar", ".tar.gz", ".tgz", ".tar.bz2", ".tar.xz"];
+
+const SKILL_FILE: &str = "SKILL.md";
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DroppedKind {
+  Project,
+  /// A single skill folder (has a `SKILL.md`).
+  Skill,
+  /// A folder whose subfolders are skills.
+  SkillCollection,
+  Archive,
+  Folder,
+  File,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DropAction {
+  OpenProject,
+  OpenInNewWindow,
+  ImportSkill,
+  AttachFile,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DroppedPath {
+  pub path: String,
+  pub name: String,
+  pub kind: DroppedKind,
+  /// Project markers found at the top level, e.g. `.git` or `package.json`.
+  pub markers: Vec<String>,
+  /// Skill folders that `import_skill` can take, as absolute paths. Archives
+  /// holding a skill list none; they go to `import_skill_archive` as a whole.
+  pub skills: Vec<String>,
+  /// Suggested actions, most fitting first.
+  pub actions: Vec<DropAction>,
+}
+
+fn is_skill_dir(dir: &Path) -> bool {
+  dir.join(SKILL_FILE).is_file()
+}
+
+/// Skill folders directly under `dir`, or under a project's `.opencode/skill`.
+fn skill_dirs(dir: &Path) -> Vec<PathBuf> {
+  let mut skills = Vec::new();
+  for parent in [dir.to_path_buf(), dir.join(".opencode").join("skill")] {
+    let Ok(entries) = fs::read_dir(&parent) else {
+      continue;
+    };
+    skills.extend(
+      entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir() && is_skill_dir(path)),
+    );
+  }
+  skills.sort();
+  skills
+}
+
+fn is_archive(path: &Path) -> bool {
+  let name = path
+    .file_name()
+    .map(|name| name.to_string_lossy().to_lowercase())
+    .unwrap_or_default();
+  ARCHIVE_SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+}
+
+/// Entries looked at before giving up on finding a `SKILL.md`.
+const MAX_ARCHIVE_ENTRIES: usize = 2_000;
+
+/// Whether an archive holds a `SKILL.md`; unreadable formats count as no.
+fn archive_has_skill(path: &Path) -> bool {
+  archive::entry_names(path, MAX_ARCHIVE_ENTRIES)
+    .unwrap_or_default()
+    .iter()
+    .any(|name| name == SKILL_FILE || name.ends_with(&format!("/{SKILL_FILE}")))
+}
+
+pub fn classify(path: &Path) -> DroppedPath {
+  let name = path
+    .file_name()
+    .map(|name| name.to_string_lossy().to_string())
+    .unwrap_or_else(|| path.to_string_lossy().to_string());
+
+  let mut markers = Vec::new();
+  let mut skills = Vec::new();
+  let (kind, actions) = if path.is_dir() {
+    markers = PROJECT_MARKERS
+      .iter()
+      .filter(|marker| path.join(marker).exists())
+      .map(|marker| marker.to_string())
+      .collect();
+
+    if is_skill_dir(path) {
+      skills.push(path.to_path_buf());
+      (DroppedKind::Skill, vec![DropAction::ImportSkill, DropAction::OpenProject])
+    } else {
+      skills = skill_dirs(path);
+      if !markers.is_empty() {
+        let mut actions = vec![DropAction::OpenProject, DropAction::OpenInNewWindow];
+        if !skills.is_empty() {
+          actions.push(DropAction::ImportSkill);
+        }
+        (DroppedKind::Project, actions)
+      } else if !skills.is_empty() {
+        (DroppedKind::SkillCollection, vec![DropAction::ImportSkill, DropAction::OpenProject])
+      } else {
+        (DroppedKind::Folder, vec![DropAction::OpenProject, DropAction::OpenInNewWindow])
+      }
+    }
+  } else if is_archive(path) {
+    if archive_has_skill(path) {
+      markers.push(SKILL_FILE.to_string());
+      (DroppedKind::Archive, vec![DropAction::ImportSkill, DropAction::AttachFile])
+    } else {
+      (DroppedKind::Archive, vec![DropAction::AttachFile])
+    }
+  } else {
+    (DroppedKind::File, vec![DropAction::AttachFile])
+  };
+
+  DroppedPath {
+    path: path.to_string_lossy().to_string(),
+    name,
+    kind,
+    markers,
+    skills: skills.iter().map(|dir| dir.to_string_lossy().to_string()).collect(),
+    actions,
+  }
+}
+
+#[tauri::command]
+pub fn classify_dropped_path(path: String) -> Result<DroppedPath, OpenWorkError> {
+  let raw = paths::validate_input(&path, "path")?;
+  if !raw.is_absolute() {
+    return Err(OpenWorkError::invalid_argument("path must be an absolute path"));
+  }
+  let path = paths::canonicalize(&raw)?;
+  Ok(classify(&path))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("openwork-dropped-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  #[test]
+  fn recognizes_projects_and_skills() {
+    let project = temp_dir("project");
+    fs::write(project.join("package.json"), "{}").unwrap();
+    fs::create_dir_all(project.join(".opencode/skill/review")).unwrap();
+    fs::write(project.join(".opencode/skill/review/SKILL.md"), "# Review").unwrap();
+    let dropped = classify(&project);
+    assert_eq!(dropped.kind, DroppedKind::Project);
+    assert_eq!(dropped.actions[0], DropAction::OpenProject);
+    assert_eq!(dropped.skills.len(), 1);
+
+    let skill = project.join(".opencode/skill/review");
+    let dropped = classify(&skill);
+    assert_eq!(dropped.kind, DroppedKind::Skill);
+    assert_eq!(dropped.actions[0], DropAction::ImportSkill);
+  }
+
+  #[test]
+  fn recognizes_collections_and_files() {
+    let collection = temp_dir("collection");
+    for skill in ["a", "b"] {
+      fs::create_dir_all(collection.join(skill)).unwrap();
+      fs::write(collection.join(skill).join("SKILL.md"), "# Skill").unwrap();
+    }
+    let dropped = classify(&collection);
+    assert_eq!(dropped.kind, DroppedKind::SkillCollection);
+    assert_eq!(dropped.skills.len(), 2);
+
+    fs::write(collection.join("skills.tar.gz"), "").unwrap();
+    assert_eq!(classify(&collection.join("skills.tar.gz")).kind, DroppedKind::Archive);
+    fs::write(collection.join("notes.txt"), "").unwrap();
+    assert_eq!(classify(&collection.join("notes.txt")).kind, DroppedKind::File);
+
+    assert_eq!(classify(&temp_dir("empty")).kind, DroppedKind::Folder);
+  }
+}
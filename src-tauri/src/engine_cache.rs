@@ -0,0 +1,121 @@
+//! Remembers where opencode was found and what it reported, so starting an
+//! engine or opening onboarding doesn't probe PATH and spawn `--version` every
+//! time.
+//!
+//! The entry is dropped when the binary's size or mtime changes, after an
+//! install, or when a caller asks for a forced refresh. Misses aren't cached:
+//! the user may install opencode outside the app at any moment.
+
+use std::{
+  fs,
+  path::{Path, PathBuf},
+  sync::Mutex,
+  thread,
+  time::{Instant, SystemTime},
+};
+
+use crate::{
+  engine_log, opencode_serve_help, opencode_version, resolve_opencode_executable_before, RESOLVE_TIMEOUT,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Fingerprint {
+  len: u64,
+  modified: Option<SystemTime>,
+}
+
+fn fingerprint(path: &Path) -> Option<Fingerprint> {
+  let metadata = fs::metadata(path).ok().filter(|metadata| metadata.is_file())?;
+  Some(Fingerprint {
+    len: metadata.len(),
+    modified: metadata.modified().ok(),
+  })
+}
+
+#[derive(Debug, Clone)]
+pub struct Probe {
+  pub version: Option<String>,
+  pub supports_serve: bool,
+  /// What makes `serve` write JSON logs, when it can.
+  pub json_log_args: Option<Vec<String>>,
+}
+
+struct Entry {
+  path: PathBuf,
+  in_path: bool,
+  notes: Vec<String>,
+  fingerprint: Fingerprint,
+  /// Filled in by the first doctor run against this binary.
+  probe: Option<Probe>,
+}
+
+#[derive(Default)]
+pub struct EngineCache {
+  entry: Mutex<Option<Entry>>,
+}
+
+impl EngineCache {
+  pub fn invalidate(&self) {
+    *self.entry.lock().expect("engine cache mutex poisoned") = None;
+    tracing::debug!("opencode path cache cleared");
+  }
+
+  pub fn resolve(&self, force: bool) -> (Option<PathBuf>, bool, Vec<String>) {
+    self.resolve_before(force, Instant::now() + RESOLVE_TIMEOUT)
+  }
+
+  /// Same result as `resolve_opencode_executable`, from cache while the binary
+  /// found last time is unchanged.
+  pub fn resolve_before(&self, force: bool, deadline: Instant) -> (Option<PathBuf>, bool, Vec<String>) {
+    // Held across the lookup so concurrent callers share one probe.
+    let mut entry = self.entry.lock().expect("engine cache mutex poisoned");
+    if let Some(cached) = entry.as_ref().filter(|_| !force) {
+      if fingerprint(&cached.path).as_ref() == Some(&cached.fingerprint) {
+        return (Some(cached.path.clone()), cached.in_path, cached.notes.clone());
+      }
+      tracing::info!(path = %cached.path.display(), "opencode changed on disk, resolving again");
+    }
+
+    let (resolved, in_path, notes) = resolve_opencode_executable_before(deadline);
+    *entry = resolved.as_ref().and_then(|path| {
+      Some(Entry {
+        path: path.clone(),
+        in_path,
+        notes: notes.clone(),
+        fingerprint: fingerprint(path)?,
+        probe: None,
+      })
+    });
+    (resolved, in_path, notes)
+  }
+
+  /// Version, `serve` support and JSON log options of `path`, running the
+  /// checks in parallel unless this binary was already probed. Probes that
+  /// hit the deadline aren't remembered.
+  pub fn probe(&self, path: &Path, deadline: Instant) -> Probe {
+    {
+      let entry = self.entry.lock().expect("engine cache mutex poisoned");
+      if let Some(probe) = entry.as_ref().filter(|e| e.path == path).and_then(|e| e.probe.clone()) {
+        return probe;
+      }
+    }
+
+    let probe = thread::scope(|scope| {
+      let version = scope.spawn(|| opencode_version(path.as_os_str(), deadline));
+      let serve_help = opencode_serve_help(path.as_os_str(), deadline);
+      Probe {
+        version: version.join().unwrap_or(None),
+        supports_serve: serve_help.is_some(),
+        json_log_args: serve_help.as_deref().and_then(engine_log::json_log_args),
+      }
+    });
+
+    if Instant::now() < deadline {
+      let mut entry = self.entry.lock().expect("engine cache mutex poisoned");
+      if let Some(entry) = entry.as_mut().filter(|e| e.path == path) {
+        entry.probe = Some(probe.clone());
+      }
+    }
+    probe
+  }
+}
@@ -0,0 +1,237 @@
+use std::{thread, time::Duration};
+
+use reqwest::{blocking::Client, Method, StatusCode};
+use serde_json::Value;
+use tauri::{State, WebviewWindow};
+
+use crate::{
+  error::{ErrorCode, OpenWorkError},
+  instance::MAIN_WINDOW,
+  EngineManager,
+};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+const MAX_ATTEMPTS: u32 = 3;
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(200);
+
+/// Minimal HTTP client for the running engine. All backend calls to the
+/// opencode server go through here so base_url resolution, retries and auth
+/// live in one place.
+#[derive(Clone)]
+pub struct EngineClient {
+  base_url: String,
+  auth_token: Option<String>,
+  client: Client,
+}
+
+impl EngineClient {
+  pub fn new(base_url: String, auth_token: Option<String>) -> Result<Self, OpenWorkError> {
+    Self::with_timeout(base_url, auth_token, Some(REQUEST_TIMEOUT))
+  }
+
+  /// `timeout: None` is for long-lived streams such as the event feed.
+  pub fn with_timeout(
+    base_url: String,
+    auth_token: Option<String>,
+    timeout: Option<Duration>,
+  ) -> Result<Self, OpenWorkError> {
+    let client = Client::builder()
+      .timeout(timeout)
+      .build()
+      .map_err(|e| format!("Failed to create HTTP client: {e}"))?;
+
+    Ok(Self {
+      base_url: base_url.trim_end_matches('/').to_string(),
+      auth_token,
+      client,
+    })
+  }
+
+  /// Builds a client for the main window's engine.
+  pub fn from_manager(manager: &EngineManager) -> Result<Self, OpenWorkError> {
+    Self::for_window(manager, MAIN_WINDOW)
+  }
+
+  /// A client without a request timeout, for event streams and prompts,
+  /// for the engine of `window`.
+  pub fn streaming_for_window(manager: &EngineManager, window: &str) -> Result<Self, OpenWorkError> {
+    let (base_url, auth_token) = running_engine(manager, window)?;
+    Self::with_timeout(base_url, auth_token, None)
+  }
+
+  /// Builds a client for the engine owned by the window labelled `window`.
+  pub fn for_window(manager: &EngineManager, window: &str) -> Result<Self, OpenWorkError> {
+    let (base_url, auth_token) = running_engine(manager, window)?;
+    Self::new(base_url, auth_token)
+  }
+
+  pub fn base_url(&self) -> &str {
+    &self.base_url
+  }
+
+  pub fn request(&self, method: Method, path: &str) -> reqwest::blocking::RequestBuilder {
+    let url = format!("{}/{}", self.base_url, path.trim_start_matches('/'));
+    let builder = self.client.request(method, url);
+    match &self.auth_token {
+      Some(token) => builder.basic_auth(crate::ENGINE_AUTH_USERNAME, Some(token)),
+      None => builder,
+    }
+  }
+
+  fn send_with_retry(
+    &self,
+    method: Method,
+    path: &str,
+    body: Option<&Value>,
+  ) -> Result<reqwest::blocking::Response, OpenWorkError> {
+    let mut last_error = OpenWorkError::new(ErrorCode::EngineUnreachable, "Engine did not respond");
+
+    for attempt in 0..MAX_ATTEMPTS {
+      if attempt > 0 {
+        thread::sleep(RETRY_BASE_DELAY * 2u32.pow(attempt - 1));
+      }
+
+      let mut request = self.request(method.clone(), path);
+      if let Some(body) = body {
+        request = request.json(body);
+      }
+
+      match request.send() {
+        // Server errors are usually transient while the engine is still booting.
+        Ok(response) if response.status().is_server_error() => {
+          let message = format!("Engine returned {} for {path}", response.status());
+          last_error = OpenWorkError::new(ErrorCode::EngineRequestFailed, message).retryable();
+        }
+        Ok(response) => return Ok(response),
+        Err(e) if e.is_connect() || e.is_timeout() => {
+          let message = format!("Failed to reach engine at {}: {e}", self.base_url);
+          last_error = OpenWorkError::new(ErrorCode::EngineUnreachable, message).retryable();
+        }
+        Err(e) => {
+          return Err(OpenWorkError::new(
+            ErrorCode::EngineRequestFailed,
+            format!("Request to engine failed: {e}"),
+          ))
+        }
+      }
+    }
+
+    Err(last_error)
+  }
+
+  pub fn send_json(&self, method: Method, path: &str, body: Option<&Value>) -> Result<Value, OpenWorkError> {
+    let response = self.send_with_retry(method, path, body)?;
+    let status = response.status();
+
+    if status == StatusCode::NOT_FOUND {
+      return Err(OpenWorkError::new(ErrorCode::NotFound, format!("Not found: {path}")));
+    }
+    if !status.is_success() {
+      let text = response.text().unwrap_or_default();
+      return Err(
+        OpenWorkError::new(
+          ErrorCode::EngineRequestFailed,
+          format!("Engine returned {status} for {path}: {}", text.trim()),
+        )
+        .with_details(serde_json::json!({ "status": status.as_u16() })),
+      );
+    }
+
+    response.json::<Value>().map_err(|e| {
+      OpenWorkError::new(
+        ErrorCode::EngineRequestFailed,
+        format!("Invalid JSON from engine for {path}: {e}"),
+      )
+    })
+  }
+
+  pub fn get_json(&self, path: &str) -> Result<Value, OpenWorkError> {
+    self.send_json(Method::GET, path, None)
+  }
+
+  /// Single, non-retried health probe.
+  pub fn is_healthy(&self) -> bool {
+    self
+      .request(Method::GET, "global/health")
+      .timeout(Duration::from_secs(3))
+      .send()
+      .ok()
+      .filter(|response| response.status().is_success())
+      .and_then(|response| response.json::<Value>().ok())
+      .and_then(|health| health.get("healthy").and_then(|h| h.as_bool()))
+      .unwrap_or(false)
+  }
+}
+
+/// The engine requests for `window` go to: its secondary while that serves
+/// (see `failover`), otherwise its own.
+fn running_engine(
+  manager: &EngineManager,
+  window: &str,
+) -> Result<(String, Option<String>), OpenWorkError> {
+  if let Some(secondary) = manager.failover.serving_secondary(window) {
+    return Ok(secondary);
+  }
+  primary_engine(manager, window)
+}
+
+/// The URL and password of the engine `window` itself runs.
+pub fn primary_engine(
+  manager: &EngineManager,
+  window: &str,
+) -> Result<(String, Option<String>), OpenWorkError> {
+  let (info, auth_token) =
+    manager.with_window(window, |state| (EngineManager::snapshot_locked(state), state.auth_token.clone()));
+
+  match (info.running, info.base_url) {
+    (true, Some(base_url)) => Ok((base_url, auth_token)),
+    _ => Err(OpenWorkError::new(
+      ErrorCode::EngineNotRunning,
+      "Engine is not running. Start it from OpenWork first.",
+    )),
+  }
+}
+
+pub fn require_session_id(id: &str) -> Result<String, OpenWorkError> {
+  let id = id.trim().to_string();
+  if id.is_empty() {
+    return Err(OpenWorkError::invalid_argument("id is required"));
+  }
+  if !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+    return Err(OpenWorkError::invalid_argument(format!("Invalid session id: {id}")));
+  }
+  Ok(id)
+}
+
+pub fn fetch_session(client: &EngineClient, id: &str) -> Result<Value, OpenWorkError> {
+  let id = require_session_id(id)?;
+  client.get_json(&format!("session/{id}"))
+}
+
+pub fn fetch_session_messages(client: &EngineClient, id: &str) -> Result<Value, OpenWorkError> {
+  let id = require_session_id(id)?;
+  client.get_json(&format!("session/{id}/message"))
+}
+
+#[tauri::command]
+pub fn sessions_list(window: WebviewWindow, manager: State<EngineManager>) -> Result<Value, OpenWorkError> {
+  EngineClient::for_window(&manager, window.label())?.get_json("session")
+}
+
+#[tauri::command]
+pub fn session_get(
+  window: WebviewWindow,
+  manager: State<EngineManager>,
+  id: String,
+) -> Result<Value, OpenWorkError> {
+  fetch_session(&EngineClient::for_window(&manager, window.label())?, &id)
+}
+
+#[tauri::command]
+pub fn session_messages(
+  window: WebviewWindow,
+  manager: State<EngineManager>,
+  id: String,
+) -> Result<Value, OpenWorkError> {
+  fetch_session_messages(&EngineClient::for_window(&manager, window.label())?, &id)
+}
@@ -0,0 +1,301 @@
+//! What an engine for a project would be started with, for debugging
+//! "works in my terminal, not in OpenWork".
+//!
+//! `engine_env_preview` builds the command the same way `spawn_engine` does
+//! and reports its program, arguments, working directory and every variable
+//! with where it came from, plus the names the env policy removed and the
+//! config files and overlay opencode will read. Values that look like
+//! credentials, everything from `.env` files and environment profile secrets
+//! (which aren't read from the keychain for this) are masked. The port and
+//! password are picked fresh at each start and shown as placeholders.
+
+use std::{collections::BTreeSet, env, path::Path, process::Command};
+
+use serde::Serialize;
+use serde_json::Value;
+use tauri::AppHandle;
+
+use crate::{
+  cors, devcontainer, dotenv,
+  env_policy::{self, EnvTarget},
+  env_profiles,
+  error::OpenWorkError,
+  permissions,
+  redact::{self, REDACTED},
+};
+
+const PORT_PLACEHOLDER: &str = "<port>";
+const PASSWORD_PLACEHOLDER: &str = "<generated at start>";
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum EnvSource {
+  /// OpenWork's environment, passed by the env policy.
+  Inherited,
+  /// A key selected from the project's `.env` files.
+  Dotenv,
+  /// The project's selected environment profile.
+  EnvProfile,
+  /// Set, or changed like `PATH`, by OpenWork for the engine.
+  OpenWork,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvVarPreview {
+  pub name: String,
+  pub value: String,
+  pub masked: bool,
+  pub source: EnvSource,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigFilePreview {
+  /// `global`, `project` or `OPENCODE_CONFIG`.
+  pub scope: String,
+  pub path: String,
+  pub exists: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineEnvPreview {
+  pub program: String,
+  pub args: Vec<String>,
+  pub cwd: String,
+  /// Sorted by name.
+  pub env: Vec<EnvVarPreview>,
+  /// Variables OpenWork has that the env policy keeps from the engine.
+  pub removed: Vec<String>,
+  pub devcontainer: bool,
+  pub permission_profile: Option<String>,
+  pub env_profile: Option<String>,
+  /// Merged by opencode after the config files.
+  pub config_overlay: Option<Value>,
+  pub config_files: Vec<ConfigFilePreview>,
+  pub notes: Vec<String>,
+}
+
+fn looks_secret(name: &str) -> bool {
+  let name = name.to_ascii_uppercase();
+  [
+    "KEY",
+    "TOKEN",
+    "SECRET",
+    "PASSWORD",
+    "PASSWD",
+    "CREDENTIAL",
+    "AUTH",
+  ]
+  .iter()
+  .any(|part| name.contains(part))
+}
+
+fn preview_var(name: String, value: String, source: EnvSource) -> EnvVarPreview {
+  let masked = source == EnvSource::Dotenv
+    || (looks_secret(&name) && value != PASSWORD_PLACEHOLDER)
+    || redact::redact(&value) != value
+    || value == REDACTED;
+  EnvVarPreview {
+    value: if masked { REDACTED.to_string() } else { value },
+    name,
+    masked,
+    source,
+  }
+}
+
+/// Names in `inherited` that aren't in `passed`, sorted.
+fn removed_names(inherited: impl Iterator<Item = String>, passed: &BTreeSet<String>) -> Vec<String> {
+  let removed: BTreeSet<String> = inherited.filter(|name| !passed.contains(name)).collect();
+  removed.into_iter().collect()
+}
+
+fn dotenv_keys(project_dir: &str) -> BTreeSet<String> {
+  let mut injected = Command::new("opencode");
+  dotenv::inject(&mut injected, project_dir);
+  injected
+    .get_envs()
+    .map(|(key, _)| key.to_string_lossy().to_string())
+    .collect()
+}
+
+fn config_files(project_dir: &str, env: &[EnvVarPreview]) -> Vec<ConfigFilePreview> {
+  let mut files: Vec<ConfigFilePreview> = ["global", "project"]
+    .iter()
+    .filter_map(|scope| {
+      let path = crate::resolve_opencode_config_path(scope, project_dir).ok()?;
+      Some(ConfigFilePreview {
+        scope: scope.to_string(),
+        exists: path.is_file(),
+        path: path.display().to_string(),
+      })
+    })
+    .collect();
+  if let Some(custom) = env
+    .iter()
+    .find(|var| var.name == "OPENCODE_CONFIG" && !var.masked)
+  {
+    files.push(ConfigFilePreview {
+      scope: "OPENCODE_CONFIG".to_string(),
+      exists: Path::new(&custom.value).is_file(),
+      path: custom.value.clone(),
+    });
+  }
+  files
+}
+
+/// The command, environment and config an engine for `project_dir` would get,
+/// with secrets masked. `profile` previews a permission profile other than
+/// the one selected for the project.
+#[tauri::command]
+pub fn engine_env_preview(
+  app: AppHandle,
+  project_dir: String,
+  profile: Option<String>,
+) -> Result<EngineEnvPreview, OpenWorkError> {
+  let project_dir = crate::require_project_dir(&project_dir)?;
+  let overlay = match profile.as_deref().map(str::trim).filter(|id| !id.is_empty()) {
+    Some(id) => Some(permissions::overlay_of(id)?),
+    None => permissions::overlay_for(&project_dir),
+  };
+  let overlay_text = overlay.as_ref().map(|(_, overlay)| overlay.as_str());
+  let in_container = devcontainer::enabled_for(&project_dir);
+  let dotenv = dotenv_keys(&project_dir);
+  let env_profile = env_profiles::resolve_masked(&project_dir);
+  let profile_env = env_profile.as_ref().map_or(&[][..], |profile| profile.vars.as_slice());
+  let mut notes = vec!["A free port and a new password are picked at each start.".to_string()];
+
+  let (program, args, cwd, vars) = if in_container {
+    notes.push(
+      "The engine runs in the project's devcontainer, which supplies the rest of its environment."
+        .to_string(),
+    );
+    let args = crate::engine_args("0.0.0.0", PORT_PLACEHOLDER, &cors::origins(&app), &[]);
+    let vars = crate::container_engine_env(&project_dir, profile_env, PASSWORD_PLACEHOLDER, overlay_text);
+    (
+      "opencode (in the devcontainer)".to_string(),
+      args,
+      project_dir.clone(),
+      vars,
+    )
+  } else {
+    let program = crate::engine_program(&app)?;
+    let log_args = crate::engine_log_args(&app, &program);
+    if !log_args.is_empty() {
+      notes.push(format!("This opencode writes JSON logs ({}).", log_args.join(" ")));
+    }
+    let args = crate::engine_args("127.0.0.1", PORT_PLACEHOLDER, &cors::origins(&app), &log_args);
+    let command = crate::host_engine_command(
+      &program,
+      &project_dir,
+      &args,
+      profile_env,
+      PASSWORD_PLACEHOLDER,
+      overlay_text,
+    );
+    let vars = command
+      .get_envs()
+      .filter_map(|(key, value)| {
+        Some((
+          key.to_string_lossy().to_string(),
+          value?.to_string_lossy().to_string(),
+        ))
+      })
+      .collect();
+    (program.display().to_string(), args, project_dir.clone(), vars)
+  };
+
+  let inherited: BTreeSet<String> = if in_container {
+    BTreeSet::new()
+  } else {
+    env_policy::filtered_env(EnvTarget::Engine)
+      .into_iter()
+      .map(|(name, _)| name)
+      .collect()
+  };
+  let mut env: Vec<EnvVarPreview> = vars
+    .into_iter()
+    .map(|(name, value)| {
+      // The profile is layered after `.env`, so it wins for names in both.
+      let source = if profile_env.iter().any(|(key, _)| *key == name) {
+        EnvSource::EnvProfile
+      } else if dotenv.contains(&name) {
+        EnvSource::Dotenv
+      } else if inherited.contains(&name) && env::var(&name).ok().as_deref() == Some(value.as_str()) {
+        EnvSource::Inherited
+      } else {
+        EnvSource::OpenWork
+      };
+      preview_var(name, value, source)
+    })
+    .collect();
+  env.sort_by(|a, b| a.name.cmp(&b.name));
+  let passed: BTreeSet<String> = env.iter().map(|var| var.name.clone()).collect();
+  let removed = if in_container {
+    Vec::new()
+  } else {
+    removed_names(env::vars().map(|(name, _)| name), &passed)
+  };
+
+  Ok(EngineEnvPreview {
+    program,
+    args,
+    cwd,
+    config_files: config_files(&project_dir, &env),
+    env,
+    removed,
+    devcontainer: in_container,
+    permission_profile: overlay.as_ref().map(|(id, _)| id.clone()),
+    env_profile: env_profile.map(|profile| profile.name),
+    config_overlay: overlay.and_then(|(_, overlay)| serde_json::from_str(&overlay).ok()),
+    notes,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn masks_credentials_and_dotenv_values() {
+    let plain = preview_var(
+      "NODE_ENV".to_string(),
+      "production".to_string(),
+      EnvSource::Inherited,
+    );
+    assert!(!plain.masked);
+    assert_eq!(plain.value, "production");
+
+    let key = preview_var(
+      "ANTHROPIC_API_KEY".to_string(),
+      "abc".to_string(),
+      EnvSource::Inherited,
+    );
+    assert!(key.masked);
+    assert_eq!(key.value, REDACTED);
+
+    let dotenv = preview_var("REGION".to_string(), "eu-west-1".to_string(), EnvSource::Dotenv);
+    assert!(dotenv.masked);
+
+    let secret = preview_var("DB_URL".to_string(), REDACTED.to_string(), EnvSource::EnvProfile);
+    assert!(secret.masked);
+
+    let placeholder = preview_var(
+      "OPENCODE_SERVER_PASSWORD".to_string(),
+      PASSWORD_PLACEHOLDER.to_string(),
+      EnvSource::OpenWork,
+    );
+    assert!(!placeholder.masked);
+  }
+
+  #[test]
+  fn lists_removed_names_once() {
+    let passed: BTreeSet<String> = ["PATH".to_string()].into();
+    let inherited = ["PATH", "TAURI_ENV", "OPENWORK_DEBUG", "TAURI_ENV"].map(str::to_string);
+    assert_eq!(
+      removed_names(inherited.into_iter(), &passed),
+      ["OPENWORK_DEBUG", "TAURI_ENV"]
+    );
+  }
+}
@@ -0,0 +1,468 @@
+//! Commands run around a project's engine: before it starts, once it answers,
+//! and after it stops.
+//!
+//! Hooks are per project and run in the user's login shell (see `shell`) in
+//! the project directory, with the project's `.env` like the engine, and
+//! `OPENWORK_PROJECT_DIR`, `OPENWORK_HOOK_STAGE` and, once ready,
+//! `OPENWORK_ENGINE_URL` set. Each one has a timeout. A failing `preStart`
+//! hook marked `required` stops the engine from starting; other failures are
+//! only reported. Every run is logged and emitted as `engine://hook`. A hook
+//! that starts a service should detach it and redirect its output, or the
+//! hook only finishes when the service does.
+//!
+//! Saving a hook with a new command asks for confirmation, since it will run
+//! unattended from then on.
+
+use std::{
+  collections::BTreeMap,
+  process::Command,
+  sync::RwLock,
+  thread,
+  time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::{
+  consent::{self, ConsentManager},
+  dotenv,
+  engine_client::EngineClient,
+  env_policy,
+  error::{ErrorCode, OpenWorkError},
+  redact,
+  shell::{Shell, ShellOptions},
+  store::{read_state, write_state},
+};
+
+pub const ENGINE_HOOKS_FILE: &str = "engine-hooks.json";
+pub const ENGINE_HOOK_EVENT: &str = "engine://hook";
+
+const MAX_HOOKS: usize = 20;
+const MAX_TIMEOUT_SECS: u64 = 600;
+/// How long `postReady` hooks wait for the engine to answer.
+const READY_TIMEOUT: Duration = Duration::from_secs(60);
+const READY_POLL: Duration = Duration::from_millis(500);
+/// Output kept per stream, from the end.
+const MAX_OUTPUT_CHARS: usize = 4000;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum HookStage {
+  /// Before the engine is spawned; the start waits for these.
+  PreStart,
+  /// Once the engine's health check passes.
+  PostReady,
+  /// After the engine was stopped, or replaced by another start.
+  PostStop,
+}
+
+impl HookStage {
+  fn as_str(self) -> &'static str {
+    match self {
+      Self::PreStart => "preStart",
+      Self::PostReady => "postReady",
+      Self::PostStop => "postStop",
+    }
+  }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EngineHook {
+  pub id: String,
+  pub stage: HookStage,
+  /// A command line for the user's shell.
+  pub command: String,
+  pub timeout_secs: u64,
+  /// `preStart` only: a failure or timeout cancels the start.
+  pub required: bool,
+  pub enabled: bool,
+}
+
+impl Default for EngineHook {
+  fn default() -> Self {
+    Self {
+      id: String::new(),
+      stage: HookStage::PreStart,
+      command: String::new(),
+      timeout_secs: 60,
+      required: false,
+      enabled: true,
+    }
+  }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EngineHookSettings {
+  /// Hooks by project directory, run in order within a stage.
+  pub projects: BTreeMap<String, Vec<EngineHook>>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HookRun {
+  pub project_dir: String,
+  pub hook_id: String,
+  pub stage: HookStage,
+  pub command: String,
+  pub ok: bool,
+  /// `None` when the hook couldn't start or timed out.
+  pub status: Option<i32>,
+  pub duration_ms: u64,
+  pub stdout: String,
+  pub stderr: String,
+  pub error: Option<String>,
+}
+
+static ACTIVE: RwLock<Option<EngineHookSettings>> = RwLock::new(None);
+
+fn active() -> EngineHookSettings {
+  ACTIVE
+    .read()
+    .expect("engine hook settings lock poisoned")
+    .clone()
+    .unwrap_or_default()
+}
+
+pub fn init(app: &AppHandle) {
+  let settings = read_state::<EngineHookSettings>(app, ENGINE_HOOKS_FILE).unwrap_or_else(|e| {
+    tracing::error!(error = %e, "failed to load engine hooks");
+    EngineHookSettings::default()
+  });
+  *ACTIVE.write().expect("engine hook settings lock poisoned") = Some(settings);
+}
+
+fn hooks_for(project_dir: &str, stage: HookStage) -> Vec<EngineHook> {
+  active()
+    .projects
+    .get(project_dir)
+    .into_iter()
+    .flatten()
+    .filter(|hook| hook.enabled && hook.stage == stage)
+    .cloned()
+    .collect()
+}
+
+fn validate(hooks: Vec<EngineHook>) -> Result<Vec<EngineHook>, OpenWorkError> {
+  if hooks.len() > MAX_HOOKS {
+    return Err(OpenWorkError::invalid_argument(format!(
+      "At most {MAX_HOOKS} hooks are supported per project"
+    )));
+  }
+  let mut validated: Vec<EngineHook> = Vec::new();
+  for mut hook in hooks {
+    hook.command = hook.command.trim().to_string();
+    if hook.command.is_empty() || hook.command.contains('\0') {
+      return Err(OpenWorkError::invalid_argument("Hook commands can't be empty"));
+    }
+    if !(1..=MAX_TIMEOUT_SECS).contains(&hook.timeout_secs) {
+      return Err(OpenWorkError::invalid_argument(format!(
+        "Hook timeouts must be between 1 and {MAX_TIMEOUT_SECS} seconds"
+      )));
+    }
+    hook.required &= hook.stage == HookStage::PreStart;
+    hook.id = hook.id.trim().to_string();
+    if hook.id.is_empty() {
+      hook.id = consent::random_token(12);
+    }
+    if validated.iter().any(|other| other.id == hook.id) {
+      return Err(OpenWorkError::invalid_argument(format!(
+        "Duplicate hook id {}",
+        hook.id
+      )));
+    }
+    validated.push(hook);
+  }
+  Ok(validated)
+}
+
+/// Commands in `hooks` that `saved` didn't have, which need the user's
+/// confirmation before they run unattended.
+fn new_commands<'a>(saved: &[EngineHook], hooks: &'a [EngineHook]) -> Vec<&'a str> {
+  let mut commands: Vec<&str> = hooks
+    .iter()
+    .filter(|hook| !saved.iter().any(|old| old.command == hook.command))
+    .map(|hook| hook.command.as_str())
+    .collect();
+  commands.dedup();
+  commands
+}
+
+/// The last `MAX_OUTPUT_CHARS` of `text`.
+fn tail(text: &str) -> String {
+  let text = text.trim_end();
+  let count = text.chars().count();
+  if count <= MAX_OUTPUT_CHARS {
+    return text.to_string();
+  }
+  let skipped: String = text.chars().skip(count - MAX_OUTPUT_CHARS).collect();
+  format!("…{skipped}")
+}
+
+fn hook_command(hook: &EngineHook, project_dir: &str, engine_url: Option<&str>) -> Command {
+  let options = ShellOptions {
+    login: true,
+    cwd: Some(project_dir.into()),
+    timeout: Duration::from_secs(hook.timeout_secs),
+    ..ShellOptions::default()
+  };
+  let mut command = Shell::user().command(&hook.command, &options);
+  env_policy::apply(&mut command, env_policy::EnvTarget::Engine);
+  dotenv::inject(&mut command, project_dir);
+  command
+    .env("OPENWORK_PROJECT_DIR", project_dir)
+    .env("OPENWORK_HOOK_STAGE", hook.stage.as_str());
+  if let Some(url) = engine_url {
+    command.env("OPENWORK_ENGINE_URL", url);
+  }
+  command
+}
+
+fn run_hook(app: &AppHandle, hook: &EngineHook, project_dir: &str, engine_url: Option<&str>) -> HookRun {
+  let started = Instant::now();
+  let timeout = Duration::from_secs(hook.timeout_secs);
+  let output = crate::output_before(
+    &mut hook_command(hook, project_dir, engine_url),
+    started + timeout,
+  );
+  let mut run = HookRun {
+    project_dir: project_dir.to_string(),
+    hook_id: hook.id.clone(),
+    stage: hook.stage,
+    command: redact::redact(&hook.command),
+    ok: false,
+    status: None,
+    duration_ms: started.elapsed().as_millis() as u64,
+    stdout: String::new(),
+    stderr: String::new(),
+    error: None,
+  };
+  match output {
+    Some(output) => {
+      run.ok = output.status.success();
+      run.status = output.status.code();
+      run.stdout = tail(&redact::redact(&String::from_utf8_lossy(&output.stdout)));
+      run.stderr = tail(&redact::redact(&String::from_utf8_lossy(&output.stderr)));
+    }
+    None if started.elapsed() >= timeout => {
+      run.error = Some(format!("Timed out after {}s", hook.timeout_secs));
+    }
+    None => run.error = Some("The hook couldn't be started".to_string()),
+  }
+
+  if run.ok {
+    tracing::info!(hook = %run.hook_id, stage = run.stage.as_str(), duration_ms = run.duration_ms, "engine hook finished");
+  } else {
+    tracing::warn!(
+      hook = %run.hook_id,
+      stage = run.stage.as_str(),
+      status = ?run.status,
+      error = ?run.error,
+      stderr = %run.stderr,
+      "engine hook failed"
+    );
+  }
+  let _ = app.emit(ENGINE_HOOK_EVENT, &run);
+  run
+}
+
+/// Runs the project's `preStart` hooks in order. A failing `required` hook
+/// is `ENGINE_START_FAILED`.
+pub fn before_start(app: &AppHandle, project_dir: &str) -> Result<(), OpenWorkError> {
+  for hook in hooks_for(project_dir, HookStage::PreStart) {
+    let run = run_hook(app, &hook, project_dir, None);
+    if hook.required && !run.ok {
+      let reason = run
+        .error
+        .clone()
+        .or_else(|| run.status.map(|status| format!("exit status {status}")))
+        .unwrap_or_else(|| "failed".to_string());
+      return Err(
+        OpenWorkError::new(
+          ErrorCode::EngineStartFailed,
+          format!("The pre-start hook `{}` failed: {reason}", run.command),
+        )
+        .with_details(serde_json::to_value(&run).unwrap_or_default()),
+      );
+    }
+  }
+  Ok(())
+}
+
+/// Waits in the background for the engine at `base_url` to answer, then
+/// runs the project's `postReady` hooks.
+pub fn after_start(app: &AppHandle, project_dir: &str, base_url: &str, auth_token: Option<String>) {
+  let hooks = hooks_for(project_dir, HookStage::PostReady);
+  if hooks.is_empty() {
+    return;
+  }
+  let (app, project_dir, base_url) = (app.clone(), project_dir.to_string(), base_url.to_string());
+  thread::spawn(move || {
+    let client = match EngineClient::new(base_url.clone(), auth_token) {
+      Ok(client) => client,
+      Err(e) => {
+        tracing::warn!(error = %e.message, "engine hooks can't reach the engine");
+        return;
+      }
+    };
+    let deadline = Instant::now() + READY_TIMEOUT;
+    while !client.is_healthy() {
+      if Instant::now() >= deadline {
+        tracing::warn!(project_dir = %project_dir, "engine not ready; skipping post-ready hooks");
+        return;
+      }
+      thread::sleep(READY_POLL);
+    }
+    for hook in hooks {
+      run_hook(&app, &hook, &project_dir, Some(&base_url));
+    }
+  });
+}
+
+/// Runs the project's `postStop` hooks in the background.
+pub fn after_stop(app: &AppHandle, project_dir: &str) {
+  let hooks = hooks_for(project_dir, HookStage::PostStop);
+  if hooks.is_empty() {
+    return;
+  }
+  let (app, project_dir) = (app.clone(), project_dir.to_string());
+  thread::spawn(move || {
+    for hook in hooks {
+      run_hook(&app, &hook, &project_dir, None);
+    }
+  });
+}
+
+#[tauri::command]
+pub fn engine_hooks_get(project_dir: String) -> Result<Vec<EngineHook>, OpenWorkError> {
+  let project_dir = crate::require_project_dir(&project_dir)?;
+  Ok(active().projects.remove(&project_dir).unwrap_or_default())
+}
+
+/// Replaces the project's hooks. New commands need confirmation.
+#[tauri::command]
+pub fn engine_hooks_set(
+  app: AppHandle,
+  consent: State<ConsentManager>,
+  project_dir: String,
+  hooks: Vec<EngineHook>,
+  confirmation_id: Option<String>,
+) -> Result<Vec<EngineHook>, OpenWorkError> {
+  let project_dir = crate::require_project_dir(&project_dir)?;
+  let hooks = validate(hooks)?;
+  let mut settings = active();
+  let saved = settings.projects.get(&project_dir).cloned().unwrap_or_default();
+  let added = new_commands(&saved, &hooks);
+  if !added.is_empty() {
+    consent.require(
+      "engine_hooks_set",
+      &project_dir,
+      &format!(
+        "Run these commands whenever the engine for {project_dir} starts or stops?\n{}",
+        added.join("\n")
+      ),
+      confirmation_id.as_deref(),
+    )?;
+  }
+  if hooks.is_empty() {
+    settings.projects.remove(&project_dir);
+  } else {
+    settings.projects.insert(project_dir, hooks.clone());
+  }
+  write_state(&app, ENGINE_HOOKS_FILE, &settings)?;
+  *ACTIVE.write().expect("engine hook settings lock poisoned") = Some(settings);
+  Ok(hooks)
+}
+
+/// Runs one saved hook now, to try it out. `postReady` hooks get the
+/// engine's URL only when it's running for the project.
+#[tauri::command]
+pub fn engine_hook_run(
+  app: AppHandle,
+  manager: State<crate::EngineManager>,
+  project_dir: String,
+  id: String,
+) -> Result<HookRun, OpenWorkError> {
+  let project_dir = crate::require_project_dir(&project_dir)?;
+  let hook = active()
+    .projects
+    .get(&project_dir)
+    .and_then(|hooks| hooks.iter().find(|hook| hook.id == id).cloned())
+    .ok_or_else(|| OpenWorkError::new(ErrorCode::NotFound, format!("No hook {id} for {project_dir}")))?;
+  let engine_url = manager
+    .window_for_project(&project_dir)
+    .and_then(|window| EngineClient::for_window(&manager, &window).ok())
+    .map(|client| client.base_url().to_string());
+  Ok(run_hook(&app, &hook, &project_dir, engine_url.as_deref()))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn hook(command: &str, stage: HookStage) -> EngineHook {
+    EngineHook {
+      stage,
+      command: command.to_string(),
+      ..EngineHook::default()
+    }
+  }
+
+  #[test]
+  fn validates_hooks() {
+    let hooks = validate(vec![
+      EngineHook {
+        id: "db".to_string(),
+        required: true,
+        ..hook(" docker compose up -d db ", HookStage::PreStart)
+      },
+      EngineHook {
+        required: true,
+        ..hook("./notify.sh stopped", HookStage::PostStop)
+      },
+    ])
+    .unwrap();
+    assert_eq!(hooks[0].command, "docker compose up -d db");
+    assert!(hooks[0].required);
+    // Only pre-start hooks can hold up the engine.
+    assert!(!hooks[1].required);
+    assert!(!hooks[1].id.is_empty());
+
+    assert!(validate(vec![hook("  ", HookStage::PreStart)]).is_err());
+    assert!(validate(vec![EngineHook {
+      timeout_secs: 0,
+      ..hook("true", HookStage::PreStart)
+    }])
+    .is_err());
+    let duplicate = EngineHook {
+      id: "a".to_string(),
+      ..hook("true", HookStage::PreStart)
+    };
+    assert!(validate(vec![duplicate.clone(), duplicate]).is_err());
+  }
+
+  #[test]
+  fn only_new_commands_need_confirmation() {
+    let saved = [hook("make db", HookStage::PreStart)];
+    let hooks = [
+      EngineHook {
+        timeout_secs: 120,
+        ..hook("make db", HookStage::PreStart)
+      },
+      hook("make warm", HookStage::PostReady),
+    ];
+    assert_eq!(new_commands(&saved, &hooks), ["make warm"]);
+    assert!(new_commands(&hooks, &saved).is_empty());
+  }
+
+  #[test]
+  fn keeps_the_end_of_long_output() {
+    assert_eq!(tail("done\n\n"), "done");
+    let long = format!("{}end", "x".repeat(MAX_OUTPUT_CHARS));
+    let kept = tail(&long);
+    assert!(kept.starts_with('…') && kept.ends_with("end"));
+    assert_eq!(kept.chars().count(), MAX_OUTPUT_CHARS + 1);
+  }
+}
@@ -0,0 +1,187 @@
+//! Which OpenWork instance runs the engine for a project, across instances.
+//!
+//! The single-instance plugin only covers launches of the same install; a
+//! portable copy or a dev build runs next to it. Each engine start writes
+//! `engines/<hash of the project dir>.json` to the user's default OpenWork
+//! data directory (readable by the user only, as it holds the engine's
+//! password), and stopping the engine removes it. `engine_start` checks the
+//! file first: a live engine of another instance is reported as
+//! `ALREADY_EXISTS` with `details.conflict = "engine"`, or attached to when
+//! the caller asks. An attached engine is left running when this instance
+//! stops using it. A file whose instance is gone or whose port no longer
+//! answers is stale and ignored.
+
+use std::{
+  fs,
+  net::{TcpStream, ToSocketAddrs},
+  path::PathBuf,
+  time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::{
+  control,
+  error::{ErrorCode, OpenWorkError},
+  private_files,
+};
+
+const LOCK_DIR: &str = "engines";
+/// How long a connection to the recorded port may take to count as live.
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineLock {
+  /// The OpenWork process that started the engine.
+  pub owner_pid: u32,
+  pub engine_pid: Option<u32>,
+  pub project_dir: String,
+  pub hostname: String,
+  pub port: u16,
+  pub base_url: String,
+  pub auth_token: String,
+}
+
+fn lock_dir() -> Option<PathBuf> {
+  Some(control::default_data_dir()?.join(LOCK_DIR))
+}
+
+fn file_name(project_dir: &str) -> String {
+  let digest = Sha256::digest(project_dir.as_bytes());
+  let hex: String = digest[..16].iter().map(|byte| format!("{byte:02x}")).collect();
+  format!("{hex}.json")
+}
+
+fn lock_path(project_dir: &str) -> Option<PathBuf> {
+  Some(lock_dir()?.join(file_name(project_dir)))
+}
+
+/// Whether process `pid` exists; assumed so where that can't be checked.
+fn process_alive(pid: u32) -> bool {
+  #[cfg(unix)]
+  {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+      return false;
+    };
+    // Signal 0 only checks; EPERM means it exists under another user.
+    unsafe { libc::kill(pid, 0) == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM) }
+  }
+  #[cfg(not(unix))]
+  {
+    let _ = pid;
+    true
+  }
+}
+
+/// Whether something accepts connections on `hostname:port`.
+pub fn reachable(hostname: &str, port: u16) -> bool {
+  (hostname, port)
+    .to_socket_addrs()
+    .ok()
+    .and_then(|mut addrs| addrs.next())
+    .is_some_and(|addr| TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).is_ok())
+}
+
+fn is_live(lock: &EngineLock) -> bool {
+  process_alive(lock.owner_pid) && reachable(&lock.hostname, lock.port)
+}
+
+fn read(project_dir: &str) -> Option<EngineLock> {
+  let content = fs::read_to_string(lock_path(project_dir)?).ok()?;
+  serde_json::from_str::<EngineLock>(&content)
+    .ok()
+    .filter(|lock| lock.project_dir == project_dir)
+}
+
+/// The engine another OpenWork instance runs for `project_dir`, if it's
+/// still up.
+pub fn running_elsewhere(project_dir: &str) -> Option<EngineLock> {
+  let lock = read(project_dir)?;
+  if lock.owner_pid == std::process::id() {
+    return None;
+  }
+  if !is_live(&lock) {
+    tracing::info!(
+      owner_pid = lock.owner_pid,
+      port = lock.port,
+      "ignoring stale engine lock"
+    );
+    return None;
+  }
+  Some(lock)
+}
+
+/// The error for an engine another instance runs; the password stays out
+/// of it.
+pub fn conflict(lock: &EngineLock) -> OpenWorkError {
+  OpenWorkError::new(
+    ErrorCode::AlreadyExists,
+    format!(
+      "Another OpenWork (process {}) already runs an engine for {} on port {}.",
+      lock.owner_pid, lock.project_dir, lock.port
+    ),
+  )
+  .with_details(json!({
+    "conflict": "engine",
+    "projectDir": lock.project_dir,
+    "ownerPid": lock.owner_pid,
+    "enginePid": lock.engine_pid,
+    "port": lock.port,
+    "baseUrl": lock.base_url,
+    "resolutions": ["attach"],
+  }))
+}
+
+/// Records the engine this instance started.
+pub fn record(lock: &EngineLock) {
+  let Some(path) = lock_path(&lock.project_dir) else {
+    return;
+  };
+  let written = (|| {
+    let dir = path.parent().unwrap_or(&path);
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+    let content = serde_json::to_string_pretty(lock).map_err(|e| e.to_string())?;
+    // Written aside and renamed, so other instances never read half a file.
+    let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+    private_files::write(&tmp, content)?;
+    fs::rename(&tmp, &path).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+  })();
+  if let Err(e) = written {
+    tracing::warn!(error = %e, "failed to record the engine lock");
+  }
+}
+
+/// Removes the lock of `project_dir` if this instance wrote it.
+pub fn release(project_dir: &str) {
+  if read(project_dir).is_some_and(|lock| lock.owner_pid == std::process::id()) {
+    if let Some(path) = lock_path(project_dir) {
+      let _ = fs::remove_file(path);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn names_locks_by_project() {
+    let name = file_name("/code/app");
+    assert_eq!(name.len(), 32 + ".json".len());
+    assert_eq!(name, file_name("/code/app"));
+    assert_ne!(name, file_name("/code/app2"));
+  }
+
+  #[test]
+  fn checks_processes_and_ports() {
+    assert!(process_alive(std::process::id()));
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    assert!(reachable("127.0.0.1", port));
+    drop(listener);
+    assert!(!reachable("127.0.0.1", port));
+  }
+}
@@ -0,0 +1,401 @@
+//! Structured events parsed from the engine's stdout/stderr.
+//!
+//! `opencode serve --print-logs` writes lines such as
+//! `INFO  2025-06-18T10:00:00 +3ms service=server method=GET path=/event request`.
+//! Each line becomes an [`EngineLogEvent`] with a level and a category so the
+//! UI can tell a provider rejecting an API key apart from routine request logs.
+//! Anything that doesn't follow the format (banners, stack traces) is still
+//! emitted, with the level guessed from its text.
+//!
+//! When `serve --help` lists a JSON log option (see `json_log_args`), the
+//! engine is started with it and lines that are JSON objects are read field
+//! by field instead, which gives exact levels and fields. Plain lines are
+//! still parsed as above, so output from before logging starts isn't lost.
+
+use std::{
+  io::{BufRead, BufReader, Read},
+  thread,
+};
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Emitter};
+
+use crate::redact::redact;
+
+/// Kept apart from `engine://event`, which carries the engine's own SSE events.
+pub const ENGINE_LOG_EVENT: &str = "engine://log";
+
+const MAX_MESSAGE_CHARS: usize = 4000;
+
+/// Text that marks a provider rejecting credentials.
+const AUTH_MARKERS: &[&str] = &[
+  "unauthorized",
+  "invalid api key",
+  "invalid_api_key",
+  "incorrect api key",
+  "api key not found",
+  "authentication",
+  "providerautherror",
+  "status=401",
+  "statuscode=401",
+  "status: 401",
+  "401 unauthorized",
+  "403 forbidden",
+];
+
+/// Services whose logs are about talking to model providers.
+const PROVIDER_SERVICES: &[&str] = &["provider", "llm", "models.dev", "session.prompt", "session.processor"];
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum EngineLogLevel {
+  Debug,
+  Info,
+  Warn,
+  Error,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum EngineLogCategory {
+  /// HTTP requests handled by the engine's server.
+  Request,
+  /// A provider refused the configured credentials.
+  ProviderAuth,
+  /// Other model provider activity and failures.
+  Provider,
+  /// Engine lifecycle: startup, shutdown, config loading.
+  Server,
+  Other,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EngineStream {
+  Stdout,
+  Stderr,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineLogEvent {
+  pub project_dir: String,
+  pub stream: EngineStream,
+  pub level: EngineLogLevel,
+  pub category: EngineLogCategory,
+  pub timestamp: Option<String>,
+  /// The `service=` tag opencode attaches to most lines.
+  pub service: Option<String>,
+  pub message: String,
+  /// Remaining `key=value` tags.
+  pub fields: Map<String, Value>,
+}
+
+fn parse_level(token: &str) -> Option<EngineLogLevel> {
+  match token.to_ascii_uppercase().as_str() {
+    "DEBUG" | "TRACE" => Some(EngineLogLevel::Debug),
+    "INFO" => Some(EngineLogLevel::Info),
+    "WARN" | "WARNING" => Some(EngineLogLevel::Warn),
+    "ERROR" | "FATAL" => Some(EngineLogLevel::Error),
+    _ => None,
+  }
+}
+
+fn is_timestamp(token: &str) -> bool {
+  token.len() >= 10 && token.as_bytes()[0].is_ascii_digit() && token.contains('-') && token.contains('T')
+}
+
+fn is_elapsed(token: &str) -> bool {
+  token
+    .strip_prefix('+')
+    .and_then(|rest| rest.strip_suffix("ms"))
+    .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn split_tag(token: &str) -> Option<(&str, &str)> {
+  let (key, value) = token.split_once('=')?;
+  let valid_key = key.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+    && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+  valid_key.then_some((key, value))
+}
+
+/// Level for a line that isn't in opencode's log format.
+fn guess_level(line: &str) -> EngineLogLevel {
+  let lower = line.trim_start().to_ascii_lowercase();
+  if lower.starts_with("error") || lower.contains(" error:") || lower.starts_with("panic") {
+    EngineLogLevel::Error
+  } else if lower.starts_with("warn") {
+    EngineLogLevel::Warn
+  } else {
+    EngineLogLevel::Info
+  }
+}
+
+fn categorize(
+  level: EngineLogLevel,
+  service: Option<&str>,
+  fields: &Map<String, Value>,
+  message: &str,
+) -> EngineLogCategory {
+  let is_provider = service.is_some_and(|s| PROVIDER_SERVICES.contains(&s));
+
+  if level >= EngineLogLevel::Warn || is_provider {
+    let mut text = message.to_ascii_lowercase();
+    for (key, value) in fields {
+      text.push_str(&format!(" {key}={}", value.as_str().unwrap_or_default()).to_ascii_lowercase());
+    }
+    if AUTH_MARKERS.iter().any(|marker| text.contains(marker)) {
+      return EngineLogCategory::ProviderAuth;
+    }
+  }
+
+  if is_provider {
+    return EngineLogCategory::Provider;
+  }
+  match service {
+    Some("server") if fields.contains_key("method") || fields.contains_key("path") => {
+      EngineLogCategory::Request
+    }
+    Some("server") | Some("bus") | Some("config") | Some("default") => EngineLogCategory::Server,
+    _ if message.contains("server listening") => EngineLogCategory::Server,
+    _ => EngineLogCategory::Other,
+  }
+}
+
+/// The arguments that switch `opencode serve` to JSON logs, read from its
+/// `--help`: a flag such as `--log-format` whose description mentions `json`,
+/// or a switch with `json` in its name such as `--json-logs`.
+pub fn json_log_args(help: &str) -> Option<Vec<String>> {
+  help.lines().find_map(|line| {
+    let lower = line.to_ascii_lowercase();
+    line.split_whitespace().find_map(|token| {
+      let flag = token
+        .trim_end_matches(',')
+        .split(['=', '[', '<'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+      if !flag.starts_with("--") || !flag.contains("log") {
+        return None;
+      }
+      if flag.contains("json") {
+        Some(vec![flag])
+      } else if flag.contains("format") && lower.contains("json") {
+        Some(vec![flag, "json".to_string()])
+      } else {
+        None
+      }
+    })
+  })
+}
+
+/// Level from a JSON log's `level`: a name, or a pino-style number.
+fn json_level(value: &Value) -> Option<EngineLogLevel> {
+  match value {
+    Value::String(name) => parse_level(name),
+    Value::Number(number) => Some(match number.as_u64()? {
+      0..=29 => EngineLogLevel::Debug,
+      30..=39 => EngineLogLevel::Info,
+      40..=49 => EngineLogLevel::Warn,
+      _ => EngineLogLevel::Error,
+    }),
+    _ => None,
+  }
+}
+
+fn take_string(fields: &mut Map<String, Value>, keys: &[&str]) -> Option<String> {
+  keys.iter().find_map(|key| match fields.remove(*key)? {
+    Value::String(text) => Some(text),
+    Value::Null => None,
+    other => Some(other.to_string()),
+  })
+}
+
+/// Parses one (already redacted) JSON log line; `None` when it isn't a JSON
+/// object.
+fn parse_json_line(project_dir: &str, stream: EngineStream, line: &str) -> Option<EngineLogEvent> {
+  let Value::Object(mut fields) = serde_json::from_str::<Value>(line.trim()).ok()? else {
+    return None;
+  };
+  let level = ["level", "lvl", "severity"]
+    .iter()
+    .find_map(|key| fields.remove(*key))
+    .and_then(|value| json_level(&value));
+  let timestamp = take_string(&mut fields, &["time", "timestamp", "ts"]);
+  let service = take_string(&mut fields, &["service"]);
+  let message = take_string(&mut fields, &["msg", "message"]).unwrap_or_default();
+  let message: String = message.chars().take(MAX_MESSAGE_CHARS).collect();
+  let level = level.unwrap_or_else(|| guess_level(&message));
+  Some(EngineLogEvent {
+    project_dir: project_dir.to_string(),
+    stream,
+    level,
+    category: categorize(level, service.as_deref(), &fields, &message),
+    timestamp,
+    service,
+    message,
+    fields,
+  })
+}
+
+/// Parses one (already redacted) line of engine output.
+pub fn parse_line(project_dir: &str, stream: EngineStream, line: &str) -> EngineLogEvent {
+  let line = line.trim_end();
+  if line.trim_start().starts_with('{') {
+    if let Some(event) = parse_json_line(project_dir, stream, line) {
+      return event;
+    }
+  }
+  let mut tokens = line.split_whitespace().peekable();
+
+  let level = tokens.peek().and_then(|token| parse_level(token));
+  let (level, timestamp, service, fields, message) = match level {
+    None => (guess_level(line), None, None, Map::new(), line.trim().to_string()),
+    Some(level) => {
+      tokens.next();
+      let timestamp = tokens
+        .next_if(|token| is_timestamp(token))
+        .map(str::to_string);
+      tokens.next_if(|token| is_elapsed(token));
+
+      let mut service = None;
+      let mut fields = Map::new();
+      while let Some((key, value)) = tokens.peek().and_then(|token| split_tag(token)) {
+        tokens.next();
+        if key == "service" {
+          service = Some(value.to_string());
+        } else {
+          fields.insert(key.to_string(), Value::String(value.to_string()));
+        }
+      }
+
+      let message = tokens.collect::<Vec<_>>().join(" ");
+      (level, timestamp, service, fields, message)
+    }
+  };
+
+  let message: String = message.chars().take(MAX_MESSAGE_CHARS).collect();
+  EngineLogEvent {
+    project_dir: project_dir.to_string(),
+    stream,
+    level,
+    category: categorize(level, service.as_deref(), &fields, &message),
+    timestamp,
+    service,
+    message,
+    fields,
+  }
+}
+
+/// Reads `output` line by line until the engine closes it, emitting each line
+/// as an [`ENGINE_LOG_EVENT`]. Warnings and errors also go to the app log.
+pub fn forward(
+  app: AppHandle,
+  project_dir: String,
+  stream: EngineStream,
+  output: impl Read + Send + 'static,
+) {
+  thread::spawn(move || {
+    for line in BufReader::new(output).lines() {
+      let Ok(line) = line else {
+        break;
+      };
+      if line.trim().is_empty() {
+        continue;
+      }
+
+      let event = parse_line(&project_dir, stream, &redact(&line));
+      match event.level {
+        EngineLogLevel::Error => {
+          tracing::error!(category = ?event.category, service = ?event.service, "{}", event.message)
+        }
+        EngineLogLevel::Warn => {
+          tracing::warn!(category = ?event.category, service = ?event.service, "{}", event.message)
+        }
+        _ => {}
+      }
+      let _ = app.emit(ENGINE_LOG_EVENT, &event);
+    }
+  });
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_request_lines() {
+    let event = parse_line(
+      "/p",
+      EngineStream::Stderr,
+      "INFO  2025-06-18T10:00:00 +3ms service=server method=GET path=/session request",
+    );
+    assert_eq!(event.level, EngineLogLevel::Info);
+    assert_eq!(event.category, EngineLogCategory::Request);
+    assert_eq!(event.timestamp.as_deref(), Some("2025-06-18T10:00:00"));
+    assert_eq!(event.service.as_deref(), Some("server"));
+    assert_eq!(event.fields.get("path"), Some(&Value::String("/session".to_string())));
+    assert_eq!(event.message, "request");
+  }
+
+  #[test]
+  fn flags_provider_auth_failures() {
+    let event = parse_line(
+      "/p",
+      EngineStream::Stderr,
+      "ERROR 2025-06-18T10:00:01 +12ms service=session.prompt providerID=anthropic error AI_APICallError: invalid x-api-key (401 Unauthorized)",
+    );
+    assert_eq!(event.level, EngineLogLevel::Error);
+    assert_eq!(event.category, EngineLogCategory::ProviderAuth);
+
+    let event = parse_line(
+      "/p",
+      EngineStream::Stderr,
+      "WARN  2025-06-18T10:00:02 +1ms service=provider providerID=openai status=429 rate limited",
+    );
+    assert_eq!(event.category, EngineLogCategory::Provider);
+  }
+
+  #[test]
+  fn keeps_unstructured_lines() {
+    let event = parse_line("/p", EngineStream::Stdout, "opencode server listening on http://127.0.0.1:4096");
+    assert_eq!(event.level, EngineLogLevel::Info);
+    assert_eq!(event.category, EngineLogCategory::Server);
+    assert!(event.fields.is_empty());
+
+    let event = parse_line("/p", EngineStream::Stderr, "Error: Unable to connect to provider");
+    assert_eq!(event.level, EngineLogLevel::Error);
+  }
+
+  #[test]
+  fn finds_json_log_options() {
+    let help = "Options:\n  --print-logs        print logs to stderr  [boolean]\n  --log-format <fmt>  log output format: text or json\n";
+    assert_eq!(json_log_args(help), Some(vec!["--log-format".to_string(), "json".to_string()]));
+    assert_eq!(
+      json_log_args("  --json-logs  emit logs as JSON lines"),
+      Some(vec!["--json-logs".to_string()])
+    );
+    assert_eq!(json_log_args("  --print-logs  print logs to stderr\n  --port  port"), None);
+  }
+
+  #[test]
+  fn parses_json_lines() {
+    let event = parse_line(
+      "/p",
+      EngineStream::Stderr,
+      r#"{"level":"error","time":"2025-06-18T10:00:01Z","service":"session.prompt","providerID":"anthropic","msg":"AI_APICallError: invalid x-api-key (401 Unauthorized)"}"#,
+    );
+    assert_eq!(event.level, EngineLogLevel::Error);
+    assert_eq!(event.category, EngineLogCategory::ProviderAuth);
+    assert_eq!(event.timestamp.as_deref(), Some("2025-06-18T10:00:01Z"));
+    assert_eq!(event.fields.get("providerID"), Some(&Value::String("anthropic".to_string())));
+
+    let event = parse_line("/p", EngineStream::Stderr, r#"{"level":40,"msg":"slow","ms":1200}"#);
+    assert_eq!(event.level, EngineLogLevel::Warn);
+    assert_eq!(event.fields.get("ms"), Some(&Value::from(1200)));
+
+    let event = parse_line("/p", EngineStream::Stdout, "{ not json");
+    assert_eq!(event.message, "{ not json");
+  }
+}
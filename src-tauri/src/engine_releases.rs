@@ -0,0 +1,318 @@
+//! Release notes for opencode versions, for the upgrade prompt.
+//!
+//! Releases come from the GitHub API and are kept in `engine-releases.json`
+//! for an hour, so reopening the prompt doesn't fetch again. When the
+//! fetch fails the last saved list is used and the result says it's stale.
+
+use std::{
+  collections::BTreeMap,
+  time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use reqwest::blocking::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, State};
+
+use crate::{
+  download,
+  engine_cache::EngineCache,
+  error::{ErrorCode, OpenWorkError},
+  network,
+  store::{read_state, write_state},
+  RESOLVE_TIMEOUT,
+};
+
+pub const ENGINE_RELEASES_FILE: &str = "engine-releases.json";
+
+const RELEASES_URL: &str = "https://api.github.com/repos/sst/opencode/releases";
+const CACHE_TTL_MS: u64 = 60 * 60 * 1000;
+const PER_PAGE: usize = 100;
+/// Enough to reach versions several hundred releases back.
+const MAX_PAGES: usize = 5;
+
+type Version = (u64, u64, u64);
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineRelease {
+  /// Without the leading `v`.
+  pub version: String,
+  pub name: Option<String>,
+  pub published_at: Option<String>,
+  /// Markdown.
+  pub body: String,
+  pub url: String,
+  pub prerelease: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct ReleaseCache {
+  fetched_at: u64,
+  releases: Vec<EngineRelease>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineReleaseNotes {
+  pub from_version: String,
+  pub to_version: String,
+  /// Newest first, after `from_version` up to and including `to_version`.
+  pub releases: Vec<EngineRelease>,
+  pub fetched_at: u64,
+  /// The list couldn't be refreshed and may miss recent releases.
+  pub stale: bool,
+}
+
+/// `(major, minor, patch)` from `v1.2.3`, `1.2` or `opencode 1.2.3-beta.1`;
+/// pre-release and build suffixes are dropped.
+fn parse_version(text: &str) -> Option<Version> {
+  let token = text
+    .split_whitespace()
+    .map(|word| word.trim_start_matches('v'))
+    .find(|word| word.starts_with(|c: char| c.is_ascii_digit()))?;
+  let core = token.split(['-', '+']).next()?;
+  let mut parts = core.split('.').map(str::parse::<u64>);
+  let major = parts.next()?.ok()?;
+  let minor = parts.next().unwrap_or(Ok(0)).ok()?;
+  let patch = parts.next().unwrap_or(Ok(0)).ok()?;
+  Some((major, minor, patch))
+}
+
+fn now_ms() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_millis() as u64)
+    .unwrap_or(0)
+}
+
+fn display(version: Version) -> String {
+  format!("{}.{}.{}", version.0, version.1, version.2)
+}
+
+/// The newest release that isn't a pre-release.
+fn latest(releases: &[EngineRelease]) -> Option<Version> {
+  releases
+    .iter()
+    .filter(|release| !release.prerelease)
+    .filter_map(|release| parse_version(&release.version))
+    .max()
+}
+
+/// Releases after `from` up to `to`, newest first. Pre-releases only count
+/// when one is `to` itself.
+fn between(releases: &[EngineRelease], from: Version, to: Version) -> Vec<EngineRelease> {
+  let mut selected: Vec<(Version, &EngineRelease)> = releases
+    .iter()
+    .filter_map(|release| Some((parse_version(&release.version)?, release)))
+    .filter(|(version, release)| *version > from && *version <= to && (!release.prerelease || *version == to))
+    .collect();
+  selected.sort_by(|a, b| b.0.cmp(&a.0));
+  selected.dedup_by(|a, b| a.0 == b.0);
+  selected.into_iter().map(|(_, release)| release.clone()).collect()
+}
+
+fn from_github(release: &Value) -> Option<EngineRelease> {
+  if release["draft"].as_bool() == Some(true) {
+    return None;
+  }
+  let tag = release["tag_name"].as_str()?;
+  Some(EngineRelease {
+    version: tag.trim_start_matches('v').to_string(),
+    name: release["name"]
+      .as_str()
+      .filter(|name| !name.is_empty())
+      .map(str::to_string),
+    published_at: release["published_at"].as_str().map(str::to_string),
+    body: release["body"].as_str().unwrap_or_default().to_string(),
+    url: release["html_url"].as_str().unwrap_or_default().to_string(),
+    prerelease: release["prerelease"].as_bool().unwrap_or(false),
+  })
+}
+
+fn fetch_page(client: &Client, page: usize) -> Result<Vec<EngineRelease>, OpenWorkError> {
+  let response = client
+    .get(RELEASES_URL)
+    .query(&[("per_page", PER_PAGE), ("page", page)])
+    .header("Accept", "application/vnd.github+json")
+    .header("User-Agent", "OpenWork")
+    .send()?;
+  let status = response.status();
+  if !status.is_success() {
+    return Err(OpenWorkError::new(
+      ErrorCode::Network,
+      format!("GitHub answered {status} when listing opencode releases"),
+    ));
+  }
+  let body: Value = response.json()?;
+  Ok(
+    body
+      .as_array()
+      .into_iter()
+      .flatten()
+      .filter_map(from_github)
+      .collect(),
+  )
+}
+
+/// Fetches pages, newest first, until one reaches back to `from`.
+fn fetch(from: Version) -> Result<Vec<EngineRelease>, OpenWorkError> {
+  let client = download::client().map_err(|e| OpenWorkError::new(ErrorCode::Network, e))?;
+  let mut releases = Vec::new();
+  for page in 1..=MAX_PAGES {
+    let batch = fetch_page(&client, page)?;
+    let done = batch.len() < PER_PAGE
+      || batch
+        .iter()
+        .filter_map(|release| parse_version(&release.version))
+        .any(|version| version <= from);
+    releases.extend(batch);
+    if done {
+      break;
+    }
+  }
+  Ok(releases)
+}
+
+/// Saved releases merged with `fetched`, which wins for the same version.
+fn merge(saved: Vec<EngineRelease>, fetched: Vec<EngineRelease>) -> Vec<EngineRelease> {
+  let mut by_version: BTreeMap<String, EngineRelease> = saved
+    .into_iter()
+    .map(|release| (release.version.clone(), release))
+    .collect();
+  for release in fetched {
+    by_version.insert(release.version.clone(), release);
+  }
+  by_version.into_values().collect()
+}
+
+fn installed_version(cache: &EngineCache) -> Option<Version> {
+  let deadline = Instant::now() + RESOLVE_TIMEOUT;
+  let (program, _, _) = cache.resolve_before(false, deadline);
+  parse_version(&cache.probe(&program?, deadline).version?)
+}
+
+/// Release notes from `from_version` (default: the installed opencode) to
+/// `to_version` (default: the latest release).
+#[tauri::command]
+pub fn engine_release_notes(
+  app: AppHandle,
+  cache: State<EngineCache>,
+  from_version: Option<String>,
+  to_version: Option<String>,
+) -> Result<EngineReleaseNotes, OpenWorkError> {
+  let parse = |value: Option<String>, name: &str| {
+    value
+      .filter(|value| !value.trim().is_empty())
+      .map(|value| {
+        parse_version(&value)
+          .ok_or_else(|| OpenWorkError::invalid_argument(format!("{name} is not a version: {value}")))
+      })
+      .transpose()
+  };
+  let to = parse(to_version, "toVersion")?;
+  let from = match parse(from_version, "fromVersion")? {
+    Some(from) => from,
+    None => installed_version(&cache).ok_or_else(|| {
+      OpenWorkError::new(
+        ErrorCode::EngineNotFound,
+        "Couldn't tell which opencode version is installed; pass fromVersion",
+      )
+    })?,
+  };
+
+  let saved: ReleaseCache = read_state(&app, ENGINE_RELEASES_FILE)?;
+  let now = now_ms();
+  let covers = |releases: &[EngineRelease]| {
+    let versions: Vec<Version> = releases
+      .iter()
+      .filter_map(|r| parse_version(&r.version))
+      .collect();
+    versions.iter().any(|version| *version <= from)
+      && match to {
+        Some(to) => versions.contains(&to),
+        None => true,
+      }
+  };
+  let fresh = now.saturating_sub(saved.fetched_at) < CACHE_TTL_MS && covers(&saved.releases);
+
+  let (releases, fetched_at, stale) = if fresh {
+    (saved.releases, saved.fetched_at, false)
+  } else {
+    match network::require_online(&app, "Fetching release notes").and_then(|()| fetch(from)) {
+      Ok(fetched) => {
+        let releases = merge(saved.releases, fetched);
+        let updated = ReleaseCache {
+          fetched_at: now,
+          releases,
+        };
+        if let Err(e) = write_state(&app, ENGINE_RELEASES_FILE, &updated) {
+          tracing::warn!(error = %e, "failed to save opencode releases");
+        }
+        (updated.releases, now, false)
+      }
+      Err(e) if !saved.releases.is_empty() => {
+        tracing::warn!(error = %e, "using saved opencode releases");
+        (saved.releases, saved.fetched_at, true)
+      }
+      Err(e) => return Err(e),
+    }
+  };
+
+  let to = to
+    .or_else(|| latest(&releases))
+    .ok_or_else(|| OpenWorkError::new(ErrorCode::NotFound, "No opencode releases found"))?;
+  Ok(EngineReleaseNotes {
+    from_version: display(from),
+    to_version: display(to),
+    releases: between(&releases, from, to),
+    fetched_at,
+    stale,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn release(version: &str, prerelease: bool) -> EngineRelease {
+    EngineRelease {
+      version: version.to_string(),
+      name: None,
+      published_at: None,
+      body: format!("notes for {version}"),
+      url: String::new(),
+      prerelease,
+    }
+  }
+
+  #[test]
+  fn parses_versions() {
+    assert_eq!(parse_version("v0.15.3"), Some((0, 15, 3)));
+    assert_eq!(parse_version("opencode 1.2"), Some((1, 2, 0)));
+    assert_eq!(parse_version("1.0.0-beta.2"), Some((1, 0, 0)));
+    assert_eq!(parse_version("latest"), None);
+  }
+
+  #[test]
+  fn selects_releases_between_versions() {
+    let releases = vec![
+      release("0.9.0", false),
+      release("0.10.0", false),
+      release("0.10.1", false),
+      release("0.11.0", true),
+      release("0.10.2", false),
+    ];
+    assert_eq!(latest(&releases), Some((0, 10, 2)));
+    let versions = |from, to| {
+      between(&releases, from, to)
+        .into_iter()
+        .map(|release| release.version)
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(versions((0, 9, 0), (0, 10, 2)), ["0.10.2", "0.10.1", "0.10.0"]);
+    assert_eq!(versions((0, 10, 1), (0, 11, 0)), ["0.11.0", "0.10.2"]);
+    assert!(versions((0, 10, 2), (0, 10, 2)).is_empty());
+  }
+}
@@ -0,0 +1,147 @@
+use std::{env, process::Command, sync::RwLock};
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::store::{read_state, write_state};
+use crate::error::OpenWorkError;
+
+pub const ENV_POLICY_FILE: &str = "env-policy.json";
+
+/// Which kind of process an environment is being prepared for.
+#[derive(Debug, Clone, Copy)]
+pub enum EnvTarget {
+  /// The opencode engine; it needs provider API keys, so only app-internal
+  /// variables are removed by default.
+  Engine,
+  /// Installers and package managers; only a minimal allowlist passes.
+  Installer,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum EnvMode {
+  Allowlist,
+  Denylist,
+}
+
+/// Patterns are variable names, optionally ending in `*` for a prefix match.
+/// Matching is case-insensitive.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvRules {
+  pub mode: EnvMode,
+  pub allow: Vec<String>,
+  pub deny: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EnvPolicy {
+  pub engine: EnvRules,
+  pub installer: EnvRules,
+}
+
+fn patterns(items: &[&str]) -> Vec<String> {
+  items.iter().map(|s| s.to_string()).collect()
+}
+
+/// Variables every process needs to find binaries, temp dirs, locale and proxies.
+const BASE_ALLOW: &[&str] = &[
+  "PATH", "PATHEXT", "HOME", "USER", "USERNAME", "LOGNAME", "SHELL", "TERM", "LANG", "LC_*", "TZ",
+  "TMPDIR", "TMP", "TEMP", "XDG_*", "SYSTEMROOT", "SYSTEMDRIVE", "WINDIR", "COMSPEC", "APPDATA",
+  "LOCALAPPDATA", "USERPROFILE", "PROGRAMDATA", "PROGRAMFILES", "PROGRAMFILES(X86)", "HTTP_PROXY",
+  "HTTPS_PROXY", "NO_PROXY", "ALL_PROXY", "SSL_CERT_FILE", "SSL_CERT_DIR", "NODE_EXTRA_CA_CERTS",
+  "NPM_CONFIG_*", "OPENCODE_INSTALL_DIR", "NIX_*",
+];
+
+/// Variables that belong to OpenWork itself and never leave the app.
+const APP_DENY: &[&str] = &["TAURI_*", "OPENWORK_*", "OPENCODE_SERVER_PASSWORD", "OPENCODE_SERVER_USERNAME"];
+
+impl Default for EnvPolicy {
+  fn default() -> Self {
+    Self {
+      engine: EnvRules {
+        mode: EnvMode::Denylist,
+        allow: Vec::new(),
+        deny: patterns(APP_DENY),
+      },
+      installer: EnvRules {
+        mode: EnvMode::Allowlist,
+        allow: patterns(BASE_ALLOW),
+        deny: patterns(APP_DENY),
+      },
+    }
+  }
+}
+
+static ACTIVE: RwLock<Option<EnvPolicy>> = RwLock::new(None);
+
+fn active() -> EnvPolicy {
+  ACTIVE
+    .read()
+    .expect("env policy lock poisoned")
+    .clone()
+    .unwrap_or_default()
+}
+
+fn matches(pattern: &str, name: &str) -> bool {
+  match pattern.strip_suffix('*') {
+    Some(prefix) => name.to_ascii_uppercase().starts_with(&prefix.to_ascii_uppercase()),
+    None => pattern.eq_ignore_ascii_case(name),
+  }
+}
+
+impl EnvRules {
+  fn permits(&self, name: &str) -> bool {
+    if self.deny.iter().any(|p| matches(p, name)) {
+      return false;
+    }
+    match self.mode {
+      EnvMode::Denylist => true,
+      EnvMode::Allowlist => self.allow.iter().any(|p| matches(p, name)),
+    }
+  }
+}
+
+/// The subset of OpenWork's environment a child process may see.
+pub fn filtered_env(target: EnvTarget) -> Vec<(String, String)> {
+  let policy = active();
+  let rules = match target {
+    EnvTarget::Engine => &policy.engine,
+    EnvTarget::Installer => &policy.installer,
+  };
+  env::vars().filter(|(name, _)| rules.permits(name)).collect()
+}
+
+/// Replaces the inherited environment of `command`. Call before any explicit
+/// `.env(...)` so those survive.
+pub fn apply(command: &mut Command, target: EnvTarget) {
+  command.env_clear().envs(filtered_env(target));
+}
+
+pub fn init(app: &AppHandle) {
+  let policy = read_state::<EnvPolicy>(app, ENV_POLICY_FILE).unwrap_or_else(|e| {
+    tracing::error!(error = %e, "failed to load env policy; using defaults");
+    EnvPolicy::default()
+  });
+  *ACTIVE.write().expect("env policy lock poisoned") = Some(policy);
+}
+
+#[tauri::command]
+pub fn env_policy_get() -> EnvPolicy {
+  active()
+}
+
+#[tauri::command]
+pub fn env_policy_set(app: AppHandle, policy: EnvPolicy) -> Result<EnvPolicy, OpenWorkError> {
+  write_state(&app, ENV_POLICY_FILE, &policy)?;
+  *ACTIVE.write().expect("env policy lock poisoned") = Some(policy.clone());
+  Ok(policy)
+}
+
+/// Restores the built-in policy.
+#[tauri::command]
+pub fn env_policy_reset(app: AppHandle) -> Result<EnvPolicy, OpenWorkError> {
+  env_policy_set(app, EnvPolicy::default())
+}
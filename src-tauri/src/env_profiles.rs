@@ -0,0 +1,448 @@
+//! Named sets of environment variables per project ("staging",
+//! "prod-readonly"), so the agent works against the environment the user
+//! picked rather than whatever the shell happened to export.
+//!
+//! Plain values are stored in `env-profiles.json`; values marked secret go to
+//! the system keychain (Keychain, Credential Manager, Secret Service) and only
+//! their names are stored. The selected profile is read on each engine start
+//! and layered after the project's `.env` selection; a secret that can't be
+//! read fails the start instead of running without it. Which profile an
+//! engine got is reported as `EngineInfo.active_env_profile`.
+
+use std::{
+  collections::{BTreeMap, BTreeSet, HashMap},
+  sync::RwLock,
+};
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{
+  dotenv,
+  error::{ErrorCode, OpenWorkError},
+  redact::{self, REDACTED},
+  store::{read_state, write_state},
+};
+
+pub const ENV_PROFILES_FILE: &str = "env-profiles.json";
+
+/// Keychain service the secrets are stored under.
+const KEYCHAIN_SERVICE: &str = "OpenWork environment profiles";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvProfile {
+  pub name: String,
+  #[serde(default)]
+  pub description: Option<String>,
+  /// Values stored in the state file.
+  #[serde(default)]
+  pub vars: BTreeMap<String, String>,
+  /// Names whose values are in the keychain.
+  #[serde(default)]
+  pub secret_keys: BTreeSet<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProjectEnvProfiles {
+  pub profiles: Vec<EnvProfile>,
+  /// Used by the next engine start; `None` adds no variables.
+  pub selected: Option<String>,
+}
+
+/// Profiles keyed by project directory.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct EnvProfileSettings {
+  projects: HashMap<String, ProjectEnvProfiles>,
+}
+
+/// A profile as sent by the UI: secret values travel once and are never
+/// sent back.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvProfileInput {
+  pub name: String,
+  #[serde(default)]
+  pub description: Option<String>,
+  #[serde(default)]
+  pub vars: BTreeMap<String, String>,
+  /// `None` keeps the value already in the keychain.
+  #[serde(default)]
+  pub secrets: BTreeMap<String, Option<String>>,
+}
+
+/// The variables of the profile an engine starts with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedProfile {
+  pub name: String,
+  pub vars: Vec<(String, String)>,
+}
+
+static ACTIVE: RwLock<Option<EnvProfileSettings>> = RwLock::new(None);
+
+fn active() -> EnvProfileSettings {
+  ACTIVE
+    .read()
+    .expect("env profiles lock poisoned")
+    .clone()
+    .unwrap_or_default()
+}
+
+fn project(project_dir: &str) -> ProjectEnvProfiles {
+  active().projects.get(project_dir).cloned().unwrap_or_default()
+}
+
+fn validate_name(name: &str) -> Result<String, OpenWorkError> {
+  let name = name.trim();
+  let valid = !name.is_empty()
+    && name.len() <= 64
+    && name
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+  if !valid {
+    return Err(OpenWorkError::invalid_argument(format!(
+      "Invalid profile name: {name}"
+    )));
+  }
+  Ok(name.to_string())
+}
+
+/// Checks `input` against the profile it replaces, if any, and returns the
+/// profile to store.
+fn validate(input: &EnvProfileInput, existing: Option<&EnvProfile>) -> Result<EnvProfile, OpenWorkError> {
+  let name = validate_name(&input.name)?;
+  for key in input.vars.keys().chain(input.secrets.keys()) {
+    if !dotenv::is_valid_key(key) {
+      return Err(OpenWorkError::invalid_argument(format!(
+        "Invalid variable name: {key}"
+      )));
+    }
+  }
+  if let Some(key) = input.vars.keys().find(|key| input.secrets.contains_key(*key)) {
+    return Err(OpenWorkError::invalid_argument(format!(
+      "{key} can't be both a plain and a secret variable"
+    )));
+  }
+  for (key, value) in &input.secrets {
+    let stored = existing.is_some_and(|profile| profile.secret_keys.contains(key));
+    if value.is_none() && !stored {
+      return Err(OpenWorkError::invalid_argument(format!(
+        "A value is required for {key}"
+      )));
+    }
+  }
+  Ok(EnvProfile {
+    name,
+    description: input
+      .description
+      .as_deref()
+      .map(str::trim)
+      .filter(|text| !text.is_empty())
+      .map(str::to_string),
+    vars: input.vars.clone(),
+    secret_keys: input.secrets.keys().cloned().collect(),
+  })
+}
+
+fn keychain_entry(project_dir: &str, profile: &str, key: &str) -> Result<keyring::Entry, OpenWorkError> {
+  keyring::Entry::new(KEYCHAIN_SERVICE, &format!("{project_dir}:{profile}:{key}"))
+    .map_err(|e| OpenWorkError::new(ErrorCode::Internal, format!("The keychain is unavailable: {e}")))
+}
+
+fn keychain_get(project_dir: &str, profile: &str, key: &str) -> Result<String, OpenWorkError> {
+  keychain_entry(project_dir, profile, key)?
+    .get_password()
+    .map_err(|e| match e {
+      keyring::Error::NoEntry => OpenWorkError::new(
+        ErrorCode::NotFound,
+        format!("{key} of environment profile {profile} is missing from the keychain"),
+      ),
+      e => OpenWorkError::new(
+        ErrorCode::Internal,
+        format!("Failed to read {key} of environment profile {profile} from the keychain: {e}"),
+      ),
+    })
+}
+
+fn keychain_set(project_dir: &str, profile: &str, key: &str, value: &str) -> Result<(), OpenWorkError> {
+  keychain_entry(project_dir, profile, key)?
+    .set_password(value)
+    .map_err(|e| {
+      OpenWorkError::new(
+        ErrorCode::Internal,
+        format!("Failed to store {key} of environment profile {profile} in the keychain: {e}"),
+      )
+    })
+}
+
+/// Best effort: a leftover entry is harmless, as nothing refers to it.
+fn keychain_delete(project_dir: &str, profile: &str, key: &str) {
+  let deleted = keychain_entry(project_dir, profile, key).and_then(|entry| match entry.delete_credential() {
+    Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+    Err(e) => Err(OpenWorkError::new(ErrorCode::Internal, e.to_string())),
+  });
+  if let Err(e) = deleted {
+    tracing::warn!(profile, key, error = %e, "failed to delete env profile secret");
+  }
+}
+
+/// The variables of `profile`, with secrets read through `secret`.
+fn resolve_with(
+  profile: &EnvProfile,
+  mut secret: impl FnMut(&str) -> Result<String, OpenWorkError>,
+) -> Result<ResolvedProfile, OpenWorkError> {
+  let mut vars: Vec<(String, String)> = profile
+    .vars
+    .iter()
+    .map(|(key, value)| (key.clone(), value.clone()))
+    .collect();
+  for key in &profile.secret_keys {
+    vars.push((key.clone(), secret(key)?));
+  }
+  vars.sort();
+  Ok(ResolvedProfile {
+    name: profile.name.clone(),
+    vars,
+  })
+}
+
+fn selected(project_dir: &str) -> Option<EnvProfile> {
+  let project = project(project_dir);
+  let name = project.selected.as_ref()?;
+  project.profiles.into_iter().find(|profile| &profile.name == name)
+}
+
+/// The selected profile of `project_dir` with its secrets, which are
+/// registered for redaction.
+pub fn resolve(project_dir: &str) -> Result<Option<ResolvedProfile>, OpenWorkError> {
+  let Some(profile) = selected(project_dir) else {
+    return Ok(None);
+  };
+  let resolved = resolve_with(&profile, |key| {
+    let value = keychain_get(project_dir, &profile.name, key)?;
+    redact::register_secret(&value);
+    Ok(value)
+  })?;
+  tracing::info!(profile = %resolved.name, vars = resolved.vars.len(), "resolved env profile");
+  Ok(Some(resolved))
+}
+
+/// Like `resolve`, with secrets replaced by a placeholder rather than read.
+pub fn resolve_masked(project_dir: &str) -> Option<ResolvedProfile> {
+  let profile = selected(project_dir)?;
+  resolve_with(&profile, |_| Ok(REDACTED.to_string())).ok()
+}
+
+fn update(
+  app: &AppHandle,
+  project_dir: &str,
+  change: impl FnOnce(&mut ProjectEnvProfiles) -> Result<(), OpenWorkError>,
+) -> Result<ProjectEnvProfiles, OpenWorkError> {
+  let mut settings = active();
+  let project = settings.projects.entry(project_dir.to_string()).or_default();
+  change(project)?;
+  let result = project.clone();
+  if result.profiles.is_empty() && result.selected.is_none() {
+    settings.projects.remove(project_dir);
+  }
+  write_state(app, ENV_PROFILES_FILE, &settings)?;
+  *ACTIVE.write().expect("env profiles lock poisoned") = Some(settings);
+  Ok(result)
+}
+
+/// Selects `name` for the next engine start of `project_dir`; `None` clears
+/// the selection.
+pub fn select(
+  app: &AppHandle,
+  project_dir: &str,
+  name: Option<String>,
+) -> Result<ProjectEnvProfiles, OpenWorkError> {
+  update(app, project_dir, |project| {
+    if let Some(name) = &name {
+      if !project.profiles.iter().any(|profile| &profile.name == name) {
+        return Err(OpenWorkError::new(
+          ErrorCode::NotFound,
+          format!("Unknown environment profile: {name}"),
+        ));
+      }
+    }
+    project.selected = name;
+    Ok(())
+  })
+}
+
+pub fn init(app: &AppHandle) {
+  let settings = read_state::<EnvProfileSettings>(app, ENV_PROFILES_FILE).unwrap_or_else(|e| {
+    tracing::error!(error = %e, "failed to load env profiles");
+    EnvProfileSettings::default()
+  });
+  *ACTIVE.write().expect("env profiles lock poisoned") = Some(settings);
+}
+
+#[tauri::command]
+pub fn env_profiles_get(project_dir: String) -> Result<ProjectEnvProfiles, OpenWorkError> {
+  let project_dir = crate::require_project_dir(&project_dir)?;
+  Ok(project(&project_dir))
+}
+
+/// Creates or replaces the profile named `profile.name`. Secrets are stored
+/// in the keychain before the profile is saved; those it no longer lists
+/// are removed from the keychain afterwards.
+#[tauri::command]
+pub fn env_profile_save(
+  app: AppHandle,
+  project_dir: String,
+  profile: EnvProfileInput,
+) -> Result<ProjectEnvProfiles, OpenWorkError> {
+  let project_dir = crate::require_project_dir(&project_dir)?;
+  let current = project(&project_dir);
+  let existing = current
+    .profiles
+    .iter()
+    .find(|existing| existing.name == profile.name.trim());
+  let saved = validate(&profile, existing)?;
+
+  for (key, value) in &profile.secrets {
+    if let Some(value) = value {
+      keychain_set(&project_dir, &saved.name, key, value)?;
+    }
+  }
+  let dropped: Vec<String> = existing
+    .map(|existing| {
+      existing
+        .secret_keys
+        .difference(&saved.secret_keys)
+        .cloned()
+        .collect()
+    })
+    .unwrap_or_default();
+
+  let name = saved.name.clone();
+  let result = update(&app, &project_dir, |project| {
+    match project
+      .profiles
+      .iter_mut()
+      .find(|existing| existing.name == saved.name)
+    {
+      Some(existing) => *existing = saved,
+      None => project.profiles.push(saved),
+    }
+    Ok(())
+  })?;
+  for key in dropped {
+    keychain_delete(&project_dir, &name, &key);
+  }
+  Ok(result)
+}
+
+#[tauri::command]
+pub fn env_profile_delete(
+  app: AppHandle,
+  project_dir: String,
+  name: String,
+) -> Result<ProjectEnvProfiles, OpenWorkError> {
+  let project_dir = crate::require_project_dir(&project_dir)?;
+  let mut removed = None;
+  let result = update(&app, &project_dir, |project| {
+    let index = project
+      .profiles
+      .iter()
+      .position(|profile| profile.name == name)
+      .ok_or_else(|| {
+        OpenWorkError::new(
+          ErrorCode::NotFound,
+          format!("Unknown environment profile: {name}"),
+        )
+      })?;
+    removed = Some(project.profiles.remove(index));
+    if project.selected.as_deref() == Some(name.as_str()) {
+      project.selected = None;
+    }
+    Ok(())
+  })?;
+  for key in removed.iter().flat_map(|profile| &profile.secret_keys) {
+    keychain_delete(&project_dir, &name, key);
+  }
+  Ok(result)
+}
+
+/// Selects the profile for the next engine start of `project_dir`; `name:
+/// None` starts engines without one.
+#[tauri::command]
+pub fn env_profile_select(
+  app: AppHandle,
+  project_dir: String,
+  name: Option<String>,
+) -> Result<ProjectEnvProfiles, OpenWorkError> {
+  let project_dir = crate::require_project_dir(&project_dir)?;
+  select(&app, &project_dir, name)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn input(name: &str, vars: &[(&str, &str)], secrets: &[(&str, Option<&str>)]) -> EnvProfileInput {
+    EnvProfileInput {
+      name: name.to_string(),
+      description: None,
+      vars: vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+      secrets: secrets
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.map(str::to_string)))
+        .collect(),
+    }
+  }
+
+  #[test]
+  fn validates_profiles() {
+    let profile = validate(
+      &input(
+        " staging ",
+        &[("API_URL", "https://staging.example.com")],
+        &[("API_KEY", Some("k"))],
+      ),
+      None,
+    )
+    .unwrap();
+    assert_eq!(profile.name, "staging");
+    assert_eq!(profile.secret_keys, BTreeSet::from(["API_KEY".to_string()]));
+
+    assert!(validate(&input("prod readonly", &[], &[]), None).is_err());
+    assert!(validate(&input("prod", &[("1KEY", "x")], &[]), None).is_err());
+    assert!(validate(&input("prod", &[("KEY", "x")], &[("KEY", Some("y"))]), None).is_err());
+    // Keeping a secret's value only works for one that's already stored.
+    assert!(validate(&input("staging", &[], &[("API_KEY", None)]), None).is_err());
+    assert!(validate(&input("staging", &[], &[("API_KEY", None)]), Some(&profile)).is_ok());
+  }
+
+  #[test]
+  fn resolves_plain_and_secret_values() {
+    let profile = validate(
+      &input(
+        "staging",
+        &[("API_URL", "https://staging.example.com")],
+        &[("API_KEY", Some("k"))],
+      ),
+      None,
+    )
+    .unwrap();
+    let resolved = resolve_with(&profile, |key| Ok(format!("secret-{key}"))).unwrap();
+    assert_eq!(
+      resolved.vars,
+      [
+        ("API_KEY".to_string(), "secret-API_KEY".to_string()),
+        ("API_URL".to_string(), "https://staging.example.com".to_string()),
+      ]
+    );
+    let missing = resolve_with(&profile, |key| {
+      Err(OpenWorkError::new(
+        ErrorCode::NotFound,
+        format!("{key} is missing"),
+      ))
+    });
+    assert!(missing.is_err());
+  }
+}
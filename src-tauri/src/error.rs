@@ -0,0 +1,171 @@
+//! The error type every command returns.
+//!
+//! The frontend receives `{ code, message, details?, retryable }` and branches
+//! on `code`, which is stable; `message` is for display only. Internal helpers
+//! may still produce plain `String`s; they convert to `INTERNAL` errors with
+//! `?`, while the places that know what went wrong (path validation, engine
+//! lifecycle, consent) build a specific code. The control server sends the
+//! same shape to its clients, which read it back.
+//!
+//! Outside English, `message` is the catalog text for the code in the chosen
+//! locale and `originalMessage` carries the English text (see `i18n`).
+
+use std::fmt;
+
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use serde_json::Value;
+
+use crate::i18n::{self, Locale};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+  /// The opencode CLI could not be located.
+  EngineNotFound,
+  EngineNotRunning,
+  EngineStartFailed,
+  /// The engine process exists but did not answer.
+  EngineUnreachable,
+  /// The engine answered with an error status.
+  EngineRequestFailed,
+  PortInUse,
+  ConfigInvalid,
+  InvalidArgument,
+  NotFound,
+  AlreadyExists,
+  /// A folder outside the approved roots; `details.path` can be approved.
+  NeedsApproval,
+  /// A destructive action awaiting the native dialog; `details.token` feeds `confirm_request`.
+  PendingConfirmation,
+  ConfirmationInvalid,
+  Cancelled,
+  BudgetExceeded,
+  /// An external program (git, opkg, ...) is not installed.
+  ToolNotFound,
+  /// Not enough free disk space; `details` has `requiredBytes` and `availableBytes`.
+  InsufficientSpace,
+  /// No internet connection; see `network`.
+  Offline,
+  Io,
+  Network,
+  Internal,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenWorkError {
+  pub code: ErrorCode,
+  /// As raised, in English; see `localized` for what's sent.
+  pub message: String,
+  #[serde(default)]
+  pub details: Option<Value>,
+  /// Whether the same call may succeed if simply tried again.
+  #[serde(default)]
+  pub retryable: bool,
+}
+
+impl OpenWorkError {
+  pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+    Self {
+      code,
+      message: message.into(),
+      details: None,
+      retryable: false,
+    }
+  }
+
+  pub fn invalid_argument(message: impl Into<String>) -> Self {
+    Self::new(ErrorCode::InvalidArgument, message)
+  }
+
+  pub fn with_details(mut self, details: Value) -> Self {
+    self.details = Some(details);
+    self
+  }
+
+  pub fn retryable(mut self) -> Self {
+    self.retryable = true;
+    self
+  }
+
+  /// The message to show in `locale`, and the English one it replaces.
+  fn localized(&self, locale: Locale) -> (&str, Option<&str>) {
+    match i18n::error_message(self.code, locale) {
+      Some(text) => (text, Some(&self.message)),
+      None => (&self.message, None),
+    }
+  }
+}
+
+impl Serialize for OpenWorkError {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let (message, original) = self.localized(i18n::current());
+    let mut fields = serializer.serialize_struct("OpenWorkError", 5)?;
+    fields.serialize_field("code", &self.code)?;
+    fields.serialize_field("message", message)?;
+    match original {
+      Some(original) => fields.serialize_field("originalMessage", original)?,
+      None => fields.skip_field("originalMessage")?,
+    }
+    match &self.details {
+      Some(details) => fields.serialize_field("details", details)?,
+      None => fields.skip_field("details")?,
+    }
+    fields.serialize_field("retryable", &self.retryable)?;
+    fields.end()
+  }
+}
+
+impl fmt::Display for OpenWorkError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.message)
+  }
+}
+
+impl std::error::Error for OpenWorkError {}
+
+impl From<String> for OpenWorkError {
+  fn from(message: String) -> Self {
+    Self::new(ErrorCode::Internal, message)
+  }
+}
+
+impl From<&str> for OpenWorkError {
+  fn from(message: &str) -> Self {
+    Self::new(ErrorCode::Internal, message)
+  }
+}
+
+/// Lets `String`-returning helpers call typed validators with `?`.
+impl From<OpenWorkError> for String {
+  fn from(error: OpenWorkError) -> Self {
+    error.message
+  }
+}
+
+impl From<reqwest::Error> for OpenWorkError {
+  fn from(error: reqwest::Error) -> Self {
+    let transient = error.is_connect() || error.is_timeout();
+    let error = Self::new(ErrorCode::Network, error.to_string());
+    if transient {
+      error.retryable()
+    } else {
+      error
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn localizes_by_code_and_keeps_the_original() {
+    let error = OpenWorkError::new(ErrorCode::NotFound, "No snapshot abc");
+    assert_eq!(error.localized(Locale::En), ("No snapshot abc", None));
+    assert_eq!(
+      error.localized(Locale::De),
+      ("Nicht gefunden.", Some("No snapshot abc"))
+    );
+  }
+}
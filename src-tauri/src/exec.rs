@@ -0,0 +1,87 @@
+//! Builds `Command`s for every external program the app runs.
+//!
+//! On Windows, a console program started from a GUI app opens its own console
+//! window, so commands are created with `CREATE_NO_WINDOW`. Bare names are also
+//! looked up on PATH as `.exe`, `.cmd` and `.bat`: std only finds `.exe`, while
+//! npm installs tools such as `npx`, `pnpm` and `opencode` as `.cmd` wrappers.
+//! std runs those through `cmd.exe` and escapes their arguments for it, and
+//! refuses arguments it can't pass through safely.
+//!
+//! Commands are started with `spawn`/`output` from here rather than the
+//! `Command` methods, so a Flatpak build can run them on the host.
+
+use std::{
+  ffi::OsStr,
+  io,
+  process::{Child, Command, Output, Stdio},
+};
+
+use crate::app_sandbox;
+
+#[cfg(windows)]
+const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+/// A `Command` for `program` that doesn't flash a console window.
+pub fn command(program: impl AsRef<OsStr>) -> Command {
+  #[cfg(windows)]
+  {
+    use std::os::windows::process::CommandExt;
+
+    let mut command = Command::new(resolve_program(program.as_ref()));
+    command.creation_flags(CREATE_NO_WINDOW);
+    command
+  }
+  #[cfg(not(windows))]
+  Command::new(program)
+}
+
+/// Full path of a bare program name found on PATH under a Windows executable
+/// extension; anything else is returned as given.
+#[cfg(windows)]
+fn resolve_program(program: &OsStr) -> std::path::PathBuf {
+  use std::path::Path;
+
+  let path = Path::new(program);
+  let bare = path.extension().is_none() && path.components().count() == 1;
+  let Some(name) = program.to_str().filter(|_| bare) else {
+    return path.to_path_buf();
+  };
+  ["exe", "cmd", "bat"]
+    .iter()
+    .find_map(|ext| crate::resolve_in_path(&format!("{name}.{ext}")))
+    .unwrap_or_else(|| path.to_path_buf())
+}
+
+/// Starts `command`. Under Flatpak it runs on the host with stdin closed and
+/// stdout/stderr piped, which is how every caller sets it up.
+pub fn spawn(command: &mut Command) -> io::Result<Child> {
+  match app_sandbox::host_command(command) {
+    Some(mut host) => host
+      .stdin(Stdio::null())
+      .stdout(Stdio::piped())
+      .stderr(Stdio::piped())
+      .spawn(),
+    None => command.spawn(),
+  }
+}
+
+/// Like `spawn`, but keeps stdin piped for programs the app talks to over
+/// stdin/stdout, such as MCP servers.
+pub fn spawn_piped(command: &mut Command) -> io::Result<Child> {
+  match app_sandbox::host_command(command) {
+    Some(mut host) => host
+      .stdin(Stdio::piped())
+      .stdout(Stdio::piped())
+      .stderr(Stdio::piped())
+      .spawn(),
+    None => command.spawn(),
+  }
+}
+
+/// Runs `command` to completion, capturing its output; see `spawn`.
+pub fn output(command: &mut Command) -> io::Result<Output> {
+  match app_sandbox::host_command(command) {
+    Some(mut host) => host.stdin(Stdio::null()).output(),
+    None => command.output(),
+  }
+}
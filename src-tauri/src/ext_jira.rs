@@ -0,0 +1,217 @@
+//! Built-in Jira extension (feature `ext-jira`).
+//!
+//! Reads Jira Cloud issues with an API token taken from `JIRA_BASE_URL`,
+//! `JIRA_EMAIL` and `JIRA_API_TOKEN`, so a project's `.env` or the shell
+//! profile can point it at a site.
+
+use std::{env, time::Duration};
+
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{
+  download,
+  error::{ErrorCode, OpenWorkError},
+  extensions::{Capability, Extension, ExtensionContext, ExtensionManifest, MethodSpec},
+  redact,
+};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+const FIELDS: &str = "summary,status,assignee,issuetype,priority,updated";
+const MAX_RESULTS: u32 = 100;
+
+pub struct Jira;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IssueParams {
+  key: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchParams {
+  jql: String,
+  max_results: Option<u32>,
+}
+
+struct Site {
+  base: Url,
+  email: String,
+  token: String,
+}
+
+fn site() -> Result<Site, OpenWorkError> {
+  let var = |name: &str| {
+    env::var(name)
+      .ok()
+      .map(|value| value.trim().to_string())
+      .filter(|value| !value.is_empty())
+      .ok_or_else(|| OpenWorkError::new(ErrorCode::ConfigInvalid, format!("{name} isn't set")))
+  };
+  let base = var("JIRA_BASE_URL")?;
+  let base = Url::parse(&format!("{}/", base.trim_end_matches('/')))
+    .map_err(|e| OpenWorkError::new(ErrorCode::ConfigInvalid, format!("JIRA_BASE_URL is invalid: {e}")))?;
+  Ok(Site {
+    base,
+    email: var("JIRA_EMAIL")?,
+    token: var("JIRA_API_TOKEN")?,
+  })
+}
+
+fn get(site: &Site, path: &str, query: &[(&str, String)]) -> Result<Value, OpenWorkError> {
+  redact::register_secret(&site.token);
+  let url = site
+    .base
+    .join(path)
+    .map_err(|e| OpenWorkError::invalid_argument(format!("Invalid Jira path: {e}")))?;
+  let client = download::client().map_err(|e| OpenWorkError::new(ErrorCode::Network, e))?;
+  let response = client
+    .get(url)
+    .query(query)
+    .basic_auth(&site.email, Some(&site.token))
+    .header("Accept", "application/json")
+    .timeout(REQUEST_TIMEOUT)
+    .send()
+    .map_err(|e| {
+      OpenWorkError::new(
+        ErrorCode::Network,
+        redact::redact(&format!("Couldn't reach Jira: {e}")),
+      )
+      .retryable()
+    })?;
+  let status = response.status();
+  if status.as_u16() == 404 {
+    return Err(OpenWorkError::new(ErrorCode::NotFound, "Jira couldn't find that"));
+  }
+  if !status.is_success() {
+    return Err(OpenWorkError::new(
+      ErrorCode::Network,
+      format!("Jira answered {status}"),
+    ));
+  }
+  response
+    .json()
+    .map_err(|e| OpenWorkError::new(ErrorCode::Network, format!("Jira sent an unreadable answer: {e}")))
+}
+
+/// The fields worth showing, flattened from Jira's issue JSON.
+fn summary(site: &Site, issue: &Value) -> Value {
+  let fields = &issue["fields"];
+  let key = issue["key"].as_str().unwrap_or_default();
+  json!({
+    "key": key,
+    "url": site.base.join(&format!("browse/{key}")).map(String::from).ok(),
+    "summary": fields["summary"],
+    "status": fields["status"]["name"],
+    "type": fields["issuetype"]["name"],
+    "priority": fields["priority"]["name"],
+    "assignee": fields["assignee"]["displayName"],
+    "updated": fields["updated"],
+  })
+}
+
+fn valid_key(key: &str) -> bool {
+  let mut parts = key.splitn(2, '-');
+  let project = parts.next().unwrap_or_default();
+  let number = parts.next().unwrap_or_default();
+  !project.is_empty()
+    && project.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    && !number.is_empty()
+    && number.chars().all(|c| c.is_ascii_digit())
+}
+
+fn params<T: serde::de::DeserializeOwned>(params: Value) -> Result<T, OpenWorkError> {
+  serde_json::from_value(params).map_err(|e| OpenWorkError::invalid_argument(format!("Invalid params: {e}")))
+}
+
+impl Extension for Jira {
+  fn manifest(&self) -> ExtensionManifest {
+    let method = |name: &str, description: &str| MethodSpec {
+      name: name.to_string(),
+      description: description.to_string(),
+      capabilities: vec![Capability::Network],
+    };
+    ExtensionManifest {
+      id: "jira".to_string(),
+      name: "Jira".to_string(),
+      version: env!("CARGO_PKG_VERSION").to_string(),
+      description: "Look up Jira Cloud issues.".to_string(),
+      capabilities: vec![Capability::Network],
+      methods: vec![
+        method("issue.get", "One issue by key, e.g. {\"key\": \"OPS-12\"}."),
+        method(
+          "issue.search",
+          "Issues matching {\"jql\": ..., \"maxResults\": 20}.",
+        ),
+      ],
+    }
+  }
+
+  fn call(
+    &self,
+    context: &ExtensionContext,
+    method: &str,
+    params_value: Value,
+  ) -> Result<Value, OpenWorkError> {
+    context.require(Capability::Network)?;
+    let site = site()?;
+    match method {
+      "issue.get" => {
+        let IssueParams { key } = params(params_value)?;
+        let key = key.trim().to_ascii_uppercase();
+        if !valid_key(&key) {
+          return Err(OpenWorkError::invalid_argument(format!(
+            "{key} isn't a Jira issue key"
+          )));
+        }
+        let issue = get(
+          &site,
+          &format!("rest/api/3/issue/{key}"),
+          &[("fields", FIELDS.to_string())],
+        )?;
+        Ok(summary(&site, &issue))
+      }
+      "issue.search" => {
+        let SearchParams { jql, max_results } = params(params_value)?;
+        let max_results = max_results.unwrap_or(20).clamp(1, MAX_RESULTS);
+        let found = get(
+          &site,
+          "rest/api/3/search/jql",
+          &[
+            ("jql", jql),
+            ("maxResults", max_results.to_string()),
+            ("fields", FIELDS.to_string()),
+          ],
+        )?;
+        let issues: Vec<Value> = found["issues"]
+          .as_array()
+          .into_iter()
+          .flatten()
+          .map(|issue| summary(&site, issue))
+          .collect();
+        Ok(json!({ "issues": issues }))
+      }
+      other => Err(OpenWorkError::new(
+        ErrorCode::NotFound,
+        format!("jira has no method {other}"),
+      )),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn checks_issue_keys() {
+    assert!(valid_key("OPS-12"));
+    assert!(valid_key("AB2-1"));
+    assert!(!valid_key("OPS"));
+    assert!(!valid_key("OPS-"));
+    assert!(!valid_key("../OPS-1"));
+    assert!(!valid_key("OPS-1/comment"));
+  }
+}
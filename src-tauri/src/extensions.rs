@@ -0,0 +1,466 @@
+//! Extensions: command providers that live outside the core commands.
+//!
+//! An extension implements [`Extension`]: a manifest naming its methods and
+//! the capabilities each one needs, and `call` to run them. Built-in
+//! extensions are compiled in behind Cargo features (`ext-jira`) and
+//! registered at startup; loading WASM plugins from disk is meant to plug into
+//! the same registry later.
+//!
+//! Capabilities are granted per extension by the user, with confirmation, and
+//! stored in `extensions.json`. `extension_call` refuses a method whose
+//! capabilities aren't all granted with `NEEDS_APPROVAL`, listing the missing
+//! ones in `details`; the context an extension runs with only exposes what
+//! was granted.
+
+use std::{
+  collections::{BTreeMap, BTreeSet},
+  path::PathBuf,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, State};
+
+use crate::{
+  consent::ConsentManager,
+  error::{ErrorCode, OpenWorkError},
+  store::{read_state, write_state},
+};
+
+pub const EXTENSIONS_FILE: &str = "extensions.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum Capability {
+  /// Outgoing HTTP requests.
+  Network,
+  /// Reading files in the project passed to the call.
+  ProjectRead,
+  /// Changing files in the project passed to the call.
+  ProjectWrite,
+  /// Starting other programs.
+  RunProcess,
+  /// Talking to the project's running engine.
+  Engine,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MethodSpec {
+  pub name: String,
+  pub description: String,
+  pub capabilities: Vec<Capability>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtensionManifest {
+  /// Lowercase letters, digits and `-`; unique across extensions.
+  pub id: String,
+  pub name: String,
+  pub version: String,
+  pub description: String,
+  /// Everything any method may need.
+  pub capabilities: Vec<Capability>,
+  pub methods: Vec<MethodSpec>,
+}
+
+impl ExtensionManifest {
+  fn method(&self, name: &str) -> Option<&MethodSpec> {
+    self.methods.iter().find(|method| method.name == name)
+  }
+}
+
+/// What an extension's method runs with. Only the built-in extensions read
+/// it, so it is unused in builds without them.
+#[cfg_attr(not(feature = "ext-jira"), allow(dead_code))]
+pub struct ExtensionContext<'a> {
+  pub app: &'a AppHandle,
+  project_dir: Option<PathBuf>,
+  granted: BTreeSet<Capability>,
+}
+
+#[cfg_attr(not(feature = "ext-jira"), allow(dead_code))]
+impl ExtensionContext<'_> {
+  pub fn require(&self, capability: Capability) -> Result<(), OpenWorkError> {
+    if self.granted.contains(&capability) {
+      Ok(())
+    } else {
+      Err(OpenWorkError::new(
+        ErrorCode::NeedsApproval,
+        format!("This extension hasn't been allowed {capability:?}"),
+      ))
+    }
+  }
+
+  /// The project the call was made for, when `ProjectRead` is granted.
+  pub fn project_dir(&self) -> Result<&PathBuf, OpenWorkError> {
+    self.require(Capability::ProjectRead)?;
+    self
+      .project_dir
+      .as_ref()
+      .ok_or_else(|| OpenWorkError::invalid_argument("This method needs a projectDir"))
+  }
+}
+
+pub trait Extension: Send + Sync {
+  #[cfg_attr(not(feature = "ext-jira"), allow(dead_code))]
+  fn manifest(&self) -> ExtensionManifest;
+
+  /// Runs `method`, which the manifest lists and whose capabilities were
+  /// checked.
+  fn call(&self, context: &ExtensionContext, method: &str, params: Value) -> Result<Value, OpenWorkError>;
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct ExtensionSettings {
+  grants: BTreeMap<String, BTreeSet<Capability>>,
+  disabled: BTreeSet<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtensionInfo {
+  pub manifest: ExtensionManifest,
+  pub enabled: bool,
+  pub granted: Vec<Capability>,
+  /// Capabilities the manifest asks for that aren't granted.
+  pub missing: Vec<Capability>,
+}
+
+struct Registered {
+  manifest: ExtensionManifest,
+  extension: Box<dyn Extension>,
+}
+
+/// The extensions loaded at startup.
+pub struct ExtensionRegistry {
+  extensions: Vec<Registered>,
+}
+
+impl Default for ExtensionRegistry {
+  /// The built-in extensions compiled into this build.
+  fn default() -> Self {
+    #[cfg_attr(not(feature = "ext-jira"), allow(unused_mut))]
+    let mut registry = Self {
+      extensions: Vec::new(),
+    };
+    #[cfg(feature = "ext-jira")]
+    registry.register(Box::new(crate::ext_jira::Jira));
+    registry
+  }
+}
+
+#[cfg_attr(not(feature = "ext-jira"), allow(dead_code))]
+fn valid_id(id: &str) -> bool {
+  !id.is_empty()
+    && id.len() <= 64
+    && id
+      .chars()
+      .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// Problems with `manifest` that keep it from being registered next to
+/// `existing`.
+#[cfg_attr(not(feature = "ext-jira"), allow(dead_code))]
+fn manifest_problem(manifest: &ExtensionManifest, existing: &[Registered]) -> Option<String> {
+  if !valid_id(&manifest.id) {
+    return Some(format!("invalid extension id {:?}", manifest.id));
+  }
+  if existing.iter().any(|other| other.manifest.id == manifest.id) {
+    return Some(format!("extension {} is already registered", manifest.id));
+  }
+  let mut names = BTreeSet::new();
+  for method in &manifest.methods {
+    if !names.insert(method.name.as_str()) {
+      return Some(format!("{} declares {} twice", manifest.id, method.name));
+    }
+    if let Some(capability) = method
+      .capabilities
+      .iter()
+      .find(|capability| !manifest.capabilities.contains(capability))
+    {
+      return Some(format!(
+        "{}'s {} needs {capability:?}, which the manifest doesn't declare",
+        manifest.id, method.name
+      ));
+    }
+  }
+  None
+}
+
+impl ExtensionRegistry {
+  /// Adds `extension` unless its manifest is invalid or its id taken, which
+  /// is logged.
+  #[cfg_attr(not(feature = "ext-jira"), allow(dead_code))]
+  pub fn register(&mut self, extension: Box<dyn Extension>) {
+    let manifest = extension.manifest();
+    if let Some(problem) = manifest_problem(&manifest, &self.extensions) {
+      tracing::error!(problem = %problem, "extension not registered");
+      return;
+    }
+    tracing::info!(id = %manifest.id, version = %manifest.version, "extension registered");
+    self.extensions.push(Registered { manifest, extension });
+  }
+
+  fn find(&self, id: &str) -> Result<&Registered, OpenWorkError> {
+    self
+      .extensions
+      .iter()
+      .find(|registered| registered.manifest.id == id)
+      .ok_or_else(|| OpenWorkError::new(ErrorCode::NotFound, format!("No extension {id}")))
+  }
+}
+
+fn settings(app: &AppHandle) -> Result<ExtensionSettings, OpenWorkError> {
+  read_state(app, EXTENSIONS_FILE)
+}
+
+fn info(registered: &Registered, settings: &ExtensionSettings) -> ExtensionInfo {
+  let manifest = registered.manifest.clone();
+  let granted: Vec<Capability> = settings
+    .grants
+    .get(&manifest.id)
+    .into_iter()
+    .flatten()
+    .copied()
+    .collect();
+  let missing = manifest
+    .capabilities
+    .iter()
+    .filter(|capability| !granted.contains(capability))
+    .copied()
+    .collect();
+  ExtensionInfo {
+    enabled: !settings.disabled.contains(&manifest.id),
+    granted,
+    missing,
+    manifest,
+  }
+}
+
+#[tauri::command]
+pub fn extensions_list(
+  app: AppHandle,
+  registry: State<ExtensionRegistry>,
+) -> Result<Vec<ExtensionInfo>, OpenWorkError> {
+  let settings = settings(&app)?;
+  Ok(
+    registry
+      .extensions
+      .iter()
+      .map(|registered| info(registered, &settings))
+      .collect(),
+  )
+}
+
+/// Sets the capabilities `id` is allowed. Granting ones it didn't have
+/// needs confirmation; revoking doesn't.
+#[tauri::command]
+pub fn extension_grant(
+  app: AppHandle,
+  registry: State<ExtensionRegistry>,
+  consent: State<ConsentManager>,
+  id: String,
+  capabilities: Vec<Capability>,
+  confirmation_id: Option<String>,
+) -> Result<ExtensionInfo, OpenWorkError> {
+  let registered = registry.find(&id)?;
+  if let Some(capability) = capabilities
+    .iter()
+    .find(|capability| !registered.manifest.capabilities.contains(capability))
+  {
+    return Err(OpenWorkError::invalid_argument(format!(
+      "{id} doesn't ask for {capability:?}"
+    )));
+  }
+  let mut settings = settings(&app)?;
+  let requested: BTreeSet<Capability> = capabilities.into_iter().collect();
+  let current = settings.grants.get(&id).cloned().unwrap_or_default();
+  let added: Vec<String> = requested
+    .difference(&current)
+    .map(|capability| format!("{capability:?}"))
+    .collect();
+  if !added.is_empty() {
+    consent.require(
+      "extension_grant",
+      &id,
+      &format!(
+        "Allow the {} extension: {}?",
+        registered.manifest.name,
+        added.join(", ")
+      ),
+      confirmation_id.as_deref(),
+    )?;
+  }
+  if requested.is_empty() {
+    settings.grants.remove(&id);
+  } else {
+    settings.grants.insert(id.clone(), requested);
+  }
+  write_state(&app, EXTENSIONS_FILE, &settings)?;
+  tracing::info!(id = %id, "extension capabilities changed");
+  Ok(info(registered, &settings))
+}
+
+#[tauri::command]
+pub fn extension_set_enabled(
+  app: AppHandle,
+  registry: State<ExtensionRegistry>,
+  id: String,
+  enabled: bool,
+) -> Result<ExtensionInfo, OpenWorkError> {
+  let registered = registry.find(&id)?;
+  let mut settings = settings(&app)?;
+  if enabled {
+    settings.disabled.remove(&id);
+  } else {
+    settings.disabled.insert(id);
+  }
+  write_state(&app, EXTENSIONS_FILE, &settings)?;
+  Ok(info(registered, &settings))
+}
+
+/// Runs one of an extension's methods. `project_dir` is passed on to
+/// methods that work on a project.
+#[tauri::command]
+pub fn extension_call(
+  app: AppHandle,
+  registry: State<ExtensionRegistry>,
+  id: String,
+  method: String,
+  params: Option<Value>,
+  project_dir: Option<String>,
+) -> Result<Value, OpenWorkError> {
+  let registered = registry.find(&id)?;
+  let settings = settings(&app)?;
+  if settings.disabled.contains(&id) {
+    return Err(OpenWorkError::new(
+      ErrorCode::NotFound,
+      format!("The {id} extension is turned off"),
+    ));
+  }
+  let spec = registered
+    .manifest
+    .method(&method)
+    .ok_or_else(|| OpenWorkError::new(ErrorCode::NotFound, format!("{id} has no method {method}")))?;
+  let granted = settings.grants.get(&id).cloned().unwrap_or_default();
+  let missing: Vec<Capability> = spec
+    .capabilities
+    .iter()
+    .filter(|capability| !granted.contains(capability))
+    .copied()
+    .collect();
+  if !missing.is_empty() {
+    return Err(
+      OpenWorkError::new(
+        ErrorCode::NeedsApproval,
+        format!(
+          "{} needs more permissions to run {method}",
+          registered.manifest.name
+        ),
+      )
+      .with_details(json!({ "extension": id, "capabilities": missing })),
+    );
+  }
+  let project_dir = project_dir
+    .map(|dir| crate::require_project_dir(&dir).map(PathBuf::from))
+    .transpose()?;
+
+  let context = ExtensionContext {
+    app: &app,
+    project_dir,
+    // Only what this method declared, so a method can't reach for more.
+    granted: spec.capabilities.iter().copied().collect(),
+  };
+  tracing::debug!(extension = %id, method = %method, "extension call");
+  crate::telemetry::record("feature.extension_call");
+  registered
+    .extension
+    .call(&context, &method, params.unwrap_or(Value::Null))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  struct Fake(ExtensionManifest);
+
+  impl Extension for Fake {
+    fn manifest(&self) -> ExtensionManifest {
+      self.0.clone()
+    }
+
+    fn call(&self, _: &ExtensionContext, _: &str, _: Value) -> Result<Value, OpenWorkError> {
+      Ok(Value::Null)
+    }
+  }
+
+  fn manifest(id: &str, methods: &[(&str, Capability)]) -> ExtensionManifest {
+    ExtensionManifest {
+      id: id.to_string(),
+      name: id.to_string(),
+      version: "1.0.0".to_string(),
+      description: String::new(),
+      capabilities: vec![Capability::Network],
+      methods: methods
+        .iter()
+        .map(|(name, capability)| MethodSpec {
+          name: name.to_string(),
+          description: String::new(),
+          capabilities: vec![*capability],
+        })
+        .collect(),
+    }
+  }
+
+  #[test]
+  fn registers_valid_manifests_only() {
+    let mut registry = ExtensionRegistry {
+      extensions: Vec::new(),
+    };
+    registry.register(Box::new(Fake(manifest(
+      "jira",
+      &[("search", Capability::Network)],
+    ))));
+    // Taken id, bad id, duplicate method, undeclared capability.
+    registry.register(Box::new(Fake(manifest("jira", &[]))));
+    registry.register(Box::new(Fake(manifest("Jira Cloud", &[]))));
+    registry.register(Box::new(Fake(manifest(
+      "dup",
+      &[("a", Capability::Network), ("a", Capability::Network)],
+    ))));
+    registry.register(Box::new(Fake(manifest(
+      "greedy",
+      &[("run", Capability::RunProcess)],
+    ))));
+    let ids: Vec<&str> = registry
+      .extensions
+      .iter()
+      .map(|registered| registered.manifest.id.as_str())
+      .collect();
+    assert_eq!(ids, ["jira"]);
+  }
+
+  #[test]
+  fn reports_missing_capabilities() {
+    let registered = Registered {
+      manifest: manifest("jira", &[("search", Capability::Network)]),
+      extension: Box::new(Fake(manifest("jira", &[]))),
+    };
+    let mut settings = ExtensionSettings::default();
+    let listed = info(&registered, &settings);
+    assert!(listed.enabled);
+    assert_eq!(listed.missing, [Capability::Network]);
+
+    settings
+      .grants
+      .insert("jira".to_string(), [Capability::Network].into());
+    settings.disabled.insert("jira".to_string());
+    let listed = info(&registered, &settings);
+    assert!(!listed.enabled);
+    assert_eq!(listed.granted, [Capability::Network]);
+    assert!(listed.missing.is_empty());
+  }
+}
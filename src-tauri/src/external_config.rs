@@ -0,0 +1,751 @@
+//! Importing settings from other AI coding tools.
+//!
+//! Claude Code, Cursor and Continue keep MCP servers, instruction files and
+//! ignore patterns in their own files. `import_external_config` maps them onto
+//! opencode: MCP servers into the `mcp` section of the project's (or, for the
+//! tool's user-level servers, the global) `opencode.json`, instructions into
+//! `AGENTS.md`, and ignore patterns into `watcher.ignore`. Without `apply` it
+//! only returns the changes as diffs; existing entries are never replaced.
+
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use tauri::State;
+
+use crate::{
+  consent::ConsentManager,
+  error::OpenWorkError,
+  jsonc, paths,
+};
+
+const AGENTS_FILE: &str = "AGENTS.md";
+/// Marks a section of `AGENTS.md` as imported from `source`, so importing
+/// again doesn't repeat it.
+const IMPORT_MARKER: &str = "<!-- Imported from";
+/// Files with more lines than this are diffed as a whole replacement.
+const MAX_DIFF_LINES: usize = 1000;
+const DIFF_CONTEXT: usize = 3;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ExternalTool {
+  ClaudeCode,
+  Cursor,
+  Continue,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportKind {
+  McpServer,
+  Instructions,
+  Ignore,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedItem {
+  pub kind: ImportKind,
+  pub name: String,
+  /// The file it was read from.
+  pub source: String,
+  /// Why it was left out, e.g. an MCP server of the same name already exists.
+  pub skipped: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FileChange {
+  pub path: String,
+  pub exists: bool,
+  /// Unified diff from the current content.
+  pub diff: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExternalImport {
+  pub tool: ExternalTool,
+  pub items: Vec<ImportedItem>,
+  pub changes: Vec<FileChange>,
+  pub applied: bool,
+  pub notes: Vec<String>,
+}
+
+struct FoundServer {
+  name: String,
+  /// The opencode `mcp` entry.
+  entry: Value,
+  source: String,
+  /// From the tool's user settings rather than the project's.
+  user_level: bool,
+}
+
+/// What was found in the tool's files.
+#[derive(Default)]
+struct Found {
+  servers: Vec<FoundServer>,
+  /// `(source, text)`.
+  instructions: Vec<(String, String)>,
+  /// `(pattern, source)`.
+  ignore: Vec<(String, String)>,
+  notes: Vec<String>,
+}
+
+struct Planned {
+  path: PathBuf,
+  before: Option<String>,
+  after: String,
+}
+
+fn read(path: &Path) -> Option<String> {
+  fs::read_to_string(paths::extended(path)).ok()
+}
+
+fn read_json(path: &Path, notes: &mut Vec<String>) -> Option<Value> {
+  let text = read(path)?;
+  match serde_json::from_str(&jsonc::strip(&text)) {
+    Ok(value) => Some(value),
+    Err(e) => {
+      notes.push(format!("Skipped {}: {e}", path.display()));
+      None
+    }
+  }
+}
+
+/// `${VAR}` and `${VAR:-default}` as opencode's `{env:VAR}`.
+fn env_refs(value: &str) -> String {
+  let mut out = String::with_capacity(value.len());
+  let mut rest = value;
+  while let Some(start) = rest.find("${") {
+    let Some(end) = rest[start..].find('}') else {
+      break;
+    };
+    let inner = &rest[start + 2..start + end];
+    let name = inner.split(":-").next().unwrap_or(inner);
+    out.push_str(&rest[..start]);
+    out.push_str(&format!("{{env:{name}}}"));
+    rest = &rest[start + end + 1..];
+  }
+  out.push_str(rest);
+  out
+}
+
+fn string_map(value: Option<&Value>) -> Map<String, Value> {
+  value
+    .and_then(Value::as_object)
+    .map(|vars| {
+      vars
+        .iter()
+        .filter_map(|(key, value)| Some((key.clone(), json!(env_refs(value.as_str()?)))))
+        .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// An opencode `mcp` entry for a server in the `mcpServers` shape shared by
+/// Claude Code, Cursor and Continue: `command` + `args` + `env`, or `url` (+
+/// `headers`) for a remote server. Continue's older configs nest the first
+/// under `transport`.
+fn mcp_entry(server: &Value) -> Option<Value> {
+  let server = server
+    .get("transport")
+    .filter(|t| t.is_object())
+    .unwrap_or(server);
+  if let Some(command) = server.get("command").and_then(Value::as_str) {
+    let mut argv = vec![json!(env_refs(command))];
+    argv.extend(
+      server
+        .get("args")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .map(|arg| json!(env_refs(arg))),
+    );
+    let mut entry = json!({ "type": "local", "command": argv, "enabled": true });
+    let environment = string_map(server.get("env"));
+    if !environment.is_empty() {
+      entry["environment"] = Value::Object(environment);
+    }
+    return Some(entry);
+  }
+  let url = server.get("url").and_then(Value::as_str)?;
+  let mut entry = json!({ "type": "remote", "url": env_refs(url), "enabled": true });
+  let headers = string_map(server.get("headers"));
+  if !headers.is_empty() {
+    entry["headers"] = Value::Object(headers);
+  }
+  Some(entry)
+}
+
+/// Servers from an `mcpServers` object (keyed by name) or array (with `name`).
+fn collect_servers(found: &mut Found, servers: Option<&Value>, source: &Path, user_level: bool) {
+  let source = source.to_string_lossy().to_string();
+  let named: Vec<(String, &Value)> = match servers {
+    Some(Value::Object(map)) => map.iter().map(|(name, server)| (name.clone(), server)).collect(),
+    Some(Value::Array(list)) => list
+      .iter()
+      .enumerate()
+      .map(|(index, server)| {
+        let name = server.get("name").and_then(Value::as_str).map(str::to_string);
+        (name.unwrap_or_else(|| format!("server-{}", index + 1)), server)
+      })
+      .collect(),
+    _ => return,
+  };
+  for (name, server) in named {
+    match mcp_entry(server) {
+      Some(entry) => found.servers.push(FoundServer {
+        name,
+        entry,
+        source: source.clone(),
+        user_level,
+      }),
+      None => found.notes.push(format!(
+        "Skipped MCP server {name} in {source}: no command or url"
+      )),
+    }
+  }
+}
+
+fn collect_instructions(found: &mut Found, path: &Path) {
+  if let Some(text) = read(path).map(|text| strip_frontmatter(&text).trim().to_string()) {
+    if !text.is_empty() {
+      found
+        .instructions
+        .push((path.to_string_lossy().to_string(), text));
+    }
+  }
+}
+
+/// Files in `dir` with one of `extensions`, sorted by name.
+fn rule_files(dir: &Path, extensions: &[&str]) -> Vec<PathBuf> {
+  let mut files: Vec<PathBuf> = fs::read_dir(paths::extended(dir))
+    .into_iter()
+    .flatten()
+    .filter_map(Result::ok)
+    .map(|entry| dir.join(entry.file_name()))
+    .filter(|path| {
+      path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| extensions.contains(&ext))
+    })
+    .collect();
+  files.sort();
+  files
+}
+
+/// Cursor's `.mdc` rules and Continue's rules start with YAML frontmatter.
+fn strip_frontmatter(text: &str) -> &str {
+  let Some(rest) = text.strip_prefix("---") else {
+    return text;
+  };
+  match rest.find("\n---") {
+    Some(end) => rest[end + 4..].trim_start_matches(['\r', '\n']),
+    None => text,
+  }
+}
+
+/// Patterns from a gitignore-style file as `watcher.ignore` globs.
+fn collect_ignore(found: &mut Found, path: &Path) {
+  let Some(text) = read(path) else {
+    return;
+  };
+  let source = path.to_string_lossy().to_string();
+  for line in text.lines().map(str::trim) {
+    if line.is_empty() || line.starts_with('#') {
+      continue;
+    }
+    if line.starts_with('!') {
+      found
+        .notes
+        .push(format!("Skipped negated pattern {line} in {source}"));
+      continue;
+    }
+    found.ignore.push((ignore_glob(line), source.clone()));
+  }
+}
+
+/// `dir/` matches everything below the directory.
+fn ignore_glob(pattern: &str) -> String {
+  match pattern.strip_suffix('/') {
+    Some(dir) => format!("{dir}/**"),
+    None => pattern.to_string(),
+  }
+}
+
+/// The path of a Claude Code `Read(...)` deny rule.
+fn denied_read(rule: &str) -> Option<String> {
+  let path = rule.strip_prefix("Read(")?.strip_suffix(')')?;
+  let path = path.trim_start_matches("./");
+  (!path.is_empty()).then(|| path.to_string())
+}
+
+fn find(project_dir: &Path, tool: ExternalTool) -> Found {
+  let mut found = Found::default();
+  let home = crate::home_dir();
+  match tool {
+    ExternalTool::ClaudeCode => {
+      let project_mcp = project_dir.join(".mcp.json");
+      if let Some(config) = read_json(&project_mcp, &mut found.notes) {
+        collect_servers(&mut found, config.get("mcpServers"), &project_mcp, false);
+      }
+      if let Some(user_file) = home.as_ref().map(|home| home.join(".claude.json")) {
+        if let Some(config) = read_json(&user_file, &mut found.notes) {
+          collect_servers(&mut found, config.get("mcpServers"), &user_file, true);
+          let project = config
+            .get("projects")
+            .and_then(|projects| projects.get(project_dir.to_string_lossy().as_ref()));
+          collect_servers(
+            &mut found,
+            project.and_then(|project| project.get("mcpServers")),
+            &user_file,
+            false,
+          );
+        }
+      }
+      collect_instructions(&mut found, &project_dir.join("CLAUDE.md"));
+      collect_instructions(&mut found, &project_dir.join(".claude").join("CLAUDE.md"));
+      let settings = project_dir.join(".claude").join("settings.json");
+      if let Some(config) = read_json(&settings, &mut found.notes) {
+        let deny = config.pointer("/permissions/deny").and_then(Value::as_array);
+        for rule in deny.into_iter().flatten().filter_map(Value::as_str) {
+          if let Some(pattern) = denied_read(rule) {
+            found
+              .ignore
+              .push((pattern, settings.to_string_lossy().to_string()));
+          }
+        }
+      }
+    }
+    ExternalTool::Cursor => {
+      let project_mcp = project_dir.join(".cursor").join("mcp.json");
+      if let Some(config) = read_json(&project_mcp, &mut found.notes) {
+        collect_servers(&mut found, config.get("mcpServers"), &project_mcp, false);
+      }
+      if let Some(user_file) = home.as_ref().map(|home| home.join(".cursor").join("mcp.json")) {
+        if let Some(config) = read_json(&user_file, &mut found.notes) {
+          collect_servers(&mut found, config.get("mcpServers"), &user_file, true);
+        }
+      }
+      collect_instructions(&mut found, &project_dir.join(".cursorrules"));
+      for rule in rule_files(&project_dir.join(".cursor").join("rules"), &["mdc", "md"]) {
+        collect_instructions(&mut found, &rule);
+      }
+      collect_ignore(&mut found, &project_dir.join(".cursorignore"));
+    }
+    ExternalTool::Continue => {
+      if let Some(user_file) = home
+        .as_ref()
+        .map(|home| home.join(".continue").join("config.json"))
+      {
+        if let Some(config) = read_json(&user_file, &mut found.notes) {
+          collect_servers(&mut found, config.get("mcpServers"), &user_file, true);
+          let legacy = config.pointer("/experimental/modelContextProtocolServers");
+          collect_servers(&mut found, legacy, &user_file, true);
+        }
+      }
+      if home
+        .as_ref()
+        .is_some_and(|home| home.join(".continue").join("config.yaml").is_file())
+      {
+        found
+          .notes
+          .push("~/.continue/config.yaml is not read; only config.json is supported.".to_string());
+      }
+      let servers_dir = project_dir.join(".continue").join("mcpServers");
+      for file in rule_files(&servers_dir, &["json"]) {
+        if let Some(config) = read_json(&file, &mut found.notes) {
+          collect_servers(&mut found, config.get("mcpServers"), &file, false);
+        }
+      }
+      collect_instructions(&mut found, &project_dir.join(".continuerules"));
+      for rule in rule_files(&project_dir.join(".continue").join("rules"), &["md"]) {
+        collect_instructions(&mut found, &rule);
+      }
+      collect_ignore(&mut found, &project_dir.join(".continueignore"));
+    }
+  }
+  found
+}
+
+fn object<'a>(config: &'a mut Value, key: &str) -> &'a mut Map<String, Value> {
+  if !config.get(key).is_some_and(Value::is_object) {
+    config[key] = json!({});
+  }
+  config[key].as_object_mut().expect("just made an object")
+}
+
+/// Adds the servers and ignore patterns to `config`. Returns the items with
+/// servers that already exist marked skipped.
+fn merge_config(
+  config: &mut Value,
+  servers: &[&FoundServer],
+  ignore: &[(String, String)],
+) -> Vec<ImportedItem> {
+  let mut items = Vec::new();
+  let mcp = object(config, "mcp");
+  for server in servers {
+    let skipped = mcp
+      .contains_key(&server.name)
+      .then(|| "An MCP server with this name is already configured".to_string());
+    if skipped.is_none() {
+      mcp.insert(server.name.clone(), server.entry.clone());
+    }
+    items.push(ImportedItem {
+      kind: ImportKind::McpServer,
+      name: server.name.clone(),
+      source: server.source.clone(),
+      skipped,
+    });
+  }
+  if mcp.is_empty() {
+    config.as_object_mut().expect("config is an object").remove("mcp");
+  }
+
+  if !ignore.is_empty() {
+    let watcher = object(config, "watcher");
+    let patterns = watcher.entry("ignore").or_insert_with(|| json!([]));
+    if !patterns.is_array() {
+      *patterns = json!([]);
+    }
+    let patterns = patterns.as_array_mut().expect("just made an array");
+    for (pattern, source) in ignore {
+      let skipped = patterns
+        .iter()
+        .any(|existing| existing.as_str() == Some(pattern))
+        .then(|| "Already ignored".to_string());
+      if skipped.is_none() {
+        patterns.push(json!(pattern));
+      }
+      items.push(ImportedItem {
+        kind: ImportKind::Ignore,
+        name: pattern.clone(),
+        source: source.clone(),
+        skipped,
+      });
+    }
+  }
+  items
+}
+
+/// `AGENTS.md` with each instructions file appended under a marker, skipping
+/// sources imported before.
+fn merge_instructions(
+  agents: &str,
+  instructions: &[(String, String)],
+  project_dir: &Path,
+) -> (String, Vec<ImportedItem>) {
+  let mut out = agents.to_string();
+  let mut items = Vec::new();
+  for (source, text) in instructions {
+    let label = Path::new(source)
+      .strip_prefix(project_dir)
+      .map(|relative| relative.to_string_lossy().to_string())
+      .unwrap_or_else(|_| source.clone());
+    let marker = format!("{IMPORT_MARKER} {label} -->");
+    let skipped = out.contains(&marker).then(|| "Already imported".to_string());
+    if skipped.is_none() {
+      if !out.is_empty() {
+        out.push_str(if out.ends_with('\n') { "\n" } else { "\n\n" });
+      }
+      out.push_str(&format!("{marker}\n\n{text}\n"));
+    }
+    items.push(ImportedItem {
+      kind: ImportKind::Instructions,
+      name: label,
+      source: source.clone(),
+      skipped,
+    });
+  }
+  (out, items)
+}
+
+/// Indices of the lines `before` and `after` have in common, in order.
+fn common_lines(before: &[&str], after: &[&str]) -> Vec<(usize, usize)> {
+  let (n, m) = (before.len(), after.len());
+  // lengths[i][j]: longest common subsequence of before[i..] and after[j..].
+  let mut lengths = vec![vec![0u32; m + 1]; n + 1];
+  for i in (0..n).rev() {
+    for j in (0..m).rev() {
+      lengths[i][j] = if before[i] == after[j] {
+        lengths[i + 1][j + 1] + 1
+      } else {
+        lengths[i + 1][j].max(lengths[i][j + 1])
+      };
+    }
+  }
+  let (mut i, mut j, mut pairs) = (0, 0, Vec::new());
+  while i < n && j < m {
+    if before[i] == after[j] {
+      pairs.push((i, j));
+      i += 1;
+      j += 1;
+    } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+      i += 1;
+    } else {
+      j += 1;
+    }
+  }
+  pairs
+}
+
+/// A unified diff of `before` to `after` for `path`; empty when they're equal.
+pub fn unified_diff(path: &str, before: &str, after: &str) -> String {
+  if before == after {
+    return String::new();
+  }
+  let old: Vec<&str> = before.lines().collect();
+  let new: Vec<&str> = after.lines().collect();
+  let common = if old.len().max(new.len()) > MAX_DIFF_LINES {
+    Vec::new()
+  } else {
+    common_lines(&old, &new)
+  };
+
+  // Each line of the edit script: ' ', '-' or '+', with its old/new index.
+  let mut script: Vec<(char, usize, usize)> = Vec::new();
+  let (mut i, mut j) = (0, 0);
+  for &(ci, cj) in common.iter().chain([(old.len(), new.len())].iter()) {
+    script.extend((i..ci).map(|k| ('-', k, j)));
+    script.extend((j..cj).map(|k| ('+', ci, k)));
+    if ci < old.len() {
+      script.push((' ', ci, cj));
+    }
+    (i, j) = (ci + 1, cj + 1);
+  }
+
+  let mut out = format!(
+    "--- {}\n+++ {path}\n",
+    if before.is_empty() { "/dev/null" } else { path }
+  );
+  let changed: Vec<usize> = (0..script.len()).filter(|&k| script[k].0 != ' ').collect();
+  let mut k = 0;
+  while k < changed.len() {
+    let start = changed[k].saturating_sub(DIFF_CONTEXT);
+    let mut end = changed[k];
+    while k < changed.len() && changed[k] <= end + 2 * DIFF_CONTEXT {
+      end = changed[k];
+      k += 1;
+    }
+    let end = (end + DIFF_CONTEXT + 1).min(script.len());
+    let hunk = &script[start..end];
+    let old_count = hunk.iter().filter(|line| line.0 != '+').count();
+    let new_count = hunk.iter().filter(|line| line.0 != '-').count();
+    let (_, old_start, new_start) = hunk[0];
+    let position = |start: usize, count: usize| if count == 0 { start } else { start + 1 };
+    out.push_str(&format!(
+      "@@ -{},{old_count} +{},{new_count} @@\n",
+      position(old_start, old_count),
+      position(new_start, new_count)
+    ));
+    for &(op, oi, ni) in hunk {
+      let line = if op == '+' { new[ni] } else { old[oi] };
+      out.push_str(&format!("{op}{line}\n"));
+    }
+  }
+  out
+}
+
+fn plan(project_dir: &Path, found: &Found) -> Result<(Vec<Planned>, Vec<ImportedItem>), OpenWorkError> {
+  let mut planned = Vec::new();
+  let mut items = Vec::new();
+
+  let project_config = project_dir.join("opencode.json");
+  let global_config = crate::resolve_opencode_config_path("global", "")?;
+  let targets = [(&project_config, false), (&global_config, true)];
+  for (path, user_level) in targets {
+    let servers: Vec<_> = found
+      .servers
+      .iter()
+      .filter(|server| server.user_level == user_level)
+      .collect();
+    // Ignore patterns belong to the project.
+    let ignore = if user_level { &[][..] } else { &found.ignore[..] };
+    if servers.is_empty() && ignore.is_empty() {
+      continue;
+    }
+    let before = read(path);
+    let mut config = crate::parse_opencode_config(before.as_deref(), path)?;
+    items.extend(merge_config(&mut config, &servers, ignore));
+    let after = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())? + "\n";
+    planned.push(Planned {
+      path: path.clone(),
+      before,
+      after,
+    });
+  }
+
+  if !found.instructions.is_empty() {
+    let path = project_dir.join(AGENTS_FILE);
+    let before = read(&path);
+    let (after, imported) =
+      merge_instructions(before.as_deref().unwrap_or(""), &found.instructions, project_dir);
+    items.extend(imported);
+    planned.push(Planned { path, before, after });
+  }
+  planned.retain(|file| file.before.as_deref() != Some(file.after.as_str()));
+  Ok((planned, items))
+}
+
+/// Maps `tool`'s MCP servers, instructions and ignore patterns for the project
+/// onto opencode's files. Returns the changes as diffs; `apply` writes them,
+/// asking for confirmation when an existing file changes.
+#[tauri::command]
+pub fn import_external_config(
+  consent: State<ConsentManager>,
+  project_dir: String,
+  tool: ExternalTool,
+  apply: Option<bool>,
+  confirmation_id: Option<String>,
+) -> Result<ExternalImport, OpenWorkError> {
+  let project_dir = paths::allowed_dir(&project_dir, "projectDir")?;
+  let found = find(&project_dir, tool);
+  let (planned, items) = plan(&project_dir, &found)?;
+  let mut notes = found.notes;
+  if planned
+    .iter()
+    .any(|file| file.path.file_name().is_some_and(|name| name == "opencode.json"))
+  {
+    let jsonc = project_dir.join("opencode.jsonc");
+    if jsonc.is_file() {
+      notes.push(format!(
+        "{} also exists; opencode merges both files.",
+        jsonc.display()
+      ));
+    }
+  }
+
+  let changes = planned
+    .iter()
+    .map(|file| {
+      let path = file.path.to_string_lossy().to_string();
+      FileChange {
+        diff: unified_diff(&path, file.before.as_deref().unwrap_or(""), &file.after),
+        exists: file.before.is_some(),
+        path,
+      }
+    })
+    .collect();
+
+  let apply = apply.unwrap_or(false) && !planned.is_empty();
+  if apply {
+    if planned.iter().any(|file| file.before.is_some()) {
+      consent.require(
+        "import_external_config",
+        &project_dir.to_string_lossy(),
+        &format!(
+          "Update {} file(s) with settings imported from another tool?",
+          planned.len()
+        ),
+        confirmation_id.as_deref(),
+      )?;
+    }
+    for file in &planned {
+      crate::write_config_file(&file.path, &file.after)?;
+    }
+    crate::telemetry::record("feature.import_external_config");
+    tracing::info!(tool = ?tool, files = planned.len(), "imported external config");
+  }
+
+  Ok(ExternalImport {
+    tool,
+    items,
+    changes,
+    applied: apply,
+    notes,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn maps_mcp_servers() {
+    let local = json!({ "command": "npx", "args": ["-y", "server", "${HOME}/data"], "env": { "TOKEN": "${GH_TOKEN:-x}" } });
+    assert_eq!(
+      mcp_entry(&local),
+      Some(json!({
+        "type": "local",
+        "command": ["npx", "-y", "server", "{env:HOME}/data"],
+        "enabled": true,
+        "environment": { "TOKEN": "{env:GH_TOKEN}" },
+      }))
+    );
+    let remote = json!({ "type": "http", "url": "https://mcp.example.com", "headers": { "Authorization": "Bearer ${KEY}" } });
+    assert_eq!(
+      mcp_entry(&remote),
+      Some(json!({
+        "type": "remote",
+        "url": "https://mcp.example.com",
+        "enabled": true,
+        "headers": { "Authorization": "Bearer {env:KEY}" },
+      }))
+    );
+    let legacy = json!({ "transport": { "type": "stdio", "command": "uvx", "args": ["mcp-git"] } });
+    assert_eq!(mcp_entry(&legacy).unwrap()["command"], json!(["uvx", "mcp-git"]));
+    assert_eq!(mcp_entry(&json!({ "type": "stdio" })), None);
+  }
+
+  #[test]
+  fn reads_rules_and_patterns() {
+    assert_eq!(
+      strip_frontmatter("---\nalwaysApply: true\n---\n\nUse tabs."),
+      "Use tabs."
+    );
+    assert_eq!(strip_frontmatter("No frontmatter"), "No frontmatter");
+    assert_eq!(ignore_glob("dist/"), "dist/**");
+    assert_eq!(denied_read("Read(./secrets/**)").as_deref(), Some("secrets/**"));
+    assert_eq!(denied_read("Bash(rm:*)"), None);
+  }
+
+  #[test]
+  fn merges_without_replacing() {
+    let mut config = json!({ "mcp": { "git": { "type": "local", "command": ["git-mcp"] } } });
+    let server = |name: &str| FoundServer {
+      name: name.to_string(),
+      entry: json!({ "type": "local" }),
+      source: "a".to_string(),
+      user_level: false,
+    };
+    let (git, web) = (server("git"), server("web"));
+    let ignore = [("dist/**".to_string(), "b".to_string())];
+    let items = merge_config(&mut config, &[&git, &web], &ignore);
+    assert!(items[0].skipped.is_some() && items[1].skipped.is_none());
+    assert_eq!(config["mcp"]["git"]["command"], json!(["git-mcp"]));
+    assert_eq!(config["watcher"]["ignore"], json!(["dist/**"]));
+
+    let project = Path::new("/code/app");
+    let rules = [("/code/app/CLAUDE.md".to_string(), "Be brief.".to_string())];
+    let (agents, _) = merge_instructions("# Agents\n", &rules, project);
+    assert_eq!(
+      agents,
+      "# Agents\n\n<!-- Imported from CLAUDE.md -->\n\nBe brief.\n"
+    );
+    let (again, items) = merge_instructions(&agents, &rules, project);
+    assert_eq!(again, agents);
+    assert!(items[0].skipped.is_some());
+  }
+
+  #[test]
+  fn diffs_files() {
+    assert_eq!(unified_diff("a", "same\n", "same\n"), "");
+    assert_eq!(
+      unified_diff("AGENTS.md", "", "one\ntwo\n"),
+      "--- /dev/null\n+++ AGENTS.md\n@@ -0,0 +1,2 @@\n+one\n+two\n"
+    );
+    let before = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n";
+    let after = "1\n2\n3\n4\n5\nfive\n7\n8\n9\n10\n";
+    assert_eq!(
+      unified_diff("f", before, after),
+      "--- f\n+++ f\n@@ -3,7 +3,7 @@\n 3\n 4\n 5\n-6\n+five\n 7\n 8\n 9\n"
+    );
+  }
+}
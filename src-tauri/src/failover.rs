@@ -0,0 +1,307 @@
+//! A secondary engine endpoint per window (another machine's engine, or one
+//! attached from another OpenWork instance) that backend requests move to
+//! while the window's own engine is unhealthy.
+//!
+//! Once a secondary is registered, a monitor checks both engines'
+//! `/global/health` every `CHECK_INTERVAL`. After `FAILOVER_AFTER` failed
+//! checks of the primary (or as soon as it isn't running) a healthy secondary
+//! takes over, and the primary takes back as soon as it answers again; with
+//! both down nothing changes. Every `EngineClient` for the window follows the
+//! serving engine, and the event relay reconnects when it changes. Switches
+//! and changes in health are emitted as `engine://backend` so the UI can
+//! follow too.
+
+use std::{
+  collections::HashMap,
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex, MutexGuard,
+  },
+  thread,
+  time::Duration,
+};
+
+use reqwest::Url;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State, WebviewWindow};
+
+use crate::{engine_client::EngineClient, error::OpenWorkError, redact, relay::EventRelay, EngineManager};
+
+pub const BACKEND_EVENT: &str = "engine://backend";
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(2);
+/// Failed checks of the primary before traffic moves, so one slow answer
+/// doesn't cause a switch.
+const FAILOVER_AFTER: u32 = 2;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Backend {
+  Primary,
+  Secondary,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BackendStatus {
+  pub window: String,
+  pub serving: Backend,
+  /// The engine requests go to; `None` when the primary isn't running and
+  /// there's no secondary.
+  pub base_url: Option<String>,
+  pub secondary_url: Option<String>,
+  pub primary_healthy: bool,
+  /// `None` until the first check, and without a secondary.
+  pub secondary_healthy: Option<bool>,
+}
+
+#[derive(Debug, Clone)]
+struct Secondary {
+  base_url: String,
+  auth_token: Option<String>,
+}
+
+#[derive(Debug)]
+struct WindowFailover {
+  secondary: Secondary,
+  serving: Backend,
+  primary_failures: u32,
+  primary_healthy: bool,
+  secondary_healthy: Option<bool>,
+  /// Identifies the monitor thread; a newer registration stops older ones.
+  generation: u64,
+}
+
+/// The secondary endpoints, kept on `EngineManager`.
+#[derive(Default)]
+pub struct EngineFailover {
+  windows: Mutex<HashMap<String, WindowFailover>>,
+  generation: AtomicU64,
+}
+
+impl EngineFailover {
+  fn windows(&self) -> MutexGuard<'_, HashMap<String, WindowFailover>> {
+    self.windows.lock().expect("failover mutex poisoned")
+  }
+
+  /// Forgets `window`'s secondary, which also stops its monitor.
+  pub fn remove(&self, window: &str) {
+    self.windows().remove(window);
+  }
+
+  /// The secondary's URL and password while it serves `window`.
+  pub fn serving_secondary(&self, window: &str) -> Option<(String, Option<String>)> {
+    let windows = self.windows();
+    let state = windows
+      .get(window)
+      .filter(|state| state.serving == Backend::Secondary)?;
+    Some((
+      state.secondary.base_url.clone(),
+      state.secondary.auth_token.clone(),
+    ))
+  }
+
+  pub fn serving(&self, window: &str) -> Backend {
+    self
+      .windows()
+      .get(window)
+      .map_or(Backend::Primary, |state| state.serving)
+  }
+}
+
+/// The backend to serve after a check.
+fn next_backend(
+  serving: Backend,
+  primary_failures: u32,
+  primary_healthy: bool,
+  secondary_healthy: bool,
+) -> Backend {
+  if primary_healthy {
+    Backend::Primary
+  } else if primary_failures >= FAILOVER_AFTER && secondary_healthy {
+    Backend::Secondary
+  } else {
+    serving
+  }
+}
+
+fn validate_url(raw: &str) -> Result<String, OpenWorkError> {
+  let url = Url::parse(raw.trim())
+    .map_err(|e| OpenWorkError::invalid_argument(format!("Invalid engine URL {raw}: {e}")))?;
+  if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+    return Err(OpenWorkError::invalid_argument(format!(
+      "Engine URL must be http:// or https://: {raw}"
+    )));
+  }
+  Ok(url.as_str().trim_end_matches('/').to_string())
+}
+
+fn status(manager: &EngineManager, window: &str) -> BackendStatus {
+  let primary = crate::engine_client::primary_engine(manager, window).ok();
+  let windows = manager.failover.windows();
+  let state = windows.get(window);
+  let serving = state.map_or(Backend::Primary, |state| state.serving);
+  let base_url = match (serving, state) {
+    (Backend::Secondary, Some(state)) => Some(state.secondary.base_url.clone()),
+    _ => primary.as_ref().map(|(base_url, _)| base_url.clone()),
+  };
+  BackendStatus {
+    window: window.to_string(),
+    serving,
+    base_url,
+    secondary_url: state.map(|state| state.secondary.base_url.clone()),
+    primary_healthy: state.map_or(primary.is_some(), |state| state.primary_healthy),
+    secondary_healthy: state.and_then(|state| state.secondary_healthy),
+  }
+}
+
+fn healthy(base_url: String, auth_token: Option<String>) -> bool {
+  EngineClient::new(base_url, auth_token).is_ok_and(|client| client.is_healthy())
+}
+
+/// Checks both engines of `window` until its registration is replaced or
+/// removed.
+fn monitor(app: AppHandle, window: String, generation: u64) {
+  loop {
+    let manager = app.state::<EngineManager>();
+    let Some(secondary) = manager
+      .failover
+      .windows()
+      .get(&window)
+      .filter(|state| state.generation == generation)
+      .map(|state| state.secondary.clone())
+    else {
+      return;
+    };
+
+    let primary = crate::engine_client::primary_engine(&manager, &window).ok();
+    // Not running counts as failed for good, not as one slow answer.
+    let running = primary.is_some();
+    let primary_healthy = primary.is_some_and(|(base_url, auth_token)| healthy(base_url, auth_token));
+    let secondary_healthy = healthy(secondary.base_url, secondary.auth_token);
+
+    let (switched, changed) = {
+      let mut windows = manager.failover.windows();
+      let Some(state) = windows
+        .get_mut(&window)
+        .filter(|state| state.generation == generation)
+      else {
+        return;
+      };
+      state.primary_failures = match (primary_healthy, running) {
+        (true, _) => 0,
+        (false, true) => state.primary_failures + 1,
+        (false, false) => FAILOVER_AFTER,
+      };
+      let health_changed =
+        state.primary_healthy != primary_healthy || state.secondary_healthy != Some(secondary_healthy);
+      state.primary_healthy = primary_healthy;
+      state.secondary_healthy = Some(secondary_healthy);
+      let next = next_backend(
+        state.serving,
+        state.primary_failures,
+        primary_healthy,
+        secondary_healthy,
+      );
+      let switched = next != state.serving;
+      state.serving = next;
+      if switched {
+        tracing::warn!(window = %window, serving = ?next, "engine backend switched");
+      }
+      (switched, switched || health_changed)
+    };
+    if switched {
+      // The relay would otherwise stay on the old engine until it sends something.
+      app.state::<EventRelay>().restart(&app, &window);
+    }
+    if changed {
+      let _ = app.emit(BACKEND_EVENT, status(&manager, &window));
+    }
+    thread::sleep(CHECK_INTERVAL);
+  }
+}
+
+/// Registers `base_url` as the secondary engine of this window, replacing
+/// any earlier one. `auth_token` is its basic-auth password, if it has one.
+#[tauri::command]
+pub fn engine_failover_set(
+  app: AppHandle,
+  window: WebviewWindow,
+  manager: State<EngineManager>,
+  base_url: String,
+  auth_token: Option<String>,
+) -> Result<BackendStatus, OpenWorkError> {
+  let base_url = validate_url(&base_url)?;
+  let auth_token = auth_token.filter(|token| !token.is_empty());
+  if let Some(token) = &auth_token {
+    redact::register_secret(token);
+  }
+  let label = window.label().to_string();
+  let generation = manager.failover.generation.fetch_add(1, Ordering::SeqCst) + 1;
+  manager.failover.windows().insert(
+    label.clone(),
+    WindowFailover {
+      secondary: Secondary { base_url, auth_token },
+      serving: Backend::Primary,
+      primary_failures: 0,
+      primary_healthy: true,
+      secondary_healthy: None,
+      generation,
+    },
+  );
+  tracing::info!(window = %label, "registered secondary engine");
+  let result = status(&manager, &label);
+  thread::spawn(move || monitor(app, label, generation));
+  Ok(result)
+}
+
+/// Removes this window's secondary engine; requests go to its own engine
+/// again.
+#[tauri::command]
+pub fn engine_failover_clear(
+  app: AppHandle,
+  window: WebviewWindow,
+  manager: State<EngineManager>,
+) -> BackendStatus {
+  let removed = manager.failover.windows().remove(window.label());
+  let result = status(&manager, window.label());
+  if removed.is_some_and(|state| state.serving == Backend::Secondary) {
+    app.state::<EventRelay>().restart(&app, window.label());
+    let _ = app.emit(BACKEND_EVENT, result.clone());
+  }
+  result
+}
+
+#[tauri::command]
+pub fn engine_failover_status(window: WebviewWindow, manager: State<EngineManager>) -> BackendStatus {
+  status(&manager, window.label())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn fails_over_after_repeated_failures() {
+    use Backend::*;
+    assert_eq!(next_backend(Primary, 1, false, true), Primary);
+    assert_eq!(next_backend(Primary, FAILOVER_AFTER, false, true), Secondary);
+    // With both down, traffic stays where it is.
+    assert_eq!(next_backend(Primary, FAILOVER_AFTER, false, false), Primary);
+    assert_eq!(
+      next_backend(Secondary, FAILOVER_AFTER + 3, false, false),
+      Secondary
+    );
+    assert_eq!(next_backend(Secondary, 0, true, true), Primary);
+  }
+
+  #[test]
+  fn validates_secondary_urls() {
+    assert_eq!(
+      validate_url(" https://engine.example.com:4096/ ").unwrap(),
+      "https://engine.example.com:4096"
+    );
+    assert!(validate_url("ftp://engine.example.com").is_err());
+    assert!(validate_url("engine.example.com").is_err());
+  }
+}
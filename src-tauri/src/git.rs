@@ -0,0 +1,154 @@
+use std::{
+  path::PathBuf,
+  process::{Command, Stdio},
+};
+
+use crate::{
+  error::{ErrorCode, OpenWorkError},
+  exec, require_project_dir, run_capture_optional, ExecResult,
+};
+
+pub fn git_command(project_dir: &str) -> Command {
+  let mut command = exec::command("git");
+  command
+    .current_dir(project_dir)
+    // Paths are validated as plain paths; don't let git reinterpret them as magic pathspecs.
+    .env("GIT_LITERAL_PATHSPECS", "1")
+    .stdin(Stdio::null())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped());
+  command
+}
+
+pub fn run_git_command(command: &mut Command) -> Result<ExecResult, OpenWorkError> {
+  run_capture_optional(command)?.ok_or_else(|| {
+    OpenWorkError::new(
+      ErrorCode::ToolNotFound,
+      "git not found. Install git and make sure it is on PATH.",
+    )
+  })
+}
+
+pub fn run_git(project_dir: &str, args: &[&str]) -> Result<ExecResult, OpenWorkError> {
+  let mut command = git_command(project_dir);
+  command.args(args);
+  run_git_command(&mut command)
+}
+
+pub fn ensure_work_tree(project_dir: &str) -> Result<(), OpenWorkError> {
+  let result = run_git(project_dir, &["rev-parse", "--is-inside-work-tree"])?;
+  if !result.ok || result.stdout.trim() != "true" {
+    return Err(OpenWorkError::invalid_argument(format!("Not a git repository: {project_dir}")));
+  }
+  Ok(())
+}
+
+/// `git symbolic-ref` fails when HEAD points at a commit instead of a branch.
+fn is_detached_head(project_dir: &str) -> Result<bool, OpenWorkError> {
+  let result = run_git(project_dir, &["symbolic-ref", "-q", "HEAD"])?;
+  Ok(!result.ok)
+}
+
+pub fn ensure_attached_head(project_dir: &str, force: bool) -> Result<(), OpenWorkError> {
+  if !force && is_detached_head(project_dir)? {
+    return Err(OpenWorkError::invalid_argument(
+      "HEAD is detached. Switch to a branch first, or pass force to continue anyway.",
+    ));
+  }
+  Ok(())
+}
+
+pub fn validate_branch_name(project_dir: &str, name: &str) -> Result<String, OpenWorkError> {
+  let name = crate::args::ref_name(name, "branch name")?;
+
+  let result = run_git(project_dir, &["check-ref-format", "--branch", &name])?;
+  if !result.ok {
+    return Err(OpenWorkError::invalid_argument(format!("Invalid branch name: {name}")));
+  }
+
+  Ok(name)
+}
+
+#[tauri::command]
+pub fn git_commit(
+  project_dir: String,
+  message: String,
+  paths: Vec<String>,
+  force: Option<bool>,
+) -> Result<ExecResult, OpenWorkError> {
+  let project_dir = require_project_dir(&project_dir)?;
+
+  let message = message.trim().to_string();
+  if message.is_empty() {
+    return Err(OpenWorkError::invalid_argument("commit message is required"));
+  }
+  crate::telemetry::record("feature.git_commit");
+
+  ensure_work_tree(&project_dir)?;
+  ensure_attached_head(&project_dir, force.unwrap_or(false))?;
+
+  let root = PathBuf::from(&project_dir);
+  let paths: Vec<String> = paths
+    .into_iter()
+    .map(|p| p.trim().to_string())
+    .filter(|p| !p.is_empty())
+    .collect();
+  for path in &paths {
+    crate::paths::resolve_within(&root, path)?;
+  }
+
+  // With no explicit paths we commit whatever is already staged.
+  if !paths.is_empty() {
+    let mut add = git_command(&project_dir);
+    add.arg("add").arg("--").args(&paths);
+    let result = run_git_command(&mut add)?;
+    if !result.ok {
+      return Ok(result);
+    }
+  }
+
+  let mut commit = git_command(&project_dir);
+  commit.arg("commit").arg("-m").arg(&message);
+  if !paths.is_empty() {
+    commit.arg("--").args(&paths);
+  }
+
+  run_git_command(&mut commit)
+}
+
+#[tauri::command]
+pub fn git_branch_create(
+  project_dir: String,
+  name: String,
+  checkout: bool,
+  force: Option<bool>,
+) -> Result<ExecResult, OpenWorkError> {
+  let project_dir = require_project_dir(&project_dir)?;
+  ensure_work_tree(&project_dir)?;
+
+  let name = validate_branch_name(&project_dir, &name)?;
+  ensure_attached_head(&project_dir, force.unwrap_or(false))?;
+
+  if checkout {
+    run_git(&project_dir, &["switch", "-c", &name])
+  } else {
+    run_git(&project_dir, &["branch", &name])
+  }
+}
+
+#[tauri::command]
+pub fn git_branch_switch(
+  project_dir: String,
+  name: String,
+  force: Option<bool>,
+) -> Result<ExecResult, OpenWorkError> {
+  let project_dir = require_project_dir(&project_dir)?;
+  ensure_work_tree(&project_dir)?;
+
+  let name = validate_branch_name(&project_dir, &name)?;
+
+  // Leaving a detached HEAD can orphan commits made there.
+  ensure_attached_head(&project_dir, force.unwrap_or(false))?;
+
+  run_git(&project_dir, &["switch", &name])
+}
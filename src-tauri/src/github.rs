@@ -0,0 +1,358 @@
+//! Pull requests through the GitHub CLI (`gh`).
+//!
+//! `github_create_pr` takes the project's changes from the current branch to
+//! a pull request: it moves them to a new branch when they sit on the base
+//! branch, commits uncommitted changes if asked, pushes, and runs
+//! `gh pr create`. Each step's output is streamed as `github://output` events.
+//! `gh` does the GitHub side with the user's own login (`gh auth login`).
+
+use std::{
+  io::{BufRead, BufReader, Read},
+  path::{Path, PathBuf},
+  process::{Command, Stdio},
+  thread,
+  time::Instant,
+};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::{
+  error::{ErrorCode, OpenWorkError},
+  exec,
+  git::{ensure_attached_head, ensure_work_tree, git_command, run_git, validate_branch_name},
+  redact, require_project_dir, resolve_in_path, task_indicator, ExecResult,
+};
+
+pub const GITHUB_OUTPUT_EVENT: &str = "github://output";
+
+/// Prefix of branches created for pull requests.
+const BRANCH_PREFIX: &str = "openwork/";
+const MAX_SLUG_LEN: usize = 48;
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GhStatus {
+  pub found: bool,
+  pub path: Option<String>,
+  pub version: Option<String>,
+  /// `gh auth status` succeeded; `None` when it couldn't be checked.
+  pub authenticated: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PullRequestResult {
+  pub url: String,
+  pub branch: String,
+  pub base: Option<String>,
+  /// Whether uncommitted changes were committed first.
+  pub committed: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct GithubOutput<'a> {
+  project_dir: &'a str,
+  step: &'static str,
+  stream: &'static str,
+  line: &'a str,
+}
+
+fn gh_path() -> Option<PathBuf> {
+  resolve_in_path(if cfg!(windows) { "gh.exe" } else { "gh" })
+}
+
+fn gh_command(program: &Path, project_dir: &str) -> Command {
+  let mut command = exec::command(program);
+  command
+    .current_dir(project_dir)
+    .env("GH_PROMPT_DISABLED", "1")
+    .env("GH_NO_UPDATE_NOTIFIER", "1")
+    .stdin(Stdio::null())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped());
+  command
+}
+
+/// `gh` for the engine doctor: where it is, its version and whether it's
+/// logged in, within `deadline`.
+pub fn detect(deadline: Instant) -> GhStatus {
+  let Some(path) = gh_path() else {
+    return GhStatus {
+      found: false,
+      path: None,
+      version: None,
+      authenticated: None,
+    };
+  };
+  let run = |args: &[&str]| {
+    let mut command = exec::command(&path);
+    command.args(args).env("GH_NO_UPDATE_NOTIFIER", "1");
+    crate::output_before(&mut command, deadline)
+  };
+  let version = run(&["--version"])
+    .filter(|output| output.status.success())
+    .and_then(|output| parse_version(&String::from_utf8_lossy(&output.stdout)));
+  let authenticated = run(&["auth", "status"]).map(|output| output.status.success());
+  GhStatus {
+    found: true,
+    path: Some(path.to_string_lossy().to_string()),
+    version,
+    authenticated,
+  }
+}
+
+/// `2.45.0` from `gh version 2.45.0 (2024-03-04)`.
+fn parse_version(output: &str) -> Option<String> {
+  let line = output.lines().next()?;
+  line
+    .strip_prefix("gh version ")
+    .and_then(|rest| rest.split_whitespace().next())
+    .map(str::to_string)
+}
+
+/// A branch name for a pull request titled `title`.
+fn branch_for_title(title: &str) -> String {
+  let mut slug = String::new();
+  for c in title.chars().flat_map(char::to_lowercase) {
+    if c.is_ascii_alphanumeric() {
+      slug.push(c);
+    } else if !slug.is_empty() && !slug.ends_with('-') {
+      slug.push('-');
+    }
+    if slug.len() >= MAX_SLUG_LEN {
+      break;
+    }
+  }
+  let slug = slug.trim_end_matches('-');
+  format!(
+    "{BRANCH_PREFIX}{}",
+    if slug.is_empty() { "changes" } else { slug }
+  )
+}
+
+/// The pull request URL in `gh pr create` output, which prints it last.
+fn pr_url(stdout: &str) -> Option<String> {
+  stdout
+    .lines()
+    .rev()
+    .map(str::trim)
+    .find(|line| line.starts_with("https://") && line.contains("/pull/"))
+    .map(str::to_string)
+}
+
+/// Runs `command` to completion, emitting each line of its output.
+fn run_streaming(
+  app: &AppHandle,
+  project_dir: &str,
+  step: &'static str,
+  command: &mut Command,
+) -> Result<ExecResult, OpenWorkError> {
+  let mut child = exec::spawn(command).map_err(|e| {
+    let code = if e.kind() == std::io::ErrorKind::NotFound {
+      ErrorCode::ToolNotFound
+    } else {
+      ErrorCode::Io
+    };
+    OpenWorkError::new(code, format!("Failed to run {step}: {e}"))
+  })?;
+  let forward = |stream: &'static str, reader: Box<dyn Read + Send>| {
+    let (app, project_dir) = (app.clone(), project_dir.to_string());
+    thread::spawn(move || {
+      let mut collected = String::new();
+      for line in BufReader::new(reader).lines().map_while(Result::ok) {
+        let line = redact::redact(&line);
+        let output = GithubOutput {
+          project_dir: &project_dir,
+          step,
+          stream,
+          line: &line,
+        };
+        let _ = app.emit(GITHUB_OUTPUT_EVENT, output);
+        collected.push_str(&line);
+        collected.push('\n');
+      }
+      collected
+    })
+  };
+  let stdout = child.stdout.take().map(|out| forward("stdout", Box::new(out)));
+  let stderr = child.stderr.take().map(|err| forward("stderr", Box::new(err)));
+  let status = child
+    .wait()
+    .map_err(|e| OpenWorkError::new(ErrorCode::Io, format!("Failed to run {step}: {e}")))?;
+  let collect = |reader: Option<thread::JoinHandle<String>>| {
+    reader.and_then(|handle| handle.join().ok()).unwrap_or_default()
+  };
+  Ok(ExecResult {
+    ok: status.success(),
+    status: status.code().unwrap_or(-1),
+    stdout: collect(stdout),
+    stderr: collect(stderr),
+  })
+}
+
+/// Fails with the step's stderr when it didn't succeed.
+fn check(step: &str, result: ExecResult) -> Result<ExecResult, OpenWorkError> {
+  if result.ok {
+    return Ok(result);
+  }
+  let detail = result.stderr.trim();
+  Err(
+    OpenWorkError::new(ErrorCode::Io, format!("{step} failed: {detail}"))
+      .with_details(serde_json::json!({ "step": step, "status": result.status })),
+  )
+}
+
+fn git_output(project_dir: &str, args: &[&str]) -> Result<String, OpenWorkError> {
+  let result = check(&format!("git {}", args[0]), run_git(project_dir, args)?)?;
+  Ok(result.stdout.trim().to_string())
+}
+
+/// The remote's default branch, from `origin/HEAD`.
+fn default_branch(project_dir: &str) -> Option<String> {
+  let result = run_git(
+    project_dir,
+    &["symbolic-ref", "--short", "refs/remotes/origin/HEAD"],
+  )
+  .ok()?;
+  result
+    .ok
+    .then(|| result.stdout.trim().trim_start_matches("origin/").to_string())
+    .filter(|name| !name.is_empty())
+}
+
+/// Pushes the project's changes and opens a pull request with `gh`. Changes on
+/// the base branch move to `branch` (or one named after the title) first, and
+/// uncommitted changes are refused unless `commit_all` commits them.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn github_create_pr(
+  app: AppHandle,
+  project_dir: String,
+  title: String,
+  body: String,
+  base: Option<String>,
+  branch: Option<String>,
+  draft: Option<bool>,
+  commit_all: Option<bool>,
+) -> Result<PullRequestResult, OpenWorkError> {
+  let project_dir = require_project_dir(&project_dir)?;
+  let title = title.trim().to_string();
+  if title.is_empty() {
+    return Err(OpenWorkError::invalid_argument("title is required"));
+  }
+  let Some(gh) = gh_path() else {
+    return Err(OpenWorkError::new(
+      ErrorCode::ToolNotFound,
+      "gh not found. Install the GitHub CLI (https://cli.github.com) and run gh auth login.",
+    ));
+  };
+  ensure_work_tree(&project_dir)?;
+  ensure_attached_head(&project_dir, false)?;
+  let base = base
+    .map(|base| validate_branch_name(&project_dir, &base))
+    .transpose()?
+    .or_else(|| default_branch(&project_dir));
+
+  let dirty = !git_output(&project_dir, &["status", "--porcelain"])?.is_empty();
+  if dirty && !commit_all.unwrap_or(false) {
+    return Err(OpenWorkError::invalid_argument(
+      "There are uncommitted changes. Commit them first, or pass commitAll to commit everything.",
+    ));
+  }
+
+  let _task = task_indicator::begin(&app, "github.pr");
+  crate::telemetry::record("feature.github_pr");
+
+  let current = git_output(&project_dir, &["rev-parse", "--abbrev-ref", "HEAD"])?;
+  let wanted = branch
+    .map(|name| validate_branch_name(&project_dir, &name))
+    .transpose()?;
+  let branch = match wanted {
+    Some(name) if name != current => {
+      check("git switch", run_git(&project_dir, &["switch", "-c", &name])?)?;
+      name
+    }
+    Some(name) => name,
+    None if base.as_deref() == Some(current.as_str()) => {
+      let name = validate_branch_name(&project_dir, &branch_for_title(&title))?;
+      check("git switch", run_git(&project_dir, &["switch", "-c", &name])?)?;
+      name
+    }
+    None => current,
+  };
+
+  if dirty {
+    check("git add", run_git(&project_dir, &["add", "-A"])?)?;
+    let mut commit = git_command(&project_dir);
+    commit.args(["commit", "-m", &title]);
+    check(
+      "git commit",
+      run_streaming(&app, &project_dir, "commit", &mut commit)?,
+    )?;
+  }
+
+  let mut push = git_command(&project_dir);
+  push
+    .env("GIT_TERMINAL_PROMPT", "0")
+    .args(["push", "--set-upstream", "origin", &branch]);
+  check("git push", run_streaming(&app, &project_dir, "push", &mut push)?)?;
+
+  let mut create = gh_command(&gh, &project_dir);
+  create.args([
+    "pr", "create", "--title", &title, "--body", &body, "--head", &branch,
+  ]);
+  if let Some(base) = &base {
+    create.args(["--base", base]);
+  }
+  if draft.unwrap_or(false) {
+    create.arg("--draft");
+  }
+  let created = check(
+    "gh pr create",
+    run_streaming(&app, &project_dir, "pr", &mut create)?,
+  )?;
+  let url = pr_url(&created.stdout).ok_or_else(|| {
+    OpenWorkError::new(ErrorCode::Io, "gh pr create did not print a pull request URL")
+      .with_details(serde_json::json!({ "stdout": created.stdout }))
+  })?;
+  tracing::info!(branch = %branch, url = %url, "pull request created");
+
+  Ok(PullRequestResult {
+    url,
+    branch,
+    base,
+    committed: dirty,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn names_branches_after_titles() {
+    assert_eq!(
+      branch_for_title("Fix login redirect (#42)"),
+      "openwork/fix-login-redirect-42"
+    );
+    assert_eq!(branch_for_title("   ¿¡!!"), "openwork/changes");
+    assert!(branch_for_title(&"long title ".repeat(20)).len() <= BRANCH_PREFIX.len() + MAX_SLUG_LEN);
+  }
+
+  #[test]
+  fn reads_gh_output() {
+    assert_eq!(
+      parse_version("gh version 2.45.0 (2024-03-04)\nhttps://github.com/cli/cli/releases/tag/v2.45.0\n")
+        .as_deref(),
+      Some("2.45.0")
+    );
+    let created = "\nCreating pull request for openwork/fix into main in acme/app\n\nhttps://github.com/acme/app/pull/7\n";
+    assert_eq!(
+      pr_url(created).as_deref(),
+      Some("https://github.com/acme/app/pull/7")
+    );
+    assert_eq!(pr_url("a pull request already exists"), None);
+  }
+}
@@ -0,0 +1,213 @@
+//! Keeps generated folders out of git by adding them to the project's root
+//! `.gitignore`.
+//!
+//! `gitignore_ensure` appends only the entries the file doesn't already cover,
+//! whether by the same line or a broader rule such as `node_modules/`; an
+//! entry the file explicitly re-includes (`!...`) is left alone. Called again
+//! with the same entries it changes nothing. Features that leave folders in a
+//! project call `ensure_generated`, which does the same for git work trees
+//! only and never fails the feature.
+
+use std::{fs, path::Path};
+
+use ignore::gitignore::GitignoreBuilder;
+use serde::Serialize;
+
+use crate::{dotenv, error::OpenWorkError, paths};
+
+/// Where opencode installs plugin dependencies, reinstalled from
+/// `.opencode/package.json`.
+pub const OPENCODE_DEPENDENCIES: &str = ".opencode/node_modules/";
+
+const HEADER: &str = "# Generated by OpenWork";
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct GitignoreChange {
+  pub path: String,
+  /// Lines appended, or that would be in a preview.
+  pub added: Vec<String>,
+  /// Whether the file was written.
+  pub applied: bool,
+}
+
+fn validate_entry(raw: &str) -> Result<String, OpenWorkError> {
+  let entry = raw.trim();
+  let invalid = entry.is_empty()
+    || entry.contains(['\n', '\r'])
+    || entry.starts_with(['#', '!'])
+    || entry.split('/').any(|segment| segment == "..");
+  if invalid {
+    return Err(OpenWorkError::invalid_argument(format!(
+      "Invalid .gitignore entry: {raw}"
+    )));
+  }
+  Ok(entry.to_string())
+}
+
+/// Whether `existing` (the root `.gitignore` of `root`) already decides
+/// about `entry`: the same line, or for a plain path, any rule that matches
+/// it or one of its parents.
+fn covered(root: &Path, existing: &str, entry: &str) -> bool {
+  if existing.lines().any(|line| line.trim() == entry) {
+    return true;
+  }
+  if entry.contains(['*', '?', '[']) {
+    return false;
+  }
+  let mut builder = GitignoreBuilder::new(root);
+  for line in existing.lines() {
+    let _ = builder.add_line(None, line);
+  }
+  let Ok(matcher) = builder.build() else {
+    return false;
+  };
+  let is_dir = entry.ends_with('/');
+  let path = root.join(entry.trim_matches('/'));
+  !matcher.matched_path_or_any_parents(&path, is_dir).is_none()
+}
+
+/// The entries of `entries` that `existing` doesn't cover, without
+/// duplicates.
+fn missing(root: &Path, existing: &str, entries: &[String]) -> Vec<String> {
+  let mut lines: Vec<String> = Vec::new();
+  for entry in entries {
+    if !lines.contains(entry) && !covered(root, existing, entry) {
+      lines.push(entry.clone());
+    }
+  }
+  lines
+}
+
+/// `existing` with `lines` appended under the OpenWork header.
+fn append(existing: &str, lines: &[String]) -> String {
+  let mut text = existing.to_string();
+  if !text.is_empty() && !text.ends_with('\n') {
+    text.push('\n');
+  }
+  if !existing.lines().any(|line| line.trim() == HEADER) {
+    if !text.is_empty() {
+      text.push('\n');
+    }
+    text.push_str(HEADER);
+    text.push('\n');
+  }
+  for line in lines {
+    text.push_str(line);
+    text.push('\n');
+  }
+  text
+}
+
+/// Adds whichever of `entries` `project_dir/.gitignore` doesn't cover yet,
+/// or only reports them when `preview` is set.
+pub fn ensure(
+  project_dir: &Path,
+  entries: &[String],
+  preview: bool,
+) -> Result<GitignoreChange, OpenWorkError> {
+  let path = project_dir.join(".gitignore");
+  let existing = match fs::read_to_string(&path) {
+    Ok(text) => text,
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+    Err(e) => return Err(format!("Failed to read {}: {e}", paths::display(&path)).into()),
+  };
+  let entries = entries
+    .iter()
+    .map(|entry| validate_entry(entry))
+    .collect::<Result<Vec<_>, _>>()?;
+  let added = missing(project_dir, &existing, &entries);
+  let applied = !preview && !added.is_empty();
+  if applied {
+    dotenv::write_atomically(&path, &append(&existing, &added))?;
+    tracing::info!(added = added.len(), "updated .gitignore");
+  }
+  Ok(GitignoreChange {
+    path: path.to_string_lossy().to_string(),
+    added,
+    applied,
+  })
+}
+
+/// `ensure` for folders a feature leaves in `project_dir`, when it's a git
+/// work tree; failures are only logged.
+pub fn ensure_generated(project_dir: &Path, entries: &[&str]) {
+  if !project_dir.join(".git").exists() {
+    return;
+  }
+  let entries: Vec<String> = entries.iter().map(|entry| entry.to_string()).collect();
+  if let Err(e) = ensure(project_dir, &entries, false) {
+    tracing::warn!(error = %e, "failed to update .gitignore");
+  }
+}
+
+/// Adds `entries` to the project's root `.gitignore` unless it already
+/// covers them; `preview` returns the lines without writing.
+#[tauri::command]
+pub fn gitignore_ensure(
+  project_dir: String,
+  entries: Vec<String>,
+  preview: Option<bool>,
+) -> Result<GitignoreChange, OpenWorkError> {
+  let project_dir = paths::allowed_dir(&project_dir, "projectDir")?;
+  ensure(&project_dir, &entries, preview.unwrap_or(false))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn strings(entries: &[&str]) -> Vec<String> {
+    entries.iter().map(|entry| entry.to_string()).collect()
+  }
+
+  #[test]
+  fn skips_covered_entries() {
+    let root = Path::new("/project");
+    let existing = "node_modules/\n/.opencode.old-*/\n!.opencode/keep/\n";
+    let entries = strings(&[
+      ".opencode/node_modules/",
+      "/.opencode.old-*/",
+      "/.opencode.restore-*/",
+      ".opencode/keep/",
+      "/.opencode.restore-*/",
+    ]);
+    assert_eq!(missing(root, existing, &entries), ["/.opencode.restore-*/"]);
+    assert_eq!(missing(root, "", &strings(&[".opencode/node_modules/"])).len(), 1);
+  }
+
+  #[test]
+  fn appends_under_one_header() {
+    let once = append("target", &strings(&["a/"]));
+    assert_eq!(once, format!("target\n\n{HEADER}\na/\n"));
+    assert_eq!(append(&once, &strings(&["b/"])), format!("{once}b/\n"));
+    assert_eq!(append("", &strings(&["a/"])), format!("{HEADER}\na/\n"));
+  }
+
+  #[test]
+  fn rejects_unsafe_entries() {
+    assert!(validate_entry("../outside").is_err());
+    assert!(validate_entry("a\nb").is_err());
+    assert!(validate_entry("!keep").is_err());
+    assert_eq!(
+      validate_entry(" /.opencode.old-*/ ").unwrap(),
+      "/.opencode.old-*/"
+    );
+  }
+
+  #[test]
+  fn ensure_is_idempotent() {
+    let dir = std::env::temp_dir().join(format!("openwork-gitignore-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let entries = strings(&[OPENCODE_DEPENDENCIES]);
+
+    let preview = ensure(&dir, &entries, true).unwrap();
+    assert_eq!((preview.added.len(), preview.applied), (1, false));
+    assert!(!dir.join(".gitignore").exists());
+
+    assert!(ensure(&dir, &entries, false).unwrap().applied);
+    let again = ensure(&dir, &entries, false).unwrap();
+    assert_eq!((again.added.len(), again.applied), (0, false));
+    fs::remove_dir_all(&dir).unwrap();
+  }
+}
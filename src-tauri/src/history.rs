@@ -0,0 +1,329 @@
+use std::{fs, sync::Mutex};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Manager, State};
+
+use crate::{error::OpenWorkError, relay::RelayedEvent, store::app_state_path};
+
+const HISTORY_FILE: &str = "history.sqlite";
+const SUMMARY_CHARS: usize = 280;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS sessions (
+  id TEXT PRIMARY KEY,
+  project_dir TEXT,
+  title TEXT,
+  created_at INTEGER,
+  updated_at INTEGER
+);
+CREATE INDEX IF NOT EXISTS sessions_project ON sessions(project_dir, updated_at);
+CREATE TABLE IF NOT EXISTS messages (
+  id TEXT PRIMARY KEY,
+  session_id TEXT NOT NULL,
+  role TEXT,
+  summary TEXT,
+  created_at INTEGER
+);
+CREATE INDEX IF NOT EXISTS messages_session ON messages(session_id, created_at);
+";
+
+/// Local mirror of session metadata and message summaries, so past work can be
+/// browsed without a running engine.
+#[derive(Default)]
+pub struct HistoryDb {
+  conn: Mutex<Option<Connection>>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HistorySession {
+  pub id: String,
+  pub project_dir: Option<String>,
+  pub title: Option<String>,
+  pub created_at: Option<i64>,
+  pub updated_at: Option<i64>,
+  pub message_count: i64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryMessage {
+  pub id: String,
+  pub session_id: String,
+  pub role: Option<String>,
+  pub summary: Option<String>,
+  pub created_at: Option<i64>,
+}
+
+fn str_field(value: &Value, pointer: &str) -> Option<String> {
+  value.pointer(pointer).and_then(|v| v.as_str()).map(str::to_string)
+}
+
+fn int_field(value: &Value, pointer: &str) -> Option<i64> {
+  value.pointer(pointer).and_then(|v| v.as_i64())
+}
+
+fn summarize(text: &str) -> String {
+  let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+  if text.chars().count() <= SUMMARY_CHARS {
+    return text;
+  }
+  let clipped: String = text.chars().take(SUMMARY_CHARS).collect();
+  format!("{clipped}…")
+}
+
+impl HistoryDb {
+  pub fn open(app: &AppHandle) -> Result<Self, String> {
+    let path = app_state_path(app, HISTORY_FILE)?;
+    if let Some(parent) = path.parent() {
+      fs::create_dir_all(parent)
+        .map_err(|e| format!("Failed to create state dir {}: {e}", parent.display()))?;
+    }
+
+    let conn =
+      Connection::open(&path).map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+    conn
+      .execute_batch(SCHEMA)
+      .map_err(|e| format!("Failed to initialize history database: {e}"))?;
+
+    Ok(Self {
+      conn: Mutex::new(Some(conn)),
+    })
+  }
+
+  pub fn with_conn<T>(&self, f: impl FnOnce(&Connection) -> rusqlite::Result<T>) -> Result<T, String> {
+    let conn = self.conn.lock().expect("history mutex poisoned");
+    let conn = conn
+      .as_ref()
+      .ok_or_else(|| "History database is not available".to_string())?;
+    f(conn).map_err(|e| format!("History database error: {e}"))
+  }
+
+  fn upsert_session(&self, info: &Value, fallback_project: Option<&str>) -> Result<(), String> {
+    let Some(id) = str_field(info, "/id") else {
+      return Ok(());
+    };
+    let project_dir = str_field(info, "/directory").or_else(|| fallback_project.map(str::to_string));
+
+    self.with_conn(|conn| {
+      conn.execute(
+        "INSERT INTO sessions (id, project_dir, title, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(id) DO UPDATE SET
+           project_dir = COALESCE(excluded.project_dir, sessions.project_dir),
+           title = COALESCE(excluded.title, sessions.title),
+           created_at = COALESCE(sessions.created_at, excluded.created_at),
+           updated_at = COALESCE(excluded.updated_at, sessions.updated_at)",
+        params![
+          id,
+          project_dir,
+          str_field(info, "/title"),
+          int_field(info, "/time/created"),
+          int_field(info, "/time/updated"),
+        ],
+      )
+    })?;
+    Ok(())
+  }
+
+  fn delete_session(&self, info: &Value) -> Result<(), String> {
+    let Some(id) = str_field(info, "/id") else {
+      return Ok(());
+    };
+    self.with_conn(|conn| {
+      conn.execute("DELETE FROM messages WHERE session_id = ?1", params![id])?;
+      conn.execute("DELETE FROM sessions WHERE id = ?1", params![id])
+    })?;
+    Ok(())
+  }
+
+  fn upsert_message(&self, info: &Value) -> Result<(), String> {
+    let (Some(id), Some(session_id)) = (str_field(info, "/id"), str_field(info, "/sessionID")) else {
+      return Ok(());
+    };
+
+    self.with_conn(|conn| {
+      conn.execute(
+        "INSERT INTO messages (id, session_id, role, created_at)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(id) DO UPDATE SET
+           role = COALESCE(excluded.role, messages.role),
+           created_at = COALESCE(messages.created_at, excluded.created_at)",
+        params![
+          id,
+          session_id,
+          str_field(info, "/role"),
+          int_field(info, "/time/created")
+        ],
+      )?;
+      // Keep the session row present even if we missed its creation event.
+      conn.execute(
+        "INSERT OR IGNORE INTO sessions (id, created_at, updated_at) VALUES (?1, ?2, ?2)",
+        params![session_id, int_field(info, "/time/created")],
+      )
+    })?;
+    Ok(())
+  }
+
+  fn update_summary(&self, part: &Value) -> Result<(), String> {
+    if str_field(part, "/type").as_deref() != Some("text") {
+      return Ok(());
+    }
+    let (Some(message_id), Some(session_id), Some(text)) = (
+      str_field(part, "/messageID"),
+      str_field(part, "/sessionID"),
+      str_field(part, "/text"),
+    ) else {
+      return Ok(());
+    };
+
+    let summary = summarize(&text);
+    if summary.is_empty() {
+      return Ok(());
+    }
+
+    self.with_conn(|conn| {
+      conn.execute(
+        "INSERT INTO messages (id, session_id, summary) VALUES (?1, ?2, ?3)
+         ON CONFLICT(id) DO UPDATE SET summary = excluded.summary",
+        params![message_id, session_id, summary],
+      )
+    })?;
+    Ok(())
+  }
+
+  pub fn record(&self, relayed: &RelayedEvent) -> Result<(), String> {
+    let event_type = relayed.event.get("type").and_then(|t| t.as_str()).unwrap_or_default();
+    let Some(properties) = relayed.event.get("properties") else {
+      return Ok(());
+    };
+
+    match event_type {
+      "session.created" | "session.updated" => {
+        if let Some(info) = properties.get("info") {
+          self.upsert_session(info, relayed.project_dir.as_deref())?;
+        }
+      }
+      "session.deleted" => {
+        if let Some(info) = properties.get("info") {
+          self.delete_session(info)?;
+        }
+      }
+      "message.updated" => {
+        if let Some(info) = properties.get("info") {
+          self.upsert_message(info)?;
+        }
+      }
+      "message.part.updated" => {
+        if let Some(part) = properties.get("part") {
+          self.update_summary(part)?;
+        }
+      }
+      _ => {}
+    }
+
+    Ok(())
+  }
+}
+
+/// Opens the database and subscribes it to the event relay.
+pub fn init(app: &AppHandle) {
+  let db = HistoryDb::open(app).unwrap_or_else(|e| {
+    tracing::error!(error = %e, "failed to open history database");
+    HistoryDb::default()
+  });
+  app.manage(db);
+
+  app
+    .state::<crate::relay::EventRelay>()
+    .subscribe(|app, event| {
+      if let Err(e) = app.state::<HistoryDb>().record(event) {
+        tracing::warn!(error = %e, "failed to record event");
+      }
+    });
+}
+
+#[tauri::command]
+pub fn history_sessions(
+  db: State<HistoryDb>,
+  project_dir: Option<String>,
+  limit: Option<u32>,
+) -> Result<Vec<HistorySession>, OpenWorkError> {
+  let project_dir = project_dir.map(|p| p.trim().to_string()).filter(|p| !p.is_empty());
+  let limit = limit.unwrap_or(100).max(1);
+
+  let sessions: Vec<HistorySession> = db.with_conn(|conn| {
+    let mut statement = conn.prepare(
+      "SELECT s.id, s.project_dir, s.title, s.created_at, s.updated_at,
+              (SELECT COUNT(*) FROM messages m WHERE m.session_id = s.id)
+       FROM sessions s
+       WHERE ?1 IS NULL OR s.project_dir = ?1
+       ORDER BY COALESCE(s.updated_at, s.created_at) DESC
+       LIMIT ?2",
+    )?;
+    let rows = statement.query_map(params![project_dir, limit], |row| {
+      Ok(HistorySession {
+        id: row.get(0)?,
+        project_dir: row.get(1)?,
+        title: row.get(2)?,
+        created_at: row.get(3)?,
+        updated_at: row.get(4)?,
+        message_count: row.get(5)?,
+      })
+    })?;
+    rows.collect()
+  })?;
+  Ok(sessions)
+}
+
+#[tauri::command]
+pub fn history_session(db: State<HistoryDb>, id: String) -> Result<Option<HistorySession>, OpenWorkError> {
+  let session = db.with_conn(|conn| {
+    conn
+      .query_row(
+        "SELECT s.id, s.project_dir, s.title, s.created_at, s.updated_at,
+                (SELECT COUNT(*) FROM messages m WHERE m.session_id = s.id)
+         FROM sessions s WHERE s.id = ?1",
+        params![id.trim()],
+        |row| {
+          Ok(HistorySession {
+            id: row.get(0)?,
+            project_dir: row.get(1)?,
+            title: row.get(2)?,
+            created_at: row.get(3)?,
+            updated_at: row.get(4)?,
+            message_count: row.get(5)?,
+          })
+        },
+      )
+      .optional()
+  })?;
+  Ok(session)
+}
+
+#[tauri::command]
+pub fn history_messages(
+  db: State<HistoryDb>,
+  session_id: String,
+) -> Result<Vec<HistoryMessage>, OpenWorkError> {
+  let messages: Vec<HistoryMessage> = db.with_conn(|conn| {
+    let mut statement = conn.prepare(
+      "SELECT id, session_id, role, summary, created_at
+       FROM messages WHERE session_id = ?1
+       ORDER BY created_at ASC, id ASC",
+    )?;
+    let rows = statement.query_map(params![session_id.trim()], |row| {
+      Ok(HistoryMessage {
+        id: row.get(0)?,
+        session_id: row.get(1)?,
+        role: row.get(2)?,
+        summary: row.get(3)?,
+        created_at: row.get(4)?,
+      })
+    })?;
+    rows.collect()
+  })?;
+  Ok(messages)
+}
@@ -0,0 +1,233 @@
+//! The language errors are reported in.
+//!
+//! Backend messages are written in English as they're raised, often with the
+//! path or value involved. For other locales, `OpenWorkError` serializes the
+//! catalog text for its code as `message` and keeps the English text as
+//! `originalMessage`, so nothing specific is lost. The locale follows the
+//! system (`LC_ALL`, `LC_MESSAGES`, `LANG`) until `set_locale` picks one.
+
+use std::{env, sync::RwLock};
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{
+  error::{ErrorCode, OpenWorkError},
+  store::{read_state, write_state},
+};
+
+pub const LOCALE_FILE: &str = "locale.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+  #[default]
+  En,
+  Es,
+  Pt,
+  Fr,
+  De,
+}
+
+impl Locale {
+  /// The locale for a tag such as `pt-BR`, `de_DE.UTF-8` or `fr`.
+  pub fn parse(tag: &str) -> Option<Self> {
+    let language = tag
+      .trim()
+      .split(['-', '_', '.', '@'])
+      .next()?
+      .to_ascii_lowercase();
+    match language.as_str() {
+      "en" => Some(Self::En),
+      "es" => Some(Self::Es),
+      "pt" => Some(Self::Pt),
+      "fr" => Some(Self::Fr),
+      "de" => Some(Self::De),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct LocaleSettings {
+  /// `None` follows the system.
+  locale: Option<Locale>,
+}
+
+static CURRENT: RwLock<Locale> = RwLock::new(Locale::En);
+
+pub fn current() -> Locale {
+  *CURRENT.read().expect("locale lock poisoned")
+}
+
+fn set_current(locale: Locale) {
+  *CURRENT.write().expect("locale lock poisoned") = locale;
+}
+
+/// The system locale, from the usual variables in order of precedence.
+fn system_locale() -> Locale {
+  ["LC_ALL", "LC_MESSAGES", "LANG"]
+    .iter()
+    .filter_map(|name| env::var(name).ok())
+    .find(|value| !value.is_empty())
+    .and_then(|value| Locale::parse(&value))
+    .unwrap_or_default()
+}
+
+pub fn init(app: &AppHandle) {
+  let settings = read_state::<LocaleSettings>(app, LOCALE_FILE).unwrap_or_else(|e| {
+    tracing::warn!(error = %e, "failed to load locale");
+    LocaleSettings::default()
+  });
+  set_current(settings.locale.unwrap_or_else(system_locale));
+}
+
+/// The catalog text for `code` in `locale`; `None` for English, whose
+/// messages are used as raised.
+pub fn error_message(code: ErrorCode, locale: Locale) -> Option<&'static str> {
+  use ErrorCode::*;
+  let text = match locale {
+    Locale::En => return None,
+    Locale::Es => match code {
+      EngineNotFound => "No se encontró la CLI de OpenCode. Instálala e inténtalo de nuevo.",
+      EngineNotRunning => "El motor no está en marcha. Inícialo desde OpenWork.",
+      EngineStartFailed => "No se pudo iniciar el motor.",
+      EngineUnreachable => "El motor no responde.",
+      EngineRequestFailed => "El motor devolvió un error.",
+      PortInUse => "El puerto ya está en uso.",
+      ConfigInvalid => "La configuración no es válida.",
+      InvalidArgument => "Valor no válido.",
+      NotFound => "No se encontró.",
+      AlreadyExists => "Ya existe.",
+      NeedsApproval => "Esta carpeta necesita tu aprobación.",
+      PendingConfirmation => "Esta acción necesita tu confirmación.",
+      ConfirmationInvalid => "La confirmación no es válida o ha caducado. Confirma de nuevo.",
+      Cancelled => "Cancelado.",
+      BudgetExceeded => "Se ha superado el presupuesto.",
+      ToolNotFound => "No se encontró un programa necesario.",
+      InsufficientSpace => "No hay suficiente espacio en disco.",
+      Offline => "No hay conexión a internet.",
+      Io => "Error al leer o escribir archivos.",
+      Network => "Error de red.",
+      Internal => "Algo salió mal.",
+    },
+    Locale::Pt => match code {
+      EngineNotFound => "A CLI do OpenCode não foi encontrada. Instale-a e tente novamente.",
+      EngineNotRunning => "O motor não está em execução. Inicie-o pelo OpenWork.",
+      EngineStartFailed => "Não foi possível iniciar o motor.",
+      EngineUnreachable => "O motor não está respondendo.",
+      EngineRequestFailed => "O motor retornou um erro.",
+      PortInUse => "A porta já está em uso.",
+      ConfigInvalid => "A configuração é inválida.",
+      InvalidArgument => "Valor inválido.",
+      NotFound => "Não encontrado.",
+      AlreadyExists => "Já existe.",
+      NeedsApproval => "Esta pasta precisa da sua aprovação.",
+      PendingConfirmation => "Esta ação precisa da sua confirmação.",
+      ConfirmationInvalid => "A confirmação é inválida ou expirou. Confirme novamente.",
+      Cancelled => "Cancelado.",
+      BudgetExceeded => "O orçamento foi ultrapassado.",
+      ToolNotFound => "Um programa necessário não foi encontrado.",
+      InsufficientSpace => "Não há espaço suficiente em disco.",
+      Offline => "Sem conexão com a internet.",
+      Io => "Erro ao ler ou gravar arquivos.",
+      Network => "Erro de rede.",
+      Internal => "Algo deu errado.",
+    },
+    Locale::Fr => match code {
+      EngineNotFound => "La CLI OpenCode est introuvable. Installez-la puis réessayez.",
+      EngineNotRunning => "Le moteur n'est pas démarré. Démarrez-le depuis OpenWork.",
+      EngineStartFailed => "Impossible de démarrer le moteur.",
+      EngineUnreachable => "Le moteur ne répond pas.",
+      EngineRequestFailed => "Le moteur a renvoyé une erreur.",
+      PortInUse => "Le port est déjà utilisé.",
+      ConfigInvalid => "La configuration n'est pas valide.",
+      InvalidArgument => "Valeur non valide.",
+      NotFound => "Introuvable.",
+      AlreadyExists => "Existe déjà.",
+      NeedsApproval => "Ce dossier doit d'abord être approuvé.",
+      PendingConfirmation => "Cette action doit être confirmée.",
+      ConfirmationInvalid => "La confirmation n'est pas valide ou a expiré. Veuillez confirmer à nouveau.",
+      Cancelled => "Annulé.",
+      BudgetExceeded => "Le budget est dépassé.",
+      ToolNotFound => "Un programme nécessaire est introuvable.",
+      InsufficientSpace => "Espace disque insuffisant.",
+      Offline => "Aucune connexion Internet.",
+      Io => "Erreur de lecture ou d'écriture de fichiers.",
+      Network => "Erreur réseau.",
+      Internal => "Une erreur s'est produite.",
+    },
+    Locale::De => match code {
+      EngineNotFound => "Die OpenCode-CLI wurde nicht gefunden. Installiere sie und versuche es erneut.",
+      EngineNotRunning => "Die Engine läuft nicht. Starte sie in OpenWork.",
+      EngineStartFailed => "Die Engine konnte nicht gestartet werden.",
+      EngineUnreachable => "Die Engine antwortet nicht.",
+      EngineRequestFailed => "Die Engine hat einen Fehler gemeldet.",
+      PortInUse => "Der Port wird bereits verwendet.",
+      ConfigInvalid => "Die Konfiguration ist ungültig.",
+      InvalidArgument => "Ungültiger Wert.",
+      NotFound => "Nicht gefunden.",
+      AlreadyExists => "Existiert bereits.",
+      NeedsApproval => "Dieser Ordner muss erst freigegeben werden.",
+      PendingConfirmation => "Diese Aktion muss bestätigt werden.",
+      ConfirmationInvalid => "Die Bestätigung ist ungültig oder abgelaufen. Bitte bestätige erneut.",
+      Cancelled => "Abgebrochen.",
+      BudgetExceeded => "Das Budget wurde überschritten.",
+      ToolNotFound => "Ein benötigtes Programm wurde nicht gefunden.",
+      InsufficientSpace => "Nicht genügend Speicherplatz.",
+      Offline => "Keine Internetverbindung.",
+      Io => "Fehler beim Lesen oder Schreiben von Dateien.",
+      Network => "Netzwerkfehler.",
+      Internal => "Etwas ist schiefgelaufen.",
+    },
+  };
+  Some(text)
+}
+
+/// The locale errors are reported in.
+#[tauri::command]
+pub fn get_locale() -> Locale {
+  current()
+}
+
+/// Picks the locale for error messages, e.g. `es` or `pt-BR`; `None` or
+/// `"system"` goes back to following the system.
+#[tauri::command]
+pub fn set_locale(app: AppHandle, locale: Option<String>) -> Result<Locale, OpenWorkError> {
+  let chosen = match locale.as_deref().map(str::trim) {
+    None | Some("") | Some("system") => None,
+    Some(tag) => Some(Locale::parse(tag).ok_or_else(|| {
+      OpenWorkError::invalid_argument(format!("Unsupported locale {tag}; supported: en, es, pt, fr, de"))
+    })?),
+  };
+  write_state(&app, LOCALE_FILE, &LocaleSettings { locale: chosen })?;
+  let locale = chosen.unwrap_or_else(system_locale);
+  set_current(locale);
+  tracing::info!(locale = ?locale, "locale set");
+  Ok(locale)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_locale_tags() {
+    assert_eq!(Locale::parse("pt-BR"), Some(Locale::Pt));
+    assert_eq!(Locale::parse("de_DE.UTF-8"), Some(Locale::De));
+    assert_eq!(Locale::parse(" FR "), Some(Locale::Fr));
+    assert_eq!(Locale::parse("C.UTF-8"), None);
+    assert_eq!(Locale::parse("ja_JP"), None);
+    assert_eq!(Locale::parse(""), None);
+  }
+
+  #[test]
+  fn english_keeps_raised_messages() {
+    assert_eq!(error_message(ErrorCode::NotFound, Locale::En), None);
+    assert_eq!(
+      error_message(ErrorCode::NotFound, Locale::Es),
+      Some("No se encontró.")
+    );
+  }
+}
@@ -0,0 +1,258 @@
+//! Runs third-party install scripts with as little access as we can arrange.
+//!
+//! The script is downloaded by us through the shared download module (never
+//! piped from `curl` into a login shell), executed by a non-login `bash` with the installer environment
+//! policy, from a throwaway working directory. On Linux it additionally runs
+//! under `bwrap` or `firejail` when one of them is installed and usable.
+//!
+//! Installs can be cancelled through a `CancelToken`: the download stops, the
+//! installer's process tree is killed, the working directory (where the
+//! script's own downloads go, via `TMPDIR`) is removed, and the call fails
+//! with `CANCELLED`.
+
+// Guided install is not offered on Windows, and the sandbox tools are Linux-only.
+#![cfg_attr(not(target_os = "linux"), allow(dead_code))]
+
+use std::{
+  fs,
+  path::{Path, PathBuf},
+  process::{Command, Output, Stdio},
+  sync::mpsc::{self, RecvTimeoutError},
+  thread,
+  time::Duration,
+};
+
+use serde::Serialize;
+
+use crate::{
+  app_sandbox,
+  cancel::{self, CancelToken},
+  consent::random_token,
+  download::{self, Download, DownloadProgress},
+  env_policy,
+  error::OpenWorkError,
+  exec, redact, ExecResult,
+};
+
+const MAX_SCRIPT_BYTES: u64 = 1024 * 1024;
+/// How often a pending download is checked for cancellation.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// For `CANCELLED` messages.
+const WHAT: &str = "The install";
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SandboxLevel {
+  /// Read-only filesystem except the install directory, private /tmp.
+  Bwrap,
+  /// No capabilities and a home directory limited to the install directory.
+  Firejail,
+  /// Filtered environment and a temporary working directory only.
+  Restricted,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallResult {
+  #[serde(flatten)]
+  pub result: ExecResult,
+  pub sandbox: SandboxLevel,
+}
+
+/// A scratch directory that is removed when dropped.
+struct WorkDir(PathBuf);
+
+impl WorkDir {
+  fn create() -> Result<Self, String> {
+    let path = app_sandbox::shared_temp_dir().join(format!("openwork-install-{}", random_token(12)));
+    fs::create_dir_all(&path).map_err(|e| format!("Failed to create {}: {e}", path.display()))?;
+    Ok(Self(path))
+  }
+}
+
+impl Drop for WorkDir {
+  fn drop(&mut self) {
+    let _ = fs::remove_dir_all(&self.0);
+  }
+}
+
+/// Downloads on a worker thread, so cancelling doesn't wait for a stalled
+/// read to time out; the abandoned download stops at its next chunk.
+fn download_script(
+  url: &str,
+  dest: &Path,
+  cancel: &CancelToken,
+  on_progress: impl FnMut(&DownloadProgress) + Send + 'static,
+) -> Result<(), OpenWorkError> {
+  let (done_tx, done_rx) = mpsc::channel();
+  let (owned_url, owned_dest, token) = (url.to_string(), dest.to_path_buf(), cancel.clone());
+  thread::spawn(move || {
+    let download = Download {
+      url: &owned_url,
+      dest: &owned_dest,
+      sha256: None,
+      max_bytes: Some(MAX_SCRIPT_BYTES),
+      cancel: Some(&token),
+    };
+    let _ = done_tx.send(download::download_to_file(&download, on_progress));
+  });
+  let result = loop {
+    match done_rx.recv_timeout(POLL_INTERVAL) {
+      Ok(result) => break result,
+      Err(RecvTimeoutError::Timeout) => cancel.check(WHAT)?,
+      Err(RecvTimeoutError::Disconnected) => break Err(format!("Download of {url} stopped unexpectedly")),
+    }
+  };
+  if let Err(e) = result {
+    cancel.check(WHAT)?;
+    return Err(e.into());
+  }
+
+  let bytes = fs::read(dest).map_err(|e| format!("Failed to read {}: {e}", dest.display()))?;
+  if std::str::from_utf8(&bytes).is_err() {
+    return Err(format!("Installer script from {url} is not text").into());
+  }
+  Ok(())
+}
+
+/// Confirms the sandbox tool can actually create a sandbox here; user
+/// namespaces are disabled on some distributions.
+#[cfg(target_os = "linux")]
+fn probe(program: &Path, args: &[&str]) -> bool {
+  exec::output(exec::command(program).args(args).stdin(Stdio::null()))
+    .map(|output| output.status.success())
+    .unwrap_or(false)
+}
+
+#[cfg(target_os = "linux")]
+fn sandboxed_command(work_dir: &Path, writable: &[PathBuf]) -> (Command, SandboxLevel) {
+  use crate::resolve_in_path;
+
+  if let Some(bwrap) = resolve_in_path("bwrap") {
+    if probe(&bwrap, &["--ro-bind", "/", "/", "--unshare-all", "--share-net", "true"]) {
+      let mut command = exec::command(bwrap);
+      command
+        .args(["--ro-bind", "/", "/"])
+        .args(["--dev", "/dev", "--proc", "/proc", "--tmpfs", "/tmp"])
+        .args(["--unshare-all", "--share-net", "--die-with-parent", "--new-session"]);
+      // Binds come after `--tmpfs /tmp` so the work dir is mounted on top of it.
+      for path in writable.iter().map(PathBuf::as_path).chain([work_dir]) {
+        command.arg("--bind").arg(path).arg(path);
+      }
+      command.arg("--chdir").arg(work_dir).arg("--").arg("bash");
+      return (command, SandboxLevel::Bwrap);
+    }
+  }
+
+  if let Some(firejail) = resolve_in_path("firejail") {
+    if probe(&firejail, &["--quiet", "--noprofile", "true"]) {
+      let mut command = exec::command(firejail);
+      command.args(["--quiet", "--noprofile", "--caps.drop=all", "--nonewprivs", "--noroot"]);
+      for path in writable {
+        command.arg(format!("--whitelist={}", path.display()));
+      }
+      command.arg(format!("--whitelist={}", work_dir.display())).arg("--").arg("bash");
+      return (command, SandboxLevel::Firejail);
+    }
+  }
+
+  (shell(), SandboxLevel::Restricted)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn sandboxed_command(_work_dir: &Path, _writable: &[PathBuf]) -> (Command, SandboxLevel) {
+  (shell(), SandboxLevel::Restricted)
+}
+
+/// `bash`, started natively: under Rosetta, `uname -m` in the script would
+/// report x86_64 and the installer would fetch the Intel build.
+fn shell() -> Command {
+  #[cfg(target_os = "macos")]
+  if crate::arch::translated() {
+    let mut command = exec::command("arch");
+    command.args(["-arm64", "bash"]);
+    return command;
+  }
+  exec::command("bash")
+}
+
+/// Downloads the script at `url` and runs it. `writable` lists the directories
+/// the script is expected to install into; they are created up front and are
+/// the only user paths a sandbox leaves writable. `sandbox: false` skips
+/// bwrap/firejail but keeps the filtered environment.
+#[tracing::instrument(level = "info", skip(writable, env, cancel, on_progress))]
+pub fn run_script(
+  url: &str,
+  writable: &[PathBuf],
+  env: &[(&str, &Path)],
+  sandbox: bool,
+  cancel: &CancelToken,
+  on_progress: impl FnMut(&DownloadProgress) + Send + 'static,
+) -> Result<InstallResult, OpenWorkError> {
+  let work_dir = WorkDir::create()?;
+  let script_path = work_dir.0.join("install.sh");
+  download_script(url, &script_path, cancel, on_progress)?;
+
+  for dir in writable {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+  }
+
+  let (mut command, level) = if sandbox {
+    sandboxed_command(&work_dir.0, writable)
+  } else {
+    (shell(), SandboxLevel::Restricted)
+  };
+
+  // `apply` clears the environment, so it must run before the explicit vars.
+  env_policy::apply(&mut command, env_policy::EnvTarget::Installer);
+  for (key, value) in env {
+    command.env(key, value);
+  }
+  // So whatever the script downloads is removed with the work dir.
+  command.env("TMPDIR", &work_dir.0);
+
+  command
+    .arg("--noprofile")
+    .arg("--norc")
+    .arg(&script_path)
+    .current_dir(&work_dir.0)
+    .stdin(Stdio::null());
+  let output = cancel::output(&mut command, cancel, WHAT)?;
+
+  tracing::info!(sandbox = ?level, status = ?output.status.code(), "installer finished");
+
+  Ok(install_result(output, level))
+}
+
+/// Runs a package manager's own install command, such as `nix profile
+/// install`, with the installer environment policy from a throwaway working
+/// directory. It isn't put under bwrap/firejail: the package manager has to
+/// reach its daemon and store.
+#[tracing::instrument(level = "info", skip(args, cancel))]
+pub fn run_package_manager(
+  program: &Path,
+  args: &[String],
+  cancel: &CancelToken,
+) -> Result<InstallResult, OpenWorkError> {
+  let work_dir = WorkDir::create()?;
+  let mut command = exec::command(program);
+  env_policy::apply(&mut command, env_policy::EnvTarget::Installer);
+  command.args(args).current_dir(&work_dir.0).stdin(Stdio::null());
+  let output = cancel::output(&mut command, cancel, WHAT)?;
+
+  tracing::info!(status = ?output.status.code(), "package manager finished");
+
+  Ok(install_result(output, SandboxLevel::Restricted))
+}
+
+fn install_result(output: Output, level: SandboxLevel) -> InstallResult {
+  InstallResult {
+    result: ExecResult {
+      ok: output.status.success(),
+      status: output.status.code().unwrap_or(-1),
+      stdout: redact::redact(&String::from_utf8_lossy(&output.stdout)),
+      stderr: redact::redact(&String::from_utf8_lossy(&output.stderr)),
+    },
+    sandbox: level,
+  }
+}
@@ -0,0 +1,100 @@
+//! Keeps OpenWork to a single running instance.
+//!
+//! A second launch exits immediately; the running instance focuses its window
+//! and, when the launch named a project folder (`openwork ~/code/app`), opens
+//! it. Two apps would otherwise each start engines for the same projects.
+
+use std::{path::Path, sync::Mutex};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::paths;
+
+pub const OPEN_PROJECT_EVENT: &str = "app://open-project";
+
+pub const MAIN_WINDOW: &str = "main";
+
+/// Project handed over by a launch, kept until the webview takes it so a
+/// request that arrives before the UI is listening isn't lost.
+#[derive(Default)]
+pub struct LaunchRequests {
+  pending: Mutex<Option<String>>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenProjectRequest {
+  pub project_dir: String,
+}
+
+/// First positional argument, resolved against the launching process's
+/// working directory. Flags (including macOS's `-psn_*`) and `openwork://`
+/// links, which the deep link plugin handles, are skipped.
+fn project_arg(args: &[String], cwd: &Path) -> Option<String> {
+  let raw = args
+    .iter()
+    .skip(1)
+    .find(|arg| !arg.starts_with('-') && !arg.contains("://"))?;
+  let path = cwd.join(raw);
+  match paths::existing_dir(&path.to_string_lossy(), "project") {
+    Ok(dir) => Some(dir.to_string_lossy().to_string()),
+    Err(e) => {
+      tracing::warn!(error = %e, "ignoring launch argument");
+      None
+    }
+  }
+}
+
+pub fn focus_window(app: &AppHandle, label: &str) -> bool {
+  let Some(window) = app.get_webview_window(label) else {
+    return false;
+  };
+  let _ = window.unminimize();
+  let _ = window.show();
+  let _ = window.set_focus();
+  true
+}
+
+pub fn focus_main_window(app: &AppHandle) {
+  focus_window(app, MAIN_WINDOW);
+}
+
+pub fn request_open(app: &AppHandle, project_dir: String) {
+  tracing::info!(project_dir = %project_dir, "project requested at launch");
+  *app
+    .state::<LaunchRequests>()
+    .pending
+    .lock()
+    .expect("launch mutex poisoned") = Some(project_dir.clone());
+  let _ = app.emit(OPEN_PROJECT_EVENT, OpenProjectRequest { project_dir });
+}
+
+/// Called by the single-instance plugin in the running app when OpenWork is
+/// launched again.
+pub fn on_second_launch(app: &AppHandle, args: Vec<String>, cwd: String) {
+  if crate::control::on_second_launch(app, &args) {
+    return;
+  }
+  focus_main_window(app);
+  if let Some(project_dir) = project_arg(&args, Path::new(&cwd)) {
+    request_open(app, project_dir);
+  }
+}
+
+/// Picks up a project passed to this (first) launch.
+pub fn init(app: &AppHandle) {
+  let args: Vec<String> = std::env::args().collect();
+  let Ok(cwd) = std::env::current_dir() else {
+    return;
+  };
+  if let Some(project_dir) = project_arg(&args, &cwd) {
+    request_open(app, project_dir);
+  }
+}
+
+/// Returns the pending launch project, if any, and clears it.
+#[tauri::command]
+pub fn launch_project_take(requests: State<LaunchRequests>) -> Option<String> {
+  requests.pending.lock().expect("launch mutex poisoned").take()
+}
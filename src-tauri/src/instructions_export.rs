@@ -0,0 +1,323 @@
+//! Exporting the project's instructions for other AI coding tools.
+//!
+//! opencode reads `AGENTS.md` plus the files listed under `instructions` in
+//! `opencode.json`. `export_instructions` merges them, in that order, into the
+//! file another tool reads: `CLAUDE.md` for Claude Code, `.cursorrules` for
+//! Cursor, `.github/copilot-instructions.md` for Copilot in VS Code. The
+//! export replaces that file as a whole and says where it came from, so
+//! `AGENTS.md` stays the one place to edit. Without `apply` it only returns
+//! the diff.
+
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::State;
+
+use crate::{
+  consent::ConsentManager,
+  error::{ErrorCode, OpenWorkError},
+  external_config::{unified_diff, FileChange},
+  packages::wildcard_match,
+  paths,
+};
+
+const AGENTS_FILE: &str = "AGENTS.md";
+const CONFIG_FILES: [&str; 2] = ["opencode.json", "opencode.jsonc"];
+/// How deep `**` looks, and folders it never enters.
+const MAX_GLOB_DEPTH: usize = 8;
+const SKIPPED_DIRS: [&str; 3] = [".git", "node_modules", "target"];
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum InstructionFormat {
+  Claude,
+  Cursor,
+  Copilot,
+}
+
+impl InstructionFormat {
+  fn target(self) -> &'static str {
+    match self {
+      Self::Claude => "CLAUDE.md",
+      Self::Cursor => ".cursorrules",
+      Self::Copilot => ".github/copilot-instructions.md",
+    }
+  }
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct InstructionsExport {
+  pub format: InstructionFormat,
+  pub path: String,
+  /// The files merged, relative to the project where possible.
+  pub sources: Vec<String>,
+  /// `None` when the target is already up to date.
+  pub change: Option<FileChange>,
+  pub applied: bool,
+  pub notes: Vec<String>,
+}
+
+fn label(project_dir: &Path, path: &Path) -> String {
+  match path.strip_prefix(project_dir) {
+    Ok(relative) => relative
+      .components()
+      .map(|c| c.as_os_str().to_string_lossy().to_string())
+      .collect::<Vec<_>>()
+      .join("/"),
+    Err(_) => path.to_string_lossy().to_string(),
+  }
+}
+
+fn has_wildcard(segment: &str) -> bool {
+  segment.contains(['*', '?'])
+}
+
+/// `dir` and the folders below it, for `**`.
+fn descendant_dirs(dir: &Path) -> Vec<PathBuf> {
+  let mut found = Vec::new();
+  let mut stack = vec![(dir.to_path_buf(), 0)];
+  while let Some((dir, depth)) = stack.pop() {
+    if depth < MAX_GLOB_DEPTH {
+      for entry in fs::read_dir(&dir).into_iter().flatten().flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if entry.file_type().is_ok_and(|t| t.is_dir()) && !SKIPPED_DIRS.contains(&name.as_str()) {
+          stack.push((entry.path(), depth + 1));
+        }
+      }
+    }
+    found.push(dir);
+  }
+  found
+}
+
+/// Files matching `pattern` under `root`: `*` and `?` within a segment, `**`
+/// across folders, as opencode's `instructions` entries use.
+fn expand(root: &Path, pattern: &str) -> Vec<PathBuf> {
+  let segments: Vec<&str> = pattern
+    .split(['/', '\\'])
+    .filter(|segment| !segment.is_empty() && *segment != ".")
+    .collect();
+  let mut current = vec![root.to_path_buf()];
+  for segment in segments {
+    let mut next = Vec::new();
+    for dir in &current {
+      if segment == "**" {
+        next.extend(descendant_dirs(dir));
+      } else if has_wildcard(segment) {
+        next.extend(
+          fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| wildcard_match(segment, &entry.file_name().to_string_lossy()))
+            .map(|entry| entry.path()),
+        );
+      } else {
+        next.push(dir.join(segment));
+      }
+    }
+    current = next;
+  }
+  current.retain(|path| path.is_file());
+  current.sort();
+  current.dedup();
+  current
+}
+
+/// The project's `AGENTS.md` and `instructions` files, in the order opencode
+/// reads them, with notes about entries that couldn't be used.
+fn sources(project_dir: &Path) -> Result<(Vec<PathBuf>, Vec<String>), OpenWorkError> {
+  let mut files = Vec::new();
+  let mut notes = Vec::new();
+  let agents = project_dir.join(AGENTS_FILE);
+  if agents.is_file() {
+    files.push(agents);
+  }
+  for name in CONFIG_FILES {
+    let path = project_dir.join(name);
+    let Ok(text) = fs::read_to_string(paths::extended(&path)) else {
+      continue;
+    };
+    let config = crate::parse_opencode_config(Some(&text), &path)?;
+    for entry in config
+      .get("instructions")
+      .and_then(Value::as_array)
+      .into_iter()
+      .flatten()
+      .filter_map(Value::as_str)
+    {
+      let matched = if entry.contains("://") {
+        notes.push(format!("Skipped {entry}: only local files are exported."));
+        continue;
+      } else if let Some(rest) = entry.strip_prefix("~/") {
+        crate::home_dir()
+          .map(|home| expand(&home, rest))
+          .unwrap_or_default()
+      } else if Path::new(entry).is_absolute() {
+        if has_wildcard(entry) {
+          notes.push(format!("Skipped {entry}: absolute patterns aren't supported."));
+          continue;
+        }
+        Some(PathBuf::from(entry))
+          .filter(|path| path.is_file())
+          .into_iter()
+          .collect()
+      } else {
+        expand(project_dir, entry)
+      };
+      if matched.is_empty() {
+        notes.push(format!("{entry} in {name} matches no files."));
+      }
+      for path in matched {
+        if !files.contains(&path) {
+          files.push(path);
+        }
+      }
+    }
+  }
+  Ok((files, notes))
+}
+
+/// The exported file: a header naming the sources, then each source's text,
+/// the later ones under a marker.
+fn render(sources: &[(String, String)]) -> String {
+  let names: Vec<&str> = sources.iter().map(|(name, _)| name.as_str()).collect();
+  let mut out = format!(
+    "<!-- Generated by OpenWork from {}. Edit those and export again. -->\n",
+    names.join(", ")
+  );
+  for (index, (name, text)) in sources.iter().enumerate() {
+    out.push('\n');
+    if index > 0 {
+      out.push_str(&format!("<!-- From {name} -->\n\n"));
+    }
+    let text = text.trim();
+    if !text.is_empty() {
+      out.push_str(text);
+      out.push('\n');
+    }
+  }
+  out
+}
+
+/// Merges `AGENTS.md` and the `instructions` files into `format`'s file in
+/// the project. Returns the change as a diff; `apply` writes it, asking for
+/// confirmation when it replaces an existing file.
+#[tauri::command]
+pub fn export_instructions(
+  consent: State<ConsentManager>,
+  project_dir: String,
+  format: InstructionFormat,
+  apply: Option<bool>,
+  confirmation_id: Option<String>,
+) -> Result<InstructionsExport, OpenWorkError> {
+  let project_dir = paths::allowed_dir(&project_dir, "projectDir")?;
+  let target = paths::resolve_within(&project_dir, format.target())?;
+  let (mut files, mut notes) = sources(&project_dir)?;
+  if files.contains(&target) {
+    files.retain(|file| file != &target);
+    notes.push(format!("Left out {}, the file being exported to.", format.target()));
+  }
+  if files.is_empty() {
+    return Err(OpenWorkError::new(
+      ErrorCode::NotFound,
+      format!(
+        "{} has no AGENTS.md or instructions in opencode.json to export",
+        project_dir.display()
+      ),
+    ));
+  }
+  let mut texts = Vec::new();
+  for file in &files {
+    let text = fs::read_to_string(paths::extended(file))
+      .map_err(|e| format!("Failed to read {}: {e}", file.display()))?;
+    texts.push((label(&project_dir, file), text));
+  }
+
+  let before = fs::read_to_string(paths::extended(&target)).ok();
+  let after = render(&texts);
+  let path = target.to_string_lossy().to_string();
+  let change = (before.as_deref() != Some(after.as_str())).then(|| FileChange {
+    diff: unified_diff(&path, before.as_deref().unwrap_or(""), &after),
+    exists: before.is_some(),
+    path: path.clone(),
+  });
+
+  let apply = apply.unwrap_or(false) && change.is_some();
+  if apply {
+    if before.is_some() {
+      consent.require(
+        "export_instructions",
+        &path,
+        &format!("Replace {} with the project's instructions?", format.target()),
+        confirmation_id.as_deref(),
+      )?;
+    }
+    crate::write_config_file(&target, &after)?;
+    crate::telemetry::record("feature.export_instructions");
+    tracing::info!(format = ?format, sources = files.len(), "exported instructions");
+  }
+
+  Ok(InstructionsExport {
+    format,
+    path,
+    sources: texts.into_iter().map(|(name, _)| name).collect(),
+    change,
+    applied: apply,
+    notes,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn renders_sources_in_order() {
+    let sources = [
+      ("AGENTS.md".to_string(), "# Project\n\nUse tabs.\n\n".to_string()),
+      ("docs/style.md".to_string(), "Prefer early returns.".to_string()),
+    ];
+    assert_eq!(
+      render(&sources),
+      "<!-- Generated by OpenWork from AGENTS.md, docs/style.md. Edit those and export again. -->\n\
+       \n# Project\n\nUse tabs.\n\
+       \n<!-- From docs/style.md -->\n\nPrefer early returns.\n"
+    );
+  }
+
+  #[test]
+  fn expands_instruction_globs() {
+    let root = std::env::temp_dir().join(format!("openwork-instructions-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    for file in [
+      "a.md",
+      "docs/b.md",
+      "docs/c.txt",
+      "docs/deep/d.md",
+      "node_modules/x/e.md",
+    ] {
+      let path = root.join(file);
+      fs::create_dir_all(path.parent().unwrap()).unwrap();
+      fs::write(path, "x").unwrap();
+    }
+    let found = |pattern: &str| -> Vec<String> {
+      let mut found: Vec<String> = expand(&root, pattern)
+        .iter()
+        .map(|path| label(&root, path))
+        .collect();
+      found.sort();
+      found
+    };
+    assert_eq!(found("a.md"), ["a.md"]);
+    assert_eq!(found("./docs/*.md"), ["docs/b.md"]);
+    assert_eq!(found("**/*.md"), ["a.md", "docs/b.md", "docs/deep/d.md"]);
+    assert!(found("missing.md").is_empty());
+    let _ = fs::remove_dir_all(&root);
+  }
+}
@@ -0,0 +1,77 @@
+//! JSON with comments and trailing commas, as opencode and devcontainer
+//! configs allow.
+
+/// `text` without `//` and `/* */` comments or trailing commas, so it parses
+/// as JSON. Line breaks are kept, so parse errors point at the right line.
+pub fn strip(text: &str) -> String {
+  let mut out = String::with_capacity(text.len());
+  let mut chars = text.chars().peekable();
+  let mut in_string = false;
+  // Where the last comma outside a string went, while only whitespace followed.
+  let mut pending_comma: Option<usize> = None;
+  while let Some(c) = chars.next() {
+    if in_string {
+      out.push(c);
+      match c {
+        '\\' => out.extend(chars.next()),
+        '"' => in_string = false,
+        _ => {}
+      }
+      continue;
+    }
+    match (c, chars.peek()) {
+      ('/', Some('/')) => while chars.next_if(|&next| next != '\n').is_some() {},
+      ('/', Some('*')) => {
+        chars.next();
+        let mut last = '\0';
+        for next in chars.by_ref() {
+          if last == '*' && next == '/' {
+            break;
+          }
+          if next == '\n' {
+            out.push(next);
+          }
+          last = next;
+        }
+      }
+      _ if c.is_whitespace() => out.push(c),
+      _ => {
+        if matches!(c, '}' | ']') {
+          if let Some(at) = pending_comma {
+            out.remove(at);
+          }
+        }
+        pending_comma = (c == ',').then_some(out.len());
+        in_string = c == '"';
+        out.push(c);
+      }
+    }
+  }
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::{json, Value};
+
+  use super::*;
+
+  #[test]
+  fn strips_comments_and_trailing_commas() {
+    let text = r#"{
+      // line comment
+      "url": "http://x//y", /* block */
+      "list": [1, 2,],
+      "quote": "a \" // b",
+    }"#;
+    let value: Value = serde_json::from_str(&strip(text)).unwrap();
+    assert_eq!(value["url"], "http://x//y");
+    assert_eq!(value["list"], json!([1, 2]));
+    assert_eq!(value["quote"], "a \" // b");
+  }
+
+  #[test]
+  fn keeps_lines_of_block_comments() {
+    assert_eq!(strip("/* a\nb */\n1").lines().count(), 3);
+  }
+}
@@ -1,19 +1,127 @@
+mod allowlist;
+mod app_sandbox;
+mod arch;
+mod args;
+mod archive;
+mod cancel;
+mod cli;
+mod asset_protocol;
+mod attachments;
+mod budget;
+mod consent;
+mod config_backup;
+mod config_schema;
+mod control;
+mod cors;
+mod crash;
+mod debug_bundle;
+mod deep_link;
+mod devcontainer;
+mod dir_copy;
+mod disk_space;
+mod dotenv;
+mod download;
+mod dropped;
+mod engine_cache;
+mod engine_client;
+mod engine_env;
+mod engine_hooks;
+mod engine_lock;
+mod engine_log;
+mod engine_releases;
+mod env_policy;
+mod env_profiles;
+mod error;
+mod exec;
+#[cfg(feature = "ext-jira")]
+mod ext_jira;
+mod extensions;
+mod external_config;
+mod failover;
+mod git;
+mod github;
+mod gitignore;
+mod history;
+mod i18n;
+mod installer;
+mod instructions_export;
+mod instance;
+mod jsonc;
+mod local_models;
+mod log_viewer;
+mod logging;
+mod mcp;
+mod menu;
+mod models;
+mod network;
+mod nix;
+mod notifier;
+mod onboarding;
+mod opencode_snapshot;
+mod opkg_queue;
+mod packages;
+mod paths;
+mod perf;
+mod portable;
+mod permissions;
+mod private_files;
+mod project;
+mod project_window;
+mod prompt_queue;
+mod providers;
+mod recent;
+mod redact;
+mod relay;
+mod remote_project;
+mod scheduler;
+mod search;
+mod shell;
+mod shell_path;
+mod skill_deps;
+mod skill_url;
+mod startup;
+mod store;
+mod task_indicator;
+mod tasks;
+mod telemetry;
+mod terminal;
+mod transcript;
+mod updater;
+mod usage;
+mod version_managers;
+mod watcher;
+mod webhooks;
+mod workspace;
+
 use std::{
+  collections::HashMap,
   env,
   ffi::OsStr,
   fs,
   net::TcpListener,
   path::{Path, PathBuf},
-  process::{Child, Command, Stdio},
-  sync::Mutex,
+  process::{Child, Command, Output, Stdio},
+  sync::{mpsc, Mutex, MutexGuard},
+  thread,
+  time::{Duration, Instant},
 };
 
 use serde::Serialize;
-use tauri::State;
+use tauri::{AppHandle, Emitter, Manager, State, WebviewWindow};
+
+use crate::{
+  engine_cache::EngineCache,
+  error::{ErrorCode, OpenWorkError},
+};
 
 #[derive(Default)]
 struct EngineManager {
+  /// Engine for the main window.
   inner: Mutex<EngineState>,
+  /// Engines for project windows, keyed by window label.
+  windows: Mutex<HashMap<String, EngineState>>,
+  /// Secondary engines that windows fall back on.
+  failover: failover::EngineFailover,
 }
 
 #[derive(Default)]
@@ -23,8 +131,25 @@ struct EngineState {
   hostname: Option<String>,
   port: Option<u16>,
   base_url: Option<String>,
+  /// Random per-launch password the engine requires on every request.
+  auth_token: Option<String>,
+  /// Permission profile layered onto the engine config at start.
+  permission_profile: Option<String>,
+  /// Environment profile whose variables the engine was started with.
+  env_profile: Option<String>,
+  /// The devcontainer the engine runs in, if any.
+  container: Option<devcontainer::ContainerTarget>,
+  /// PID of an engine another OpenWork instance started, which this one
+  /// only uses; see `engine_lock`.
+  attached: Option<u32>,
+  /// Whether this instance recorded the engine in `engine_lock`, so only
+  /// that lock is removed on stop.
+  locked: bool,
 }
 
+/// Username the engine expects alongside `OPENCODE_SERVER_PASSWORD`.
+const ENGINE_AUTH_USERNAME: &str = "opencode";
+
 #[derive(Debug, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct EngineInfo {
@@ -34,6 +159,17 @@ pub struct EngineInfo {
   pub hostname: Option<String>,
   pub port: Option<u16>,
   pub pid: Option<u32>,
+  /// Basic-auth credentials for the engine API (username `opencode`).
+  pub auth_token: Option<String>,
+  pub permission_profile: Option<String>,
+  /// The environment profile (see `env_profiles`) the engine was started with.
+  pub active_env_profile: Option<String>,
+  /// The engine belongs to another OpenWork instance; stopping only lets go
+  /// of it.
+  pub attached: bool,
+  /// Set when the engine runs in the project's devcontainer; its paths are
+  /// under `container.workspaceFolder`.
+  pub container: Option<devcontainer::ContainerTarget>,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -44,7 +180,27 @@ pub struct EngineDoctorResult {
   pub resolved_path: Option<String>,
   pub version: Option<String>,
   pub supports_serve: bool,
+  /// `serve` can write JSON logs, which the engine is then started with.
+  pub json_logs: bool,
   pub notes: Vec<String>,
+  /// Node version managers installed for this user; opencode installed
+  /// through npm may live in one of their version folders.
+  pub version_managers: Vec<version_managers::VersionManager>,
+  /// Flatpak or Snap packaging, which changes where programs and config live.
+  pub sandbox: app_sandbox::AppSandbox,
+  /// Architectures the resolved binary is built for; empty for launcher
+  /// scripts.
+  pub binary_arch: Vec<arch::Arch>,
+  pub host_arch: Option<arch::Arch>,
+  /// Nix or NixOS, where opencode comes from nix profiles.
+  pub nix: nix::NixEnv,
+  /// The GitHub CLI, used to open pull requests.
+  pub gh: github::GhStatus,
+  /// Ollama and LM Studio, for running models locally.
+  pub local_models: Vec<local_models::LocalRuntime>,
+  /// opencode config and credential files other users can read; see
+  /// `config_permissions_fix`.
+  pub exposed_configs: Vec<private_files::ExposedFile>,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -64,9 +220,12 @@ pub struct OpencodeConfigFile {
   pub content: Option<String>,
 }
 
-fn find_free_port() -> Result<u16, String> {
-  let listener = TcpListener::bind(("127.0.0.1", 0)).map_err(|e| e.to_string())?;
-  let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+fn find_free_port() -> Result<u16, OpenWorkError> {
+  let no_port = |e: std::io::Error| {
+    OpenWorkError::new(ErrorCode::PortInUse, format!("No free local port for the engine: {e}")).retryable()
+  };
+  let listener = TcpListener::bind(("127.0.0.1", 0)).map_err(no_port)?;
+  let port = listener.local_addr().map_err(no_port)?.port();
   Ok(port)
 }
 
@@ -79,7 +238,18 @@ const OPENCODE_CMD: &str = "opencode.cmd";
 #[cfg(not(windows))]
 const OPENCODE_EXECUTABLE: &str = "opencode";
 
+/// Budget for finding opencode on disk.
+const RESOLVE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Budget for the whole doctor run, so a hung binary or an unresponsive
+/// network drive can't stall onboarding.
+const DOCTOR_TIMEOUT: Duration = Duration::from_secs(8);
+
 fn home_dir() -> Option<PathBuf> {
+  if let Some(home) = app_sandbox::real_home() {
+    return Some(home);
+  }
+
   if let Ok(home) = env::var("HOME") {
     if !home.trim().is_empty() {
       return Some(PathBuf::from(home));
@@ -95,6 +265,18 @@ fn home_dir() -> Option<PathBuf> {
   None
 }
 
+/// opencode's data directory (`$XDG_DATA_HOME/opencode`), where it keeps logs and credentials.
+fn opencode_data_dir() -> Option<PathBuf> {
+  if let Some(dir) = portable::opencode_xdg_dir("XDG_DATA_HOME") {
+    return Some(dir.join("opencode"));
+  }
+  let data = match app_sandbox::xdg_dir("XDG_DATA_HOME") {
+    Some(dir) => dir,
+    None => home_dir()?.join(".local").join("share"),
+  };
+  Some(data.join("opencode"))
+}
+
 fn path_entries() -> Vec<PathBuf> {
   let mut entries = Vec::new();
   let Some(path) = env::var_os("PATH") else {
@@ -131,6 +313,10 @@ fn candidate_opencode_paths() -> Vec<PathBuf> {
 
   let home = home_dir();
 
+  if let Some(tools) = portable::dir("tools") {
+    candidates.push(tools.join("opencode").join("bin").join(OPENCODE_EXECUTABLE));
+  }
+
   if let Some(ref h) = home {
     candidates.push(h.join(".opencode").join("bin").join(OPENCODE_EXECUTABLE));
   }
@@ -157,13 +343,40 @@ fn candidate_opencode_paths() -> Vec<PathBuf> {
 
     // Common Linux paths.
     candidates.push(PathBuf::from("/usr/bin").join(OPENCODE_EXECUTABLE));
+
+    // Nix profiles; on NixOS these are the only place opencode can be.
+    candidates.extend(nix::profile_bin_dirs().into_iter().map(|dir| dir.join(OPENCODE_EXECUTABLE)));
   }
 
   candidates
 }
 
-fn opencode_version(program: &OsStr) -> Option<String> {
-  let output = Command::new(program).arg("--version").output().ok()?;
+/// Runs `command` to completion, killing it if it's still running at `deadline`.
+fn output_before(command: &mut Command, deadline: Instant) -> Option<Output> {
+  command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped());
+  let mut child = exec::spawn(command).ok()?;
+  loop {
+    match child.try_wait() {
+      Ok(Some(_)) => return child.wait_with_output().ok(),
+      Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(20)),
+      _ => {
+        let _ = child.kill();
+        let _ = child.wait();
+        return None;
+      }
+    }
+  }
+}
+
+/// A command running the opencode at `program`.
+fn opencode_command(program: &OsStr) -> Command {
+  let mut command = exec::command(program);
+  version_managers::prepend_node_dir(&mut command, Path::new(program));
+  command
+}
+
+fn opencode_version(program: &OsStr, deadline: Instant) -> Option<String> {
+  let output = output_before(opencode_command(program).arg("--version"), deadline)?;
   let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
   let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
 
@@ -177,61 +390,148 @@ fn opencode_version(program: &OsStr) -> Option<String> {
   None
 }
 
-fn opencode_supports_serve(program: &OsStr) -> bool {
-  Command::new(program)
-    .arg("serve")
-    .arg("--help")
-    .stdout(Stdio::null())
-    .stderr(Stdio::null())
-    .status()
-    .map(|s| s.success())
-    .unwrap_or(false)
+/// `serve --help` output, or `None` when this opencode has no `serve`.
+fn opencode_serve_help(program: &OsStr, deadline: Instant) -> Option<String> {
+  let output = output_before(opencode_command(program).arg("serve").arg("--help"), deadline)?;
+  output.status.success().then(|| {
+    format!(
+      "{}\n{}",
+      String::from_utf8_lossy(&output.stdout),
+      String::from_utf8_lossy(&output.stderr)
+    )
+  })
+}
+
+/// Checks which `candidates` are files, all at once, so one slow network drive
+/// on PATH doesn't hold up the rest. Candidates still unanswered at `deadline`
+/// come back as `None`.
+fn probe_files(candidates: &[PathBuf], deadline: Instant) -> Vec<Option<bool>> {
+  let (tx, rx) = mpsc::channel();
+  for (index, candidate) in candidates.iter().cloned().enumerate() {
+    let tx = tx.clone();
+    thread::spawn(move || {
+      let _ = tx.send((index, candidate.is_file()));
+    });
+  }
+  drop(tx);
+
+  let mut results = vec![None; candidates.len()];
+  while let Some(timeout) = deadline.checked_duration_since(Instant::now()) {
+    match rx.recv_timeout(timeout) {
+      Ok((index, is_file)) => {
+        results[index] = Some(is_file);
+        // Once every candidate up to a hit has answered, later ones can't win.
+        if results.iter().map_while(|result| *result).any(|is_file| is_file) {
+          break;
+        }
+      }
+      Err(_) => break,
+    }
+  }
+  results
+}
+
+/// Looks `name` up in the host user's login shell; under Flatpak, `exec` runs
+/// this on the host.
+fn host_which(name: &str, deadline: Instant) -> Option<PathBuf> {
+  let sh = shell::Shell::sh();
+  let script = sh.join("command", &["-v", name]).ok()?;
+  let options = shell::ShellOptions {
+    login: true,
+    timeout: deadline.saturating_duration_since(Instant::now()),
+    ..shell::ShellOptions::default()
+  };
+  let result = shell::run_in_shell(&sh, &script, &options).ok().filter(|result| result.ok)?;
+  let path = result.stdout.trim();
+  path.starts_with('/').then(|| PathBuf::from(path))
 }
 
-fn resolve_opencode_executable() -> (Option<PathBuf>, bool, Vec<String>) {
+fn resolve_opencode_executable_before(deadline: Instant) -> (Option<PathBuf>, bool, Vec<String>) {
   let mut notes = Vec::new();
 
-  // Try to find opencode executable in PATH first.
-  // On Windows, we check for both opencode.exe and opencode.cmd (npm wrapper).
-  // On Unix, we check for opencode.
-  if let Some(path) = resolve_in_path(OPENCODE_EXECUTABLE) {
-    notes.push(format!("Found in PATH: {}", path.display()));
-    return (Some(path), true, notes);
+  // PATH comes first: on Windows both opencode.exe and opencode.cmd (npm's
+  // wrapper), on Unix just opencode. The well-known install locations follow,
+  // then the bin folders of Node version managers.
+  let mut names = vec![OPENCODE_EXECUTABLE];
+  #[cfg(windows)]
+  names.push(OPENCODE_CMD);
+  let mut candidates: Vec<(PathBuf, bool, Option<version_managers::VersionManager>)> = Vec::new();
+  for name in &names {
+    candidates.extend(path_entries().into_iter().map(|dir| (dir.join(name), true, None)));
+  }
+  candidates.extend(candidate_opencode_paths().into_iter().map(|path| (path, false, None)));
+  for (manager, dir) in version_managers::bin_dirs() {
+    candidates.extend(names.iter().map(|name| (dir.join(name), false, Some(manager))));
   }
 
-  // On Windows, also check for opencode.cmd (npm's wrapper script)
-  #[cfg(windows)]
-  if let Some(path) = resolve_in_path(OPENCODE_CMD) {
-    notes.push(format!("Found in PATH: {}", path.display()));
-    return (Some(path), true, notes);
+  // In Flatpak, the host's PATH and /usr aren't visible; ask the host instead.
+  if app_sandbox::current() == app_sandbox::AppSandbox::Flatpak {
+    if let Some(path) = host_which(OPENCODE_EXECUTABLE, deadline) {
+      notes.push(format!("Found in host PATH: {}", path.display()));
+      return (Some(path), true, notes);
+    }
+    notes.push("Not found on host PATH".to_string());
   }
 
-  notes.push("Not found on PATH".to_string());
+  let paths: Vec<PathBuf> = candidates.iter().map(|(path, _, _)| path.clone()).collect();
+  let found = probe_files(&paths, deadline);
 
-  for candidate in candidate_opencode_paths() {
-    if candidate.is_file() {
-      notes.push(format!("Found at {}", candidate.display()));
-      return (Some(candidate), false, notes);
+  // Earlier candidates win, exactly as a sequential search would pick.
+  let mut path_missing_noted = false;
+  for ((candidate, in_path, manager), found) in candidates.into_iter().zip(found) {
+    if !in_path && !path_missing_noted {
+      notes.push("Not found on PATH".to_string());
+      path_missing_noted = true;
     }
-
-    notes.push(format!("Missing: {}", candidate.display()));
+    match found {
+      Some(true) if in_path => {
+        notes.push(format!("Found in PATH: {}", candidate.display()));
+        return (Some(candidate), true, notes);
+      }
+      Some(true) => {
+        match manager {
+          Some(manager) => notes.push(format!("Found at {} ({})", candidate.display(), manager.label())),
+          None => notes.push(format!("Found at {}", candidate.display())),
+        }
+        return (Some(candidate), false, notes);
+      }
+      Some(false) if in_path => {}
+      // Version folders are listed as found; no need to report each miss.
+      Some(false) if manager.is_some() => {}
+      Some(false) => notes.push(format!("Missing: {}", candidate.display())),
+      None => notes.push(format!("Timed out checking {}", candidate.display())),
+    }
+  }
+  if !path_missing_noted {
+    notes.push("Not found on PATH".to_string());
   }
 
   (None, false, notes)
 }
 
+/// Validates and canonicalizes a `projectDir` argument.
+fn require_project_dir(project_dir: &str) -> Result<String, OpenWorkError> {
+  let project_dir = paths::allowed_dir(project_dir, "projectDir")?;
+  Ok(project_dir.to_string_lossy().to_string())
+}
+
+#[tracing::instrument(level = "debug", skip_all, fields(program = %command.get_program().to_string_lossy()))]
 fn run_capture_optional(command: &mut Command) -> Result<Option<ExecResult>, String> {
-  match command.output() {
+  match exec::output(command) {
     Ok(output) => {
       let status = output.status.code().unwrap_or(-1);
+      tracing::debug!(status, "command finished");
       Ok(Some(ExecResult {
         ok: output.status.success(),
         status,
-        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        stdout: redact::redact(&String::from_utf8_lossy(&output.stdout)),
+        stderr: redact::redact(&String::from_utf8_lossy(&output.stderr)),
       }))
     }
-    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+      tracing::debug!("command not found");
+      Ok(None)
+    }
     Err(e) => Err(format!(
       "Failed to run {}: {e}",
       command.get_program().to_string_lossy()
@@ -239,63 +539,139 @@ fn run_capture_optional(command: &mut Command) -> Result<Option<ExecResult>, Str
   }
 }
 
-fn copy_dir_recursive(src: &Path, dest: &Path) -> Result<(), String> {
-  if !src.is_dir() {
-    return Err(format!("Source is not a directory: {}", src.display()));
+/// Deletes a file or directory. Unless `permanent` is set, the item goes to the
+/// OS trash so it can be recovered.
+fn remove_path(path: &Path, permanent: bool) -> Result<(), String> {
+  if !permanent {
+    return trash::delete(path).map_err(|e| format!("Failed to move {} to trash: {e}", path.display()));
   }
 
-  fs::create_dir_all(dest).map_err(|e| format!("Failed to create dir {}: {e}", dest.display()))?;
-
-  for entry in fs::read_dir(src).map_err(|e| format!("Failed to read dir {}: {e}", src.display()))? {
-    let entry = entry.map_err(|e| e.to_string())?;
-    let file_type = entry.file_type().map_err(|e| e.to_string())?;
-
-    let from = entry.path();
-    let to = dest.join(entry.file_name());
-
-    if file_type.is_dir() {
-      copy_dir_recursive(&from, &to)?;
-      continue;
-    }
-
-    if file_type.is_file() {
-      fs::copy(&from, &to)
-        .map_err(|e| format!("Failed to copy {} -> {}: {e}", from.display(), to.display()))?;
-      continue;
-    }
-
-    // Skip symlinks and other non-regular entries.
-  }
-
-  Ok(())
+  let target = paths::extended(path);
+  let result = if target.is_dir() {
+    fs::remove_dir_all(&target)
+  } else {
+    fs::remove_file(&target)
+  };
+  result.map_err(|e| format!("Failed to remove {}: {e}", path.display()))
 }
 
-fn resolve_opencode_config_path(scope: &str, project_dir: &str) -> Result<PathBuf, String> {
+fn resolve_opencode_config_path(scope: &str, project_dir: &str) -> Result<PathBuf, OpenWorkError> {
   match scope {
-    "project" => {
-      if project_dir.trim().is_empty() {
-        return Err("projectDir is required".to_string());
-      }
-      Ok(PathBuf::from(project_dir).join("opencode.json"))
-    }
+    "project" => Ok(paths::allowed_dir(project_dir, "projectDir")?.join("opencode.json")),
     "global" => {
-      let base = if let Ok(dir) = env::var("XDG_CONFIG_HOME") {
-        PathBuf::from(dir)
-      } else if let Ok(home) = env::var("HOME") {
-        PathBuf::from(home).join(".config")
+      let base = if let Some(dir) = portable::opencode_xdg_dir("XDG_CONFIG_HOME") {
+        dir
+      } else if let Some(dir) = app_sandbox::xdg_dir("XDG_CONFIG_HOME") {
+        dir
+      } else if let Some(home) = home_dir() {
+        home.join(".config")
       } else {
-        return Err("Unable to resolve config directory".to_string());
+        return Err("Unable to resolve config directory".into());
       };
 
       Ok(base.join("opencode").join("opencode.json"))
     }
-    _ => Err("scope must be 'project' or 'global'".to_string()),
+    _ => Err(OpenWorkError::invalid_argument("scope must be 'project' or 'global'")),
+  }
+}
+
+/// Parses an opencode config, or starts one when there's none.
+fn parse_opencode_config(text: Option<&str>, path: &Path) -> Result<serde_json::Value, OpenWorkError> {
+  let Some(text) = text.filter(|text| !text.trim().is_empty()) else {
+    return Ok(serde_json::json!({ "$schema": "https://opencode.ai/config.json" }));
+  };
+  let config: serde_json::Value = serde_json::from_str(&jsonc::strip(text)).map_err(|e| {
+    OpenWorkError::new(
+      ErrorCode::ConfigInvalid,
+      format!("Failed to parse {}: {e}", path.display()),
+    )
+    .with_details(serde_json::json!({ "path": path.to_string_lossy() }))
+  })?;
+  if !config.is_object() {
+    return Err(OpenWorkError::new(
+      ErrorCode::ConfigInvalid,
+      format!("{} is not a JSON object", path.display()),
+    ));
   }
+  Ok(config)
+}
+
+/// Locks engine state, including workspace engines and the install slot.
+/// Every update leaves that state consistent, so a panic elsewhere while
+/// the lock was held is logged and the state reused, rather than failing
+/// every engine command from then on.
+fn lock_engines<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+  mutex.lock().unwrap_or_else(|poisoned| {
+    tracing::warn!("engine lock poisoned by an earlier panic, recovering");
+    mutex.clear_poison();
+    poisoned.into_inner()
+  })
 }
 
 impl EngineManager {
+  /// The main window's engine.
+  fn main_engine(&self) -> MutexGuard<'_, EngineState> {
+    lock_engines(&self.inner)
+  }
+
+  /// Engines of project windows.
+  fn window_engines(&self) -> MutexGuard<'_, HashMap<String, EngineState>> {
+    lock_engines(&self.windows)
+  }
+
+  /// Runs `f` on the engine owned by the window labelled `window`. Keep `f`
+  /// short: `engine_info` waits on the same lock.
+  fn with_window<R>(&self, window: &str, f: impl FnOnce(&mut EngineState) -> R) -> R {
+    if window == instance::MAIN_WINDOW {
+      return f(&mut self.main_engine());
+    }
+    f(self.window_engines().entry(window.to_string()).or_default())
+  }
+
+  /// Labels of the windows that have an engine, running or attached.
+  fn engine_windows(&self) -> Vec<String> {
+    let mut windows: Vec<String> = self
+      .window_engines()
+      .iter()
+      .filter(|(_, state)| state.base_url.is_some())
+      .map(|(label, _)| label.clone())
+      .collect();
+    if self.main_engine().base_url.is_some() {
+      windows.insert(0, instance::MAIN_WINDOW.to_string());
+    }
+    windows
+  }
+
+  fn stop_all(&self) {
+    let mut children = vec![Self::detach_locked(&mut self.main_engine())];
+    children.extend(self.window_engines().values_mut().map(Self::detach_locked));
+    children.into_iter().for_each(Self::reap);
+  }
+
+  /// Label of the window whose engine is running `project_dir`, if any.
+  fn window_for_project(&self, project_dir: &str) -> Option<String> {
+    let running = |state: &mut EngineState| {
+      Self::snapshot_locked(state).running && state.project_dir.as_deref() == Some(project_dir)
+    };
+    if running(&mut self.main_engine()) {
+      return Some(instance::MAIN_WINDOW.to_string());
+    }
+    self
+      .window_engines()
+      .iter_mut()
+      .find(|(_, state)| running(state))
+      .map(|(label, _)| label.clone())
+  }
+
   fn snapshot_locked(state: &mut EngineState) -> EngineInfo {
     let (running, pid) = match state.child.as_mut() {
+      None if state.attached.is_some() => {
+        let reachable = match (&state.hostname, state.port) {
+          (Some(hostname), Some(port)) => engine_lock::reachable(hostname, port),
+          _ => false,
+        };
+        (reachable, state.attached)
+      }
       None => (false, None),
       Some(child) => match child.try_wait() {
         Ok(Some(_status)) => {
@@ -315,45 +691,119 @@ impl EngineManager {
       hostname: state.hostname.clone(),
       port: state.port,
       pid,
+      auth_token: state.auth_token.clone(),
+      permission_profile: state.permission_profile.clone(),
+      active_env_profile: state.env_profile.clone(),
+      attached: state.attached.is_some(),
+      container: state.container.clone(),
     }
   }
 
-  fn stop_locked(state: &mut EngineState) {
-    if let Some(mut child) = state.child.take() {
-      let _ = child.kill();
-      let _ = child.wait();
+  /// Clears `state` and hands back its process, so it can be stopped after
+  /// the lock is released.
+  fn detach_locked(state: &mut EngineState) -> Option<Child> {
+    let child = state.child.take();
+    if let Some(child) = &child {
+      tracing::info!(pid = child.id(), project_dir = ?state.project_dir, "stopping engine");
+    }
+    if std::mem::take(&mut state.locked) {
+      if let Some(project_dir) = &state.project_dir {
+        engine_lock::release(project_dir);
+      }
+    }
+    if let Some(pid) = state.attached.take() {
+      tracing::info!(pid, project_dir = ?state.project_dir, "detaching from engine of another instance");
     }
     state.base_url = None;
     state.project_dir = None;
     state.hostname = None;
     state.port = None;
+    if let Some(token) = state.auth_token.take() {
+      redact::unregister_secret(&token);
+    }
+    state.permission_profile = None;
+    state.env_profile = None;
+    child
+  }
+
+  /// Kills and waits for an engine process taken out by `detach_locked`.
+  fn reap(child: Option<Child>) {
+    if let Some(mut child) = child {
+      let _ = child.kill();
+      let _ = child.wait();
+    }
+  }
+
+  /// Stops an engine that isn't behind the manager's locks, such as one
+  /// already removed from its map.
+  fn stop_locked(state: &mut EngineState) {
+    Self::reap(Self::detach_locked(state));
   }
 }
 
 #[tauri::command]
-fn engine_info(manager: State<EngineManager>) -> EngineInfo {
-  let mut state = manager.inner.lock().expect("engine mutex poisoned");
-  EngineManager::snapshot_locked(&mut state)
+fn engine_info(window: WebviewWindow, manager: State<EngineManager>) -> EngineInfo {
+  engine_info_for(&manager, window.label())
+}
+
+fn engine_info_for(manager: &EngineManager, window: &str) -> EngineInfo {
+  manager.with_window(window, EngineManager::snapshot_locked)
 }
 
 #[tauri::command]
-fn engine_stop(manager: State<EngineManager>) -> EngineInfo {
-  let mut state = manager.inner.lock().expect("engine mutex poisoned");
-  EngineManager::stop_locked(&mut state);
-  EngineManager::snapshot_locked(&mut state)
+fn engine_stop(app: AppHandle, window: WebviewWindow, manager: State<EngineManager>) -> EngineInfo {
+  stop_engine(&app, &manager, window.label())
+}
+
+/// Stops the engine for `window`, then runs its project's stop hooks.
+fn stop_engine(app: &AppHandle, manager: &EngineManager, window: &str) -> EngineInfo {
+  let (child, project_dir, info) = manager.with_window(window, |state| {
+    let project_dir = state.project_dir.clone();
+    (EngineManager::detach_locked(state), project_dir, EngineManager::snapshot_locked(state))
+  });
+  reap_engine(app, child, project_dir);
+  info
+}
+
+/// Stops an engine process taken out by `detach_locked`, then runs the stop
+/// hooks of its project.
+fn reap_engine(app: &AppHandle, child: Option<Child>, project_dir: Option<String>) {
+  let stopped = child.is_some();
+  EngineManager::reap(child);
+  if let Some(project_dir) = project_dir.filter(|_| stopped) {
+    engine_hooks::after_stop(app, &project_dir);
+  }
 }
 
+/// `force` skips the cached resolution and re-probes the binary.
 #[tauri::command]
-fn engine_doctor() -> EngineDoctorResult {
-  let (resolved, in_path, notes) = resolve_opencode_executable();
-
-  let (version, supports_serve) = match resolved.as_ref() {
-    Some(path) => (
-      opencode_version(path.as_os_str()),
-      opencode_supports_serve(path.as_os_str()),
-    ),
-    None => (None, false),
+fn engine_doctor(cache: State<EngineCache>, force: Option<bool>) -> EngineDoctorResult {
+  doctor_report(&cache, force.unwrap_or(false))
+}
+
+fn doctor_report(cache: &EngineCache, force: bool) -> EngineDoctorResult {
+  let deadline = Instant::now() + DOCTOR_TIMEOUT;
+  let (resolved, in_path, mut notes) = cache.resolve_before(force, deadline);
+
+  let (version, supports_serve, json_logs) = match resolved.as_ref() {
+    Some(path) => {
+      let probe = cache.probe(path, deadline);
+      (probe.version, probe.supports_serve, probe.json_log_args.is_some())
+    }
+    None => (None, false, false),
   };
+  if resolved.is_some() && Instant::now() >= deadline {
+    notes.push(format!("Doctor timed out after {}s", DOCTOR_TIMEOUT.as_secs()));
+  }
+  let binary_arch = resolved.as_deref().map(arch::binary_arches).unwrap_or_default();
+  notes.extend(arch::mismatch_note(&binary_arch));
+  if let Some(path) = resolved.as_deref() {
+    notes.extend(nix::foreign_binary_note(path, !binary_arch.is_empty()));
+  }
+  let exposed_configs = private_files::exposed();
+  notes.extend(exposed_configs.iter().map(|file| {
+    format!("{} may hold API keys and other users can read it ({})", file.path, file.access)
+  }));
 
   EngineDoctorResult {
     found: resolved.is_some(),
@@ -361,123 +811,466 @@ fn engine_doctor() -> EngineDoctorResult {
     resolved_path: resolved.map(|path| path.to_string_lossy().to_string()),
     version,
     supports_serve,
+    json_logs,
     notes,
+    version_managers: version_managers::detected(),
+    sandbox: app_sandbox::current(),
+    binary_arch,
+    host_arch: arch::host(),
+    nix: nix::current(),
+    gh: github::detect(deadline),
+    local_models: local_models::detect(deadline),
+    exposed_configs,
   }
 }
 
-#[tauri::command]
-fn engine_install() -> Result<ExecResult, String> {
+/// The engine install in progress, for `engine_install_cancel`.
+#[derive(Default)]
+struct EngineInstall(Mutex<Option<cancel::CancelToken>>);
+
+/// Clears the install slot when the install ends, however it ends.
+#[cfg(not(windows))]
+struct EngineInstallSlot<'a>(&'a EngineInstall);
+
+#[cfg(not(windows))]
+impl Drop for EngineInstallSlot<'_> {
+  fn drop(&mut self) {
+    *lock_engines(&self.0 .0) = None;
+  }
+}
+
+// Runs off the main thread so `engine_install_cancel` can be handled meanwhile.
+#[tauri::command(async)]
+fn engine_install(
+  app: AppHandle,
+  sandbox: Option<bool>,
+) -> Result<installer::InstallResult, OpenWorkError> {
   #[cfg(windows)]
   {
-    return Ok(ExecResult {
-      ok: false,
-      status: -1,
-      stdout: String::new(),
-      stderr: "Guided install is not supported on Windows yet. Install OpenCode via:\n- npm install -g opencode-ai\n- https://opencode.ai/install\n\nThen restart OpenWork.".to_string(),
+    let _ = (app, sandbox);
+    return Ok(installer::InstallResult {
+      result: ExecResult {
+        ok: false,
+        status: -1,
+        stdout: String::new(),
+        stderr: "Guided install is not supported on Windows yet. Install OpenCode via:\n- npm install -g opencode-ai\n- https://opencode.ai/install\n\nThen restart OpenWork.".to_string(),
+      },
+      sandbox: installer::SandboxLevel::Restricted,
     });
   }
 
   #[cfg(not(windows))]
   {
-    let install_dir = home_dir()
-      .unwrap_or_else(|| PathBuf::from("."))
-      .join(".opencode")
-      .join("bin");
-
-    let output = Command::new("bash")
-      .arg("-lc")
-      .arg("curl -fsSL https://opencode.ai/install | bash")
-      .env("OPENCODE_INSTALL_DIR", install_dir)
-      .output()
-      .map_err(|e| format!("Failed to run installer: {e}"))?;
-
-    let status = output.status.code().unwrap_or(-1);
-    Ok(ExecResult {
-      ok: output.status.success(),
-      status,
-      stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-      stderr: String::from_utf8_lossy(&output.stderr).to_string(),
-    })
+    // Portable installs stay beside the app.
+    let opencode_dir = match portable::dir("tools") {
+      Some(tools) => tools.join("opencode"),
+      None => home_dir().unwrap_or_else(|| PathBuf::from(".")).join(".opencode"),
+    };
+    let install_dir = opencode_dir.join("bin");
+    disk_space::ensure(&opencode_dir, disk_space::ENGINE_INSTALL_BYTES)?;
+    network::require_online(&app, "Installing OpenCode")?;
+
+    let install = app.state::<EngineInstall>();
+    let token = {
+      let mut running = lock_engines(&install.0);
+      if running.is_some() {
+        return Err(OpenWorkError::new(
+          ErrorCode::AlreadyExists,
+          "OpenCode is already being installed",
+        ));
+      }
+      running.insert(cancel::CancelToken::default()).clone()
+    };
+    let _slot = EngineInstallSlot(&install);
+
+    let _task = task_indicator::begin(&app, "engine.install");
+    // The script's prebuilt binary can't run on NixOS; nix builds one that can.
+    let result = if nix::current() == nix::NixEnv::NixOs {
+      match nix::install_command() {
+        Some((program, args)) => installer::run_package_manager(&program, &args, &token),
+        None => Err(OpenWorkError::from(format!(
+          "nix was not found. Install OpenCode with {}",
+          nix::INSTALL_HINT
+        ))),
+      }
+    } else {
+      let progress_app = app.clone();
+      installer::run_script(
+        "https://opencode.ai/install",
+        &[opencode_dir],
+        &[("OPENCODE_INSTALL_DIR", &install_dir)],
+        sandbox.unwrap_or(true),
+        &token,
+        move |progress| {
+          let _ = progress_app.emit(download::DOWNLOAD_PROGRESS_EVENT, progress);
+        },
+      )
+    };
+    match &result {
+      Err(e) if e.code == ErrorCode::Cancelled => {
+        tracing::info!("engine install cancelled");
+        telemetry::record("engine.install.cancelled");
+      }
+      _ => telemetry::record_outcome("engine.install", result.as_ref().is_ok_and(|r| r.result.ok)),
+    }
+    // The install may have replaced the binary or put a new one ahead of it.
+    app.state::<EngineCache>().invalidate();
+    result
   }
 }
 
+/// Stops a running `engine_install`, which then fails with `CANCELLED`.
+/// `false` when no install is running.
 #[tauri::command]
-fn engine_start(manager: State<EngineManager>, project_dir: String) -> Result<EngineInfo, String> {
-  let project_dir = project_dir.trim().to_string();
-  if project_dir.is_empty() {
-    return Err("projectDir is required".to_string());
+fn engine_install_cancel(install: State<EngineInstall>) -> bool {
+  match lock_engines(&install.0).as_ref() {
+    Some(token) => {
+      token.cancel();
+      true
+    }
+    None => false,
   }
+}
 
-  let hostname = "127.0.0.1".to_string();
-  let port = find_free_port()?;
-
-  let mut state = manager.inner.lock().expect("engine mutex poisoned");
-
-  // Stop any existing engine first.
-  EngineManager::stop_locked(&mut state);
-
-  let (program, _in_path, notes) = resolve_opencode_executable();
+/// The opencode binary, or `ENGINE_NOT_FOUND` with install instructions.
+fn engine_program(app: &AppHandle) -> Result<PathBuf, OpenWorkError> {
+  let (program, _in_path, notes) = app.state::<EngineCache>().resolve(false);
   let Some(program) = program else {
     let notes_text = notes.join("\n");
     #[cfg(windows)]
-    return Err(format!(
+    let message = format!(
       "OpenCode CLI not found.\n\nInstall with:\n- npm install -g opencode-ai\n- https://opencode.ai/install\n\nNotes:\n{notes_text}"
-    ));
+    );
     #[cfg(not(windows))]
-    return Err(format!(
-      "OpenCode CLI not found.\n\nInstall with:\n- npm install -g opencode-ai\n- brew install anomalyco/tap/opencode\n- curl -fsSL https://opencode.ai/install | bash\n\nNotes:\n{notes_text}"
-    ));
+    let message = if nix::current() == nix::NixEnv::NixOs {
+      format!("OpenCode CLI not found.\n\nInstall with:\n- {}\n\nNotes:\n{notes_text}", nix::INSTALL_HINT)
+    } else {
+      format!(
+        "OpenCode CLI not found.\n\nInstall with:\n- npm install -g opencode-ai\n- brew install anomalyco/tap/opencode\n- curl -fsSL https://opencode.ai/install | bash\n\nNotes:\n{notes_text}"
+      )
+    };
+    let details = serde_json::json!({ "notes": notes });
+    return Err(OpenWorkError::new(ErrorCode::EngineNotFound, message).with_details(details));
   };
+  Ok(program)
+}
 
-  let mut command = Command::new(&program);
+/// The arguments for JSON logs when `program` supports them, from the cached
+/// probe of the binary.
+fn engine_log_args(app: &AppHandle, program: &Path) -> Vec<String> {
+  let probe = app.state::<EngineCache>().probe(program, Instant::now() + RESOLVE_TIMEOUT);
+  probe.json_log_args.unwrap_or_default()
+}
+
+/// `opencode serve` arguments for an engine listening on `bind`:`port` that
+/// accepts requests from `origins` (see `cors`). `log_args` switch it to JSON
+/// logs when it supports them (see `engine_log::json_log_args`).
+fn engine_args(bind: &str, port: &str, origins: &[String], log_args: &[String]) -> Vec<String> {
+  let mut args: Vec<String> = ["serve", "--print-logs", "--hostname", bind, "--port", port]
+    .iter()
+    .map(|arg| arg.to_string())
+    .collect();
+  args.extend_from_slice(log_args);
+  for origin in origins {
+    args.push("--cors".to_string());
+    args.push(origin.clone());
+  }
+  args
+}
+
+/// The engine command on this machine. Its environment is layered in order:
+/// the env policy, the project's `.env` selection, the environment profile,
+/// then OpenWork's own variables, which nothing before can override.
+fn host_engine_command(
+  program: &Path,
+  project_dir: &str,
+  args: &[String],
+  profile_env: &[(String, String)],
+  auth_token: &str,
+  overlay: Option<&str>,
+) -> Command {
+  let mut command = exec::command(program);
+  env_policy::apply(&mut command, env_policy::EnvTarget::Engine);
+  dotenv::inject(&mut command, project_dir);
+  command.envs(profile_env.iter().map(|(key, value)| (key, value)));
+  version_managers::prepend_node_dir(&mut command, program);
+  portable::apply_opencode_env(&mut command);
   command
-    .arg("serve")
-    .arg("--hostname")
-    .arg(&hostname)
-    .arg("--port")
-    .arg(port.to_string())
-    // Allow the Vite dev server origin, plus common Tauri origins.
-    .arg("--cors")
-    .arg("http://localhost:5173")
-    .arg("--cors")
-    .arg("tauri://localhost")
-    .arg("--cors")
-    .arg("http://tauri.localhost")
-    .current_dir(&project_dir)
-    .stdin(Stdio::null())
-    .stdout(Stdio::null())
-    .stderr(Stdio::null());
+    .env("OPENCODE_SERVER_USERNAME", ENGINE_AUTH_USERNAME)
+    .env("OPENCODE_SERVER_PASSWORD", auth_token)
+    .args(args)
+    .current_dir(project_dir);
+  if let Some(overlay) = overlay {
+    command.env("OPENCODE_CONFIG_CONTENT", overlay);
+  }
+  command
+}
 
-  let child = command
-    .spawn()
-    .map_err(|e| format!("Failed to start opencode: {e}"))?;
+/// The variables passed into a devcontainer engine: the project's `.env`
+/// selection, the environment profile and OpenWork's own; the container
+/// supplies the rest.
+fn container_engine_env(
+  project_dir: &str,
+  profile_env: &[(String, String)],
+  auth_token: &str,
+  overlay: Option<&str>,
+) -> Vec<(String, String)> {
+  let mut injected = Command::new("opencode");
+  dotenv::inject(&mut injected, project_dir);
+  injected.envs(profile_env.iter().map(|(key, value)| (key, value)));
+  let mut env: Vec<(String, String)> = injected
+    .get_envs()
+    .filter_map(|(key, value)| Some((key.to_string_lossy().to_string(), value?.to_string_lossy().to_string())))
+    .collect();
+  env.push(("OPENCODE_SERVER_USERNAME".to_string(), ENGINE_AUTH_USERNAME.to_string()));
+  env.push(("OPENCODE_SERVER_PASSWORD".to_string(), auth_token.to_string()));
+  if let Some(overlay) = overlay {
+    env.push(("OPENCODE_CONFIG_CONTENT".to_string(), overlay.to_string()));
+  }
+  env
+}
 
-  state.child = Some(child);
-  state.project_dir = Some(project_dir);
-  state.hostname = Some(hostname.clone());
-  state.port = Some(port);
-  state.base_url = Some(format!("http://{hostname}:{port}"));
+/// Spawns `opencode serve` for `project_dir` on a free local port, or in the
+/// project's devcontainer when that's enabled. Its output is parsed into
+/// `engine://log` events.
+#[tracing::instrument(level = "info", skip_all, fields(project_dir = %project_dir))]
+fn spawn_engine(app: &AppHandle, project_dir: String) -> Result<EngineState, OpenWorkError> {
+  let port = find_free_port()?;
+  let container = if devcontainer::enabled_for(&project_dir) {
+    Some(devcontainer::prepare(&project_dir)?)
+  } else {
+    None
+  };
+  let hostname = container
+    .as_ref()
+    .map_or_else(|| "127.0.0.1".to_string(), |target| target.address.clone());
+
+  // Other local processes can reach the port; without the password they
+  // can't drive the agent or read the project through it.
+  let auth_token = consent::random_token(40);
+  redact::register_secret(&auth_token);
+
+  // Inside a container the engine listens on all of its interfaces; the
+  // container's address is only reachable from this machine.
+  let bind = if container.is_some() { "0.0.0.0" } else { hostname.as_str() };
+  let origins = cors::origins(app);
+  let overlay = permissions::overlay_for(&project_dir);
+  let permission_profile = overlay.as_ref().map(|(id, _)| id.clone());
+  let overlay = overlay.as_ref().map(|(_, overlay)| overlay.as_str());
+  let env_profile = env_profiles::resolve(&project_dir)?;
+  let profile_env = env_profile.as_ref().map_or(&[][..], |profile| profile.vars.as_slice());
+
+  let (mut command, label) = match &container {
+    Some(target) => {
+      // The container's opencode isn't probed, so it keeps plain-text logs.
+      let args = engine_args(bind, &port.to_string(), &origins, &[]);
+      let env = container_engine_env(&project_dir, profile_env, &auth_token, overlay);
+      let command = devcontainer::engine_command(target, &project_dir, &args, &env);
+      (command, format!("devcontainer {}", target.id))
+    }
+    None => {
+      let program = engine_program(app)?;
+      let args = engine_args(bind, &port.to_string(), &origins, &engine_log_args(app, &program));
+      let mut command = host_engine_command(&program, &project_dir, &args, profile_env, &auth_token, overlay);
+      command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped());
+      (command, program.display().to_string())
+    }
+  };
 
-  Ok(EngineManager::snapshot_locked(&mut state))
+  // The container engine runs until its stdin closes, which the child keeps
+  // open until it's stopped.
+  let spawned = if container.is_some() { exec::spawn_piped(&mut command) } else { exec::spawn(&mut command) };
+  let mut child = spawned.map_err(|e| {
+    tracing::error!(error = %e, program = %label, "failed to start engine");
+    OpenWorkError::new(ErrorCode::EngineStartFailed, format!("Failed to start opencode: {e}"))
+  })?;
+  tracing::info!(pid = child.id(), port, permission_profile = ?permission_profile, env_profile = ?env_profile.as_ref().map(|profile| &profile.name), container = ?container.as_ref().map(|target| &target.id), "engine started");
+  if let Some(stdout) = child.stdout.take() {
+    engine_log::forward(app.clone(), project_dir.clone(), engine_log::EngineStream::Stdout, stdout);
+  }
+  if let Some(stderr) = child.stderr.take() {
+    engine_log::forward(app.clone(), project_dir.clone(), engine_log::EngineStream::Stderr, stderr);
+  }
+  telemetry::record("engine.start");
+
+  Ok(EngineState {
+    child: Some(child),
+    project_dir: Some(project_dir),
+    hostname: Some(hostname.clone()),
+    port: Some(port),
+    base_url: Some(format!("http://{hostname}:{port}")),
+    auth_token: Some(auth_token),
+    permission_profile,
+    env_profile: env_profile.map(|profile| profile.name),
+    container,
+    attached: None,
+    locked: false,
+  })
 }
 
-#[tauri::command]
-fn opkg_install(project_dir: String, package: String) -> Result<ExecResult, String> {
-  let project_dir = project_dir.trim().to_string();
-  if project_dir.is_empty() {
-    return Err("projectDir is required".to_string());
+/// The checks every engine start makes: the budget, one engine per project
+/// in this instance, and an engine another instance runs, which is returned.
+/// `window` is the window starting it; workspace starts pass `None`.
+fn check_engine_start(
+  app: &AppHandle,
+  manager: &EngineManager,
+  window: Option<&str>,
+  project_dir: &str,
+) -> Result<Option<engine_lock::EngineLock>, OpenWorkError> {
+  budget::ensure_engine_start_allowed(app, project_dir)?;
+
+  // One engine per project: two engines would edit the same files.
+  if let Some(owner) = manager
+    .window_for_project(project_dir)
+    .filter(|owner| Some(owner.as_str()) != window)
+  {
+    return Err(
+      OpenWorkError::new(
+        ErrorCode::AlreadyExists,
+        format!("{project_dir} is already open in another window."),
+      )
+      .with_details(serde_json::json!({ "window": owner })),
+    );
+  }
+  let workspaces = app.state::<workspace::WorkspaceManager>();
+  if let Some(workspace_id) = workspaces.workspace_for_project(project_dir) {
+    return Err(
+      OpenWorkError::new(
+        ErrorCode::AlreadyExists,
+        format!("{project_dir} already runs as part of a workspace."),
+      )
+      .with_details(serde_json::json!({ "workspaceId": workspace_id })),
+    );
   }
 
-  let package = package.trim().to_string();
-  if package.is_empty() {
-    return Err("package is required".to_string());
+  Ok(engine_lock::running_elsewhere(project_dir))
+}
+
+/// Runs the start hooks of `project_dir` and spawns its engine. Call outside
+/// the manager's locks, and `engine_started` once the engine is stored.
+fn launch_engine(app: &AppHandle, project_dir: &str) -> Result<EngineState, OpenWorkError> {
+  engine_hooks::before_start(app, project_dir)?;
+  let mut started = spawn_engine(app, project_dir.to_string())?;
+  started.locked = true;
+  Ok(started)
+}
+
+/// Records a launched engine in `engine_lock` and runs its project's
+/// after-start hooks. Call after the engine it displaced was stopped, since
+/// that one's lock may be for the same project.
+fn engine_started(app: &AppHandle, info: &EngineInfo) {
+  let (Some(project_dir), Some(base_url)) = (&info.project_dir, &info.base_url) else {
+    return;
+  };
+  engine_lock::record(&engine_lock::EngineLock {
+    owner_pid: std::process::id(),
+    engine_pid: info.pid,
+    project_dir: project_dir.clone(),
+    hostname: info.hostname.clone().unwrap_or_default(),
+    port: info.port.unwrap_or_default(),
+    base_url: base_url.clone(),
+    auth_token: info.auth_token.clone().unwrap_or_default(),
+  });
+  engine_hooks::after_start(app, project_dir, base_url, info.auth_token.clone());
+}
+
+/// Starts the engine for `window`, replacing whatever it was running. When
+/// another OpenWork instance already runs one for the project, `attach`
+/// uses that engine; otherwise it's a conflict.
+fn start_engine(
+  app: &AppHandle,
+  manager: &EngineManager,
+  window: &str,
+  project_dir: &str,
+  attach: bool,
+) -> Result<EngineInfo, OpenWorkError> {
+  let project_dir = require_project_dir(project_dir)?;
+
+  if let Some(other) = check_engine_start(app, manager, Some(window), &project_dir)? {
+    if !attach {
+      return Err(engine_lock::conflict(&other));
+    }
+    stop_engine(app, manager, window);
+    redact::register_secret(&other.auth_token);
+    tracing::info!(owner_pid = other.owner_pid, port = other.port, "attaching to engine of another instance");
+    let attached = EngineState {
+      project_dir: Some(project_dir.clone()),
+      hostname: Some(other.hostname),
+      port: Some(other.port),
+      base_url: Some(other.base_url),
+      auth_token: Some(other.auth_token),
+      attached: Some(other.engine_pid.unwrap_or(other.owner_pid)),
+      ..EngineState::default()
+    };
+    let (displaced, info) = manager.with_window(window, |state| {
+      let displaced = EngineManager::detach_locked(state);
+      *state = attached;
+      (displaced, EngineManager::snapshot_locked(state))
+    });
+    EngineManager::reap(displaced);
+    recent::record(app, &project_dir);
+    return Ok(info);
   }
 
-  let mut opkg = Command::new("opkg");
+  // Stop any existing engine first. Stopping and spawning happen outside the
+  // lock so `engine_info` stays responsive meanwhile.
+  stop_engine(app, manager, window);
+  let started = launch_engine(app, &project_dir)?;
+  let (displaced, info) = manager.with_window(window, |state| {
+    // A concurrent start may have filled the slot in the meantime.
+    let displaced = EngineManager::detach_locked(state);
+    *state = started;
+    (displaced, EngineManager::snapshot_locked(state))
+  });
+  EngineManager::reap(displaced);
+  recent::record(app, &project_dir);
+  engine_started(app, &info);
+  Ok(info)
+}
+
+/// Starts the engine for `project_dir`. `env_profile` selects that
+/// environment profile for the project first, for this and later starts.
+/// `attach` uses an engine another OpenWork instance runs for the project
+/// instead of failing with the conflict.
+#[tauri::command]
+fn engine_start(
+  app: AppHandle,
+  window: WebviewWindow,
+  manager: State<EngineManager>,
+  project_dir: String,
+  env_profile: Option<String>,
+  attach: Option<bool>,
+) -> Result<EngineInfo, OpenWorkError> {
+  if let Some(name) = env_profile {
+    env_profiles::select(&app, &require_project_dir(&project_dir)?, Some(name))?;
+  }
+  start_engine(&app, &manager, window.label(), &project_dir, attach.unwrap_or(false))
+}
+
+#[tauri::command]
+fn opkg_install(app: AppHandle, project_dir: String, package: String) -> Result<ExecResult, OpenWorkError> {
+  let project_dir = require_project_dir(&project_dir)?;
+  let package = args::package_spec(&package, "package")?;
+  disk_space::ensure(Path::new(&project_dir), disk_space::PACKAGE_INSTALL_BYTES)?;
+  network::require_online(&app, "Installing packages")?;
+  let _task = task_indicator::begin(&app, "opkg.install");
+
+  // Installs in the same project wait for each other; see `opkg_queue`.
+  let result = opkg_queue::run(&app, &project_dir, "install", &package, || {
+    run_opkg_install(&project_dir, &package)
+  });
+  telemetry::record_outcome("opkg.install", result.as_ref().is_ok_and(|r| r.ok));
+  Ok(result?)
+}
+
+/// Tries the OpenPackage CLI under each name it may be installed as.
+fn run_opkg_install(project_dir: &str, package: &str) -> Result<ExecResult, String> {
+  let mut opkg = exec::command("opkg");
+  env_policy::apply(&mut opkg, env_policy::EnvTarget::Installer);
   opkg
     .arg("install")
-    .arg(&package)
-    .current_dir(&project_dir)
+    .arg(package)
+    .current_dir(project_dir)
     .stdin(Stdio::null())
     .stdout(Stdio::piped())
     .stderr(Stdio::piped());
@@ -486,11 +1279,12 @@ fn opkg_install(project_dir: String, package: String) -> Result<ExecResult, Stri
     return Ok(result);
   }
 
-  let mut openpackage = Command::new("openpackage");
+  let mut openpackage = exec::command("openpackage");
+  env_policy::apply(&mut openpackage, env_policy::EnvTarget::Installer);
   openpackage
     .arg("install")
-    .arg(&package)
-    .current_dir(&project_dir)
+    .arg(package)
+    .current_dir(project_dir)
     .stdin(Stdio::null())
     .stdout(Stdio::piped())
     .stderr(Stdio::piped());
@@ -499,13 +1293,14 @@ fn opkg_install(project_dir: String, package: String) -> Result<ExecResult, Stri
     return Ok(result);
   }
 
-  let mut pnpm = Command::new("pnpm");
+  let mut pnpm = exec::command("pnpm");
+  env_policy::apply(&mut pnpm, env_policy::EnvTarget::Installer);
   pnpm
     .arg("dlx")
     .arg("opkg")
     .arg("install")
-    .arg(&package)
-    .current_dir(&project_dir)
+    .arg(package)
+    .current_dir(project_dir)
     .stdin(Stdio::null())
     .stdout(Stdio::piped())
     .stderr(Stdio::piped());
@@ -514,12 +1309,13 @@ fn opkg_install(project_dir: String, package: String) -> Result<ExecResult, Stri
     return Ok(result);
   }
 
-  let mut npx = Command::new("npx");
+  let mut npx = exec::command("npx");
+  env_policy::apply(&mut npx, env_policy::EnvTarget::Installer);
   npx
     .arg("opkg")
     .arg("install")
-    .arg(&package)
-    .current_dir(&project_dir)
+    .arg(package)
+    .current_dir(project_dir)
     .stdin(Stdio::null())
     .stdout(Stdio::piped())
     .stderr(Stdio::piped());
@@ -536,55 +1332,349 @@ fn opkg_install(project_dir: String, package: String) -> Result<ExecResult, Stri
   })
 }
 
-#[tauri::command]
-fn import_skill(project_dir: String, source_dir: String, overwrite: bool) -> Result<ExecResult, String> {
-  let project_dir = project_dir.trim().to_string();
-  if project_dir.is_empty() {
-    return Err("projectDir is required".to_string());
-  }
+pub const SKILL_IMPORT_PROGRESS_EVENT: &str = "skill://import-progress";
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct SkillImportProgress {
+  name: String,
+  #[serde(flatten)]
+  progress: dir_copy::CopyProgress,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct SkillImportResult {
+  #[serde(flatten)]
+  result: ExecResult,
+  /// What the skill declares it needs, and what of that is missing.
+  dependencies: skill_deps::DependencyReport,
+}
 
-  let source_dir = source_dir.trim().to_string();
-  if source_dir.is_empty() {
-    return Err("sourceDir is required".to_string());
+/// Copies a skill folder into the project. `exclude` lists file or folder
+/// names (one `*` wildcard allowed) to leave out; it defaults to
+/// `node_modules` and `.git`. `symlinks` defaults to preserving links that
+/// stay inside the skill. `name` replaces the folder's own name, e.g. to
+/// resolve a conflict. `install_dependencies` installs the opkg packages
+/// the skill declares; see `skill_deps`.
+// Runs off the main thread, since installing dependencies can take a while.
+#[tauri::command(async)]
+#[allow(clippy::too_many_arguments)]
+fn import_skill(
+  app: AppHandle,
+  consent: State<'_, consent::ConsentManager>,
+  project_dir: String,
+  source_dir: String,
+  overwrite: bool,
+  permanent: Option<bool>,
+  confirmation_id: Option<String>,
+  exclude: Option<Vec<String>>,
+  symlinks: Option<dir_copy::SymlinkPolicy>,
+  name: Option<String>,
+  install_dependencies: Option<bool>,
+) -> Result<SkillImportResult, OpenWorkError> {
+  let project_dir = paths::allowed_dir(&project_dir, "projectDir")?;
+  let src = paths::allowed_dir(&source_dir, "sourceDir")?;
+  let mut options = dir_copy::CopyOptions::default();
+  if let Some(patterns) = exclude {
+    options.exclude = patterns
+      .iter()
+      .map(|pattern| paths::file_name_segment(pattern, "exclude pattern"))
+      .collect::<Result<_, _>>()?;
+  }
+  if let Some(symlinks) = symlinks {
+    options.symlinks = symlinks;
   }
 
-  let src = PathBuf::from(&source_dir);
-  let name = src
-    .file_name()
-    .and_then(|s| s.to_str())
-    .ok_or_else(|| "Failed to infer skill name from directory".to_string())?;
+  let name = match &name {
+    Some(name) => name.as_str(),
+    None => src
+      .file_name()
+      .and_then(|s| s.to_str())
+      .ok_or_else(|| "Failed to infer skill name from directory".to_string())?,
+  };
+  let name = paths::file_name_segment(name, "skill name")?;
+
+  let overwrite = overwrite.then(|| SkillOverwrite {
+    consent: &consent,
+    permanent: permanent.unwrap_or(false),
+    confirmation_id: confirmation_id.as_deref(),
+  });
+  let install_dependencies = install_dependencies.unwrap_or(false);
+  install_skill_dir(&app, &project_dir, &src, &name, overwrite, &options, install_dependencies)
+}
 
-  let dest = PathBuf::from(&project_dir)
-    .join(".opencode")
-    .join("skill")
-    .join(name);
+/// Consent details for replacing an existing skill.
+struct SkillOverwrite<'a> {
+  consent: &'a consent::ConsentManager,
+  permanent: bool,
+  confirmation_id: Option<&'a str>,
+}
 
-  if dest.exists() {
-    if overwrite {
-      fs::remove_dir_all(&dest)
-        .map_err(|e| format!("Failed to remove existing skill dir {}: {e}", dest.display()))?;
+/// Copies `src` into the project's skill folder as `name`, then checks the
+/// skill's dependencies.
+fn install_skill_dir(
+  app: &AppHandle,
+  project_dir: &Path,
+  src: &Path,
+  name: &str,
+  overwrite: Option<SkillOverwrite<'_>>,
+  options: &dir_copy::CopyOptions,
+  install_dependencies: bool,
+) -> Result<SkillImportResult, OpenWorkError> {
+  // Read first, so a malformed declaration doesn't leave a copied skill.
+  let dependencies = skill_deps::read(src)?;
+  let dest = paths::resolve_within(project_dir, &format!(".opencode/skill/{name}"))?;
+
+  // A folder whose name differs only in case is the same folder on macOS and
+  // Windows. It's a conflict everywhere, since projects move between machines.
+  let skills_dir = dest.parent().map(Path::to_path_buf).unwrap_or_default();
+  let siblings: Vec<String> = fs::read_dir(&skills_dir)
+    .map(|entries| {
+      entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect()
+    })
+    .unwrap_or_default();
+  let case_conflict = paths::case_conflict(siblings.iter().map(String::as_str), name);
+  let existing = match case_conflict {
+    Some(other) => Some(skills_dir.join(other)),
+    None => dest.exists().then(|| dest.clone()),
+  };
+  let overlaps = |path: &Path| path.starts_with(src) || src.starts_with(path);
+  if overlaps(&dest) || existing.as_deref().is_some_and(overlaps) {
+    return Err(OpenWorkError::invalid_argument(
+      "sourceDir must not overlap the destination skill folder",
+    ));
+  }
+
+  if let Some(existing) = existing {
+    if let Some(overwrite) = overwrite {
+      let existing_name = existing.file_name().unwrap_or_default().to_string_lossy();
+      overwrite.consent.require(
+        "overwrite_skill",
+        &existing.to_string_lossy(),
+        &format!("Replace the existing skill '{existing_name}'?"),
+        overwrite.confirmation_id,
+      )?;
+      remove_path(&existing, overwrite.permanent)?;
+    } else if let Some(other) = case_conflict {
+      return Err(
+        OpenWorkError::new(
+          ErrorCode::AlreadyExists,
+          format!(
+            "A skill named '{other}' already exists, and on macOS and Windows it is the same folder as \
+             '{name}'. Replace it, or import under a different name."
+          ),
+        )
+        .with_details(serde_json::json!({
+          "conflict": "case",
+          "existing": other,
+          "requested": name,
+          "resolutions": ["replace", "rename"],
+        })),
+      );
     } else {
-      return Err(format!("Skill already exists at {}", dest.display()));
+      return Err(OpenWorkError::new(
+        ErrorCode::AlreadyExists,
+        format!("Skill already exists at {}", dest.display()),
+      ));
     }
   }
 
-  copy_dir_recursive(&src, &dest)?;
+  let done = dir_copy::copy_dir(src, &dest, options, |progress| {
+    let _ = app.emit(
+      SKILL_IMPORT_PROGRESS_EVENT,
+      SkillImportProgress {
+        name: name.to_string(),
+        progress,
+      },
+    );
+  })?;
+  // Skills may come with plugins whose dependencies opencode installs there.
+  gitignore::ensure_generated(project_dir, &[gitignore::OPENCODE_DEPENDENCIES]);
+
+  let dependencies = skill_deps::resolve(app, project_dir, &dependencies, install_dependencies);
+  Ok(SkillImportResult {
+    result: ExecResult {
+      ok: true,
+      status: 0,
+      stdout: format!("Imported skill to {} ({} files)", dest.display(), done.files_copied),
+      stderr: dependencies.summary().unwrap_or_default(),
+    },
+    dependencies,
+  })
+}
+
+/// The skill inside an unpacked archive: the root itself, or its only
+/// top-level folder. Returns the folder and the name to install it under.
+fn archived_skill(unpacked: &Path, archive_name: &str) -> Result<(PathBuf, String), OpenWorkError> {
+  if unpacked.join("SKILL.md").is_file() {
+    return Ok((unpacked.to_path_buf(), archive_name.to_string()));
+  }
+  let skills: Vec<PathBuf> = fs::read_dir(unpacked)
+    .map_err(|e| format!("Failed to read dir {}: {e}", unpacked.display()))?
+    .filter_map(Result::ok)
+    .map(|entry| entry.path())
+    .filter(|path| path.is_dir() && path.join("SKILL.md").is_file())
+    .collect();
+  match skills.as_slice() {
+    [skill] => {
+      let name = skill.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+      Ok((skill.clone(), name))
+    }
+    [] => Err(OpenWorkError::invalid_argument("The archive does not contain a SKILL.md")),
+    _ => Err(OpenWorkError::invalid_argument(format!(
+      "The archive contains {} skills; unpack it and import them one at a time",
+      skills.len()
+    ))),
+  }
+}
+
+/// Imports a skill from a `.zip`, `.tar` or `.tar.gz`. The archive is
+/// unpacked to a temporary folder first, with the usual archive limits.
+/// `name` replaces the name taken from the archive, and
+/// `install_dependencies` works as for `import_skill`.
+#[tauri::command(async)]
+#[allow(clippy::too_many_arguments)]
+fn import_skill_archive(
+  app: AppHandle,
+  consent: State<'_, consent::ConsentManager>,
+  project_dir: String,
+  archive_path: String,
+  overwrite: bool,
+  permanent: Option<bool>,
+  confirmation_id: Option<String>,
+  name: Option<String>,
+  install_dependencies: Option<bool>,
+) -> Result<SkillImportResult, OpenWorkError> {
+  let install_dependencies = install_dependencies.unwrap_or(false);
+  let project_dir = paths::allowed_dir(&project_dir, "projectDir")?;
+  let archive_file = paths::allowed_file(&archive_path, "archivePath")?;
+
+  let unpacked = env::temp_dir().join(format!("openwork-skill-{}", consent::random_token(12)));
+  let result = archive::extract(&archive_file, &unpacked, &archive::ArchiveLimits::default(), |progress| {
+    let _ = app.emit(archive::ARCHIVE_PROGRESS_EVENT, progress);
+  })
+  .and_then(|_| {
+    let archive_name = archive::strip_archive_suffix(&archive_file);
+    let (src, archived_name) = archived_skill(&unpacked, &archive_name)?;
+    let name = paths::file_name_segment(name.as_deref().unwrap_or(&archived_name), "skill name")?;
+    let overwrite = overwrite.then(|| SkillOverwrite {
+      consent: &consent,
+      permanent: permanent.unwrap_or(false),
+      confirmation_id: confirmation_id.as_deref(),
+    });
+    let options = dir_copy::CopyOptions::default();
+    install_skill_dir(&app, &project_dir, &src, &name, overwrite, &options, install_dependencies)
+  });
+  let _ = fs::remove_dir_all(&unpacked);
+  result
+}
+
+/// Imports a skill from a link: a `.zip`, `.tar` or `.tar.gz` URL, or a
+/// GitHub repository or folder page (see `skill_url`). `name` replaces the
+/// name taken from the link or the archive, and `install_dependencies` works
+/// as for `import_skill`.
+// Runs off the main thread, since the download can take a while.
+#[tauri::command(async)]
+#[allow(clippy::too_many_arguments)]
+fn import_skill_from_url(
+  app: AppHandle,
+  consent: State<'_, consent::ConsentManager>,
+  project_dir: String,
+  url: String,
+  overwrite: bool,
+  permanent: Option<bool>,
+  confirmation_id: Option<String>,
+  name: Option<String>,
+  install_dependencies: Option<bool>,
+) -> Result<SkillImportResult, OpenWorkError> {
+  let install_dependencies = install_dependencies.unwrap_or(false);
+  let project_dir = paths::allowed_dir(&project_dir, "projectDir")?;
+  let source = skill_url::parse(&url)?;
+  network::require_online(&app, "Importing a skill from a link")?;
+
+  let work_dir = env::temp_dir().join(format!("openwork-skill-{}", consent::random_token(12)));
+  let result = skill_url::download(&source, &work_dir, |progress| {
+    let _ = app.emit(download::DOWNLOAD_PROGRESS_EVENT, progress);
+  })
+  .and_then(|archive_file| {
+    let unpacked = work_dir.join("unpacked");
+    archive::extract(&archive_file, &unpacked, &archive::ArchiveLimits::default(), |progress| {
+      let _ = app.emit(archive::ARCHIVE_PROGRESS_EVENT, progress);
+    })?;
+    let (src, archived_name) = match &source.subdir {
+      Some(subdir) => (skill_url::subdir_skill(&unpacked, subdir)?, source.archive_name.clone()),
+      None => archived_skill(&unpacked, &source.archive_name)?,
+    };
+    let name = name.as_deref().or(source.name.as_deref()).unwrap_or(&archived_name);
+    let name = paths::file_name_segment(name, "skill name")?;
+    let overwrite = overwrite.then(|| SkillOverwrite {
+      consent: &consent,
+      permanent: permanent.unwrap_or(false),
+      confirmation_id: confirmation_id.as_deref(),
+    });
+    let options = dir_copy::CopyOptions::default();
+    install_skill_dir(&app, &project_dir, &src, &name, overwrite, &options, install_dependencies)
+  });
+  let _ = fs::remove_dir_all(&work_dir);
+  result
+}
+
+#[tauri::command]
+fn remove_skill(
+  consent: State<consent::ConsentManager>,
+  project_dir: String,
+  name: String,
+  permanent: Option<bool>,
+  confirmation_id: Option<String>,
+) -> Result<ExecResult, OpenWorkError> {
+  let project_dir = paths::allowed_dir(&project_dir, "projectDir")?;
+  let name = paths::file_name_segment(&name, "skill name")?;
+  let dest = paths::resolve_within(&project_dir, &format!(".opencode/skill/{name}"))?;
+
+  if !dest.exists() {
+    return Err(OpenWorkError::new(
+      ErrorCode::NotFound,
+      format!("Skill not found at {}", dest.display()),
+    ));
+  }
+
+  let permanent = permanent.unwrap_or(false);
+  consent.require(
+    "remove_skill",
+    &dest.to_string_lossy(),
+    &if permanent {
+      format!("Permanently delete the skill '{name}'? This cannot be undone.")
+    } else {
+      format!("Move the skill '{name}' to the trash?")
+    },
+    confirmation_id.as_deref(),
+  )?;
+  remove_path(&dest, permanent)?;
 
   Ok(ExecResult {
     ok: true,
     status: 0,
-    stdout: format!("Imported skill to {}", dest.display()),
+    stdout: if permanent {
+      format!("Deleted skill {}", dest.display())
+    } else {
+      format!("Moved skill {} to trash", dest.display())
+    },
     stderr: String::new(),
   })
 }
 
 #[tauri::command]
-fn read_opencode_config(scope: String, project_dir: String) -> Result<OpencodeConfigFile, String> {
+fn read_opencode_config(scope: String, project_dir: String) -> Result<OpencodeConfigFile, OpenWorkError> {
   let path = resolve_opencode_config_path(scope.trim(), &project_dir)?;
-  let exists = path.exists();
+  let file = paths::extended(&path);
+  let exists = file.exists();
 
   let content = if exists {
-    Some(fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?)
+    Some(fs::read_to_string(&file).map_err(|e| format!("Failed to read {}: {e}", path.display()))?)
   } else {
     None
   };
@@ -598,19 +1688,41 @@ fn read_opencode_config(scope: String, project_dir: String) -> Result<OpencodeCo
 
 #[tauri::command]
 fn write_opencode_config(
+  consent: State<consent::ConsentManager>,
   scope: String,
   project_dir: String,
   content: String,
-) -> Result<ExecResult, String> {
+  confirmation_id: Option<String>,
+) -> Result<ExecResult, OpenWorkError> {
   let path = resolve_opencode_config_path(scope.trim(), &project_dir)?;
+  let file = paths::extended(&path);
+
+  let existing = fs::read_to_string(&file).ok();
+  if existing.is_some_and(|existing| existing != content) {
+    consent.require(
+      "overwrite_config",
+      &path.to_string_lossy(),
+      &format!("Overwrite {}?", path.display()),
+      confirmation_id.as_deref(),
+    )?;
+  }
+  write_config_file(&path, &content)
+}
 
-  if let Some(parent) = path.parent() {
+/// Writes an opencode config file, creating its directory.
+fn write_config_file(path: &Path, content: &str) -> Result<ExecResult, OpenWorkError> {
+  let file = paths::extended(path);
+  if let Some(parent) = file.parent() {
     fs::create_dir_all(parent)
-      .map_err(|e| format!("Failed to create config dir {}: {e}", parent.display()))?;
+      .map_err(|e| format!("Failed to create config dir {}: {e}", paths::display(parent)))?;
   }
 
-  fs::write(&path, content)
-    .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+  // The global config may hold provider keys.
+  if private_files::is_global_config(path) {
+    private_files::write(&file, content)?;
+  } else {
+    fs::write(&file, content).map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+  }
 
   Ok(ExecResult {
     ok: true,
@@ -620,21 +1732,263 @@ fn write_opencode_config(
   })
 }
 
+/// Entry point of `openwork-cli`.
+pub fn run_cli() -> std::process::ExitCode {
+  shell_path::init();
+  cli::main()
+}
+
 pub fn run() {
+  // Changes the environment, so it has to run before any other thread starts.
+  shell_path::init();
+  portable::init();
+
   tauri::Builder::default()
+    // Must be registered first so a second launch exits before doing any work.
+    .plugin(tauri_plugin_single_instance::init(instance::on_second_launch))
+    .plugin(tauri_plugin_deep_link::init())
     .plugin(tauri_plugin_dialog::init())
+    .plugin(tauri_plugin_notification::init())
+    .plugin(startup::plugin())
+    .plugin(tauri_plugin_updater::Builder::new().build())
     .manage(EngineManager::default())
-    .invoke_handler(tauri::generate_handler![
+    .manage(watcher::WatcherManager::default())
+    .manage(workspace::WorkspaceManager::default())
+    .manage(relay::EventRelay::default())
+    .manage(consent::ConsentManager::default())
+    .manage(log_viewer::LogFollower::default())
+    .manage(perf::PerfStats::default())
+    .manage(instance::LaunchRequests::default())
+    .manage(deep_link::DeepLinkRequests::default())
+    .manage(project_window::ProjectWindows::default())
+    .manage(task_indicator::TaskIndicator::default())
+    .manage(updater::UpdateState::default())
+    .manage(EngineCache::default())
+    .manage(EngineInstall::default())
+    .manage(onboarding::OnboardingManager::default())
+    .manage(scheduler::Scheduler::default())
+    .manage(mcp::McpManager::default())
+    .manage(terminal::TerminalManager::default())
+    .manage(tasks::TaskRunner::default())
+    .manage(control::ControlServer::default())
+    .manage(remote_project::RemoteSync::default())
+    .manage(models::ModelCatalogCache::default())
+    .manage(extensions::ExtensionRegistry::default())
+    .manage(opkg_queue::OpkgQueue::default())
+    .setup(|app| {
+      logging::init(app.handle());
+      i18n::init(app.handle());
+      shell_path::log_outcome();
+      crash::init(app.handle());
+      download::init(app.handle());
+      network::init(app.handle());
+      allowlist::init(app.handle());
+      env_policy::init(app.handle());
+      permissions::init(app.handle());
+      cors::init(app.handle());
+      dotenv::init(app.handle());
+      env_profiles::init(app.handle());
+      devcontainer::init(app.handle());
+      history::init(app.handle());
+      usage::init(app.handle());
+      notifier::init(app.handle());
+      prompt_queue::init(app.handle());
+      budget::init(app.handle());
+      webhooks::init(app.handle());
+      engine_hooks::init(app.handle());
+      telemetry::init(app.handle());
+      scheduler::init(app.handle());
+      instance::init(app.handle());
+      deep_link::init(app.handle());
+      startup::init(app.handle());
+      control::init(app.handle());
+      menu::init(app.handle());
+      app.state::<relay::EventRelay>().start(app.handle());
+      Ok(())
+    })
+    .register_asynchronous_uri_scheme_protocol(asset_protocol::SCHEME, asset_protocol::handle)
+    .on_menu_event(menu::handle_event)
+    .on_window_event(|window, event| {
+      startup::on_window_event(window, event);
+      project_window::on_window_event(window, event);
+      task_indicator::on_window_event(window, event);
+    })
+    .invoke_handler(perf::track(tauri::generate_handler![
       engine_start,
       engine_stop,
       engine_info,
       engine_doctor,
+      private_files::config_permissions_fix,
       engine_install,
+      engine_install_cancel,
       opkg_install,
+      opkg_queue::opkg_queue_status,
+      network::network_status,
+      engine_releases::engine_release_notes,
+      engine_env::engine_env_preview,
       import_skill,
+      import_skill_archive,
+      import_skill_from_url,
+      remove_skill,
       read_opencode_config,
-      write_opencode_config
-    ])
+      write_opencode_config,
+      allowlist::allowlist_get,
+      allowlist::allowlist_set,
+      allowlist::allowlist_approve,
+      attachments::attachment_stage,
+      attachments::attachment_clear,
+      consent::confirm_request,
+      control::control_status,
+      control::control_set_enabled,
+      budget::budget_get,
+      budget::budget_set,
+      budget::budget_status,
+      crash::crash_reports_list,
+      crash::crash_report_read,
+      debug_bundle::debug_bundle_create,
+      devcontainer::devcontainer_detect,
+      devcontainer::devcontainer_set_enabled,
+      devcontainer::devcontainer_map_path,
+      dotenv::dotenv_files_list,
+      dotenv::dotenv_read,
+      dotenv::dotenv_set,
+      dotenv::dotenv_inject_set,
+      gitignore::gitignore_ensure,
+      skill_deps::skill_dependencies_resolve,
+      failover::engine_failover_set,
+      failover::engine_failover_clear,
+      failover::engine_failover_status,
+      env_profiles::env_profiles_get,
+      env_profiles::env_profile_save,
+      env_profiles::env_profile_delete,
+      env_profiles::env_profile_select,
+      engine_client::sessions_list,
+      env_policy::env_policy_get,
+      env_policy::env_policy_set,
+      env_policy::env_policy_reset,
+      external_config::import_external_config,
+      instructions_export::export_instructions,
+      providers::provider_validate,
+      models::models_list,
+      engine_hooks::engine_hooks_get,
+      engine_hooks::engine_hooks_set,
+      engine_hooks::engine_hook_run,
+      opencode_snapshot::opencode_dir_snapshot,
+      opencode_snapshot::opencode_dir_snapshots,
+      opencode_snapshot::opencode_dir_restore,
+      opencode_snapshot::opencode_dir_snapshot_delete,
+      config_backup::backups_list,
+      config_backup::backup_restore,
+      config_backup::backup_settings_get,
+      config_backup::backup_settings_set,
+      i18n::get_locale,
+      i18n::set_locale,
+      extensions::extensions_list,
+      extensions::extension_grant,
+      extensions::extension_set_enabled,
+      extensions::extension_call,
+      log_viewer::app_logs_tail,
+      log_viewer::app_logs_follow_start,
+      log_viewer::app_logs_follow_stop,
+      log_viewer::app_logs_follow_status,
+      local_models::local_models_list,
+      local_models::local_models_configure,
+      logging::log_get_level,
+      logging::log_set_level,
+      mcp::mcp_server_logs,
+      mcp::mcp_server_start,
+      mcp::mcp_server_stop,
+      mcp::mcp_servers_list,
+      perf::perf_record,
+      perf::perf_stats,
+      perf::perf_reset,
+      portable::portable_status,
+      permissions::permission_profiles_get,
+      permissions::permission_profile_save,
+      permissions::permission_profile_delete,
+      permissions::permission_profile_select,
+      engine_client::session_get,
+      engine_client::session_messages,
+      history::history_sessions,
+      history::history_session,
+      history::history_messages,
+      git::git_commit,
+      git::git_branch_create,
+      git::git_branch_switch,
+      github::github_create_pr,
+      instance::launch_project_take,
+      deep_link::deep_link_skill_take,
+      dropped::classify_dropped_path,
+      download::download_proxy_get,
+      download::download_proxy_set,
+      cors::cors_settings_get,
+      cors::cors_settings_set,
+      notifier::notification_prefs_get,
+      notifier::notification_prefs_set,
+      onboarding::onboarding_status,
+      onboarding::onboarding_advance,
+      packages::project_detect_packages,
+      project::project_stats,
+      project::project_archive,
+      project::project_recent_files,
+      project::project_disk_usage,
+      project_window::project_window_open,
+      project_window::window_project,
+      prompt_queue::queue_prompt,
+      prompt_queue::queue_list,
+      prompt_queue::queue_remove,
+      prompt_queue::queue_clear,
+      recent::recent_projects_list,
+      recent::recent_projects_clear,
+      relay::event_relay_start,
+      relay::event_relay_stop,
+      relay::event_relay_status,
+      relay::event_relay_backfill,
+      scheduler::scheduler_jobs_list,
+      scheduler::scheduler_job_toggle,
+      search::project_search,
+      startup::startup_settings_get,
+      startup::startup_settings_set,
+      tasks::tasks_detect,
+      tasks::task_run,
+      tasks::task_stop,
+      tasks::tasks_running,
+      telemetry::telemetry_get,
+      telemetry::telemetry_set,
+      telemetry::telemetry_flush,
+      terminal::terminal_create,
+      terminal::terminal_write,
+      terminal::terminal_resize,
+      terminal::terminal_kill,
+      terminal::terminal_list,
+      transcript::session_export,
+      updater::app_update_check,
+      updater::app_update_install,
+      usage::usage_summary,
+      watcher::project_watch_start,
+      watcher::project_watch_stop,
+      watcher::project_watch_info,
+      webhooks::webhooks_get,
+      webhooks::webhooks_set,
+      webhooks::webhook_test,
+      config_schema::config_schema_get,
+      config_schema::config_schema_describe,
+      config_schema::config_schema_validate,
+      remote_project::remote_project_add,
+      remote_project::remote_project_list,
+      remote_project::remote_project_status,
+      remote_project::remote_project_pull,
+      remote_project::remote_project_push,
+      remote_project::remote_project_resolve,
+      remote_project::remote_project_remove,
+      workspace::workspace_list,
+      workspace::workspace_create,
+      workspace::workspace_update,
+      workspace::workspace_delete,
+      workspace::workspace_start,
+      workspace::workspace_stop,
+      workspace::workspace_info
+    ]))
     .run(tauri::generate_context!())
     .expect("error while running OpenWork");
 }
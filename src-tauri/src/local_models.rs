@@ -0,0 +1,413 @@
+//! Local model runtimes: Ollama and LM Studio.
+//!
+//! Both serve an OpenAI-compatible API on localhost, which opencode uses
+//! through a custom provider. `local_models_list` finds the running ones and
+//! their models, and `local_models_configure` writes the provider entry into
+//! the opencode config, so a fully local setup needs no hand-written JSON.
+
+use std::{
+  env,
+  time::{Duration, Instant},
+};
+
+use reqwest::blocking::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use tauri::State;
+
+use crate::{
+  consent::ConsentManager,
+  error::{ErrorCode, OpenWorkError},
+  paths,
+};
+
+const OLLAMA_DEFAULT: &str = "http://127.0.0.1:11434";
+const LM_STUDIO_DEFAULT: &str = "http://127.0.0.1:1234";
+/// Both answer at once when running; anything slower is treated as absent.
+const PROBE_TIMEOUT: Duration = Duration::from_millis(1500);
+const PROVIDER_PACKAGE: &str = "@ai-sdk/openai-compatible";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum LocalRuntimeKind {
+  Ollama,
+  LmStudio,
+}
+
+impl LocalRuntimeKind {
+  const ALL: [Self; 2] = [Self::Ollama, Self::LmStudio];
+
+  fn name(self) -> &'static str {
+    match self {
+      Self::Ollama => "Ollama",
+      Self::LmStudio => "LM Studio",
+    }
+  }
+
+  /// The provider ID in the opencode config.
+  fn provider_id(self) -> &'static str {
+    match self {
+      Self::Ollama => "ollama",
+      Self::LmStudio => "lmstudio",
+    }
+  }
+
+  /// Where the runtime listens; Ollama honours `OLLAMA_HOST`.
+  fn base_url(self) -> String {
+    match self {
+      Self::Ollama => ollama_base(env::var("OLLAMA_HOST").ok().as_deref()),
+      Self::LmStudio => LM_STUDIO_DEFAULT.to_string(),
+    }
+  }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalModel {
+  pub id: String,
+  /// Size on disk in bytes, when the runtime reports it.
+  pub size: Option<u64>,
+  /// e.g. `8B`.
+  pub parameters: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalRuntime {
+  pub kind: LocalRuntimeKind,
+  pub name: String,
+  pub base_url: String,
+  pub running: bool,
+  pub version: Option<String>,
+  pub models: Vec<LocalModel>,
+  /// Why the runtime didn't answer, or answered unexpectedly.
+  pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalProviderResult {
+  pub path: String,
+  pub provider: String,
+  /// Models added to the provider; models already listed are left as they are.
+  pub added: Vec<String>,
+}
+
+/// `OLLAMA_HOST` (`0.0.0.0:11434`, `http://box:11434`, ...) as a URL to
+/// connect to; a wildcard bind address is reached on localhost.
+fn ollama_base(host: Option<&str>) -> String {
+  let Some(host) = host.map(str::trim).filter(|host| !host.is_empty()) else {
+    return OLLAMA_DEFAULT.to_string();
+  };
+  let (scheme, rest) = match host.split_once("://") {
+    Some((scheme, rest)) => (scheme, rest),
+    None => ("http", host),
+  };
+  let rest = rest.trim_end_matches('/');
+  let (address, port) = match rest.rsplit_once(':') {
+    Some((address, port)) if port.chars().all(|c| c.is_ascii_digit()) => (address, port),
+    _ => (rest, "11434"),
+  };
+  let address = match address {
+    "" | "0.0.0.0" => "127.0.0.1",
+    "::" | "[::]" => "[::1]",
+    other => other,
+  };
+  format!("{scheme}://{address}:{port}")
+}
+
+/// Models from Ollama's `/api/tags`.
+fn parse_ollama_models(tags: &Value) -> Vec<LocalModel> {
+  tags
+    .get("models")
+    .and_then(Value::as_array)
+    .into_iter()
+    .flatten()
+    .filter_map(|model| {
+      Some(LocalModel {
+        id: model.get("name").and_then(Value::as_str)?.to_string(),
+        size: model.get("size").and_then(Value::as_u64),
+        parameters: model
+          .pointer("/details/parameter_size")
+          .and_then(Value::as_str)
+          .map(str::to_string),
+      })
+    })
+    .collect()
+}
+
+/// Models from an OpenAI-style `/v1/models`, leaving out embedding models,
+/// which can't chat.
+fn parse_openai_models(list: &Value) -> Vec<LocalModel> {
+  list
+    .get("data")
+    .and_then(Value::as_array)
+    .into_iter()
+    .flatten()
+    .filter_map(|model| model.get("id").and_then(Value::as_str))
+    .filter(|id| !id.contains("embed"))
+    .map(|id| LocalModel {
+      id: id.to_string(),
+      size: None,
+      parameters: None,
+    })
+    .collect()
+}
+
+fn get_json(client: &Client, url: &str) -> Result<Value, String> {
+  let response = client.get(url).send().map_err(|e| {
+    if e.is_connect() {
+      "Not running".to_string()
+    } else {
+      e.to_string()
+    }
+  })?;
+  if !response.status().is_success() {
+    return Err(format!("{url} answered {}", response.status()));
+  }
+  response
+    .json()
+    .map_err(|e| format!("Invalid JSON from {url}: {e}"))
+}
+
+fn probe(kind: LocalRuntimeKind, timeout: Duration) -> LocalRuntime {
+  let base_url = kind.base_url();
+  let mut runtime = LocalRuntime {
+    kind,
+    name: kind.name().to_string(),
+    base_url: base_url.clone(),
+    running: false,
+    version: None,
+    models: Vec::new(),
+    error: None,
+  };
+  // Local servers; a configured proxy can't reach them.
+  let client = match Client::builder().no_proxy().timeout(timeout).build() {
+    Ok(client) => client,
+    Err(e) => {
+      runtime.error = Some(e.to_string());
+      return runtime;
+    }
+  };
+  let models = match kind {
+    LocalRuntimeKind::Ollama => {
+      get_json(&client, &format!("{base_url}/api/tags")).map(|tags| parse_ollama_models(&tags))
+    }
+    LocalRuntimeKind::LmStudio => {
+      get_json(&client, &format!("{base_url}/v1/models")).map(|list| parse_openai_models(&list))
+    }
+  };
+  match models {
+    Ok(models) => {
+      runtime.running = true;
+      runtime.models = models;
+    }
+    Err(e) => runtime.error = Some(e),
+  }
+  if runtime.running && kind == LocalRuntimeKind::Ollama {
+    runtime.version = get_json(&client, &format!("{base_url}/api/version"))
+      .ok()
+      .and_then(|version| version.get("version")?.as_str().map(str::to_string));
+  }
+  runtime
+}
+
+/// Both runtimes, probed in parallel, within `deadline`.
+pub fn detect(deadline: Instant) -> Vec<LocalRuntime> {
+  let timeout = deadline
+    .saturating_duration_since(Instant::now())
+    .min(PROBE_TIMEOUT)
+    .max(Duration::from_millis(100));
+  let probes: Vec<_> = LocalRuntimeKind::ALL
+    .into_iter()
+    .map(|kind| std::thread::spawn(move || probe(kind, timeout)))
+    .collect();
+  probes
+    .into_iter()
+    .zip(LocalRuntimeKind::ALL)
+    .map(|(probe, kind)| {
+      probe.join().unwrap_or_else(|_| LocalRuntime {
+        kind,
+        name: kind.name().to_string(),
+        base_url: kind.base_url(),
+        running: false,
+        version: None,
+        models: Vec::new(),
+        error: Some("Detection failed".to_string()),
+      })
+    })
+    .collect()
+}
+
+/// Adds `kind`'s provider with `models` to `config`, keeping whatever the
+/// provider entry already has. Returns the models added.
+fn merge_provider(
+  config: &mut Value,
+  kind: LocalRuntimeKind,
+  base_url: &str,
+  models: &[String],
+) -> Vec<String> {
+  if !config.get("provider").is_some_and(Value::is_object) {
+    config["provider"] = json!({});
+  }
+  let provider = config["provider"]
+    .as_object_mut()
+    .expect("just made an object")
+    .entry(kind.provider_id())
+    .or_insert_with(|| json!({}));
+  if !provider.is_object() {
+    *provider = json!({});
+  }
+  let provider = provider.as_object_mut().expect("just made an object");
+  provider.entry("npm").or_insert_with(|| json!(PROVIDER_PACKAGE));
+  provider
+    .entry("name")
+    .or_insert_with(|| json!(format!("{} (local)", kind.name())));
+  let options = provider.entry("options").or_insert_with(|| json!({}));
+  if let Some(options) = options.as_object_mut() {
+    options
+      .entry("baseURL")
+      .or_insert_with(|| json!(format!("{base_url}/v1")));
+  }
+  let listed = provider
+    .entry("models")
+    .or_insert_with(|| Value::Object(Map::new()));
+  if !listed.is_object() {
+    *listed = json!({});
+  }
+  let listed = listed.as_object_mut().expect("just made an object");
+  let mut added = Vec::new();
+  for model in models {
+    if !listed.contains_key(model) {
+      listed.insert(model.clone(), json!({ "name": model }));
+      added.push(model.clone());
+    }
+  }
+  added
+}
+
+/// Ollama and LM Studio instances on this machine and their models.
+#[tauri::command]
+pub fn local_models_list() -> Vec<LocalRuntime> {
+  detect(Instant::now() + PROBE_TIMEOUT)
+}
+
+/// Writes `runtime` as a provider in the opencode config for `scope`, with
+/// `models` (by default every model the runtime has).
+#[tauri::command]
+pub fn local_models_configure(
+  consent: State<ConsentManager>,
+  scope: String,
+  project_dir: String,
+  runtime: LocalRuntimeKind,
+  models: Option<Vec<String>>,
+  confirmation_id: Option<String>,
+) -> Result<LocalProviderResult, OpenWorkError> {
+  let path = crate::resolve_opencode_config_path(scope.trim(), &project_dir)?;
+  let models = match models {
+    Some(models) => models
+      .iter()
+      .map(|model| model.trim().to_string())
+      .filter(|model| !model.is_empty())
+      .collect(),
+    None => {
+      let detected = probe(runtime, PROBE_TIMEOUT);
+      if !detected.running {
+        return Err(OpenWorkError::new(
+          ErrorCode::NotFound,
+          format!("{} is not running at {}", detected.name, detected.base_url),
+        ));
+      }
+      detected
+        .models
+        .into_iter()
+        .map(|model| model.id)
+        .collect::<Vec<_>>()
+    }
+  };
+  if models.is_empty() {
+    return Err(OpenWorkError::invalid_argument(format!(
+      "No {} models to add. Download one first.",
+      runtime.name()
+    )));
+  }
+
+  let before = std::fs::read_to_string(paths::extended(&path)).ok();
+  let mut config = crate::parse_opencode_config(before.as_deref(), &path)?;
+  let added = merge_provider(&mut config, runtime, &runtime.base_url(), &models);
+  let after = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())? + "\n";
+  if before.as_deref() != Some(after.as_str()) {
+    if before.is_some() {
+      consent.require(
+        "overwrite_config",
+        &path.to_string_lossy(),
+        &format!("Add {} to {}?", runtime.name(), path.display()),
+        confirmation_id.as_deref(),
+      )?;
+    }
+    crate::write_config_file(&path, &after)?;
+  }
+  crate::telemetry::record("feature.local_models");
+  tracing::info!(
+    provider = runtime.provider_id(),
+    added = added.len(),
+    "local model provider configured"
+  );
+
+  Ok(LocalProviderResult {
+    path: path.to_string_lossy().to_string(),
+    provider: runtime.provider_id().to_string(),
+    added,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn resolves_ollama_host() {
+    assert_eq!(ollama_base(None), OLLAMA_DEFAULT);
+    assert_eq!(ollama_base(Some("0.0.0.0")), "http://127.0.0.1:11434");
+    assert_eq!(ollama_base(Some("0.0.0.0:8080")), "http://127.0.0.1:8080");
+    assert_eq!(ollama_base(Some("https://gpu.lan:443/")), "https://gpu.lan:443");
+  }
+
+  #[test]
+  fn parses_model_lists() {
+    let tags = json!({ "models": [
+      { "name": "llama3.2:latest", "size": 2019393189u64, "details": { "parameter_size": "3.2B" } },
+      { "size": 1 },
+    ] });
+    assert_eq!(
+      parse_ollama_models(&tags),
+      vec![LocalModel {
+        id: "llama3.2:latest".to_string(),
+        size: Some(2019393189),
+        parameters: Some("3.2B".to_string()),
+      }]
+    );
+    let list = json!({ "data": [{ "id": "qwen2.5-7b-instruct" }, { "id": "text-embedding-nomic" }] });
+    let ids: Vec<String> = parse_openai_models(&list)
+      .into_iter()
+      .map(|model| model.id)
+      .collect();
+    assert_eq!(ids, vec!["qwen2.5-7b-instruct"]);
+  }
+
+  #[test]
+  fn merges_provider_entries() {
+    let mut config = json!({ "provider": { "ollama": { "models": { "mistral": { "name": "Mistral" } } } } });
+    let added = merge_provider(
+      &mut config,
+      LocalRuntimeKind::Ollama,
+      OLLAMA_DEFAULT,
+      &["mistral".to_string(), "llama3.2".to_string()],
+    );
+    assert_eq!(added, vec!["llama3.2"]);
+    let provider = &config["provider"]["ollama"];
+    assert_eq!(provider["npm"], PROVIDER_PACKAGE);
+    assert_eq!(provider["options"]["baseURL"], "http://127.0.0.1:11434/v1");
+    assert_eq!(provider["models"]["mistral"]["name"], "Mistral");
+  }
+}
@@ -0,0 +1,283 @@
+use std::{
+  fs::{self, File},
+  io::{Read, Seek, SeekFrom},
+  path::{Path, PathBuf},
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex,
+  },
+  thread,
+  time::Duration,
+};
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::{
+  error::OpenWorkError,
+  logging::{log_dir, module_target, parse_level, LEVELS, LOG_FILE_PREFIX, LOG_FILE_SUFFIX},
+};
+
+pub const LOG_LINES_EVENT: &str = "logs://lines";
+
+const DEFAULT_TAIL_LINES: usize = 200;
+const MAX_TAIL_LINES: usize = 5000;
+/// How far back from the end of the file `app_logs_tail` looks.
+const MAX_TAIL_BYTES: u64 = 4 * 1024 * 1024;
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LogLine {
+  pub timestamp: Option<String>,
+  pub level: String,
+  pub target: String,
+  pub message: String,
+  /// Structured fields other than the message.
+  pub fields: Map<String, Value>,
+  /// Names of the spans the event was recorded in, outermost first.
+  pub spans: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LogFollowStatus {
+  pub following: bool,
+  pub level_filter: Option<String>,
+  pub module_filter: Option<String>,
+}
+
+impl LogFollowStatus {
+  fn idle() -> Self {
+    Self {
+      following: false,
+      level_filter: None,
+      module_filter: None,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Default)]
+struct LineFilter {
+  /// Index into `LEVELS`; lines below it are dropped.
+  min_level: usize,
+  module: Option<String>,
+}
+
+impl LineFilter {
+  fn new(level_filter: Option<&str>, module_filter: Option<&str>) -> Result<Self, String> {
+    let min_level = match level_filter {
+      Some(level) => {
+        let level = parse_level(level)?;
+        LEVELS.iter().position(|l| *l == level).unwrap_or(0)
+      }
+      None => 0,
+    };
+    let module = module_filter.map(module_target).transpose()?;
+    Ok(Self { min_level, module })
+  }
+
+  fn matches(&self, line: &LogLine) -> bool {
+    let rank = LEVELS.iter().position(|l| *l == line.level).unwrap_or(0);
+    if rank < self.min_level {
+      return false;
+    }
+    match &self.module {
+      Some(module) => line.target == *module || line.target.starts_with(&format!("{module}::")),
+      None => true,
+    }
+  }
+}
+
+#[derive(Default)]
+pub struct LogFollower {
+  generation: AtomicU64,
+  status: Mutex<Option<LogFollowStatus>>,
+}
+
+/// The file currently being written; the appender starts a new one each day.
+fn latest_log_file(dir: &Path) -> Option<PathBuf> {
+  fs::read_dir(dir)
+    .ok()?
+    .filter_map(Result::ok)
+    .filter(|entry| {
+      let name = entry.file_name().to_string_lossy().to_string();
+      name.starts_with(LOG_FILE_PREFIX) && name.ends_with(LOG_FILE_SUFFIX)
+    })
+    .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+    .max_by_key(|(modified, _)| *modified)
+    .map(|(_, path)| path)
+}
+
+fn parse_line(raw: &str) -> Option<LogLine> {
+  let value: Value = serde_json::from_str(raw.trim()).ok()?;
+  let mut fields = value.get("fields")?.as_object()?.clone();
+  let message = match fields.remove("message") {
+    Some(Value::String(message)) => message,
+    Some(other) => other.to_string(),
+    None => String::new(),
+  };
+  let spans = value
+    .get("spans")
+    .and_then(|s| s.as_array())
+    .map(|spans| {
+      spans
+        .iter()
+        .filter_map(|span| span.get("name").and_then(|n| n.as_str()).map(str::to_string))
+        .collect()
+    })
+    .unwrap_or_default();
+
+  Some(LogLine {
+    timestamp: value.get("timestamp").and_then(|t| t.as_str()).map(str::to_string),
+    level: value.get("level")?.as_str()?.to_ascii_lowercase(),
+    target: value.get("target").and_then(|t| t.as_str()).unwrap_or_default().to_string(),
+    message,
+    fields,
+    spans,
+  })
+}
+
+/// Reads `path` from `offset` up to the last complete line. Returns the text
+/// and the offset just past it.
+fn read_from(path: &Path, offset: u64) -> Result<(String, u64), String> {
+  let mut file = File::open(path).map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+  file
+    .seek(SeekFrom::Start(offset))
+    .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+  let mut bytes = Vec::new();
+  file
+    .read_to_end(&mut bytes)
+    .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+
+  let complete = bytes.iter().rposition(|b| *b == b'\n').map(|i| i + 1).unwrap_or(0);
+  bytes.truncate(complete);
+  Ok((String::from_utf8_lossy(&bytes).to_string(), offset + complete as u64))
+}
+
+fn file_len(path: &Path) -> u64 {
+  fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+/// The last `limit` raw lines of the current log file.
+pub fn recent_raw_lines(dir: &Path, limit: usize) -> Vec<String> {
+  let Some(path) = latest_log_file(dir) else {
+    return Vec::new();
+  };
+  let Ok((text, _)) = read_from(&path, file_len(&path).saturating_sub(MAX_TAIL_BYTES)) else {
+    return Vec::new();
+  };
+  let lines: Vec<&str> = text.lines().collect();
+  lines[lines.len().saturating_sub(limit)..]
+    .iter()
+    .map(|line| line.to_string())
+    .collect()
+}
+
+#[tauri::command]
+pub fn app_logs_tail(
+  app: AppHandle,
+  lines: Option<usize>,
+  level_filter: Option<String>,
+  module_filter: Option<String>,
+) -> Result<Vec<LogLine>, OpenWorkError> {
+  let filter = LineFilter::new(level_filter.as_deref(), module_filter.as_deref())?;
+  let limit = lines.unwrap_or(DEFAULT_TAIL_LINES).clamp(1, MAX_TAIL_LINES);
+
+  let Some(path) = latest_log_file(&log_dir(&app)?) else {
+    return Ok(Vec::new());
+  };
+  let (text, _) = read_from(&path, file_len(&path).saturating_sub(MAX_TAIL_BYTES))?;
+
+  let mut tail: Vec<LogLine> = text
+    .lines()
+    .rev()
+    .filter_map(parse_line)
+    .filter(|line| filter.matches(line))
+    .take(limit)
+    .collect();
+  tail.reverse();
+  Ok(tail)
+}
+
+fn follow(app: AppHandle, generation: u64, filter: LineFilter) {
+  let follower = app.state::<LogFollower>();
+  let Ok(dir) = log_dir(&app) else {
+    return;
+  };
+
+  let mut current = latest_log_file(&dir);
+  let mut offset = current.as_deref().map(file_len).unwrap_or(0);
+
+  while follower.generation.load(Ordering::SeqCst) == generation {
+    thread::sleep(FOLLOW_INTERVAL);
+
+    let latest = latest_log_file(&dir);
+    if latest != current {
+      // Rotated to a new day's file.
+      current = latest;
+      offset = 0;
+    }
+    let Some(path) = current.as_deref() else {
+      continue;
+    };
+    if file_len(path) < offset {
+      offset = 0;
+    }
+
+    let Ok((text, next)) = read_from(path, offset) else {
+      continue;
+    };
+    offset = next;
+
+    let lines: Vec<LogLine> = text
+      .lines()
+      .filter_map(parse_line)
+      .filter(|line| filter.matches(line))
+      .collect();
+    if !lines.is_empty() {
+      let _ = app.emit(LOG_LINES_EVENT, lines);
+    }
+  }
+}
+
+/// Streams new log lines as `logs://lines` events until stopped. Starting
+/// again replaces the previous filter.
+#[tauri::command]
+pub fn app_logs_follow_start(
+  app: AppHandle,
+  follower: State<LogFollower>,
+  level_filter: Option<String>,
+  module_filter: Option<String>,
+) -> Result<LogFollowStatus, OpenWorkError> {
+  let filter = LineFilter::new(level_filter.as_deref(), module_filter.as_deref())?;
+  let generation = follower.generation.fetch_add(1, Ordering::SeqCst) + 1;
+
+  let status = LogFollowStatus {
+    following: true,
+    level_filter,
+    module_filter,
+  };
+  *follower.status.lock().expect("log follower mutex poisoned") = Some(status.clone());
+
+  thread::spawn(move || follow(app, generation, filter));
+  Ok(status)
+}
+
+#[tauri::command]
+pub fn app_logs_follow_stop(follower: State<LogFollower>) -> LogFollowStatus {
+  follower.generation.fetch_add(1, Ordering::SeqCst);
+  *follower.status.lock().expect("log follower mutex poisoned") = None;
+  LogFollowStatus::idle()
+}
+
+#[tauri::command]
+pub fn app_logs_follow_status(follower: State<LogFollower>) -> LogFollowStatus {
+  follower
+    .status
+    .lock()
+    .expect("log follower mutex poisoned")
+    .clone()
+    .unwrap_or_else(LogFollowStatus::idle)
+}
@@ -0,0 +1,207 @@
+//! `tracing` setup: a daily-rotated log file in the app log directory (plus
+//! stderr in debug builds), per-module levels that can be changed at runtime,
+//! and secret redaction on everything written.
+
+use std::{
+  collections::BTreeMap,
+  io::{self, Write},
+  path::PathBuf,
+  sync::{OnceLock, RwLock},
+};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tracing_appender::{non_blocking::WorkerGuard, rolling};
+use tracing_subscriber::{fmt, layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Registry};
+
+use crate::{
+  error::OpenWorkError,
+  redact::redact,
+  store::{read_state, write_state},
+};
+
+pub const LOGGING_FILE: &str = "logging.json";
+pub const LOG_FILE_PREFIX: &str = "openwork";
+pub const LOG_FILE_SUFFIX: &str = "log";
+const MAX_LOG_FILES: usize = 7;
+const CRATE_TARGET: &str = env!("CARGO_CRATE_NAME");
+pub const LEVELS: &[&str] = &["trace", "debug", "info", "warn", "error", "off"];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LogSettings {
+  /// Level for OpenWork's own modules. Dependencies always log at `warn`.
+  pub level: String,
+  /// Overrides keyed by module target, e.g. `openwork::relay`.
+  pub modules: BTreeMap<String, String>,
+}
+
+impl Default for LogSettings {
+  fn default() -> Self {
+    Self {
+      level: "info".to_string(),
+      modules: BTreeMap::new(),
+    }
+  }
+}
+
+static FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+// Dropping the guard stops the background writer, so it lives for the process.
+static FILE_GUARD: OnceLock<WorkerGuard> = OnceLock::new();
+static SETTINGS: RwLock<Option<LogSettings>> = RwLock::new(None);
+
+/// Scrubs each formatted log line before it reaches the sink.
+struct RedactingWriter<W: Write>(W);
+
+impl<W: Write> Write for RedactingWriter<W> {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    let line = redact(&String::from_utf8_lossy(buf));
+    self.0.write_all(line.as_bytes())?;
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    self.0.flush()
+  }
+}
+
+pub fn log_dir(app: &AppHandle) -> Result<PathBuf, String> {
+  if let Some(dir) = crate::portable::dir("logs") {
+    return Ok(dir);
+  }
+  app
+    .path()
+    .app_log_dir()
+    .map_err(|e| format!("Failed to resolve app log dir: {e}"))
+}
+
+pub fn parse_level(raw: &str) -> Result<String, OpenWorkError> {
+  let level = raw.trim().to_ascii_lowercase();
+  if !LEVELS.contains(&level.as_str()) {
+    return Err(OpenWorkError::invalid_argument(format!(
+      "Invalid log level: {raw} (expected one of {})",
+      LEVELS.join(", ")
+    )));
+  }
+  Ok(level)
+}
+
+/// Accepts `relay`, `openwork::relay` or a dependency target such as `reqwest`.
+pub fn module_target(raw: &str) -> Result<String, OpenWorkError> {
+  let module = raw.trim().replace('-', "_");
+  let valid = !module.is_empty()
+    && module
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':');
+  if !valid {
+    return Err(OpenWorkError::invalid_argument(format!("Invalid module: {raw}")));
+  }
+  if module.contains("::") || module == CRATE_TARGET {
+    return Ok(module);
+  }
+  Ok(format!("{CRATE_TARGET}::{module}"))
+}
+
+fn build_filter(settings: &LogSettings) -> Result<EnvFilter, String> {
+  let mut directives = vec!["warn".to_string(), format!("{CRATE_TARGET}={}", settings.level)];
+  directives.extend(settings.modules.iter().map(|(module, level)| format!("{module}={level}")));
+  EnvFilter::try_new(directives.join(",")).map_err(|e| format!("Invalid log filter: {e}"))
+}
+
+fn current() -> LogSettings {
+  SETTINGS
+    .read()
+    .expect("log settings lock poisoned")
+    .clone()
+    .unwrap_or_default()
+}
+
+/// Installs the global subscriber. Runs first in `setup` so other subsystems
+/// can log while they initialize.
+pub fn init(app: &AppHandle) {
+  let settings = read_state::<LogSettings>(app, LOGGING_FILE).unwrap_or_default();
+  let filter = build_filter(&settings).unwrap_or_else(|_| EnvFilter::new(format!("warn,{CRATE_TARGET}=info")));
+  let (filter_layer, handle) = reload::Layer::new(filter);
+
+  let file_layer = log_dir(app).and_then(|dir| {
+    rolling::Builder::new()
+      .rotation(rolling::Rotation::DAILY)
+      .filename_prefix(LOG_FILE_PREFIX)
+      .filename_suffix(LOG_FILE_SUFFIX)
+      .max_log_files(MAX_LOG_FILES)
+      .build(&dir)
+      .map_err(|e| format!("Failed to open log file in {}: {e}", dir.display()))
+  });
+  let file_layer = match file_layer {
+    Ok(appender) => {
+      let (writer, guard) = tracing_appender::non_blocking(appender);
+      let _ = FILE_GUARD.set(guard);
+      Some(
+        // JSON lines so the in-app viewer can filter by level and module.
+        fmt::layer()
+          .json()
+          .with_current_span(false)
+          .with_span_list(true)
+          .with_writer(move || RedactingWriter(writer.clone())),
+      )
+    }
+    Err(e) => {
+      eprintln!("{e}");
+      None
+    }
+  };
+
+  let stderr_layer =
+    cfg!(debug_assertions).then(|| fmt::layer().with_writer(|| RedactingWriter(io::stderr())));
+
+  if tracing_subscriber::registry()
+    .with(filter_layer)
+    .with(file_layer)
+    .with(stderr_layer)
+    .try_init()
+    .is_ok()
+  {
+    let _ = FILTER.set(handle);
+  }
+  *SETTINGS.write().expect("log settings lock poisoned") = Some(settings);
+}
+
+#[tauri::command]
+pub fn log_get_level() -> LogSettings {
+  current()
+}
+
+/// Sets the level for OpenWork as a whole, or for one module when `module` is
+/// given. `level: "inherit"` removes a module override.
+#[tauri::command]
+pub fn log_set_level(
+  app: AppHandle,
+  level: String,
+  module: Option<String>,
+) -> Result<LogSettings, OpenWorkError> {
+  let mut settings = current();
+
+  match module {
+    Some(module) => {
+      let module = module_target(&module)?;
+      if level.trim().eq_ignore_ascii_case("inherit") {
+        settings.modules.remove(&module);
+      } else {
+        settings.modules.insert(module, parse_level(&level)?);
+      }
+    }
+    None => settings.level = parse_level(&level)?,
+  }
+
+  let filter = build_filter(&settings)?;
+  if let Some(handle) = FILTER.get() {
+    handle
+      .reload(filter)
+      .map_err(|e| format!("Failed to apply log level: {e}"))?;
+  }
+
+  write_state(&app, LOGGING_FILE, &settings)?;
+  *SETTINGS.write().expect("log settings lock poisoned") = Some(settings.clone());
+  tracing::info!(level = %settings.level, modules = ?settings.modules, "log levels updated");
+  Ok(settings)
+}
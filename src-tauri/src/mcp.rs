@@ -0,0 +1,682 @@
+//! Local (stdio) MCP servers from the opencode config, run by the app.
+//!
+//! opencode starts the MCP servers in its config itself and says little when
+//! one fails, so a tool that "never shows up" is hard to explain without a
+//! terminal. Here the same command is started on its own, with the engine's
+//! environment and the config's `{env:...}` references expanded. The app
+//! performs the MCP handshake, lists the server's tools and keeps its stderr,
+//! and status changes and log lines are emitted as events.
+//!
+//! These processes are separate from the ones the engine runs.
+
+use std::{
+  collections::{BTreeMap, HashMap, VecDeque},
+  env, fs,
+  io::{BufRead, BufReader, Read, Write},
+  path::Path,
+  process::{Child, ChildStdin, Stdio},
+  sync::{mpsc, Arc, Mutex},
+  thread,
+  time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::{
+  env_policy,
+  error::{ErrorCode, OpenWorkError},
+  exec, jsonc, paths, redact,
+};
+
+pub const MCP_LOG_EVENT: &str = "mcp://log";
+pub const MCP_STATUS_EVENT: &str = "mcp://status";
+
+const MAX_LOG_LINES: usize = 500;
+/// `npx` may download the server on first start.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(60);
+const PROTOCOL_VERSION: &str = "2024-11-05";
+/// Environment names whose values are registered for redaction.
+const SECRET_MARKERS: &[&str] = &["KEY", "TOKEN", "SECRET", "PASSWORD", "CREDENTIAL"];
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum McpTransport {
+  /// A command talking MCP over stdin/stdout; these can be started here.
+  Local,
+  Remote,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct McpServerConfig {
+  pub name: String,
+  pub transport: McpTransport,
+  pub command: Vec<String>,
+  pub url: Option<String>,
+  pub environment: BTreeMap<String, String>,
+  pub enabled: bool,
+  /// Config file the entry comes from.
+  pub source: String,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(tag = "state", rename_all = "camelCase")]
+pub enum McpHealth {
+  Stopped,
+  /// Started; waiting for the handshake.
+  Starting,
+  Ready {
+    server: Option<String>,
+    version: Option<String>,
+    tools: Vec<String>,
+  },
+  /// Running, but the handshake failed or timed out.
+  Failed {
+    message: String,
+  },
+  Exited {
+    code: Option<i32>,
+  },
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct McpLogLine {
+  pub at: u64,
+  /// `stdout` lines are a protocol error: MCP over stdio reserves stdout for
+  /// JSON-RPC messages, and stray output there breaks the connection.
+  pub stream: &'static str,
+  pub line: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct McpServerStatus {
+  #[serde(flatten)]
+  pub config: McpServerConfig,
+  pub health: McpHealth,
+  pub pid: Option<u32>,
+  pub started_at: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct McpStatusEvent<'a> {
+  project_dir: &'a str,
+  name: &'a str,
+  health: &'a McpHealth,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct McpLogEvent<'a> {
+  project_dir: &'a str,
+  name: &'a str,
+  #[serde(flatten)]
+  line: &'a McpLogLine,
+}
+
+struct McpProcess {
+  child: Option<Child>,
+  /// Held open: stdio servers exit when their stdin closes.
+  stdin: Option<ChildStdin>,
+  health: McpHealth,
+  started_at: u64,
+  logs: VecDeque<McpLogLine>,
+}
+
+type Shared = Arc<Mutex<McpProcess>>;
+
+/// Servers started from the app, by project and name.
+#[derive(Default)]
+pub struct McpManager {
+  servers: Mutex<HashMap<(String, String), Shared>>,
+}
+
+fn now_ms() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_millis() as u64)
+    .unwrap_or(0)
+}
+
+/// A process lock; a panic in one server's thread shouldn't take the others
+/// down with it.
+fn lock(process: &Shared) -> std::sync::MutexGuard<'_, McpProcess> {
+  process.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Replaces opencode's `{env:NAME}` references with values from `lookup`;
+/// unset variables become empty, as in opencode.
+fn expand_env_refs(value: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+  let mut out = String::with_capacity(value.len());
+  let mut rest = value;
+  while let Some(start) = rest.find("{env:") {
+    let Some(end) = rest[start..].find('}') else {
+      break;
+    };
+    out.push_str(&rest[..start]);
+    out.push_str(&lookup(&rest[start + 5..start + end]).unwrap_or_default());
+    rest = &rest[start + end + 1..];
+  }
+  out.push_str(rest);
+  out
+}
+
+/// The `mcp` entries of one parsed config file.
+fn parse_servers(config: &Value, source: &str) -> Vec<McpServerConfig> {
+  let Some(entries) = config.get("mcp").and_then(Value::as_object) else {
+    return Vec::new();
+  };
+  let strings = |value: Option<&Value>| -> Vec<String> {
+    value
+      .and_then(Value::as_array)
+      .map(|items| {
+        items
+          .iter()
+          .filter_map(Value::as_str)
+          .map(str::to_string)
+          .collect()
+      })
+      .unwrap_or_default()
+  };
+  entries
+    .iter()
+    .filter_map(|(name, entry)| {
+      let transport = match entry.get("type").and_then(Value::as_str) {
+        Some("local") => McpTransport::Local,
+        Some("remote") => McpTransport::Remote,
+        _ => return None,
+      };
+      let environment = entry
+        .get("environment")
+        .and_then(Value::as_object)
+        .map(|vars| {
+          vars
+            .iter()
+            .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+            .collect()
+        })
+        .unwrap_or_default();
+      Some(McpServerConfig {
+        name: name.clone(),
+        transport,
+        command: strings(entry.get("command")),
+        url: entry.get("url").and_then(Value::as_str).map(str::to_string),
+        environment,
+        enabled: entry.get("enabled").and_then(Value::as_bool).unwrap_or(true),
+        source: source.to_string(),
+      })
+    })
+    .collect()
+}
+
+fn read_config_dir(dir: &Path) -> Vec<McpServerConfig> {
+  ["opencode.json", "opencode.jsonc"]
+    .iter()
+    .map(|file| dir.join(file))
+    .filter_map(|path| {
+      let text = fs::read_to_string(paths::extended(&path)).ok()?;
+      match serde_json::from_str::<Value>(&jsonc::strip(&text)) {
+        Ok(config) => Some(parse_servers(&config, &path.to_string_lossy())),
+        Err(e) => {
+          tracing::warn!(path = %path.display(), error = %e, "unreadable opencode config");
+          None
+        }
+      }
+    })
+    .flatten()
+    .collect()
+}
+
+/// Servers from the global config and the project's, which wins by name.
+fn configured_servers(project_dir: &Path) -> Vec<McpServerConfig> {
+  let mut servers: BTreeMap<String, McpServerConfig> = BTreeMap::new();
+  let global_dir = crate::resolve_opencode_config_path("global", "")
+    .ok()
+    .and_then(|path| path.parent().map(Path::to_path_buf));
+  for dir in global_dir.iter().map(|dir| dir.as_path()).chain([project_dir]) {
+    for server in read_config_dir(dir) {
+      servers.insert(server.name.clone(), server);
+    }
+  }
+  servers.into_values().collect()
+}
+
+fn find_server(project_dir: &Path, name: &str) -> Result<McpServerConfig, OpenWorkError> {
+  configured_servers(project_dir)
+    .into_iter()
+    .find(|server| server.name == name)
+    .ok_or_else(|| {
+      OpenWorkError::new(
+        ErrorCode::NotFound,
+        format!("No MCP server named '{name}' in the config"),
+      )
+    })
+}
+
+fn push_log(
+  app: &AppHandle,
+  project_dir: &str,
+  name: &str,
+  process: &Shared,
+  stream: &'static str,
+  line: &str,
+) {
+  let line = McpLogLine {
+    at: now_ms(),
+    stream,
+    line: redact::redact(line),
+  };
+  let _ = app.emit(
+    MCP_LOG_EVENT,
+    McpLogEvent {
+      project_dir,
+      name,
+      line: &line,
+    },
+  );
+  let mut process = lock(process);
+  if process.logs.len() == MAX_LOG_LINES {
+    process.logs.pop_front();
+  }
+  process.logs.push_back(line);
+}
+
+fn set_health(app: &AppHandle, project_dir: &str, name: &str, process: &Shared, health: McpHealth) {
+  tracing::info!(server = name, health = ?health, "mcp server status");
+  let _ = app.emit(
+    MCP_STATUS_EVENT,
+    McpStatusEvent {
+      project_dir,
+      name,
+      health: &health,
+    },
+  );
+  lock(process).health = health;
+}
+
+fn send(process: &Shared, message: &Value) -> Result<(), String> {
+  let mut process = lock(process);
+  let stdin = process.stdin.as_mut().ok_or("stdin is closed")?;
+  writeln!(stdin, "{message}")
+    .and_then(|()| stdin.flush())
+    .map_err(|e| format!("Failed to write to the server: {e}"))
+}
+
+/// Waits for the response to request `id`, logging anything else.
+fn response(messages: &mpsc::Receiver<Value>, id: u64, deadline: Instant) -> Result<Value, String> {
+  loop {
+    let timeout = deadline
+      .checked_duration_since(Instant::now())
+      .ok_or("The server did not answer the MCP handshake in time")?;
+    let message = messages.recv_timeout(timeout).map_err(|e| match e {
+      mpsc::RecvTimeoutError::Timeout => "The server did not answer the MCP handshake in time".to_string(),
+      mpsc::RecvTimeoutError::Disconnected => "The server closed its output during the handshake".to_string(),
+    })?;
+    if message.get("id").and_then(Value::as_u64) != Some(id) {
+      continue;
+    }
+    if let Some(error) = message.get("error") {
+      let text = error
+        .get("message")
+        .and_then(Value::as_str)
+        .unwrap_or("unknown error");
+      return Err(format!("The server rejected the request: {text}"));
+    }
+    return Ok(message.get("result").cloned().unwrap_or(Value::Null));
+  }
+}
+
+/// `initialize`, then `tools/list`.
+fn handshake(process: &Shared, messages: &mpsc::Receiver<Value>) -> Result<McpHealth, String> {
+  let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+  send(
+    process,
+    &json!({
+      "jsonrpc": "2.0",
+      "id": 1,
+      "method": "initialize",
+      "params": {
+        "protocolVersion": PROTOCOL_VERSION,
+        "capabilities": {},
+        "clientInfo": { "name": "openwork", "version": env!("CARGO_PKG_VERSION") },
+      },
+    }),
+  )?;
+  let init = response(messages, 1, deadline)?;
+  send(
+    process,
+    &json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+  )?;
+  send(
+    process,
+    &json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list" }),
+  )?;
+  let listed = response(messages, 2, deadline)?;
+
+  let info = |key: &str| {
+    init
+      .pointer(&format!("/serverInfo/{key}"))
+      .and_then(Value::as_str)
+      .map(str::to_string)
+  };
+  let tools = listed
+    .get("tools")
+    .and_then(Value::as_array)
+    .map(|tools| {
+      tools
+        .iter()
+        .filter_map(|tool| tool.get("name").and_then(Value::as_str))
+        .map(str::to_string)
+        .collect()
+    })
+    .unwrap_or_default();
+  Ok(McpHealth::Ready {
+    server: info("name"),
+    version: info("version"),
+    tools,
+  })
+}
+
+/// Starts the reader threads and the handshake for a freshly spawned server.
+fn monitor(app: AppHandle, project_dir: String, name: String, process: Shared) {
+  let (stdout, stderr) = {
+    let mut locked = lock(&process);
+    let child = locked.child.as_mut().expect("monitored server has a child");
+    (child.stdout.take(), child.stderr.take())
+  };
+
+  if let Some(stderr) = stderr {
+    let (app, project_dir, name, process) = (app.clone(), project_dir.clone(), name.clone(), process.clone());
+    thread::spawn(move || {
+      for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+        if !line.trim().is_empty() {
+          push_log(&app, &project_dir, &name, &process, "stderr", &line);
+        }
+      }
+    });
+  }
+
+  let (tx, messages) = mpsc::channel();
+  if let Some(stdout) = stdout {
+    let (app, project_dir, name, process) = (app.clone(), project_dir.clone(), name.clone(), process.clone());
+    thread::spawn(move || {
+      read_messages(stdout, |line| match serde_json::from_str::<Value>(line) {
+        Ok(message) => {
+          let _ = tx.send(message);
+        }
+        Err(_) => push_log(&app, &project_dir, &name, &process, "stdout", line),
+      });
+    });
+  }
+
+  thread::spawn(move || {
+    let health = handshake(&process, &messages).unwrap_or_else(|message| McpHealth::Failed { message });
+    if lock(&process).child.is_none() {
+      // Stopped from the app during the handshake.
+      return;
+    }
+    set_health(&app, &project_dir, &name, &process, health);
+    // Drain until the server closes stdout, then record how it ended.
+    while messages.recv().is_ok() {}
+    let code = {
+      let mut locked = lock(&process);
+      let Some(child) = locked.child.as_mut() else {
+        // Stopped from the app.
+        return;
+      };
+      let deadline = Instant::now() + Duration::from_secs(2);
+      loop {
+        match child.try_wait() {
+          Ok(Some(status)) => break status.code(),
+          Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(50)),
+          _ => break None,
+        }
+      }
+    };
+    set_health(&app, &project_dir, &name, &process, McpHealth::Exited { code });
+  });
+}
+
+fn read_messages(stdout: impl Read, mut on_line: impl FnMut(&str)) {
+  for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+    let line = line.trim();
+    if !line.is_empty() {
+      on_line(line);
+    }
+  }
+}
+
+fn is_secret_name(name: &str) -> bool {
+  let upper = name.to_ascii_uppercase();
+  SECRET_MARKERS.iter().any(|marker| upper.contains(marker))
+}
+
+fn spawn_server(project_dir: &Path, server: &McpServerConfig) -> Result<McpProcess, OpenWorkError> {
+  if server.transport != McpTransport::Local {
+    return Err(OpenWorkError::invalid_argument(format!(
+      "'{}' is a remote MCP server; only local ones can be started",
+      server.name
+    )));
+  }
+  let lookup = |key: &str| env::var(key).ok();
+  let command: Vec<String> = server
+    .command
+    .iter()
+    .map(|arg| expand_env_refs(arg, lookup))
+    .collect();
+  let Some((program, args)) = command.split_first() else {
+    return Err(OpenWorkError::invalid_argument(format!(
+      "'{}' has no command",
+      server.name
+    )));
+  };
+
+  let mut child_command = exec::command(program);
+  env_policy::apply(&mut child_command, env_policy::EnvTarget::Engine);
+  for (key, value) in &server.environment {
+    let value = expand_env_refs(value, lookup);
+    if is_secret_name(key) {
+      redact::register_secret(&value);
+    }
+    child_command.env(key, value);
+  }
+  child_command
+    .args(args)
+    .current_dir(project_dir)
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped());
+  let mut child = exec::spawn_piped(&mut child_command).map_err(|e| {
+    OpenWorkError::new(
+      if e.kind() == std::io::ErrorKind::NotFound {
+        ErrorCode::ToolNotFound
+      } else {
+        ErrorCode::Io
+      },
+      format!("Failed to start {program}: {e}"),
+    )
+  })?;
+  tracing::info!(server = %server.name, pid = child.id(), "mcp server started");
+
+  Ok(McpProcess {
+    stdin: child.stdin.take(),
+    child: Some(child),
+    health: McpHealth::Starting,
+    started_at: now_ms(),
+    logs: VecDeque::new(),
+  })
+}
+
+/// Closes the server's stdin, which ends a well-behaved stdio server, then
+/// kills it if it's still there.
+fn stop_process(process: &Shared) {
+  let (stdin, child) = {
+    let mut locked = lock(process);
+    locked.health = McpHealth::Stopped;
+    (locked.stdin.take(), locked.child.take())
+  };
+  drop(stdin);
+  if let Some(mut child) = child {
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while Instant::now() < deadline && matches!(child.try_wait(), Ok(None)) {
+      thread::sleep(Duration::from_millis(50));
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+  }
+}
+
+impl McpManager {
+  pub fn stop_all(&self) {
+    let servers: Vec<Shared> = self
+      .servers
+      .lock()
+      .expect("mcp mutex poisoned")
+      .drain()
+      .map(|(_, process)| process)
+      .collect();
+    servers.iter().for_each(stop_process);
+  }
+
+  fn get(&self, project_dir: &str, name: &str) -> Option<Shared> {
+    let servers = self.servers.lock().expect("mcp mutex poisoned");
+    servers.get(&(project_dir.to_string(), name.to_string())).cloned()
+  }
+
+  fn status(&self, project_dir: &str, config: McpServerConfig) -> McpServerStatus {
+    let Some(process) = self.get(project_dir, &config.name) else {
+      return McpServerStatus {
+        config,
+        health: McpHealth::Stopped,
+        pid: None,
+        started_at: None,
+      };
+    };
+    let process = lock(&process);
+    McpServerStatus {
+      config,
+      health: process.health.clone(),
+      pid: process.child.as_ref().map(Child::id),
+      started_at: process.child.as_ref().map(|_| process.started_at),
+    }
+  }
+}
+
+/// MCP servers in the global and project config, with the state of the ones
+/// started from the app.
+#[tauri::command]
+pub fn mcp_servers_list(
+  manager: State<McpManager>,
+  project_dir: String,
+) -> Result<Vec<McpServerStatus>, OpenWorkError> {
+  let project_dir = paths::allowed_dir(&project_dir, "projectDir")?;
+  let key = project_dir.to_string_lossy().to_string();
+  Ok(
+    configured_servers(&project_dir)
+      .into_iter()
+      .map(|config| manager.status(&key, config))
+      .collect(),
+  )
+}
+
+/// Starts (or restarts) a local MCP server and checks it over MCP; the result
+/// arrives as an `mcp://status` event.
+#[tauri::command]
+pub fn mcp_server_start(
+  app: AppHandle,
+  manager: State<McpManager>,
+  project_dir: String,
+  name: String,
+) -> Result<McpServerStatus, OpenWorkError> {
+  let project_dir = paths::allowed_dir(&project_dir, "projectDir")?;
+  let key = project_dir.to_string_lossy().to_string();
+  let server = find_server(&project_dir, name.trim())?;
+
+  if let Some(previous) = manager.get(&key, &server.name) {
+    stop_process(&previous);
+  }
+  let process: Shared = Arc::new(Mutex::new(spawn_server(&project_dir, &server)?));
+  manager
+    .servers
+    .lock()
+    .expect("mcp mutex poisoned")
+    .insert((key.clone(), server.name.clone()), process.clone());
+  monitor(app, key.clone(), server.name.clone(), process);
+  crate::telemetry::record("feature.mcp_start");
+  Ok(manager.status(&key, server))
+}
+
+#[tauri::command]
+pub fn mcp_server_stop(
+  manager: State<McpManager>,
+  project_dir: String,
+  name: String,
+) -> Result<McpServerStatus, OpenWorkError> {
+  let project_dir = paths::allowed_dir(&project_dir, "projectDir")?;
+  let key = project_dir.to_string_lossy().to_string();
+  let server = find_server(&project_dir, name.trim())?;
+  let removed = manager
+    .servers
+    .lock()
+    .expect("mcp mutex poisoned")
+    .remove(&(key.clone(), server.name.clone()));
+  if let Some(process) = removed {
+    stop_process(&process);
+  }
+  Ok(manager.status(&key, server))
+}
+
+/// Recent stderr (and stray stdout) lines of a server started from the app.
+#[tauri::command]
+pub fn mcp_server_logs(
+  manager: State<McpManager>,
+  project_dir: String,
+  name: String,
+) -> Result<Vec<McpLogLine>, OpenWorkError> {
+  let project_dir = paths::allowed_dir(&project_dir, "projectDir")?;
+  let key = project_dir.to_string_lossy().to_string();
+  Ok(
+    manager
+      .get(&key, name.trim())
+      .map(|process| lock(&process).logs.iter().cloned().collect())
+      .unwrap_or_default(),
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_mcp_entries() {
+    let config = json!({
+      "mcp": {
+        "files": {
+          "type": "local",
+          "command": ["npx", "-y", "@modelcontextprotocol/server-filesystem", "{env:HOME}"],
+          "environment": { "API_KEY": "{env:FILES_KEY}" },
+        },
+        "docs": { "type": "remote", "url": "https://example.com/mcp", "enabled": false },
+        "broken": { "command": ["x"] },
+      }
+    });
+    let servers = parse_servers(&config, "opencode.json");
+    assert_eq!(servers.len(), 2);
+    let files = servers.iter().find(|server| server.name == "files").unwrap();
+    assert_eq!(files.transport, McpTransport::Local);
+    assert_eq!(files.command[0], "npx");
+    assert!(files.enabled);
+    let docs = servers.iter().find(|server| server.name == "docs").unwrap();
+    assert_eq!(docs.url.as_deref(), Some("https://example.com/mcp"));
+    assert!(!docs.enabled);
+
+    let lookup = |key: &str| (key == "HOME").then(|| "/home/ada".to_string());
+    assert_eq!(expand_env_refs("{env:HOME}/data", lookup), "/home/ada/data");
+    assert_eq!(expand_env_refs("key={env:MISSING}", lookup), "key=");
+    assert_eq!(expand_env_refs("{env:HOME", lookup), "{env:HOME");
+  }
+}
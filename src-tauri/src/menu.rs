@@ -0,0 +1,192 @@
+//! Native application menu: File (open / recent projects), Engine
+//! (start / stop / restart) and Help (doctor).
+//!
+//! Project actions go through the same hand-off as launch arguments, so the
+//! UI starts the engine and connects exactly as if the folder had been picked
+//! in onboarding. The menu is rebuilt whenever the recent list changes.
+
+use std::{path::Path, thread};
+
+use tauri::{
+  menu::{Menu, MenuBuilder, MenuEvent, MenuItemBuilder, SubmenuBuilder},
+  AppHandle, Emitter, Manager, Wry,
+};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+
+use crate::{engine_cache::EngineCache, instance, recent, EngineManager};
+
+/// Emitted after the menu stops the main window's engine so the UI can reset.
+pub const ENGINE_STOPPED_EVENT: &str = "engine://stopped";
+
+const OPEN_PROJECT: &str = "menu:open-project";
+const RECENT_PREFIX: &str = "menu:recent:";
+const RECENT_CLEAR: &str = "menu:recent-clear";
+const ENGINE_START: &str = "menu:engine-start";
+const ENGINE_STOP: &str = "menu:engine-stop";
+const ENGINE_RESTART: &str = "menu:engine-restart";
+const DOCTOR: &str = "menu:doctor";
+
+fn build(app: &AppHandle) -> tauri::Result<Menu<Wry>> {
+  let projects: Vec<String> = recent::list(app)
+    .into_iter()
+    .filter(|dir| Path::new(dir).is_dir())
+    .collect();
+
+  let mut recent_menu = SubmenuBuilder::new(app, "Open Recent");
+  for dir in &projects {
+    recent_menu = recent_menu.text(format!("{RECENT_PREFIX}{dir}"), dir);
+  }
+  if projects.is_empty() {
+    recent_menu = recent_menu.item(
+      &MenuItemBuilder::with_id("menu:recent-empty", "No Recent Projects")
+        .enabled(false)
+        .build(app)?,
+    );
+  }
+  let recent_menu = recent_menu.separator().text(RECENT_CLEAR, "Clear Recent").build()?;
+
+  let open_project = MenuItemBuilder::with_id(OPEN_PROJECT, "Open Project…")
+    .accelerator("CmdOrCtrl+O")
+    .build(app)?;
+  let file = SubmenuBuilder::new(app, "File")
+    .item(&open_project)
+    .item(&recent_menu)
+    .separator()
+    .close_window();
+  #[cfg(not(target_os = "macos"))]
+  let file = file.quit();
+  let file = file.build()?;
+
+  let edit = SubmenuBuilder::new(app, "Edit")
+    .undo()
+    .redo()
+    .separator()
+    .cut()
+    .copy()
+    .paste()
+    .select_all()
+    .build()?;
+
+  let engine = SubmenuBuilder::new(app, "Engine")
+    .text(ENGINE_START, "Start")
+    .text(ENGINE_STOP, "Stop")
+    .text(ENGINE_RESTART, "Restart")
+    .build()?;
+
+  let help = SubmenuBuilder::new(app, "Help").text(DOCTOR, "Doctor…").build()?;
+
+  let menu = MenuBuilder::new(app);
+  #[cfg(target_os = "macos")]
+  let menu = menu.item(
+    &SubmenuBuilder::new(app, "OpenWork")
+      .about(None)
+      .separator()
+      .hide()
+      .hide_others()
+      .show_all()
+      .separator()
+      .quit()
+      .build()?,
+  );
+  menu.items(&[&file, &edit, &engine, &help]).build()
+}
+
+/// Replaces the app menu, e.g. after the recent projects change.
+pub fn rebuild(app: &AppHandle) {
+  match build(app) {
+    Ok(menu) => {
+      if let Err(e) = app.set_menu(menu) {
+        tracing::warn!(error = %e, "failed to set app menu");
+      }
+    }
+    Err(e) => tracing::warn!(error = %e, "failed to build app menu"),
+  }
+}
+
+fn main_project(app: &AppHandle) -> Option<String> {
+  let manager = app.state::<EngineManager>();
+  let info = EngineManager::snapshot_locked(&mut manager.main_engine());
+  info.running.then_some(info.project_dir).flatten()
+}
+
+fn pick_project(app: &AppHandle) {
+  let handle = app.clone();
+  app.dialog().file().set_title("Open Project").pick_folder(move |folder| {
+    let Some(Ok(path)) = folder.map(|folder| folder.into_path()) else {
+      return;
+    };
+    instance::focus_main_window(&handle);
+    instance::request_open(&handle, path.to_string_lossy().to_string());
+  });
+}
+
+fn stop_engine(app: &AppHandle) {
+  let info = crate::stop_engine(app, &app.state::<EngineManager>(), instance::MAIN_WINDOW);
+  let _ = app.emit(ENGINE_STOPPED_EVENT, info);
+}
+
+fn show_doctor(app: &AppHandle) {
+  let result = crate::doctor_report(&app.state::<EngineCache>(), true);
+  let mut message = match (&result.resolved_path, &result.version) {
+    (Some(path), Some(version)) => format!("OpenCode {version}\n{path}"),
+    (Some(path), None) => format!("OpenCode found at {path}, but its version could not be read."),
+    _ => "OpenCode CLI not found. Install it with `npm install -g opencode-ai`.".to_string(),
+  };
+  if result.found && !result.supports_serve {
+    message.push_str("\n\nThis version does not support `opencode serve`. Update OpenCode.");
+  }
+  if !result.notes.is_empty() {
+    message.push_str("\n\n");
+    message.push_str(&result.notes.join("\n"));
+  }
+
+  let kind = if result.found && result.supports_serve {
+    MessageDialogKind::Info
+  } else {
+    MessageDialogKind::Warning
+  };
+  app.dialog().message(message).title("Doctor").kind(kind).show(|_| {});
+}
+
+pub fn handle_event(app: &AppHandle, event: MenuEvent) {
+  let id = event.id().as_ref();
+  if let Some(dir) = id.strip_prefix(RECENT_PREFIX) {
+    instance::focus_main_window(app);
+    instance::request_open(app, dir.to_string());
+    return;
+  }
+
+  match id {
+    OPEN_PROJECT => pick_project(app),
+    RECENT_CLEAR => {
+      if let Err(e) = recent::clear(app) {
+        tracing::warn!(error = %e, "failed to clear recent projects");
+      }
+    }
+    ENGINE_START => {
+      if main_project(app).is_some() {
+        instance::focus_main_window(app);
+      } else if let Some(dir) = recent::list(app).into_iter().next() {
+        instance::request_open(app, dir);
+      } else {
+        pick_project(app);
+      }
+    }
+    ENGINE_RESTART => {
+      if let Some(dir) = main_project(app) {
+        instance::request_open(app, dir);
+      }
+    }
+    ENGINE_STOP => stop_engine(app),
+    DOCTOR => {
+      // The doctor runs `opencode`, which must not block the menu's thread.
+      let app = app.clone();
+      thread::spawn(move || show_doctor(&app));
+    }
+    _ => {}
+  }
+}
+
+pub fn init(app: &AppHandle) {
+  rebuild(app);
+}
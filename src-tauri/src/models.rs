@@ -0,0 +1,393 @@
+//! The model catalog behind the model picker.
+//!
+//! `models_list` asks the project's running engine, which already knows the
+//! configured providers and their models. With no engine running, it asks
+//! each provider with a key for its models instead and fills in names, limits
+//! and prices from models.dev, the catalog opencode itself uses. Catalogs are
+//! kept for a few minutes per project, models.dev for a day.
+
+use std::{
+  collections::HashMap,
+  sync::{Mutex, RwLock},
+  thread,
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, State};
+
+use crate::{
+  download,
+  engine_client::EngineClient,
+  error::{ErrorCode, OpenWorkError},
+  network, providers, EngineManager,
+};
+
+const CATALOG_URL: &str = "https://models.dev/api.json";
+const CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+const CATALOG_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+const FETCH_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ModelSource {
+  Engine,
+  Providers,
+}
+
+/// Dollars per million tokens.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelPricing {
+  pub input: f64,
+  pub output: f64,
+  pub cache_read: Option<f64>,
+  pub cache_write: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelInfo {
+  pub provider_id: String,
+  pub provider_name: String,
+  pub id: String,
+  pub name: String,
+  pub context_window: Option<u64>,
+  pub output_limit: Option<u64>,
+  pub pricing: Option<ModelPricing>,
+  pub reasoning: bool,
+  pub tool_call: bool,
+  pub attachment: bool,
+  /// The engine's default model for its provider.
+  pub is_default: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelCatalog {
+  pub source: ModelSource,
+  pub fetched_at: u64,
+  pub models: Vec<ModelInfo>,
+  /// Providers that couldn't be listed, or metadata that couldn't be loaded.
+  pub notes: Vec<String>,
+}
+
+/// Catalogs by project, for `CACHE_TTL`.
+#[derive(Default)]
+pub struct ModelCatalogCache {
+  catalogs: Mutex<HashMap<String, ModelCatalog>>,
+}
+
+/// models.dev's catalog and when it was fetched.
+static MODELS_DEV: RwLock<Option<(u64, Value)>> = RwLock::new(None);
+
+fn now_ms() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_millis() as u64)
+    .unwrap_or(0)
+}
+
+fn is_fresh(fetched_at: u64, ttl: Duration) -> bool {
+  now_ms().saturating_sub(fetched_at) < ttl.as_millis() as u64
+}
+
+fn str_field(value: &Value, key: &str) -> Option<String> {
+  value
+    .get(key)
+    .and_then(Value::as_str)
+    .filter(|s| !s.is_empty())
+    .map(str::to_string)
+}
+
+/// Prices as models.dev gives them (`cache_read`) or as opencode passes them
+/// on (`cache.read`).
+fn pricing(cost: &Value) -> Option<ModelPricing> {
+  let input = cost.get("input").and_then(Value::as_f64)?;
+  let output = cost.get("output").and_then(Value::as_f64)?;
+  let cache = |flat: &str, nested: &str| {
+    cost
+      .get(flat)
+      .or_else(|| cost.get("cache").and_then(|cache| cache.get(nested)))
+      .and_then(Value::as_f64)
+  };
+  Some(ModelPricing {
+    input,
+    output,
+    cache_read: cache("cache_read", "read"),
+    cache_write: cache("cache_write", "write"),
+  })
+}
+
+/// A model entry from the engine or models.dev, whose shapes match.
+fn model_info(provider_id: &str, provider_name: &str, id: &str, model: &Value) -> ModelInfo {
+  let limit = |key: &str| {
+    model
+      .get("limit")
+      .and_then(|limit| limit.get(key))
+      .and_then(Value::as_u64)
+      .filter(|n| *n > 0)
+  };
+  let flag = |key: &str| model.get(key).and_then(Value::as_bool).unwrap_or(false);
+  ModelInfo {
+    provider_id: provider_id.to_string(),
+    provider_name: provider_name.to_string(),
+    id: id.to_string(),
+    name: str_field(model, "name").unwrap_or_else(|| id.to_string()),
+    context_window: limit("context"),
+    output_limit: limit("output"),
+    pricing: model.get("cost").and_then(pricing),
+    reasoning: flag("reasoning"),
+    tool_call: flag("tool_call"),
+    attachment: flag("attachment"),
+    is_default: false,
+  }
+}
+
+fn sort(models: &mut [ModelInfo]) {
+  models.sort_by(|a, b| {
+    (a.provider_name.to_lowercase(), a.name.to_lowercase(), &a.id).cmp(&(
+      b.provider_name.to_lowercase(),
+      b.name.to_lowercase(),
+      &b.id,
+    ))
+  });
+}
+
+/// Models from the engine's `/config/providers`: `providers` with their
+/// `models` keyed by id, and the `default` model per provider.
+fn from_engine(body: &Value) -> Vec<ModelInfo> {
+  let mut models = Vec::new();
+  for provider in body
+    .get("providers")
+    .and_then(Value::as_array)
+    .into_iter()
+    .flatten()
+  {
+    let Some(provider_id) = str_field(provider, "id") else {
+      continue;
+    };
+    let provider_name = str_field(provider, "name").unwrap_or_else(|| provider_id.clone());
+    let default = body
+      .get("default")
+      .and_then(|default| default.get(&provider_id))
+      .and_then(Value::as_str);
+    for (key, model) in provider
+      .get("models")
+      .and_then(Value::as_object)
+      .into_iter()
+      .flatten()
+    {
+      let id = str_field(model, "id").unwrap_or_else(|| key.clone());
+      let mut info = model_info(&provider_id, &provider_name, &id, model);
+      info.is_default = default == Some(id.as_str());
+      models.push(info);
+    }
+  }
+  sort(&mut models);
+  models
+}
+
+/// `ids` listed by `provider_id`, described from models.dev's `catalog`
+/// where it knows them.
+fn from_catalog(catalog: &Value, provider_id: &str, ids: &[String]) -> Vec<ModelInfo> {
+  let provider = catalog.get(provider_id);
+  let provider_name = provider
+    .and_then(|provider| str_field(provider, "name"))
+    .unwrap_or_else(|| provider_id.to_string());
+  let known = provider.and_then(|provider| provider.get("models"));
+  ids
+    .iter()
+    .map(|id| {
+      let model = known.and_then(|known| known.get(id)).unwrap_or(&Value::Null);
+      model_info(provider_id, &provider_name, id, model)
+    })
+    .collect()
+}
+
+fn models_dev() -> Result<Value, OpenWorkError> {
+  if let Some((fetched_at, catalog)) = MODELS_DEV.read().expect("models.dev lock poisoned").as_ref() {
+    if is_fresh(*fetched_at, CATALOG_TTL) {
+      return Ok(catalog.clone());
+    }
+  }
+  let network = |message: String| OpenWorkError::new(ErrorCode::Network, message).retryable();
+  let catalog: Value = download::client()
+    .map_err(network)?
+    .get(CATALOG_URL)
+    .timeout(FETCH_TIMEOUT)
+    .send()
+    .and_then(|response| response.error_for_status())
+    .and_then(|response| response.json())
+    .map_err(|e| network(format!("Failed to fetch {CATALOG_URL}: {e}")))?;
+  *MODELS_DEV.write().expect("models.dev lock poisoned") = Some((now_ms(), catalog.clone()));
+  Ok(catalog)
+}
+
+fn from_providers() -> (Vec<ModelInfo>, Vec<String>) {
+  let listed: Vec<(&str, Result<Option<Vec<String>>, OpenWorkError>)> = thread::scope(|scope| {
+    let handles: Vec<_> = providers::supported()
+      .map(|id| (id, scope.spawn(move || providers::list_models(id))))
+      .collect();
+    handles
+      .into_iter()
+      .map(|(id, handle)| {
+        let result = handle
+          .join()
+          .unwrap_or_else(|_| Err(OpenWorkError::new(ErrorCode::Internal, "model listing panicked")));
+        (id, result)
+      })
+      .collect()
+  });
+
+  let mut notes = Vec::new();
+  let catalog = models_dev().unwrap_or_else(|e| {
+    notes.push(format!("Model details are unavailable: {}", e.message));
+    Value::Null
+  });
+  let mut models = Vec::new();
+  for (id, result) in listed {
+    match result {
+      Ok(Some(ids)) => models.extend(from_catalog(&catalog, id, &ids)),
+      Ok(None) => {}
+      Err(e) => notes.push(format!("Couldn't list {id} models: {}", e.message)),
+    }
+  }
+  sort(&mut models);
+  (models, notes)
+}
+
+/// The models available to `project_dir`, from its running engine or else
+/// straight from the providers, which needs a connection. Cached for five
+/// minutes unless `refresh`.
+#[tauri::command]
+pub fn models_list(
+  app: AppHandle,
+  manager: State<EngineManager>,
+  cache: State<ModelCatalogCache>,
+  project_dir: String,
+  refresh: Option<bool>,
+) -> Result<ModelCatalog, OpenWorkError> {
+  let project_dir = crate::require_project_dir(&project_dir)?;
+  if !refresh.unwrap_or(false) {
+    let catalogs = cache.catalogs.lock().expect("model catalog lock poisoned");
+    if let Some(catalog) = catalogs
+      .get(&project_dir)
+      .filter(|catalog| is_fresh(catalog.fetched_at, CACHE_TTL))
+    {
+      return Ok(catalog.clone());
+    }
+  }
+
+  let engine = manager.window_for_project(&project_dir).map(|window| {
+    EngineClient::for_window(&manager, &window).and_then(|client| client.get_json("config/providers"))
+  });
+  let catalog = match engine {
+    Some(Ok(body)) => ModelCatalog {
+      source: ModelSource::Engine,
+      fetched_at: now_ms(),
+      models: from_engine(&body),
+      notes: Vec::new(),
+    },
+    engine => {
+      network::require_online(&app, "Listing models without a running engine")?;
+      let (models, mut notes) = from_providers();
+      if let Some(Err(e)) = engine {
+        tracing::warn!(error = %e.message, "engine model list failed");
+        notes.insert(0, format!("The engine couldn't list models: {}", e.message));
+      }
+      ModelCatalog {
+        source: ModelSource::Providers,
+        fetched_at: now_ms(),
+        models,
+        notes,
+      }
+    }
+  };
+
+  cache
+    .catalogs
+    .lock()
+    .expect("model catalog lock poisoned")
+    .insert(project_dir, catalog.clone());
+  Ok(catalog)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  #[test]
+  fn reads_engine_providers() {
+    let body = json!({
+      "providers": [{
+        "id": "anthropic",
+        "name": "Anthropic",
+        "models": {
+          "claude-sonnet-4": {
+            "id": "claude-sonnet-4",
+            "name": "Claude Sonnet 4",
+            "cost": { "input": 3, "output": 15, "cache": { "read": 0.3, "write": 3.75 } },
+            "limit": { "context": 200000, "output": 64000 },
+            "reasoning": true,
+            "tool_call": true,
+            "attachment": true
+          },
+          "claude-haiku": { "name": "Claude Haiku", "limit": { "context": 0 } }
+        }
+      }],
+      "default": { "anthropic": "claude-sonnet-4" }
+    });
+    let models = from_engine(&body);
+    assert_eq!(models.len(), 2);
+    assert_eq!(models[0].id, "claude-haiku");
+    assert_eq!(models[0].context_window, None);
+    assert_eq!(models[0].pricing, None);
+    assert!(!models[0].is_default);
+    let sonnet = &models[1];
+    assert_eq!(sonnet.provider_name, "Anthropic");
+    assert_eq!(sonnet.context_window, Some(200000));
+    assert_eq!(sonnet.output_limit, Some(64000));
+    assert_eq!(
+      sonnet.pricing,
+      Some(ModelPricing {
+        input: 3.0,
+        output: 15.0,
+        cache_read: Some(0.3),
+        cache_write: Some(3.75),
+      })
+    );
+    assert!(sonnet.reasoning && sonnet.tool_call && sonnet.attachment && sonnet.is_default);
+  }
+
+  #[test]
+  fn describes_listed_models_from_catalog() {
+    let catalog = json!({
+      "openai": {
+        "name": "OpenAI",
+        "models": {
+          "gpt-4o": {
+            "name": "GPT-4o",
+            "cost": { "input": 2.5, "output": 10, "cache_read": 1.25 },
+            "limit": { "context": 128000, "output": 16384 },
+            "tool_call": true
+          }
+        }
+      }
+    });
+    let ids = ["gpt-4o".to_string(), "whisper-1".to_string()];
+    let models = from_catalog(&catalog, "openai", &ids);
+    assert_eq!(models[0].name, "GPT-4o");
+    assert_eq!(models[0].pricing.as_ref().and_then(|p| p.cache_read), Some(1.25));
+    assert!(models[0].tool_call);
+    assert_eq!(models[1].name, "whisper-1");
+    assert_eq!(models[1].provider_name, "OpenAI");
+    assert_eq!(models[1].context_window, None);
+
+    let bare = from_catalog(&Value::Null, "groq", &ids[..1]);
+    assert_eq!(bare[0].provider_name, "groq");
+    assert_eq!(bare[0].pricing, None);
+  }
+}
@@ -0,0 +1,188 @@
+//! Connectivity monitor.
+//!
+//! A background thread tries a TCP connection to a few well-known addresses
+//! (or to the configured proxy, which is then the only way out) every
+//! `PROBE_INTERVAL`, and emits `network://status` when the answer changes.
+//! Commands that need the internet — installs, model catalogs from the
+//! providers, update checks — call `require_online` first and fail with
+//! `OFFLINE` right away rather than waiting out their timeouts. A cached
+//! "offline" is re-checked on the spot so reconnecting takes effect at once.
+//! Nothing local is gated: engines, including ones backed by local models,
+//! start as usual.
+
+use std::{
+  net::{SocketAddr, TcpStream, ToSocketAddrs},
+  sync::RwLock,
+  thread,
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use reqwest::Url;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::{
+  download,
+  error::{ErrorCode, OpenWorkError},
+};
+
+pub const NETWORK_STATUS_EVENT: &str = "network://status";
+
+const PROBE_INTERVAL: Duration = Duration::from_secs(15);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+/// Shorter, for the re-check a command waits on.
+const RECHECK_TIMEOUT: Duration = Duration::from_millis(1500);
+
+/// Public DNS resolvers on their HTTPS ports; any one answering means we're
+/// online. Addresses, so a broken resolver can't stall the probe.
+const PROBE_ADDRS: &[&str] = &["1.1.1.1:443", "8.8.8.8:443", "9.9.9.9:443"];
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkStatus {
+  pub online: bool,
+  /// Unix ms of the last probe; `None` before the first one.
+  pub checked_at: Option<u64>,
+  /// Unix ms when `online` last changed.
+  pub changed_at: Option<u64>,
+}
+
+/// Until the first probe, assume online so nothing fails spuriously.
+static STATUS: RwLock<NetworkStatus> = RwLock::new(NetworkStatus {
+  online: true,
+  checked_at: None,
+  changed_at: None,
+});
+
+fn now_ms() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_millis() as u64)
+    .unwrap_or(0)
+}
+
+pub fn status() -> NetworkStatus {
+  *STATUS.read().expect("network status lock poisoned")
+}
+
+/// `status` after a probe that found `online` at `now`; `true` when that's a
+/// change worth announcing.
+fn apply_probe(status: &mut NetworkStatus, online: bool, now: u64) -> bool {
+  let changed = status.online != online || status.checked_at.is_none();
+  if status.online != online {
+    status.changed_at = Some(now);
+  }
+  status.online = online;
+  status.checked_at = Some(now);
+  changed
+}
+
+/// Where to probe: the proxy when one is set, else the public addresses.
+fn probe_targets(proxy: Option<&Url>) -> Vec<String> {
+  match proxy.and_then(|url| Some((url.host_str()?.to_string(), url.port_or_known_default()?))) {
+    Some((host, port)) => vec![format!("{host}:{port}")],
+    None => PROBE_ADDRS.iter().map(|addr| addr.to_string()).collect(),
+  }
+}
+
+fn reachable(target: &str, timeout: Duration) -> bool {
+  let addrs: Vec<SocketAddr> = target
+    .to_socket_addrs()
+    .map(Iterator::collect)
+    .unwrap_or_default();
+  addrs
+    .iter()
+    .any(|addr| TcpStream::connect_timeout(addr, timeout).is_ok())
+}
+
+fn probe(timeout: Duration) -> bool {
+  let targets = probe_targets(download::proxy_url().as_ref());
+  thread::scope(|scope| {
+    let handles: Vec<_> = targets
+      .iter()
+      .map(|target| scope.spawn(move || reachable(target, timeout)))
+      .collect();
+    handles.into_iter().any(|handle| handle.join().unwrap_or(false))
+  })
+}
+
+/// Probes now, records the result and announces a change.
+fn check(app: &AppHandle, timeout: Duration) -> NetworkStatus {
+  let online = probe(timeout);
+  let (status, changed) = {
+    let mut status = STATUS.write().expect("network status lock poisoned");
+    let changed = apply_probe(&mut status, online, now_ms());
+    (*status, changed)
+  };
+  if changed {
+    tracing::info!(online, "network status changed");
+    let _ = app.emit(NETWORK_STATUS_EVENT, status);
+  }
+  status
+}
+
+pub fn init(app: &AppHandle) {
+  let app = app.clone();
+  thread::spawn(move || loop {
+    check(&app, PROBE_TIMEOUT);
+    thread::sleep(PROBE_INTERVAL);
+  });
+}
+
+/// Fails with `OFFLINE` when there's no connection. `what` names the action
+/// for the message, e.g. "Installing packages".
+pub fn require_online(app: &AppHandle, what: &str) -> Result<(), OpenWorkError> {
+  if status().online || check(app, RECHECK_TIMEOUT).online {
+    return Ok(());
+  }
+  Err(
+    OpenWorkError::new(
+      ErrorCode::Offline,
+      format!("{what} needs an internet connection, and this computer is offline."),
+    )
+    .retryable(),
+  )
+}
+
+/// The last known connectivity; `refresh` probes first.
+#[tauri::command]
+pub fn network_status(app: AppHandle, refresh: Option<bool>) -> NetworkStatus {
+  if refresh.unwrap_or(false) {
+    check(&app, PROBE_TIMEOUT)
+  } else {
+    status()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn announces_first_probe_and_changes_only() {
+    let mut status = NetworkStatus {
+      online: true,
+      checked_at: None,
+      changed_at: None,
+    };
+    assert!(apply_probe(&mut status, true, 1));
+    assert!(!apply_probe(&mut status, true, 2));
+    assert_eq!(status.changed_at, None);
+    assert!(apply_probe(&mut status, false, 3));
+    assert_eq!(
+      (status.online, status.checked_at, status.changed_at),
+      (false, Some(3), Some(3))
+    );
+    assert!(!apply_probe(&mut status, false, 4));
+    assert_eq!(status.changed_at, Some(3));
+  }
+
+  #[test]
+  fn probes_the_proxy_when_set() {
+    let proxy = Url::parse("http://proxy.corp:3128").unwrap();
+    assert_eq!(probe_targets(Some(&proxy)), ["proxy.corp:3128"]);
+    let https = Url::parse("https://proxy.corp").unwrap();
+    assert_eq!(probe_targets(Some(&https)), ["proxy.corp:443"]);
+    assert_eq!(probe_targets(None).len(), PROBE_ADDRS.len());
+  }
+}
@@ -0,0 +1,209 @@
+//! Nix and NixOS.
+//!
+//! On NixOS nothing is installed under `/usr/bin`: programs live in
+//! `/nix/store` and reach PATH through profiles (the user's `~/.nix-profile`,
+//! the system's `/run/current-system/sw`). The curl installer's prebuilt
+//! opencode expects `/lib64/ld-linux*`, which NixOS doesn't have, so there the
+//! engine is installed through nix itself.
+
+// Nix isn't available on Windows; only `current` is used there.
+#![cfg_attr(windows, allow(dead_code))]
+
+use std::{
+  env, fs,
+  path::{Path, PathBuf},
+  sync::OnceLock,
+};
+
+use serde::Serialize;
+
+use crate::{home_dir, resolve_in_path};
+
+/// The nixpkgs attribute that provides opencode.
+const OPENCODE_ATTR: &str = "opencode";
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NixEnv {
+  None,
+  /// Nix installed on another distribution or macOS.
+  Nix,
+  NixOs,
+}
+
+/// `ID` from an os-release file.
+fn os_release_id(content: &str) -> Option<&str> {
+  content
+    .lines()
+    .find_map(|line| line.strip_prefix("ID="))
+    .map(|id| id.trim().trim_matches('"'))
+}
+
+/// The nix setup of this machine, detected once.
+pub fn current() -> NixEnv {
+  static ENV: OnceLock<NixEnv> = OnceLock::new();
+  *ENV.get_or_init(|| {
+    if cfg!(windows) {
+      return NixEnv::None;
+    }
+    let nixos = Path::new("/etc/NIXOS").exists()
+      || fs::read_to_string("/etc/os-release").is_ok_and(|content| os_release_id(&content) == Some("nixos"));
+    if nixos {
+      NixEnv::NixOs
+    } else if Path::new("/nix/store").is_dir() {
+      NixEnv::Nix
+    } else {
+      NixEnv::None
+    }
+  })
+}
+
+/// Profile bin directories, highest priority first. `nix_profiles` is
+/// `$NIX_PROFILES`, which lists them lowest priority first.
+fn profile_bin_dirs_from(
+  nix_profiles: Option<&str>,
+  home: Option<&Path>,
+  state_home: Option<&Path>,
+  user: Option<&str>,
+) -> Vec<PathBuf> {
+  let mut profiles: Vec<PathBuf> = nix_profiles
+    .unwrap_or_default()
+    .split_whitespace()
+    .rev()
+    .map(PathBuf::from)
+    .collect();
+  if let Some(home) = home {
+    profiles.push(home.join(".nix-profile"));
+    // Where `use-xdg-base-directories` puts the user profile.
+    let state_home = state_home.map_or_else(|| home.join(".local").join("state"), Path::to_path_buf);
+    profiles.push(state_home.join("nix").join("profile"));
+  }
+  if let Some(user) = user.filter(|user| !user.is_empty()) {
+    // `users.users.<name>.packages` and home-manager's NixOS module.
+    profiles.push(Path::new("/etc/profiles/per-user").join(user));
+  }
+  profiles.push(PathBuf::from("/run/current-system/sw"));
+  profiles.push(PathBuf::from("/nix/var/nix/profiles/default"));
+
+  let mut dirs: Vec<PathBuf> = Vec::new();
+  for bin in profiles.into_iter().map(|profile| profile.join("bin")) {
+    if !dirs.contains(&bin) {
+      dirs.push(bin);
+    }
+  }
+  dirs
+}
+
+/// Bin directories of the nix profiles that may hold opencode; empty when nix
+/// isn't installed.
+pub fn profile_bin_dirs() -> Vec<PathBuf> {
+  if current() == NixEnv::None {
+    return Vec::new();
+  }
+  let nix_profiles = env::var("NIX_PROFILES").ok();
+  let state_home = env::var_os("XDG_STATE_HOME")
+    .filter(|value| !value.is_empty())
+    .map(PathBuf::from);
+  let user = env::var("USER").ok();
+  profile_bin_dirs_from(
+    nix_profiles.as_deref(),
+    home_dir().as_deref(),
+    state_home.as_deref(),
+    user.as_deref(),
+  )
+}
+
+/// `name` from PATH or, since a GUI session's PATH may lack them, the profiles.
+fn find_tool(name: &str) -> Option<PathBuf> {
+  resolve_in_path(name).or_else(|| {
+    profile_bin_dirs()
+      .into_iter()
+      .map(|dir| dir.join(name))
+      .find(|path| path.is_file())
+  })
+}
+
+/// The command that installs opencode into the user's profile. `nix profile`
+/// manages profiles that have a `manifest.json`; older ones belong to
+/// `nix-env`, and mixing the two breaks the profile.
+pub fn install_command() -> Option<(PathBuf, Vec<String>)> {
+  let home = home_dir()?;
+  let new_style = home.join(".nix-profile").join("manifest.json").is_file()
+    || home.join(".local/state/nix/profile/manifest.json").is_file();
+  let (program, args) = if new_style {
+    let installable = format!("nixpkgs#{OPENCODE_ATTR}");
+    let args = [
+      "--extra-experimental-features",
+      "nix-command flakes",
+      "profile",
+      "install",
+      &installable,
+    ];
+    (find_tool("nix")?, Vec::from(args.map(str::to_string)))
+  } else {
+    let args = ["-f", "<nixpkgs>", "-iA", OPENCODE_ATTR];
+    (find_tool("nix-env")?, Vec::from(args.map(str::to_string)))
+  };
+  Some((program, args))
+}
+
+/// A warning when `binary` is a native executable nix didn't build: on
+/// NixOS it can't find its dynamic loader and fails to start.
+pub fn foreign_binary_note(binary: &Path, is_native: bool) -> Option<String> {
+  if current() != NixEnv::NixOs || !is_native {
+    return None;
+  }
+  let resolved = fs::canonicalize(binary).unwrap_or_else(|_| binary.to_path_buf());
+  (!resolved.starts_with("/nix/store")).then(|| {
+    format!(
+      "{} was not built by nix and may not start on NixOS. Install OpenCode with {INSTALL_HINT}",
+      binary.display()
+    )
+  })
+}
+
+/// How to install opencode by hand, for messages.
+pub const INSTALL_HINT: &str =
+  "nix profile install nixpkgs#opencode (or add opencode to environment.systemPackages)";
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn reads_os_release_id() {
+    assert_eq!(
+      os_release_id("NAME=NixOS\nID=nixos\nVERSION_ID=\"24.05\"\n"),
+      Some("nixos")
+    );
+    assert_eq!(
+      os_release_id("NAME=\"Ubuntu\"\nID=\"ubuntu\"\nID_LIKE=debian\n"),
+      Some("ubuntu")
+    );
+    assert_eq!(os_release_id("NAME=Unknown\n"), None);
+  }
+
+  #[test]
+  fn lists_profiles_by_priority() {
+    let home = Path::new("/home/ada");
+    let dirs = profile_bin_dirs_from(
+      Some("/nix/var/nix/profiles/default /home/ada/.nix-profile"),
+      Some(home),
+      None,
+      Some("ada"),
+    );
+    assert_eq!(
+      dirs,
+      [
+        "/home/ada/.nix-profile/bin",
+        "/nix/var/nix/profiles/default/bin",
+        "/home/ada/.local/state/nix/profile/bin",
+        "/etc/profiles/per-user/ada/bin",
+        "/run/current-system/sw/bin",
+      ]
+      .iter()
+      .map(PathBuf::from)
+      .collect::<Vec<_>>()
+    );
+  }
+}
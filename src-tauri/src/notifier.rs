@@ -0,0 +1,193 @@
+use std::{
+  collections::{HashMap, HashSet},
+  sync::Mutex,
+};
+
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::{
+  error::OpenWorkError,
+  history::HistoryDb,
+  relay::{EventRelay, RelayedEvent},
+  store::{read_state, write_state},
+};
+
+pub const PREFS_FILE: &str = "notifications.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NotificationPrefs {
+  pub enabled: bool,
+  /// A session finished working and is idle again.
+  pub session_idle: bool,
+  pub session_error: bool,
+  /// The agent is waiting for the user to approve a tool call.
+  pub permission_requested: bool,
+  /// Also notify while an OpenWork window is focused.
+  pub when_focused: bool,
+}
+
+impl Default for NotificationPrefs {
+  fn default() -> Self {
+    Self {
+      enabled: true,
+      session_idle: true,
+      session_error: true,
+      permission_requested: true,
+      when_focused: false,
+    }
+  }
+}
+
+/// Tracks per-session activity between busy and idle so the "finished"
+/// notification can say what happened.
+#[derive(Default)]
+pub struct Notifier {
+  prefs: Mutex<Option<NotificationPrefs>>,
+  edited_files: Mutex<HashMap<String, HashSet<String>>>,
+}
+
+impl Notifier {
+  fn prefs(&self, app: &AppHandle) -> NotificationPrefs {
+    let mut prefs = self.prefs.lock().expect("notifier mutex poisoned");
+    prefs
+      .get_or_insert_with(|| read_state(app, PREFS_FILE).unwrap_or_default())
+      .clone()
+  }
+}
+
+fn any_window_focused(app: &AppHandle) -> bool {
+  app
+    .webview_windows()
+    .values()
+    .any(|window| window.is_focused().unwrap_or(false))
+}
+
+fn session_title(app: &AppHandle, session_id: &str) -> Option<String> {
+  app
+    .state::<HistoryDb>()
+    .with_conn(|conn| {
+      conn
+        .query_row(
+          "SELECT title FROM sessions WHERE id = ?1",
+          params![session_id],
+          |row| row.get::<_, Option<String>>(0),
+        )
+        .optional()
+    })
+    .ok()
+    .flatten()
+    .flatten()
+    .filter(|title| !title.trim().is_empty())
+}
+
+fn session_label(app: &AppHandle, session_id: &str) -> String {
+  match session_title(app, session_id) {
+    Some(title) => format!("Session '{title}'"),
+    None => "Your session".to_string(),
+  }
+}
+
+fn notify(app: &AppHandle, title: &str, body: &str) {
+  if let Err(e) = app.notification().builder().title(title).body(body).show() {
+    tracing::warn!(error = %e, "failed to show notification");
+  }
+}
+
+fn handle_event(app: &AppHandle, relayed: &RelayedEvent) {
+  let notifier = app.state::<Notifier>();
+  let event_type = relayed.event.get("type").and_then(|t| t.as_str()).unwrap_or_default();
+  let properties = relayed.event.get("properties");
+  let str_prop = |key: &str| {
+    properties
+      .and_then(|p| p.get(key))
+      .and_then(|v| v.as_str())
+      .map(str::to_string)
+  };
+
+  // Edits are tracked regardless of preferences so counts stay right if
+  // notifications are turned on mid-session.
+  if event_type == "file.edited" {
+    if let (Some(file), Some(project_dir)) = (str_prop("file"), relayed.project_dir.clone()) {
+      notifier
+        .edited_files
+        .lock()
+        .expect("notifier mutex poisoned")
+        .entry(project_dir)
+        .or_default()
+        .insert(file);
+    }
+    return;
+  }
+
+  let prefs = notifier.prefs(app);
+  if !prefs.enabled || (!prefs.when_focused && any_window_focused(app)) {
+    if event_type == "session.idle" {
+      if let Some(project_dir) = &relayed.project_dir {
+        notifier.edited_files.lock().expect("notifier mutex poisoned").remove(project_dir);
+      }
+    }
+    return;
+  }
+
+  match event_type {
+    "session.idle" => {
+      let edited = relayed
+        .project_dir
+        .as_ref()
+        .and_then(|dir| notifier.edited_files.lock().expect("notifier mutex poisoned").remove(dir))
+        .map(|files| files.len())
+        .unwrap_or(0);
+
+      if !prefs.session_idle {
+        return;
+      }
+      let Some(session_id) = str_prop("sessionID") else {
+        return;
+      };
+
+      let label = session_label(app, &session_id);
+      let body = match edited {
+        0 => format!("{label} finished."),
+        1 => format!("{label} finished, 1 file changed."),
+        n => format!("{label} finished, {n} files changed."),
+      };
+      notify(app, "OpenWork", &body);
+    }
+    "session.error" if prefs.session_error => {
+      let label = str_prop("sessionID")
+        .map(|id| session_label(app, &id))
+        .unwrap_or_else(|| "A session".to_string());
+      notify(app, "OpenWork", &format!("{label} stopped with an error."));
+    }
+    "permission.updated" if prefs.permission_requested => {
+      let title = str_prop("title").unwrap_or_else(|| "The agent needs your approval".to_string());
+      notify(app, "Approval needed", &title);
+    }
+    _ => {}
+  }
+}
+
+pub fn init(app: &AppHandle) {
+  app.manage(Notifier::default());
+  app.state::<EventRelay>().subscribe(handle_event);
+}
+
+#[tauri::command]
+pub fn notification_prefs_get(app: AppHandle, notifier: State<Notifier>) -> NotificationPrefs {
+  notifier.prefs(&app)
+}
+
+#[tauri::command]
+pub fn notification_prefs_set(
+  app: AppHandle,
+  notifier: State<Notifier>,
+  prefs: NotificationPrefs,
+) -> Result<NotificationPrefs, OpenWorkError> {
+  write_state(&app, PREFS_FILE, &prefs)?;
+  *notifier.prefs.lock().expect("notifier mutex poisoned") = Some(prefs.clone());
+  Ok(prefs)
+}
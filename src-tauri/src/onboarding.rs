@@ -0,0 +1,170 @@
+//! First-run progress, kept in the backend so it survives webview reloads.
+//!
+//! Onboarding walks through installing the engine, configuring a provider and
+//! opening a first project. A step counts as done once the user finished it
+//! here or the app can see it's already in place (opencode on PATH, existing
+//! credentials, a recent project), so an existing opencode setup skips ahead.
+
+use std::{env, fs, sync::Mutex};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::{
+  engine_cache::EngineCache,
+  error::{ErrorCode, OpenWorkError},
+  opencode_data_dir, recent, resolve_opencode_config_path,
+  store::{read_state, write_state},
+};
+
+pub const ONBOARDING_FILE: &str = "onboarding.json";
+
+/// Environment variables opencode picks provider credentials up from.
+const PROVIDER_ENV_KEYS: &[&str] = &[
+  "ANTHROPIC_API_KEY",
+  "OPENAI_API_KEY",
+  "GEMINI_API_KEY",
+  "GOOGLE_GENERATIVE_AI_API_KEY",
+  "OPENROUTER_API_KEY",
+  "GROQ_API_KEY",
+  "XAI_API_KEY",
+  "DEEPSEEK_API_KEY",
+  "MISTRAL_API_KEY",
+  "AWS_BEARER_TOKEN_BEDROCK",
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum OnboardingStep {
+  InstallEngine,
+  ConfigureProvider,
+  OpenProject,
+  Done,
+}
+
+/// Steps in the order onboarding presents them.
+const STEPS: [OnboardingStep; 3] = [
+  OnboardingStep::InstallEngine,
+  OnboardingStep::ConfigureProvider,
+  OnboardingStep::OpenProject,
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct OnboardingState {
+  /// Steps the user finished in onboarding.
+  completed: Vec<OnboardingStep>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StepStatus {
+  pub step: OnboardingStep,
+  /// Finished in onboarding.
+  pub completed: bool,
+  /// Already in place on this machine.
+  pub detected: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OnboardingStatus {
+  /// First step that still needs the user, or `done`.
+  pub current: OnboardingStep,
+  pub steps: Vec<StepStatus>,
+}
+
+#[derive(Default)]
+pub struct OnboardingManager {
+  state: Mutex<Option<OnboardingState>>,
+}
+
+fn provider_configured() -> bool {
+  if opencode_data_dir().is_some_and(|dir| dir.join("auth.json").is_file()) {
+    return true;
+  }
+  if PROVIDER_ENV_KEYS
+    .iter()
+    .any(|key| env::var(key).is_ok_and(|value| !value.trim().is_empty()))
+  {
+    return true;
+  }
+  // The global config may be JSONC, so look for the key rather than parse it.
+  resolve_opencode_config_path("global", "")
+    .ok()
+    .and_then(|path| fs::read_to_string(path).ok())
+    .is_some_and(|config| config.contains("\"provider\""))
+}
+
+fn detect(app: &AppHandle, step: OnboardingStep) -> bool {
+  match step {
+    OnboardingStep::InstallEngine => app.state::<EngineCache>().resolve(false).0.is_some(),
+    OnboardingStep::ConfigureProvider => provider_configured(),
+    OnboardingStep::OpenProject => !recent::list(app).is_empty(),
+    OnboardingStep::Done => false,
+  }
+}
+
+fn status(app: &AppHandle, state: &OnboardingState) -> OnboardingStatus {
+  let steps: Vec<StepStatus> = STEPS
+    .iter()
+    .map(|&step| StepStatus {
+      step,
+      completed: state.completed.contains(&step),
+      detected: detect(app, step),
+    })
+    .collect();
+  let current = steps
+    .iter()
+    .find(|status| !status.completed && !status.detected)
+    .map(|status| status.step)
+    .unwrap_or(OnboardingStep::Done);
+  OnboardingStatus { current, steps }
+}
+
+#[tauri::command]
+pub fn onboarding_status(app: AppHandle, manager: State<OnboardingManager>) -> OnboardingStatus {
+  let mut state = manager.state.lock().expect("onboarding mutex poisoned");
+  let state = state.get_or_insert_with(|| read_state(&app, ONBOARDING_FILE).unwrap_or_default());
+  status(&app, state)
+}
+
+/// Marks `step` as finished. Steps can't be skipped, and the engine step only
+/// completes once opencode can actually be found.
+#[tauri::command]
+pub fn onboarding_advance(
+  app: AppHandle,
+  manager: State<OnboardingManager>,
+  step: OnboardingStep,
+) -> Result<OnboardingStatus, OpenWorkError> {
+  let mut state = manager.state.lock().expect("onboarding mutex poisoned");
+  let state = state.get_or_insert_with(|| read_state(&app, ONBOARDING_FILE).unwrap_or_default());
+
+  let current = status(&app, state).current;
+  if step == OnboardingStep::Done {
+    return Err(OpenWorkError::invalid_argument("step must be a setup step"));
+  }
+  if state.completed.contains(&step) {
+    return Ok(status(&app, state));
+  }
+  let index = |step| STEPS.iter().position(|&s| s == step).unwrap_or(STEPS.len());
+  if index(step) > index(current) {
+    return Err(
+      OpenWorkError::invalid_argument(format!("Finish {current:?} before {step:?}."))
+        .with_details(serde_json::json!({ "current": current })),
+    );
+  }
+  if step == OnboardingStep::InstallEngine && !detect(&app, step) {
+    return Err(OpenWorkError::new(
+      ErrorCode::EngineNotFound,
+      "OpenCode CLI not found. Install it before continuing.",
+    ));
+  }
+
+  let mut next = state.clone();
+  next.completed.push(step);
+  write_state(&app, ONBOARDING_FILE, &next)?;
+  *state = next;
+  tracing::info!(?step, "onboarding step completed");
+  Ok(status(&app, state))
+}
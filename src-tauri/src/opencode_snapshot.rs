@@ -0,0 +1,384 @@
+//! Snapshots of a project's `.opencode` folder, so trying out packages,
+//! skills and agents can be undone.
+//!
+//! `opencode_dir_snapshot` zips the folder (skills, commands, agents, plugins
+//! and any config kept there) into the app data dir. `node_modules` is left
+//! out: opencode reinstalls it from `package.json`. `opencode_dir_restore`
+//! first takes an automatic snapshot of the current folder, so a restore can
+//! itself be undone; only the newest automatic snapshots of a project are
+//! kept.
+
+use std::{
+  fs,
+  path::{Path, PathBuf},
+  sync::Mutex,
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+use crate::{
+  archive::{self, ArchiveLimits},
+  consent::{self, ConsentManager},
+  error::{ErrorCode, OpenWorkError},
+  gitignore, paths,
+  project::relative_display,
+  store::{app_state_path, read_state, write_state},
+  EngineManager,
+};
+
+pub const SNAPSHOTS_FILE: &str = "opencode-snapshots.json";
+
+const SNAPSHOTS_DIR: &str = "opencode-snapshots";
+const OPENCODE_DIR: &str = ".opencode";
+const SKIPPED_DIRS: [&str; 1] = ["node_modules"];
+/// Left beside `.opencode` if a restore is interrupted.
+const RESTORE_STAGING: [&str; 2] = ["/.opencode.restore-*/", "/.opencode.old-*/"];
+const MAX_LABEL_CHARS: usize = 200;
+/// Automatic snapshots kept per project; the user's own are never pruned.
+const MAX_AUTOMATIC: usize = 10;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OpencodeSnapshot {
+  pub id: String,
+  pub project_dir: String,
+  pub label: String,
+  pub created_at: u64,
+  pub files: u64,
+  pub bytes: u64,
+  /// Taken by `opencode_dir_restore` before replacing the folder.
+  #[serde(default)]
+  pub automatic: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct SnapshotIndex {
+  snapshots: Vec<OpencodeSnapshot>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotRestore {
+  pub snapshot: OpencodeSnapshot,
+  /// The folder as it was before the restore; `None` when there was none.
+  pub previous: Option<OpencodeSnapshot>,
+  /// The project's engine is running and only sees the change after a
+  /// restart.
+  pub restart_needed: bool,
+}
+
+/// Serializes changes to the index and the snapshot files.
+static INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+fn now_ms() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_millis() as u64)
+    .unwrap_or(0)
+}
+
+fn snapshot_path(app: &AppHandle, id: &str) -> Result<PathBuf, String> {
+  Ok(app_state_path(app, SNAPSHOTS_DIR)?.join(format!("{id}.zip")))
+}
+
+/// Files under `.opencode` as (path, archive name), without `node_modules`
+/// or links.
+fn snapshot_entries(opencode: &Path) -> Vec<(PathBuf, String)> {
+  let mut entries = Vec::new();
+  let mut stack = vec![opencode.to_path_buf()];
+  while let Some(dir) = stack.pop() {
+    for entry in fs::read_dir(&dir).into_iter().flatten().flatten() {
+      let Ok(file_type) = entry.file_type() else {
+        continue;
+      };
+      let path = entry.path();
+      if file_type.is_dir() {
+        if !SKIPPED_DIRS.contains(&entry.file_name().to_string_lossy().as_ref()) {
+          stack.push(path);
+        }
+      } else if file_type.is_file() {
+        let name = relative_display(opencode, &path);
+        entries.push((path, name));
+      }
+    }
+  }
+  entries.sort_by(|a, b| a.1.cmp(&b.1));
+  entries
+}
+
+fn normalize_label(label: &str) -> Result<String, OpenWorkError> {
+  let label = label.trim();
+  if label.is_empty() {
+    return Err(OpenWorkError::invalid_argument("label can't be empty"));
+  }
+  if label.chars().count() > MAX_LABEL_CHARS {
+    return Err(OpenWorkError::invalid_argument(format!(
+      "label can't be longer than {MAX_LABEL_CHARS} characters"
+    )));
+  }
+  Ok(label.to_string())
+}
+
+/// Automatic snapshots of `project_dir` beyond the newest `MAX_AUTOMATIC`.
+fn pruned(snapshots: &[OpencodeSnapshot], project_dir: &str) -> Vec<String> {
+  let mut automatic: Vec<&OpencodeSnapshot> = snapshots
+    .iter()
+    .filter(|snapshot| snapshot.automatic && snapshot.project_dir == project_dir)
+    .collect();
+  automatic.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+  automatic
+    .into_iter()
+    .skip(MAX_AUTOMATIC)
+    .map(|snapshot| snapshot.id.clone())
+    .collect()
+}
+
+/// Zips `project_dir`'s `.opencode` and records it. Call with `INDEX_LOCK`
+/// held.
+fn take_snapshot(
+  app: &AppHandle,
+  project_dir: &Path,
+  label: String,
+  automatic: bool,
+) -> Result<OpencodeSnapshot, OpenWorkError> {
+  let opencode = project_dir.join(OPENCODE_DIR);
+  if !opencode.is_dir() {
+    return Err(OpenWorkError::new(
+      ErrorCode::NotFound,
+      format!("{} has no {OPENCODE_DIR} folder", paths::display(project_dir)),
+    ));
+  }
+  let id = consent::random_token(16);
+  let path = snapshot_path(app, &id)?;
+  let written = archive::write_zip(&path, &snapshot_entries(&opencode))?;
+  let snapshot = OpencodeSnapshot {
+    id,
+    project_dir: project_dir.to_string_lossy().to_string(),
+    label,
+    created_at: now_ms(),
+    files: written.files,
+    bytes: written.bytes,
+    automatic,
+  };
+
+  let mut index = read_state::<SnapshotIndex>(app, SNAPSHOTS_FILE)?;
+  index.snapshots.push(snapshot.clone());
+  let stale = pruned(&index.snapshots, &snapshot.project_dir);
+  index.snapshots.retain(|snapshot| !stale.contains(&snapshot.id));
+  if let Err(e) = write_state(app, SNAPSHOTS_FILE, &index) {
+    let _ = fs::remove_file(&path);
+    return Err(e.into());
+  }
+  for id in stale {
+    if let Ok(path) = snapshot_path(app, &id) {
+      let _ = fs::remove_file(path);
+    }
+  }
+  tracing::info!(id = %snapshot.id, files = snapshot.files, automatic, "snapshotted .opencode");
+  Ok(snapshot)
+}
+
+fn find(app: &AppHandle, snapshot_id: &str) -> Result<OpencodeSnapshot, OpenWorkError> {
+  read_state::<SnapshotIndex>(app, SNAPSHOTS_FILE)?
+    .snapshots
+    .into_iter()
+    .find(|snapshot| snapshot.id == snapshot_id)
+    .ok_or_else(|| OpenWorkError::new(ErrorCode::NotFound, format!("No snapshot {snapshot_id}")))
+}
+
+#[tauri::command]
+pub fn opencode_dir_snapshot(
+  app: AppHandle,
+  project_dir: String,
+  label: String,
+) -> Result<OpencodeSnapshot, OpenWorkError> {
+  let project_dir = paths::allowed_dir(&project_dir, "projectDir")?;
+  let label = normalize_label(&label)?;
+  let _guard = INDEX_LOCK.lock().expect("snapshot index lock poisoned");
+  let snapshot = take_snapshot(&app, &project_dir, label, false)?;
+  crate::telemetry::record("feature.opencode_snapshot");
+  Ok(snapshot)
+}
+
+/// Snapshots, newest first; only `project_dir`'s when given.
+#[tauri::command]
+pub fn opencode_dir_snapshots(
+  app: AppHandle,
+  project_dir: Option<String>,
+) -> Result<Vec<OpencodeSnapshot>, OpenWorkError> {
+  let project_dir = project_dir
+    .map(|dir| paths::allowed_dir(&dir, "projectDir"))
+    .transpose()?
+    .map(|dir| dir.to_string_lossy().to_string());
+  let mut snapshots: Vec<OpencodeSnapshot> = read_state::<SnapshotIndex>(&app, SNAPSHOTS_FILE)?
+    .snapshots
+    .into_iter()
+    .filter(|snapshot| {
+      project_dir
+        .as_ref()
+        .is_none_or(|dir| &snapshot.project_dir == dir)
+    })
+    .collect();
+  snapshots.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+  Ok(snapshots)
+}
+
+/// Replaces the project's `.opencode` with the snapshot, after snapshotting
+/// the current one. The installed `node_modules` is kept.
+#[tauri::command]
+pub fn opencode_dir_restore(
+  app: AppHandle,
+  manager: State<EngineManager>,
+  consent: State<ConsentManager>,
+  snapshot_id: String,
+  confirmation_id: Option<String>,
+) -> Result<SnapshotRestore, OpenWorkError> {
+  let _guard = INDEX_LOCK.lock().expect("snapshot index lock poisoned");
+  let snapshot = find(&app, &snapshot_id)?;
+  let project_dir = paths::allowed_dir(&snapshot.project_dir, "projectDir")?;
+  let opencode = project_dir.join(OPENCODE_DIR);
+  consent.require(
+    "opencode_dir_restore",
+    &opencode.to_string_lossy(),
+    &format!(
+      "Replace {} with the snapshot \"{}\"? The current folder is snapshotted first.",
+      paths::display(&opencode),
+      snapshot.label
+    ),
+    confirmation_id.as_deref(),
+  )?;
+
+  let previous = if opencode.is_dir() {
+    let label = format!("Before restoring \"{}\"", snapshot.label);
+    Some(take_snapshot(&app, &project_dir, label, true)?)
+  } else {
+    None
+  };
+
+  // Unpack beside the folder so the swap is two renames on one volume.
+  let token = consent::random_token(8);
+  let restored = project_dir.join(format!("{OPENCODE_DIR}.restore-{token}"));
+  let replaced = project_dir.join(format!("{OPENCODE_DIR}.old-{token}"));
+  archive::extract(
+    &snapshot_path(&app, &snapshot.id)?,
+    &restored,
+    &ArchiveLimits::default(),
+    |_| {},
+  )?;
+  if opencode.exists() {
+    if let Err(e) = fs::rename(&opencode, &replaced) {
+      let _ = fs::remove_dir_all(&restored);
+      return Err(format!("Failed to move {} aside: {e}", paths::display(&opencode)).into());
+    }
+  }
+  if let Err(e) = fs::rename(&restored, &opencode) {
+    let _ = fs::rename(&replaced, &opencode);
+    let _ = fs::remove_dir_all(&restored);
+    return Err(format!("Failed to restore {}: {e}", paths::display(&opencode)).into());
+  }
+  if replaced.exists() {
+    for name in SKIPPED_DIRS {
+      let _ = fs::rename(replaced.join(name), opencode.join(name));
+    }
+    if let Err(e) = crate::remove_path(&replaced, true) {
+      tracing::warn!(error = %e, "failed to remove the replaced .opencode");
+    }
+  }
+
+  let mut generated = RESTORE_STAGING.to_vec();
+  generated.push(gitignore::OPENCODE_DEPENDENCIES);
+  gitignore::ensure_generated(&project_dir, &generated);
+
+  crate::telemetry::record("feature.opencode_restore");
+  tracing::info!(id = %snapshot.id, "restored .opencode");
+  let restart_needed = manager
+    .window_for_project(&project_dir.to_string_lossy())
+    .is_some();
+  Ok(SnapshotRestore {
+    snapshot,
+    previous,
+    restart_needed,
+  })
+}
+
+#[tauri::command]
+pub fn opencode_dir_snapshot_delete(app: AppHandle, snapshot_id: String) -> Result<(), OpenWorkError> {
+  let _guard = INDEX_LOCK.lock().expect("snapshot index lock poisoned");
+  let snapshot = find(&app, &snapshot_id)?;
+  let mut index = read_state::<SnapshotIndex>(&app, SNAPSHOTS_FILE)?;
+  index.snapshots.retain(|other| other.id != snapshot.id);
+  write_state(&app, SNAPSHOTS_FILE, &index)?;
+  let path = snapshot_path(&app, &snapshot.id)?;
+  if let Err(e) = fs::remove_file(&path) {
+    tracing::warn!(error = %e, path = %path.display(), "failed to delete snapshot archive");
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn snapshot(id: &str, project_dir: &str, created_at: u64, automatic: bool) -> OpencodeSnapshot {
+    OpencodeSnapshot {
+      id: id.to_string(),
+      project_dir: project_dir.to_string(),
+      label: id.to_string(),
+      created_at,
+      files: 0,
+      bytes: 0,
+      automatic,
+    }
+  }
+
+  #[test]
+  fn prunes_oldest_automatic_snapshots() {
+    let mut snapshots = vec![snapshot("mine", "/p", 0, false), snapshot("other", "/q", 0, true)];
+    for n in 0..MAX_AUTOMATIC as u64 + 2 {
+      snapshots.push(snapshot(&format!("auto{n}"), "/p", n + 1, true));
+    }
+    assert_eq!(pruned(&snapshots, "/p"), ["auto1", "auto0"]);
+    assert!(pruned(&snapshots, "/q").is_empty());
+  }
+
+  #[test]
+  fn snapshots_everything_but_node_modules() {
+    let root = std::env::temp_dir().join(format!("openwork-opencode-snapshot-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    for file in [
+      "opencode.json",
+      "package.json",
+      "skill/review/SKILL.md",
+      "agent/docs.md",
+      "node_modules/plugin/index.js",
+    ] {
+      let path = root.join(file);
+      fs::create_dir_all(path.parent().unwrap()).unwrap();
+      fs::write(path, "x").unwrap();
+    }
+    let names: Vec<String> = snapshot_entries(&root)
+      .into_iter()
+      .map(|(_, name)| name)
+      .collect();
+    assert_eq!(
+      names,
+      [
+        "agent/docs.md",
+        "opencode.json",
+        "package.json",
+        "skill/review/SKILL.md"
+      ]
+    );
+    let _ = fs::remove_dir_all(&root);
+  }
+
+  #[test]
+  fn normalizes_labels() {
+    assert_eq!(normalize_label("  before opkg  ").unwrap(), "before opkg");
+    assert!(normalize_label(" ").is_err());
+    assert!(normalize_label(&"x".repeat(MAX_LABEL_CHARS + 1)).is_err());
+  }
+}
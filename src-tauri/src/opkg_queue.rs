@@ -0,0 +1,273 @@
+//! One package operation at a time per project.
+//!
+//! `opkg install` runs npm underneath; two at once in the same project race
+//! on `node_modules` and the npm cache. Operations for a project wait in
+//! order here, and a request identical to one that is still waiting (same
+//! operation and package) shares that one's result instead of queueing
+//! again. Every change is emitted as `opkg://queue` so the UI can show
+//! "waiting for previous install".
+
+use std::{
+  collections::{BTreeMap, VecDeque},
+  sync::{Condvar, Mutex, MutexGuard},
+};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::{error::OpenWorkError, ExecResult};
+
+pub const OPKG_QUEUE_EVENT: &str = "opkg://queue";
+
+type Outcome = Result<ExecResult, String>;
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedOperation {
+  pub id: u64,
+  /// e.g. `install`.
+  pub operation: String,
+  pub package: String,
+  /// Requests waiting on this one, including the first.
+  pub requests: usize,
+}
+
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OpkgQueueStatus {
+  pub project_dir: String,
+  pub running: Option<QueuedOperation>,
+  pub waiting: Vec<QueuedOperation>,
+}
+
+#[derive(Debug)]
+struct Job {
+  op: QueuedOperation,
+  started: bool,
+}
+
+/// What a request has to do once queued.
+#[derive(Debug, PartialEq)]
+enum Ticket {
+  /// Run the operation when it reaches the front.
+  Run(u64),
+  /// Wait for an identical operation's outcome.
+  Share(u64),
+}
+
+#[derive(Default)]
+struct QueueState {
+  next_id: u64,
+  projects: BTreeMap<String, VecDeque<Job>>,
+  /// Outcomes kept until every sharing request has taken its copy.
+  finished: BTreeMap<u64, (Outcome, usize)>,
+}
+
+impl QueueState {
+  fn enqueue(&mut self, project_dir: &str, operation: &str, package: &str) -> Ticket {
+    let jobs = self.projects.entry(project_dir.to_string()).or_default();
+    if let Some(job) = jobs
+      .iter_mut()
+      .find(|job| !job.started && job.op.operation == operation && job.op.package == package)
+    {
+      job.op.requests += 1;
+      return Ticket::Share(job.op.id);
+    }
+    self.next_id += 1;
+    jobs.push_back(Job {
+      op: QueuedOperation {
+        id: self.next_id,
+        operation: operation.to_string(),
+        package: package.to_string(),
+        requests: 1,
+      },
+      started: false,
+    });
+    Ticket::Run(self.next_id)
+  }
+
+  /// Marks `id` started if it's at the front of its project's queue.
+  fn try_start(&mut self, project_dir: &str, id: u64) -> bool {
+    match self
+      .projects
+      .get_mut(project_dir)
+      .and_then(|jobs| jobs.front_mut())
+    {
+      Some(job) if job.op.id == id => {
+        job.started = true;
+        true
+      }
+      _ => false,
+    }
+  }
+
+  fn finish(&mut self, project_dir: &str, id: u64, outcome: Outcome) {
+    let Some(jobs) = self.projects.get_mut(project_dir) else {
+      return;
+    };
+    let Some(index) = jobs.iter().position(|job| job.op.id == id) else {
+      return;
+    };
+    let job = jobs.remove(index).expect("index in range");
+    if jobs.is_empty() {
+      self.projects.remove(project_dir);
+    }
+    let sharing = job.op.requests - 1;
+    if sharing > 0 {
+      self.finished.insert(id, (outcome, sharing));
+    }
+  }
+
+  /// A sharing request's copy of `id`'s outcome, once there is one.
+  fn take_shared(&mut self, id: u64) -> Option<Outcome> {
+    let (outcome, remaining) = self.finished.get_mut(&id)?;
+    *remaining -= 1;
+    let outcome = outcome.clone();
+    if *remaining == 0 {
+      self.finished.remove(&id);
+    }
+    Some(outcome)
+  }
+
+  fn status(&self, project_dir: &str) -> OpkgQueueStatus {
+    let mut status = OpkgQueueStatus {
+      project_dir: project_dir.to_string(),
+      ..OpkgQueueStatus::default()
+    };
+    for job in self.projects.get(project_dir).into_iter().flatten() {
+      if job.started {
+        status.running = Some(job.op.clone());
+      } else {
+        status.waiting.push(job.op.clone());
+      }
+    }
+    status
+  }
+}
+
+#[derive(Default)]
+pub struct OpkgQueue {
+  inner: Mutex<QueueState>,
+  changed: Condvar,
+}
+
+impl OpkgQueue {
+  fn lock(&self) -> MutexGuard<'_, QueueState> {
+    self.inner.lock().expect("opkg queue mutex poisoned")
+  }
+}
+
+fn emit(app: &AppHandle, state: &QueueState, project_dir: &str) {
+  let _ = app.emit(OPKG_QUEUE_EVENT, state.status(project_dir));
+}
+
+/// Runs `operation` on `package` through the project's queue: `job` is called
+/// once it's this request's turn, or not at all when an identical waiting
+/// request already covers it.
+pub fn run(
+  app: &AppHandle,
+  project_dir: &str,
+  operation: &str,
+  package: &str,
+  job: impl FnOnce() -> Outcome,
+) -> Outcome {
+  let queue = app.state::<OpkgQueue>();
+  let mut state = queue.lock();
+  let ticket = state.enqueue(project_dir, operation, package);
+  emit(app, &state, project_dir);
+
+  let id = match ticket {
+    Ticket::Share(id) => {
+      tracing::debug!(project = %project_dir, package, "sharing a queued package operation");
+      loop {
+        if let Some(outcome) = state.take_shared(id) {
+          return outcome;
+        }
+        state = queue.changed.wait(state).expect("opkg queue mutex poisoned");
+      }
+    }
+    Ticket::Run(id) => id,
+  };
+
+  while !state.try_start(project_dir, id) {
+    tracing::debug!(project = %project_dir, package, "waiting for a previous package operation");
+    state = queue.changed.wait(state).expect("opkg queue mutex poisoned");
+  }
+  emit(app, &state, project_dir);
+  drop(state);
+
+  let outcome = job();
+
+  let mut state = queue.lock();
+  state.finish(project_dir, id, outcome.clone());
+  emit(app, &state, project_dir);
+  queue.changed.notify_all();
+  outcome
+}
+
+/// What's running and waiting in the project's package queue.
+#[tauri::command]
+pub fn opkg_queue_status(
+  queue: State<OpkgQueue>,
+  project_dir: String,
+) -> Result<OpkgQueueStatus, OpenWorkError> {
+  let project_dir = crate::require_project_dir(&project_dir)?;
+  Ok(queue.lock().status(&project_dir))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn ok() -> Outcome {
+    Ok(ExecResult {
+      ok: true,
+      status: 0,
+      stdout: "added 1 package".to_string(),
+      stderr: String::new(),
+    })
+  }
+
+  #[test]
+  fn runs_one_at_a_time_per_project() {
+    let mut state = QueueState::default();
+    assert_eq!(state.enqueue("/a", "install", "x"), Ticket::Run(1));
+    assert_eq!(state.enqueue("/a", "install", "y"), Ticket::Run(2));
+    assert_eq!(state.enqueue("/b", "install", "x"), Ticket::Run(3));
+
+    assert!(state.try_start("/a", 1));
+    assert!(!state.try_start("/a", 2));
+    assert!(state.try_start("/b", 3));
+    let status = state.status("/a");
+    assert_eq!(status.running.map(|op| op.id), Some(1));
+    assert_eq!(status.waiting.len(), 1);
+
+    state.finish("/a", 1, ok());
+    assert!(state.try_start("/a", 2));
+    state.finish("/a", 2, ok());
+    assert!(!state.projects.contains_key("/a"));
+    assert!(state.finished.is_empty());
+  }
+
+  #[test]
+  fn identical_waiting_requests_share_an_outcome() {
+    let mut state = QueueState::default();
+    assert_eq!(state.enqueue("/a", "install", "x"), Ticket::Run(1));
+    assert!(state.try_start("/a", 1));
+    // Already running, so the same package queues again once...
+    assert_eq!(state.enqueue("/a", "install", "x"), Ticket::Run(2));
+    // ...and further copies share that waiting one.
+    assert_eq!(state.enqueue("/a", "install", "x"), Ticket::Share(2));
+    assert_eq!(state.enqueue("/a", "install", "x"), Ticket::Share(2));
+    assert_eq!(state.status("/a").waiting[0].requests, 3);
+
+    state.finish("/a", 1, ok());
+    assert!(state.try_start("/a", 2));
+    assert!(state.take_shared(2).is_none());
+    state.finish("/a", 2, Err("npm failed".to_string()));
+    for _ in 0..2 {
+      assert!(matches!(state.take_shared(2), Some(Err(e)) if e == "npm failed"));
+    }
+    assert!(state.finished.is_empty());
+  }
+}
@@ -0,0 +1,289 @@
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use crate::project::{project_root, relative_display};
+use crate::error::OpenWorkError;
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DetectedPackage {
+  pub name: String,
+  /// Path relative to the project root, using `/` separators.
+  pub path: String,
+  pub absolute_path: String,
+  /// `pnpm`, `npm` or `cargo`.
+  pub manager: String,
+  pub has_opencode_config: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectPackages {
+  pub workspaces: Vec<String>,
+  pub packages: Vec<DetectedPackage>,
+}
+
+pub fn wildcard_match(pattern: &str, name: &str) -> bool {
+  let pattern: Vec<char> = pattern.chars().collect();
+  let name: Vec<char> = name.chars().collect();
+
+  let (mut p, mut n) = (0, 0);
+  let mut star: Option<(usize, usize)> = None;
+
+  while n < name.len() {
+    if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+      p += 1;
+      n += 1;
+    } else if p < pattern.len() && pattern[p] == '*' {
+      star = Some((p, n));
+      p += 1;
+    } else if let Some((sp, sn)) = star {
+      p = sp + 1;
+      n = sn + 1;
+      star = Some((sp, sn + 1));
+    } else {
+      return false;
+    }
+  }
+
+  pattern[p..].iter().all(|c| *c == '*')
+}
+
+fn child_dirs(dir: &Path) -> Vec<PathBuf> {
+  let Ok(entries) = fs::read_dir(dir) else {
+    return Vec::new();
+  };
+  let mut dirs: Vec<PathBuf> = entries
+    .filter_map(Result::ok)
+    .filter(|e| e.file_type().map(|t| t.is_dir()).unwrap_or(false))
+    .filter(|e| {
+      let name = e.file_name();
+      name != "node_modules" && name != "target" && name != ".git"
+    })
+    .map(|e| e.path())
+    .collect();
+  dirs.sort();
+  dirs
+}
+
+/// Expands the directory globs used by workspace manifests (`packages/*`,
+/// `crates/**`, `apps/web`). Only directory segments are supported, and
+/// patterns that climb out of `root` match nothing.
+fn expand_dir_glob(root: &Path, pattern: &str) -> Vec<PathBuf> {
+  let pattern = pattern.trim().trim_start_matches("./").trim_end_matches('/');
+  if pattern.split(['/', '\\']).any(|segment| segment == "..") {
+    return Vec::new();
+  }
+  let mut current = vec![root.to_path_buf()];
+
+  for segment in pattern.split('/').filter(|s| !s.is_empty() && *s != ".") {
+    let mut next = Vec::new();
+    for dir in &current {
+      if segment == "**" {
+        let mut stack = vec![dir.clone()];
+        while let Some(d) = stack.pop() {
+          let children = child_dirs(&d);
+          next.push(d);
+          stack.extend(children);
+        }
+      } else if segment.contains(['*', '?']) {
+        for child in child_dirs(dir) {
+          let name = child.file_name().map(|n| n.to_string_lossy().to_string());
+          if name.is_some_and(|n| wildcard_match(segment, &n)) {
+            next.push(child);
+          }
+        }
+      } else {
+        let child = dir.join(segment);
+        if child.is_dir() {
+          next.push(child);
+        }
+      }
+    }
+    current = next;
+  }
+
+  current.retain(|dir| dir.starts_with(root));
+  current
+}
+
+fn expand_members(root: &Path, patterns: &[String], manifest: &str) -> Vec<PathBuf> {
+  let mut included: Vec<PathBuf> = Vec::new();
+  let mut excluded: Vec<PathBuf> = Vec::new();
+
+  for pattern in patterns {
+    if let Some(negated) = pattern.strip_prefix('!') {
+      excluded.extend(expand_dir_glob(root, negated));
+    } else {
+      included.extend(expand_dir_glob(root, pattern));
+    }
+  }
+
+  let mut members: Vec<PathBuf> = included
+    .into_iter()
+    .filter(|dir| !excluded.contains(dir))
+    .filter(|dir| dir.join(manifest).is_file())
+    .collect();
+  members.sort();
+  members.dedup();
+  members
+}
+
+fn read_json(path: &Path) -> Option<serde_json::Value> {
+  let content = fs::read_to_string(path).ok()?;
+  serde_json::from_str(&content).ok()
+}
+
+fn string_array(value: Option<&serde_json::Value>) -> Vec<String> {
+  value
+    .and_then(|v| v.as_array())
+    .map(|items| {
+      items
+        .iter()
+        .filter_map(|item| item.as_str().map(str::to_string))
+        .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// Reads the `packages:` list from `pnpm-workspace.yaml` without a YAML parser;
+/// the file format is a single flat list in practice.
+fn pnpm_workspace_patterns(root: &Path) -> Option<Vec<String>> {
+  let content = fs::read_to_string(root.join("pnpm-workspace.yaml")).ok()?;
+  let mut patterns = Vec::new();
+  let mut in_packages = false;
+
+  for line in content.lines() {
+    let trimmed = line.trim();
+    if trimmed.is_empty() || trimmed.starts_with('#') {
+      continue;
+    }
+    if !line.starts_with([' ', '\t', '-']) {
+      in_packages = trimmed.starts_with("packages:");
+      continue;
+    }
+    if !in_packages {
+      continue;
+    }
+    if let Some(item) = trimmed.strip_prefix('-') {
+      let item = item.split(" #").next().unwrap_or_default().trim();
+      let item = item.trim_matches(|c| c == '"' || c == '\'');
+      if !item.is_empty() {
+        patterns.push(item.to_string());
+      }
+    }
+  }
+
+  Some(patterns)
+}
+
+fn npm_workspace_patterns(root: &Path) -> Option<Vec<String>> {
+  let manifest = read_json(&root.join("package.json"))?;
+  let workspaces = manifest.get("workspaces")?;
+
+  // Either `"workspaces": [...]` or yarn's `"workspaces": { "packages": [...] }`.
+  let patterns = if workspaces.is_array() {
+    string_array(Some(workspaces))
+  } else {
+    string_array(workspaces.get("packages"))
+  };
+  Some(patterns)
+}
+
+fn cargo_workspace_patterns(root: &Path) -> Option<Vec<String>> {
+  let content = fs::read_to_string(root.join("Cargo.toml")).ok()?;
+  let manifest: toml::Value = content.parse().ok()?;
+  let workspace = manifest.get("workspace")?;
+
+  let list = |key: &str| -> Vec<String> {
+    workspace
+      .get(key)
+      .and_then(|v| v.as_array())
+      .map(|items| {
+        items
+          .iter()
+          .filter_map(|item| item.as_str().map(str::to_string))
+          .collect()
+      })
+      .unwrap_or_default()
+  };
+
+  let mut patterns = list("members");
+  patterns.extend(list("exclude").into_iter().map(|p| format!("!{p}")));
+  Some(patterns)
+}
+
+fn js_package_name(dir: &Path) -> Option<String> {
+  read_json(&dir.join("package.json"))?
+    .get("name")?
+    .as_str()
+    .map(str::to_string)
+}
+
+fn cargo_package_name(dir: &Path) -> Option<String> {
+  let content = fs::read_to_string(dir.join("Cargo.toml")).ok()?;
+  let manifest: toml::Value = content.parse().ok()?;
+  manifest
+    .get("package")?
+    .get("name")?
+    .as_str()
+    .map(str::to_string)
+}
+
+fn to_package(root: &Path, dir: &Path, manager: &str, name: Option<String>) -> DetectedPackage {
+  let fallback = dir
+    .file_name()
+    .map(|n| n.to_string_lossy().to_string())
+    .unwrap_or_default();
+
+  DetectedPackage {
+    name: name.unwrap_or(fallback),
+    path: relative_display(root, dir),
+    absolute_path: dir.to_string_lossy().to_string(),
+    manager: manager.to_string(),
+    has_opencode_config: dir.join("opencode.json").is_file() || dir.join(".opencode").is_dir(),
+  }
+}
+
+#[tauri::command]
+pub fn project_detect_packages(project_dir: String) -> Result<ProjectPackages, OpenWorkError> {
+  let root = project_root(&project_dir)?;
+
+  let mut workspaces = Vec::new();
+  let mut packages = Vec::new();
+
+  // pnpm ignores package.json `workspaces`, so it takes precedence.
+  let js_workspace = match pnpm_workspace_patterns(&root) {
+    Some(patterns) => Some(("pnpm", patterns)),
+    None => npm_workspace_patterns(&root).map(|patterns| ("npm", patterns)),
+  };
+
+  if let Some((manager, patterns)) = js_workspace {
+    workspaces.push(manager.to_string());
+    for dir in expand_members(&root, &patterns, "package.json") {
+      if dir == root {
+        continue;
+      }
+      packages.push(to_package(&root, &dir, manager, js_package_name(&dir)));
+    }
+  }
+
+  if let Some(patterns) = cargo_workspace_patterns(&root) {
+    workspaces.push("cargo".to_string());
+    for dir in expand_members(&root, &patterns, "Cargo.toml") {
+      if dir == root {
+        continue;
+      }
+      packages.push(to_package(&root, &dir, "cargo", cargo_package_name(&dir)));
+    }
+  }
+
+  Ok(ProjectPackages {
+    workspaces,
+    packages,
+  })
+}
@@ -0,0 +1,450 @@
+//! Validation for every path that arrives from the webview.
+//!
+//! Commands never touch a user-supplied path directly: directories are
+//! canonicalized, paths meant to live inside a project are checked for
+//! containment after symlink resolution, and UNC/device paths are rejected
+//! outright.
+
+use std::path::{Component, Path, PathBuf};
+
+use crate::error::{ErrorCode, OpenWorkError};
+
+/// Names Windows treats as devices regardless of directory or extension.
+const WINDOWS_RESERVED: &[&str] = &[
+  "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8", "com9",
+  "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+fn is_unc_or_device(raw: &str) -> bool {
+  let normalized = raw.replace('/', "\\");
+  normalized.starts_with("\\\\")
+}
+
+fn is_reserved_name(component: &str) -> bool {
+  if !cfg!(windows) {
+    return false;
+  }
+  reserved_device_name(component)
+}
+
+fn reserved_device_name(component: &str) -> bool {
+  let stem = component.split('.').next().unwrap_or_default().trim_end();
+  WINDOWS_RESERVED.iter().any(|r| r.eq_ignore_ascii_case(stem))
+}
+
+/// Basic checks shared by every path argument.
+pub fn validate_input(raw: &str, label: &str) -> Result<PathBuf, OpenWorkError> {
+  let trimmed = raw.trim();
+  if trimmed.is_empty() {
+    return Err(OpenWorkError::invalid_argument(format!("{label} is required")));
+  }
+  if trimmed.contains('\0') {
+    return Err(OpenWorkError::invalid_argument(format!("{label} contains invalid characters")));
+  }
+  if is_unc_or_device(trimmed) {
+    return Err(OpenWorkError::invalid_argument(format!(
+      "{label} must be a local path (network and device paths are not allowed)"
+    )));
+  }
+
+  let path = PathBuf::from(trimmed);
+  for component in path.components() {
+    if let Component::Normal(name) = component {
+      if is_reserved_name(&name.to_string_lossy()) {
+        return Err(OpenWorkError::invalid_argument(format!("{label} contains a reserved device name")));
+      }
+    }
+  }
+
+  Ok(path)
+}
+
+/// `raw` with the `\\?\` (or `\\?\UNC\`) prefix removed, if it has one.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn strip_extended(raw: &str) -> Option<String> {
+  if let Some(rest) = raw.strip_prefix(r"\\?\UNC\") {
+    return Some(format!(r"\\{rest}"));
+  }
+  raw.strip_prefix(r"\\?\").map(str::to_string)
+}
+
+/// `raw` as an extended-length path (`\\?\C:\...`, `\\?\UNC\server\share\...`),
+/// which Windows APIs accept past the 260-character `MAX_PATH` limit. The
+/// prefix also turns off Win32 normalization, so separators are converted and
+/// `.`/`..` resolved here. `None` for relative and device paths.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn to_extended(raw: &str) -> Option<String> {
+  let raw = raw.replace('/', "\\");
+  if raw.starts_with(r"\\?\") {
+    return Some(raw);
+  }
+  let (mut prefix, rest) = if let Some(unc) = raw.strip_prefix(r"\\") {
+    if unc.starts_with(['.', '?']) {
+      return None;
+    }
+    let mut parts = unc.splitn(3, '\\');
+    let server = parts.next().filter(|part| !part.is_empty())?;
+    let share = parts.next().filter(|part| !part.is_empty())?;
+    let rest = parts.next().unwrap_or_default();
+    (format!(r"\\?\UNC\{server}\{share}"), rest)
+  } else {
+    let bytes = raw.as_bytes();
+    if bytes.len() < 3 || !bytes[0].is_ascii_alphabetic() || bytes[1] != b':' || bytes[2] != b'\\' {
+      return None;
+    }
+    (format!(r"\\?\{}", &raw[..2]), &raw[3..])
+  };
+
+  let mut parts: Vec<&str> = Vec::new();
+  for part in rest.split('\\') {
+    match part {
+      "" | "." => {}
+      ".." => {
+        parts.pop();
+      }
+      _ => parts.push(part),
+    }
+  }
+  if parts.is_empty() {
+    prefix.push('\\');
+  }
+  for part in parts {
+    prefix.push('\\');
+    prefix.push_str(part);
+  }
+  Some(prefix)
+}
+
+/// `path` in extended-length form on Windows, so folders nested below it
+/// (copied skills, unpacked archives, config files) can be created and read
+/// whatever their depth. Other platforms, and paths that aren't absolute or
+/// valid Unicode, are returned unchanged. Use `display` in messages.
+pub fn extended(path: &Path) -> PathBuf {
+  #[cfg(windows)]
+  if let Some(extended) = path.to_str().and_then(to_extended) {
+    return PathBuf::from(extended);
+  }
+  path.to_path_buf()
+}
+
+/// Strips the `\\?\` prefix `canonicalize` and `extended` add on Windows so
+/// paths stay readable and comparable with user input.
+fn simplify(path: PathBuf) -> PathBuf {
+  #[cfg(windows)]
+  if let Some(simple) = path.to_str().and_then(strip_extended) {
+    return PathBuf::from(simple);
+  }
+  path
+}
+
+/// `path` as the user would write it, for messages.
+pub fn display(path: &Path) -> String {
+  simplify(path.to_path_buf()).display().to_string()
+}
+
+pub fn canonicalize(path: &Path) -> Result<PathBuf, OpenWorkError> {
+  path
+    .canonicalize()
+    .map(simplify)
+    .map_err(|e| {
+      let code = if e.kind() == std::io::ErrorKind::NotFound {
+        ErrorCode::NotFound
+      } else {
+        ErrorCode::Io
+      };
+      OpenWorkError::new(code, format!("Failed to resolve {}: {e}", display(path)))
+    })
+}
+
+/// An existing directory, canonicalized.
+pub fn existing_dir(raw: &str, label: &str) -> Result<PathBuf, OpenWorkError> {
+  let path = validate_input(raw, label)?;
+  if !path.is_absolute() {
+    return Err(OpenWorkError::invalid_argument(format!("{label} must be an absolute path")));
+  }
+  let canonical = canonicalize(&path)?;
+  if !canonical.is_dir() {
+    return Err(OpenWorkError::invalid_argument(format!(
+      "{label} is not a directory: {}",
+      canonical.display()
+    )));
+  }
+  Ok(canonical)
+}
+
+/// An existing directory the user may open as a project or import from;
+/// subject to the root allowlist when that mode is enabled.
+pub fn allowed_dir(raw: &str, label: &str) -> Result<PathBuf, OpenWorkError> {
+  let dir = existing_dir(raw, label)?;
+  crate::allowlist::ensure_allowed(&dir)?;
+  Ok(dir)
+}
+
+/// An existing file the user may import from, canonicalized; subject to the
+/// root allowlist like `allowed_dir`.
+pub fn allowed_file(raw: &str, label: &str) -> Result<PathBuf, OpenWorkError> {
+  let path = validate_input(raw, label)?;
+  if !path.is_absolute() {
+    return Err(OpenWorkError::invalid_argument(format!("{label} must be an absolute path")));
+  }
+  let canonical = canonicalize(&path)?;
+  if !canonical.is_file() {
+    return Err(OpenWorkError::invalid_argument(format!(
+      "{label} is not a file: {}",
+      canonical.display()
+    )));
+  }
+  crate::allowlist::ensure_allowed(&canonical)?;
+  Ok(canonical)
+}
+
+/// An absolute destination that may not exist yet (export targets and similar).
+pub fn absolute_target(raw: &str, label: &str) -> Result<PathBuf, OpenWorkError> {
+  let path = validate_input(raw, label)?;
+  if !path.is_absolute() {
+    return Err(OpenWorkError::invalid_argument(format!("{label} must be an absolute path")));
+  }
+  if path.components().any(|c| matches!(c, Component::ParentDir)) {
+    return Err(OpenWorkError::invalid_argument(format!("{label} must not contain '..'")));
+  }
+  Ok(path)
+}
+
+/// A file to export to: its parent must be an existing directory the
+/// allowlist permits, and an existing file is only replaced once the user
+/// confirms. Returns the destination under the canonical parent.
+pub fn export_target(
+  raw: &str,
+  label: &str,
+  consent: &crate::consent::ConsentManager,
+  confirmation_id: Option<&str>,
+) -> Result<PathBuf, OpenWorkError> {
+  let path = absolute_target(raw, label)?;
+  let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+    return Err(OpenWorkError::invalid_argument(format!("{label} must name a file")));
+  };
+  let dest = allowed_dir(&parent.to_string_lossy(), label)?.join(name);
+
+  if dest.is_dir() {
+    return Err(OpenWorkError::invalid_argument(format!(
+      "{label} is a directory: {}",
+      dest.display()
+    )));
+  }
+  if dest.exists() {
+    consent.require(
+      "overwrite_export",
+      &dest.to_string_lossy(),
+      &format!("Replace {}?", display(&dest)),
+      confirmation_id,
+    )?;
+  }
+  Ok(dest)
+}
+
+/// Lexically normalizes a relative path, refusing anything that climbs out.
+fn normalize_relative(relative: &Path) -> Result<PathBuf, OpenWorkError> {
+  let mut normalized = PathBuf::new();
+  for component in relative.components() {
+    match component {
+      Component::Normal(name) => normalized.push(name),
+      Component::CurDir => {}
+      Component::ParentDir => {
+        if !normalized.pop() {
+          return Err(OpenWorkError::invalid_argument(format!(
+            "Path escapes its root: {}",
+            relative.display()
+          )));
+        }
+      }
+      Component::RootDir | Component::Prefix(_) => {
+        return Err(OpenWorkError::invalid_argument(format!(
+          "Expected a relative path: {}",
+          relative.display()
+        )));
+      }
+    }
+  }
+  Ok(normalized)
+}
+
+/// Resolves `relative` under `root` (already canonical) and verifies the result
+/// stays inside `root` even after following symlinks. The target itself does
+/// not need to exist.
+pub fn resolve_within(root: &Path, relative: &str) -> Result<PathBuf, OpenWorkError> {
+  let relative_path = validate_input(relative, "path")?;
+  let normalized = normalize_relative(&relative_path)?;
+  let joined = root.join(&normalized);
+  ensure_within(root, &joined)?;
+  Ok(joined)
+}
+
+/// Checks that `path` is inside `root`, resolving symlinks on the deepest
+/// existing ancestor so a link inside the project can't point outside it.
+pub fn ensure_within(root: &Path, path: &Path) -> Result<(), OpenWorkError> {
+  let root = canonicalize(root)?;
+
+  let mut existing = path.to_path_buf();
+  let mut rest = Vec::new();
+  while !existing.exists() {
+    let Some(name) = existing.file_name().map(|n| n.to_os_string()) else {
+      break;
+    };
+    rest.push(name);
+    if !existing.pop() {
+      break;
+    }
+  }
+
+  let mut resolved = canonicalize(&existing)?;
+  for name in rest.into_iter().rev() {
+    resolved.push(name);
+  }
+
+  if !resolved.starts_with(&root) {
+    return Err(OpenWorkError::invalid_argument(format!(
+      "Path is outside {}: {}",
+      root.display(),
+      path.display()
+    )));
+  }
+  Ok(())
+}
+
+/// A single path segment such as a skill name: no separators, no dots-only.
+pub fn file_name_segment(raw: &str, label: &str) -> Result<String, OpenWorkError> {
+  let name = raw.trim();
+  if name.is_empty() {
+    return Err(OpenWorkError::invalid_argument(format!("{label} is required")));
+  }
+  // Device names are rejected everywhere so skills stay portable to Windows.
+  if name == "." || name == ".." || name.contains(['/', '\\', '\0', ':']) || reserved_device_name(name) {
+    return Err(OpenWorkError::invalid_argument(format!("Invalid {label}: {name}")));
+  }
+  Ok(name.to_string())
+}
+
+/// The name in `existing` that differs from `name` only in case. Filesystems
+/// that ignore case (the default on macOS and Windows) treat the two as one.
+pub fn case_conflict<'a>(existing: impl IntoIterator<Item = &'a str>, name: &str) -> Option<&'a str> {
+  let folded = name.to_lowercase();
+  existing
+    .into_iter()
+    .find(|other| *other != name && other.to_lowercase() == folded)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::fs;
+
+  fn temp_root(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("openwork-paths-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("inner")).unwrap();
+    canonicalize(&dir).unwrap()
+  }
+
+  #[test]
+  fn rejects_parent_escapes() {
+    let root = temp_root("escape");
+    assert!(resolve_within(&root, "../outside").is_err());
+    assert!(resolve_within(&root, "inner/../../outside").is_err());
+    assert!(resolve_within(&root, "inner/../../../etc/passwd").is_err());
+  }
+
+  #[test]
+  fn allows_paths_that_stay_inside() {
+    let root = temp_root("inside");
+    assert_eq!(resolve_within(&root, "inner/../inner/new.txt").unwrap(), root.join("inner/new.txt"));
+    assert_eq!(resolve_within(&root, "./inner").unwrap(), root.join("inner"));
+  }
+
+  #[test]
+  fn rejects_absolute_relative_paths() {
+    let root = temp_root("absolute");
+    assert!(resolve_within(&root, "/etc/passwd").is_err());
+  }
+
+  #[test]
+  fn rejects_unc_and_device_paths() {
+    assert!(validate_input(r"\\server\share\project", "projectDir").is_err());
+    assert!(validate_input("//server/share/project", "projectDir").is_err());
+    assert!(validate_input(r"\\.\PhysicalDrive0", "projectDir").is_err());
+    assert!(validate_input(r"\\?\C:\Windows", "projectDir").is_err());
+    assert!(validate_input("bad\0path", "projectDir").is_err());
+  }
+
+  #[cfg(windows)]
+  #[test]
+  fn rejects_reserved_device_names() {
+    assert!(validate_input(r"C:\work\NUL.txt", "projectDir").is_err());
+    assert!(validate_input(r"C:\work\com1", "projectDir").is_err());
+  }
+
+  #[test]
+  fn rejects_bad_segments() {
+    assert!(file_name_segment("..", "skill name").is_err());
+    assert!(file_name_segment("a/b", "skill name").is_err());
+    assert!(file_name_segment(r"a\b", "skill name").is_err());
+    assert!(file_name_segment("con", "skill name").is_err());
+    assert_eq!(file_name_segment(" my-skill ", "skill name").unwrap(), "my-skill");
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn rejects_symlink_escapes() {
+    let root = temp_root("symlink");
+    let outside = temp_root("symlink-target");
+    std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+    assert!(resolve_within(&root, "link/file.txt").is_err());
+  }
+
+  #[test]
+  fn existing_dir_requires_absolute_directory() {
+    let root = temp_root("existing");
+    assert!(existing_dir("relative/dir", "projectDir").is_err());
+    assert!(existing_dir(&root.join("missing").to_string_lossy(), "projectDir").is_err());
+    assert_eq!(existing_dir(&root.to_string_lossy(), "projectDir").unwrap(), root);
+  }
+
+  #[test]
+  fn converts_to_extended_length_paths() {
+    assert_eq!(to_extended(r"C:\Users\Zoë\skills").as_deref(), Some(r"\\?\C:\Users\Zoë\skills"));
+    assert_eq!(to_extended("C:/work/./a/../b/").as_deref(), Some(r"\\?\C:\work\b"));
+    assert_eq!(to_extended(r"C:\").as_deref(), Some(r"\\?\C:\"));
+    assert_eq!(to_extended(r"\\server\share\dir").as_deref(), Some(r"\\?\UNC\server\share\dir"));
+    assert_eq!(to_extended(r"\\?\C:\already").as_deref(), Some(r"\\?\C:\already"));
+    assert_eq!(to_extended(r"relative\dir"), None);
+    assert_eq!(to_extended(r"\\.\PhysicalDrive0"), None);
+
+    assert_eq!(strip_extended(r"\\?\C:\Users\Zoë").as_deref(), Some(r"C:\Users\Zoë"));
+    assert_eq!(strip_extended(r"\\?\UNC\server\share").as_deref(), Some(r"\\server\share"));
+    assert_eq!(strip_extended(r"C:\plain"), None);
+  }
+
+  #[test]
+  fn handles_unicode_and_deep_paths() {
+    let root = temp_root("unicode");
+    let relative = ["проект", "日本語のスキル", "café-ñandú"].repeat(12).join("/");
+    let deep = root.join(&relative);
+    assert!(deep.as_os_str().len() > 260);
+    fs::create_dir_all(extended(&deep)).unwrap();
+
+    assert_eq!(existing_dir(&deep.to_string_lossy(), "sourceDir").unwrap(), deep);
+    assert_eq!(resolve_within(&root, &format!("{relative}/SKILL.md")).unwrap(), deep.join("SKILL.md"));
+
+    let missing = root.join("größe").join("missing");
+    let error = existing_dir(&missing.to_string_lossy(), "sourceDir").unwrap_err();
+    assert!(error.message.contains(&missing.display().to_string()));
+  }
+
+  #[test]
+  fn finds_case_conflicts() {
+    let existing = ["myskill", "Other", "Ünïcode"];
+    assert_eq!(case_conflict(existing, "MySkill"), Some("myskill"));
+    assert_eq!(case_conflict(existing, "üNÏCODE"), Some("Ünïcode"));
+    assert_eq!(case_conflict(existing, "myskill"), None);
+    assert_eq!(case_conflict(existing, "new-skill"), None);
+  }
+}
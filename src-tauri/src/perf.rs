@@ -0,0 +1,150 @@
+//! Per-command timing. The frontend's `invoke` wrapper times every command
+//! round trip (what the user actually waits for) and reports samples in
+//! batches; this keeps a rolling window per command for `perf_stats`.
+//! Samples are only taken for commands the backend has actually handled,
+//! so a webview cannot grow the table with made-up names.
+
+use std::{
+  collections::{HashMap, HashSet, VecDeque},
+  sync::Mutex,
+};
+
+use serde::{Deserialize, Serialize};
+use tauri::{ipc::Invoke, Manager, Runtime, State};
+
+/// Samples kept per command for percentile calculation.
+const WINDOW: usize = 500;
+const MAX_SAMPLES_PER_BATCH: usize = 1000;
+/// Upper bound on tracked commands, well above the number registered.
+const MAX_COMMANDS: usize = 1000;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PerfSample {
+  pub command: String,
+  pub duration_ms: f64,
+  pub ok: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandPerf {
+  pub command: String,
+  pub calls: u64,
+  pub errors: u64,
+  pub error_rate: f64,
+  /// Percentiles over the last `WINDOW` calls.
+  pub p50_ms: f64,
+  pub p95_ms: f64,
+  pub max_ms: f64,
+}
+
+#[derive(Default)]
+struct CommandSamples {
+  calls: u64,
+  errors: u64,
+  durations: VecDeque<f64>,
+}
+
+#[derive(Default)]
+pub struct PerfStats {
+  inner: Mutex<HashMap<String, CommandSamples>>,
+  /// Commands the invoke handler has dispatched since launch.
+  handled: Mutex<HashSet<String>>,
+}
+
+impl PerfStats {
+  fn is_handled(&self, command: &str) -> bool {
+    self
+      .handled
+      .lock()
+      .expect("perf mutex poisoned")
+      .contains(command)
+  }
+}
+
+/// Wraps the app's invoke handler to note which commands it dispatched.
+pub fn track<R: Runtime>(
+  handler: impl Fn(Invoke<R>) -> bool + Send + Sync + 'static,
+) -> impl Fn(Invoke<R>) -> bool + Send + Sync + 'static {
+  move |invoke| {
+    let command = invoke.message.command().to_string();
+    let webview = invoke.message.webview();
+    let handled = handler(invoke);
+    if handled {
+      let stats = webview.state::<PerfStats>();
+      let mut known = stats.handled.lock().expect("perf mutex poisoned");
+      if !known.contains(&command) && known.len() < MAX_COMMANDS {
+        known.insert(command);
+      }
+    }
+    handled
+  }
+}
+
+/// Nearest-rank percentile of an ascending slice.
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+  if sorted.is_empty() {
+    return 0.0;
+  }
+  let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+  sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn summarize(command: &str, samples: &CommandSamples) -> CommandPerf {
+  let mut sorted: Vec<f64> = samples.durations.iter().copied().collect();
+  sorted.sort_by(f64::total_cmp);
+
+  CommandPerf {
+    command: command.to_string(),
+    calls: samples.calls,
+    errors: samples.errors,
+    error_rate: if samples.calls == 0 {
+      0.0
+    } else {
+      samples.errors as f64 / samples.calls as f64
+    },
+    p50_ms: percentile(&sorted, 50.0),
+    p95_ms: percentile(&sorted, 95.0),
+    max_ms: sorted.last().copied().unwrap_or(0.0),
+  }
+}
+
+#[tauri::command]
+pub fn perf_record(stats: State<PerfStats>, samples: Vec<PerfSample>) {
+  let mut inner = stats.inner.lock().expect("perf mutex poisoned");
+  for sample in samples.into_iter().take(MAX_SAMPLES_PER_BATCH) {
+    if !sample.duration_ms.is_finite() || sample.duration_ms < 0.0 || !stats.is_handled(&sample.command) {
+      continue;
+    }
+    if !inner.contains_key(&sample.command) && inner.len() >= MAX_COMMANDS {
+      continue;
+    }
+    let entry = inner.entry(sample.command).or_default();
+    entry.calls += 1;
+    if !sample.ok {
+      entry.errors += 1;
+    }
+    entry.durations.push_back(sample.duration_ms);
+    while entry.durations.len() > WINDOW {
+      entry.durations.pop_front();
+    }
+  }
+}
+
+/// Slowest commands (by p95) first.
+#[tauri::command]
+pub fn perf_stats(stats: State<PerfStats>) -> Vec<CommandPerf> {
+  let inner = stats.inner.lock().expect("perf mutex poisoned");
+  let mut result: Vec<CommandPerf> = inner
+    .iter()
+    .map(|(command, samples)| summarize(command, samples))
+    .collect();
+  result.sort_by(|a, b| b.p95_ms.total_cmp(&a.p95_ms).then_with(|| a.command.cmp(&b.command)));
+  result
+}
+
+#[tauri::command]
+pub fn perf_reset(stats: State<PerfStats>) {
+  stats.inner.lock().expect("perf mutex poisoned").clear();
+}
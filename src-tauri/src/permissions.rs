@@ -0,0 +1,312 @@
+//! Named permission profiles ("read-only review", "full auto") that are
+//! layered onto the engine's config when it starts, so switching between
+//! cautious and autonomous modes doesn't mean editing `opencode.json`.
+//!
+//! The selected profile is passed as `OPENCODE_CONFIG_CONTENT`, which opencode
+//! merges after the global and project config files; the files on disk are
+//! never touched. Changes apply on the next engine start.
+
+use std::{collections::HashMap, sync::RwLock};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use tauri::AppHandle;
+
+use crate::{
+  error::{ErrorCode, OpenWorkError},
+  store::{read_state, write_state},
+};
+
+pub const PERMISSIONS_FILE: &str = "permission-profiles.json";
+
+/// Keys opencode accepts under `permission`.
+const PERMISSION_KEYS: &[&str] = &["edit", "bash", "webfetch", "doom_loop", "external_directory"];
+const PERMISSION_ACTIONS: &[&str] = &["allow", "ask", "deny"];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionProfile {
+  pub id: String,
+  pub name: String,
+  #[serde(default)]
+  pub description: Option<String>,
+  /// The engine's `permission` config block, e.g. `{ "edit": "ask", "bash": { "git *": "allow", "*": "ask" } }`.
+  pub permission: Value,
+  #[serde(default, skip_deserializing)]
+  pub builtin: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PermissionSettings {
+  /// User-defined profiles; built-ins are not stored.
+  pub profiles: Vec<PermissionProfile>,
+  /// Profile id keyed by project directory.
+  pub project_profiles: HashMap<String, String>,
+  /// Used for projects without their own selection. `None` leaves the
+  /// engine's own config in charge.
+  pub default_profile: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionProfilesInfo {
+  pub profiles: Vec<PermissionProfile>,
+  pub project_profiles: HashMap<String, String>,
+  pub default_profile: Option<String>,
+}
+
+fn builtin_profiles() -> Vec<PermissionProfile> {
+  let profile = |id: &str, name: &str, description: &str, permission: Value| PermissionProfile {
+    id: id.to_string(),
+    name: name.to_string(),
+    description: Some(description.to_string()),
+    permission,
+    builtin: true,
+  };
+
+  vec![
+    profile(
+      "read-only-review",
+      "Read-only review",
+      "No file edits; only read-only git and search commands run without asking.",
+      json!({
+        "edit": "deny",
+        "bash": {
+          "git status*": "allow",
+          "git diff*": "allow",
+          "git log*": "allow",
+          "git show*": "allow",
+          "ls*": "allow",
+          "rg *": "allow",
+          "*": "deny"
+        },
+        "webfetch": "ask",
+        "external_directory": "deny"
+      }),
+    ),
+    profile(
+      "ask-first",
+      "Ask first",
+      "Every edit, command and fetch needs approval.",
+      json!({ "edit": "ask", "bash": "ask", "webfetch": "ask", "external_directory": "ask" }),
+    ),
+    profile(
+      "full-auto",
+      "Full auto",
+      "Edits, commands and fetches run without asking.",
+      json!({ "edit": "allow", "bash": "allow", "webfetch": "allow", "doom_loop": "ask", "external_directory": "ask" }),
+    ),
+  ]
+}
+
+static ACTIVE: RwLock<Option<PermissionSettings>> = RwLock::new(None);
+
+fn active() -> PermissionSettings {
+  ACTIVE
+    .read()
+    .expect("permission profiles lock poisoned")
+    .clone()
+    .unwrap_or_default()
+}
+
+fn all_profiles(settings: &PermissionSettings) -> Vec<PermissionProfile> {
+  let mut profiles = builtin_profiles();
+  profiles.extend(settings.profiles.iter().cloned());
+  profiles
+}
+
+fn find_profile(settings: &PermissionSettings, id: &str) -> Option<PermissionProfile> {
+  all_profiles(settings).into_iter().find(|p| p.id == id)
+}
+
+fn validate_action(value: &Value, key: &str) -> Result<(), OpenWorkError> {
+  match value.as_str() {
+    Some(action) if PERMISSION_ACTIONS.contains(&action) => Ok(()),
+    _ => Err(OpenWorkError::invalid_argument(format!(
+      "permission.{key} must be one of allow, ask or deny"
+    ))),
+  }
+}
+
+fn validate_permission(permission: &Value) -> Result<(), OpenWorkError> {
+  let Some(map) = permission.as_object() else {
+    return Err(OpenWorkError::invalid_argument("permission must be an object"));
+  };
+
+  for (key, value) in map {
+    if !PERMISSION_KEYS.contains(&key.as_str()) {
+      return Err(OpenWorkError::invalid_argument(format!("Unknown permission key: {key}")));
+    }
+    match value {
+      // `bash` may map command patterns to actions.
+      Value::Object(patterns) if key == "bash" => {
+        for (pattern, action) in patterns {
+          validate_action(action, &format!("bash.{pattern}"))?;
+        }
+      }
+      _ => validate_action(value, key)?,
+    }
+  }
+
+  Ok(())
+}
+
+fn validate_id(id: &str) -> Result<String, OpenWorkError> {
+  let id = id.trim();
+  let valid = !id.is_empty()
+    && id.len() <= 64
+    && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+  if !valid {
+    return Err(OpenWorkError::invalid_argument(format!("Invalid profile id: {id}")));
+  }
+  Ok(id.to_string())
+}
+
+/// Profile id and config overlay for an engine started in `project_dir`, if a
+/// profile applies.
+pub fn overlay_for(project_dir: &str) -> Option<(String, String)> {
+  let settings = active();
+  let id = settings
+    .project_profiles
+    .get(project_dir)
+    .or(settings.default_profile.as_ref())?;
+  find_profile(&settings, id).map(overlay)
+}
+
+/// Profile id and config overlay for profile `id`, whichever projects use it.
+pub fn overlay_of(id: &str) -> Result<(String, String), OpenWorkError> {
+  find_profile(&active(), id)
+    .map(overlay)
+    .ok_or_else(|| OpenWorkError::new(ErrorCode::NotFound, format!("Unknown permission profile: {id}")))
+}
+
+fn overlay(profile: PermissionProfile) -> (String, String) {
+  let mut overlay = Map::new();
+  overlay.insert("permission".to_string(), profile.permission);
+  (profile.id, Value::Object(overlay).to_string())
+}
+
+fn info(settings: &PermissionSettings) -> PermissionProfilesInfo {
+  PermissionProfilesInfo {
+    profiles: all_profiles(settings),
+    project_profiles: settings.project_profiles.clone(),
+    default_profile: settings.default_profile.clone(),
+  }
+}
+
+fn update(
+  app: &AppHandle,
+  change: impl FnOnce(&mut PermissionSettings) -> Result<(), OpenWorkError>,
+) -> Result<PermissionProfilesInfo, OpenWorkError> {
+  let mut settings = active();
+  change(&mut settings)?;
+  write_state(app, PERMISSIONS_FILE, &settings)?;
+  let result = info(&settings);
+  *ACTIVE.write().expect("permission profiles lock poisoned") = Some(settings);
+  Ok(result)
+}
+
+pub fn init(app: &AppHandle) {
+  let settings = read_state::<PermissionSettings>(app, PERMISSIONS_FILE).unwrap_or_else(|e| {
+    tracing::error!(error = %e, "failed to load permission profiles");
+    PermissionSettings::default()
+  });
+  *ACTIVE.write().expect("permission profiles lock poisoned") = Some(settings);
+}
+
+#[tauri::command]
+pub fn permission_profiles_get() -> PermissionProfilesInfo {
+  info(&active())
+}
+
+/// Creates or replaces a user-defined profile.
+#[tauri::command]
+pub fn permission_profile_save(
+  app: AppHandle,
+  profile: PermissionProfile,
+) -> Result<PermissionProfilesInfo, OpenWorkError> {
+  let id = validate_id(&profile.id)?;
+  if builtin_profiles().iter().any(|p| p.id == id) {
+    return Err(OpenWorkError::new(
+      ErrorCode::AlreadyExists,
+      format!("{id} is a built-in profile; save it under a new id"),
+    ));
+  }
+  let name = profile.name.trim().to_string();
+  if name.is_empty() {
+    return Err(OpenWorkError::invalid_argument("name is required"));
+  }
+  validate_permission(&profile.permission)?;
+
+  let profile = PermissionProfile {
+    id: id.clone(),
+    name,
+    builtin: false,
+    ..profile
+  };
+
+  update(&app, |settings| {
+    match settings.profiles.iter_mut().find(|p| p.id == id) {
+      Some(existing) => *existing = profile,
+      None => settings.profiles.push(profile),
+    }
+    Ok(())
+  })
+}
+
+#[tauri::command]
+pub fn permission_profile_delete(
+  app: AppHandle,
+  id: String,
+) -> Result<PermissionProfilesInfo, OpenWorkError> {
+  update(&app, |settings| {
+    let before = settings.profiles.len();
+    settings.profiles.retain(|p| p.id != id);
+    if settings.profiles.len() == before {
+      return Err(OpenWorkError::new(
+        ErrorCode::NotFound,
+        format!("No custom profile with id {id}"),
+      ));
+    }
+    settings.project_profiles.retain(|_, selected| *selected != id);
+    if settings.default_profile.as_deref() == Some(id.as_str()) {
+      settings.default_profile = None;
+    }
+    Ok(())
+  })
+}
+
+/// Selects the profile for `project_dir`, or the default profile when no
+/// project is given. `profile_id: None` clears the selection.
+#[tauri::command]
+pub fn permission_profile_select(
+  app: AppHandle,
+  project_dir: Option<String>,
+  profile_id: Option<String>,
+) -> Result<PermissionProfilesInfo, OpenWorkError> {
+  let project_dir = project_dir
+    .map(|dir| crate::require_project_dir(&dir))
+    .transpose()?;
+
+  update(&app, |settings| {
+    if let Some(id) = &profile_id {
+      if find_profile(settings, id).is_none() {
+        return Err(OpenWorkError::new(
+          ErrorCode::NotFound,
+          format!("Unknown permission profile: {id}"),
+        ));
+      }
+    }
+    match (project_dir, profile_id) {
+      (Some(dir), Some(id)) => {
+        settings.project_profiles.insert(dir, id);
+      }
+      (Some(dir), None) => {
+        settings.project_profiles.remove(&dir);
+      }
+      (None, id) => settings.default_profile = id,
+    }
+    Ok(())
+  })
+}
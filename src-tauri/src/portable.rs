@@ -0,0 +1,152 @@
+//! Portable mode: everything OpenWork writes lives next to the app.
+//!
+//! It is on when a file named `portable` sits next to the executable (next to
+//! the `.app` bundle on macOS), or when `OPENWORK_PORTABLE` is set. The
+//! variable may name the directory to use; any other value picks the default
+//! `openwork-data` folder beside the app.
+//!
+//! Inside that folder, `data`, `logs` and `cache` replace the per-user app
+//! directories, `tools` receives the engine install, and `opencode` holds the
+//! engine's own config, credentials and caches (through its XDG variables).
+
+use std::{
+  env,
+  path::{Path, PathBuf},
+  sync::OnceLock,
+};
+
+use serde::Serialize;
+
+pub const PORTABLE_MARKER: &str = "portable";
+pub const PORTABLE_ENV: &str = "OPENWORK_PORTABLE";
+
+const DEFAULT_DIR: &str = "openwork-data";
+
+/// Folder the app lives in: the executable's, or the one holding the bundle.
+fn app_dir(exe: &Path) -> Option<PathBuf> {
+  let dir = exe.parent()?;
+  let bundle = dir
+    .parent()
+    .filter(|contents| dir.ends_with("Contents/MacOS") && contents.ends_with("Contents"))
+    .and_then(Path::parent)
+    .filter(|bundle| bundle.extension().is_some_and(|ext| ext == "app"));
+  match bundle {
+    Some(bundle) => bundle.parent().map(Path::to_path_buf),
+    None => Some(dir.to_path_buf()),
+  }
+}
+
+/// Portable root for an `OPENWORK_PORTABLE` value and the app's folder.
+fn resolve_root(env_value: Option<&str>, app_dir: &Path, has_marker: bool) -> Option<PathBuf> {
+  match env_value.map(str::trim) {
+    Some("" | "0" | "false") => None,
+    Some(value) if Path::new(value).is_absolute() => Some(PathBuf::from(value)),
+    Some(_) => Some(app_dir.join(DEFAULT_DIR)),
+    None => has_marker.then(|| app_dir.join(DEFAULT_DIR)),
+  }
+}
+
+/// The portable root, or `None` when OpenWork uses the usual per-user folders.
+pub fn root() -> Option<&'static Path> {
+  static ROOT: OnceLock<Option<PathBuf>> = OnceLock::new();
+  ROOT
+    .get_or_init(|| {
+      let exe = env::current_exe().ok()?;
+      let app_dir = app_dir(&exe)?;
+      let env_value = env::var(PORTABLE_ENV).ok();
+      resolve_root(
+        env_value.as_deref(),
+        &app_dir,
+        app_dir.join(PORTABLE_MARKER).is_file(),
+      )
+    })
+    .as_deref()
+}
+
+/// `<root>/<name>` in portable mode.
+pub fn dir(name: &str) -> Option<PathBuf> {
+  root().map(|root| root.join(name))
+}
+
+/// The engine's XDG base directory for `key` (`XDG_CONFIG_HOME`, ...) in
+/// portable mode.
+pub fn opencode_xdg_dir(key: &str) -> Option<PathBuf> {
+  let name = match key {
+    "XDG_CONFIG_HOME" => "config",
+    "XDG_DATA_HOME" => "data",
+    "XDG_CACHE_HOME" => "cache",
+    "XDG_STATE_HOME" => "state",
+    _ => return None,
+  };
+  root().map(|root| root.join("opencode").join(name))
+}
+
+/// Points the engine's XDG variables into the portable folder.
+pub fn apply_opencode_env(command: &mut std::process::Command) {
+  for key in [
+    "XDG_CONFIG_HOME",
+    "XDG_DATA_HOME",
+    "XDG_CACHE_HOME",
+    "XDG_STATE_HOME",
+  ] {
+    if let Some(dir) = opencode_xdg_dir(key) {
+      command.env(key, dir);
+    }
+  }
+}
+
+/// Keeps the webview's own storage in the portable folder too. Windows only:
+/// WebView2 reads the folder from the environment, while WebKit has no
+/// equivalent. Runs before any thread starts, like `shell_path::init`.
+pub fn init() {
+  #[cfg(windows)]
+  if let Some(dir) = dir("webview") {
+    env::set_var("WEBVIEW2_USER_DATA_FOLDER", dir);
+  }
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PortableStatus {
+  pub enabled: bool,
+  pub root: Option<String>,
+}
+
+#[tauri::command]
+pub fn portable_status() -> PortableStatus {
+  PortableStatus {
+    enabled: root().is_some(),
+    root: root().map(|root| root.to_string_lossy().to_string()),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn finds_the_folder_beside_the_app() {
+    assert_eq!(
+      app_dir(Path::new("/Volumes/USB/OpenWork.app/Contents/MacOS/OpenWork")),
+      Some(PathBuf::from("/Volumes/USB"))
+    );
+    assert_eq!(
+      app_dir(Path::new("/media/usb/openwork/openwork")),
+      Some(PathBuf::from("/media/usb/openwork"))
+    );
+  }
+
+  #[test]
+  fn resolves_root_from_marker_and_env() {
+    let app = Path::new("/media/usb");
+    assert_eq!(resolve_root(None, app, false), None);
+    assert_eq!(resolve_root(None, app, true), Some(app.join(DEFAULT_DIR)));
+    assert_eq!(resolve_root(Some("1"), app, false), Some(app.join(DEFAULT_DIR)));
+    assert_eq!(resolve_root(Some("0"), app, true), None);
+    #[cfg(unix)]
+    assert_eq!(
+      resolve_root(Some("/srv/openwork"), app, false),
+      Some(PathBuf::from("/srv/openwork"))
+    );
+  }
+}
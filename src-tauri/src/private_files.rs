@@ -0,0 +1,210 @@
+//! Files that may hold API keys: opencode's global config and `auth.json`,
+//! and OpenWork's own state files.
+//!
+//! They are written readable by the user only: mode 0600 on unix, and on
+//! Windows an ACL without inherited entries that grants the user, SYSTEM and
+//! Administrators. The doctor lists existing ones other users can read, and
+//! `config_permissions_fix` tightens them. On Windows the check looks for
+//! grants to Everyone, Users and Authenticated Users by their English names.
+
+use std::{
+  fs::{self, OpenOptions},
+  io::Write,
+  path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use crate::error::OpenWorkError;
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExposedFile {
+  pub path: String,
+  /// Who else can read it, e.g. `mode 644` or `Everyone`.
+  pub access: String,
+}
+
+/// Whether a unix mode lets the group or others in.
+#[cfg_attr(not(unix), allow(dead_code))]
+fn mode_exposed(mode: u32) -> bool {
+  mode & 0o077 != 0
+}
+
+/// Principals other than the user that `icacls` output grants access to.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn broad_grants(icacls: &str) -> Vec<&'static str> {
+  const BROAD: &[&str] = &["Everyone", "BUILTIN\\Users", "NT AUTHORITY\\Authenticated Users"];
+  BROAD
+    .iter()
+    .copied()
+    .filter(|principal| icacls.lines().any(|line| line.contains(&format!("{principal}:"))))
+    .collect()
+}
+
+/// Who besides the user can read `path`; `None` when nobody can, or it
+/// can't be told.
+fn exposure(path: &Path) -> Option<String> {
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = fs::metadata(path).ok()?.permissions().mode() & 0o777;
+    mode_exposed(mode).then(|| format!("mode {mode:o}"))
+  }
+  #[cfg(windows)]
+  {
+    let output = crate::exec::output(&mut crate::exec::command("icacls").arg(path)).ok()?;
+    let grants = broad_grants(&String::from_utf8_lossy(&output.stdout));
+    (!grants.is_empty()).then(|| grants.join(", "))
+  }
+  #[cfg(not(any(unix, windows)))]
+  {
+    let _ = path;
+    None
+  }
+}
+
+/// Makes `path` readable by the user only.
+pub fn restrict(path: &Path) -> Result<(), String> {
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+      .map_err(|e| format!("Failed to restrict {}: {e}", path.display()))
+  }
+  #[cfg(windows)]
+  {
+    let user = std::env::var("USERNAME").map_err(|_| "USERNAME is not set".to_string())?;
+    let output = crate::exec::output(
+      crate::exec::command("icacls")
+        .arg(path)
+        .arg("/inheritance:r")
+        .args(["/remove:g", "*S-1-1-0", "*S-1-5-32-545", "*S-1-5-11"])
+        .arg("/grant:r")
+        .arg(format!("{user}:F"))
+        .args(["*S-1-5-18:F", "*S-1-5-32-544:F"]),
+    )
+    .map_err(|e| format!("Failed to run icacls: {e}"))?;
+    if !output.status.success() {
+      return Err(format!(
+        "Failed to restrict {}: {}",
+        path.display(),
+        String::from_utf8_lossy(&output.stdout).trim()
+      ));
+    }
+    Ok(())
+  }
+  #[cfg(not(any(unix, windows)))]
+  {
+    let _ = path;
+    Ok(())
+  }
+}
+
+/// Writes `content` to `path`, readable by the user only. On unix the file
+/// is restricted before anything is written to it.
+pub fn write(path: &Path, content: impl AsRef<[u8]>) -> Result<(), String> {
+  let mut options = OpenOptions::new();
+  options.write(true).create(true).truncate(true);
+  #[cfg(unix)]
+  std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+  let mut file = options
+    .open(path)
+    .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+  #[cfg(unix)]
+  restrict(path)?;
+  file
+    .write_all(content.as_ref())
+    .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+  drop(file);
+  #[cfg(not(unix))]
+  if let Err(e) = restrict(path) {
+    tracing::warn!(error = %e, "couldn't restrict access to a private file");
+  }
+  Ok(())
+}
+
+/// Whether `path` is one of opencode's global config files, which may hold
+/// provider keys.
+pub fn is_global_config(path: &Path) -> bool {
+  let global = crate::resolve_opencode_config_path("global", "").ok();
+  let dir = global.as_deref().and_then(Path::parent);
+  dir.is_some_and(|dir| path.parent() == Some(dir))
+}
+
+/// opencode's files that may hold keys.
+fn candidates() -> Vec<PathBuf> {
+  let mut files = Vec::new();
+  if let Some(dir) = crate::resolve_opencode_config_path("global", "")
+    .ok()
+    .and_then(|path| path.parent().map(Path::to_path_buf))
+  {
+    files.extend(["opencode.json", "opencode.jsonc", "config.json"].map(|name| dir.join(name)));
+  }
+  if let Some(dir) = crate::opencode_data_dir() {
+    files.push(dir.join("auth.json"));
+  }
+  files
+}
+
+/// Existing key-bearing files other users can read.
+pub fn exposed() -> Vec<ExposedFile> {
+  candidates()
+    .into_iter()
+    .filter(|path| path.is_file())
+    .filter_map(|path| {
+      Some(ExposedFile {
+        access: exposure(&path)?,
+        path: path.to_string_lossy().to_string(),
+      })
+    })
+    .collect()
+}
+
+/// Restricts the files the doctor flags, returning any that are still
+/// exposed afterwards.
+#[tauri::command]
+pub fn config_permissions_fix() -> Result<Vec<ExposedFile>, OpenWorkError> {
+  for file in exposed() {
+    restrict(Path::new(&file.path))?;
+    tracing::info!(path = %file.path, was = %file.access, "restricted access to config file");
+  }
+  Ok(exposed())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn flags_group_and_world_access() {
+    assert!(!mode_exposed(0o600));
+    assert!(!mode_exposed(0o700));
+    assert!(mode_exposed(0o644));
+    assert!(mode_exposed(0o640));
+  }
+
+  #[test]
+  fn reads_broad_grants_from_icacls() {
+    let output = "C:\\Users\\ana\\.config\\opencode\\opencode.json NT AUTHORITY\\SYSTEM:(F)\n    BUILTIN\\Administrators:(F)\n    DESKTOP\\ana:(F)\n    BUILTIN\\Users:(RX)\n\nSuccessfully processed 1 files";
+    assert_eq!(broad_grants(output), ["BUILTIN\\Users"]);
+    assert!(broad_grants("C:\\x DESKTOP\\ana:(F)").is_empty());
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn writes_files_for_the_user_only() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = std::env::temp_dir().join(format!("openwork-private-{}.json", std::process::id()));
+    fs::write(&path, "{}").unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+    assert_eq!(exposure(&path).as_deref(), Some("mode 644"));
+
+    write(&path, "{\"key\":1}").unwrap();
+    assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "{\"key\":1}");
+    assert_eq!(exposure(&path), None);
+    fs::remove_file(&path).unwrap();
+  }
+}
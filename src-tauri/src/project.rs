@@ -0,0 +1,322 @@
+use std::{
+  collections::HashMap,
+  fs,
+  path::{Path, PathBuf},
+  time::UNIX_EPOCH,
+};
+
+use ignore::{DirEntry, WalkBuilder};
+use serde::Serialize;
+use tauri::State;
+
+use crate::archive::{write_zip, ArchiveResult};
+use crate::consent::ConsentManager;
+use crate::error::OpenWorkError;
+
+/// Walks a project tree honoring `.gitignore`, `.ignore` and git excludes,
+/// even when the directory is not (yet) a git repository.
+pub fn project_walker(root: &Path, include_hidden: bool) -> WalkBuilder {
+  let mut builder = WalkBuilder::new(root);
+  builder
+    .hidden(!include_hidden)
+    .git_ignore(true)
+    .git_exclude(true)
+    .git_global(true)
+    .require_git(false)
+    .follow_links(false)
+    .filter_entry(|entry| entry.file_name() != ".git");
+  builder
+}
+
+/// Regular files under `root`, skipping anything ignored.
+pub fn project_files(root: &Path, include_hidden: bool) -> impl Iterator<Item = DirEntry> {
+  project_walker(root, include_hidden)
+    .build()
+    .filter_map(Result::ok)
+    .filter(|entry| entry.file_type().map(|t| t.is_file()).unwrap_or(false))
+}
+
+pub fn relative_display(root: &Path, path: &Path) -> String {
+  path
+    .strip_prefix(root)
+    .unwrap_or(path)
+    .to_string_lossy()
+    .replace('\\', "/")
+}
+
+pub fn project_root(project_dir: &str) -> Result<PathBuf, OpenWorkError> {
+  crate::paths::allowed_dir(project_dir, "projectDir")
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguageStat {
+  pub language: String,
+  pub files: u64,
+  pub bytes: u64,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct OpencodeAssetCounts {
+  pub skills: u64,
+  pub commands: u64,
+  pub agents: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectStats {
+  pub files: u64,
+  pub total_bytes: u64,
+  pub languages: Vec<LanguageStat>,
+  pub opencode: OpencodeAssetCounts,
+}
+
+fn language_for_extension(ext: &str) -> &'static str {
+  match ext {
+    "rs" => "Rust",
+    "ts" | "tsx" | "mts" | "cts" => "TypeScript",
+    "js" | "jsx" | "mjs" | "cjs" => "JavaScript",
+    "py" => "Python",
+    "go" => "Go",
+    "java" => "Java",
+    "kt" | "kts" => "Kotlin",
+    "swift" => "Swift",
+    "c" | "h" => "C",
+    "cc" | "cpp" | "cxx" | "hpp" | "hh" => "C++",
+    "cs" => "C#",
+    "rb" => "Ruby",
+    "php" => "PHP",
+    "sh" | "bash" | "zsh" => "Shell",
+    "html" | "htm" => "HTML",
+    "css" | "scss" | "sass" | "less" => "CSS",
+    "vue" => "Vue",
+    "svelte" => "Svelte",
+    "md" | "mdx" => "Markdown",
+    "json" | "jsonc" => "JSON",
+    "yml" | "yaml" => "YAML",
+    "toml" => "TOML",
+    "sql" => "SQL",
+    _ => "Other",
+  }
+}
+
+fn count_entries(dir: &Path, predicate: impl Fn(&Path) -> bool) -> u64 {
+  let Ok(entries) = fs::read_dir(dir) else {
+    return 0;
+  };
+  entries
+    .filter_map(Result::ok)
+    .filter(|entry| predicate(&entry.path()))
+    .count() as u64
+}
+
+fn is_markdown(path: &Path) -> bool {
+  path.is_file() && path.extension().is_some_and(|ext| ext == "md")
+}
+
+fn opencode_asset_counts(root: &Path) -> OpencodeAssetCounts {
+  let opencode = root.join(".opencode");
+  OpencodeAssetCounts {
+    skills: count_entries(&opencode.join("skill"), |p| p.join("SKILL.md").is_file()),
+    commands: count_entries(&opencode.join("command"), is_markdown),
+    agents: count_entries(&opencode.join("agent"), is_markdown),
+  }
+}
+
+#[tauri::command]
+pub fn project_stats(project_dir: String) -> Result<ProjectStats, OpenWorkError> {
+  let root = project_root(&project_dir)?;
+
+  let mut files = 0u64;
+  let mut total_bytes = 0u64;
+  let mut languages: HashMap<&'static str, LanguageStat> = HashMap::new();
+
+  for entry in project_files(&root, false) {
+    let bytes = entry.metadata().map(|m| m.len()).unwrap_or(0);
+    files += 1;
+    total_bytes += bytes;
+
+    let ext = entry
+      .path()
+      .extension()
+      .map(|e| e.to_string_lossy().to_lowercase())
+      .unwrap_or_default();
+    let language = language_for_extension(&ext);
+    let stat = languages.entry(language).or_insert_with(|| LanguageStat {
+      language: language.to_string(),
+      files: 0,
+      bytes: 0,
+    });
+    stat.files += 1;
+    stat.bytes += bytes;
+  }
+
+  let mut languages: Vec<LanguageStat> = languages.into_values().collect();
+  languages.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.language.cmp(&b.language)));
+
+  Ok(ProjectStats {
+    files,
+    total_bytes,
+    languages,
+    opencode: opencode_asset_counts(&root),
+  })
+}
+
+#[tauri::command]
+pub fn project_archive(
+  consent: State<ConsentManager>,
+  project_dir: String,
+  dest: String,
+  include_opencode: bool,
+  confirmation_id: Option<String>,
+) -> Result<ArchiveResult, OpenWorkError> {
+  let root = project_root(&project_dir)?;
+
+  let dest = crate::paths::export_target(&dest, "dest", &consent, confirmation_id.as_deref())?;
+
+  let mut entries = Vec::new();
+  for entry in project_files(&root, true) {
+    let path = entry.path();
+    if path == dest {
+      continue;
+    }
+
+    let name = relative_display(&root, path);
+    if !include_opencode && (name == ".opencode" || name.starts_with(".opencode/")) {
+      continue;
+    }
+
+    entries.push((path.to_path_buf(), name));
+  }
+
+  Ok(write_zip(&dest, &entries)?)
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentFile {
+  pub path: String,
+  pub bytes: u64,
+  /// Milliseconds since the Unix epoch.
+  pub modified_ms: u64,
+}
+
+fn modified_ms(metadata: &fs::Metadata) -> u64 {
+  metadata
+    .modified()
+    .ok()
+    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+    .map(|d| d.as_millis() as u64)
+    .unwrap_or(0)
+}
+
+#[tauri::command]
+pub fn project_recent_files(
+  project_dir: String,
+  limit: Option<usize>,
+) -> Result<Vec<RecentFile>, OpenWorkError> {
+  let root = project_root(&project_dir)?;
+  let limit = limit.unwrap_or(50).max(1);
+
+  let mut files: Vec<RecentFile> = project_files(&root, true)
+    .filter_map(|entry| {
+      let metadata = entry.metadata().ok()?;
+      Some(RecentFile {
+        path: relative_display(&root, entry.path()),
+        bytes: metadata.len(),
+        modified_ms: modified_ms(&metadata),
+      })
+    })
+    .collect();
+
+  files.sort_by(|a, b| b.modified_ms.cmp(&a.modified_ms).then_with(|| a.path.cmp(&b.path)));
+  files.truncate(limit);
+
+  Ok(files)
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskUsageEntry {
+  pub name: String,
+  pub is_dir: bool,
+  pub bytes: u64,
+  pub files: u64,
+  /// Portion of `bytes` excluded by ignore rules (dependencies, build output,
+  /// caches, `.git` itself).
+  pub ignored_bytes: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskUsage {
+  pub total_bytes: u64,
+  pub ignored_bytes: u64,
+  pub entries: Vec<DiskUsageEntry>,
+}
+
+fn top_level_name(root: &Path, path: &Path) -> Option<String> {
+  path
+    .strip_prefix(root)
+    .ok()?
+    .components()
+    .next()
+    .map(|c| c.as_os_str().to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub fn project_disk_usage(project_dir: String) -> Result<DiskUsage, OpenWorkError> {
+  let root = project_root(&project_dir)?;
+
+  let mut entries: HashMap<String, DiskUsageEntry> = HashMap::new();
+
+  // Everything on disk, ignored or not (but without following symlinks).
+  let all = WalkBuilder::new(&root)
+    .standard_filters(false)
+    .follow_links(false)
+    .build()
+    .filter_map(Result::ok)
+    .filter(|entry| entry.file_type().map(|t| t.is_file()).unwrap_or(false));
+
+  for entry in all {
+    let Some(name) = top_level_name(&root, entry.path()) else {
+      continue;
+    };
+    let bytes = entry.metadata().map(|m| m.len()).unwrap_or(0);
+    let usage = entries.entry(name.clone()).or_insert_with(|| DiskUsageEntry {
+      is_dir: root.join(&name).is_dir(),
+      name,
+      bytes: 0,
+      files: 0,
+      ignored_bytes: 0,
+    });
+    usage.bytes += bytes;
+    usage.files += 1;
+  }
+
+  // Whatever the ignore-aware walk does not visit counts as ignored.
+  let mut visible: HashMap<String, u64> = HashMap::new();
+  for entry in project_files(&root, true) {
+    if let Some(name) = top_level_name(&root, entry.path()) {
+      *visible.entry(name).or_default() += entry.metadata().map(|m| m.len()).unwrap_or(0);
+    }
+  }
+
+  let mut entries: Vec<DiskUsageEntry> = entries
+    .into_values()
+    .map(|mut usage| {
+      let visible_bytes = visible.get(&usage.name).copied().unwrap_or(0);
+      usage.ignored_bytes = usage.bytes.saturating_sub(visible_bytes);
+      usage
+    })
+    .collect();
+  entries.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.name.cmp(&b.name)));
+
+  Ok(DiskUsage {
+    total_bytes: entries.iter().map(|e| e.bytes).sum(),
+    ignored_bytes: entries.iter().map(|e| e.ignored_bytes).sum(),
+    entries,
+  })
+}
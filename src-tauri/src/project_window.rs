@@ -0,0 +1,119 @@
+//! Extra windows, each bound to one project with its own engine.
+//!
+//! The main window's engine lives in `EngineManager::inner`; engine and session
+//! commands called from a project window are routed to that window's engine by
+//! its label, and so are its event stream, file watcher and queued prompts.
+
+use std::{
+  collections::HashMap,
+  path::Path,
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex,
+  },
+};
+
+use tauri::{AppHandle, Manager, State, WebviewUrl, WebviewWindow, WebviewWindowBuilder, Window, WindowEvent};
+
+use crate::{error::OpenWorkError, instance, watcher::WatcherManager, EngineManager};
+
+const LABEL_PREFIX: &str = "project-";
+
+/// Project each project window was opened for, keyed by window label.
+#[derive(Default)]
+pub struct ProjectWindows {
+  projects: Mutex<HashMap<String, String>>,
+  next_id: AtomicU64,
+}
+
+impl ProjectWindows {
+  fn label_for(&self, project_dir: &str) -> Option<String> {
+    self
+      .projects
+      .lock()
+      .expect("project window mutex poisoned")
+      .iter()
+      .find(|(_, dir)| dir.as_str() == project_dir)
+      .map(|(label, _)| label.clone())
+  }
+}
+
+/// Stops a project window's engine once the window is gone.
+pub fn on_window_event(window: &Window, event: &WindowEvent) {
+  if !matches!(event, WindowEvent::Destroyed) || !window.label().starts_with(LABEL_PREFIX) {
+    return;
+  }
+  let app = window.app_handle();
+  app
+    .state::<ProjectWindows>()
+    .projects
+    .lock()
+    .expect("project window mutex poisoned")
+    .remove(window.label());
+
+  app.state::<WatcherManager>().remove(window.label());
+  app.state::<EngineManager>().failover.remove(window.label());
+  let engine = app.state::<EngineManager>().window_engines().remove(window.label());
+  if let Some(mut engine) = engine {
+    EngineManager::stop_locked(&mut engine);
+  }
+}
+
+/// Opens `project_dir` in a new window, or focuses the window that already has it.
+/// Returns the window label.
+// Async: creating a window from a synchronous command deadlocks on Windows.
+#[tauri::command]
+pub async fn project_window_open(
+  app: AppHandle,
+  manager: State<'_, EngineManager>,
+  windows: State<'_, ProjectWindows>,
+  project_dir: String,
+) -> Result<String, OpenWorkError> {
+  let project_dir = crate::require_project_dir(&project_dir)?;
+
+  let existing = manager
+    .window_for_project(&project_dir)
+    .or_else(|| windows.label_for(&project_dir));
+  if let Some(label) = existing {
+    if instance::focus_window(&app, &label) {
+      return Ok(label);
+    }
+  }
+
+  let label = format!("{LABEL_PREFIX}{}", windows.next_id.fetch_add(1, Ordering::Relaxed) + 1);
+  let name = Path::new(&project_dir)
+    .file_name()
+    .map(|name| name.to_string_lossy().to_string())
+    .unwrap_or_else(|| project_dir.clone());
+
+  // Registered before the page loads so `window_project` can find it.
+  windows
+    .projects
+    .lock()
+    .expect("project window mutex poisoned")
+    .insert(label.clone(), project_dir);
+
+  let built = WebviewWindowBuilder::new(&app, &label, WebviewUrl::default())
+    .title(format!("OpenWork · {name}"))
+    .inner_size(1180.0, 820.0)
+    .resizable(true)
+    .build();
+  if let Err(e) = built {
+    windows.projects.lock().expect("project window mutex poisoned").remove(&label);
+    return Err(format!("Failed to open window: {e}").into());
+  }
+
+  tracing::info!(window = %label, "project window opened");
+  Ok(label)
+}
+
+/// The project the calling window was opened for; `None` for the main window.
+#[tauri::command]
+pub fn window_project(window: WebviewWindow, windows: State<ProjectWindows>) -> Option<String> {
+  windows
+    .projects
+    .lock()
+    .expect("project window mutex poisoned")
+    .get(window.label())
+    .cloned()
+}
@@ -0,0 +1,252 @@
+use std::{
+  collections::HashSet,
+  sync::{Mutex, MutexGuard},
+  thread,
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::{
+  engine_client::{require_session_id, EngineClient},
+  error::OpenWorkError,
+  store::{read_state, write_state},
+  EngineManager,
+};
+
+const QUEUE_FILE: &str = "prompt-queue.json";
+const FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+/// After this many failed submissions an item stays queued but is no longer
+/// retried until the user removes or re-queues it. A submission that failed
+/// after the request went out counts as all of them.
+const MAX_ATTEMPTS: u32 = 5;
+
+pub const QUEUE_FLUSHED_EVENT: &str = "queue://flushed";
+pub const QUEUE_FAILED_EVENT: &str = "queue://failed";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedPrompt {
+  pub id: String,
+  pub project_dir: String,
+  pub session_id: String,
+  pub text: String,
+  pub created_at: u64,
+  pub attempts: u32,
+  pub last_error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct QueueFile {
+  items: Vec<QueuedPrompt>,
+}
+
+/// Prompts written while the engine was down, submitted in order once an
+/// engine for the same project is healthy.
+#[derive(Default)]
+pub struct PromptQueue {
+  inner: Mutex<Option<QueueFile>>,
+}
+
+fn now() -> Duration {
+  SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default()
+}
+
+impl PromptQueue {
+  /// The queue, read from disk on first use.
+  fn loaded(&self, app: &AppHandle) -> Result<MutexGuard<'_, Option<QueueFile>>, OpenWorkError> {
+    let mut state = self.inner.lock().expect("queue mutex poisoned");
+    if state.is_none() {
+      *state = Some(read_state(app, QUEUE_FILE)?);
+    }
+    Ok(state)
+  }
+
+  /// Runs `f` on the items, saving them only if `f` changed them.
+  fn with_items<T>(
+    &self,
+    app: &AppHandle,
+    f: impl FnOnce(&mut Vec<QueuedPrompt>) -> T,
+  ) -> Result<T, OpenWorkError> {
+    let mut state = self.loaded(app)?;
+    let file = state.as_mut().expect("queue loaded");
+    let before = file.items.clone();
+    let result = f(&mut file.items);
+    if file.items != before {
+      write_state(app, QUEUE_FILE, file)?;
+    }
+    Ok(result)
+  }
+
+  fn items(&self, app: &AppHandle) -> Result<Vec<QueuedPrompt>, OpenWorkError> {
+    Ok(self.loaded(app)?.as_ref().expect("queue loaded").items.clone())
+  }
+}
+
+enum SubmitError {
+  /// The engine couldn't be reached, so the prompt wasn't sent.
+  Unreachable(String),
+  /// The request went out; the engine may have taken the prompt, so sending
+  /// it again could submit it twice.
+  Sent(String),
+}
+
+fn submit(client: &EngineClient, item: &QueuedPrompt) -> Result<(), SubmitError> {
+  let body = json!({
+    "parts": [{ "type": "text", "text": item.text }],
+  });
+
+  let response = client
+    .request(Method::POST, &format!("session/{}/message", item.session_id))
+    .json(&body)
+    .send()
+    .map_err(|e| {
+      let message = format!("Failed to submit prompt: {e}");
+      if e.is_connect() {
+        SubmitError::Unreachable(message)
+      } else {
+        SubmitError::Sent(message)
+      }
+    })?;
+
+  if !response.status().is_success() {
+    let status = response.status();
+    let text = response.text().unwrap_or_default();
+    return Err(SubmitError::Sent(format!(
+      "Engine returned {status}: {}",
+      text.trim()
+    )));
+  }
+  Ok(())
+}
+
+/// Finds a running, healthy engine for `project_dir`, in whichever window
+/// has it open.
+fn engine_for(app: &AppHandle, project_dir: &str) -> Option<EngineClient> {
+  let manager = app.state::<EngineManager>();
+  let window = manager.window_for_project(project_dir)?;
+
+  // Prompt submission blocks until the agent finishes, so no request timeout.
+  let client = EngineClient::streaming_for_window(&manager, &window).ok()?;
+  EngineClient::for_window(&manager, &window)
+    .ok()
+    .filter(EngineClient::is_healthy)
+    .map(|_| client)
+}
+
+fn flush_once(app: &AppHandle) {
+  let queue = app.state::<PromptQueue>();
+  let Ok(items) = queue.items(app) else {
+    return;
+  };
+
+  // Keep prompts for a session in order: once one of its items is stuck or
+  // fails, its later items wait. Other sessions carry on.
+  let mut blocked: HashSet<(String, String)> = HashSet::new();
+  for item in items {
+    let session = (item.project_dir.clone(), item.session_id.clone());
+    if blocked.contains(&session) {
+      continue;
+    }
+    if item.attempts >= MAX_ATTEMPTS {
+      blocked.insert(session);
+      continue;
+    }
+    let Some(client) = engine_for(app, &item.project_dir) else {
+      blocked.insert(session);
+      continue;
+    };
+
+    match submit(&client, &item) {
+      Ok(()) => {
+        let _ = queue.with_items(app, |items| items.retain(|i| i.id != item.id));
+        let _ = app.emit(QUEUE_FLUSHED_EVENT, &item);
+      }
+      Err(error) => {
+        let (attempts, error) = match error {
+          SubmitError::Unreachable(error) => (item.attempts + 1, error),
+          SubmitError::Sent(error) => (MAX_ATTEMPTS, error),
+        };
+        let failed = queue.with_items(app, |items| {
+          let entry = items.iter_mut().find(|i| i.id == item.id)?;
+          entry.attempts = attempts;
+          entry.last_error = Some(crate::redact::redact(&error));
+          Some(entry.clone())
+        });
+        if let Ok(Some(failed)) = failed {
+          let _ = app.emit(QUEUE_FAILED_EVENT, failed);
+        }
+        blocked.insert(session);
+      }
+    }
+  }
+}
+
+pub fn init(app: &AppHandle) {
+  app.manage(PromptQueue::default());
+
+  let app = app.clone();
+  thread::spawn(move || loop {
+    flush_once(&app);
+    thread::sleep(FLUSH_INTERVAL);
+  });
+}
+
+#[tauri::command]
+pub fn queue_prompt(
+  app: AppHandle,
+  queue: State<PromptQueue>,
+  project_dir: String,
+  session_id: String,
+  text: String,
+) -> Result<QueuedPrompt, OpenWorkError> {
+  let project_dir = crate::require_project_dir(&project_dir)?;
+  let session_id = require_session_id(&session_id)?;
+  if text.trim().is_empty() {
+    return Err(OpenWorkError::invalid_argument("text is required"));
+  }
+
+  let now = now();
+  let item = QueuedPrompt {
+    id: format!("q-{:x}", now.as_nanos()),
+    project_dir,
+    session_id,
+    text,
+    created_at: now.as_millis() as u64,
+    attempts: 0,
+    last_error: None,
+  };
+
+  let queued = item.clone();
+  queue.with_items(&app, move |items| items.push(queued))?;
+  Ok(item)
+}
+
+#[tauri::command]
+pub fn queue_list(app: AppHandle, queue: State<PromptQueue>) -> Result<Vec<QueuedPrompt>, OpenWorkError> {
+  queue.items(&app)
+}
+
+#[tauri::command]
+pub fn queue_remove(
+  app: AppHandle,
+  queue: State<PromptQueue>,
+  id: String,
+) -> Result<Vec<QueuedPrompt>, OpenWorkError> {
+  queue.with_items(&app, |items| {
+    items.retain(|i| i.id != id.trim());
+    items.clone()
+  })
+}
+
+#[tauri::command]
+pub fn queue_clear(app: AppHandle, queue: State<PromptQueue>) -> Result<Vec<QueuedPrompt>, OpenWorkError> {
+  queue.with_items(&app, |items| {
+    items.clear();
+    items.clone()
+  })
+}
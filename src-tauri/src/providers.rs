@@ -0,0 +1,510 @@
+//! Checking provider API keys before they're saved.
+//!
+//! `provider_validate` makes the cheapest authenticated request each provider
+//! offers, usually listing models, and reports whether the key was accepted.
+//! Where the answer carries more (rate limit headers, OpenRouter's credit,
+//! DeepSeek's balance) it's passed on as hints. Without a key, the one opencode
+//! would use is checked: from its `auth.json`, else the environment.
+
+use std::{env, fs, time::Duration};
+
+use reqwest::{blocking::Response, header::HeaderMap};
+use serde::Serialize;
+use serde_json::Value;
+use tauri::AppHandle;
+
+use crate::{
+  download,
+  error::{ErrorCode, OpenWorkError},
+  network, opencode_data_dir, redact,
+};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+enum Auth {
+  Bearer,
+  Header(&'static str),
+}
+
+struct ProviderSpec {
+  /// opencode's provider id.
+  id: &'static str,
+  env: &'static [&'static str],
+  /// What `provider_validate` requests.
+  url: &'static str,
+  /// Lists the models the key can use; the same as `url` for most.
+  models_url: &'static str,
+  auth: Auth,
+  headers: &'static [(&'static str, &'static str)],
+}
+
+const PROVIDERS: &[ProviderSpec] = &[
+  ProviderSpec {
+    id: "anthropic",
+    env: &["ANTHROPIC_API_KEY"],
+    url: "https://api.anthropic.com/v1/models?limit=1000",
+    models_url: "https://api.anthropic.com/v1/models?limit=1000",
+    auth: Auth::Header("x-api-key"),
+    headers: &[("anthropic-version", "2023-06-01")],
+  },
+  ProviderSpec {
+    id: "openai",
+    env: &["OPENAI_API_KEY"],
+    url: "https://api.openai.com/v1/models",
+    models_url: "https://api.openai.com/v1/models",
+    auth: Auth::Bearer,
+    headers: &[],
+  },
+  ProviderSpec {
+    id: "google",
+    env: &["GOOGLE_GENERATIVE_AI_API_KEY", "GEMINI_API_KEY"],
+    url: "https://generativelanguage.googleapis.com/v1beta/models?pageSize=1000",
+    models_url: "https://generativelanguage.googleapis.com/v1beta/models?pageSize=1000",
+    auth: Auth::Header("x-goog-api-key"),
+    headers: &[],
+  },
+  ProviderSpec {
+    id: "openrouter",
+    env: &["OPENROUTER_API_KEY"],
+    url: "https://openrouter.ai/api/v1/key",
+    models_url: "https://openrouter.ai/api/v1/models",
+    auth: Auth::Bearer,
+    headers: &[],
+  },
+  ProviderSpec {
+    id: "groq",
+    env: &["GROQ_API_KEY"],
+    url: "https://api.groq.com/openai/v1/models",
+    models_url: "https://api.groq.com/openai/v1/models",
+    auth: Auth::Bearer,
+    headers: &[],
+  },
+  ProviderSpec {
+    id: "xai",
+    env: &["XAI_API_KEY"],
+    url: "https://api.x.ai/v1/models",
+    models_url: "https://api.x.ai/v1/models",
+    auth: Auth::Bearer,
+    headers: &[],
+  },
+  ProviderSpec {
+    id: "deepseek",
+    env: &["DEEPSEEK_API_KEY"],
+    url: "https://api.deepseek.com/user/balance",
+    models_url: "https://api.deepseek.com/models",
+    auth: Auth::Bearer,
+    headers: &[],
+  },
+  ProviderSpec {
+    id: "mistral",
+    env: &["MISTRAL_API_KEY"],
+    url: "https://api.mistral.ai/v1/models",
+    models_url: "https://api.mistral.ai/v1/models",
+    auth: Auth::Bearer,
+    headers: &[],
+  },
+];
+
+/// `(what, remaining header, limit header)` for the rate limits providers
+/// report on every response.
+const RATE_LIMIT_HEADERS: &[(&str, &str, &str)] = &[
+  (
+    "requests",
+    "anthropic-ratelimit-requests-remaining",
+    "anthropic-ratelimit-requests-limit",
+  ),
+  (
+    "tokens",
+    "anthropic-ratelimit-tokens-remaining",
+    "anthropic-ratelimit-tokens-limit",
+  ),
+  (
+    "requests",
+    "x-ratelimit-remaining-requests",
+    "x-ratelimit-limit-requests",
+  ),
+  (
+    "tokens",
+    "x-ratelimit-remaining-tokens",
+    "x-ratelimit-limit-tokens",
+  ),
+];
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum KeySource {
+  Argument,
+  /// opencode's `auth.json`.
+  Auth,
+  Env,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderValidation {
+  pub provider: String,
+  /// The provider accepted the key.
+  pub valid: bool,
+  pub key_source: KeySource,
+  pub status: u16,
+  pub message: String,
+  /// Models the key can use, when the check lists them.
+  pub models: Vec<String>,
+  /// Quota, credit and rate limit details the provider reported.
+  pub hints: Vec<String>,
+}
+
+fn spec(provider: &str) -> Result<&'static ProviderSpec, OpenWorkError> {
+  PROVIDERS.iter().find(|spec| spec.id == provider).ok_or_else(|| {
+    let ids: Vec<&str> = PROVIDERS.iter().map(|spec| spec.id).collect();
+    OpenWorkError::invalid_argument(format!(
+      "Can't validate keys for {provider}; supported: {}",
+      ids.join(", ")
+    ))
+  })
+}
+
+/// The API key `auth.json` holds for `provider`; OAuth logins have none.
+fn auth_key(auth: &Value, provider: &str) -> Option<String> {
+  let entry = auth.get(provider)?;
+  if entry.get("type").and_then(Value::as_str) != Some("api") {
+    return None;
+  }
+  entry
+    .get("key")
+    .and_then(Value::as_str)
+    .filter(|key| !key.trim().is_empty())
+    .map(str::to_string)
+}
+
+fn find_key(spec: &ProviderSpec) -> Option<(String, KeySource)> {
+  let auth = opencode_data_dir()
+    .and_then(|dir| fs::read_to_string(dir.join("auth.json")).ok())
+    .and_then(|text| serde_json::from_str::<Value>(&text).ok());
+  if let Some(key) = auth.and_then(|auth| auth_key(&auth, spec.id)) {
+    return Some((key, KeySource::Auth));
+  }
+  spec.env.iter().find_map(|name| {
+    env::var(name)
+      .ok()
+      .filter(|key| !key.trim().is_empty())
+      .map(|key| (key, KeySource::Env))
+  })
+}
+
+/// Provider ids keys can be checked and models listed for.
+pub fn supported() -> impl Iterator<Item = &'static str> {
+  PROVIDERS.iter().map(|spec| spec.id)
+}
+
+/// Model ids from an OpenAI-style `data` list or Google's `models` list.
+fn model_ids(body: &Value) -> Vec<String> {
+  let mut ids: Vec<String> = match (body.get("data"), body.get("models")) {
+    (Some(Value::Array(models)), _) => models
+      .iter()
+      .filter_map(|model| model.get("id").and_then(Value::as_str))
+      .map(str::to_string)
+      .collect(),
+    (_, Some(Value::Array(models))) => models
+      .iter()
+      .filter_map(|model| model.get("name").and_then(Value::as_str))
+      .map(|name| name.trim_start_matches("models/").to_string())
+      .collect(),
+    _ => Vec::new(),
+  };
+  ids.sort();
+  ids.dedup();
+  ids
+}
+
+fn error_message(body: &Value) -> Option<String> {
+  let error = body.get("error")?;
+  error
+    .get("message")
+    .and_then(Value::as_str)
+    .or_else(|| error.as_str())
+    .map(str::to_string)
+}
+
+fn rate_limit_hints(headers: &[(String, String)]) -> Vec<String> {
+  let header = |name: &str| {
+    headers
+      .iter()
+      .find(|(key, _)| key.eq_ignore_ascii_case(name))
+      .map(|(_, value)| value.as_str())
+  };
+  RATE_LIMIT_HEADERS
+    .iter()
+    .filter_map(|(what, remaining, limit)| {
+      Some(format!(
+        "{} of {} {what} left in the current window",
+        header(remaining)?,
+        header(limit)?
+      ))
+    })
+    .collect()
+}
+
+fn money(value: &Value) -> Option<String> {
+  match value {
+    Value::Number(number) => number.as_f64().map(|amount| format!("{amount:.2}")),
+    Value::String(text) => Some(text.clone()),
+    _ => None,
+  }
+}
+
+/// Credit and balance details from OpenRouter's and DeepSeek's key checks.
+fn credit_hints(body: &Value) -> Vec<String> {
+  let mut hints = Vec::new();
+  if let Some(key) = body.get("data").filter(|data| data.get("usage").is_some()) {
+    if let Some(usage) = key.get("usage").and_then(money) {
+      hints.push(format!("${usage} used"));
+    }
+    match (
+      key.get("limit").and_then(money),
+      key.get("limit_remaining").and_then(money),
+    ) {
+      (Some(limit), Some(remaining)) => hints.push(format!("${remaining} of ${limit} credit left")),
+      (Some(limit), None) => hints.push(format!("${limit} credit limit")),
+      _ => hints.push("No credit limit on this key".to_string()),
+    }
+    if key.get("is_free_tier").and_then(Value::as_bool) == Some(true) {
+      hints.push("Free tier".to_string());
+    }
+  }
+  for balance in body
+    .get("balance_infos")
+    .and_then(Value::as_array)
+    .into_iter()
+    .flatten()
+  {
+    if let (Some(total), Some(currency)) = (
+      balance.get("total_balance").and_then(money),
+      balance.get("currency").and_then(Value::as_str),
+    ) {
+      hints.push(format!("Balance: {total} {currency}"));
+    }
+  }
+  if body.get("is_available").and_then(Value::as_bool) == Some(false) {
+    hints.push("Balance is too low to make requests".to_string());
+  }
+  hints
+}
+
+/// Reads the provider's answer. Rate limited (429) still means the key was
+/// accepted; Google answers an unknown key with 400.
+fn interpret(
+  provider: &str,
+  key_source: KeySource,
+  status: u16,
+  headers: &[(String, String)],
+  body: &Value,
+) -> ProviderValidation {
+  let detail = error_message(body);
+  let google_invalid = status == 400
+    && detail
+      .as_deref()
+      .is_some_and(|message| message.contains("API key not valid"));
+  let (valid, message) = match status {
+    200..=299 => (true, "Key accepted".to_string()),
+    429 => (
+      true,
+      format!(
+        "Key accepted, but rate limited or out of quota: {}",
+        detail.as_deref().unwrap_or("try again later")
+      ),
+    ),
+    401 | 403 => (
+      false,
+      detail.unwrap_or_else(|| "The provider rejected the key".to_string()),
+    ),
+    400 if google_invalid => (false, detail.unwrap_or_default()),
+    _ => (
+      false,
+      format!(
+        "Unexpected answer ({status}): {}",
+        detail.as_deref().unwrap_or("no details")
+      ),
+    ),
+  };
+  let mut hints = rate_limit_hints(headers);
+  if valid {
+    hints.extend(credit_hints(body));
+  }
+  ProviderValidation {
+    provider: provider.to_string(),
+    valid,
+    key_source,
+    status,
+    message,
+    models: if valid { model_ids(body) } else { Vec::new() },
+    hints,
+  }
+}
+
+/// `url` with `key` attached the way `spec`'s provider expects.
+fn get(spec: &ProviderSpec, url: &str, key: &str) -> Result<Response, OpenWorkError> {
+  redact::register_secret(key);
+  let client = download::client().map_err(|e| OpenWorkError::new(ErrorCode::Network, e))?;
+  let mut request = client.get(url).timeout(REQUEST_TIMEOUT);
+  request = match spec.auth {
+    Auth::Bearer => request.bearer_auth(key),
+    Auth::Header(name) => request.header(name, key),
+  };
+  for (name, value) in spec.headers {
+    request = request.header(*name, *value);
+  }
+  request.send().map_err(|e| {
+    OpenWorkError::new(
+      ErrorCode::Network,
+      redact::redact(&format!("Couldn't reach {}: {e}", spec.id)),
+    )
+    .retryable()
+  })
+}
+
+/// The models `provider` lists for the key opencode would use, or `None`
+/// when there's no key for it.
+pub fn list_models(provider: &str) -> Result<Option<Vec<String>>, OpenWorkError> {
+  let spec = spec(provider)?;
+  let Some((key, _)) = find_key(spec) else {
+    return Ok(None);
+  };
+  let response = get(spec, spec.models_url, &key)?;
+  let status = response.status();
+  if !status.is_success() {
+    return Err(OpenWorkError::new(
+      ErrorCode::Network,
+      format!("{provider} answered {status} when listing models"),
+    ));
+  }
+  let body: Value = response.json().unwrap_or(Value::Null);
+  Ok(Some(model_ids(&body)))
+}
+
+fn header_pairs(headers: &HeaderMap) -> Vec<(String, String)> {
+  headers
+    .iter()
+    .filter_map(|(name, value)| Some((name.as_str().to_string(), value.to_str().ok()?.to_string())))
+    .collect()
+}
+
+/// Checks `key`, or the key opencode would use for `provider`, with one
+/// request to the provider. Network failures are errors; a rejected key is a
+/// result with `valid: false`.
+#[tauri::command]
+pub fn provider_validate(
+  app: AppHandle,
+  provider: String,
+  key: Option<String>,
+) -> Result<ProviderValidation, OpenWorkError> {
+  let provider = provider.trim().to_lowercase();
+  let spec = spec(&provider)?;
+  network::require_online(&app, "Checking an API key")?;
+  let (key, key_source) = match key
+    .map(|key| key.trim().to_string())
+    .filter(|key| !key.is_empty())
+  {
+    Some(key) => (key, KeySource::Argument),
+    None => find_key(spec).ok_or_else(|| {
+      OpenWorkError::new(
+        ErrorCode::NotFound,
+        format!(
+          "No API key for {provider}: pass one, set {}, or run `opencode auth login`",
+          spec.env.join(" or ")
+        ),
+      )
+    })?,
+  };
+  let response = get(spec, spec.url, &key)?;
+  let status = response.status().as_u16();
+  let headers = header_pairs(response.headers());
+  let body: Value = response.json().unwrap_or(Value::Null);
+  let result = interpret(&provider, key_source, status, &headers, &body);
+  tracing::info!(provider = %provider, status, valid = result.valid, "validated provider key");
+  Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+
+  fn headers(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs
+      .iter()
+      .map(|(name, value)| (name.to_string(), value.to_string()))
+      .collect()
+  }
+
+  #[test]
+  fn reads_keys_from_auth_json() {
+    let auth = json!({
+      "anthropic": { "type": "oauth", "refresh": "r" },
+      "openai": { "type": "api", "key": "sk-test" },
+    });
+    assert_eq!(auth_key(&auth, "openai").as_deref(), Some("sk-test"));
+    assert_eq!(auth_key(&auth, "anthropic"), None);
+    assert_eq!(auth_key(&auth, "groq"), None);
+  }
+
+  #[test]
+  fn accepts_keys_with_models_and_limits() {
+    let body = json!({ "data": [{ "id": "claude-sonnet-4" }, { "id": "claude-haiku-4" }] });
+    let result = interpret(
+      "anthropic",
+      KeySource::Argument,
+      200,
+      &headers(&[
+        ("Anthropic-Ratelimit-Requests-Remaining", "49"),
+        ("anthropic-ratelimit-requests-limit", "50"),
+      ]),
+      &body,
+    );
+    assert!(result.valid);
+    assert_eq!(result.models, ["claude-haiku-4", "claude-sonnet-4"]);
+    assert_eq!(result.hints, ["49 of 50 requests left in the current window"]);
+
+    let google = json!({ "models": [{ "name": "models/gemini-2.5-pro" }] });
+    assert_eq!(model_ids(&google), ["gemini-2.5-pro"]);
+  }
+
+  #[test]
+  fn rejects_bad_keys() {
+    let body = json!({ "error": { "type": "authentication_error", "message": "invalid x-api-key" } });
+    let result = interpret("anthropic", KeySource::Env, 401, &[], &body);
+    assert!(!result.valid);
+    assert_eq!(result.message, "invalid x-api-key");
+
+    let google =
+      json!({ "error": { "code": 400, "message": "API key not valid. Please pass a valid API key." } });
+    assert!(!interpret("google", KeySource::Auth, 400, &[], &google).valid);
+
+    let quota =
+      json!({ "error": { "message": "You exceeded your current quota", "code": "insufficient_quota" } });
+    let result = interpret("openai", KeySource::Argument, 429, &[], &quota);
+    assert!(result.valid && result.message.contains("exceeded your current quota"));
+  }
+
+  #[test]
+  fn reports_credit() {
+    let openrouter =
+      json!({ "data": { "usage": 1.5, "limit": 10, "limit_remaining": 8.5, "is_free_tier": false } });
+    assert_eq!(
+      credit_hints(&openrouter),
+      ["$1.50 used", "$8.50 of $10.00 credit left"]
+    );
+    let unlimited = json!({ "data": { "usage": 0, "limit": null, "is_free_tier": true } });
+    assert_eq!(
+      credit_hints(&unlimited),
+      ["$0.00 used", "No credit limit on this key", "Free tier"]
+    );
+    let deepseek =
+      json!({ "is_available": false, "balance_infos": [{ "currency": "USD", "total_balance": "0.00" }] });
+    assert_eq!(
+      credit_hints(&deepseek),
+      ["Balance: 0.00 USD", "Balance is too low to make requests"]
+    );
+  }
+}
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::{
+  error::OpenWorkError,
+  menu,
+  store::{read_state, write_state},
+};
+
+pub const RECENT_FILE: &str = "recent-projects.json";
+pub const RECENT_PROJECTS_EVENT: &str = "app://recent-projects";
+
+const MAX_RECENT: usize = 10;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RecentProjects {
+  /// Most recently opened first.
+  pub projects: Vec<String>,
+}
+
+pub fn list(app: &AppHandle) -> Vec<String> {
+  read_state::<RecentProjects>(app, RECENT_FILE)
+    .unwrap_or_default()
+    .projects
+}
+
+fn save(app: &AppHandle, projects: Vec<String>) -> Result<Vec<String>, String> {
+  write_state(app, RECENT_FILE, &RecentProjects { projects: projects.clone() })?;
+  menu::rebuild(app);
+  let _ = app.emit(RECENT_PROJECTS_EVENT, &projects);
+  Ok(projects)
+}
+
+/// Moves `project_dir` to the front of the list.
+pub fn record(app: &AppHandle, project_dir: &str) {
+  let mut projects = list(app);
+  if projects.first().map(String::as_str) == Some(project_dir) {
+    return;
+  }
+  projects.retain(|dir| dir != project_dir);
+  projects.insert(0, project_dir.to_string());
+  projects.truncate(MAX_RECENT);
+  if let Err(e) = save(app, projects) {
+    tracing::warn!(error = %e, "failed to record recent project");
+  }
+}
+
+pub fn clear(app: &AppHandle) -> Result<Vec<String>, String> {
+  save(app, Vec::new())
+}
+
+#[tauri::command]
+pub fn recent_projects_list(app: AppHandle) -> Vec<String> {
+  list(&app)
+}
+
+#[tauri::command]
+pub fn recent_projects_clear(app: AppHandle) -> Result<Vec<String>, OpenWorkError> {
+  Ok(clear(&app)?)
+}
@@ -0,0 +1,386 @@
+use std::{
+  collections::{HashMap, VecDeque},
+  io::{BufRead, BufReader},
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex, MutexGuard,
+  },
+  thread,
+  time::Duration,
+};
+
+use reqwest::Method;
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager, State, WebviewWindow};
+
+use crate::{engine_client::EngineClient, EngineManager};
+
+pub const ENGINE_EVENT: &str = "engine://event";
+pub const RELAY_STATUS_EVENT: &str = "engine://relay-status";
+
+/// Events kept in memory so a reloaded webview can catch up.
+const BACKFILL_CAPACITY: usize = 500;
+const IDLE_POLL: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(10);
+
+type Subscriber = Box<dyn Fn(&AppHandle, &RelayedEvent) + Send + Sync>;
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RelayedEvent {
+  pub seq: u64,
+  /// Label of the window whose engine sent the event.
+  pub window: String,
+  pub base_url: String,
+  pub project_dir: Option<String>,
+  pub event: Value,
+}
+
+/// The relay as seen by one window.
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RelayStatus {
+  pub running: bool,
+  pub connected: bool,
+  pub base_url: Option<String>,
+  /// Set on the first connect after a drop; the UI should refetch state.
+  pub reconnected: bool,
+  pub last_seq: u64,
+  pub last_error: Option<String>,
+}
+
+/// The stream of one window's engine.
+#[derive(Default)]
+struct Stream {
+  /// Identifies the thread reading it; a restart replaces it.
+  id: u64,
+  connected: bool,
+  base_url: Option<String>,
+  reconnected: bool,
+  last_error: Option<String>,
+}
+
+#[derive(Default)]
+struct RelayState {
+  running: bool,
+  last_seq: u64,
+  /// Streams by window label.
+  streams: HashMap<String, Stream>,
+  buffer: VecDeque<RelayedEvent>,
+}
+
+impl RelayState {
+  fn status(&self, window: &str) -> RelayStatus {
+    let stream = self.streams.get(window);
+    RelayStatus {
+      running: self.running,
+      connected: stream.is_some_and(|stream| stream.connected),
+      base_url: stream.and_then(|stream| stream.base_url.clone()),
+      reconnected: stream.is_some_and(|stream| stream.reconnected),
+      last_seq: self.last_seq,
+      last_error: stream.and_then(|stream| stream.last_error.clone()),
+    }
+  }
+}
+
+/// Relays the engines' `/event` server-sent-event streams as Tauri events,
+/// one stream per window with an engine, each sent to its own window.
+///
+/// The relay follows whichever engine `EngineManager` currently owns for a
+/// window, so it survives engine restarts and project switches.
+#[derive(Default)]
+pub struct EventRelay {
+  inner: Mutex<RelayState>,
+  subscribers: Mutex<Vec<Subscriber>>,
+  generation: AtomicU64,
+  next_seq: AtomicU64,
+  next_stream: AtomicU64,
+}
+
+impl EventRelay {
+  /// Registers a backend consumer that sees every relayed event.
+  pub fn subscribe(&self, subscriber: impl Fn(&AppHandle, &RelayedEvent) + Send + Sync + 'static) {
+    self
+      .subscribers
+      .lock()
+      .expect("relay subscribers mutex poisoned")
+      .push(Box::new(subscriber));
+  }
+
+  fn lock(&self) -> MutexGuard<'_, RelayState> {
+    self.inner.lock().expect("relay mutex poisoned")
+  }
+
+  /// Updates the stream of `window` while `id` still reads it.
+  fn update_stream(&self, app: &AppHandle, window: &str, id: u64, update: impl FnOnce(&mut Stream)) {
+    let status = {
+      let mut state = self.lock();
+      let Some(stream) = state.streams.get_mut(window).filter(|stream| stream.id == id) else {
+        return;
+      };
+      update(stream);
+      state.status(window)
+    };
+    let _ = app.emit_to(window, RELAY_STATUS_EVENT, status);
+  }
+
+  fn publish(
+    &self,
+    app: &AppHandle,
+    window: &str,
+    base_url: &str,
+    project_dir: Option<String>,
+    mut event: Value,
+  ) {
+    // Tool output can echo environment files or shell history.
+    crate::redact::redact_json(&mut event);
+
+    let relayed = RelayedEvent {
+      seq: self.next_seq.fetch_add(1, Ordering::SeqCst) + 1,
+      window: window.to_string(),
+      base_url: base_url.to_string(),
+      project_dir,
+      event,
+    };
+
+    {
+      let mut state = self.lock();
+      state.last_seq = relayed.seq;
+      state.buffer.push_back(relayed.clone());
+      while state.buffer.len() > BACKFILL_CAPACITY {
+        state.buffer.pop_front();
+      }
+    }
+
+    for subscriber in self.subscribers.lock().expect("relay subscribers mutex poisoned").iter() {
+      subscriber(app, &relayed);
+    }
+
+    let _ = app.emit_to(window, ENGINE_EVENT, relayed);
+  }
+
+  fn is_current(&self, generation: u64) -> bool {
+    self.generation.load(Ordering::SeqCst) == generation
+  }
+
+  /// Whether thread `id` still reads the stream of `window`.
+  fn stream_is_current(&self, generation: u64, window: &str, id: u64) -> bool {
+    self.is_current(generation) && self.lock().streams.get(window).is_some_and(|stream| stream.id == id)
+  }
+
+  /// Starts a thread reading `window`'s stream, replacing any earlier one.
+  /// The replaced thread exits as soon as its connection yields, without
+  /// publishing anything more.
+  fn spawn_stream(&self, app: &AppHandle, generation: u64, window: &str) {
+    let id = self.next_stream.fetch_add(1, Ordering::SeqCst) + 1;
+    let reconnected = {
+      let mut state = self.lock();
+      let stream = state.streams.entry(window.to_string()).or_default();
+      let reconnected = stream.connected || stream.reconnected;
+      *stream = Stream { id, ..Stream::default() };
+      reconnected
+    };
+    let app = app.clone();
+    let window = window.to_string();
+    thread::spawn(move || run_stream(app, generation, window, id, reconnected));
+  }
+
+  /// Reconnects `window`'s stream at once, e.g. after failover moved the
+  /// window to another engine.
+  pub fn restart(&self, app: &AppHandle, window: &str) {
+    let generation = self.generation.load(Ordering::SeqCst);
+    let known = {
+      let state = self.lock();
+      state.running && state.streams.contains_key(window)
+    };
+    if known {
+      self.spawn_stream(app, generation, window);
+    }
+  }
+
+  pub fn start(&self, app: &AppHandle) {
+    let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+    {
+      let mut state = self.lock();
+      state.running = true;
+      state.streams.clear();
+    }
+
+    let app = app.clone();
+    thread::spawn(move || run_relay(app, generation));
+  }
+
+  pub fn stop(&self) {
+    // Bumping the generation makes the running threads exit on their next wakeup.
+    self.generation.fetch_add(1, Ordering::SeqCst);
+    let mut state = self.lock();
+    state.running = false;
+    state.streams.clear();
+  }
+
+  pub fn status(&self, window: &str) -> RelayStatus {
+    self.lock().status(window)
+  }
+}
+
+fn current_project_dir(app: &AppHandle, window: &str) -> Option<String> {
+  let manager = app.state::<EngineManager>();
+  manager.with_window(window, |state| state.project_dir.clone())
+}
+
+/// Reads one SSE stream until it ends. Returns `Ok` on a clean close.
+fn stream_events(
+  app: &AppHandle,
+  relay: &EventRelay,
+  client: &EngineClient,
+  (generation, window, id): (u64, &str, u64),
+  reconnected: bool,
+) -> Result<(), String> {
+  let response = client
+    .request(Method::GET, "event")
+    .header("Accept", "text/event-stream")
+    .send()
+    .map_err(|e| format!("Failed to connect to event stream: {e}"))?;
+
+  if !response.status().is_success() {
+    return Err(format!("Event stream returned {}", response.status()));
+  }
+
+  let project_dir = current_project_dir(app, window);
+  let base_url = client.base_url().to_string();
+
+  relay.update_stream(app, window, id, |stream| {
+    stream.reconnected = reconnected;
+    stream.connected = true;
+    stream.base_url = Some(base_url.clone());
+    stream.last_error = None;
+  });
+
+  let mut data = String::new();
+  for line in BufReader::new(response).lines() {
+    if !relay.stream_is_current(generation, window, id) {
+      return Ok(());
+    }
+
+    let line = line.map_err(|e| format!("Event stream interrupted: {e}"))?;
+    if line.is_empty() {
+      if !data.is_empty() {
+        if let Ok(event) = serde_json::from_str::<Value>(&data) {
+          relay.publish(app, window, &base_url, project_dir.clone(), event);
+        }
+        data.clear();
+      }
+      continue;
+    }
+
+    if let Some(rest) = line.strip_prefix("data:") {
+      if !data.is_empty() {
+        data.push('\n');
+      }
+      data.push_str(rest.strip_prefix(' ').unwrap_or(rest));
+    }
+    // `event:`, `id:`, `retry:` and `:` comment lines carry nothing we need.
+  }
+
+  Ok(())
+}
+
+/// Keeps one stream per window with an engine, and drops the streams of
+/// windows whose engine is gone.
+fn run_relay(app: AppHandle, generation: u64) {
+  let relay = app.state::<EventRelay>();
+  while relay.is_current(generation) {
+    let windows = app.state::<EngineManager>().engine_windows();
+    let missing: Vec<String> = {
+      let mut state = relay.lock();
+      state.streams.retain(|window, _| windows.contains(window));
+      windows
+        .iter()
+        .filter(|window| !state.streams.contains_key(*window))
+        .cloned()
+        .collect()
+    };
+    for window in missing {
+      relay.spawn_stream(&app, generation, &window);
+    }
+    thread::sleep(IDLE_POLL);
+  }
+}
+
+/// Reads the stream of `window` while thread `id` is current, reconnecting
+/// when it drops.
+fn run_stream(app: AppHandle, generation: u64, window: String, id: u64, mut reconnected: bool) {
+  let relay = app.state::<EventRelay>();
+  let mut delay = IDLE_POLL;
+
+  while relay.stream_is_current(generation, &window, id) {
+    let client = {
+      let manager = app.state::<EngineManager>();
+      EngineClient::streaming_for_window(&manager, &window)
+    };
+
+    let Ok(client) = client else {
+      // No engine yet; wait for one to start.
+      thread::sleep(IDLE_POLL);
+      continue;
+    };
+
+    let result = stream_events(&app, &relay, &client, (generation, &window, id), reconnected);
+    if !relay.stream_is_current(generation, &window, id) {
+      break;
+    }
+
+    let error = result.err();
+    let connected_before = relay.lock().streams.get(&window).is_some_and(|stream| stream.connected);
+    reconnected = reconnected || connected_before;
+    relay.update_stream(&app, &window, id, |stream| {
+      stream.connected = false;
+      stream.reconnected = false;
+      stream.last_error = error.clone();
+    });
+
+    // Back off while the engine refuses connections, reset after a good session.
+    delay = if connected_before {
+      IDLE_POLL
+    } else {
+      (delay * 2).min(MAX_RECONNECT_DELAY)
+    };
+    thread::sleep(delay);
+  }
+}
+
+#[tauri::command]
+pub fn event_relay_start(app: AppHandle, window: WebviewWindow, relay: State<EventRelay>) -> RelayStatus {
+  relay.start(&app);
+  relay.status(window.label())
+}
+
+#[tauri::command]
+pub fn event_relay_stop(window: WebviewWindow, relay: State<EventRelay>) -> RelayStatus {
+  relay.stop();
+  relay.status(window.label())
+}
+
+/// The relay as seen by the calling window.
+#[tauri::command]
+pub fn event_relay_status(window: WebviewWindow, relay: State<EventRelay>) -> RelayStatus {
+  relay.status(window.label())
+}
+
+/// Returns the calling window's buffered events with `seq > after_seq`,
+/// oldest first.
+#[tauri::command]
+pub fn event_relay_backfill(
+  window: WebviewWindow,
+  relay: State<EventRelay>,
+  after_seq: u64,
+) -> Vec<RelayedEvent> {
+  let state = relay.lock();
+  state
+    .buffer
+    .iter()
+    .filter(|event| event.seq > after_seq && event.window == window.label())
+    .cloned()
+    .collect()
+}
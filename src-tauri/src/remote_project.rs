@@ -0,0 +1,957 @@
+//! Remote projects synced over SFTP.
+//!
+//! For a project on a server that doesn't have opencode, the remote directory
+//! is mirrored into a local folder. The engine runs against the mirror like
+//! any other project, and edits are pushed back. Transfers go through the
+//! system `sftp` in batch mode, so hosts, keys and agents come from the user's
+//! SSH setup; password prompts aren't possible.
+//!
+//! Each sync records the hash of every file as both sides agreed on it, so a
+//! file changed on both sides since then is a conflict: the local copy is left
+//! alone, and the remote one is kept aside until `remote_project_resolve`.
+//! A pull downloads the whole directory; a push fetches only the files that
+//! changed locally, to check them, before uploading.
+
+use std::{
+  collections::{BTreeMap, BTreeSet, HashSet},
+  fs::{self, File},
+  io,
+  path::{Path, PathBuf},
+  process::Stdio,
+  sync::Mutex,
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, State};
+
+use crate::{
+  consent::{self, ConsentManager},
+  error::{ErrorCode, OpenWorkError},
+  exec, paths, remove_path, run_capture_optional,
+  store::{app_state_path, read_state, write_state},
+  ExecResult,
+};
+
+pub const REMOTE_PROJECTS_FILE: &str = "remote-projects.json";
+
+/// Per-project manifests, downloads and conflict copies, in the data dir.
+const WORK_DIR: &str = "remote-projects";
+const CONNECT_TIMEOUT_SECS: u32 = 15;
+/// Characters sftp expands as globs unless escaped.
+const GLOB_CHARS: &[char] = &['*', '?', '[', ']'];
+const ECHO_PREFIX: &str = "sftp> ";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RemoteProject {
+  pub id: String,
+  pub name: String,
+  /// `host`, `user@host` or an alias from `~/.ssh/config`.
+  pub host: String,
+  pub port: Option<u16>,
+  pub identity_file: Option<String>,
+  /// Absolute, or relative to the login directory.
+  pub remote_dir: String,
+  /// The mirror, opened as the project.
+  pub local_dir: String,
+  pub last_sync_ms: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct RemoteProjects {
+  projects: Vec<RemoteProject>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct Manifest {
+  /// SHA-256 of each file as of the last sync that left both sides equal.
+  files: BTreeMap<String, String>,
+  conflicts: BTreeSet<String>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangeKind {
+  Added,
+  Modified,
+  Deleted,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalChange {
+  pub path: String,
+  pub kind: ChangeKind,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncConflict {
+  pub path: String,
+  /// The remote version, kept aside; `None` when it was deleted there.
+  pub remote_copy: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncResult {
+  /// Files written on the receiving side.
+  pub updated: Vec<String>,
+  /// Files deleted on the receiving side.
+  pub deleted: Vec<String>,
+  pub conflicts: Vec<SyncConflict>,
+  /// Local changes a pull left for the next push.
+  pub pending: Vec<LocalChange>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteStatus {
+  pub changes: Vec<LocalChange>,
+  pub conflicts: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictSide {
+  Local,
+  Remote,
+}
+
+/// Projects with a sync in flight; one at a time per project.
+#[derive(Default)]
+pub struct RemoteSync {
+  busy: Mutex<HashSet<String>>,
+}
+
+struct SyncGuard<'a> {
+  sync: &'a RemoteSync,
+  id: String,
+}
+
+impl Drop for SyncGuard<'_> {
+  fn drop(&mut self) {
+    self
+      .sync
+      .busy
+      .lock()
+      .expect("remote sync mutex poisoned")
+      .remove(&self.id);
+  }
+}
+
+impl RemoteSync {
+  fn start(&self, project: &RemoteProject) -> Result<SyncGuard<'_>, OpenWorkError> {
+    if !self
+      .busy
+      .lock()
+      .expect("remote sync mutex poisoned")
+      .insert(project.id.clone())
+    {
+      return Err(
+        OpenWorkError::new(
+          ErrorCode::AlreadyExists,
+          format!("{} is already syncing", project.name),
+        )
+        .retryable(),
+      );
+    }
+    Ok(SyncGuard {
+      sync: self,
+      id: project.id.clone(),
+    })
+  }
+}
+
+/// How one file stands between the last sync (`base`) and both sides now.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+  InSync,
+  TakeRemote,
+  LocalAhead,
+  Conflict,
+}
+
+fn step(base: Option<&String>, local: Option<&String>, remote: Option<&String>) -> Step {
+  if local == remote {
+    Step::InSync
+  } else if local == base {
+    Step::TakeRemote
+  } else if remote == base {
+    Step::LocalAhead
+  } else {
+    Step::Conflict
+  }
+}
+
+/// Every path known to any side, with its `Step`, in path order.
+fn plan(
+  base: &BTreeMap<String, String>,
+  local: &BTreeMap<String, String>,
+  remote: &BTreeMap<String, String>,
+) -> Vec<(String, Step)> {
+  let paths: BTreeSet<&String> = base.keys().chain(local.keys()).chain(remote.keys()).collect();
+  paths
+    .into_iter()
+    .map(|path| {
+      let step = step(base.get(path), local.get(path), remote.get(path));
+      (path.clone(), step)
+    })
+    .collect()
+}
+
+fn change(path: &str, base: Option<&String>, local: Option<&String>) -> Option<LocalChange> {
+  let kind = match (base, local) {
+    (None, Some(_)) => ChangeKind::Added,
+    (Some(_), None) => ChangeKind::Deleted,
+    (Some(before), Some(now)) if before != now => ChangeKind::Modified,
+    _ => return None,
+  };
+  Some(LocalChange {
+    path: path.to_string(),
+    kind,
+  })
+}
+
+fn changes(base: &BTreeMap<String, String>, local: &BTreeMap<String, String>) -> Vec<LocalChange> {
+  let paths: BTreeSet<&String> = base.keys().chain(local.keys()).collect();
+  paths
+    .into_iter()
+    .filter_map(|path| change(path, base.get(path), local.get(path)))
+    .collect()
+}
+
+fn set_base(files: &mut BTreeMap<String, String>, path: &str, hash: Option<&String>) {
+  match hash {
+    Some(hash) => files.insert(path.to_string(), hash.clone()),
+    None => files.remove(path),
+  };
+}
+
+/// `path` as one sftp batch argument: double-quoted, with quotes, backslashes
+/// and glob characters escaped.
+fn quote(path: &str) -> Result<String, OpenWorkError> {
+  if path.contains(['\n', '\r']) {
+    return Err(OpenWorkError::invalid_argument(format!(
+      "Paths with line breaks can't be synced: {path:?}"
+    )));
+  }
+  let mut quoted = String::from('"');
+  for c in path.chars() {
+    if c == '"' || c == '\\' || GLOB_CHARS.contains(&c) {
+      quoted.push('\\');
+    }
+    quoted.push(c);
+  }
+  quoted.push('"');
+  Ok(quoted)
+}
+
+/// How many batch commands sftp started, from the lines it echoes. When the
+/// batch fails, the last one started is the one that failed.
+fn commands_started(stdout: &str) -> usize {
+  stdout
+    .lines()
+    .filter(|line| line.starts_with(ECHO_PREFIX))
+    .count()
+}
+
+fn validate_host(host: &str) -> Result<String, OpenWorkError> {
+  let host = host.trim();
+  if host.is_empty() || host.starts_with('-') || host.chars().any(|c| c.is_whitespace() || c.is_control()) {
+    return Err(OpenWorkError::invalid_argument(format!(
+      "Invalid SSH host: {host:?}"
+    )));
+  }
+  Ok(host.to_string())
+}
+
+fn validate_remote_dir(remote_dir: &str) -> Result<String, OpenWorkError> {
+  let trimmed = remote_dir.trim();
+  let remote_dir = if trimmed == "/" {
+    trimmed
+  } else {
+    trimmed.trim_end_matches('/')
+  };
+  if remote_dir.is_empty() || remote_dir.contains(['\n', '\r']) {
+    return Err(OpenWorkError::invalid_argument(format!(
+      "Invalid remote directory: {remote_dir:?}"
+    )));
+  }
+  Ok(remote_dir.to_string())
+}
+
+fn remote_path(project: &RemoteProject, relative: &str) -> String {
+  format!("{}/{relative}", project.remote_dir.trim_end_matches('/'))
+}
+
+fn now_ms() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_millis() as u64)
+    .unwrap_or(0)
+}
+
+fn hash_file(path: &Path) -> Result<String, String> {
+  let mut file = File::open(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+  let mut hasher = Sha256::new();
+  io::copy(&mut file, &mut hasher).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+  Ok(hasher.finalize().iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// Hashes of the regular files under `root` by `/`-separated relative path.
+/// Symlinks are skipped, as sftp doesn't transfer them either.
+fn snapshot(root: &Path) -> Result<BTreeMap<String, String>, String> {
+  let mut files = BTreeMap::new();
+  if !root.exists() {
+    return Ok(files);
+  }
+  let mut dirs = vec![root.to_path_buf()];
+  while let Some(dir) = dirs.pop() {
+    let entries = fs::read_dir(&dir).map_err(|e| format!("Failed to read {}: {e}", dir.display()))?;
+    for entry in entries {
+      let entry = entry.map_err(|e| format!("Failed to read {}: {e}", dir.display()))?;
+      let path = entry.path();
+      let file_type = entry
+        .file_type()
+        .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+      if file_type.is_dir() {
+        dirs.push(path);
+      } else if file_type.is_file() {
+        let Ok(relative) = path.strip_prefix(root) else {
+          continue;
+        };
+        let relative: Vec<String> = relative
+          .components()
+          .map(|c| c.as_os_str().to_string_lossy().to_string())
+          .collect();
+        files.insert(relative.join("/"), hash_file(&path)?);
+      }
+    }
+  }
+  Ok(files)
+}
+
+fn load(app: &AppHandle) -> Vec<RemoteProject> {
+  read_state::<RemoteProjects>(app, REMOTE_PROJECTS_FILE)
+    .unwrap_or_default()
+    .projects
+}
+
+fn save(app: &AppHandle, projects: &[RemoteProject]) -> Result<(), String> {
+  write_state(
+    app,
+    REMOTE_PROJECTS_FILE,
+    &RemoteProjects {
+      projects: projects.to_vec(),
+    },
+  )
+}
+
+fn find(app: &AppHandle, id: &str) -> Result<RemoteProject, OpenWorkError> {
+  load(app)
+    .into_iter()
+    .find(|project| project.id == id)
+    .ok_or_else(|| OpenWorkError::new(ErrorCode::NotFound, format!("No remote project {id}")))
+}
+
+fn record_sync(app: &AppHandle, id: &str) {
+  let mut projects = load(app);
+  if let Some(project) = projects.iter_mut().find(|project| project.id == id) {
+    project.last_sync_ms = Some(now_ms());
+  }
+  if let Err(e) = save(app, &projects) {
+    tracing::warn!(error = %e, "failed to record remote sync");
+  }
+}
+
+fn work_dir(app: &AppHandle, id: &str) -> Result<PathBuf, String> {
+  app_state_path(app, &format!("{WORK_DIR}/{id}"))
+}
+
+fn manifest_file(id: &str) -> String {
+  format!("{WORK_DIR}/{id}/manifest.json")
+}
+
+fn fresh_dir(dir: &Path) -> Result<(), String> {
+  if dir.exists() {
+    fs::remove_dir_all(dir).map_err(|e| format!("Failed to clear {}: {e}", dir.display()))?;
+  }
+  if let Some(parent) = dir.parent() {
+    fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+  }
+  Ok(())
+}
+
+fn create_parent(path: &Path) -> Result<(), String> {
+  match path.parent() {
+    Some(parent) => {
+      fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {e}", parent.display()))
+    }
+    None => Ok(()),
+  }
+}
+
+/// Runs `commands` as one sftp batch against the project's host.
+fn sftp(project: &RemoteProject, work: &Path, commands: &[String]) -> Result<ExecResult, OpenWorkError> {
+  let batch = work.join("batch.txt");
+  create_parent(&batch)?;
+  fs::write(&batch, commands.join("\n") + "\n")
+    .map_err(|e| format!("Failed to write {}: {e}", batch.display()))?;
+  let mut command = exec::command("sftp");
+  command
+    .arg("-b")
+    .arg(&batch)
+    .args(["-o", "BatchMode=yes", "-o", "StrictHostKeyChecking=accept-new"])
+    .arg("-o")
+    .arg(format!("ConnectTimeout={CONNECT_TIMEOUT_SECS}"));
+  if let Some(port) = project.port {
+    command.arg("-P").arg(port.to_string());
+  }
+  if let Some(identity) = &project.identity_file {
+    command.arg("-i").arg(identity);
+  }
+  command
+    .arg("--")
+    .arg(&project.host)
+    .stdin(Stdio::null())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped());
+  let result = run_capture_optional(&mut command);
+  let _ = fs::remove_file(&batch);
+  result?.ok_or_else(|| {
+    OpenWorkError::new(
+      ErrorCode::ToolNotFound,
+      "sftp not found. Install OpenSSH and make sure it is on PATH.",
+    )
+  })
+}
+
+fn sftp_error(project: &RemoteProject, result: &ExecResult) -> OpenWorkError {
+  let reason = result
+    .stderr
+    .lines()
+    .rev()
+    .find(|line| !line.trim().is_empty())
+    .unwrap_or("sftp failed");
+  OpenWorkError::new(
+    ErrorCode::Network,
+    format!("Sync with {} failed: {}", project.host, reason.trim()),
+  )
+  .with_details(json!({ "status": result.status, "stderr": result.stderr }))
+  .retryable()
+}
+
+/// Moves the downloaded remote side of a conflict out of `staging`.
+fn keep_remote_copy(work: &Path, staging: &Path, path: &str, exists: bool) -> Result<SyncConflict, String> {
+  let copy = work.join("conflicts").join(path);
+  if copy.exists() {
+    fs::remove_file(&copy).map_err(|e| format!("Failed to replace {}: {e}", copy.display()))?;
+  }
+  if !exists {
+    return Ok(SyncConflict {
+      path: path.to_string(),
+      remote_copy: None,
+    });
+  }
+  create_parent(&copy)?;
+  fs::copy(staging.join(path), &copy).map_err(|e| format!("Failed to keep {}: {e}", copy.display()))?;
+  Ok(SyncConflict {
+    path: path.to_string(),
+    remote_copy: Some(copy.to_string_lossy().to_string()),
+  })
+}
+
+fn pull(app: &AppHandle, project: &RemoteProject) -> Result<SyncResult, OpenWorkError> {
+  let work = work_dir(app, &project.id)?;
+  let staging = work.join("staging");
+  fresh_dir(&staging)?;
+  let command = format!(
+    "get -r {} {}",
+    quote(&project.remote_dir)?,
+    quote(&staging.to_string_lossy())?
+  );
+  let result = sftp(project, &work, &[command])?;
+  if !result.ok {
+    let _ = fs::remove_dir_all(&staging);
+    return Err(sftp_error(project, &result));
+  }
+
+  let local_root = paths::existing_dir(&project.local_dir, "localDir")?;
+  let remote = snapshot(&staging)?;
+  let local = snapshot(&local_root)?;
+  let mut manifest: Manifest = read_state(app, &manifest_file(&project.id))?;
+  let mut outcome = SyncResult::default();
+  for (path, step) in plan(&manifest.files, &local, &remote) {
+    match step {
+      Step::InSync => {
+        set_base(&mut manifest.files, &path, local.get(&path));
+        manifest.conflicts.remove(&path);
+      }
+      Step::TakeRemote => {
+        let target = paths::resolve_within(&local_root, &path)?;
+        if remote.contains_key(&path) {
+          create_parent(&target)?;
+          fs::copy(staging.join(&path), &target)
+            .map_err(|e| format!("Failed to write {}: {e}", target.display()))?;
+          outcome.updated.push(path.clone());
+        } else {
+          fs::remove_file(&target).map_err(|e| format!("Failed to delete {}: {e}", target.display()))?;
+          outcome.deleted.push(path.clone());
+        }
+        set_base(&mut manifest.files, &path, remote.get(&path));
+      }
+      Step::LocalAhead => outcome
+        .pending
+        .extend(change(&path, manifest.files.get(&path), local.get(&path))),
+      Step::Conflict => {
+        outcome.conflicts.push(keep_remote_copy(
+          &work,
+          &staging,
+          &path,
+          remote.contains_key(&path),
+        )?);
+        manifest.conflicts.insert(path);
+      }
+    }
+  }
+  write_state(app, &manifest_file(&project.id), &manifest)?;
+  let _ = fs::remove_dir_all(&staging);
+  Ok(outcome)
+}
+
+fn push(
+  app: &AppHandle,
+  consent: &ConsentManager,
+  project: &RemoteProject,
+  confirmation_id: Option<&str>,
+) -> Result<SyncResult, OpenWorkError> {
+  let local_root = paths::existing_dir(&project.local_dir, "localDir")?;
+  let local = snapshot(&local_root)?;
+  let mut manifest: Manifest = read_state(app, &manifest_file(&project.id))?;
+  let changed = changes(&manifest.files, &local);
+  if changed.is_empty() {
+    return Ok(SyncResult::default());
+  }
+  let deletions = changed
+    .iter()
+    .filter(|change| change.kind == ChangeKind::Deleted)
+    .count();
+  if deletions > 0 {
+    consent.require(
+      "remote_project_push",
+      &project.id,
+      &format!(
+        "Delete {deletions} file(s) from {}:{}?",
+        project.host, project.remote_dir
+      ),
+      confirmation_id,
+    )?;
+  }
+
+  // Fetch the remote side of every local change, to see whether it moved too.
+  let work = work_dir(app, &project.id)?;
+  let staging = work.join("staging");
+  fresh_dir(&staging)?;
+  let mut fetches = Vec::new();
+  for change in &changed {
+    let target = staging.join(&change.path);
+    create_parent(&target)?;
+    fetches.push(format!(
+      "-get {} {}",
+      quote(&remote_path(project, &change.path))?,
+      quote(&target.to_string_lossy())?
+    ));
+  }
+  let result = sftp(project, &work, &fetches)?;
+  if !result.ok {
+    let _ = fs::remove_dir_all(&staging);
+    return Err(sftp_error(project, &result));
+  }
+  let mut remote = manifest.files.clone();
+  for change in &changed {
+    let fetched = staging.join(&change.path);
+    if fetched.is_file() {
+      remote.insert(change.path.clone(), hash_file(&fetched)?);
+    } else {
+      remote.remove(&change.path);
+    }
+  }
+
+  let mut outcome = SyncResult::default();
+  let mut uploads = Vec::new();
+  for (path, step) in plan(&manifest.files, &local, &remote) {
+    match step {
+      Step::InSync => {
+        set_base(&mut manifest.files, &path, local.get(&path));
+        manifest.conflicts.remove(&path);
+      }
+      Step::LocalAhead => uploads.push(path),
+      Step::Conflict => {
+        outcome.conflicts.push(keep_remote_copy(
+          &work,
+          &staging,
+          &path,
+          remote.contains_key(&path),
+        )?);
+        manifest.conflicts.insert(path);
+      }
+      // Changed remotely only; the next pull brings it in.
+      Step::TakeRemote => {}
+    }
+  }
+
+  let mut commands = Vec::new();
+  let mut actions = Vec::new();
+  let parents: BTreeSet<&str> = uploads
+    .iter()
+    .filter(|path| local.contains_key(*path))
+    .flat_map(|path| path.match_indices('/').map(move |(at, _)| &path[..at]))
+    .collect();
+  for parent in parents {
+    commands.push(format!("-mkdir {}", quote(&remote_path(project, parent))?));
+    actions.push(None);
+  }
+  for (index, path) in uploads.iter().enumerate() {
+    let remote_file = quote(&remote_path(project, path))?;
+    commands.push(if local.contains_key(path) {
+      let source = paths::resolve_within(&local_root, path)?;
+      format!("put {} {remote_file}", quote(&source.to_string_lossy())?)
+    } else {
+      format!("rm {remote_file}")
+    });
+    actions.push(Some(index));
+  }
+  let result = if commands.is_empty() {
+    None
+  } else {
+    Some(sftp(project, &work, &commands)?)
+  };
+  let done = match &result {
+    Some(result) if !result.ok => commands_started(&result.stdout).saturating_sub(1),
+    _ => commands.len(),
+  };
+  for index in actions.iter().take(done).flatten() {
+    let path = &uploads[*index];
+    set_base(&mut manifest.files, path, local.get(path));
+    if local.contains_key(path) {
+      outcome.updated.push(path.clone());
+    } else {
+      outcome.deleted.push(path.clone());
+    }
+  }
+  write_state(app, &manifest_file(&project.id), &manifest)?;
+  let _ = fs::remove_dir_all(&staging);
+  match result {
+    Some(result) if !result.ok => Err(sftp_error(project, &result)),
+    _ => Ok(outcome),
+  }
+}
+
+/// Registers a remote directory and pulls it into `local_dir`, which must be
+/// new or empty.
+// Runs off the main thread, since the transfer can take a while.
+#[tauri::command(async)]
+pub fn remote_project_add(
+  app: AppHandle,
+  name: Option<String>,
+  host: String,
+  port: Option<u16>,
+  identity_file: Option<String>,
+  remote_dir: String,
+  local_dir: String,
+) -> Result<RemoteProject, OpenWorkError> {
+  let host = validate_host(&host)?;
+  let remote_dir = validate_remote_dir(&remote_dir)?;
+  let identity_file = match identity_file.filter(|file| !file.trim().is_empty()) {
+    Some(file) => {
+      let path = paths::validate_input(&file, "identityFile")?;
+      if !path.is_absolute() || !path.is_file() {
+        return Err(OpenWorkError::invalid_argument(format!(
+          "identityFile is not a file: {}",
+          path.display()
+        )));
+      }
+      Some(path.to_string_lossy().to_string())
+    }
+    None => None,
+  };
+  let local = paths::absolute_target(&local_dir, "localDir")?;
+  let created = !local.exists();
+  if !created
+    && fs::read_dir(&local)
+      .map(|mut entries| entries.next().is_some())
+      .unwrap_or(true)
+  {
+    return Err(OpenWorkError::new(
+      ErrorCode::AlreadyExists,
+      format!("{} must be a new or empty folder", local.display()),
+    ));
+  }
+  fs::create_dir_all(&local).map_err(|e| format!("Failed to create {}: {e}", local.display()))?;
+  let local = paths::canonicalize(&local)?;
+
+  let name = name
+    .map(|name| name.trim().to_string())
+    .filter(|name| !name.is_empty())
+    .or_else(|| {
+      let last = remote_dir.rsplit('/').next()?;
+      (!last.is_empty()).then(|| last.to_string())
+    })
+    .unwrap_or_else(|| host.clone());
+  let mut project = RemoteProject {
+    id: consent::random_token(12),
+    name,
+    host,
+    port,
+    identity_file,
+    remote_dir,
+    local_dir: local.to_string_lossy().to_string(),
+    last_sync_ms: None,
+  };
+  if let Err(e) = pull(&app, &project) {
+    if let Ok(work) = work_dir(&app, &project.id) {
+      let _ = fs::remove_dir_all(work);
+    }
+    if created {
+      let _ = fs::remove_dir_all(&local);
+    }
+    return Err(e);
+  }
+  project.last_sync_ms = Some(now_ms());
+  let mut projects = load(&app);
+  projects.push(project.clone());
+  save(&app, &projects)?;
+  tracing::info!(id = %project.id, "remote project added");
+  Ok(project)
+}
+
+#[tauri::command]
+pub fn remote_project_list(app: AppHandle) -> Vec<RemoteProject> {
+  load(&app)
+}
+
+/// Local changes not pushed yet and unresolved conflicts; doesn't connect.
+#[tauri::command]
+pub fn remote_project_status(app: AppHandle, id: String) -> Result<RemoteStatus, OpenWorkError> {
+  status(&app, &find(&app, &id)?)
+}
+
+fn status(app: &AppHandle, project: &RemoteProject) -> Result<RemoteStatus, OpenWorkError> {
+  let local = snapshot(&paths::existing_dir(&project.local_dir, "localDir")?)?;
+  let manifest: Manifest = read_state(app, &manifest_file(&project.id))?;
+  Ok(RemoteStatus {
+    changes: changes(&manifest.files, &local),
+    conflicts: manifest.conflicts.into_iter().collect(),
+  })
+}
+
+// Runs off the main thread, since the transfer can take a while.
+#[tauri::command(async)]
+pub fn remote_project_pull(
+  app: AppHandle,
+  sync: State<'_, RemoteSync>,
+  id: String,
+) -> Result<SyncResult, OpenWorkError> {
+  let project = find(&app, &id)?;
+  let _guard = sync.start(&project)?;
+  let outcome = pull(&app, &project)?;
+  record_sync(&app, &id);
+  Ok(outcome)
+}
+
+/// Uploads local changes. Deleting remote files needs confirmation.
+// Runs off the main thread, since the transfer can take a while.
+#[tauri::command(async)]
+pub fn remote_project_push(
+  app: AppHandle,
+  sync: State<'_, RemoteSync>,
+  consent: State<'_, ConsentManager>,
+  id: String,
+  confirmation_id: Option<String>,
+) -> Result<SyncResult, OpenWorkError> {
+  let project = find(&app, &id)?;
+  let _guard = sync.start(&project)?;
+  let outcome = push(&app, &consent, &project, confirmation_id.as_deref())?;
+  record_sync(&app, &id);
+  Ok(outcome)
+}
+
+/// Settles a conflict. `remote` replaces the local file with the copy kept
+/// aside; `local` keeps it, and the next push uploads it over the remote one.
+#[tauri::command]
+pub fn remote_project_resolve(
+  app: AppHandle,
+  sync: State<RemoteSync>,
+  id: String,
+  path: String,
+  keep: ConflictSide,
+) -> Result<RemoteStatus, OpenWorkError> {
+  let project = find(&app, &id)?;
+  let _guard = sync.start(&project)?;
+  let mut manifest: Manifest = read_state(&app, &manifest_file(&project.id))?;
+  if !manifest.conflicts.remove(&path) {
+    return Err(OpenWorkError::new(
+      ErrorCode::NotFound,
+      format!("{path} has no conflict"),
+    ));
+  }
+  let work = work_dir(&app, &project.id)?;
+  let copy = paths::resolve_within(&work, &format!("conflicts/{path}"))?;
+  let remote_hash = if copy.is_file() {
+    Some(hash_file(&copy)?)
+  } else {
+    None
+  };
+  if keep == ConflictSide::Remote {
+    let target = paths::resolve_within(&paths::existing_dir(&project.local_dir, "localDir")?, &path)?;
+    if copy.is_file() {
+      create_parent(&target)?;
+      fs::copy(&copy, &target).map_err(|e| format!("Failed to write {}: {e}", target.display()))?;
+    } else if target.exists() {
+      fs::remove_file(&target).map_err(|e| format!("Failed to delete {}: {e}", target.display()))?;
+    }
+  }
+  set_base(&mut manifest.files, &path, remote_hash.as_ref());
+  write_state(&app, &manifest_file(&project.id), &manifest)?;
+  let _ = fs::remove_file(&copy);
+  status(&app, &project)
+}
+
+/// Forgets a remote project. With `delete_mirror`, the local mirror goes to
+/// the trash too; the remote directory is never touched.
+#[tauri::command]
+pub fn remote_project_remove(
+  app: AppHandle,
+  sync: State<RemoteSync>,
+  consent: State<ConsentManager>,
+  id: String,
+  delete_mirror: Option<bool>,
+  confirmation_id: Option<String>,
+) -> Result<Vec<RemoteProject>, OpenWorkError> {
+  let project = find(&app, &id)?;
+  let _guard = sync.start(&project)?;
+  if delete_mirror.unwrap_or(false) && Path::new(&project.local_dir).exists() {
+    consent.require(
+      "remote_project_remove",
+      &project.local_dir,
+      &format!("Move the local mirror {} to the trash?", project.local_dir),
+      confirmation_id.as_deref(),
+    )?;
+    remove_path(Path::new(&project.local_dir), false)?;
+  }
+  if let Ok(work) = work_dir(&app, &project.id) {
+    let _ = fs::remove_dir_all(work);
+  }
+  let mut projects = load(&app);
+  projects.retain(|project| project.id != id);
+  save(&app, &projects)?;
+  Ok(projects)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn files(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+    entries
+      .iter()
+      .map(|(path, hash)| (path.to_string(), hash.to_string()))
+      .collect()
+  }
+
+  #[test]
+  fn plans_three_way() {
+    let base = files(&[
+      ("same", "a"),
+      ("theirs", "a"),
+      ("ours", "a"),
+      ("both", "a"),
+      ("gone", "a"),
+    ]);
+    let local = files(&[
+      ("same", "a"),
+      ("theirs", "a"),
+      ("ours", "b"),
+      ("both", "b"),
+      ("new", "x"),
+    ]);
+    let remote = files(&[
+      ("same", "a"),
+      ("theirs", "c"),
+      ("ours", "a"),
+      ("both", "c"),
+      ("new", "x"),
+    ]);
+    let steps: BTreeMap<String, Step> = plan(&base, &local, &remote).into_iter().collect();
+    assert_eq!(steps["same"], Step::InSync);
+    assert_eq!(steps["theirs"], Step::TakeRemote);
+    assert_eq!(steps["ours"], Step::LocalAhead);
+    assert_eq!(steps["both"], Step::Conflict);
+    // Deleted on both sides, and added identically on both.
+    assert_eq!(steps["gone"], Step::InSync);
+    assert_eq!(steps["new"], Step::InSync);
+
+    // A remote deletion of an untouched file is taken; of an edited one, it conflicts.
+    let remote = files(&[("ours", "a")]);
+    let steps: BTreeMap<String, Step> = plan(&base, &local, &remote).into_iter().collect();
+    assert_eq!(steps["theirs"], Step::TakeRemote);
+    assert_eq!(steps["both"], Step::Conflict);
+  }
+
+  #[test]
+  fn lists_local_changes() {
+    let base = files(&[("a", "1"), ("b", "1"), ("c", "1")]);
+    let local = files(&[("a", "1"), ("b", "2"), ("d", "1")]);
+    assert_eq!(
+      changes(&base, &local),
+      vec![
+        LocalChange {
+          path: "b".into(),
+          kind: ChangeKind::Modified
+        },
+        LocalChange {
+          path: "c".into(),
+          kind: ChangeKind::Deleted
+        },
+        LocalChange {
+          path: "d".into(),
+          kind: ChangeKind::Added
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn quotes_batch_arguments() {
+    assert_eq!(quote("src/main.rs").unwrap(), "\"src/main.rs\"");
+    assert_eq!(quote("a \"b\"\\c").unwrap(), "\"a \\\"b\\\"\\\\c\"");
+    assert_eq!(quote("[id]/*.ts?").unwrap(), "\"\\[id\\]/\\*.ts\\?\"");
+    assert!(quote("a\nrm -r /").is_err());
+  }
+
+  #[test]
+  fn counts_echoed_commands() {
+    let stdout = "sftp> -mkdir \"/srv/app/src\"\nsftp> put \"a\" \"/srv/app/a\"\nUploading a\n";
+    assert_eq!(commands_started(stdout), 2);
+    assert_eq!(commands_started(""), 0);
+  }
+
+  #[test]
+  fn validates_hosts_and_dirs() {
+    assert_eq!(
+      validate_host(" deploy@example.com ").unwrap(),
+      "deploy@example.com"
+    );
+    assert!(validate_host("-oProxyCommand=x").is_err());
+    assert!(validate_host("a b").is_err());
+    assert_eq!(validate_remote_dir("/srv/app/").unwrap(), "/srv/app");
+    assert_eq!(validate_remote_dir("/").unwrap(), "/");
+    assert!(validate_remote_dir("  ").is_err());
+  }
+}
@@ -0,0 +1,256 @@
+//! Recurring background jobs: update checks, config backups, engine health
+//! probes, usage totals and telemetry batches.
+//!
+//! Each job runs on its own timer on the async runtime, blocking work is moved
+//! to the blocking pool, and every job can be switched off from settings. A
+//! disabled job keeps its timer but skips its work, so re-enabling takes effect
+//! at the next tick.
+
+use std::{
+  collections::HashMap,
+  sync::Mutex,
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::{
+  budget, config_backup,
+  engine_client::EngineClient,
+  error::OpenWorkError,
+  menu::ENGINE_STOPPED_EVENT,
+  store::{read_state, write_state},
+  telemetry, updater,
+  webhooks::{self, WebhookEvent},
+  EngineManager,
+};
+
+pub const SCHEDULER_FILE: &str = "scheduler.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum JobId {
+  UpdateCheck,
+  ConfigBackup,
+  EngineHealth,
+  UsageAggregation,
+  TelemetryFlush,
+}
+
+struct JobDef {
+  id: JobId,
+  name: &'static str,
+  description: &'static str,
+  interval: Duration,
+  /// Delay before the first run, so startup isn't slowed down.
+  first_run: Duration,
+}
+
+const JOBS: &[JobDef] = &[
+  JobDef {
+    id: JobId::UpdateCheck,
+    name: "Update check",
+    description: "Looks for a new OpenWork release.",
+    interval: Duration::from_secs(6 * 60 * 60),
+    first_run: Duration::from_secs(60),
+  },
+  JobDef {
+    id: JobId::ConfigBackup,
+    name: "Config backup",
+    description: "Keeps copies of your OpenWork settings and the opencode configs and AGENTS.md of recent projects.",
+    interval: Duration::from_secs(60 * 60),
+    first_run: Duration::from_secs(10 * 60),
+  },
+  JobDef {
+    id: JobId::EngineHealth,
+    name: "Engine health",
+    description: "Checks that the main window's engine is still running and responding.",
+    interval: Duration::from_secs(30),
+    first_run: Duration::from_secs(30),
+  },
+  JobDef {
+    id: JobId::UsageAggregation,
+    name: "Usage totals",
+    description: "Totals month-to-date spend and raises budget alerts.",
+    interval: Duration::from_secs(60 * 60),
+    first_run: Duration::from_secs(5 * 60),
+  },
+  JobDef {
+    id: JobId::TelemetryFlush,
+    name: "Telemetry",
+    description: "Sends pending usage counters, when telemetry is enabled.",
+    interval: telemetry::FLUSH_INTERVAL,
+    first_run: telemetry::FLUSH_INTERVAL,
+  },
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SchedulerSettings {
+  /// Jobs the user switched off; everything else runs.
+  pub disabled: Vec<JobId>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct JobRun {
+  last_run_ms: Option<u64>,
+  last_error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JobInfo {
+  pub id: JobId,
+  pub name: String,
+  pub description: String,
+  pub interval_secs: u64,
+  pub enabled: bool,
+  pub last_run_ms: Option<u64>,
+  /// Error from the last run, if it failed.
+  pub last_error: Option<String>,
+}
+
+#[derive(Default)]
+pub struct Scheduler {
+  settings: Mutex<Option<SchedulerSettings>>,
+  runs: Mutex<HashMap<JobId, JobRun>>,
+}
+
+impl Scheduler {
+  fn settings(&self, app: &AppHandle) -> SchedulerSettings {
+    let mut settings = self.settings.lock().expect("scheduler mutex poisoned");
+    settings
+      .get_or_insert_with(|| read_state(app, SCHEDULER_FILE).unwrap_or_default())
+      .clone()
+  }
+
+  fn jobs(&self, app: &AppHandle) -> Vec<JobInfo> {
+    let settings = self.settings(app);
+    let runs = self.runs.lock().expect("scheduler mutex poisoned");
+    JOBS
+      .iter()
+      .map(|job| {
+        let run = runs.get(&job.id).cloned().unwrap_or_default();
+        JobInfo {
+          id: job.id,
+          name: job.name.to_string(),
+          description: job.description.to_string(),
+          interval_secs: job.interval.as_secs(),
+          enabled: !settings.disabled.contains(&job.id),
+          last_run_ms: run.last_run_ms,
+          last_error: run.last_error,
+        }
+      })
+      .collect()
+  }
+}
+
+fn now_ms() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_millis() as u64)
+    .unwrap_or(0)
+}
+
+/// Reports an engine that exited on its own, and one that stopped answering.
+fn probe_engine(app: &AppHandle) -> Result<(), String> {
+  let manager = app.state::<EngineManager>();
+  let (exited, info) = {
+    let mut state = manager.main_engine();
+    let had_child = state.child.is_some();
+    let info = EngineManager::snapshot_locked(&mut state);
+    (had_child && !info.running, info)
+  };
+
+  if exited {
+    tracing::warn!(project_dir = ?info.project_dir, "engine exited unexpectedly");
+    let text = match &info.project_dir {
+      Some(dir) => format!("The OpenWork engine for {dir} exited unexpectedly."),
+      None => "The OpenWork engine exited unexpectedly.".to_string(),
+    };
+    webhooks::send(
+      WebhookEvent::EngineCrashed,
+      &text,
+      serde_json::json!({ "projectDir": info.project_dir }),
+    );
+    let _ = app.emit(ENGINE_STOPPED_EVENT, info);
+    return Err("The engine exited unexpectedly.".to_string());
+  }
+  if info.running && !EngineClient::from_manager(&manager)?.is_healthy() {
+    return Err("The engine is running but not responding.".to_string());
+  }
+  Ok(())
+}
+
+async fn run_job(app: &AppHandle, id: JobId) -> Result<(), String> {
+  if id == JobId::UpdateCheck {
+    return updater::check_in_background(app).await.map_err(|e| e.to_string());
+  }
+
+  let app = app.clone();
+  tauri::async_runtime::spawn_blocking(move || match id {
+    JobId::ConfigBackup => config_backup::run(&app),
+    JobId::EngineHealth => probe_engine(&app),
+    JobId::UsageAggregation => {
+      budget::check_and_alert(&app, None);
+      Ok(())
+    }
+    JobId::TelemetryFlush => telemetry::flush().map(|_| ()),
+    JobId::UpdateCheck => Ok(()),
+  })
+  .await
+  .map_err(|e| format!("Job panicked: {e}"))?
+}
+
+async fn job_loop(app: AppHandle, job: &'static JobDef) {
+  tokio::time::sleep(job.first_run).await;
+  loop {
+    let enabled = !app.state::<Scheduler>().settings(&app).disabled.contains(&job.id);
+    if enabled {
+      let result = run_job(&app, job.id).await;
+      if let Err(e) = &result {
+        tracing::debug!(job = ?job.id, error = %e, "scheduled job failed");
+      }
+      let scheduler = app.state::<Scheduler>();
+      let mut runs = scheduler.runs.lock().expect("scheduler mutex poisoned");
+      runs.insert(
+        job.id,
+        JobRun {
+          last_run_ms: Some(now_ms()),
+          last_error: result.err(),
+        },
+      );
+    }
+    tokio::time::sleep(job.interval).await;
+  }
+}
+
+pub fn init(app: &AppHandle) {
+  for job in JOBS {
+    tauri::async_runtime::spawn(job_loop(app.clone(), job));
+  }
+}
+
+#[tauri::command]
+pub fn scheduler_jobs_list(app: AppHandle, scheduler: State<Scheduler>) -> Vec<JobInfo> {
+  scheduler.jobs(&app)
+}
+
+#[tauri::command]
+pub fn scheduler_job_toggle(
+  app: AppHandle,
+  scheduler: State<Scheduler>,
+  id: JobId,
+  enabled: bool,
+) -> Result<Vec<JobInfo>, OpenWorkError> {
+  let mut settings = scheduler.settings(&app);
+  settings.disabled.retain(|disabled| *disabled != id);
+  if !enabled {
+    settings.disabled.push(id);
+  }
+  write_state(&app, SCHEDULER_FILE, &settings)?;
+  *scheduler.settings.lock().expect("scheduler mutex poisoned") = Some(settings);
+  tracing::info!(job = ?id, enabled, "scheduled job toggled");
+  Ok(scheduler.jobs(&app))
+}
@@ -0,0 +1,325 @@
+use std::{
+  fs,
+  io::{BufRead, BufReader},
+  path::Path,
+  process::Stdio,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  error::OpenWorkError,
+  exec,
+  project::{project_files, project_root, relative_display},
+  resolve_in_path,
+};
+
+const DEFAULT_MAX_RESULTS: usize = 200;
+const MAX_CONTEXT_LINES: usize = 10;
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+const MAX_LINE_CHARS: usize = 400;
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProjectSearchOptions {
+  pub case_sensitive: bool,
+  pub context_lines: usize,
+  pub max_results: Option<usize>,
+  pub include_hidden: bool,
+  /// Match file paths only (quick-open) instead of file contents.
+  pub files_only: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchMatch {
+  pub path: String,
+  pub line_number: Option<u64>,
+  pub line: Option<String>,
+  pub before: Vec<String>,
+  pub after: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectSearchResult {
+  pub matches: Vec<SearchMatch>,
+  pub truncated: bool,
+  pub backend: String,
+}
+
+fn clip_line(line: &str) -> String {
+  let line = line.trim_end_matches(['\r', '\n']);
+  if line.chars().count() <= MAX_LINE_CHARS {
+    return line.to_string();
+  }
+  line.chars().take(MAX_LINE_CHARS).collect()
+}
+
+fn contains(haystack: &str, needle: &str, case_sensitive: bool) -> bool {
+  if case_sensitive {
+    haystack.contains(needle)
+  } else {
+    haystack.to_lowercase().contains(needle)
+  }
+}
+
+fn search_file_names(
+  root: &Path,
+  query: &str,
+  options: &ProjectSearchOptions,
+  limit: usize,
+) -> ProjectSearchResult {
+  let needle = if options.case_sensitive {
+    query.to_string()
+  } else {
+    query.to_lowercase()
+  };
+
+  let mut matches = Vec::new();
+  let mut truncated = false;
+
+  for entry in project_files(root, options.include_hidden) {
+    let path = relative_display(root, entry.path());
+    if !contains(&path, &needle, options.case_sensitive) {
+      continue;
+    }
+    if matches.len() >= limit {
+      truncated = true;
+      break;
+    }
+    matches.push(SearchMatch {
+      path,
+      line_number: None,
+      line: None,
+      before: Vec::new(),
+      after: Vec::new(),
+    });
+  }
+
+  ProjectSearchResult {
+    matches,
+    truncated,
+    backend: "builtin".to_string(),
+  }
+}
+
+fn search_builtin(
+  root: &Path,
+  query: &str,
+  options: &ProjectSearchOptions,
+  limit: usize,
+) -> ProjectSearchResult {
+  let needle = if options.case_sensitive {
+    query.to_string()
+  } else {
+    query.to_lowercase()
+  };
+  let context = options.context_lines.min(MAX_CONTEXT_LINES);
+
+  let mut matches = Vec::new();
+  let mut truncated = false;
+
+  'files: for entry in project_files(root, options.include_hidden) {
+    let too_large = entry
+      .metadata()
+      .map(|m| m.len() > MAX_FILE_BYTES)
+      .unwrap_or(true);
+    if too_large {
+      continue;
+    }
+
+    let Ok(bytes) = fs::read(entry.path()) else {
+      continue;
+    };
+    // Treat anything with a NUL byte near the start as binary.
+    if bytes.iter().take(8192).any(|b| *b == 0) {
+      continue;
+    }
+
+    let text = String::from_utf8_lossy(&bytes);
+    let lines: Vec<&str> = text.lines().collect();
+    let path = relative_display(root, entry.path());
+
+    for (index, line) in lines.iter().enumerate() {
+      if !contains(line, &needle, options.case_sensitive) {
+        continue;
+      }
+      if matches.len() >= limit {
+        truncated = true;
+        break 'files;
+      }
+
+      let start = index.saturating_sub(context);
+      let end = (index + 1 + context).min(lines.len());
+      matches.push(SearchMatch {
+        path: path.clone(),
+        line_number: Some(index as u64 + 1),
+        line: Some(clip_line(line)),
+        before: lines[start..index].iter().map(|l| clip_line(l)).collect(),
+        after: lines[index + 1..end].iter().map(|l| clip_line(l)).collect(),
+      });
+    }
+  }
+
+  ProjectSearchResult {
+    matches,
+    truncated,
+    backend: "builtin".to_string(),
+  }
+}
+
+fn push_context(lines: &mut Vec<String>, line: String, context: usize) {
+  lines.push(line);
+  if lines.len() > context {
+    let excess = lines.len() - context;
+    lines.drain(..excess);
+  }
+}
+
+fn json_text(value: &serde_json::Value) -> Option<String> {
+  value.get("text").and_then(|t| t.as_str()).map(str::to_string)
+}
+
+/// Streams `rg --json` output. Returns `None` when ripgrep could not be run so
+/// the caller can fall back to the builtin search.
+fn search_ripgrep(
+  root: &Path,
+  query: &str,
+  options: &ProjectSearchOptions,
+  limit: usize,
+) -> Option<ProjectSearchResult> {
+  #[cfg(windows)]
+  let rg = resolve_in_path("rg.exe")?;
+  #[cfg(not(windows))]
+  let rg = resolve_in_path("rg")?;
+
+  let context = options.context_lines.min(MAX_CONTEXT_LINES);
+
+  let mut command = exec::command(rg);
+  command
+    .arg("--json")
+    .arg("--fixed-strings")
+    .arg("--max-filesize")
+    .arg(MAX_FILE_BYTES.to_string())
+    .arg("--context")
+    .arg(context.to_string())
+    .arg(if options.case_sensitive {
+      "--case-sensitive"
+    } else {
+      "--ignore-case"
+    });
+  if options.include_hidden {
+    command.arg("--hidden").arg("--glob").arg("!.git");
+  }
+  command
+    .arg("--")
+    .arg(query)
+    .arg(".")
+    .current_dir(root)
+    .stdin(Stdio::null())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::null());
+
+  let mut child = exec::spawn(&mut command).ok()?;
+  let stdout = child.stdout.take()?;
+
+  let mut matches: Vec<SearchMatch> = Vec::new();
+  let mut pending_before: Vec<String> = Vec::new();
+  let mut in_file_with_match = false;
+  let mut truncated = false;
+
+  for line in BufReader::new(stdout).lines() {
+    let Ok(line) = line else {
+      break;
+    };
+    let Ok(event) = serde_json::from_str::<serde_json::Value>(&line) else {
+      continue;
+    };
+
+    let kind = event.get("type").and_then(|t| t.as_str()).unwrap_or_default();
+    let data = event.get("data");
+
+    match kind {
+      "begin" | "end" => {
+        pending_before.clear();
+        in_file_with_match = false;
+      }
+      "context" => {
+        let Some(text) = data.and_then(|d| d.get("lines")).and_then(json_text) else {
+          continue;
+        };
+        let text = clip_line(&text);
+        if in_file_with_match {
+          if let Some(last) = matches.last_mut() {
+            if last.after.len() < context {
+              last.after.push(text.clone());
+            }
+          }
+        }
+        push_context(&mut pending_before, text, context);
+      }
+      "match" => {
+        if matches.len() >= limit {
+          truncated = true;
+          break;
+        }
+        let Some(data) = data else {
+          continue;
+        };
+        let path = data.get("path").and_then(json_text).unwrap_or_default();
+        let path = path.strip_prefix("./").unwrap_or(&path).replace('\\', "/");
+        let text = data.get("lines").and_then(json_text).unwrap_or_default();
+        let text = clip_line(&text);
+
+        matches.push(SearchMatch {
+          path,
+          line_number: data.get("line_number").and_then(|n| n.as_u64()),
+          line: Some(text.clone()),
+          before: std::mem::take(&mut pending_before),
+          after: Vec::new(),
+        });
+        in_file_with_match = true;
+        // A match can serve as context for the next match in the same file.
+        push_context(&mut pending_before, text, context);
+      }
+      _ => {}
+    }
+  }
+
+  let _ = child.kill();
+  let _ = child.wait();
+
+  Some(ProjectSearchResult {
+    matches,
+    truncated,
+    backend: "ripgrep".to_string(),
+  })
+}
+
+#[tauri::command]
+pub fn project_search(
+  project_dir: String,
+  query: String,
+  options: Option<ProjectSearchOptions>,
+) -> Result<ProjectSearchResult, OpenWorkError> {
+  let root = project_root(&project_dir)?;
+
+  if query.is_empty() {
+    return Err(OpenWorkError::invalid_argument("query is required"));
+  }
+
+  let options = options.unwrap_or_default();
+  crate::telemetry::record(if options.files_only { "feature.quick_open" } else { "feature.search" });
+  let limit = options.max_results.unwrap_or(DEFAULT_MAX_RESULTS).max(1);
+
+  if options.files_only {
+    return Ok(search_file_names(&root, &query, &options, limit));
+  }
+
+  if let Some(result) = search_ripgrep(&root, &query, &options, limit) {
+    return Ok(result);
+  }
+
+  Ok(search_builtin(&root, &query, &options, limit))
+}
@@ -0,0 +1,317 @@
+//! Running command lines through the platform's shell.
+//!
+//! Anything that needs shell syntax (a login environment, `command -v`, user
+//! hooks) goes through here instead of hard-coding `sh -c`. The user's shell
+//! is picked once: `$SHELL` when it's one we know how to quote for, otherwise
+//! `bash` or `sh`; on Windows PowerShell 7, Windows PowerShell or `cmd.exe`.
+//!
+//! Values interpolated into a script must be passed through `Shell::quote`,
+//! which refuses what it can't quote safely (`cmd.exe` has no reliable escape
+//! for `%` or `"`).
+
+use std::{
+  path::{Path, PathBuf},
+  process::Command,
+  sync::OnceLock,
+  time::{Duration, Instant},
+};
+
+use crate::{exec, redact, ExecResult};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShellKind {
+  Sh,
+  Bash,
+  Zsh,
+  Fish,
+  PowerShell,
+  Cmd,
+}
+
+impl ShellKind {
+  /// The kind of shell `program` is, from its file name.
+  fn from_program(program: &Path) -> Option<Self> {
+    let name = program.file_stem()?.to_str()?.to_ascii_lowercase();
+    match name.as_str() {
+      "sh" | "dash" | "ash" => Some(ShellKind::Sh),
+      "bash" => Some(ShellKind::Bash),
+      "zsh" => Some(ShellKind::Zsh),
+      "fish" => Some(ShellKind::Fish),
+      "pwsh" | "powershell" => Some(ShellKind::PowerShell),
+      "cmd" => Some(ShellKind::Cmd),
+      _ => None,
+    }
+  }
+}
+
+/// Characters that never need quoting in any supported shell.
+fn is_plain(arg: &str) -> bool {
+  !arg.is_empty()
+    && arg
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/' | ':' | '=' | '+' | ',' | '@'))
+}
+
+/// `arg` as a single word for `kind`, or `None` when that shell can't take it
+/// literally.
+fn quote_for(kind: ShellKind, arg: &str) -> Option<String> {
+  if arg.contains('\0') {
+    return None;
+  }
+  if is_plain(arg) && !(kind == ShellKind::PowerShell && arg.starts_with(['-', '@'])) {
+    return Some(arg.to_string());
+  }
+  match kind {
+    ShellKind::Sh | ShellKind::Bash | ShellKind::Zsh => Some(format!("'{}'", arg.replace('\'', r"'\''"))),
+    // Inside fish's single quotes only `\'` and `\\` are escapes.
+    ShellKind::Fish => Some(format!("'{}'", arg.replace('\\', r"\\").replace('\'', r"\'"))),
+    // PowerShell also treats the typographic single quotes as quotes.
+    ShellKind::PowerShell => {
+      let mut quoted = String::from("'");
+      for c in arg.chars() {
+        if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201a}' | '\u{201b}') {
+          quoted.push(c);
+        }
+        quoted.push(c);
+      }
+      quoted.push('\'');
+      Some(quoted)
+    }
+    // Variables expand inside cmd's quotes, and a `"` ends them.
+    ShellKind::Cmd => (!arg.contains(['"', '%', '!', '\r', '\n'])).then(|| format!("\"{arg}\"")),
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct Shell {
+  pub kind: ShellKind,
+  pub program: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+pub struct ShellOptions {
+  /// Start a login shell, which reads the user's profile (PATH and the like).
+  pub login: bool,
+  /// Also read interactive startup files such as `.zshrc`; POSIX shells and
+  /// fish only.
+  pub interactive: bool,
+  pub cwd: Option<PathBuf>,
+  /// The shell is killed if it hasn't finished by then.
+  pub timeout: Duration,
+}
+
+impl Default for ShellOptions {
+  fn default() -> Self {
+    Self {
+      login: false,
+      interactive: false,
+      cwd: None,
+      timeout: Duration::from_secs(30),
+    }
+  }
+}
+
+impl Shell {
+  fn new(kind: ShellKind, program: impl Into<PathBuf>) -> Self {
+    Self {
+      kind,
+      program: program.into(),
+    }
+  }
+
+  /// The user's shell, detected once.
+  pub fn user() -> &'static Shell {
+    static SHELL: OnceLock<Shell> = OnceLock::new();
+    SHELL.get_or_init(detect)
+  }
+
+  /// Plain POSIX `sh`, for scripts that have to run the same everywhere,
+  /// including on a Flatpak host whose shell we can't see.
+  pub fn sh() -> Shell {
+    Shell::new(ShellKind::Sh, "sh")
+  }
+
+  /// `arg` quoted as one word for this shell.
+  pub fn quote(&self, arg: &str) -> Result<String, String> {
+    quote_for(self.kind, arg)
+      .ok_or_else(|| format!("Can't pass {arg:?} to {} safely", self.program.display()))
+  }
+
+  /// A command line running `program` with `args`, every word quoted.
+  pub fn join<S: AsRef<str>>(&self, program: &str, args: &[S]) -> Result<String, String> {
+    let mut words = vec![self.quote(program)?];
+    if self.kind == ShellKind::PowerShell && words[0].starts_with('\'') {
+      // A quoted string is an expression; `&` runs it as a command.
+      words[0].insert_str(0, "& ");
+    }
+    for arg in args {
+      words.push(self.quote(arg.as_ref())?);
+    }
+    Ok(words.join(" "))
+  }
+
+  /// A `Command` that runs `script` in this shell; spawn it through `exec`.
+  pub fn command(&self, script: &str, options: &ShellOptions) -> Command {
+    let mut command = exec::command(&self.program);
+    match self.kind {
+      ShellKind::Sh | ShellKind::Bash | ShellKind::Zsh | ShellKind::Fish => {
+        if options.login {
+          command.arg("-l");
+        }
+        if options.interactive {
+          command.arg("-i");
+        }
+        command.arg("-c").arg(script);
+      }
+      ShellKind::PowerShell => {
+        command.args(["-NoLogo", "-NonInteractive"]);
+        if !options.login {
+          command.arg("-NoProfile");
+        }
+        command.arg("-Command").arg(script);
+      }
+      ShellKind::Cmd => {
+        command.args(["/d", "/s", "/c"]);
+        raw_arg(&mut command, &format!("\"{script}\""));
+      }
+    }
+    if let Some(cwd) = &options.cwd {
+      command.current_dir(cwd);
+    }
+    command
+  }
+}
+
+/// Passes `arg` to cmd.exe untouched: it doesn't follow the quoting rules std
+/// applies to Windows arguments.
+#[cfg(windows)]
+fn raw_arg(command: &mut Command, arg: &str) {
+  use std::os::windows::process::CommandExt;
+
+  command.raw_arg(arg);
+}
+
+#[cfg(not(windows))]
+fn raw_arg(command: &mut Command, arg: &str) {
+  command.arg(arg);
+}
+
+#[cfg(windows)]
+fn detect() -> Shell {
+  for name in ["pwsh.exe", "powershell.exe"] {
+    if let Some(program) = crate::resolve_in_path(name) {
+      return Shell::new(ShellKind::PowerShell, program);
+    }
+  }
+  let comspec = std::env::var_os("COMSPEC").filter(|value| !value.is_empty());
+  Shell::new(ShellKind::Cmd, comspec.unwrap_or_else(|| "cmd.exe".into()))
+}
+
+#[cfg(not(windows))]
+fn detect() -> Shell {
+  let user = std::env::var_os("SHELL")
+    .filter(|value| !value.is_empty())
+    .map(PathBuf::from);
+  if let Some(program) = user {
+    match ShellKind::from_program(&program) {
+      Some(kind @ (ShellKind::Sh | ShellKind::Bash | ShellKind::Zsh | ShellKind::Fish)) => {
+        return Shell::new(kind, program)
+      }
+      _ => tracing::debug!(shell = %program.display(), "unsupported login shell, falling back"),
+    }
+  }
+  match crate::resolve_in_path("bash") {
+    Some(bash) => Shell::new(ShellKind::Bash, bash),
+    None => Shell::new(ShellKind::Sh, "/bin/sh"),
+  }
+}
+
+/// Runs `script` in `shell` to completion, killing it at the timeout, and
+/// returns its output redacted.
+#[tracing::instrument(level = "debug", skip_all, fields(shell = ?shell.kind))]
+pub fn run_in_shell(shell: &Shell, script: &str, options: &ShellOptions) -> Result<ExecResult, String> {
+  let mut command = shell.command(script, options);
+  let output = crate::output_before(&mut command, Instant::now() + options.timeout).ok_or_else(|| {
+    format!(
+      "{} did not finish within {}s",
+      shell.program.display(),
+      options.timeout.as_secs()
+    )
+  })?;
+  let status = output.status.code().unwrap_or(-1);
+  tracing::debug!(status, "shell finished");
+  Ok(ExecResult {
+    ok: output.status.success(),
+    status,
+    stdout: redact::redact(&String::from_utf8_lossy(&output.stdout)),
+    stderr: redact::redact(&String::from_utf8_lossy(&output.stderr)),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn recognizes_shells_by_name() {
+    assert_eq!(
+      ShellKind::from_program(Path::new("/bin/zsh")),
+      Some(ShellKind::Zsh)
+    );
+    assert_eq!(
+      ShellKind::from_program(Path::new("/usr/local/bin/fish")),
+      Some(ShellKind::Fish)
+    );
+    assert_eq!(
+      ShellKind::from_program(Path::new("pwsh.exe")),
+      Some(ShellKind::PowerShell)
+    );
+    assert_eq!(ShellKind::from_program(Path::new("/usr/bin/nu")), None);
+  }
+
+  #[test]
+  fn quotes_for_each_shell() {
+    assert_eq!(
+      quote_for(ShellKind::Bash, "opencode").as_deref(),
+      Some("opencode")
+    );
+    assert_eq!(
+      quote_for(ShellKind::Bash, "it's $HOME").as_deref(),
+      Some(r"'it'\''s $HOME'")
+    );
+    assert_eq!(
+      quote_for(ShellKind::Fish, r"a\b'c").as_deref(),
+      Some(r"'a\\b\'c'")
+    );
+    assert_eq!(
+      quote_for(ShellKind::PowerShell, "it's $env:PATH").as_deref(),
+      Some("'it''s $env:PATH'")
+    );
+    assert_eq!(
+      quote_for(ShellKind::PowerShell, "-Force").as_deref(),
+      Some("'-Force'")
+    );
+    assert_eq!(
+      quote_for(ShellKind::Cmd, r"C:\Program Files\x").as_deref(),
+      Some(r#""C:\Program Files\x""#)
+    );
+    assert_eq!(quote_for(ShellKind::Cmd, "100%"), None);
+    assert_eq!(quote_for(ShellKind::Bash, "nul\0byte"), None);
+  }
+
+  #[test]
+  fn joins_quoted_command_lines() {
+    let bash = Shell::new(ShellKind::Bash, "/bin/bash");
+    assert_eq!(
+      bash.join("command", &["-v", "open code"]).unwrap(),
+      "command -v 'open code'"
+    );
+    let pwsh = Shell::new(ShellKind::PowerShell, "pwsh.exe");
+    assert_eq!(
+      pwsh
+        .join(r"C:\Program Files\nodejs\npm.cmd", &["--version"])
+        .unwrap(),
+      r"& 'C:\Program Files\nodejs\npm.cmd' '--version'"
+    );
+  }
+}
@@ -0,0 +1,113 @@
+//! Login-shell PATH for apps started from Finder or the Dock.
+//!
+//! macOS gives GUI apps launchd's PATH (`/usr/bin:/bin:/usr/sbin:/sbin`), so
+//! tools installed through Homebrew, nvm or `~/.opencode/bin` are invisible
+//! even though every terminal finds them. At startup the user's login shell is
+//! asked for its PATH once, and its entries are put in front of the inherited
+//! ones. Everything that resolves or spawns programs reads PATH from the
+//! process environment, so it all sees the merged value.
+
+#[cfg(any(target_os = "macos", test))]
+use std::path::PathBuf;
+
+/// Wraps the printed PATH so output from rc files can't be mistaken for it.
+#[cfg(any(target_os = "macos", test))]
+const MARKER: &str = "__OPENWORK_PATH__";
+
+/// The text between the first pair of markers in `output`.
+#[cfg(any(target_os = "macos", test))]
+fn extract_marked(output: &str) -> Option<&str> {
+  let (_, rest) = output.split_once(MARKER)?;
+  let (path, _) = rest.split_once(MARKER)?;
+  Some(path.trim()).filter(|path| !path.is_empty())
+}
+
+/// `login` entries first, then `current` ones the login shell doesn't have.
+/// Empty entries (which mean the working directory) are dropped.
+#[cfg(any(target_os = "macos", test))]
+fn merge_paths(login: Vec<PathBuf>, current: Vec<PathBuf>) -> Vec<PathBuf> {
+  let mut merged: Vec<PathBuf> = Vec::new();
+  for entry in login.into_iter().chain(current) {
+    if !entry.as_os_str().is_empty() && !merged.contains(&entry) {
+      merged.push(entry);
+    }
+  }
+  merged
+}
+
+#[cfg(target_os = "macos")]
+static OUTCOME: std::sync::OnceLock<Result<usize, String>> = std::sync::OnceLock::new();
+
+#[cfg(target_os = "macos")]
+fn login_shell_path() -> Result<Vec<PathBuf>, String> {
+  use std::{env, time::Duration};
+
+  let shell = crate::shell::Shell::user();
+  let script = format!("printf '%s' \"{MARKER}$PATH{MARKER}\"");
+  // Interactive as well, since nvm and friends are usually set up in .zshrc.
+  let options = crate::shell::ShellOptions {
+    login: true,
+    interactive: true,
+    timeout: Duration::from_secs(5),
+    ..Default::default()
+  };
+  let output = crate::shell::run_in_shell(shell, &script, &options)?;
+  let name = shell.program.display();
+  let path = extract_marked(&output.stdout).ok_or_else(|| format!("{name} did not print PATH"))?;
+  Ok(env::split_paths(path).collect())
+}
+
+/// Merges the login shell's PATH into this process's environment. Runs before
+/// the app starts any threads, since changing the environment isn't safe once
+/// others may read it.
+pub fn init() {
+  #[cfg(target_os = "macos")]
+  {
+    use std::env;
+
+    let outcome = login_shell_path().and_then(|login| {
+      let current = env::var_os("PATH")
+        .map(|path| env::split_paths(&path).collect())
+        .unwrap_or_default();
+      let merged = merge_paths(login, current);
+      let count = merged.len();
+      let joined = env::join_paths(merged).map_err(|e| format!("Invalid PATH entry: {e}"))?;
+      env::set_var("PATH", joined);
+      Ok(count)
+    });
+    let _ = OUTCOME.set(outcome);
+  }
+}
+
+/// Logs what `init` did; it runs before logging is set up.
+pub fn log_outcome() {
+  #[cfg(target_os = "macos")]
+  match OUTCOME.get() {
+    Some(Ok(entries)) => tracing::info!(entries, "using login shell PATH"),
+    Some(Err(e)) => tracing::warn!(error = %e, "login shell PATH unavailable, using the inherited one"),
+    None => {}
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn extracts_marked_path() {
+    let output = format!("motd from .zshrc\n{MARKER}/opt/homebrew/bin:/usr/bin{MARKER}");
+    assert_eq!(extract_marked(&output), Some("/opt/homebrew/bin:/usr/bin"));
+    assert_eq!(extract_marked("no markers here"), None);
+    assert_eq!(extract_marked(&format!("{MARKER}{MARKER}")), None);
+  }
+
+  #[test]
+  fn merges_login_entries_first_without_duplicates() {
+    let paths = |entries: &[&str]| entries.iter().map(PathBuf::from).collect::<Vec<_>>();
+    let merged = merge_paths(
+      paths(&["/opt/homebrew/bin", "/usr/bin", ""]),
+      paths(&["/usr/bin", "/bin", "/opt/homebrew/bin"]),
+    );
+    assert_eq!(merged, paths(&["/opt/homebrew/bin", "/usr/bin", "/bin"]));
+  }
+}
@@ -0,0 +1,406 @@
+//! What a skill needs besides its own files, declared in the `SKILL.md`
+//! frontmatter:
+//!
+//! ```yaml
+//! dependencies:
+//!   skills: [review]
+//!   tools:
+//!     - jq
+//!   packages:
+//!     - "@acme/lint-rules"
+//! ```
+//!
+//! `skills` are other skills of the project, `tools` programs on PATH, and
+//! `packages` opkg packages. Importing a skill checks them and reports what's
+//! missing instead of leaving the skill to fail when it runs. opkg can't be
+//! asked which packages a project has, so packages count as missing until
+//! `installDependencies` installs them; those installs are recorded per
+//! project in `SKILL_PACKAGES_FILE`. Only this subset of YAML is read: a
+//! list per kind, in block or `[a, b]` form.
+
+use std::{
+  collections::{BTreeMap, BTreeSet},
+  fs,
+  path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  args, disk_space,
+  error::OpenWorkError,
+  network, opkg_queue, paths,
+  store::{read_state, write_state},
+  task_indicator,
+};
+
+pub const SKILL_PACKAGES_FILE: &str = "skill-packages.json";
+
+const SKILL_FILE: &str = "SKILL.md";
+
+/// opkg packages installed for skill dependencies, by project directory.
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct InstalledPackages {
+  projects: BTreeMap<String, BTreeSet<String>>,
+}
+
+#[derive(Debug, Serialize, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SkillDependencies {
+  pub skills: Vec<String>,
+  pub tools: Vec<String>,
+  pub packages: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyReport {
+  pub declared: SkillDependencies,
+  pub missing_skills: Vec<String>,
+  pub missing_tools: Vec<String>,
+  /// Declared packages OpenWork hasn't installed for the project.
+  pub missing_packages: Vec<String>,
+  pub installed_packages: Vec<String>,
+  /// Why packages failed to install.
+  pub errors: Vec<String>,
+}
+
+impl DependencyReport {
+  /// One line naming what's missing, or `None` when nothing is.
+  pub fn summary(&self) -> Option<String> {
+    let parts: Vec<String> = [
+      ("skills", &self.missing_skills),
+      ("tools", &self.missing_tools),
+      ("opkg packages", &self.missing_packages),
+    ]
+    .iter()
+    .filter(|(_, names)| !names.is_empty())
+    .map(|(kind, names)| format!("{kind} {}", names.join(", ")))
+    .collect();
+    (!parts.is_empty()).then(|| format!("Missing dependencies: {}", parts.join("; ")))
+  }
+}
+
+/// The YAML between the leading `---` lines, if any.
+fn frontmatter(text: &str) -> Option<&str> {
+  let rest = text.strip_prefix("---")?;
+  let rest = rest.strip_prefix("\r\n").or_else(|| rest.strip_prefix('\n'))?;
+  let end = rest.find("\n---").map_or(rest.len(), |end| end + 1);
+  Some(&rest[..end])
+}
+
+/// A YAML scalar: quotes removed, or a trailing ` # comment` when unquoted.
+fn scalar(raw: &str) -> String {
+  let raw = raw.trim();
+  for quote in ['"', '\''] {
+    if let Some(inner) = raw.strip_prefix(quote).and_then(|rest| rest.strip_suffix(quote)) {
+      return inner.to_string();
+    }
+  }
+  raw.split(" #").next().unwrap_or_default().trim().to_string()
+}
+
+fn flow_list(raw: &str) -> Result<Vec<String>, String> {
+  let inner = raw
+    .trim()
+    .strip_prefix('[')
+    .and_then(|rest| rest.strip_suffix(']'))
+    .ok_or_else(|| format!("Expected a list: {raw}"))?;
+  Ok(
+    inner
+      .split(',')
+      .map(scalar)
+      .filter(|item| !item.is_empty())
+      .collect(),
+  )
+}
+
+fn indent(line: &str) -> usize {
+  line.len() - line.trim_start().len()
+}
+
+/// The `dependencies` of a `SKILL.md`; empty without frontmatter or the key.
+fn parse(text: &str) -> Result<SkillDependencies, String> {
+  let mut deps = SkillDependencies::default();
+  let Some(yaml) = frontmatter(text) else {
+    return Ok(deps);
+  };
+  let mut lines = yaml
+    .lines()
+    .map(|line| line.trim_end())
+    .filter(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+    .skip_while(|line| !line.starts_with("dependencies:"));
+  let Some(header) = lines.next() else {
+    return Ok(deps);
+  };
+  if !scalar(&header["dependencies:".len()..]).is_empty() {
+    return Err("dependencies must list skills, tools or packages".to_string());
+  }
+
+  let mut current: Option<&mut Vec<String>> = None;
+  for line in lines.take_while(|line| indent(line) > 0) {
+    let trimmed = line.trim_start();
+    if let Some(item) = trimmed.strip_prefix("- ").or((trimmed == "-").then_some("")) {
+      let list = current
+        .as_mut()
+        .ok_or_else(|| format!("List item outside a kind: {trimmed}"))?;
+      list.push(scalar(item));
+      continue;
+    }
+    let (key, value) = trimmed
+      .split_once(':')
+      .ok_or_else(|| format!("Expected `kind: list`: {trimmed}"))?;
+    let list = match key.trim() {
+      "skills" => &mut deps.skills,
+      "tools" => &mut deps.tools,
+      "packages" => &mut deps.packages,
+      other => return Err(format!("Unknown dependency kind: {other}")),
+    };
+    if scalar(value).is_empty() {
+      current = Some(list);
+    } else {
+      list.extend(flow_list(value)?);
+      current = None;
+    }
+  }
+  Ok(deps)
+}
+
+fn dedup(names: Vec<String>) -> Vec<String> {
+  let mut unique: Vec<String> = Vec::new();
+  for name in names {
+    if !unique.contains(&name) {
+      unique.push(name);
+    }
+  }
+  unique
+}
+
+fn validate(deps: SkillDependencies) -> Result<SkillDependencies, OpenWorkError> {
+  let check = |names: Vec<String>, valid: &dyn Fn(&str) -> Result<String, OpenWorkError>| {
+    names
+      .iter()
+      .map(|name| valid(name))
+      .collect::<Result<Vec<_>, _>>()
+      .map(dedup)
+  };
+  Ok(SkillDependencies {
+    skills: check(deps.skills, &|name| {
+      paths::file_name_segment(name, "skill dependency")
+    })?,
+    tools: check(deps.tools, &|name| {
+      paths::file_name_segment(name, "tool dependency")
+    })?,
+    packages: check(deps.packages, &|name| {
+      args::package_spec(name, "package dependency")
+    })?,
+  })
+}
+
+/// The dependencies declared by the skill in `skill_dir`.
+pub fn read(skill_dir: &Path) -> Result<SkillDependencies, OpenWorkError> {
+  let path = skill_dir.join(SKILL_FILE);
+  let text = match fs::read_to_string(&path) {
+    Ok(text) => text,
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(SkillDependencies::default()),
+    Err(e) => return Err(format!("Failed to read {}: {e}", paths::display(&path)).into()),
+  };
+  let deps = parse(&text)
+    .map_err(|e| OpenWorkError::invalid_argument(format!("Invalid dependencies in {SKILL_FILE}: {e}")))?;
+  validate(deps)
+}
+
+/// Where `tool` is on PATH; on Windows also as `.exe`, `.cmd` or `.bat`.
+fn find_tool(tool: &str) -> Option<PathBuf> {
+  #[cfg(windows)]
+  {
+    if Path::new(tool).extension().is_none() {
+      return ["exe", "cmd", "bat"]
+        .iter()
+        .find_map(|ext| crate::resolve_in_path(&format!("{tool}.{ext}")));
+    }
+  }
+  crate::resolve_in_path(tool)
+}
+
+/// What of `deps` the project in `project_dir` lacks, without installing;
+/// `installed` are the packages already installed for it.
+pub fn check(project_dir: &Path, deps: &SkillDependencies, installed: &BTreeSet<String>) -> DependencyReport {
+  let skills_dir = project_dir.join(".opencode").join("skill");
+  DependencyReport {
+    declared: deps.clone(),
+    missing_skills: deps
+      .skills
+      .iter()
+      .filter(|name| !skills_dir.join(name).join(SKILL_FILE).is_file())
+      .cloned()
+      .collect(),
+    missing_tools: deps
+      .tools
+      .iter()
+      .filter(|tool| find_tool(tool).is_none())
+      .cloned()
+      .collect(),
+    missing_packages: deps
+      .packages
+      .iter()
+      .filter(|package| !installed.contains(*package))
+      .cloned()
+      .collect(),
+    ..DependencyReport::default()
+  }
+}
+
+/// Installs the missing packages of `report` with opkg, one at a time
+/// through `opkg_queue`. Failures are recorded in the report.
+fn install_packages(app: &tauri::AppHandle, project_dir: &Path, report: &mut DependencyReport) {
+  if report.missing_packages.is_empty() {
+    return;
+  }
+  let ready = disk_space::ensure(project_dir, disk_space::PACKAGE_INSTALL_BYTES)
+    .and_then(|_| network::require_online(app, "Installing skill dependencies"));
+  if let Err(e) = ready {
+    report.errors.push(e.to_string());
+    return;
+  }
+  let _task = task_indicator::begin(app, "opkg.install");
+  let project = project_dir.to_string_lossy().to_string();
+  for package in std::mem::take(&mut report.missing_packages) {
+    let result = opkg_queue::run(app, &project, "install", &package, || {
+      crate::run_opkg_install(&project, &package)
+    });
+    match result {
+      Ok(result) if result.ok => report.installed_packages.push(package),
+      Ok(result) => {
+        report
+          .errors
+          .push(format!("opkg install {package}: {}", result.stderr.trim()));
+        report.missing_packages.push(package);
+      }
+      Err(e) => {
+        report.errors.push(format!("opkg install {package}: {e}"));
+        report.missing_packages.push(package);
+      }
+    }
+  }
+  tracing::info!(
+    installed = report.installed_packages.len(),
+    failed = report.missing_packages.len(),
+    "installed skill dependencies"
+  );
+}
+
+/// Checks `deps` for the project, installing opkg packages when `install`
+/// is set.
+pub fn resolve(
+  app: &tauri::AppHandle,
+  project_dir: &Path,
+  deps: &SkillDependencies,
+  install: bool,
+) -> DependencyReport {
+  let project = project_dir.to_string_lossy().to_string();
+  let mut record: InstalledPackages = read_state(app, SKILL_PACKAGES_FILE).unwrap_or_default();
+  let installed = record.projects.entry(project).or_default();
+
+  let mut report = check(project_dir, deps, installed);
+  if install {
+    install_packages(app, project_dir, &mut report);
+  }
+  if !report.installed_packages.is_empty() {
+    installed.extend(report.installed_packages.iter().cloned());
+    if let Err(e) = write_state(app, SKILL_PACKAGES_FILE, &record) {
+      tracing::warn!(error = %e, "failed to record installed skill packages");
+    }
+  }
+  report
+}
+
+/// Checks the dependencies of the project's skill `name` again, e.g. after
+/// installing a missing tool; `install_dependencies` installs its opkg
+/// packages.
+#[tauri::command(async)]
+pub fn skill_dependencies_resolve(
+  app: tauri::AppHandle,
+  project_dir: String,
+  name: String,
+  install_dependencies: Option<bool>,
+) -> Result<DependencyReport, OpenWorkError> {
+  let project_dir = paths::allowed_dir(&project_dir, "projectDir")?;
+  let name = paths::file_name_segment(&name, "skill name")?;
+  let skill_dir = paths::resolve_within(&project_dir, &format!(".opencode/skill/{name}"))?;
+  let deps = read(&skill_dir)?;
+  Ok(resolve(
+    &app,
+    &project_dir,
+    &deps,
+    install_dependencies.unwrap_or(false),
+  ))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn strings(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+  }
+
+  #[test]
+  fn parses_dependency_lists() {
+    let text =
+      "---\nname: lint\ndependencies:\n  skills: [review, 'format']\n  tools:\n    - jq # JSON\n    \
+                - \"rg\"\n  packages:\n    - \"@acme/rules\"\ndescription: Lints\n---\n\n# Lint\n";
+    let deps = parse(text).unwrap();
+    assert_eq!(deps.skills, strings(&["review", "format"]));
+    assert_eq!(deps.tools, strings(&["jq", "rg"]));
+    assert_eq!(deps.packages, strings(&["@acme/rules"]));
+
+    assert_eq!(parse("# No frontmatter").unwrap(), SkillDependencies::default());
+    assert_eq!(
+      parse("---\nname: x\n---\n").unwrap(),
+      SkillDependencies::default()
+    );
+  }
+
+  #[test]
+  fn rejects_malformed_dependencies() {
+    assert!(parse("---\ndependencies: [jq]\n---\n").is_err());
+    assert!(parse("---\ndependencies:\n  binaries: [jq]\n---\n").is_err());
+    assert!(parse("---\ndependencies:\n  - jq\n---\n").is_err());
+    assert!(parse("---\ndependencies:\n  tools: jq\n---\n").is_err());
+    let deps = SkillDependencies {
+      tools: strings(&["../bin/jq"]),
+      ..SkillDependencies::default()
+    };
+    assert!(validate(deps).is_err());
+  }
+
+  #[test]
+  fn reports_missing_dependencies() {
+    let project = std::env::temp_dir().join(format!("openwork-skill-deps-{}", std::process::id()));
+    fs::create_dir_all(project.join(".opencode/skill/review")).unwrap();
+    fs::write(project.join(".opencode/skill/review/SKILL.md"), "# Review").unwrap();
+
+    let deps = SkillDependencies {
+      skills: strings(&["review", "format"]),
+      tools: strings(&["openwork-missing-tool"]),
+      packages: strings(&["@acme/rules"]),
+    };
+    let report = check(&project, &deps, &BTreeSet::new());
+    assert_eq!(report.missing_skills, strings(&["format"]));
+    assert_eq!(report.missing_tools, strings(&["openwork-missing-tool"]));
+    assert_eq!(report.missing_packages, strings(&["@acme/rules"]));
+    assert_eq!(
+      report.summary().unwrap(),
+      "Missing dependencies: skills format; tools openwork-missing-tool; opkg packages @acme/rules"
+    );
+    let installed = BTreeSet::from(["@acme/rules".to_string()]);
+    assert!(check(&project, &deps, &installed).missing_packages.is_empty());
+    assert_eq!(
+      check(&project, &SkillDependencies::default(), &installed).summary(),
+      None
+    );
+    fs::remove_dir_all(&project).unwrap();
+  }
+}
@@ -0,0 +1,258 @@
+//! Skill imports from a link, for "paste a link, get a skill".
+//!
+//! A link is either a `.zip`, `.tar` or `.tar.gz` URL, or a GitHub page: a
+//! repository, a folder (`/tree/<ref>/<path>`) or a file in the skill's
+//! folder (`/blob/<ref>/<path>/SKILL.md`), which are fetched as the
+//! repository's tarball. Only HTTPS is accepted, the download is capped at
+//! `MAX_DOWNLOAD_BYTES`, and the archive type is read from the file's first
+//! bytes rather than trusted from the URL or the server. Unpacking then goes
+//! through `archive::extract` with its usual limits.
+
+use std::{
+  fs::{self, File},
+  io::Read,
+  path::{Path, PathBuf},
+};
+
+use crate::{
+  archive::{self, ArchiveFormat},
+  download::{self, Download, DownloadProgress},
+  error::OpenWorkError,
+};
+
+/// Skills are text and a few assets; anything bigger is most likely the
+/// wrong link.
+pub const MAX_DOWNLOAD_BYTES: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkillSource {
+  /// What to download.
+  pub archive_url: String,
+  /// The skill's folder inside the archive's top-level folder, for links to
+  /// a folder in a repository.
+  pub subdir: Option<PathBuf>,
+  /// The name the link suggests (the repository or folder), used over the
+  /// archive's top-level folder, which GitHub names `<repo>-<ref>`.
+  pub name: Option<String>,
+  /// The name for a skill at the archive root: the file name without its
+  /// archive suffix.
+  pub archive_name: String,
+}
+
+/// Reads a pasted link.
+pub fn parse(raw: &str) -> Result<SkillSource, OpenWorkError> {
+  let url = raw.trim();
+  let Some(rest) = url.strip_prefix("https://") else {
+    return Err(OpenWorkError::invalid_argument(format!(
+      "Only https:// links can be imported: {url}"
+    )));
+  };
+  let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+  let path = path.split(['?', '#']).next().unwrap_or_default();
+  if host.is_empty() || host.contains('@') {
+    return Err(OpenWorkError::invalid_argument(format!("Invalid link: {url}")));
+  }
+
+  let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+  let file_name = segments.last().copied().unwrap_or_default();
+  let has_archive_suffix = ArchiveFormat::detect(Path::new(file_name)).is_some();
+  let archive_name = if has_archive_suffix {
+    archive::strip_archive_suffix(Path::new(file_name))
+  } else {
+    "skill".to_string()
+  };
+
+  let github = matches!(
+    host.to_ascii_lowercase().as_str(),
+    "github.com" | "www.github.com"
+  );
+  if !github || has_archive_suffix || segments.len() < 2 {
+    return Ok(SkillSource {
+      archive_url: url.to_string(),
+      subdir: None,
+      name: None,
+      archive_name,
+    });
+  }
+
+  let owner = segments[0];
+  let repo = segments[1].trim_end_matches(".git");
+  let (git_ref, subdir) = match segments.get(2..) {
+    None | Some([]) => ("HEAD", &[][..]),
+    Some([kind, git_ref, rest @ ..]) if *kind == "tree" || *kind == "blob" => {
+      // A file link points into the skill's folder.
+      let rest = if *kind == "blob" {
+        &rest[..rest.len().saturating_sub(1)]
+      } else {
+        rest
+      };
+      (*git_ref, rest)
+    }
+    Some(_) => {
+      return Err(OpenWorkError::invalid_argument(format!(
+        "Not a link to a GitHub repository or folder: {url}"
+      )))
+    }
+  };
+  if subdir.iter().any(|segment| *segment == "..") {
+    return Err(OpenWorkError::invalid_argument(format!("Invalid link: {url}")));
+  }
+
+  Ok(SkillSource {
+    archive_url: format!("https://github.com/{owner}/{repo}/archive/{git_ref}.tar.gz"),
+    subdir: (!subdir.is_empty()).then(|| subdir.iter().collect()),
+    name: Some(subdir.last().copied().unwrap_or(repo).to_string()),
+    archive_name: repo.to_string(),
+  })
+}
+
+/// The archive type from a file's first bytes.
+fn sniff(head: &[u8]) -> Option<ArchiveFormat> {
+  if head.starts_with(b"PK\x03\x04") || head.starts_with(b"PK\x05\x06") {
+    Some(ArchiveFormat::Zip)
+  } else if head.starts_with(&[0x1f, 0x8b]) {
+    Some(ArchiveFormat::TarGz)
+  } else if head.get(257..262) == Some(b"ustar") {
+    Some(ArchiveFormat::Tar)
+  } else {
+    None
+  }
+}
+
+fn suffix(format: ArchiveFormat) -> &'static str {
+  match format {
+    ArchiveFormat::Zip => "zip",
+    ArchiveFormat::Tar => "tar",
+    ArchiveFormat::TarGz => "tar.gz",
+  }
+}
+
+/// Downloads `source` into `work_dir` and returns the archive, named with
+/// the suffix `archive::extract` expects.
+pub fn download(
+  source: &SkillSource,
+  work_dir: &Path,
+  on_progress: impl FnMut(&DownloadProgress),
+) -> Result<PathBuf, OpenWorkError> {
+  let dest = work_dir.join("download");
+  download::download_to_file(
+    &Download {
+      url: &source.archive_url,
+      dest: &dest,
+      sha256: None,
+      max_bytes: Some(MAX_DOWNLOAD_BYTES),
+      cancel: None,
+    },
+    on_progress,
+  )?;
+
+  let mut head = [0u8; 512];
+  let read = File::open(&dest)
+    .and_then(|mut file| file.read(&mut head))
+    .map_err(|e| format!("Failed to read {}: {e}", dest.display()))?;
+  let Some(format) = sniff(&head[..read]) else {
+    let page = head[..read]
+      .iter()
+      .find(|byte| !byte.is_ascii_whitespace())
+      .is_some_and(|byte| *byte == b'<');
+    return Err(OpenWorkError::invalid_argument(if page {
+      format!("{} is a web page, not an archive", source.archive_url)
+    } else {
+      format!("{} is not a .zip, .tar or .tar.gz archive", source.archive_url)
+    }));
+  };
+  let archive = work_dir.join(format!("download.{}", suffix(format)));
+  fs::rename(&dest, &archive).map_err(|e| format!("Failed to write {}: {e}", archive.display()))?;
+  Ok(archive)
+}
+
+/// `subdir` inside the unpacked archive's only top-level folder, which must
+/// hold a `SKILL.md`.
+pub fn subdir_skill(unpacked: &Path, subdir: &Path) -> Result<PathBuf, OpenWorkError> {
+  let top: Vec<PathBuf> = fs::read_dir(unpacked)
+    .map_err(|e| format!("Failed to read dir {}: {e}", unpacked.display()))?
+    .filter_map(Result::ok)
+    .map(|entry| entry.path())
+    .filter(|path| path.is_dir())
+    .collect();
+  let [root] = top.as_slice() else {
+    return Err(OpenWorkError::invalid_argument(
+      "The downloaded archive is not a single repository folder",
+    ));
+  };
+  let skill = root.join(subdir);
+  if !skill.join("SKILL.md").is_file() {
+    return Err(OpenWorkError::invalid_argument(format!(
+      "{} in the repository does not contain a SKILL.md",
+      subdir.display()
+    )));
+  }
+  Ok(skill)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn reads_archive_links() {
+    let source = parse(" https://example.com/skills/review.tar.gz?download=1 ").unwrap();
+    assert_eq!(
+      source.archive_url,
+      "https://example.com/skills/review.tar.gz?download=1"
+    );
+    assert_eq!((source.subdir, source.name), (None, None));
+    assert_eq!(source.archive_name, "review");
+    assert!(parse("http://example.com/review.zip").is_err());
+    assert!(parse("file:///tmp/review.zip").is_err());
+  }
+
+  #[test]
+  fn reads_github_links() {
+    let repo = parse("https://github.com/acme/review-skill").unwrap();
+    assert_eq!(
+      repo.archive_url,
+      "https://github.com/acme/review-skill/archive/HEAD.tar.gz"
+    );
+    assert_eq!(repo.subdir, None);
+    assert_eq!(repo.name.as_deref(), Some("review-skill"));
+
+    let folder = parse("https://github.com/acme/skills/tree/main/skills/review").unwrap();
+    assert_eq!(
+      folder.archive_url,
+      "https://github.com/acme/skills/archive/main.tar.gz"
+    );
+    assert_eq!(folder.subdir, Some(PathBuf::from("skills/review")));
+    assert_eq!(folder.name.as_deref(), Some("review"));
+
+    let file = parse("https://github.com/acme/skills/blob/v1/review/SKILL.md").unwrap();
+    assert_eq!(file.subdir, Some(PathBuf::from("review")));
+
+    assert!(parse("https://github.com/acme/skills/issues/3").is_err());
+    assert!(parse("https://github.com/acme/skills/tree/main/../x").is_err());
+  }
+
+  #[test]
+  fn sniffs_archive_types() {
+    assert_eq!(sniff(b"PK\x03\x04rest"), Some(ArchiveFormat::Zip));
+    assert_eq!(sniff(&[0x1f, 0x8b, 8, 0]), Some(ArchiveFormat::TarGz));
+    let mut tar = vec![0u8; 512];
+    tar[257..262].copy_from_slice(b"ustar");
+    assert_eq!(sniff(&tar), Some(ArchiveFormat::Tar));
+    assert_eq!(sniff(b"<!doctype html>"), None);
+  }
+
+  #[test]
+  fn finds_the_skill_folder_in_a_repository() {
+    let unpacked = std::env::temp_dir().join(format!("openwork-skill-url-{}", std::process::id()));
+    let skill = unpacked.join("skills-main/skills/review");
+    fs::create_dir_all(&skill).unwrap();
+    fs::write(skill.join("SKILL.md"), "# Review").unwrap();
+    assert_eq!(
+      subdir_skill(&unpacked, Path::new("skills/review")).unwrap(),
+      skill
+    );
+    assert!(subdir_skill(&unpacked, Path::new("skills")).is_err());
+    fs::remove_dir_all(&unpacked).unwrap();
+  }
+}
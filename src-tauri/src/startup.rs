@@ -0,0 +1,179 @@
+//! Launch at login and background mode.
+//!
+//! In background mode the login launch starts hidden in the tray with the
+//! engine already running for the pinned project, and closing the window
+//! hides it instead of quitting, so the agent is ready when the user opens it.
+
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{
+  menu::{Menu, MenuItem},
+  tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
+  AppHandle, Manager, State, Window, WindowEvent,
+};
+use tauri_plugin_autostart::{MacosLauncher, ManagerExt};
+
+use crate::{
+  error::OpenWorkError,
+  instance::{self, MAIN_WINDOW},
+  store::{read_state, write_state},
+  EngineManager,
+};
+
+pub const STARTUP_FILE: &str = "startup.json";
+
+/// Passed by the login item so a login launch can be told apart from one the
+/// user started.
+pub const BACKGROUND_ARG: &str = "--background";
+
+const TRAY_ID: &str = "main";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StartupSettings {
+  pub launch_at_login: bool,
+  /// Start hidden in the tray and keep running when the window is closed.
+  pub background: bool,
+  /// Project whose engine is started in the background at login.
+  pub pinned_project: Option<String>,
+}
+
+#[derive(Default)]
+pub struct StartupManager {
+  settings: Mutex<Option<StartupSettings>>,
+}
+
+impl StartupManager {
+  fn settings(&self, app: &AppHandle) -> StartupSettings {
+    let mut settings = self.settings.lock().expect("startup mutex poisoned");
+    settings
+      .get_or_insert_with(|| read_state(app, STARTUP_FILE).unwrap_or_default())
+      .clone()
+  }
+}
+
+pub fn plugin() -> tauri::plugin::TauriPlugin<tauri::Wry> {
+  tauri_plugin_autostart::init(MacosLauncher::LaunchAgent, Some(vec![BACKGROUND_ARG]))
+}
+
+fn build_tray(app: &AppHandle) -> tauri::Result<()> {
+  if app.tray_by_id(TRAY_ID).is_some() {
+    return Ok(());
+  }
+
+  let show = MenuItem::with_id(app, "show", "Show OpenWork", true, None::<&str>)?;
+  let quit = MenuItem::with_id(app, "quit", "Quit OpenWork", true, None::<&str>)?;
+  let menu = Menu::with_items(app, &[&show, &quit])?;
+
+  let mut tray = TrayIconBuilder::with_id(TRAY_ID)
+    .tooltip("OpenWork")
+    .menu(&menu)
+    .on_menu_event(|app, event| match event.id.as_ref() {
+      "show" => instance::focus_main_window(app),
+      "quit" => app.exit(0),
+      _ => {}
+    })
+    .on_tray_icon_event(|tray, event| {
+      if let TrayIconEvent::Click {
+        button: MouseButton::Left,
+        button_state: MouseButtonState::Up,
+        ..
+      } = event
+      {
+        instance::focus_main_window(tray.app_handle());
+      }
+    });
+  if let Some(icon) = app.default_window_icon() {
+    tray = tray.icon(icon.clone());
+  }
+  tray.build(app)?;
+  Ok(())
+}
+
+fn sync_tray(app: &AppHandle, settings: &StartupSettings) {
+  if !settings.background {
+    let _ = app.remove_tray_by_id(TRAY_ID);
+    return;
+  }
+  if let Err(e) = build_tray(app) {
+    tracing::warn!(error = %e, "failed to create tray icon");
+  }
+}
+
+fn warm_engine(app: &AppHandle, project_dir: &str) {
+  let manager = app.state::<EngineManager>();
+  match crate::start_engine(app, &manager, MAIN_WINDOW, project_dir, false) {
+    Ok(_) => tracing::info!(project_dir, "engine started in background"),
+    Err(e) => tracing::warn!(project_dir, error = %e, "failed to start engine in background"),
+  }
+}
+
+pub fn init(app: &AppHandle) {
+  app.manage(StartupManager::default());
+  let settings = app.state::<StartupManager>().settings(app);
+  sync_tray(app, &settings);
+
+  let login_launch = std::env::args().any(|arg| arg == BACKGROUND_ARG);
+  if !login_launch || !settings.background {
+    return;
+  }
+
+  if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+    let _ = window.hide();
+  }
+  if let Some(project_dir) = &settings.pinned_project {
+    warm_engine(app, project_dir);
+  }
+}
+
+/// Hides the window instead of quitting while background mode is on.
+pub fn on_window_event(window: &Window, event: &WindowEvent) {
+  let WindowEvent::CloseRequested { api, .. } = event else {
+    return;
+  };
+  if window.label() != MAIN_WINDOW {
+    return;
+  }
+  let app = window.app_handle();
+  if app.state::<StartupManager>().settings(app).background {
+    api.prevent_close();
+    let _ = window.hide();
+  }
+}
+
+#[tauri::command]
+pub fn startup_settings_get(app: AppHandle, manager: State<StartupManager>) -> StartupSettings {
+  let mut settings = manager.settings(&app);
+  // The login item can be removed from system settings behind our back.
+  if let Ok(enabled) = app.autolaunch().is_enabled() {
+    settings.launch_at_login = enabled;
+  }
+  settings
+}
+
+#[tauri::command]
+pub fn startup_settings_set(
+  app: AppHandle,
+  manager: State<StartupManager>,
+  mut settings: StartupSettings,
+) -> Result<StartupSettings, OpenWorkError> {
+  settings.pinned_project = match settings.pinned_project.as_deref().map(str::trim) {
+    Some(dir) if !dir.is_empty() => Some(crate::require_project_dir(dir)?),
+    _ => None,
+  };
+
+  let autolaunch = app.autolaunch();
+  let enabled = autolaunch.is_enabled().unwrap_or(false);
+  let result = match (settings.launch_at_login, enabled) {
+    (true, false) => autolaunch.enable(),
+    (false, true) => autolaunch.disable(),
+    _ => Ok(()),
+  };
+  result.map_err(|e| format!("Failed to update launch at login: {e}"))?;
+
+  write_state(&app, STARTUP_FILE, &settings)?;
+  sync_tray(&app, &settings);
+  *manager.settings.lock().expect("startup mutex poisoned") = Some(settings.clone());
+  Ok(settings)
+}
@@ -0,0 +1,57 @@
+use std::{fs, path::PathBuf};
+
+use serde::{de::DeserializeOwned, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::{
+  error::{ErrorCode, OpenWorkError},
+  portable, private_files,
+};
+
+/// Location of a JSON state file inside the app data directory.
+pub fn app_state_path(app: &AppHandle, file_name: &str) -> Result<PathBuf, String> {
+  if let Some(dir) = portable::dir("data") {
+    return Ok(dir.join(file_name));
+  }
+  let dir = app
+    .path()
+    .app_data_dir()
+    .map_err(|e| format!("Failed to resolve app data dir: {e}"))?;
+  Ok(dir.join(file_name))
+}
+
+/// Reads a JSON state file, falling back to the default when it does not exist yet.
+/// A file that exists but does not parse is `CONFIG_INVALID`.
+pub fn read_state<T: DeserializeOwned + Default>(
+  app: &AppHandle,
+  file_name: &str,
+) -> Result<T, OpenWorkError> {
+  let path = app_state_path(app, file_name)?;
+  if !path.exists() {
+    return Ok(T::default());
+  }
+
+  let content =
+    fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+  serde_json::from_str(&content).map_err(|e| {
+    OpenWorkError::new(ErrorCode::ConfigInvalid, format!("Failed to parse {}: {e}", path.display()))
+      .with_details(serde_json::json!({ "path": path.to_string_lossy() }))
+  })
+}
+
+/// Writes a JSON state file via a temp file so a crash never leaves it half-written.
+pub fn write_state<T: Serialize>(app: &AppHandle, file_name: &str, value: &T) -> Result<(), String> {
+  let path = app_state_path(app, file_name)?;
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent)
+      .map_err(|e| format!("Failed to create state dir {}: {e}", parent.display()))?;
+  }
+
+  let content = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+  let tmp = path.with_extension("json.tmp");
+  // Some state files hold tokens, so none is readable by other users.
+  private_files::write(&tmp, content)?;
+  fs::rename(&tmp, &path).map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+
+  Ok(())
+}
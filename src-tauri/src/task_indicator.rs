@@ -0,0 +1,96 @@
+//! Dock/taskbar feedback for long installs.
+//!
+//! While any install runs, the main window's icon shows an indeterminate
+//! progress bar (taskbar on Windows, dock on macOS). Installs that finish while
+//! the window is in the background add to a badge count and request attention;
+//! focusing the window clears it.
+
+use std::sync::Mutex;
+
+use tauri::{
+  window::{ProgressBarState, ProgressBarStatus},
+  AppHandle, Manager, UserAttentionType, WebviewWindow, Window, WindowEvent,
+};
+
+use crate::instance::MAIN_WINDOW;
+
+#[derive(Default)]
+pub struct TaskIndicator {
+  inner: Mutex<IndicatorState>,
+}
+
+#[derive(Default)]
+struct IndicatorState {
+  running: usize,
+  finished_unseen: i64,
+}
+
+/// Marks a long task as running until dropped.
+pub struct TaskGuard {
+  app: AppHandle,
+  name: &'static str,
+}
+
+fn main_window(app: &AppHandle) -> Option<WebviewWindow> {
+  app.get_webview_window(MAIN_WINDOW)
+}
+
+fn apply(window: &WebviewWindow, state: &IndicatorState) {
+  let status = if state.running > 0 {
+    ProgressBarStatus::Indeterminate
+  } else {
+    ProgressBarStatus::None
+  };
+  let _ = window.set_progress_bar(ProgressBarState {
+    status: Some(status),
+    progress: None,
+  });
+  // Badges are macOS (and some Linux docks) only; elsewhere this is a no-op.
+  let _ = window.set_badge_count((state.finished_unseen > 0).then_some(state.finished_unseen));
+}
+
+pub fn begin(app: &AppHandle, name: &'static str) -> TaskGuard {
+  let indicator = app.state::<TaskIndicator>();
+  let mut state = indicator.inner.lock().expect("task indicator mutex poisoned");
+  state.running += 1;
+  if let Some(window) = main_window(app) {
+    apply(&window, &state);
+  }
+  tracing::debug!(task = name, running = state.running, "long task started");
+  TaskGuard { app: app.clone(), name }
+}
+
+impl Drop for TaskGuard {
+  fn drop(&mut self) {
+    let indicator = self.app.state::<TaskIndicator>();
+    let mut state = indicator.inner.lock().expect("task indicator mutex poisoned");
+    state.running = state.running.saturating_sub(1);
+
+    let Some(window) = main_window(&self.app) else {
+      return;
+    };
+    if !window.is_focused().unwrap_or(false) {
+      state.finished_unseen += 1;
+      let _ = window.request_user_attention(Some(UserAttentionType::Informational));
+    }
+    apply(&window, &state);
+    tracing::debug!(task = self.name, running = state.running, "long task finished");
+  }
+}
+
+/// Clears the badge once the user looks at the main window.
+pub fn on_window_event(window: &Window, event: &WindowEvent) {
+  if !matches!(event, WindowEvent::Focused(true)) || window.label() != MAIN_WINDOW {
+    return;
+  }
+  let app = window.app_handle();
+  let indicator = app.state::<TaskIndicator>();
+  let mut state = indicator.inner.lock().expect("task indicator mutex poisoned");
+  if state.finished_unseen == 0 {
+    return;
+  }
+  state.finished_unseen = 0;
+  if let Some(window) = main_window(app) {
+    apply(&window, &state);
+  }
+}
@@ -0,0 +1,432 @@
+//! Project scripts the user can run from the app.
+//!
+//! `tasks_detect` lists package.json scripts, Makefile targets, justfile
+//! recipes and the usual cargo commands. `task_run` starts one in the project
+//! directory and streams its output line by line as `task://output` events,
+//! then `task://exit` with the status; `task_stop` kills it.
+
+use std::{
+  collections::HashMap,
+  fs,
+  io::{BufRead, BufReader, Read},
+  path::Path,
+  process::{Child, Stdio},
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+  },
+  thread,
+  time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::{
+  env_policy,
+  error::{ErrorCode, OpenWorkError},
+  exec, paths, redact, task_indicator,
+  webhooks::{self, WebhookEvent},
+};
+
+pub const TASK_OUTPUT_EVENT: &str = "task://output";
+pub const TASK_EXIT_EVENT: &str = "task://exit";
+
+const MAKEFILES: &[&str] = &["GNUmakefile", "makefile", "Makefile"];
+const JUSTFILES: &[&str] = &["justfile", "Justfile", ".justfile"];
+/// How often a running task is checked for exit.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectTask {
+  /// `<source>:<name>`, e.g. `npm:build` or `make:test`.
+  pub id: String,
+  pub source: &'static str,
+  pub name: String,
+  pub command: Vec<String>,
+  pub description: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskRun {
+  pub run_id: u64,
+  pub task_id: String,
+  pub project_dir: String,
+  pub command: Vec<String>,
+  pub pid: u32,
+  pub started_at: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct TaskOutput<'a> {
+  run_id: u64,
+  stream: &'static str,
+  line: &'a str,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct TaskExit<'a> {
+  run_id: u64,
+  task_id: &'a str,
+  code: Option<i32>,
+  duration_ms: u64,
+}
+
+struct RunningTask {
+  run: TaskRun,
+  child: Arc<Mutex<Child>>,
+}
+
+#[derive(Default)]
+pub struct TaskRunner {
+  runs: Mutex<HashMap<u64, RunningTask>>,
+  next_id: AtomicU64,
+}
+
+fn now_ms() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_millis() as u64)
+    .unwrap_or(0)
+}
+
+fn task(source: &'static str, name: &str, command: &[&str], description: Option<String>) -> ProjectTask {
+  ProjectTask {
+    id: format!("{source}:{name}"),
+    source,
+    name: name.to_string(),
+    command: command.iter().map(|s| s.to_string()).collect(),
+    description,
+  }
+}
+
+/// The package manager a JS project uses, from its lockfile.
+fn package_manager(dir: &Path) -> &'static str {
+  [
+    ("pnpm-lock.yaml", "pnpm"),
+    ("yarn.lock", "yarn"),
+    ("bun.lockb", "bun"),
+    ("bun.lock", "bun"),
+  ]
+  .iter()
+  .find(|(lockfile, _)| dir.join(lockfile).is_file())
+  .map_or("npm", |(_, manager)| manager)
+}
+
+fn package_scripts(package_json: &str, manager: &'static str) -> Vec<ProjectTask> {
+  let Ok(package) = serde_json::from_str::<Value>(package_json) else {
+    return Vec::new();
+  };
+  let Some(scripts) = package.get("scripts").and_then(Value::as_object) else {
+    return Vec::new();
+  };
+  scripts
+    .iter()
+    .map(|(name, script)| {
+      task(
+        manager,
+        name,
+        &[manager, "run", name],
+        script.as_str().map(str::to_string),
+      )
+    })
+    .collect()
+}
+
+/// Targets defined in a Makefile, with `## text` after the target as the
+/// description. Pattern rules, special targets and variables are skipped.
+fn makefile_targets(makefile: &str) -> Vec<ProjectTask> {
+  let mut targets: Vec<ProjectTask> = Vec::new();
+  for line in makefile.lines() {
+    if line.starts_with(['\t', ' ', '#', '.']) {
+      continue;
+    }
+    let (line, description) = match line.split_once("##") {
+      Some((rule, text)) => (
+        rule,
+        Some(text.trim().to_string()).filter(|text| !text.is_empty()),
+      ),
+      None => (line, None),
+    };
+    let Some((names, rest)) = line.split_once(':') else {
+      continue;
+    };
+    // `a := b`, `a ::= b` and `a = b` are assignments.
+    if rest.starts_with('=') || rest.starts_with(":=") || names.contains('=') {
+      continue;
+    }
+    for name in names.split_whitespace() {
+      let valid = name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/'));
+      if valid && !targets.iter().any(|target| target.name == name) {
+        targets.push(task("make", name, &["make", name], description.clone()));
+      }
+    }
+  }
+  targets
+}
+
+/// Recipes in a justfile, described by the comment line above them. Private
+/// recipes (leading `_` or `[private]`) are skipped.
+fn justfile_recipes(justfile: &str) -> Vec<ProjectTask> {
+  let mut recipes = Vec::new();
+  let mut comment: Option<String> = None;
+  let mut private = false;
+  for line in justfile.lines() {
+    let trimmed = line.trim();
+    if let Some(text) = trimmed.strip_prefix('#') {
+      if !line.starts_with([' ', '\t']) && !text.starts_with('!') {
+        comment = Some(text.trim().to_string());
+      }
+      continue;
+    }
+    if trimmed.starts_with('[') {
+      private |= trimmed.contains("private");
+      continue;
+    }
+    let header = !line.starts_with([' ', '\t']) && !trimmed.is_empty();
+    let recipe = header
+      .then(|| trimmed.trim_start_matches('@'))
+      .and_then(|rest| rest.split_once(':'))
+      .filter(|(_, after)| !after.starts_with('='))
+      .and_then(|(head, _)| head.split_whitespace().next())
+      .filter(|name| {
+        !["set", "alias", "export", "import", "mod"].contains(name)
+          && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-'))
+      });
+    if let Some(name) = recipe {
+      if !private && !name.starts_with('_') {
+        recipes.push(task("just", name, &["just", name], comment.clone()));
+      }
+    }
+    if header || trimmed.is_empty() {
+      comment = None;
+      private = false;
+    }
+  }
+  recipes
+}
+
+fn cargo_tasks(dir: &Path) -> Vec<ProjectTask> {
+  let mut commands = vec!["build", "check", "test", "clippy"];
+  if dir.join("src").join("main.rs").is_file() {
+    commands.push("run");
+  }
+  commands
+    .into_iter()
+    .map(|name| task("cargo", name, &["cargo", name], None))
+    .collect()
+}
+
+fn detect(dir: &Path) -> Vec<ProjectTask> {
+  let read = |name: &str| fs::read_to_string(dir.join(name)).ok();
+  let mut tasks = Vec::new();
+  if let Some(package_json) = read("package.json") {
+    tasks.extend(package_scripts(&package_json, package_manager(dir)));
+  }
+  if let Some(makefile) = MAKEFILES.iter().find_map(|name| read(name)) {
+    tasks.extend(makefile_targets(&makefile));
+  }
+  if let Some(justfile) = JUSTFILES.iter().find_map(|name| read(name)) {
+    tasks.extend(justfile_recipes(&justfile));
+  }
+  if dir.join("Cargo.toml").is_file() {
+    tasks.extend(cargo_tasks(dir));
+  }
+  tasks
+}
+
+#[tauri::command]
+pub fn tasks_detect(project_dir: String) -> Result<Vec<ProjectTask>, OpenWorkError> {
+  let project_dir = paths::allowed_dir(&project_dir, "projectDir")?;
+  Ok(detect(&project_dir))
+}
+
+fn forward_lines(app: AppHandle, run_id: u64, stream: &'static str, reader: impl Read + Send + 'static) {
+  thread::spawn(move || {
+    for line in BufReader::new(reader).lines().map_while(Result::ok) {
+      let line = redact::redact(&line);
+      let _ = app.emit(
+        TASK_OUTPUT_EVENT,
+        TaskOutput {
+          run_id,
+          stream,
+          line: &line,
+        },
+      );
+    }
+  });
+}
+
+/// Runs a detected task; its output and exit arrive as events.
+#[tauri::command]
+pub fn task_run(
+  app: AppHandle,
+  runner: State<TaskRunner>,
+  project_dir: String,
+  task_id: String,
+) -> Result<TaskRun, OpenWorkError> {
+  let project_dir = paths::allowed_dir(&project_dir, "projectDir")?;
+  let task = detect(&project_dir)
+    .into_iter()
+    .find(|task| task.id == task_id)
+    .ok_or_else(|| {
+      OpenWorkError::new(
+        ErrorCode::NotFound,
+        format!("No task '{task_id}' in this project"),
+      )
+    })?;
+  let (program, args) = task.command.split_first().expect("tasks have a command");
+
+  let mut command = exec::command(program);
+  env_policy::apply(&mut command, env_policy::EnvTarget::Engine);
+  command
+    .args(args)
+    .current_dir(&project_dir)
+    .stdin(Stdio::null())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped());
+  let mut child = exec::spawn(&mut command).map_err(|e| {
+    let code = if e.kind() == std::io::ErrorKind::NotFound {
+      ErrorCode::ToolNotFound
+    } else {
+      ErrorCode::Io
+    };
+    OpenWorkError::new(code, format!("Failed to start {program}: {e}"))
+  })?;
+
+  let run_id = runner.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+  if let Some(stdout) = child.stdout.take() {
+    forward_lines(app.clone(), run_id, "stdout", stdout);
+  }
+  if let Some(stderr) = child.stderr.take() {
+    forward_lines(app.clone(), run_id, "stderr", stderr);
+  }
+  let run = TaskRun {
+    run_id,
+    task_id: task.id.clone(),
+    project_dir: project_dir.to_string_lossy().to_string(),
+    command: task.command.clone(),
+    pid: child.id(),
+    started_at: now_ms(),
+  };
+  tracing::info!(run_id, task = %task.id, pid = run.pid, "task started");
+  let child = Arc::new(Mutex::new(child));
+  runner.runs.lock().expect("task runner mutex poisoned").insert(
+    run_id,
+    RunningTask {
+      run: run.clone(),
+      child: child.clone(),
+    },
+  );
+
+  let project = run.project_dir.clone();
+  thread::spawn(move || {
+    let _task = task_indicator::begin(&app, "task.run");
+    let started = Instant::now();
+    let code = loop {
+      match child.lock().expect("task child mutex poisoned").try_wait() {
+        Ok(Some(status)) => break status.code(),
+        Ok(None) => {}
+        Err(_) => break None,
+      }
+      thread::sleep(POLL_INTERVAL);
+    };
+    let duration_ms = started.elapsed().as_millis() as u64;
+    tracing::info!(run_id, code = ?code, duration_ms, "task finished");
+    app
+      .state::<TaskRunner>()
+      .runs
+      .lock()
+      .expect("task runner mutex poisoned")
+      .remove(&run_id);
+    let task_id = task.id;
+    if webhooks::is_long_task(duration_ms) {
+      let outcome = match code {
+        Some(0) => "finished".to_string(),
+        Some(code) => format!("failed with exit code {code}"),
+        None => "was stopped".to_string(),
+      };
+      webhooks::send(
+        WebhookEvent::TaskFinished,
+        &format!(
+          "Task {task_id} in {project} {outcome} after {}s.",
+          duration_ms / 1000
+        ),
+        serde_json::json!({
+          "taskId": task_id,
+          "projectDir": project,
+          "code": code,
+          "durationMs": duration_ms,
+        }),
+      );
+    }
+    let _ = app.emit(
+      TASK_EXIT_EVENT,
+      TaskExit {
+        run_id,
+        task_id: &task_id,
+        code,
+        duration_ms,
+      },
+    );
+    crate::telemetry::record_outcome("task.run", code == Some(0));
+  });
+  Ok(run)
+}
+
+/// Kills a running task; `task://exit` follows.
+#[tauri::command]
+pub fn task_stop(runner: State<TaskRunner>, run_id: u64) -> Result<(), OpenWorkError> {
+  let child = runner
+    .runs
+    .lock()
+    .expect("task runner mutex poisoned")
+    .get(&run_id)
+    .map(|running| running.child.clone())
+    .ok_or_else(|| OpenWorkError::new(ErrorCode::NotFound, format!("No running task {run_id}")))?;
+  let result = child.lock().expect("task child mutex poisoned").kill();
+  result.map_err(|e| OpenWorkError::new(ErrorCode::Io, format!("Failed to stop the task: {e}")))
+}
+
+#[tauri::command]
+pub fn tasks_running(runner: State<TaskRunner>) -> Vec<TaskRun> {
+  let runs = runner.runs.lock().expect("task runner mutex poisoned");
+  let mut list: Vec<TaskRun> = runs.values().map(|running| running.run.clone()).collect();
+  list.sort_by_key(|run| run.run_id);
+  list
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn names(tasks: &[ProjectTask]) -> Vec<&str> {
+    tasks.iter().map(|task| task.name.as_str()).collect()
+  }
+
+  #[test]
+  fn reads_makefile_targets() {
+    let makefile = "CC := gcc\nPREFIX = /usr\n.PHONY: build test\nbuild: deps ## Compile everything\n\t$(CC) main.c\ntest lint:\n\t./run\n%.o: %.c\n\tcc\nbuild:\n";
+    let targets = makefile_targets(makefile);
+    assert_eq!(names(&targets), ["build", "test", "lint"]);
+    assert_eq!(targets[0].description.as_deref(), Some("Compile everything"));
+    assert_eq!(targets[0].command, ["make", "build"]);
+  }
+
+  #[test]
+  fn reads_justfile_recipes() {
+    let justfile = "set shell := [\"bash\", \"-c\"]\nversion := \"1\"\n\n# Run the tests\ntest *args:\n  cargo test {{args}}\n\n@fmt:\n  cargo fmt\n\n[private]\nhelper:\n  echo\n\n_hidden:\n  echo\n";
+    let recipes = justfile_recipes(justfile);
+    assert_eq!(names(&recipes), ["test", "fmt"]);
+    assert_eq!(recipes[0].description.as_deref(), Some("Run the tests"));
+  }
+}
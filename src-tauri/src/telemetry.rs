@@ -0,0 +1,219 @@
+//! Strictly opt-in, anonymous usage counters.
+//!
+//! Nothing is recorded until the user enables telemetry. Payloads carry only
+//! counter names and totals, the app version and platform, and a random
+//! install id generated at opt-in; no paths, prompts or project data. Counters
+//! are batched in memory and sent periodically to the configured endpoint.
+
+use std::{
+  collections::BTreeMap,
+  sync::Mutex,
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::AppHandle;
+
+use crate::{
+  consent::random_token,
+  error::OpenWorkError,
+  store::{read_state, write_state},
+};
+
+pub const TELEMETRY_FILE: &str = "telemetry.json";
+pub const FLUSH_INTERVAL: Duration = Duration::from_secs(15 * 60);
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+/// Endpoint used when the user has not configured one; set at build time.
+const DEFAULT_ENDPOINT: Option<&str> = option_env!("OPENWORK_TELEMETRY_ENDPOINT");
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TelemetrySettings {
+  pub enabled: bool,
+  pub endpoint: Option<String>,
+  /// Random id created on opt-in and discarded on opt-out.
+  pub install_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TelemetryStatus {
+  pub enabled: bool,
+  pub endpoint: Option<String>,
+  /// Counters waiting for the next batch, so users can see exactly what would be sent.
+  pub pending: BTreeMap<String, u64>,
+}
+
+struct TelemetryState {
+  settings: TelemetrySettings,
+  counters: BTreeMap<String, u64>,
+  period_start_ms: u64,
+}
+
+static STATE: Mutex<Option<TelemetryState>> = Mutex::new(None);
+
+fn now_ms() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_millis() as u64)
+    .unwrap_or(0)
+}
+
+fn endpoint(settings: &TelemetrySettings) -> Option<String> {
+  settings
+    .endpoint
+    .clone()
+    .or_else(|| DEFAULT_ENDPOINT.map(str::to_string))
+}
+
+/// Counts one occurrence of `name` (e.g. `engine.start`). A no-op unless the
+/// user opted in.
+pub fn record(name: &str) {
+  let mut state = STATE.lock().expect("telemetry mutex poisoned");
+  let Some(state) = state.as_mut() else {
+    return;
+  };
+  if !state.settings.enabled {
+    return;
+  }
+  *state.counters.entry(name.to_string()).or_default() += 1;
+}
+
+/// Records `<name>.success` or `<name>.failure`.
+pub fn record_outcome(name: &str, ok: bool) {
+  record(&format!("{name}.{}", if ok { "success" } else { "failure" }));
+}
+
+/// Sends pending counters. On failure they are merged back into the next batch.
+pub fn flush() -> Result<usize, String> {
+  let (url, payload, counters, period_start_ms) = {
+    let mut guard = STATE.lock().expect("telemetry mutex poisoned");
+    let Some(state) = guard.as_mut() else {
+      return Ok(0);
+    };
+    if !state.settings.enabled || state.counters.is_empty() {
+      return Ok(0);
+    }
+    let Some(url) = endpoint(&state.settings) else {
+      return Ok(0);
+    };
+
+    let counters = std::mem::take(&mut state.counters);
+    let period_start_ms = state.period_start_ms;
+    state.period_start_ms = now_ms();
+
+    let payload = json!({
+      "installId": state.settings.install_id,
+      "appVersion": env!("CARGO_PKG_VERSION"),
+      "os": std::env::consts::OS,
+      "arch": std::env::consts::ARCH,
+      "periodStartMs": period_start_ms,
+      "periodEndMs": state.period_start_ms,
+      "counters": counters,
+    });
+    (url, payload, counters, period_start_ms)
+  };
+
+  let sent = reqwest::blocking::Client::builder()
+    .timeout(SEND_TIMEOUT)
+    .build()
+    .and_then(|client| client.post(&url).json(&payload).send())
+    .and_then(|response| response.error_for_status());
+
+  if let Err(e) = sent {
+    let mut guard = STATE.lock().expect("telemetry mutex poisoned");
+    if let Some(state) = guard.as_mut() {
+      for (name, count) in counters {
+        *state.counters.entry(name).or_default() += count;
+      }
+      state.period_start_ms = state.period_start_ms.min(period_start_ms);
+    }
+    return Err(format!("Failed to send telemetry: {e}"));
+  }
+
+  Ok(counters.len())
+}
+
+fn status() -> TelemetryStatus {
+  let state = STATE.lock().expect("telemetry mutex poisoned");
+  match state.as_ref() {
+    Some(state) => TelemetryStatus {
+      enabled: state.settings.enabled,
+      endpoint: endpoint(&state.settings),
+      pending: state.counters.clone(),
+    },
+    None => TelemetryStatus {
+      enabled: false,
+      endpoint: None,
+      pending: BTreeMap::new(),
+    },
+  }
+}
+
+pub fn init(app: &AppHandle) {
+  let settings = read_state::<TelemetrySettings>(app, TELEMETRY_FILE).unwrap_or_else(|e| {
+    tracing::warn!(error = %e, "failed to load telemetry settings; telemetry stays off");
+    TelemetrySettings::default()
+  });
+  *STATE.lock().expect("telemetry mutex poisoned") = Some(TelemetryState {
+    settings,
+    counters: BTreeMap::new(),
+    period_start_ms: now_ms(),
+  });
+}
+
+#[tauri::command]
+pub fn telemetry_get() -> TelemetryStatus {
+  status()
+}
+
+/// Opts in or out. Opting out drops pending counters and the install id.
+/// `endpoint: None` falls back to the build-time default.
+#[tauri::command]
+pub fn telemetry_set(
+  app: AppHandle,
+  enabled: bool,
+  endpoint: Option<String>,
+) -> Result<TelemetryStatus, OpenWorkError> {
+  let endpoint = endpoint.map(|e| e.trim().to_string()).filter(|e| !e.is_empty());
+  if let Some(endpoint) = &endpoint {
+    if !endpoint.starts_with("https://") {
+      return Err(OpenWorkError::invalid_argument("Telemetry endpoint must use https"));
+    }
+  }
+
+  {
+    let mut guard = STATE.lock().expect("telemetry mutex poisoned");
+    let state = guard.get_or_insert_with(|| TelemetryState {
+      settings: TelemetrySettings::default(),
+      counters: BTreeMap::new(),
+      period_start_ms: now_ms(),
+    });
+
+    let settings = TelemetrySettings {
+      enabled,
+      endpoint,
+      install_id: match (enabled, &state.settings.install_id) {
+        (false, _) => None,
+        (true, Some(id)) => Some(id.clone()),
+        (true, None) => Some(random_token(32)),
+      },
+    };
+    write_state(&app, TELEMETRY_FILE, &settings)?;
+
+    if !enabled {
+      state.counters.clear();
+    }
+    state.settings = settings;
+  }
+
+  Ok(status())
+}
+
+/// Sends pending counters now instead of waiting for the next batch.
+#[tauri::command]
+pub fn telemetry_flush() -> Result<TelemetryStatus, OpenWorkError> {
+  flush()?;
+  Ok(status())
+}
@@ -0,0 +1,348 @@
+//! Terminal sessions for the integrated terminal pane.
+//!
+//! Each session runs the user's shell on a pseudo-terminal in the project
+//! directory, with the environment the engine gets. Output is streamed as
+//! `terminal://output` events and the end of the shell as `terminal://exit`;
+//! the pane writes keystrokes back with `terminal_write`.
+//!
+//! Under Flatpak the shell runs on the host through `flatpak-spawn`, like
+//! every other command, so resizing the pane doesn't reach it.
+
+use std::{
+  collections::HashMap,
+  io::{Read, Write},
+  process::Command,
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+  },
+  thread,
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+use portable_pty::{native_pty_system, ChildKiller, CommandBuilder, MasterPty, PtySize};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::{
+  app_sandbox, env_policy,
+  error::{ErrorCode, OpenWorkError},
+  exec, paths,
+  shell::{Shell, ShellKind},
+};
+
+pub const TERMINAL_OUTPUT_EVENT: &str = "terminal://output";
+pub const TERMINAL_EXIT_EVENT: &str = "terminal://exit";
+
+const MAX_SESSIONS: usize = 16;
+const READ_BUFFER: usize = 16 * 1024;
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalInfo {
+  pub id: u64,
+  pub project_dir: String,
+  pub shell: String,
+  pub pid: Option<u32>,
+  pub cols: u16,
+  pub rows: u16,
+  pub started_at: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct TerminalOutput<'a> {
+  id: u64,
+  data: &'a str,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct TerminalExit {
+  id: u64,
+  code: Option<u32>,
+}
+
+struct Session {
+  info: TerminalInfo,
+  master: Box<dyn MasterPty + Send>,
+  /// Locked on its own so a write blocked on a full pty does not hold up
+  /// the other terminals.
+  writer: Arc<Mutex<Box<dyn Write + Send>>>,
+  killer: Box<dyn ChildKiller + Send + Sync>,
+}
+
+#[derive(Default)]
+pub struct TerminalManager {
+  sessions: Mutex<HashMap<u64, Session>>,
+  next_id: AtomicU64,
+}
+
+fn now_ms() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_millis() as u64)
+    .unwrap_or(0)
+}
+
+/// Length of the part of `bytes` that ends on a UTF-8 character boundary; a
+/// read can stop in the middle of a character, whose rest comes next time.
+fn complete_utf8_len(bytes: &[u8]) -> usize {
+  match std::str::from_utf8(bytes) {
+    Ok(_) => bytes.len(),
+    // `error_len` is `None` only for a sequence cut off by the end.
+    Err(e) if e.error_len().is_none() => e.valid_up_to(),
+    Err(_) => bytes.len(),
+  }
+}
+
+fn pty_size(cols: u16, rows: u16) -> Result<PtySize, OpenWorkError> {
+  if cols == 0 || rows == 0 {
+    return Err(OpenWorkError::invalid_argument("cols and rows must be positive"));
+  }
+  Ok(PtySize {
+    rows,
+    cols,
+    pixel_width: 0,
+    pixel_height: 0,
+  })
+}
+
+fn pty_error(action: &str, e: impl std::fmt::Display) -> OpenWorkError {
+  OpenWorkError::new(ErrorCode::Io, format!("Failed to {action}: {e}"))
+}
+
+/// The user's shell as an interactive session in `cwd`, started on the host
+/// under Flatpak.
+fn shell_command(shell: &Shell, cwd: &std::path::Path) -> CommandBuilder {
+  let mut command: Command = exec::command(&shell.program);
+  match shell.kind {
+    ShellKind::Sh | ShellKind::Bash | ShellKind::Zsh | ShellKind::Fish => {
+      command.arg("-l");
+    }
+    ShellKind::PowerShell => {
+      command.arg("-NoLogo");
+    }
+    ShellKind::Cmd => {}
+  }
+  env_policy::apply(&mut command, env_policy::EnvTarget::Engine);
+  command
+    .env("TERM", "xterm-256color")
+    .env("COLORTERM", "truecolor")
+    .current_dir(cwd);
+
+  let (command, inherit) = match app_sandbox::host_command(&command) {
+    // flatpak-spawn needs the app's own environment to reach the host.
+    Some(host) => (host, true),
+    None => (command, false),
+  };
+  let mut builder = CommandBuilder::new(command.get_program());
+  builder.args(command.get_args());
+  if !inherit {
+    builder.env_clear();
+  }
+  for (key, value) in command.get_envs() {
+    if let Some(value) = value {
+      builder.env(key, value);
+    }
+  }
+  builder.cwd(cwd);
+  builder
+}
+
+fn stream_output(app: AppHandle, id: u64, mut reader: Box<dyn Read + Send>) {
+  let mut buffer = vec![0u8; READ_BUFFER];
+  let mut pending: Vec<u8> = Vec::new();
+  loop {
+    let read = match reader.read(&mut buffer) {
+      Ok(0) | Err(_) => break,
+      Ok(read) => read,
+    };
+    pending.extend_from_slice(&buffer[..read]);
+    let complete = complete_utf8_len(&pending);
+    if complete == 0 {
+      continue;
+    }
+    let data = String::from_utf8_lossy(&pending[..complete]).into_owned();
+    pending.drain(..complete);
+    let _ = app.emit(TERMINAL_OUTPUT_EVENT, TerminalOutput { id, data: &data });
+  }
+}
+
+impl TerminalManager {
+  pub fn stop_all(&self) {
+    let mut sessions = self.sessions.lock().expect("terminal mutex poisoned");
+    for (_, mut session) in sessions.drain() {
+      let _ = session.killer.kill();
+    }
+  }
+}
+
+fn too_many_sessions() -> OpenWorkError {
+  OpenWorkError::invalid_argument(format!("At most {MAX_SESSIONS} terminals can be open"))
+}
+
+/// Opens a terminal in `project_dir` running the user's shell.
+#[tauri::command]
+pub fn terminal_create(
+  app: AppHandle,
+  manager: State<TerminalManager>,
+  project_dir: String,
+  cols: u16,
+  rows: u16,
+) -> Result<TerminalInfo, OpenWorkError> {
+  let project_dir = paths::allowed_dir(&project_dir, "projectDir")?;
+  let size = pty_size(cols, rows)?;
+  if manager.sessions.lock().expect("terminal mutex poisoned").len() >= MAX_SESSIONS {
+    return Err(too_many_sessions());
+  }
+
+  let shell = Shell::user();
+  let pair = native_pty_system()
+    .openpty(size)
+    .map_err(|e| pty_error("open a terminal", e))?;
+  let mut child = pair
+    .slave
+    .spawn_command(shell_command(shell, &project_dir))
+    .map_err(|e| pty_error(&format!("start {}", shell.program.display()), e))?;
+  // The shell holds the only copy now, so its exit ends the output.
+  drop(pair.slave);
+  let reader = pair
+    .master
+    .try_clone_reader()
+    .map_err(|e| pty_error("read the terminal", e))?;
+  let writer = pair
+    .master
+    .take_writer()
+    .map_err(|e| pty_error("write to the terminal", e))?;
+
+  let id = manager.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+  let info = TerminalInfo {
+    id,
+    project_dir: project_dir.to_string_lossy().to_string(),
+    shell: shell.program.to_string_lossy().to_string(),
+    pid: child.process_id(),
+    cols,
+    rows,
+    started_at: now_ms(),
+  };
+  {
+    let mut sessions = manager.sessions.lock().expect("terminal mutex poisoned");
+    // Other terminals may have opened while this one was spawning.
+    if sessions.len() >= MAX_SESSIONS {
+      drop(sessions);
+      let _ = child.kill();
+      let _ = child.wait();
+      return Err(too_many_sessions());
+    }
+    sessions.insert(
+      id,
+      Session {
+        info: info.clone(),
+        master: pair.master,
+        writer: Arc::new(Mutex::new(writer)),
+        killer: child.clone_killer(),
+      },
+    );
+  }
+  tracing::info!(id, pid = ?info.pid, shell = %info.shell, "terminal opened");
+
+  let output_app = app.clone();
+  thread::spawn(move || stream_output(output_app, id, reader));
+  thread::spawn(move || {
+    let code = child.wait().ok().map(|status| status.exit_code());
+    tracing::info!(id, code = ?code, "terminal exited");
+    app
+      .state::<TerminalManager>()
+      .sessions
+      .lock()
+      .expect("terminal mutex poisoned")
+      .remove(&id);
+    let _ = app.emit(TERMINAL_EXIT_EVENT, TerminalExit { id, code });
+  });
+  crate::telemetry::record("feature.terminal");
+  Ok(info)
+}
+
+fn with_session<R>(
+  manager: &TerminalManager,
+  id: u64,
+  f: impl FnOnce(&mut Session) -> Result<R, OpenWorkError>,
+) -> Result<R, OpenWorkError> {
+  let mut sessions = manager.sessions.lock().expect("terminal mutex poisoned");
+  let session = sessions
+    .get_mut(&id)
+    .ok_or_else(|| OpenWorkError::new(ErrorCode::NotFound, format!("No terminal {id}")))?;
+  f(session)
+}
+
+/// Sends keystrokes (or pasted text) to the shell.
+#[tauri::command]
+pub fn terminal_write(manager: State<TerminalManager>, id: u64, data: String) -> Result<(), OpenWorkError> {
+  let writer = with_session(&manager, id, |session| Ok(session.writer.clone()))?;
+  let mut writer = writer.lock().expect("terminal writer mutex poisoned");
+  writer
+    .write_all(data.as_bytes())
+    .and_then(|()| writer.flush())
+    .map_err(|e| pty_error("write to the terminal", e))
+}
+
+#[tauri::command]
+pub fn terminal_resize(
+  manager: State<TerminalManager>,
+  id: u64,
+  cols: u16,
+  rows: u16,
+) -> Result<TerminalInfo, OpenWorkError> {
+  let size = pty_size(cols, rows)?;
+  with_session(&manager, id, |session| {
+    session
+      .master
+      .resize(size)
+      .map_err(|e| pty_error("resize the terminal", e))?;
+    session.info.cols = cols;
+    session.info.rows = rows;
+    Ok(session.info.clone())
+  })
+}
+
+/// Kills the shell; the `terminal://exit` event follows.
+#[tauri::command]
+pub fn terminal_kill(manager: State<TerminalManager>, id: u64) -> Result<(), OpenWorkError> {
+  with_session(&manager, id, |session| {
+    session
+      .killer
+      .kill()
+      .map_err(|e| pty_error("stop the terminal", e))
+  })
+}
+
+/// Open terminals, optionally only those of `project_dir`.
+#[tauri::command]
+pub fn terminal_list(manager: State<TerminalManager>, project_dir: Option<String>) -> Vec<TerminalInfo> {
+  let project_dir = project_dir
+    .and_then(|dir| paths::allowed_dir(&dir, "projectDir").ok())
+    .map(|dir| dir.to_string_lossy().to_string());
+  let sessions = manager.sessions.lock().expect("terminal mutex poisoned");
+  let mut list: Vec<TerminalInfo> = sessions
+    .values()
+    .map(|session| session.info.clone())
+    .filter(|info| project_dir.as_ref().is_none_or(|dir| &info.project_dir == dir))
+    .collect();
+  list.sort_by_key(|info| info.id);
+  list
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn keeps_split_characters_for_the_next_read() {
+    let text = "é→".as_bytes();
+    assert_eq!(complete_utf8_len(text), text.len());
+    assert_eq!(complete_utf8_len(&text[..3]), 2);
+    assert_eq!(complete_utf8_len(&text[..1]), 0);
+    assert_eq!(complete_utf8_len(b"\xffabc"), 4);
+  }
+}
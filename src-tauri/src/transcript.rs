@@ -0,0 +1,262 @@
+use std::fs;
+
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{State, WebviewWindow};
+
+use crate::{
+  consent::ConsentManager,
+  engine_client::{fetch_session, fetch_session_messages, EngineClient},
+  error::OpenWorkError,
+  EngineManager,
+};
+
+const MAX_TOOL_OUTPUT_CHARS: usize = 2000;
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionExportResult {
+  pub path: String,
+  pub format: String,
+  pub messages: usize,
+}
+
+enum TranscriptFormat {
+  Markdown,
+  Html,
+  Json,
+}
+
+impl TranscriptFormat {
+  fn parse(value: &str) -> Result<Self, OpenWorkError> {
+    match value.trim().to_ascii_lowercase().as_str() {
+      "markdown" | "md" => Ok(Self::Markdown),
+      "html" => Ok(Self::Html),
+      "json" => Ok(Self::Json),
+      _ => Err(OpenWorkError::invalid_argument("format must be 'markdown', 'html' or 'json'")),
+    }
+  }
+
+  fn name(&self) -> &'static str {
+    match self {
+      Self::Markdown => "markdown",
+      Self::Html => "html",
+      Self::Json => "json",
+    }
+  }
+}
+
+fn str_at<'a>(value: &'a Value, pointer: &str) -> Option<&'a str> {
+  value.pointer(pointer).and_then(|v| v.as_str())
+}
+
+fn clamp(text: &str, max: usize) -> String {
+  if text.chars().count() <= max {
+    return text.to_string();
+  }
+  let clipped: String = text.chars().take(max).collect();
+  format!("{clipped}\n… (truncated)")
+}
+
+/// One rendered block of a message, independent of the output format.
+enum Block {
+  Text(String),
+  Tool {
+    name: String,
+    status: String,
+    title: Option<String>,
+    output: Option<String>,
+    error: Option<String>,
+  },
+  File(String),
+}
+
+fn message_blocks(message: &Value) -> Vec<Block> {
+  let Some(parts) = message.get("parts").and_then(|p| p.as_array()) else {
+    return Vec::new();
+  };
+
+  parts
+    .iter()
+    .filter_map(|part| match str_at(part, "/type")? {
+      "text" => {
+        let text = str_at(part, "/text")?.trim();
+        (!text.is_empty()).then(|| Block::Text(text.to_string()))
+      }
+      "tool" => Some(Block::Tool {
+        name: str_at(part, "/tool").unwrap_or("tool").to_string(),
+        status: str_at(part, "/state/status").unwrap_or("unknown").to_string(),
+        title: str_at(part, "/state/title").map(str::to_string),
+        output: str_at(part, "/state/output").map(|o| clamp(o, MAX_TOOL_OUTPUT_CHARS)),
+        error: str_at(part, "/state/error").map(str::to_string),
+      }),
+      "file" => {
+        let name = str_at(part, "/filename")
+          .or_else(|| str_at(part, "/url"))
+          .unwrap_or("file");
+        Some(Block::File(name.to_string()))
+      }
+      _ => None,
+    })
+    .collect()
+}
+
+fn role(message: &Value) -> &str {
+  str_at(message, "/info/role").unwrap_or("unknown")
+}
+
+fn role_label(role: &str) -> &str {
+  match role {
+    "user" => "User",
+    "assistant" => "Assistant",
+    _ => role,
+  }
+}
+
+fn session_title(session: &Value, id: &str) -> String {
+  str_at(session, "/title")
+    .filter(|t| !t.trim().is_empty())
+    .map(str::to_string)
+    .unwrap_or_else(|| format!("Session {id}"))
+}
+
+fn render_markdown(title: &str, messages: &[Value]) -> String {
+  let mut out = format!("# {title}\n");
+
+  for message in messages {
+    out.push_str(&format!("\n## {}\n\n", role_label(role(message))));
+    for block in message_blocks(message) {
+      match block {
+        Block::Text(text) => {
+          out.push_str(&text);
+          out.push_str("\n\n");
+        }
+        Block::Tool {
+          name,
+          status,
+          title,
+          output,
+          error,
+        } => {
+          out.push_str(&format!("> **Tool · {name}** ({status})"));
+          if let Some(title) = title {
+            out.push_str(&format!(" — {title}"));
+          }
+          out.push_str("\n\n");
+          if let Some(output) = output {
+            out.push_str(&format!("```\n{}\n```\n\n", output.trim_end()));
+          }
+          if let Some(error) = error {
+            out.push_str(&format!("> Error: {error}\n\n"));
+          }
+        }
+        Block::File(name) => out.push_str(&format!("📎 {name}\n\n")),
+      }
+    }
+  }
+
+  out
+}
+
+fn escape_html(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
+
+fn render_html(title: &str, messages: &[Value]) -> String {
+  let mut body = String::new();
+
+  for message in messages {
+    let role = role(message);
+    body.push_str(&format!(
+      "<section class=\"message {}\">\n<h2>{}</h2>\n",
+      escape_html(role),
+      escape_html(role_label(role))
+    ));
+    for block in message_blocks(message) {
+      match block {
+        Block::Text(text) => body.push_str(&format!("<div class=\"text\">{}</div>\n", escape_html(&text))),
+        Block::Tool {
+          name,
+          status,
+          title,
+          output,
+          error,
+        } => {
+          body.push_str(&format!(
+            "<details class=\"tool\"><summary>Tool · {} ({}){}</summary>\n",
+            escape_html(&name),
+            escape_html(&status),
+            title
+              .map(|t| format!(" — {}", escape_html(&t)))
+              .unwrap_or_default()
+          ));
+          if let Some(output) = output {
+            body.push_str(&format!("<pre>{}</pre>\n", escape_html(&output)));
+          }
+          if let Some(error) = error {
+            body.push_str(&format!("<p class=\"error\">{}</p>\n", escape_html(&error)));
+          }
+          body.push_str("</details>\n");
+        }
+        Block::File(name) => body.push_str(&format!("<p class=\"file\">📎 {}</p>\n", escape_html(&name))),
+      }
+    }
+    body.push_str("</section>\n");
+  }
+
+  format!(
+    "<!doctype html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>\n\
+body {{ font-family: system-ui, sans-serif; max-width: 860px; margin: 2rem auto; padding: 0 1rem; color: #111; }}\n\
+.message {{ border-top: 1px solid #ddd; padding: 1rem 0; }}\n\
+.text {{ white-space: pre-wrap; }}\n\
+pre {{ background: #f4f4f5; padding: .75rem; border-radius: 6px; overflow-x: auto; white-space: pre-wrap; }}\n\
+.error {{ color: #b91c1c; }}\n\
+</style>\n</head>\n<body>\n<h1>{title}</h1>\n{body}</body>\n</html>\n",
+    title = escape_html(title),
+    body = body
+  )
+}
+
+#[tauri::command]
+pub fn session_export(
+  window: WebviewWindow,
+  manager: State<EngineManager>,
+  consent: State<ConsentManager>,
+  id: String,
+  format: String,
+  dest: String,
+  confirmation_id: Option<String>,
+) -> Result<SessionExportResult, OpenWorkError> {
+  let format = TranscriptFormat::parse(&format)?;
+
+  let dest = crate::paths::export_target(&dest, "dest", &consent, confirmation_id.as_deref())?;
+  crate::telemetry::record("feature.session_export");
+
+  let client = EngineClient::for_window(&manager, window.label())?;
+  let session = fetch_session(&client, &id)?;
+  let messages = fetch_session_messages(&client, &id)?;
+  let messages = messages.as_array().cloned().unwrap_or_default();
+  let title = session_title(&session, id.trim());
+
+  let content = match format {
+    TranscriptFormat::Markdown => render_markdown(&title, &messages),
+    TranscriptFormat::Html => render_html(&title, &messages),
+    TranscriptFormat::Json => serde_json::to_string_pretty(&serde_json::json!({
+      "session": session,
+      "messages": messages,
+    }))
+    .map_err(|e| e.to_string())?,
+  };
+
+  fs::write(&dest, content).map_err(|e| format!("Failed to write {}: {e}", dest.display()))?;
+
+  Ok(SessionExportResult {
+    path: dest.to_string_lossy().to_string(),
+    format: format.name().to_string(),
+    messages: messages.len(),
+  })
+}
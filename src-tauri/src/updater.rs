@@ -0,0 +1,146 @@
+//! Self-update from the GitHub release feed.
+//!
+//! Releases need signed updater bundles and a `latest.json` (tauri-action
+//! produces both when `TAURI_SIGNING_PRIVATE_KEY` is set). Downloads are
+//! checked against the public key baked in at build time
+//! (`OPENWORK_UPDATER_PUBKEY`); builds without a key report updates as
+//! unavailable instead of installing anything unverified.
+
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State, Url};
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+use crate::{
+  download,
+  error::{ErrorCode, OpenWorkError},
+  network,
+  EngineManager,
+};
+
+pub const UPDATE_PROGRESS_EVENT: &str = "update://progress";
+pub const UPDATE_INSTALLED_EVENT: &str = "update://installed";
+/// Emitted when a scheduled check finds a release, with its `AppUpdate`.
+pub const UPDATE_AVAILABLE_EVENT: &str = "update://available";
+
+const UPDATE_FEED: &str = "https://github.com/different-ai/openwork/releases/latest/download/latest.json";
+const UPDATE_PUBKEY: Option<&str> = option_env!("OPENWORK_UPDATER_PUBKEY");
+
+/// The update found by the last check, kept so install doesn't query the feed again.
+#[derive(Default)]
+pub struct UpdateState {
+  pending: Mutex<Option<Update>>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AppUpdate {
+  pub version: String,
+  pub current_version: String,
+  pub notes: Option<String>,
+  /// Publish date, when the feed has one.
+  pub date: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateProgress {
+  pub downloaded: u64,
+  pub total: Option<u64>,
+}
+
+fn updater_error(e: tauri_plugin_updater::Error) -> OpenWorkError {
+  OpenWorkError::new(ErrorCode::Network, format!("Update failed: {e}")).retryable()
+}
+
+async fn check(app: &AppHandle) -> Result<Option<Update>, OpenWorkError> {
+  let pubkey = UPDATE_PUBKEY.filter(|key| !key.trim().is_empty()).ok_or_else(|| {
+    OpenWorkError::new(ErrorCode::NotFound, "Updates are not available for this build.")
+  })?;
+  let feed = Url::parse(UPDATE_FEED).map_err(|e| format!("Invalid update feed: {e}"))?;
+  network::require_online(app, "Checking for updates")?;
+
+  let mut builder = app
+    .updater_builder()
+    .pubkey(pubkey)
+    .endpoints(vec![feed])
+    .map_err(updater_error)?;
+  // The plugin downloads on its own, but honours the same proxy setting.
+  if let Some(proxy) = download::proxy_url() {
+    builder = builder.proxy(proxy);
+  }
+  builder
+    .build()
+    .map_err(updater_error)?
+    .check()
+    .await
+    .map_err(updater_error)
+}
+
+/// Checks the feed and keeps what it found for `app_update_install`.
+async fn check_and_remember(app: &AppHandle) -> Result<Option<AppUpdate>, OpenWorkError> {
+  let update = check(app).await?;
+  let info = update.as_ref().map(|update| AppUpdate {
+    version: update.version.clone(),
+    current_version: update.current_version.clone(),
+    notes: update.body.clone(),
+    date: update.date.map(|date| date.to_string()),
+  });
+  tracing::info!(available = ?info.as_ref().map(|u| &u.version), "checked for app update");
+  let state = app.state::<UpdateState>();
+  *state.pending.lock().expect("update mutex poisoned") = update;
+  Ok(info)
+}
+
+/// Scheduled check; tells the frontend when a release is available. Builds
+/// without updates enabled, and offline computers, skip it quietly.
+pub async fn check_in_background(app: &AppHandle) -> Result<(), OpenWorkError> {
+  if UPDATE_PUBKEY.filter(|key| !key.trim().is_empty()).is_none() || !network::status().online {
+    return Ok(());
+  }
+  if let Some(info) = check_and_remember(app).await? {
+    let _ = app.emit(UPDATE_AVAILABLE_EVENT, info);
+  }
+  Ok(())
+}
+
+#[tauri::command]
+pub async fn app_update_check(app: AppHandle) -> Result<Option<AppUpdate>, OpenWorkError> {
+  check_and_remember(&app).await
+}
+
+/// Downloads, verifies and installs the update found by `app_update_check`,
+/// then restarts the app.
+#[tauri::command]
+pub async fn app_update_install(app: AppHandle, state: State<'_, UpdateState>) -> Result<(), OpenWorkError> {
+  let pending = state.pending.lock().expect("update mutex poisoned").take();
+  let update = match pending {
+    Some(update) => update,
+    None => check(&app)
+      .await?
+      .ok_or_else(|| OpenWorkError::new(ErrorCode::NotFound, "OpenWork is already up to date."))?,
+  };
+  tracing::info!(version = %update.version, "installing app update");
+
+  let mut downloaded = 0u64;
+  let progress_app = app.clone();
+  update
+    .download_and_install(
+      move |chunk, total| {
+        downloaded += chunk as u64;
+        let _ = progress_app.emit(UPDATE_PROGRESS_EVENT, UpdateProgress { downloaded, total });
+      },
+      || {},
+    )
+    .await
+    .map_err(updater_error)?;
+
+  let _ = app.emit(UPDATE_INSTALLED_EVENT, &update.version);
+  // Engines, MCP servers and terminals are child processes and would outlive
+  // the restart.
+  app.state::<EngineManager>().stop_all();
+  app.state::<crate::mcp::McpManager>().stop_all();
+  app.state::<crate::terminal::TerminalManager>().stop_all();
+  app.restart();
+}
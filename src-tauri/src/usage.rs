@@ -0,0 +1,261 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::params;
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Manager, State};
+
+use crate::{
+  error::OpenWorkError,
+  history::HistoryDb,
+  relay::{EventRelay, RelayedEvent},
+};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS usage (
+  message_id TEXT PRIMARY KEY,
+  session_id TEXT NOT NULL,
+  project_dir TEXT,
+  provider TEXT,
+  model TEXT,
+  input_tokens INTEGER NOT NULL DEFAULT 0,
+  output_tokens INTEGER NOT NULL DEFAULT 0,
+  reasoning_tokens INTEGER NOT NULL DEFAULT 0,
+  cache_read_tokens INTEGER NOT NULL DEFAULT 0,
+  cache_write_tokens INTEGER NOT NULL DEFAULT 0,
+  cost REAL NOT NULL DEFAULT 0,
+  created_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS usage_created ON usage(created_at);
+CREATE INDEX IF NOT EXISTS usage_project ON usage(project_dir, created_at);
+";
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageTotals {
+  pub input_tokens: i64,
+  pub output_tokens: i64,
+  pub reasoning_tokens: i64,
+  pub cache_read_tokens: i64,
+  pub cache_write_tokens: i64,
+  pub cost: f64,
+  pub messages: i64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageBucket {
+  /// Day (`YYYY-MM-DD`, UTC), `provider/model`, or project directory.
+  pub key: String,
+  #[serde(flatten)]
+  pub totals: UsageTotals,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageSummary {
+  pub range: String,
+  pub since: Option<i64>,
+  pub totals: UsageTotals,
+  pub by_day: Vec<UsageBucket>,
+  pub by_model: Vec<UsageBucket>,
+  pub by_project: Vec<UsageBucket>,
+}
+
+pub fn now_ms() -> i64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_millis() as i64)
+    .unwrap_or(0)
+}
+
+/// Start of the range in ms since the epoch; `None` means all time.
+pub fn range_start(range: &str, now: i64) -> Result<Option<i64>, String> {
+  let start_of_today = now - now.rem_euclid(DAY_MS);
+  match range.trim() {
+    "today" => Ok(Some(start_of_today)),
+    "7d" => Ok(Some(start_of_today - 6 * DAY_MS)),
+    "30d" => Ok(Some(start_of_today - 29 * DAY_MS)),
+    "month" => Ok(Some(start_of_month(now))),
+    "all" | "" => Ok(None),
+    other => Err(format!("Unknown range '{other}'. Use today, 7d, 30d, month or all.")),
+  }
+}
+
+/// First millisecond of the current UTC month.
+pub fn start_of_month(now: i64) -> i64 {
+  let days = now.div_euclid(DAY_MS);
+  // Civil-from-days (Howard Hinnant), enough to find the day of month.
+  let z = days + 719_468;
+  let era = z.div_euclid(146_097);
+  let doe = z - era * 146_097;
+  let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let day_of_month = doy - (153 * mp + 2) / 5 + 1;
+  (days - (day_of_month - 1)) * DAY_MS
+}
+
+fn int_at(info: &Value, pointer: &str) -> i64 {
+  info.pointer(pointer).and_then(|v| v.as_i64()).unwrap_or(0)
+}
+
+fn str_at(info: &Value, pointer: &str) -> Option<String> {
+  info.pointer(pointer).and_then(|v| v.as_str()).map(str::to_string)
+}
+
+fn record(db: &HistoryDb, relayed: &RelayedEvent) -> Result<(), String> {
+  if relayed.event.get("type").and_then(|t| t.as_str()) != Some("message.updated") {
+    return Ok(());
+  }
+  let Some(info) = relayed.event.pointer("/properties/info") else {
+    return Ok(());
+  };
+  if str_at(info, "/role").as_deref() != Some("assistant") {
+    return Ok(());
+  }
+  let (Some(message_id), Some(session_id)) = (str_at(info, "/id"), str_at(info, "/sessionID")) else {
+    return Ok(());
+  };
+
+  let created_at = info
+    .pointer("/time/created")
+    .and_then(|v| v.as_i64())
+    .unwrap_or_else(now_ms);
+  let cost = info.get("cost").and_then(|v| v.as_f64()).unwrap_or(0.0);
+
+  db.with_conn(|conn| {
+    conn.execute(
+      "INSERT INTO usage (message_id, session_id, project_dir, provider, model,
+                          input_tokens, output_tokens, reasoning_tokens,
+                          cache_read_tokens, cache_write_tokens, cost, created_at)
+       VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+       ON CONFLICT(message_id) DO UPDATE SET
+         provider = excluded.provider,
+         model = excluded.model,
+         input_tokens = excluded.input_tokens,
+         output_tokens = excluded.output_tokens,
+         reasoning_tokens = excluded.reasoning_tokens,
+         cache_read_tokens = excluded.cache_read_tokens,
+         cache_write_tokens = excluded.cache_write_tokens,
+         cost = excluded.cost",
+      params![
+        message_id,
+        session_id,
+        str_at(info, "/path/root").or_else(|| relayed.project_dir.clone()),
+        str_at(info, "/providerID"),
+        str_at(info, "/modelID"),
+        int_at(info, "/tokens/input"),
+        int_at(info, "/tokens/output"),
+        int_at(info, "/tokens/reasoning"),
+        int_at(info, "/tokens/cache/read"),
+        int_at(info, "/tokens/cache/write"),
+        cost,
+        created_at,
+      ],
+    )
+  })?;
+  Ok(())
+}
+
+/// Creates the usage table and subscribes it to the event relay.
+pub fn init(app: &AppHandle) {
+  if let Err(e) = app.state::<HistoryDb>().with_conn(|conn| conn.execute_batch(SCHEMA)) {
+    tracing::error!(error = %e, "failed to create usage table");
+  }
+
+  app.state::<EventRelay>().subscribe(|app, event| {
+    if let Err(e) = record(&app.state::<HistoryDb>(), event) {
+      tracing::warn!(error = %e, "failed to record usage");
+    }
+  });
+}
+
+fn grouped(
+  db: &HistoryDb,
+  key_expr: &str,
+  since: Option<i64>,
+  project: Option<&str>,
+  order: &str,
+) -> Result<Vec<UsageBucket>, String> {
+  let sql = format!(
+    "SELECT {key_expr} AS key,
+            SUM(input_tokens), SUM(output_tokens), SUM(reasoning_tokens),
+            SUM(cache_read_tokens), SUM(cache_write_tokens), SUM(cost), COUNT(*)
+     FROM usage
+     WHERE (?1 IS NULL OR created_at >= ?1) AND (?2 IS NULL OR project_dir = ?2)
+     GROUP BY key
+     ORDER BY {order}"
+  );
+
+  db.with_conn(|conn| {
+    let mut statement = conn.prepare(&sql)?;
+    let rows = statement.query_map(params![since, project], |row| {
+      Ok(UsageBucket {
+        key: row.get::<_, Option<String>>(0)?.unwrap_or_else(|| "unknown".to_string()),
+        totals: UsageTotals {
+          input_tokens: row.get(1)?,
+          output_tokens: row.get(2)?,
+          reasoning_tokens: row.get(3)?,
+          cache_read_tokens: row.get(4)?,
+          cache_write_tokens: row.get(5)?,
+          cost: row.get(6)?,
+          messages: row.get(7)?,
+        },
+      })
+    })?;
+    rows.collect()
+  })
+}
+
+pub fn summarize(db: &HistoryDb, range: &str, project: Option<&str>) -> Result<UsageSummary, String> {
+  let since = range_start(range, now_ms())?;
+
+  let by_day = grouped(
+    db,
+    "date(created_at / 1000, 'unixepoch')",
+    since,
+    project,
+    "key ASC",
+  )?;
+  let by_model = grouped(
+    db,
+    "COALESCE(provider, 'unknown') || '/' || COALESCE(model, 'unknown')",
+    since,
+    project,
+    "SUM(cost) DESC",
+  )?;
+  let by_project = grouped(db, "project_dir", since, project, "SUM(cost) DESC")?;
+
+  let mut totals = UsageTotals::default();
+  for bucket in &by_day {
+    totals.input_tokens += bucket.totals.input_tokens;
+    totals.output_tokens += bucket.totals.output_tokens;
+    totals.reasoning_tokens += bucket.totals.reasoning_tokens;
+    totals.cache_read_tokens += bucket.totals.cache_read_tokens;
+    totals.cache_write_tokens += bucket.totals.cache_write_tokens;
+    totals.cost += bucket.totals.cost;
+    totals.messages += bucket.totals.messages;
+  }
+
+  Ok(UsageSummary {
+    range: range.trim().to_string(),
+    since,
+    totals,
+    by_day,
+    by_model,
+    by_project,
+  })
+}
+
+#[tauri::command]
+pub fn usage_summary(
+  db: State<HistoryDb>,
+  range: String,
+  project: Option<String>,
+) -> Result<UsageSummary, OpenWorkError> {
+  let project = project.map(|p| p.trim().to_string()).filter(|p| !p.is_empty());
+  Ok(summarize(&db, &range, project.as_deref())?)
+}
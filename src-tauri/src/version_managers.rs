@@ -0,0 +1,328 @@
+//! Node version managers (Volta, fnm, nvm, asdf, mise).
+//!
+//! `npm install -g opencode-ai` under a version manager puts opencode in that
+//! Node version's own bin directory, which is only on PATH inside a shell the
+//! manager has hooked. These helpers list those directories, the default
+//! version first and then newest to oldest, followed by the manager's shims.
+//! Real bin directories are preferred over shims: asdf and mise shims need the
+//! manager on PATH, and pick the version from the working directory.
+//!
+//! npm's `opencode` launcher starts `node` from PATH, so an executable found
+//! this way is spawned with its own bin directory in front of PATH.
+
+use std::{
+  env,
+  ffi::OsString,
+  fs,
+  path::{Path, PathBuf},
+  process::Command,
+};
+
+use serde::Serialize;
+
+use crate::{app_sandbox, home_dir};
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum VersionManager {
+  Volta,
+  Fnm,
+  Nvm,
+  Asdf,
+  Mise,
+}
+
+impl VersionManager {
+  pub fn label(self) -> &'static str {
+    match self {
+      VersionManager::Volta => "volta",
+      VersionManager::Fnm => "fnm",
+      VersionManager::Nvm => "nvm",
+      VersionManager::Asdf => "asdf",
+      VersionManager::Mise => "mise",
+    }
+  }
+}
+
+/// Where one manager keeps its Node installs.
+struct Layout {
+  manager: VersionManager,
+  root: PathBuf,
+  /// Directory holding one folder per installed version, relative to `root`.
+  versions: Option<PathBuf>,
+  /// Bin directory inside a version folder.
+  version_bin: PathBuf,
+  /// Bin directory of the default version, relative to `root`, when the
+  /// manager keeps a link to it.
+  default_bin: Option<PathBuf>,
+  /// File naming the default version, relative to `root`.
+  default_alias: Option<PathBuf>,
+  shims: Option<PathBuf>,
+}
+
+fn env_dir(key: &str) -> Option<PathBuf> {
+  env::var_os(key)
+    .filter(|value| !value.is_empty())
+    .map(PathBuf::from)
+}
+
+/// `$XDG_DATA_HOME`, or `~/.local/share`.
+#[cfg(not(windows))]
+fn data_dir(home: &Path) -> PathBuf {
+  app_sandbox::xdg_dir("XDG_DATA_HOME").unwrap_or_else(|| home.join(".local").join("share"))
+}
+
+#[cfg(not(windows))]
+fn layouts(home: &Path) -> Vec<Layout> {
+  let bin = PathBuf::from("bin");
+  let fnm_roots = vec![
+    data_dir(home).join("fnm"),
+    #[cfg(target_os = "macos")]
+    home.join("Library").join("Application Support").join("fnm"),
+    home.join(".fnm"),
+  ];
+  let fnm_root = env_dir("FNM_DIR")
+    .or_else(|| fnm_roots.iter().find(|root| root.is_dir()).cloned())
+    .unwrap_or_else(|| fnm_roots[0].clone());
+
+  vec![
+    Layout {
+      manager: VersionManager::Volta,
+      root: env_dir("VOLTA_HOME").unwrap_or_else(|| home.join(".volta")),
+      versions: None,
+      version_bin: bin.clone(),
+      default_bin: None,
+      default_alias: None,
+      // Volta's shims are native and pick the version themselves.
+      shims: Some(bin.clone()),
+    },
+    Layout {
+      manager: VersionManager::Fnm,
+      root: fnm_root,
+      versions: Some(PathBuf::from("node-versions")),
+      version_bin: Path::new("installation").join("bin"),
+      default_bin: Some(Path::new("aliases").join("default").join("bin")),
+      default_alias: None,
+      shims: None,
+    },
+    Layout {
+      manager: VersionManager::Nvm,
+      root: env_dir("NVM_DIR").unwrap_or_else(|| home.join(".nvm")),
+      versions: Some(Path::new("versions").join("node")),
+      version_bin: bin.clone(),
+      default_bin: None,
+      default_alias: Some(Path::new("alias").join("default")),
+      shims: None,
+    },
+    Layout {
+      manager: VersionManager::Asdf,
+      root: env_dir("ASDF_DATA_DIR").unwrap_or_else(|| home.join(".asdf")),
+      versions: Some(Path::new("installs").join("nodejs")),
+      version_bin: bin.clone(),
+      default_bin: None,
+      default_alias: None,
+      shims: Some(PathBuf::from("shims")),
+    },
+    Layout {
+      manager: VersionManager::Mise,
+      root: env_dir("MISE_DATA_DIR").unwrap_or_else(|| data_dir(home).join("mise")),
+      versions: Some(Path::new("installs").join("node")),
+      version_bin: bin,
+      default_bin: None,
+      default_alias: None,
+      shims: Some(PathBuf::from("shims")),
+    },
+  ]
+}
+
+#[cfg(windows)]
+fn layouts(home: &Path) -> Vec<Layout> {
+  let local = env_dir("LOCALAPPDATA").unwrap_or_else(|| home.join("AppData").join("Local"));
+  let roaming = env_dir("APPDATA").unwrap_or_else(|| home.join("AppData").join("Roaming"));
+  // Node on Windows keeps node.exe and npm's launchers at the top of the
+  // version folder rather than in `bin`.
+  let top = PathBuf::new();
+
+  vec![
+    Layout {
+      manager: VersionManager::Volta,
+      root: env_dir("VOLTA_HOME").unwrap_or_else(|| local.join("Volta")),
+      versions: None,
+      version_bin: top.clone(),
+      default_bin: None,
+      default_alias: None,
+      shims: Some(PathBuf::from("bin")),
+    },
+    Layout {
+      manager: VersionManager::Fnm,
+      root: env_dir("FNM_DIR").unwrap_or_else(|| roaming.join("fnm")),
+      versions: Some(PathBuf::from("node-versions")),
+      version_bin: PathBuf::from("installation"),
+      default_bin: Some(Path::new("aliases").join("default")),
+      default_alias: None,
+      shims: None,
+    },
+    Layout {
+      // nvm-windows keeps versions as `v20.11.1` folders at its root and
+      // links the active one to `NVM_SYMLINK`.
+      manager: VersionManager::Nvm,
+      root: env_dir("NVM_HOME").unwrap_or_else(|| roaming.join("nvm")),
+      versions: Some(top.clone()),
+      version_bin: top.clone(),
+      default_bin: env_dir("NVM_SYMLINK"),
+      default_alias: None,
+      shims: None,
+    },
+    Layout {
+      manager: VersionManager::Mise,
+      root: env_dir("MISE_DATA_DIR").unwrap_or_else(|| local.join("mise")),
+      versions: Some(Path::new("installs").join("node")),
+      version_bin: top,
+      default_bin: None,
+      default_alias: None,
+      shims: Some(PathBuf::from("shims")),
+    },
+  ]
+}
+
+/// Numeric components of a version folder name such as `v20.11.1`.
+fn parse_version(name: &str) -> Option<Vec<u64>> {
+  let name = name.trim().trim_start_matches('v');
+  let parts: Vec<u64> = name.split('.').map_while(|part| part.parse().ok()).collect();
+  (!parts.is_empty()).then_some(parts)
+}
+
+/// Version folder names, the one `alias` picks first and then newest to
+/// oldest. Aliases are nvm's: a full or partial version, or `node`/`stable`
+/// for the newest.
+fn order_versions(mut names: Vec<String>, alias: Option<&str>) -> Vec<String> {
+  names.retain(|name| parse_version(name).is_some());
+  names.sort_by(|a, b| parse_version(b).cmp(&parse_version(a)));
+  let wanted = alias.and_then(parse_version);
+  if let Some(wanted) = wanted {
+    let matches = |name: &String| parse_version(name).is_some_and(|version| version.starts_with(&wanted));
+    if let Some(index) = names.iter().position(matches) {
+      let default = names.remove(index);
+      names.insert(0, default);
+    }
+  }
+  names
+}
+
+impl Layout {
+  fn bin_dirs(&self) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(default_bin) = &self.default_bin {
+      dirs.push(self.root.join(default_bin));
+    }
+    if let Some(versions) = &self.versions {
+      let versions_dir = self.root.join(versions);
+      let names = fs::read_dir(&versions_dir)
+        .map(|entries| {
+          entries
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().is_dir())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect()
+        })
+        .unwrap_or_default();
+      let alias = self
+        .default_alias
+        .as_ref()
+        .and_then(|alias| fs::read_to_string(self.root.join(alias)).ok());
+      for name in order_versions(names, alias.as_deref()) {
+        dirs.push(versions_dir.join(name).join(&self.version_bin));
+      }
+    }
+    if let Some(shims) = &self.shims {
+      dirs.push(self.root.join(shims));
+    }
+    dirs
+  }
+}
+
+/// Version managers installed for this user.
+pub fn detected() -> Vec<VersionManager> {
+  let Some(home) = home_dir() else {
+    return Vec::new();
+  };
+  layouts(&home)
+    .into_iter()
+    .filter(|layout| layout.root.is_dir())
+    .map(|layout| layout.manager)
+    .collect()
+}
+
+/// Bin directories of every installed version manager, in the order they
+/// should be searched.
+pub fn bin_dirs() -> Vec<(VersionManager, PathBuf)> {
+  let Some(home) = home_dir() else {
+    return Vec::new();
+  };
+  layouts(&home)
+    .into_iter()
+    .filter(|layout| layout.root.is_dir())
+    .flat_map(|layout| {
+      let manager = layout.manager;
+      layout.bin_dirs().into_iter().map(move |dir| (manager, dir))
+    })
+    .collect()
+}
+
+/// Puts `program`'s directory in front of the command's PATH when a `node`
+/// sits next to it, so npm's launcher runs on the Node it was installed with.
+pub fn prepend_node_dir(command: &mut Command, program: &Path) {
+  let node = if cfg!(windows) { "node.exe" } else { "node" };
+  let Some(dir) = program.parent().filter(|dir| dir.join(node).is_file()) else {
+    return;
+  };
+  let current: Option<OsString> = command
+    .get_envs()
+    .find(|(key, _)| *key == "PATH")
+    .map(|(_, value)| value.map(OsString::from))
+    .unwrap_or_else(|| env::var_os("PATH"));
+  let mut entries = vec![dir.to_path_buf()];
+  entries.extend(
+    current
+      .iter()
+      .flat_map(env::split_paths)
+      .filter(|entry| entry.as_path() != dir),
+  );
+  if let Ok(path) = env::join_paths(entries) {
+    command.env("PATH", path);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn names(list: &[&str]) -> Vec<String> {
+    list.iter().map(|name| name.to_string()).collect()
+  }
+
+  #[test]
+  fn parses_version_folders() {
+    assert_eq!(parse_version("v20.11.1"), Some(vec![20, 11, 1]));
+    assert_eq!(parse_version("18"), Some(vec![18]));
+    assert_eq!(parse_version("lts/*"), None);
+    assert_eq!(parse_version(".DS_Store"), None);
+  }
+
+  #[test]
+  fn orders_default_then_newest() {
+    let installed = names(&["v18.19.0", "v20.11.1", "v9.0.0", "notes", "v20.2.0"]);
+    assert_eq!(
+      order_versions(installed.clone(), None),
+      names(&["v20.11.1", "v20.2.0", "v18.19.0", "v9.0.0"])
+    );
+    assert_eq!(
+      order_versions(installed.clone(), Some("18\n")),
+      names(&["v18.19.0", "v20.11.1", "v20.2.0", "v9.0.0"])
+    );
+    assert_eq!(
+      order_versions(installed, Some("lts/*")),
+      names(&["v20.11.1", "v20.2.0", "v18.19.0", "v9.0.0"])
+    );
+  }
+}
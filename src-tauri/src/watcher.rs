@@ -0,0 +1,278 @@
+use std::{
+  collections::{BTreeMap, HashMap},
+  path::{Path, PathBuf},
+  sync::{mpsc, Mutex},
+  thread,
+  time::{Duration, Instant},
+};
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use notify::{
+  event::{ModifyKind, RenameMode},
+  Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher,
+};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State, WebviewWindow};
+
+use crate::project::{project_root, project_walker, relative_display};
+use crate::error::OpenWorkError;
+
+pub const FS_CHANGED_EVENT: &str = "project://fs-changed";
+
+/// Quiet period before a batch of changes is flushed to the UI.
+const DEBOUNCE: Duration = Duration::from_millis(250);
+/// Upper bound on how long a continuous stream of changes is held back.
+const MAX_BATCH_DELAY: Duration = Duration::from_secs(1);
+
+/// One watched project per window, by window label.
+#[derive(Default)]
+pub struct WatcherManager {
+  inner: Mutex<HashMap<String, ProjectWatcher>>,
+}
+
+struct ProjectWatcher {
+  project_dir: String,
+  // Dropping the watcher closes the event channel, which ends the debounce thread.
+  _watcher: RecommendedWatcher,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WatcherInfo {
+  pub watching: bool,
+  pub project_dir: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FsChangedEvent {
+  pub project_dir: String,
+  pub created: Vec<String>,
+  pub modified: Vec<String>,
+  pub deleted: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Change {
+  Created,
+  Modified,
+  Deleted,
+}
+
+/// One matcher per directory that has a `.gitignore`, so nested ignore files
+/// apply relative to their own directory.
+struct IgnoreRules {
+  matchers: Vec<Gitignore>,
+}
+
+impl IgnoreRules {
+  fn load(root: &Path) -> Self {
+    let mut matchers = Vec::new();
+
+    let mut root_builder = GitignoreBuilder::new(root);
+    root_builder.add(root.join(".git").join("info").join("exclude"));
+    if let Ok(matcher) = root_builder.build() {
+      matchers.push(matcher);
+    }
+
+    for entry in project_walker(root, true).build().filter_map(Result::ok) {
+      if entry.file_name() != ".gitignore" {
+        continue;
+      }
+      let Some(dir) = entry.path().parent() else {
+        continue;
+      };
+      let mut builder = GitignoreBuilder::new(dir);
+      builder.add(entry.path());
+      if let Ok(matcher) = builder.build() {
+        matchers.push(matcher);
+      }
+    }
+
+    Self { matchers }
+  }
+
+  fn is_ignored(&self, root: &Path, path: &Path) -> bool {
+    let Ok(relative) = path.strip_prefix(root) else {
+      return true;
+    };
+    if relative.components().any(|c| c.as_os_str() == ".git") {
+      return true;
+    }
+
+    let is_dir = path.is_dir();
+    self.matchers.iter().any(|matcher| {
+      path.starts_with(matcher.path())
+        && matcher
+          .matched_path_or_any_parents(path, is_dir)
+          .is_ignore()
+    })
+  }
+}
+
+fn record(changes: &mut BTreeMap<PathBuf, Change>, path: PathBuf, change: Change) {
+  let previous = changes.get(&path).copied();
+  match (previous, change) {
+    // Created and removed within one batch: nothing for the UI to see.
+    (Some(Change::Created), Change::Deleted) => {
+      changes.remove(&path);
+    }
+    (Some(Change::Created), Change::Modified) => {}
+    (Some(Change::Deleted), Change::Created) => {
+      changes.insert(path, Change::Modified);
+    }
+    _ => {
+      changes.insert(path, change);
+    }
+  }
+}
+
+fn collect_event(changes: &mut BTreeMap<PathBuf, Change>, event: Event) {
+  let mut paths = event.paths.into_iter();
+  match event.kind {
+    EventKind::Create(_) => paths.for_each(|p| record(changes, p, Change::Created)),
+    EventKind::Remove(_) => paths.for_each(|p| record(changes, p, Change::Deleted)),
+    EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+      paths.for_each(|p| record(changes, p, Change::Deleted))
+    }
+    EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+      paths.for_each(|p| record(changes, p, Change::Created))
+    }
+    EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+      if let Some(from) = paths.next() {
+        record(changes, from, Change::Deleted);
+      }
+      paths.for_each(|p| record(changes, p, Change::Created));
+    }
+    EventKind::Modify(_) => paths.for_each(|p| record(changes, p, Change::Modified)),
+    _ => {}
+  }
+}
+
+fn run_debouncer(
+  app: AppHandle,
+  window: String,
+  root: PathBuf,
+  project_dir: String,
+  rx: mpsc::Receiver<notify::Result<Event>>,
+) {
+  let mut rules = IgnoreRules::load(&root);
+
+  // `recv` errors once the watcher (and with it the sender) is dropped.
+  while let Ok(first) = rx.recv() {
+    let mut changes = BTreeMap::new();
+    if let Ok(event) = first {
+      collect_event(&mut changes, event);
+    }
+
+    let started = Instant::now();
+    while started.elapsed() < MAX_BATCH_DELAY {
+      match rx.recv_timeout(DEBOUNCE) {
+        Ok(Ok(event)) => collect_event(&mut changes, event),
+        Ok(Err(_)) => {}
+        Err(mpsc::RecvTimeoutError::Timeout) => break,
+        Err(mpsc::RecvTimeoutError::Disconnected) => return,
+      }
+    }
+
+    if changes.keys().any(|p| p.file_name().is_some_and(|n| n == ".gitignore")) {
+      rules = IgnoreRules::load(&root);
+    }
+
+    let mut payload = FsChangedEvent {
+      project_dir: project_dir.clone(),
+      created: Vec::new(),
+      modified: Vec::new(),
+      deleted: Vec::new(),
+    };
+
+    for (path, change) in changes {
+      if rules.is_ignored(&root, &path) {
+        continue;
+      }
+      let display = relative_display(&root, &path);
+      match change {
+        Change::Created => payload.created.push(display),
+        Change::Modified => payload.modified.push(display),
+        Change::Deleted => payload.deleted.push(display),
+      }
+    }
+
+    if payload.created.is_empty() && payload.modified.is_empty() && payload.deleted.is_empty() {
+      continue;
+    }
+
+    let _ = app.emit_to(window.as_str(), FS_CHANGED_EVENT, payload);
+  }
+}
+
+impl WatcherManager {
+  fn info_locked(state: &HashMap<String, ProjectWatcher>, window: &str) -> WatcherInfo {
+    let watcher = state.get(window);
+    WatcherInfo {
+      watching: watcher.is_some(),
+      project_dir: watcher.map(|w| w.project_dir.clone()),
+    }
+  }
+
+  /// Stops watching for `window`, e.g. once it's closed.
+  pub fn remove(&self, window: &str) {
+    self.inner.lock().expect("watcher mutex poisoned").remove(window);
+  }
+}
+
+/// Watches `project_dir` for the calling window, replacing what it watched.
+/// Changes are sent to that window only.
+#[tauri::command]
+pub fn project_watch_start(
+  app: AppHandle,
+  window: WebviewWindow,
+  manager: State<WatcherManager>,
+  project_dir: String,
+) -> Result<WatcherInfo, OpenWorkError> {
+  let root = project_root(&project_dir)?;
+  let project_dir = root.to_string_lossy().to_string();
+  let label = window.label().to_string();
+
+  let mut state = manager.inner.lock().expect("watcher mutex poisoned");
+
+  // One project per window; replace the window's previous watcher.
+  state.remove(&label);
+
+  let (tx, rx) = mpsc::channel();
+  let mut watcher = notify::recommended_watcher(move |res| {
+    let _ = tx.send(res);
+  })
+  .map_err(|e| format!("Failed to create file watcher: {e}"))?;
+
+  watcher
+    .watch(&root, RecursiveMode::Recursive)
+    .map_err(|e| format!("Failed to watch {}: {e}", root.display()))?;
+
+  let thread_project_dir = project_dir.clone();
+  let thread_label = label.clone();
+  thread::spawn(move || run_debouncer(app, thread_label, root, thread_project_dir, rx));
+
+  state.insert(
+    label.clone(),
+    ProjectWatcher {
+      project_dir,
+      _watcher: watcher,
+    },
+  );
+
+  Ok(WatcherManager::info_locked(&state, &label))
+}
+
+#[tauri::command]
+pub fn project_watch_stop(window: WebviewWindow, manager: State<WatcherManager>) -> WatcherInfo {
+  let mut state = manager.inner.lock().expect("watcher mutex poisoned");
+  state.remove(window.label());
+  WatcherManager::info_locked(&state, window.label())
+}
+
+#[tauri::command]
+pub fn project_watch_info(window: WebviewWindow, manager: State<WatcherManager>) -> WatcherInfo {
+  let state = manager.inner.lock().expect("watcher mutex poisoned");
+  WatcherManager::info_locked(&state, window.label())
+}
@@ -0,0 +1,355 @@
+//! Webhooks for engine, task and budget events.
+//!
+//! Each configured URL receives a JSON `POST` for the events it selected,
+//! from the backend, so it works while no window is open:
+//!
+//! ```text
+//! {"event": "engineCrashed", "text": "...", "timestamp": 1700000000000, "data": {...}}
+//! ```
+//!
+//! `text` is a one-line summary, which is what Slack's incoming webhooks
+//! display. With a secret, `X-OpenWork-Signature: sha256=<hex>` is the
+//! HMAC-SHA256 of the body, as GitHub signs its webhooks. Delivery uses the
+//! download proxy and is retried a few times; failures are only logged.
+
+use std::{
+  sync::RwLock,
+  thread,
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tauri::AppHandle;
+
+use crate::{
+  consent, download,
+  error::{ErrorCode, OpenWorkError},
+  redact,
+  store::{read_state, write_state},
+};
+
+pub const WEBHOOKS_FILE: &str = "webhooks.json";
+
+const SIGNATURE_HEADER: &str = "X-OpenWork-Signature";
+const EVENT_HEADER: &str = "X-OpenWork-Event";
+const ATTEMPTS: u32 = 3;
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+const MAX_HOOKS: usize = 20;
+const SHA256_BLOCK: usize = 64;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum WebhookEvent {
+  /// The engine exited without being stopped.
+  EngineCrashed,
+  /// A project task ran for at least `long_task_secs`.
+  TaskFinished,
+  /// A budget went over its limit.
+  BudgetExceeded,
+  /// Sent by `webhook_test` only.
+  Test,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Webhook {
+  pub id: String,
+  pub url: String,
+  /// Signs each body when set.
+  pub secret: Option<String>,
+  pub events: Vec<WebhookEvent>,
+  pub enabled: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WebhookSettings {
+  pub hooks: Vec<Webhook>,
+  /// Tasks shorter than this don't trigger `taskFinished`.
+  pub long_task_secs: u64,
+}
+
+impl Default for WebhookSettings {
+  fn default() -> Self {
+    Self {
+      hooks: Vec::new(),
+      long_task_secs: 120,
+    }
+  }
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookDelivery {
+  pub ok: bool,
+  pub status: Option<u16>,
+  pub error: Option<String>,
+}
+
+static ACTIVE: RwLock<Option<WebhookSettings>> = RwLock::new(None);
+
+fn active() -> WebhookSettings {
+  ACTIVE
+    .read()
+    .expect("webhook settings lock poisoned")
+    .clone()
+    .unwrap_or_default()
+}
+
+fn now_ms() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_millis() as u64)
+    .unwrap_or(0)
+}
+
+/// Secrets and URLs (Slack's carry a token) are kept out of logs.
+fn set_active(settings: WebhookSettings) {
+  for hook in &settings.hooks {
+    redact::register_secret(&hook.url);
+    if let Some(secret) = &hook.secret {
+      redact::register_secret(secret);
+    }
+  }
+  *ACTIVE.write().expect("webhook settings lock poisoned") = Some(settings);
+}
+
+pub fn init(app: &AppHandle) {
+  let settings = read_state::<WebhookSettings>(app, WEBHOOKS_FILE).unwrap_or_else(|e| {
+    tracing::warn!(error = %e, "failed to load webhook settings");
+    WebhookSettings::default()
+  });
+  set_active(settings);
+}
+
+/// HMAC-SHA256 (RFC 2104) of `message` under `key`, as lowercase hex.
+fn hmac_sha256(key: &[u8], message: &[u8]) -> String {
+  let mut block = [0u8; SHA256_BLOCK];
+  if key.len() > SHA256_BLOCK {
+    block[..32].copy_from_slice(&Sha256::digest(key));
+  } else {
+    block[..key.len()].copy_from_slice(key);
+  }
+  let pad = |byte: u8| block.map(|b| b ^ byte);
+  let inner = Sha256::new()
+    .chain_update(pad(0x36))
+    .chain_update(message)
+    .finalize();
+  let outer = Sha256::new()
+    .chain_update(pad(0x5c))
+    .chain_update(inner)
+    .finalize();
+  outer.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn validate(settings: WebhookSettings) -> Result<WebhookSettings, OpenWorkError> {
+  if settings.hooks.len() > MAX_HOOKS {
+    return Err(OpenWorkError::invalid_argument(format!(
+      "At most {MAX_HOOKS} webhooks are supported"
+    )));
+  }
+  let hooks = settings
+    .hooks
+    .into_iter()
+    .map(|mut hook| {
+      hook.url = hook.url.trim().to_string();
+      let parsed = reqwest::Url::parse(&hook.url)
+        .map_err(|e| OpenWorkError::invalid_argument(format!("Invalid webhook URL {}: {e}", hook.url)))?;
+      if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+        return Err(OpenWorkError::invalid_argument(format!(
+          "Webhook URL must be http:// or https://: {}",
+          hook.url
+        )));
+      }
+      hook.secret = hook.secret.filter(|secret| !secret.is_empty());
+      let mut events = Vec::new();
+      for event in hook.events {
+        if !events.contains(&event) {
+          events.push(event);
+        }
+      }
+      hook.events = events;
+      if hook.id.trim().is_empty() {
+        hook.id = consent::random_token(12);
+      }
+      Ok(hook)
+    })
+    .collect::<Result<_, _>>()?;
+  Ok(WebhookSettings { hooks, ..settings })
+}
+
+/// Posts `body` to `hook`, retrying connection failures and server errors.
+fn deliver(hook: &Webhook, event: WebhookEvent, body: &str) -> WebhookDelivery {
+  let client = match download::client() {
+    Ok(client) => client,
+    Err(e) => {
+      return WebhookDelivery {
+        ok: false,
+        status: None,
+        error: Some(e),
+      }
+    }
+  };
+  let event_name = serde_json::to_value(event)
+    .ok()
+    .and_then(|value| value.as_str().map(str::to_string))
+    .unwrap_or_default();
+  let mut last = WebhookDelivery {
+    ok: false,
+    status: None,
+    error: None,
+  };
+  for attempt in 1..=ATTEMPTS {
+    let mut request = client
+      .post(&hook.url)
+      .header("Content-Type", "application/json")
+      .header(EVENT_HEADER, &event_name)
+      .body(body.to_string());
+    if let Some(secret) = &hook.secret {
+      let signature = hmac_sha256(secret.as_bytes(), body.as_bytes());
+      request = request.header(SIGNATURE_HEADER, format!("sha256={signature}"));
+    }
+    let retry = match request.send() {
+      Ok(response) => {
+        let status = response.status();
+        last = WebhookDelivery {
+          ok: status.is_success(),
+          status: Some(status.as_u16()),
+          error: (!status.is_success()).then(|| format!("Answered {status}")),
+        };
+        status.is_server_error()
+      }
+      Err(e) => {
+        last = WebhookDelivery {
+          ok: false,
+          status: None,
+          error: Some(redact::redact(&e.to_string())),
+        };
+        e.is_connect() || e.is_timeout()
+      }
+    };
+    if !retry || attempt == ATTEMPTS {
+      break;
+    }
+    thread::sleep(RETRY_DELAY * attempt);
+  }
+  last
+}
+
+fn body(event: WebhookEvent, text: &str, data: &Value) -> String {
+  json!({ "event": event, "text": text, "timestamp": now_ms(), "data": data }).to_string()
+}
+
+/// Sends `event` to every enabled webhook that selected it, in the
+/// background.
+pub fn send(event: WebhookEvent, text: &str, data: Value) {
+  let hooks: Vec<Webhook> = active()
+    .hooks
+    .into_iter()
+    .filter(|hook| hook.enabled && hook.events.contains(&event))
+    .collect();
+  if hooks.is_empty() {
+    return;
+  }
+  let body = body(event, text, &data);
+  thread::spawn(move || {
+    for hook in hooks {
+      let delivery = deliver(&hook, event, &body);
+      if delivery.ok {
+        tracing::info!(hook = %hook.id, event = ?event, "webhook delivered");
+      } else {
+        tracing::warn!(hook = %hook.id, event = ?event, status = ?delivery.status, error = ?delivery.error, "webhook failed");
+      }
+    }
+  });
+}
+
+/// Whether a task that ran for `duration_ms` counts as long.
+pub fn is_long_task(duration_ms: u64) -> bool {
+  duration_ms >= active().long_task_secs.saturating_mul(1000)
+}
+
+#[tauri::command]
+pub fn webhooks_get() -> WebhookSettings {
+  active()
+}
+
+#[tauri::command]
+pub fn webhooks_set(app: AppHandle, settings: WebhookSettings) -> Result<WebhookSettings, OpenWorkError> {
+  let settings = validate(settings)?;
+  write_state(&app, WEBHOOKS_FILE, &settings)?;
+  set_active(settings.clone());
+  Ok(settings)
+}
+
+/// Sends a `test` event to one webhook and waits for the answer.
+// Runs off the main thread, since delivery retries with a delay.
+#[tauri::command(async)]
+pub fn webhook_test(id: String) -> Result<WebhookDelivery, OpenWorkError> {
+  let hook = active()
+    .hooks
+    .into_iter()
+    .find(|hook| hook.id == id)
+    .ok_or_else(|| OpenWorkError::new(ErrorCode::NotFound, format!("No webhook {id}")))?;
+  let text = "OpenWork webhook test";
+  Ok(deliver(
+    &hook,
+    WebhookEvent::Test,
+    &body(WebhookEvent::Test, text, &json!({})),
+  ))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn signs_like_rfc_4231() {
+    assert_eq!(
+      hmac_sha256(&[0x0b; 20], b"Hi There"),
+      "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+    );
+    assert_eq!(
+      hmac_sha256(b"Jefe", b"what do ya want for nothing?"),
+      "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+    // Keys longer than a block are hashed first.
+    assert_eq!(
+      hmac_sha256(
+        &[0xaa; 131],
+        b"Test Using Larger Than Block-Size Key - Hash Key First"
+      ),
+      "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+    );
+  }
+
+  #[test]
+  fn validates_hooks() {
+    let hook = |url: &str| Webhook {
+      url: url.to_string(),
+      secret: Some(String::new()),
+      events: vec![WebhookEvent::EngineCrashed, WebhookEvent::EngineCrashed],
+      enabled: true,
+      ..Webhook::default()
+    };
+    let valid = validate(WebhookSettings {
+      hooks: vec![hook(" https://hooks.slack.com/services/T/B/x ")],
+      ..WebhookSettings::default()
+    })
+    .expect("valid hook");
+    let saved = &valid.hooks[0];
+    assert_eq!(saved.url, "https://hooks.slack.com/services/T/B/x");
+    assert!(!saved.id.is_empty() && saved.secret.is_none());
+    assert_eq!(saved.events, vec![WebhookEvent::EngineCrashed]);
+
+    for url in ["ftp://example.com", "not a url"] {
+      let settings = WebhookSettings {
+        hooks: vec![hook(url)],
+        ..WebhookSettings::default()
+      };
+      assert!(validate(settings).is_err());
+    }
+  }
+}
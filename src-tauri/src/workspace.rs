@@ -0,0 +1,281 @@
+use std::{
+  collections::HashMap,
+  sync::Mutex,
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+use crate::{
+  check_engine_start, engine_lock, engine_started,
+  error::{ErrorCode, OpenWorkError},
+  launch_engine, lock_engines,
+  project::project_root,
+  reap_engine,
+  store::{read_state, write_state},
+  EngineInfo, EngineManager, EngineState,
+};
+
+pub const WORKSPACES_FILE: &str = "workspaces.json";
+
+/// A named group of project directories that are worked on together.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Workspace {
+  pub id: String,
+  pub name: String,
+  pub members: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct WorkspaceFile {
+  workspaces: Vec<Workspace>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceEngines {
+  pub workspace_id: String,
+  pub engines: Vec<EngineInfo>,
+}
+
+/// Engines started for workspace members, keyed by workspace id then project dir.
+#[derive(Default)]
+pub struct WorkspaceManager {
+  inner: Mutex<HashMap<String, HashMap<String, EngineState>>>,
+}
+
+impl WorkspaceManager {
+  /// The workspace whose engine is running `project_dir`, if any.
+  pub fn workspace_for_project(&self, project_dir: &str) -> Option<String> {
+    let mut state = lock_engines(&self.inner);
+    state
+      .iter_mut()
+      .find(|(_, engines)| {
+        engines
+          .get_mut(project_dir)
+          .is_some_and(|engine| EngineManager::snapshot_locked(engine).running)
+      })
+      .map(|(id, _)| id.clone())
+  }
+
+  /// Takes the engines of workspace `id` out, to be stopped after the lock
+  /// is released.
+  fn take(&self, id: &str) -> HashMap<String, EngineState> {
+    lock_engines(&self.inner).remove(id).unwrap_or_default()
+  }
+}
+
+/// Stops engines taken out of `WorkspaceManager`, running their stop hooks.
+fn stop_all(app: &AppHandle, engines: impl IntoIterator<Item = EngineState>) {
+  for mut engine in engines {
+    let project_dir = engine.project_dir.clone();
+    reap_engine(app, EngineManager::detach_locked(&mut engine), project_dir);
+  }
+}
+
+fn load(app: &AppHandle) -> Result<WorkspaceFile, OpenWorkError> {
+  read_state(app, WORKSPACES_FILE)
+}
+
+fn save(app: &AppHandle, file: &WorkspaceFile) -> Result<(), String> {
+  write_state(app, WORKSPACES_FILE, file)
+}
+
+fn normalize_name(name: &str) -> Result<String, OpenWorkError> {
+  let name = name.trim().to_string();
+  if name.is_empty() {
+    return Err(OpenWorkError::invalid_argument("name is required"));
+  }
+  Ok(name)
+}
+
+fn normalize_members(members: Vec<String>) -> Result<Vec<String>, OpenWorkError> {
+  let mut normalized: Vec<String> = Vec::new();
+  for member in members {
+    let root = project_root(&member)?;
+    let member = root.to_string_lossy().to_string();
+    if !normalized.contains(&member) {
+      normalized.push(member);
+    }
+  }
+  if normalized.is_empty() {
+    return Err(OpenWorkError::invalid_argument("a workspace needs at least one member"));
+  }
+  Ok(normalized)
+}
+
+fn new_workspace_id() -> String {
+  let nanos = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_nanos())
+    .unwrap_or_default();
+  format!("ws-{nanos:x}")
+}
+
+fn find<'a>(file: &'a mut WorkspaceFile, id: &str) -> Result<&'a mut Workspace, OpenWorkError> {
+  file
+    .workspaces
+    .iter_mut()
+    .find(|w| w.id == id)
+    .ok_or_else(|| OpenWorkError::new(ErrorCode::NotFound, format!("Unknown workspace: {id}")))
+}
+
+fn snapshot(workspace_id: &str, engines: &mut HashMap<String, EngineState>) -> WorkspaceEngines {
+  let mut infos: Vec<EngineInfo> = engines
+    .values_mut()
+    .map(EngineManager::snapshot_locked)
+    .collect();
+  infos.sort_by(|a, b| a.project_dir.cmp(&b.project_dir));
+  WorkspaceEngines {
+    workspace_id: workspace_id.to_string(),
+    engines: infos,
+  }
+}
+
+#[tauri::command]
+pub fn workspace_list(app: AppHandle) -> Result<Vec<Workspace>, OpenWorkError> {
+  Ok(load(&app)?.workspaces)
+}
+
+#[tauri::command]
+pub fn workspace_create(
+  app: AppHandle,
+  name: String,
+  members: Vec<String>,
+) -> Result<Workspace, OpenWorkError> {
+  let workspace = Workspace {
+    id: new_workspace_id(),
+    name: normalize_name(&name)?,
+    members: normalize_members(members)?,
+  };
+
+  let mut file = load(&app)?;
+  file.workspaces.push(workspace.clone());
+  save(&app, &file)?;
+
+  Ok(workspace)
+}
+
+#[tauri::command]
+pub fn workspace_update(
+  app: AppHandle,
+  id: String,
+  name: Option<String>,
+  members: Option<Vec<String>>,
+) -> Result<Workspace, OpenWorkError> {
+  let mut file = load(&app)?;
+  let workspace = find(&mut file, id.trim())?;
+
+  if let Some(name) = name {
+    workspace.name = normalize_name(&name)?;
+  }
+  if let Some(members) = members {
+    workspace.members = normalize_members(members)?;
+  }
+
+  let updated = workspace.clone();
+  save(&app, &file)?;
+  Ok(updated)
+}
+
+#[tauri::command]
+pub fn workspace_delete(
+  app: AppHandle,
+  manager: State<WorkspaceManager>,
+  id: String,
+) -> Result<Vec<Workspace>, OpenWorkError> {
+  let id = id.trim().to_string();
+  let mut file = load(&app)?;
+  find(&mut file, &id)?;
+
+  stop_all(&app, manager.take(&id).into_values());
+
+  file.workspaces.retain(|w| w.id != id);
+  save(&app, &file)?;
+  Ok(file.workspaces)
+}
+
+/// Starts one engine per member that is not already running, with the
+/// same checks and hooks as a window's engine. Engines are stopped and
+/// spawned outside the workspace lock.
+#[tauri::command]
+pub fn workspace_start(
+  app: AppHandle,
+  manager: State<WorkspaceManager>,
+  engines: State<EngineManager>,
+  id: String,
+) -> Result<WorkspaceEngines, OpenWorkError> {
+  let id = id.trim().to_string();
+  let mut file = load(&app)?;
+  let workspace = find(&mut file, &id)?.clone();
+  crate::telemetry::record("feature.workspace_start");
+
+  let (removed, pending) = {
+    let mut state = lock_engines(&manager.inner);
+    let running = state.entry(id.clone()).or_default();
+    // Engines for members that were removed since the last start.
+    let removed_dirs: Vec<String> = running
+      .keys()
+      .filter(|dir| !workspace.members.contains(dir))
+      .cloned()
+      .collect();
+    let removed: Vec<EngineState> = removed_dirs
+      .iter()
+      .filter_map(|dir| running.remove(dir))
+      .collect();
+    let pending: Vec<String> = workspace
+      .members
+      .iter()
+      .filter(|member| {
+        !running
+          .get_mut(member.as_str())
+          .is_some_and(|engine| EngineManager::snapshot_locked(engine).running)
+      })
+      .cloned()
+      .collect();
+    (removed, pending)
+  };
+  stop_all(&app, removed);
+
+  for member in pending {
+    if let Some(other) = check_engine_start(&app, &engines, None, &member)? {
+      return Err(engine_lock::conflict(&other));
+    }
+    let started = launch_engine(&app, &member)?;
+    let (displaced, info) = {
+      let mut state = lock_engines(&manager.inner);
+      let running = state.entry(id.clone()).or_default();
+      // A concurrent start may have filled the slot in the meantime.
+      let displaced = running.insert(member.clone(), started);
+      let info = running.get_mut(&member).map(EngineManager::snapshot_locked);
+      (displaced, info)
+    };
+    stop_all(&app, displaced);
+    if let Some(info) = info {
+      engine_started(&app, &info);
+    }
+  }
+
+  let mut state = lock_engines(&manager.inner);
+  Ok(snapshot(&id, state.entry(id.clone()).or_default()))
+}
+
+#[tauri::command]
+pub fn workspace_stop(app: AppHandle, manager: State<WorkspaceManager>, id: String) -> WorkspaceEngines {
+  let id = id.trim().to_string();
+  stop_all(&app, manager.take(&id).into_values());
+  snapshot(&id, &mut HashMap::new())
+}
+
+#[tauri::command]
+pub fn workspace_info(manager: State<WorkspaceManager>, id: String) -> WorkspaceEngines {
+  let id = id.trim().to_string();
+  let mut state = lock_engines(&manager.inner);
+  match state.get_mut(&id) {
+    Some(engines) => snapshot(&id, engines),
+    None => snapshot(&id, &mut HashMap::new()),
+  }
+}
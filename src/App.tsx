@@ -10,6 +10,8 @@ import {
   onMount,
 } from "solid-js";
 
+import { listen } from "@tauri-apps/api/event";
+import { getCurrentWebview } from "@tauri-apps/api/webview";
 import { applyEdits, modify, parse } from "jsonc-parser";
 
 import type {
@@ -50,15 +52,26 @@ import PartView from "./components/PartView";
 import TextInput from "./components/TextInput";
 import { createClient, unwrap, waitForHealthy } from "./lib/opencode";
 import {
+  classifyDroppedPath,
+  deepLinkSkillTake,
+  ENGINE_STOPPED_EVENT,
   engineDoctor,
   engineInfo,
   engineInstall,
   engineStart,
   engineStop,
   importSkill,
+  importSkillArchive,
+  INSTALL_SKILL_EVENT,
+  isOpenWorkError,
+  launchProjectTake,
+  OPEN_PROJECT_EVENT,
   opkgInstall,
   pickDirectory,
+  projectWindowOpen,
   readOpencodeConfig,
+  windowProject,
+  withConfirmation,
   writeOpencodeConfig,
   type EngineDoctorResult,
   type EngineInfo,
@@ -380,6 +393,13 @@ function safeStringify(value: unknown) {
   }
 }
 
+/** Display text for a rejected promise; backend commands reject with an `OpenWorkError`. */
+function describeError(e: unknown, fallback?: string) {
+  if (isOpenWorkError(e)) return e.message;
+  if (e instanceof Error) return e.message;
+  return fallback ?? safeStringify(e);
+}
+
 function normalizeEvent(raw: unknown): OpencodeEvent | null {
   if (!raw || typeof raw !== "object") {
     return null;
@@ -514,6 +534,8 @@ function lastUserModelFromMessages(list: MessageWithParts[]): ModelRef | null {
 
 export default function App() {
   const [view, setView] = createSignal<View>("onboarding");
+  // Set in windows opened for a single project; those never touch the main window's saved state.
+  const [boundProject, setBoundProject] = createSignal<string | null>(null);
   const [mode, setMode] = createSignal<Mode | null>(null);
   const [onboardingStep, setOnboardingStep] = createSignal<OnboardingStep>("mode");
   const [rememberModeChoice, setRememberModeChoice] = createSignal(false);
@@ -678,7 +700,7 @@ export default function App() {
       setPluginList(next);
     } catch (e) {
       setPluginList([]);
-      setPluginStatus(e instanceof Error ? e.message : "Failed to parse opencode.json");
+      setPluginStatus(describeError(e, "Failed to parse opencode.json"));
     }
   };
 
@@ -772,17 +794,17 @@ export default function App() {
     }
   }
 
-  async function refreshEngineDoctor() {
+  async function refreshEngineDoctor(force = false) {
     if (!isTauriRuntime()) return;
 
     try {
-      const result = await engineDoctor();
+      const result = await engineDoctor(force);
       setEngineDoctorResult(result);
       setEngineDoctorCheckedAt(Date.now());
     } catch (e) {
       setEngineDoctorResult(null);
       setEngineDoctorCheckedAt(Date.now());
-      setEngineInstallLogs(e instanceof Error ? e.message : safeStringify(e));
+      setEngineInstallLogs(describeError(e));
     }
   }
 
@@ -801,7 +823,7 @@ export default function App() {
     });
   }
 
-  async function connectToServer(nextBaseUrl: string, directory?: string) {
+  async function connectToServer(nextBaseUrl: string, directory?: string, authToken?: string) {
     setError(null);
     setBusy(true);
     setBusyLabel("Connecting");
@@ -809,7 +831,7 @@ export default function App() {
     setSseConnected(false);
 
     try {
-      const nextClient = createClient(nextBaseUrl, directory);
+      const nextClient = createClient(nextBaseUrl, directory, authToken);
       const health = await waitForHealthy(nextClient, { timeoutMs: 12_000 });
 
       setClient(nextClient);
@@ -830,7 +852,7 @@ export default function App() {
     } catch (e) {
       setClient(null);
       setConnectedVersion(null);
-      setError(e instanceof Error ? e.message : safeStringify(e));
+      setError(describeError(e));
       return false;
     } finally {
       setBusy(false);
@@ -868,7 +890,7 @@ export default function App() {
         return false;
       }
     } catch (e) {
-      setEngineInstallLogs(e instanceof Error ? e.message : safeStringify(e));
+      setEngineInstallLogs(describeError(e));
     }
 
     setError(null);
@@ -881,13 +903,17 @@ export default function App() {
       setEngine(info);
 
       if (info.baseUrl) {
-        const ok = await connectToServer(info.baseUrl, info.projectDir ?? undefined);
+        const ok = await connectToServer(
+          info.baseUrl,
+          info.projectDir ?? undefined,
+          info.authToken ?? undefined,
+        );
         if (!ok) return false;
       }
 
       return true;
     } catch (e) {
-      setError(e instanceof Error ? e.message : safeStringify(e));
+      setError(describeError(e));
       return false;
     } finally {
       setBusy(false);
@@ -922,7 +948,7 @@ export default function App() {
       setOnboardingStep("mode");
       setView("onboarding");
     } catch (e) {
-      setError(e instanceof Error ? e.message : safeStringify(e));
+      setError(describeError(e));
     } finally {
       setBusy(false);
       setBusyLabel(null);
@@ -983,7 +1009,7 @@ export default function App() {
       await selectSession(session.id);
       setView("session");
     } catch (e) {
-      setError(e instanceof Error ? e.message : safeStringify(e));
+      setError(describeError(e));
     } finally {
       setBusy(false);
       setBusyLabel(null);
@@ -1041,7 +1067,7 @@ export default function App() {
 
       await loadSessions(c);
     } catch (e) {
-      setError(e instanceof Error ? e.message : safeStringify(e));
+      setError(describeError(e));
     } finally {
       setBusy(false);
       setBusyLabel(null);
@@ -1113,7 +1139,7 @@ export default function App() {
         [session.id]: model,
       }));
     } catch (e) {
-      setError(e instanceof Error ? e.message : "Unknown error");
+      setError(describeError(e, "Unknown error"));
     } finally {
       setBusy(false);
     }
@@ -1162,7 +1188,7 @@ export default function App() {
       }
     } catch (e) {
       setSkills([]);
-      setSkillsStatus(e instanceof Error ? e.message : "Failed to load skills");
+      setSkillsStatus(describeError(e, "Failed to load skills"));
     }
   }
 
@@ -1197,7 +1223,7 @@ export default function App() {
     } catch (e) {
       setPluginConfig(null);
       setPluginList([]);
-      setPluginStatus(e instanceof Error ? e.message : "Failed to load opencode.json");
+      setPluginStatus(describeError(e, "Failed to load opencode.json"));
     }
   }
 
@@ -1258,13 +1284,15 @@ export default function App() {
       });
       const updated = applyEdits(raw, edits);
 
-      await writeOpencodeConfig(scope, targetDir, updated);
+      await withConfirmation((confirmationId) =>
+        writeOpencodeConfig(scope, targetDir, updated, { confirmationId }),
+      );
       if (isManualInput) {
         setPluginInput("");
       }
       await refreshPlugins(scope);
     } catch (e) {
-      setPluginStatus(e instanceof Error ? e.message : "Failed to update opencode.json");
+      setPluginStatus(describeError(e, "Failed to update opencode.json"));
     }
   }
 
@@ -1302,7 +1330,7 @@ export default function App() {
 
       await refreshSkills();
     } catch (e) {
-      setError(e instanceof Error ? e.message : safeStringify(e));
+      setError(describeError(e));
     } finally {
       setBusy(false);
     }
@@ -1320,7 +1348,7 @@ export default function App() {
     );
   }
 
-  async function importLocalSkill() {
+  async function importLocalSkill(sourceOverride?: string, fromArchive = false) {
     if (mode() !== "host" || !isTauriRuntime()) {
       setError("Skill import is only available in Host mode.");
       return;
@@ -1337,7 +1365,7 @@ export default function App() {
     setSkillsStatus(null);
 
     try {
-      const selection = await pickDirectory({ title: "Select skill folder" });
+      const selection = sourceOverride ?? (await pickDirectory({ title: "Select skill folder" }));
       const sourceDir =
         typeof selection === "string" ? selection : Array.isArray(selection) ? selection[0] : null;
 
@@ -1345,7 +1373,9 @@ export default function App() {
         return;
       }
 
-      const result = await importSkill(targetDir, sourceDir, { overwrite: false });
+      const result = fromArchive
+        ? await importSkillArchive(targetDir, sourceDir, { overwrite: false })
+        : await importSkill(targetDir, sourceDir, { overwrite: false });
       if (!result.ok) {
         setSkillsStatus(result.stderr || result.stdout || `Import failed (${result.status})`);
       } else {
@@ -1354,7 +1384,7 @@ export default function App() {
 
       await refreshSkills();
     } catch (e) {
-      setError(e instanceof Error ? e.message : "Unknown error");
+      setError(describeError(e, "Unknown error"));
     } finally {
       setBusy(false);
     }
@@ -1371,7 +1401,7 @@ export default function App() {
       unwrap(await c.permission.reply({ requestID, reply }));
       await refreshPendingPermissions(c);
     } catch (e) {
-      setError(e instanceof Error ? e.message : "Unknown error");
+      setError(describeError(e, "Unknown error"));
     } finally {
       setPermissionReplyBusy(false);
     }
@@ -1392,6 +1422,113 @@ export default function App() {
     setAuthorizedDirs((current) => current.filter((_, i) => i !== index));
   }
 
+  // Projects handed over by `openwork <dir>`, either at launch or from a second launch.
+  async function openLaunchProject() {
+    if (boundProject()) return false;
+    const dir = await launchProjectTake().catch(() => null);
+    if (!dir) return false;
+
+    setProjectDir(dir);
+    if (!authorizedDirs().includes(dir)) {
+      setAuthorizedDirs([...authorizedDirs(), dir]);
+    }
+
+    setMode("host");
+    setOnboardingStep("connecting");
+    const ok = await startHost();
+    if (!ok) {
+      setOnboardingStep("host");
+    }
+    return true;
+  }
+
+  // Skills from approved `openwork://install-skill` links wait until a project is connected.
+  async function installLinkedSkill() {
+    if (mode() !== "host" || !client() || boundProject()) return;
+    const source = await deepLinkSkillTake().catch(() => null);
+    if (!source) return;
+
+    setView("dashboard");
+    setTab("skills");
+    await installFromOpenPackage(source);
+  }
+
+  async function openProjectWindow() {
+    try {
+      const selection = await pickDirectory({ title: "Open project in a new window" });
+      const dir = typeof selection === "string" ? selection : Array.isArray(selection) ? selection[0] : null;
+      if (!dir) return;
+      await projectWindowOpen(dir);
+    } catch (e) {
+      setError(describeError(e));
+    }
+  }
+
+  async function handleDroppedPath(path: string) {
+    try {
+      const dropped = await classifyDroppedPath(path);
+      for (const action of dropped.actions) {
+        if (action === "importSkill" && mode() === "host" && client()) {
+          const fromArchive = dropped.kind === "archive";
+          if (fromArchive || dropped.skills.length === 1) {
+            setView("dashboard");
+            setTab("skills");
+            await importLocalSkill(fromArchive ? dropped.path : dropped.skills[0], fromArchive);
+            return;
+          }
+        }
+        if (action === "openProject" && !client() && !boundProject()) {
+          setProjectDir(dropped.path);
+          if (!authorizedDirs().includes(dropped.path)) {
+            setAuthorizedDirs([...authorizedDirs(), dropped.path]);
+          }
+          setMode("host");
+          setOnboardingStep("connecting");
+          const ok = await startHost();
+          if (!ok) {
+            setOnboardingStep("host");
+          }
+          return;
+        }
+        if (action === "openInNewWindow" || (action === "openProject" && client())) {
+          await projectWindowOpen(dropped.path);
+          return;
+        }
+      }
+    } catch (e) {
+      setError(describeError(e));
+    }
+  }
+
+  onMount(() => {
+    if (!isTauriRuntime()) return;
+    const unlisten = [
+      getCurrentWebview().onDragDropEvent((event) => {
+        if (event.payload.type !== "drop" || busy()) return;
+        const [path] = event.payload.paths;
+        if (path) void handleDroppedPath(path);
+      }),
+      listen(OPEN_PROJECT_EVENT, () => {
+        void openLaunchProject();
+      }),
+      listen(INSTALL_SKILL_EVENT, () => {
+        void installLinkedSkill();
+      }),
+      listen(ENGINE_STOPPED_EVENT, () => {
+        if (mode() === "host" && !boundProject()) void stopHost();
+      }),
+    ];
+    onCleanup(() => {
+      unlisten.forEach((pending) => void pending.then((stop) => stop()));
+    });
+  });
+
+  createEffect(() => {
+    if (isTauriRuntime() && mode() === "host" && client()) {
+      void installLinkedSkill();
+    }
+  });
+
   onMount(async () => {
     const modePref = readModePreference();
     if (modePref) {
@@ -1456,6 +1593,23 @@ export default function App() {
       setBaseUrl(info.baseUrl);
     }
 
+    if (isTauriRuntime()) {
+      const bound = await windowProject().catch(() => null);
+      if (bound) {
+        setBoundProject(bound);
+        setProjectDir(bound);
+        setMode("host");
+        setOnboardingStep("connecting");
+        const ok = await startHost();
+        if (!ok) {
+          setOnboardingStep("host");
+        }
+        return;
+      }
+
+      if (await openLaunchProject()) return;
+    }
+
     // Auto-continue based on saved preference.
     if (!modePref) return;
 
@@ -1464,7 +1618,11 @@ export default function App() {
 
       if (info?.running && info.baseUrl) {
         setOnboardingStep("connecting");
-        const ok = await connectToServer(info.baseUrl, info.projectDir ?? undefined);
+        const ok = await connectToServer(
+          info.baseUrl,
+          info.projectDir ?? undefined,
+          info.authToken ?? undefined,
+        );
         if (!ok) {
           setMode(null);
           setOnboardingStep("mode");
@@ -1534,6 +1692,7 @@ export default function App() {
 
   createEffect(() => {
     if (typeof window === "undefined") return;
+    if (boundProject()) return;
     try {
       window.localStorage.setItem("openwork.projectDir", projectDir());
     } catch {
@@ -1836,7 +1995,7 @@ export default function App() {
                               setProjectDir(path);
                             }
                           } catch (e) {
-                            setError(e instanceof Error ? e.message : "Unknown error");
+                            setError(describeError(e, "Unknown error"));
                           }
                         }}
                         disabled={busy()}
@@ -1907,7 +2066,7 @@ export default function App() {
                               );
                             }
                           } catch (e) {
-                            setError(e instanceof Error ? e.message : safeStringify(e));
+                            setError(describeError(e));
                           }
                         }}
                         disabled={busy()}
@@ -1957,7 +2116,7 @@ export default function App() {
                           variant="secondary"
                           onClick={async () => {
                             setEngineInstallLogs(null);
-                            await refreshEngineDoctor();
+                            await refreshEngineDoctor(true);
                           }}
                           disabled={busy()}
                         >
@@ -1990,7 +2149,7 @@ export default function App() {
                                 try {
                                   const result = await engineInstall();
                                   const combined = `${result.stdout}${result.stderr ? `\n${result.stderr}` : ""}`.trim();
-                                  setEngineInstallLogs(combined || null);
+                                  setEngineInstallLogs(`[sandbox: ${result.sandbox}]\n${combined}`.trim());
 
                                   if (!result.ok) {
                                     setError(
@@ -2000,7 +2159,7 @@ export default function App() {
 
                                   await refreshEngineDoctor();
                                 } catch (e) {
-                                  setError(e instanceof Error ? e.message : safeStringify(e));
+                                  setError(describeError(e));
                                 } finally {
                                   setBusy(false);
                                   setBusyLabel(null);
@@ -2220,6 +2379,7 @@ export default function App() {
                         const ok = await connectToServer(
                           engine()!.baseUrl!,
                           engine()!.projectDir ?? undefined,
+                          engine()!.authToken ?? undefined,
                         );
                         if (!ok) {
                           setMode(null);
@@ -2578,7 +2738,7 @@ export default function App() {
                 <div class="text-sm font-medium text-white">Import local skill</div>
                 <Button
                   variant="secondary"
-                  onClick={importLocalSkill}
+                  onClick={() => importLocalSkill()}
                   disabled={busy() || mode() !== "host" || !isTauriRuntime()}
                 >
                   <Upload size={16} />
@@ -2899,6 +3059,9 @@ export default function App() {
                   <Button variant="danger" onClick={stopHost} disabled={busy()}>
                     Stop engine
                   </Button>
+                  <Button variant="outline" onClick={openProjectWindow} disabled={busy()}>
+                    Open project in new window
+                  </Button>
                 </Show>
                 <Show when={mode() === "client"}>
                   <Button variant="outline" onClick={stopHost} disabled={busy()}>
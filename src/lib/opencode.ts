@@ -17,10 +17,17 @@ export function unwrap<T>(result: FieldsResult<T>): NonNullable<T> {
   throw new Error(message || "Unknown error");
 }
 
-export function createClient(baseUrl: string, directory?: string) {
+export const ENGINE_AUTH_USERNAME = "opencode";
+
+export function engineAuthHeader(authToken: string) {
+  return `Basic ${btoa(`${ENGINE_AUTH_USERNAME}:${authToken}`)}`;
+}
+
+export function createClient(baseUrl: string, directory?: string, authToken?: string) {
   return createOpencodeClient({
     baseUrl,
     directory,
+    headers: authToken ? { Authorization: engineAuthHeader(authToken) } : undefined,
   });
 }
 
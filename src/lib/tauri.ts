@@ -1,4 +1,86 @@
-import { invoke } from "@tauri-apps/api/core";
+import { invoke as tauriInvoke, type InvokeArgs } from "@tauri-apps/api/core";
+
+export type OpenWorkErrorCode =
+  | "ENGINE_NOT_FOUND"
+  | "ENGINE_NOT_RUNNING"
+  | "ENGINE_START_FAILED"
+  | "ENGINE_UNREACHABLE"
+  | "ENGINE_REQUEST_FAILED"
+  | "PORT_IN_USE"
+  | "CONFIG_INVALID"
+  | "INVALID_ARGUMENT"
+  | "NOT_FOUND"
+  | "ALREADY_EXISTS"
+  | "NEEDS_APPROVAL"
+  | "PENDING_CONFIRMATION"
+  | "CONFIRMATION_INVALID"
+  | "CANCELLED"
+  | "BUDGET_EXCEEDED"
+  | "TOOL_NOT_FOUND"
+  | "INSUFFICIENT_SPACE"
+  | "OFFLINE"
+  | "IO"
+  | "NETWORK"
+  | "INTERNAL";
+
+/** What every failing command rejects with. Branch on `code`; `message` is for display. */
+export type OpenWorkError = {
+  code: OpenWorkErrorCode;
+  message: string;
+  /** The English message, when `message` was translated for the locale. */
+  originalMessage?: string;
+  details?: unknown;
+  retryable: boolean;
+};
+
+export function isOpenWorkError(error: unknown): error is OpenWorkError {
+  return (
+    typeof error === "object" &&
+    error !== null &&
+    typeof (error as OpenWorkError).code === "string" &&
+    typeof (error as OpenWorkError).message === "string"
+  );
+}
+
+type PerfSample = {
+  command: string;
+  durationMs: number;
+  ok: boolean;
+};
+
+const PERF_FLUSH_MS = 10_000;
+// Reporting on the reporting commands would only measure ourselves.
+const PERF_UNTRACKED = new Set(["perf_record", "perf_stats", "perf_reset"]);
+
+let perfSamples: PerfSample[] = [];
+let perfFlushTimer: ReturnType<typeof setTimeout> | null = null;
+
+function flushPerfSamples() {
+  perfFlushTimer = null;
+  const samples = perfSamples;
+  perfSamples = [];
+  if (samples.length) {
+    tauriInvoke("perf_record", { samples }).catch(() => undefined);
+  }
+}
+
+/** `invoke` that records round-trip time and outcome for `perfStats`. */
+async function invoke<T>(command: string, args?: InvokeArgs): Promise<T> {
+  if (PERF_UNTRACKED.has(command)) {
+    return tauriInvoke<T>(command, args);
+  }
+
+  const started = performance.now();
+  let ok = false;
+  try {
+    const result = await tauriInvoke<T>(command, args);
+    ok = true;
+    return result;
+  } finally {
+    perfSamples.push({ command, durationMs: performance.now() - started, ok });
+    perfFlushTimer ??= setTimeout(flushPerfSamples, PERF_FLUSH_MS);
+  }
+}
 
 export type EngineInfo = {
   running: boolean;
@@ -7,6 +89,20 @@ export type EngineInfo = {
   hostname: string | null;
   port: number | null;
   pid: number | null;
+  authToken: string | null;
+  permissionProfile: string | null;
+  /** The environment profile the engine was started with; see `envProfileSelect`. */
+  activeEnvProfile: string | null;
+  /** The engine belongs to another OpenWork instance; stopping only lets go of it. */
+  attached: boolean;
+  /** Set when the engine runs in the project's devcontainer; its paths are under `container.workspaceFolder`. */
+  container: ContainerTarget | null;
+};
+
+export type ContainerTarget = {
+  id: string;
+  workspaceFolder: string;
+  address: string;
 };
 
 export type EngineDoctorResult = {
@@ -15,11 +111,48 @@ export type EngineDoctorResult = {
   resolvedPath: string | null;
   version: string | null;
   supportsServe: boolean;
+  /** Whether `opencode serve` can write JSON logs, which the engine log reads with their levels. */
+  jsonLogs: boolean;
   notes: string[];
+  /** Node version managers installed for this user. */
+  versionManagers: Array<"volta" | "fnm" | "nvm" | "asdf" | "mise">;
+  /** Flatpak or Snap packaging, which changes where programs and config live. */
+  sandbox: "none" | "flatpak" | "snap";
+  /** Architectures the resolved binary is built for; empty for launcher scripts. */
+  binaryArch: Arch[];
+  hostArch: Arch | null;
+  /** Nix or NixOS, where opencode comes from nix profiles. */
+  nix: "none" | "nix" | "nixos";
+  /** The GitHub CLI, used to open pull requests. */
+  gh: GhStatus;
+  /** Ollama and LM Studio, for running models locally. */
+  localModels: LocalRuntime[];
+  /** opencode config and credential files other users can read; see `configPermissionsFix`. */
+  exposedConfigs: ExposedFile[];
+};
+
+export type ExposedFile = {
+  path: string;
+  /** Who else can read it, e.g. "mode 644" or "Everyone". */
+  access: string;
+};
+
+export type GhStatus = {
+  found: boolean;
+  path: string | null;
+  version: string | null;
+  authenticated: boolean | null;
 };
 
-export async function engineStart(projectDir: string): Promise<EngineInfo> {
-  return invoke<EngineInfo>("engine_start", { projectDir });
+export type Arch = "x86_64" | "aarch64" | "x86" | "arm";
+
+/**
+ * `envProfile` selects that environment profile for the project first, for this and later starts.
+ * When another OpenWork instance runs an engine for the project, this fails with `ALREADY_EXISTS` and
+ * `details.conflict: "engine"` unless `attach` is set, which uses that engine instead.
+ */
+export async function engineStart(projectDir: string, envProfile?: string, attach?: boolean): Promise<EngineInfo> {
+  return invoke<EngineInfo>("engine_start", { projectDir, envProfile: envProfile ?? null, attach: attach ?? null });
 }
 
 export async function engineStop(): Promise<EngineInfo> {
@@ -30,8 +163,66 @@ export async function engineInfo(): Promise<EngineInfo> {
   return invoke<EngineInfo>("engine_info");
 }
 
-export async function engineDoctor(): Promise<EngineDoctorResult> {
-  return invoke<EngineDoctorResult>("engine_doctor");
+/** Opens `projectDir` in its own window with its own engine; resolves to the window label. */
+export async function projectWindowOpen(projectDir: string): Promise<string> {
+  return invoke<string>("project_window_open", { projectDir });
+}
+
+/** The project this window was opened for, or null in the main window. */
+export async function windowProject(): Promise<string | null> {
+  return invoke<string | null>("window_project");
+}
+
+export const UPDATE_PROGRESS_EVENT = "update://progress";
+export const UPDATE_INSTALLED_EVENT = "update://installed";
+/** Sent with an `AppUpdate` when a background check finds a release. */
+export const UPDATE_AVAILABLE_EVENT = "update://available";
+
+export type AppUpdate = {
+  version: string;
+  currentVersion: string;
+  notes: string | null;
+  date: string | null;
+};
+
+export type UpdateProgress = {
+  downloaded: number;
+  total: number | null;
+};
+
+/** Resolves to null when OpenWork is up to date. */
+export async function appUpdateCheck(): Promise<AppUpdate | null> {
+  return invoke<AppUpdate | null>("app_update_check");
+}
+
+/** Downloads and installs the update, then restarts the app. */
+export async function appUpdateInstall(): Promise<void> {
+  return invoke<void>("app_update_install");
+}
+
+/**
+ * URL that loads `relativePath` from this window's project through the
+ * `project-asset://` protocol, for image and HTML previews.
+ */
+export function projectAssetUrl(relativePath: string): string {
+  const path = relativePath
+    .replace(/\\/g, "/")
+    .split("/")
+    .filter(Boolean)
+    .map(encodeURIComponent)
+    .join("/");
+  const isWindows = typeof navigator !== "undefined" && /windows/i.test(navigator.userAgent);
+  return isWindows ? `http://project-asset.localhost/${path}` : `project-asset://localhost/${path}`;
+}
+
+/** `force` skips the cached lookup, e.g. for a manual re-check. */
+export async function engineDoctor(force?: boolean): Promise<EngineDoctorResult> {
+  return invoke<EngineDoctorResult>("engine_doctor", { force: force ?? null });
+}
+
+/** Makes the files in `exposedConfigs` readable by the user only; returns any still exposed. */
+export async function configPermissionsFix(): Promise<ExposedFile[]> {
+  return invoke<ExposedFile[]>("config_permissions_fix");
 }
 
 export async function pickDirectory(options?: {
@@ -55,23 +246,204 @@ export type ExecResult = {
   stderr: string;
 };
 
-export async function engineInstall(): Promise<ExecResult> {
-  return invoke<ExecResult>("engine_install");
+export type InstallResult = ExecResult & {
+  sandbox: "bwrap" | "firejail" | "restricted";
+};
+
+/** Fails with `CANCELLED` when stopped through `engineInstallCancel`. */
+export async function engineInstall(sandbox?: boolean): Promise<InstallResult> {
+  return invoke<InstallResult>("engine_install", { sandbox: sandbox ?? null });
+}
+
+/** Stops a running `engineInstall`; `false` when none is running. */
+export async function engineInstallCancel(): Promise<boolean> {
+  return invoke<boolean>("engine_install_cancel");
 }
 
 export async function opkgInstall(projectDir: string, pkg: string): Promise<ExecResult> {
   return invoke<ExecResult>("opkg_install", { projectDir, package: pkg });
 }
 
+export const OPKG_QUEUE_EVENT = "opkg://queue";
+
+export type QueuedPackageOperation = {
+  id: number;
+  operation: string;
+  package: string;
+  /** Requests waiting on this one, including the first. */
+  requests: number;
+};
+
+/** Emitted as `opkg://queue` whenever a project's package queue changes. */
+export type OpkgQueueStatus = {
+  projectDir: string;
+  running: QueuedPackageOperation | null;
+  waiting: QueuedPackageOperation[];
+};
+
+export async function opkgQueueStatus(projectDir: string): Promise<OpkgQueueStatus> {
+  return invoke<OpkgQueueStatus>("opkg_queue_status", { projectDir });
+}
+
+export const SKILL_IMPORT_PROGRESS_EVENT = "skill://import-progress";
+
+/** How symlinks in a skill are copied; "preserve" only keeps links that stay inside the skill. */
+export type SymlinkPolicy = "skip" | "follow" | "preserve";
+
+export type SkillImportProgress = {
+  name: string;
+  filesCopied: number;
+  filesTotal: number;
+  bytesCopied: number;
+  bytesTotal: number;
+};
+
+export type SkillDependencies = {
+  skills: string[];
+  tools: string[];
+  packages: string[];
+};
+
+/** The `dependencies` a skill declares in its SKILL.md frontmatter, checked against the project. */
+export type DependencyReport = {
+  declared: SkillDependencies;
+  missingSkills: string[];
+  missingTools: string[];
+  /** opkg can't report installed packages, so these are the declared ones OpenWork hasn't installed for the project. */
+  missingPackages: string[];
+  installedPackages: string[];
+  errors: string[];
+};
+
+/** `stderr` names the missing dependencies, if any. */
+export type SkillImportResult = ExecResult & {
+  dependencies: DependencyReport;
+};
+
 export async function importSkill(
   projectDir: string,
   sourceDir: string,
-  options?: { overwrite?: boolean },
-): Promise<ExecResult> {
-  return invoke<ExecResult>("import_skill", {
+  options?: {
+    overwrite?: boolean;
+    permanent?: boolean;
+    confirmationId?: string;
+    /** File or folder names to skip (one `*` allowed); defaults to node_modules and .git. */
+    exclude?: string[];
+    symlinks?: SymlinkPolicy;
+    /** Installs under this name instead of the folder's own. */
+    name?: string;
+    /** Installs the opkg packages the skill declares. */
+    installDependencies?: boolean;
+  },
+): Promise<SkillImportResult> {
+  return invoke<SkillImportResult>("import_skill", {
     projectDir,
     sourceDir,
     overwrite: options?.overwrite ?? false,
+    permanent: options?.permanent ?? false,
+    confirmationId: options?.confirmationId ?? null,
+    exclude: options?.exclude ?? null,
+    symlinks: options?.symlinks ?? null,
+    name: options?.name ?? null,
+    installDependencies: options?.installDependencies ?? null,
+  });
+}
+
+/**
+ * `details` of the ALREADY_EXISTS error a skill import rejects with when an
+ * existing skill's name differs only in case. Resolve by importing again with
+ * `overwrite` (replaces the existing skill) or a different `name`.
+ */
+export type SkillCaseConflict = {
+  conflict: "case";
+  existing: string;
+  requested: string;
+  resolutions: Array<"replace" | "rename">;
+};
+
+export const ARCHIVE_PROGRESS_EVENT = "archive://progress";
+
+export type ArchiveProgress = {
+  archive: string;
+  entries: number;
+  /** Known up front for zips only. */
+  entriesTotal: number | null;
+  bytes: number;
+};
+
+/** Imports the single skill in a .zip, .tar or .tar.gz. */
+export async function importSkillArchive(
+  projectDir: string,
+  archivePath: string,
+  options?: {
+    overwrite?: boolean;
+    permanent?: boolean;
+    confirmationId?: string;
+    name?: string;
+    installDependencies?: boolean;
+  },
+): Promise<SkillImportResult> {
+  return invoke<SkillImportResult>("import_skill_archive", {
+    projectDir,
+    archivePath,
+    overwrite: options?.overwrite ?? false,
+    permanent: options?.permanent ?? false,
+    confirmationId: options?.confirmationId ?? null,
+    name: options?.name ?? null,
+    installDependencies: options?.installDependencies ?? null,
+  });
+}
+
+/**
+ * Imports a skill from a link: a .zip, .tar or .tar.gz URL, or a GitHub
+ * repository or folder page. Progress comes as `download://progress`, then
+ * `archive://progress`.
+ */
+export async function importSkillFromUrl(
+  projectDir: string,
+  url: string,
+  options?: {
+    overwrite?: boolean;
+    permanent?: boolean;
+    confirmationId?: string;
+    name?: string;
+    installDependencies?: boolean;
+  },
+): Promise<SkillImportResult> {
+  return invoke<SkillImportResult>("import_skill_from_url", {
+    projectDir,
+    url,
+    overwrite: options?.overwrite ?? false,
+    permanent: options?.permanent ?? false,
+    confirmationId: options?.confirmationId ?? null,
+    name: options?.name ?? null,
+    installDependencies: options?.installDependencies ?? null,
+  });
+}
+
+/** Checks an imported skill's dependencies again; `installDependencies` installs its opkg packages. */
+export async function skillDependenciesResolve(
+  projectDir: string,
+  name: string,
+  installDependencies?: boolean,
+): Promise<DependencyReport> {
+  return invoke<DependencyReport>("skill_dependencies_resolve", {
+    projectDir,
+    name,
+    installDependencies: installDependencies ?? null,
+  });
+}
+
+export async function removeSkill(
+  projectDir: string,
+  name: string,
+  options?: { permanent?: boolean; confirmationId?: string },
+): Promise<ExecResult> {
+  return invoke<ExecResult>("remove_skill", {
+    projectDir,
+    name,
+    permanent: options?.permanent ?? false,
+    confirmationId: options?.confirmationId ?? null,
   });
 }
 
@@ -92,6 +464,1883 @@ export async function writeOpencodeConfig(
   scope: "project" | "global",
   projectDir: string,
   content: string,
+  options?: { confirmationId?: string },
+): Promise<ExecResult> {
+  return invoke<ExecResult>("write_opencode_config", {
+    scope,
+    projectDir,
+    content,
+    confirmationId: options?.confirmationId ?? null,
+  });
+}
+
+export async function gitCommit(
+  projectDir: string,
+  message: string,
+  paths: string[],
+  options?: { force?: boolean },
+): Promise<ExecResult> {
+  return invoke<ExecResult>("git_commit", {
+    projectDir,
+    message,
+    paths,
+    force: options?.force ?? false,
+  });
+}
+
+export async function gitBranchCreate(
+  projectDir: string,
+  name: string,
+  options?: { checkout?: boolean; force?: boolean },
 ): Promise<ExecResult> {
-  return invoke<ExecResult>("write_opencode_config", { scope, projectDir, content });
+  return invoke<ExecResult>("git_branch_create", {
+    projectDir,
+    name,
+    checkout: options?.checkout ?? true,
+    force: options?.force ?? false,
+  });
+}
+
+export async function gitBranchSwitch(
+  projectDir: string,
+  name: string,
+  options?: { force?: boolean },
+): Promise<ExecResult> {
+  return invoke<ExecResult>("git_branch_switch", {
+    projectDir,
+    name,
+    force: options?.force ?? false,
+  });
+}
+
+export const GITHUB_OUTPUT_EVENT = "github://output";
+
+export type GithubOutputEvent = {
+  projectDir: string;
+  step: "commit" | "push" | "pr";
+  stream: "stdout" | "stderr";
+  line: string;
+};
+
+export type PullRequestResult = {
+  url: string;
+  branch: string;
+  base: string | null;
+  committed: boolean;
+};
+
+export async function githubCreatePr(
+  projectDir: string,
+  title: string,
+  body: string,
+  options?: { base?: string; branch?: string; draft?: boolean; commitAll?: boolean },
+): Promise<PullRequestResult> {
+  return invoke<PullRequestResult>("github_create_pr", {
+    projectDir,
+    title,
+    body,
+    base: options?.base ?? null,
+    branch: options?.branch ?? null,
+    draft: options?.draft ?? null,
+    commitAll: options?.commitAll ?? null,
+  });
+}
+
+export type ProjectSearchOptions = {
+  caseSensitive?: boolean;
+  contextLines?: number;
+  maxResults?: number;
+  includeHidden?: boolean;
+  filesOnly?: boolean;
+};
+
+export type SearchMatch = {
+  path: string;
+  lineNumber: number | null;
+  line: string | null;
+  before: string[];
+  after: string[];
+};
+
+export type ProjectSearchResult = {
+  matches: SearchMatch[];
+  truncated: boolean;
+  backend: "ripgrep" | "builtin";
+};
+
+export async function projectSearch(
+  projectDir: string,
+  query: string,
+  options?: ProjectSearchOptions,
+): Promise<ProjectSearchResult> {
+  return invoke<ProjectSearchResult>("project_search", { projectDir, query, options });
+}
+
+export type WatcherInfo = {
+  watching: boolean;
+  projectDir: string | null;
+};
+
+export type FsChangedEvent = {
+  projectDir: string;
+  created: string[];
+  modified: string[];
+  deleted: string[];
+};
+
+export const FS_CHANGED_EVENT = "project://fs-changed";
+
+/** Watches `projectDir` for this window, replacing what it watched; changes arrive in this window only. */
+export async function projectWatchStart(projectDir: string): Promise<WatcherInfo> {
+  return invoke<WatcherInfo>("project_watch_start", { projectDir });
+}
+
+export async function projectWatchStop(): Promise<WatcherInfo> {
+  return invoke<WatcherInfo>("project_watch_stop");
+}
+
+export async function projectWatchInfo(): Promise<WatcherInfo> {
+  return invoke<WatcherInfo>("project_watch_info");
+}
+
+export type LanguageStat = {
+  language: string;
+  files: number;
+  bytes: number;
+};
+
+export type ProjectStats = {
+  files: number;
+  totalBytes: number;
+  languages: LanguageStat[];
+  opencode: {
+    skills: number;
+    commands: number;
+    agents: number;
+  };
+};
+
+export async function projectStats(projectDir: string): Promise<ProjectStats> {
+  return invoke<ProjectStats>("project_stats", { projectDir });
+}
+
+export type Workspace = {
+  id: string;
+  name: string;
+  members: string[];
+};
+
+export type WorkspaceEngines = {
+  workspaceId: string;
+  engines: EngineInfo[];
+};
+
+export async function workspaceList(): Promise<Workspace[]> {
+  return invoke<Workspace[]>("workspace_list");
+}
+
+export async function workspaceCreate(name: string, members: string[]): Promise<Workspace> {
+  return invoke<Workspace>("workspace_create", { name, members });
+}
+
+export async function workspaceUpdate(
+  id: string,
+  changes: { name?: string; members?: string[] },
+): Promise<Workspace> {
+  return invoke<Workspace>("workspace_update", {
+    id,
+    name: changes.name ?? null,
+    members: changes.members ?? null,
+  });
+}
+
+export async function workspaceDelete(id: string): Promise<Workspace[]> {
+  return invoke<Workspace[]>("workspace_delete", { id });
+}
+
+export async function workspaceStart(id: string): Promise<WorkspaceEngines> {
+  return invoke<WorkspaceEngines>("workspace_start", { id });
+}
+
+export async function workspaceStop(id: string): Promise<WorkspaceEngines> {
+  return invoke<WorkspaceEngines>("workspace_stop", { id });
+}
+
+export async function workspaceInfo(id: string): Promise<WorkspaceEngines> {
+  return invoke<WorkspaceEngines>("workspace_info", { id });
+}
+
+export type DetectedPackage = {
+  name: string;
+  path: string;
+  absolutePath: string;
+  manager: "pnpm" | "npm" | "cargo";
+  hasOpencodeConfig: boolean;
+};
+
+export type ProjectPackages = {
+  workspaces: string[];
+  packages: DetectedPackage[];
+};
+
+export async function projectDetectPackages(projectDir: string): Promise<ProjectPackages> {
+  return invoke<ProjectPackages>("project_detect_packages", { projectDir });
+}
+
+export type ArchiveResult = {
+  path: string;
+  files: number;
+  bytes: number;
+};
+
+/** Replacing an existing file needs confirmation; see `withConfirmation`. */
+export async function projectArchive(
+  projectDir: string,
+  dest: string,
+  options?: { includeOpencode?: boolean; confirmationId?: string },
+): Promise<ArchiveResult> {
+  return invoke<ArchiveResult>("project_archive", {
+    projectDir,
+    dest,
+    includeOpencode: options?.includeOpencode ?? true,
+    confirmationId: options?.confirmationId ?? null,
+  });
+}
+
+export type RecentFile = {
+  path: string;
+  bytes: number;
+  modifiedMs: number;
+};
+
+export async function projectRecentFiles(projectDir: string, limit?: number): Promise<RecentFile[]> {
+  return invoke<RecentFile[]>("project_recent_files", { projectDir, limit: limit ?? null });
+}
+
+export type DiskUsageEntry = {
+  name: string;
+  isDir: boolean;
+  bytes: number;
+  files: number;
+  ignoredBytes: number;
+};
+
+export type DiskUsage = {
+  totalBytes: number;
+  ignoredBytes: number;
+  entries: DiskUsageEntry[];
+};
+
+export async function projectDiskUsage(projectDir: string): Promise<DiskUsage> {
+  return invoke<DiskUsage>("project_disk_usage", { projectDir });
+}
+
+// Session payloads are passed through as returned by the opencode server (see @opencode-ai/sdk types).
+export async function sessionsList<T = unknown>(): Promise<T[]> {
+  return invoke<T[]>("sessions_list");
+}
+
+export async function sessionGet<T = unknown>(id: string): Promise<T> {
+  return invoke<T>("session_get", { id });
+}
+
+export async function sessionMessages<T = unknown>(id: string): Promise<T[]> {
+  return invoke<T[]>("session_messages", { id });
+}
+
+export type SessionExportResult = {
+  path: string;
+  format: "markdown" | "html" | "json";
+  messages: number;
+};
+
+/** Replacing an existing file needs confirmation; see `withConfirmation`. */
+export async function sessionExport(
+  id: string,
+  format: "markdown" | "html" | "json",
+  dest: string,
+  confirmationId?: string,
+): Promise<SessionExportResult> {
+  return invoke<SessionExportResult>("session_export", {
+    id,
+    format,
+    dest,
+    confirmationId: confirmationId ?? null,
+  });
+}
+
+/** Events of this window's engine; each window gets its own engine's stream. */
+export const ENGINE_EVENT = "engine://event";
+export const RELAY_STATUS_EVENT = "engine://relay-status";
+
+export type RelayedEvent<T = unknown> = {
+  seq: number;
+  /** Label of the window whose engine sent it. */
+  window: string;
+  baseUrl: string;
+  projectDir: string | null;
+  event: T;
+};
+
+export type RelayStatus = {
+  running: boolean;
+  connected: boolean;
+  baseUrl: string | null;
+  reconnected: boolean;
+  lastSeq: number;
+  lastError: string | null;
+};
+
+export const ENGINE_LOG_EVENT = "engine://log";
+
+export type EngineLogEvent = {
+  projectDir: string;
+  stream: "stdout" | "stderr";
+  level: "debug" | "info" | "warn" | "error";
+  category: "request" | "providerAuth" | "provider" | "server" | "other";
+  timestamp: string | null;
+  service: string | null;
+  message: string;
+  fields: Record<string, string>;
+};
+
+export const OPEN_PROJECT_EVENT = "app://open-project";
+
+/** Project folder passed to `openwork <dir>`, returned once and then cleared. */
+export async function launchProjectTake(): Promise<string | null> {
+  return invoke<string | null>("launch_project_take");
+}
+
+export const RECENT_PROJECTS_EVENT = "app://recent-projects";
+/** The app menu stopped the main window's engine; payload is the new EngineInfo. */
+export const ENGINE_STOPPED_EVENT = "engine://stopped";
+
+/** Recently opened project folders, most recent first. */
+export async function recentProjectsList(): Promise<string[]> {
+  return invoke<string[]>("recent_projects_list");
+}
+
+export async function recentProjectsClear(): Promise<string[]> {
+  return invoke<string[]>("recent_projects_clear");
+}
+
+export type DroppedKind = "project" | "skill" | "skillCollection" | "archive" | "folder" | "file";
+export type DropAction = "openProject" | "openInNewWindow" | "importSkill" | "attachFile";
+
+export type DroppedPath = {
+  path: string;
+  name: string;
+  kind: DroppedKind;
+  markers: string[];
+  skills: string[];
+  /** Most fitting first. */
+  actions: DropAction[];
+};
+
+export async function classifyDroppedPath(path: string): Promise<DroppedPath> {
+  return invoke<DroppedPath>("classify_dropped_path", { path });
+}
+
+export const INSTALL_SKILL_EVENT = "app://install-skill";
+
+/** Skill source from an approved `openwork://install-skill` link, returned once and then cleared. */
+export async function deepLinkSkillTake(): Promise<string | null> {
+  return invoke<string | null>("deep_link_skill_take");
+}
+
+export async function eventRelayStart(): Promise<RelayStatus> {
+  return invoke<RelayStatus>("event_relay_start");
+}
+
+export async function eventRelayStop(): Promise<RelayStatus> {
+  return invoke<RelayStatus>("event_relay_stop");
+}
+
+/** The relay as seen by this window. */
+export async function eventRelayStatus(): Promise<RelayStatus> {
+  return invoke<RelayStatus>("event_relay_status");
+}
+
+/** This window's buffered events after `afterSeq`. */
+export async function eventRelayBackfill<T = unknown>(afterSeq: number): Promise<RelayedEvent<T>[]> {
+  return invoke<RelayedEvent<T>[]>("event_relay_backfill", { afterSeq });
+}
+
+export type HistorySession = {
+  id: string;
+  projectDir: string | null;
+  title: string | null;
+  createdAt: number | null;
+  updatedAt: number | null;
+  messageCount: number;
+};
+
+export type HistoryMessage = {
+  id: string;
+  sessionId: string;
+  role: string | null;
+  summary: string | null;
+  createdAt: number | null;
+};
+
+export async function historySessions(options?: {
+  projectDir?: string;
+  limit?: number;
+}): Promise<HistorySession[]> {
+  return invoke<HistorySession[]>("history_sessions", {
+    projectDir: options?.projectDir ?? null,
+    limit: options?.limit ?? null,
+  });
+}
+
+export async function historySession(id: string): Promise<HistorySession | null> {
+  return invoke<HistorySession | null>("history_session", { id });
+}
+
+export async function historyMessages(sessionId: string): Promise<HistoryMessage[]> {
+  return invoke<HistoryMessage[]>("history_messages", { sessionId });
+}
+
+export type UsageRange = "today" | "7d" | "30d" | "month" | "all";
+
+export type UsageTotals = {
+  inputTokens: number;
+  outputTokens: number;
+  reasoningTokens: number;
+  cacheReadTokens: number;
+  cacheWriteTokens: number;
+  cost: number;
+  messages: number;
+};
+
+export type UsageBucket = UsageTotals & { key: string };
+
+export type UsageSummary = {
+  range: UsageRange;
+  since: number | null;
+  totals: UsageTotals;
+  byDay: UsageBucket[];
+  byModel: UsageBucket[];
+  byProject: UsageBucket[];
+};
+
+export async function usageSummary(range: UsageRange, project?: string): Promise<UsageSummary> {
+  return invoke<UsageSummary>("usage_summary", { range, project: project ?? null });
+}
+
+export type NotificationPrefs = {
+  enabled: boolean;
+  sessionIdle: boolean;
+  sessionError: boolean;
+  permissionRequested: boolean;
+  whenFocused: boolean;
+};
+
+export async function notificationPrefsGet(): Promise<NotificationPrefs> {
+  return invoke<NotificationPrefs>("notification_prefs_get");
+}
+
+export async function notificationPrefsSet(prefs: NotificationPrefs): Promise<NotificationPrefs> {
+  return invoke<NotificationPrefs>("notification_prefs_set", { prefs });
+}
+
+export type StartupSettings = {
+  launchAtLogin: boolean;
+  /** Start hidden in the tray at login and keep running when the window is closed. */
+  background: boolean;
+  /** Project whose engine is started in the background at login. */
+  pinnedProject: string | null;
+};
+
+export async function startupSettingsGet(): Promise<StartupSettings> {
+  return invoke<StartupSettings>("startup_settings_get");
+}
+
+export async function startupSettingsSet(settings: StartupSettings): Promise<StartupSettings> {
+  return invoke<StartupSettings>("startup_settings_set", { settings });
+}
+
+export const QUEUE_FLUSHED_EVENT = "queue://flushed";
+export const QUEUE_FAILED_EVENT = "queue://failed";
+
+export type QueuedPrompt = {
+  id: string;
+  projectDir: string;
+  sessionId: string;
+  text: string;
+  createdAt: number;
+  attempts: number;
+  lastError: string | null;
+};
+
+export async function queuePrompt(
+  projectDir: string,
+  sessionId: string,
+  text: string,
+): Promise<QueuedPrompt> {
+  return invoke<QueuedPrompt>("queue_prompt", { projectDir, sessionId, text });
+}
+
+export async function queueList(): Promise<QueuedPrompt[]> {
+  return invoke<QueuedPrompt[]>("queue_list");
+}
+
+export async function queueRemove(id: string): Promise<QueuedPrompt[]> {
+  return invoke<QueuedPrompt[]>("queue_remove", { id });
+}
+
+export async function queueClear(): Promise<QueuedPrompt[]> {
+  return invoke<QueuedPrompt[]>("queue_clear");
+}
+
+export type StagedAttachment = {
+  path: string;
+  filename: string;
+  mime: string;
+  bytes: number;
+  part: { type: "file"; mime: string; filename: string; url: string };
+};
+
+export async function attachmentStage(
+  projectDir: string,
+  sourcePath: string,
+  sessionId?: string,
+): Promise<StagedAttachment> {
+  return invoke<StagedAttachment>("attachment_stage", {
+    projectDir,
+    sourcePath,
+    sessionId: sessionId ?? null,
+  });
+}
+
+export async function attachmentClear(sessionId?: string): Promise<void> {
+  return invoke<void>("attachment_clear", { sessionId: sessionId ?? null });
+}
+
+export const BUDGET_WARNING_EVENT = "budget://warning";
+export const BUDGET_EXCEEDED_EVENT = "budget://exceeded";
+
+export type BudgetSettings = {
+  monthly: number | null;
+  projects: Record<string, number>;
+  warnAt: number;
+  blockEngineStart: boolean;
+};
+
+export type BudgetStatus = {
+  projectDir: string | null;
+  limit: number;
+  spent: number;
+  level: "ok" | "warning" | "exceeded";
+};
+
+export async function budgetGet(): Promise<BudgetSettings> {
+  return invoke<BudgetSettings>("budget_get");
+}
+
+export async function budgetSet(settings: BudgetSettings): Promise<BudgetSettings> {
+  return invoke<BudgetSettings>("budget_set", { settings });
+}
+
+export async function budgetStatus(projectDir?: string): Promise<BudgetStatus[]> {
+  return invoke<BudgetStatus[]>("budget_status", { projectDir: projectDir ?? null });
+}
+
+export type AllowlistSettings = {
+  enabled: boolean;
+  roots: string[];
+};
+
+export type NeedsApprovalError = {
+  path: string;
+  message: string;
+};
+
+/** Recognizes the error returned for folders outside the allowlist. */
+export function parseNeedsApproval(error: unknown): NeedsApprovalError | null {
+  if (!isOpenWorkError(error) || error.code !== "NEEDS_APPROVAL") return null;
+  const details = error.details as { path: string };
+  return { path: details.path, message: error.message };
+}
+
+export async function allowlistGet(): Promise<AllowlistSettings> {
+  return invoke<AllowlistSettings>("allowlist_get");
+}
+
+/** Approving new folders or turning the allowlist off needs confirmation; see `withConfirmation`. */
+export async function allowlistSet(settings: AllowlistSettings, confirmationId?: string): Promise<AllowlistSettings> {
+  return invoke<AllowlistSettings>("allowlist_set", { settings, confirmationId: confirmationId ?? null });
+}
+
+/** Needs confirmation; see `withConfirmation`. */
+export async function allowlistApprove(path: string, confirmationId?: string): Promise<AllowlistSettings> {
+  return invoke<AllowlistSettings>("allowlist_approve", { path, confirmationId: confirmationId ?? null });
+}
+
+export type PendingConfirmation = {
+  token: string;
+  action: string;
+  target: string;
+  message: string;
+};
+
+/** Recognizes the error destructive commands return until the user confirms. */
+export function parsePendingConfirmation(error: unknown): PendingConfirmation | null {
+  if (!isOpenWorkError(error) || error.code !== "PENDING_CONFIRMATION") return null;
+  const details = error.details as Omit<PendingConfirmation, "message">;
+  return { ...details, message: error.message };
+}
+
+/** Shows the native confirmation dialog; resolves with a one-time confirmation ID. */
+export async function confirmRequest(token: string): Promise<{ confirmationId: string }> {
+  return invoke<{ confirmationId: string }>("confirm_request", { token });
+}
+
+/**
+ * Runs a destructive command, routing a pending confirmation through the native
+ * dialog and retrying once with the issued confirmation ID.
+ */
+export async function withConfirmation<T>(run: (confirmationId?: string) => Promise<T>): Promise<T> {
+  try {
+    return await run();
+  } catch (error) {
+    const pending = parsePendingConfirmation(error);
+    if (!pending) throw error;
+    const { confirmationId } = await confirmRequest(pending.token);
+    return run(confirmationId);
+  }
+}
+
+export type EnvRules = {
+  mode: "allowlist" | "denylist";
+  allow: string[];
+  deny: string[];
+};
+
+export type EnvPolicy = {
+  engine: EnvRules;
+  installer: EnvRules;
+};
+
+export async function envPolicyGet(): Promise<EnvPolicy> {
+  return invoke<EnvPolicy>("env_policy_get");
+}
+
+export async function envPolicySet(policy: EnvPolicy): Promise<EnvPolicy> {
+  return invoke<EnvPolicy>("env_policy_set", { policy });
+}
+
+export async function envPolicyReset(): Promise<EnvPolicy> {
+  return invoke<EnvPolicy>("env_policy_reset");
+}
+
+export type PermissionAction = "allow" | "ask" | "deny";
+
+export type PermissionConfig = {
+  edit?: PermissionAction;
+  bash?: PermissionAction | Record<string, PermissionAction>;
+  webfetch?: PermissionAction;
+  doom_loop?: PermissionAction;
+  external_directory?: PermissionAction;
+};
+
+export type PermissionProfile = {
+  id: string;
+  name: string;
+  description?: string | null;
+  permission: PermissionConfig;
+  builtin?: boolean;
+};
+
+export type PermissionProfilesInfo = {
+  profiles: PermissionProfile[];
+  projectProfiles: Record<string, string>;
+  defaultProfile: string | null;
+};
+
+export async function permissionProfilesGet(): Promise<PermissionProfilesInfo> {
+  return invoke<PermissionProfilesInfo>("permission_profiles_get");
+}
+
+export async function permissionProfileSave(profile: PermissionProfile): Promise<PermissionProfilesInfo> {
+  return invoke<PermissionProfilesInfo>("permission_profile_save", { profile });
+}
+
+export async function permissionProfileDelete(id: string): Promise<PermissionProfilesInfo> {
+  return invoke<PermissionProfilesInfo>("permission_profile_delete", { id });
+}
+
+export async function permissionProfileSelect(
+  projectDir: string | null,
+  profileId: string | null,
+): Promise<PermissionProfilesInfo> {
+  return invoke<PermissionProfilesInfo>("permission_profile_select", { projectDir, profileId });
+}
+
+export type LogLevel = "trace" | "debug" | "info" | "warn" | "error" | "off";
+
+export type LogSettings = {
+  level: LogLevel;
+  modules: Record<string, LogLevel>;
+};
+
+export async function logGetLevel(): Promise<LogSettings> {
+  return invoke<LogSettings>("log_get_level");
+}
+
+export async function logSetLevel(level: LogLevel | "inherit", module?: string): Promise<LogSettings> {
+  return invoke<LogSettings>("log_set_level", { level, module: module ?? null });
+}
+
+export type DevcontainerInfo = {
+  configPath: string;
+  name: string | null;
+  workspaceFolder: string;
+  enabled: boolean;
+  cli: string | null;
+  docker: boolean;
+};
+
+export async function devcontainerDetect(projectDir: string): Promise<DevcontainerInfo | null> {
+  return invoke<DevcontainerInfo | null>("devcontainer_detect", { projectDir });
+}
+
+export async function devcontainerSetEnabled(projectDir: string, enabled: boolean): Promise<boolean> {
+  return invoke<boolean>("devcontainer_set_enabled", { projectDir, enabled });
+}
+
+export async function devcontainerMapPath(
+  projectDir: string,
+  path: string,
+  toContainer: boolean,
+  workspaceFolder?: string,
+): Promise<string> {
+  return invoke<string>("devcontainer_map_path", {
+    projectDir,
+    path,
+    toContainer,
+    workspaceFolder: workspaceFolder ?? null,
+  });
+}
+
+export type DotenvFile = {
+  name: string;
+  path: string;
+  keys: number;
+  injected: string[];
+};
+
+export type DotenvEntry = {
+  key: string;
+  value: string;
+  masked: boolean;
+  injected: boolean;
+  line: number;
+};
+
+export type DotenvContent = {
+  name: string;
+  entries: DotenvEntry[];
+};
+
+export async function dotenvFilesList(projectDir: string): Promise<DotenvFile[]> {
+  return invoke<DotenvFile[]>("dotenv_files_list", { projectDir });
+}
+
+export async function dotenvRead(projectDir: string, name: string, reveal?: boolean): Promise<DotenvContent> {
+  return invoke<DotenvContent>("dotenv_read", { projectDir, name, reveal: reveal ?? null });
+}
+
+export async function dotenvSet(
+  projectDir: string,
+  name: string,
+  key: string,
+  value: string | null,
+): Promise<DotenvContent> {
+  return invoke<DotenvContent>("dotenv_set", { projectDir, name, key, value });
+}
+
+export async function dotenvInjectSet(projectDir: string, name: string, keys: string[]): Promise<string[]> {
+  return invoke<string[]>("dotenv_inject_set", { projectDir, name, keys });
+}
+
+export type EnvProfile = {
+  name: string;
+  description: string | null;
+  vars: Record<string, string>;
+  /** Names whose values are kept in the system keychain. */
+  secretKeys: string[];
+};
+
+export type ProjectEnvProfiles = {
+  profiles: EnvProfile[];
+  /** Used by the next engine start. */
+  selected: string | null;
+};
+
+export type EnvProfileInput = {
+  name: string;
+  description?: string | null;
+  vars: Record<string, string>;
+  /** Values go to the keychain; `null` keeps the stored one. */
+  secrets: Record<string, string | null>;
+};
+
+export async function envProfilesGet(projectDir: string): Promise<ProjectEnvProfiles> {
+  return invoke<ProjectEnvProfiles>("env_profiles_get", { projectDir });
+}
+
+/** Creates or replaces the profile with `profile.name`. */
+export async function envProfileSave(projectDir: string, profile: EnvProfileInput): Promise<ProjectEnvProfiles> {
+  return invoke<ProjectEnvProfiles>("env_profile_save", { projectDir, profile });
+}
+
+export async function envProfileDelete(projectDir: string, name: string): Promise<ProjectEnvProfiles> {
+  return invoke<ProjectEnvProfiles>("env_profile_delete", { projectDir, name });
+}
+
+/** Selects the profile for the next engine start; `null` starts without one. */
+export async function envProfileSelect(projectDir: string, name: string | null): Promise<ProjectEnvProfiles> {
+  return invoke<ProjectEnvProfiles>("env_profile_select", { projectDir, name });
+}
+
+export type GitignoreChange = {
+  path: string;
+  /** Lines appended, or that would be with `preview`. */
+  added: string[];
+  applied: boolean;
+};
+
+/** Adds the entries the project's `.gitignore` doesn't cover yet; `preview` only reports them. */
+export async function gitignoreEnsure(
+  projectDir: string,
+  entries: string[],
+  preview?: boolean,
+): Promise<GitignoreChange> {
+  return invoke<GitignoreChange>("gitignore_ensure", { projectDir, entries, preview: preview ?? null });
+}
+
+/** A backend switch or health change; requests for the window now go to `baseUrl`. */
+export const BACKEND_EVENT = "engine://backend";
+
+export type Backend = "primary" | "secondary";
+
+export type BackendStatus = {
+  window: string;
+  serving: Backend;
+  /** The engine requests go to; null when none is running. */
+  baseUrl: string | null;
+  secondaryUrl: string | null;
+  primaryHealthy: boolean;
+  /** Null until the first check, and without a secondary. */
+  secondaryHealthy: boolean | null;
+};
+
+/** Registers an engine this window falls back on while its own is unhealthy. */
+export async function engineFailoverSet(baseUrl: string, authToken?: string): Promise<BackendStatus> {
+  return invoke<BackendStatus>("engine_failover_set", { baseUrl, authToken: authToken ?? null });
+}
+
+export async function engineFailoverClear(): Promise<BackendStatus> {
+  return invoke<BackendStatus>("engine_failover_clear");
+}
+
+export async function engineFailoverStatus(): Promise<BackendStatus> {
+  return invoke<BackendStatus>("engine_failover_status");
+}
+
+export const MCP_STATUS_EVENT = "mcp://status";
+export const MCP_LOG_EVENT = "mcp://log";
+
+export type McpHealth =
+  | { state: "stopped" }
+  | { state: "starting" }
+  | { state: "ready"; server: string | null; version: string | null; tools: string[] }
+  | { state: "failed"; message: string }
+  | { state: "exited"; code: number | null };
+
+export type McpServerStatus = {
+  name: string;
+  transport: "local" | "remote";
+  command: string[];
+  url: string | null;
+  environment: Record<string, string>;
+  enabled: boolean;
+  source: string;
+  health: McpHealth;
+  pid: number | null;
+  startedAt: number | null;
+};
+
+export type McpLogLine = {
+  at: number;
+  stream: "stderr" | "stdout";
+  line: string;
+};
+
+export type McpStatusEvent = {
+  projectDir: string;
+  name: string;
+  health: McpHealth;
+};
+
+export type McpLogEvent = McpLogLine & {
+  projectDir: string;
+  name: string;
+};
+
+export async function mcpServersList(projectDir: string): Promise<McpServerStatus[]> {
+  return invoke<McpServerStatus[]>("mcp_servers_list", { projectDir });
+}
+
+export async function mcpServerStart(projectDir: string, name: string): Promise<McpServerStatus> {
+  return invoke<McpServerStatus>("mcp_server_start", { projectDir, name });
+}
+
+export async function mcpServerStop(projectDir: string, name: string): Promise<McpServerStatus> {
+  return invoke<McpServerStatus>("mcp_server_stop", { projectDir, name });
+}
+
+export async function mcpServerLogs(projectDir: string, name: string): Promise<McpLogLine[]> {
+  return invoke<McpLogLine[]>("mcp_server_logs", { projectDir, name });
+}
+
+export const TERMINAL_OUTPUT_EVENT = "terminal://output";
+export const TERMINAL_EXIT_EVENT = "terminal://exit";
+
+export type TerminalInfo = {
+  id: number;
+  projectDir: string;
+  shell: string;
+  pid: number | null;
+  cols: number;
+  rows: number;
+  startedAt: number;
+};
+
+export type TerminalOutputEvent = {
+  id: number;
+  data: string;
+};
+
+export type TerminalExitEvent = {
+  id: number;
+  code: number | null;
+};
+
+export async function terminalCreate(projectDir: string, cols: number, rows: number): Promise<TerminalInfo> {
+  return invoke<TerminalInfo>("terminal_create", { projectDir, cols, rows });
+}
+
+export async function terminalWrite(id: number, data: string): Promise<void> {
+  return invoke<void>("terminal_write", { id, data });
+}
+
+export async function terminalResize(id: number, cols: number, rows: number): Promise<TerminalInfo> {
+  return invoke<TerminalInfo>("terminal_resize", { id, cols, rows });
+}
+
+export async function terminalKill(id: number): Promise<void> {
+  return invoke<void>("terminal_kill", { id });
+}
+
+export async function terminalList(projectDir?: string): Promise<TerminalInfo[]> {
+  return invoke<TerminalInfo[]>("terminal_list", { projectDir: projectDir ?? null });
+}
+
+export const TASK_OUTPUT_EVENT = "task://output";
+export const TASK_EXIT_EVENT = "task://exit";
+
+export type ProjectTask = {
+  id: string;
+  source: "npm" | "pnpm" | "yarn" | "bun" | "make" | "just" | "cargo";
+  name: string;
+  command: string[];
+  description: string | null;
+};
+
+export type TaskRun = {
+  runId: number;
+  taskId: string;
+  projectDir: string;
+  command: string[];
+  pid: number;
+  startedAt: number;
+};
+
+export type TaskOutputEvent = {
+  runId: number;
+  stream: "stdout" | "stderr";
+  line: string;
+};
+
+export type TaskExitEvent = {
+  runId: number;
+  taskId: string;
+  code: number | null;
+  durationMs: number;
+};
+
+export async function tasksDetect(projectDir: string): Promise<ProjectTask[]> {
+  return invoke<ProjectTask[]>("tasks_detect", { projectDir });
+}
+
+export async function taskRun(projectDir: string, taskId: string): Promise<TaskRun> {
+  return invoke<TaskRun>("task_run", { projectDir, taskId });
+}
+
+export async function taskStop(runId: number): Promise<void> {
+  return invoke<void>("task_stop", { runId });
+}
+
+export async function tasksRunning(): Promise<TaskRun[]> {
+  return invoke<TaskRun[]>("tasks_running");
+}
+
+export type LogLine = {
+  timestamp: string | null;
+  level: Exclude<LogLevel, "off">;
+  target: string;
+  message: string;
+  fields: Record<string, unknown>;
+  spans: string[];
+};
+
+export type LogFollowStatus = {
+  following: boolean;
+  levelFilter: string | null;
+  moduleFilter: string | null;
+};
+
+export const LOG_LINES_EVENT = "logs://lines";
+
+export async function appLogsTail(
+  lines?: number,
+  levelFilter?: LogLevel,
+  moduleFilter?: string,
+): Promise<LogLine[]> {
+  return invoke<LogLine[]>("app_logs_tail", {
+    lines: lines ?? null,
+    levelFilter: levelFilter ?? null,
+    moduleFilter: moduleFilter ?? null,
+  });
+}
+
+export async function appLogsFollowStart(levelFilter?: LogLevel, moduleFilter?: string): Promise<LogFollowStatus> {
+  return invoke<LogFollowStatus>("app_logs_follow_start", {
+    levelFilter: levelFilter ?? null,
+    moduleFilter: moduleFilter ?? null,
+  });
+}
+
+export async function appLogsFollowStop(): Promise<LogFollowStatus> {
+  return invoke<LogFollowStatus>("app_logs_follow_stop");
+}
+
+export async function appLogsFollowStatus(): Promise<LogFollowStatus> {
+  return invoke<LogFollowStatus>("app_logs_follow_status");
+}
+
+export type CrashReportSummary = {
+  id: string;
+  createdMs: number;
+  bytes: number;
+  message: string | null;
+};
+
+export async function crashReportsList(): Promise<CrashReportSummary[]> {
+  return invoke<CrashReportSummary[]>("crash_reports_list");
+}
+
+export async function crashReportRead(id: string): Promise<string> {
+  return invoke<string>("crash_report_read", { id });
+}
+
+/** Replacing an existing file needs confirmation; see `withConfirmation`. */
+export async function debugBundleCreate(
+  dest: string,
+  projectDir?: string,
+  confirmationId?: string,
+): Promise<ArchiveResult> {
+  return invoke<ArchiveResult>("debug_bundle_create", {
+    dest,
+    projectDir: projectDir ?? null,
+    confirmationId: confirmationId ?? null,
+  });
+}
+
+export type TelemetryStatus = {
+  enabled: boolean;
+  endpoint: string | null;
+  pending: Record<string, number>;
+};
+
+export async function telemetryGet(): Promise<TelemetryStatus> {
+  return invoke<TelemetryStatus>("telemetry_get");
+}
+
+export async function telemetrySet(enabled: boolean, endpoint?: string): Promise<TelemetryStatus> {
+  return invoke<TelemetryStatus>("telemetry_set", { enabled, endpoint: endpoint ?? null });
+}
+
+export async function telemetryFlush(): Promise<TelemetryStatus> {
+  return invoke<TelemetryStatus>("telemetry_flush");
+}
+
+export type CommandPerf = {
+  command: string;
+  calls: number;
+  errors: number;
+  errorRate: number;
+  p50Ms: number;
+  p95Ms: number;
+  maxMs: number;
+};
+
+export async function perfStats(): Promise<CommandPerf[]> {
+  flushPerfSamples();
+  return invoke<CommandPerf[]>("perf_stats");
+}
+
+export async function perfReset(): Promise<void> {
+  perfSamples = [];
+  return invoke<void>("perf_reset");
+}
+
+export type PortableStatus = {
+  enabled: boolean;
+  root: string | null;
+};
+
+export async function portableStatus(): Promise<PortableStatus> {
+  return invoke<PortableStatus>("portable_status");
+}
+
+export type OnboardingStep = "installEngine" | "configureProvider" | "openProject" | "done";
+
+export type OnboardingStatus = {
+  /** First step that still needs the user, or "done". */
+  current: OnboardingStep;
+  steps: Array<{
+    step: OnboardingStep;
+    /** Finished in onboarding. */
+    completed: boolean;
+    /** Already in place on this machine. */
+    detected: boolean;
+  }>;
+};
+
+export async function onboardingStatus(): Promise<OnboardingStatus> {
+  return invoke<OnboardingStatus>("onboarding_status");
+}
+
+export async function onboardingAdvance(step: OnboardingStep): Promise<OnboardingStatus> {
+  return invoke<OnboardingStatus>("onboarding_advance", { step });
+}
+
+export const DOWNLOAD_PROGRESS_EVENT = "download://progress";
+
+export type DownloadProgress = {
+  url: string;
+  downloaded: number;
+  total: number | null;
+  /** 1 for the first try; higher after a dropped connection was resumed. */
+  attempt: number;
+};
+
+export type ProxySettings = {
+  /** http:// or https:// proxy for downloads and updates; null uses HTTPS_PROXY from the environment. */
+  url: string | null;
+};
+
+export async function downloadProxyGet(): Promise<ProxySettings> {
+  return invoke<ProxySettings>("download_proxy_get");
+}
+
+export async function downloadProxySet(settings: ProxySettings): Promise<ProxySettings> {
+  return invoke<ProxySettings>("download_proxy_set", { settings });
+}
+
+export type CorsSettings = {
+  /** Origins the engine accepts on top of the app's own, e.g. https://openwork.example.com. */
+  extraOrigins: string[];
+};
+
+export async function corsSettingsGet(): Promise<CorsSettings> {
+  return invoke<CorsSettings>("cors_settings_get");
+}
+
+/** Applies on the next engine start. */
+export async function corsSettingsSet(settings: CorsSettings): Promise<CorsSettings> {
+  return invoke<CorsSettings>("cors_settings_set", { settings });
+}
+
+export type SchedulerJobId =
+  | "updateCheck"
+  | "configBackup"
+  | "engineHealth"
+  | "usageAggregation"
+  | "telemetryFlush";
+
+export type SchedulerJob = {
+  id: SchedulerJobId;
+  name: string;
+  description: string;
+  intervalSecs: number;
+  enabled: boolean;
+  lastRunMs: number | null;
+  /** Error from the last run, if it failed. */
+  lastError: string | null;
+};
+
+export async function schedulerJobsList(): Promise<SchedulerJob[]> {
+  return invoke<SchedulerJob[]>("scheduler_jobs_list");
+}
+
+export async function schedulerJobToggle(id: SchedulerJobId, enabled: boolean): Promise<SchedulerJob[]> {
+  return invoke<SchedulerJob[]>("scheduler_job_toggle", { id, enabled });
+}
+
+export type ControlStatus = {
+  enabled: boolean;
+  /** Started by `--headless` for this launch. */
+  headless: boolean;
+  running: boolean;
+  transport: "unix" | "tcp" | null;
+  address: string | null;
+  /** File with the address and the token requests must carry. */
+  endpointFile: string | null;
+};
+
+export async function controlStatus(): Promise<ControlStatus> {
+  return invoke<ControlStatus>("control_status");
+}
+
+export async function controlSetEnabled(enabled: boolean): Promise<ControlStatus> {
+  return invoke<ControlStatus>("control_set_enabled", { enabled });
+}
+
+export type ExternalTool = "claudeCode" | "cursor" | "continue";
+
+export type ExternalImportItem = {
+  kind: "mcpServer" | "instructions" | "ignore";
+  name: string;
+  /** The file it was read from. */
+  source: string;
+  /** Why it was left out, e.g. a server of the same name already exists. */
+  skipped: string | null;
+};
+
+export type ExternalImport = {
+  tool: ExternalTool;
+  items: ExternalImportItem[];
+  /** Files that would change (or changed), with unified diffs. */
+  changes: { path: string; exists: boolean; diff: string }[];
+  applied: boolean;
+  notes: string[];
+};
+
+export async function importExternalConfig(
+  projectDir: string,
+  tool: ExternalTool,
+  options?: { apply?: boolean; confirmationId?: string },
+): Promise<ExternalImport> {
+  return invoke<ExternalImport>("import_external_config", {
+    projectDir,
+    tool,
+    apply: options?.apply ?? null,
+    confirmationId: options?.confirmationId ?? null,
+  });
+}
+
+export type LocalRuntimeKind = "ollama" | "lmStudio";
+
+export type LocalRuntime = {
+  kind: LocalRuntimeKind;
+  name: string;
+  baseUrl: string;
+  running: boolean;
+  version: string | null;
+  models: { id: string; size: number | null; parameters: string | null }[];
+  /** Why the runtime didn't answer, or answered unexpectedly. */
+  error: string | null;
+};
+
+export type LocalProviderResult = {
+  path: string;
+  provider: string;
+  /** Models added; ones already in the config are left as they are. */
+  added: string[];
+};
+
+export async function localModelsList(): Promise<LocalRuntime[]> {
+  return invoke<LocalRuntime[]>("local_models_list");
+}
+
+export async function localModelsConfigure(
+  scope: "project" | "global",
+  projectDir: string,
+  runtime: LocalRuntimeKind,
+  options?: { models?: string[]; confirmationId?: string },
+): Promise<LocalProviderResult> {
+  return invoke<LocalProviderResult>("local_models_configure", {
+    scope,
+    projectDir,
+    runtime,
+    models: options?.models ?? null,
+    confirmationId: options?.confirmationId ?? null,
+  });
+}
+
+export type WebhookEvent = "engineCrashed" | "taskFinished" | "budgetExceeded" | "test";
+
+export type Webhook = {
+  /** Assigned on save when empty. */
+  id: string;
+  url: string;
+  /** Signs each body as `X-OpenWork-Signature: sha256=<hmac>` when set. */
+  secret: string | null;
+  events: WebhookEvent[];
+  enabled: boolean;
+};
+
+export type WebhookSettings = {
+  hooks: Webhook[];
+  /** Tasks shorter than this don't trigger `taskFinished`. */
+  longTaskSecs: number;
+};
+
+export type WebhookDelivery = {
+  ok: boolean;
+  status: number | null;
+  error: string | null;
+};
+
+export async function webhooksGet(): Promise<WebhookSettings> {
+  return invoke<WebhookSettings>("webhooks_get");
+}
+
+export async function webhooksSet(settings: WebhookSettings): Promise<WebhookSettings> {
+  return invoke<WebhookSettings>("webhooks_set", { settings });
+}
+
+export async function webhookTest(id: string): Promise<WebhookDelivery> {
+  return invoke<WebhookDelivery>("webhook_test", { id });
+}
+
+export type ConfigSchema = {
+  url: string;
+  schema: Record<string, unknown>;
+  fetchedAt: number;
+  /** An old cached copy, used because refreshing it failed. */
+  stale: boolean;
+};
+
+export type SchemaCompletion = {
+  key: string;
+  description: string | null;
+  types: string[];
+  required: boolean;
+  deprecated: boolean;
+};
+
+export type SchemaInfo = {
+  path: string[];
+  description: string | null;
+  types: string[];
+  /** Allowed values, from `enum` and `const`. */
+  values: unknown[];
+  default: unknown | null;
+  deprecated: boolean;
+  completions: SchemaCompletion[];
+  /** Whether keys beyond `completions` are accepted, as in `provider`. */
+  otherKeys: boolean;
+};
+
+export type ConfigDiagnostic = {
+  path: string[];
+  message: string;
+  severity: "error" | "warning";
+  /** Set for syntax errors, 1-based. */
+  line: number | null;
+  column: number | null;
+};
+
+export async function configSchemaGet(force?: boolean): Promise<ConfigSchema> {
+  return invoke<ConfigSchema>("config_schema_get", { force: force ?? null });
+}
+
+/** `path` is the keys from the root, e.g. `["provider", "ollama", "options"]`. */
+export async function configSchemaDescribe(path: string[]): Promise<SchemaInfo | null> {
+  return invoke<SchemaInfo | null>("config_schema_describe", { path });
+}
+
+export async function configSchemaValidate(text: string): Promise<ConfigDiagnostic[]> {
+  return invoke<ConfigDiagnostic[]>("config_schema_validate", { text });
+}
+
+export type RemoteProject = {
+  id: string;
+  name: string;
+  /** `host`, `user@host` or an alias from `~/.ssh/config`. */
+  host: string;
+  port: number | null;
+  identityFile: string | null;
+  /** Absolute, or relative to the login directory. */
+  remoteDir: string;
+  /** The mirror, opened as the project. */
+  localDir: string;
+  lastSyncMs: number | null;
+};
+
+export type LocalChange = {
+  path: string;
+  kind: "added" | "modified" | "deleted";
+};
+
+export type SyncConflict = {
+  path: string;
+  /** The remote version, kept aside; null when it was deleted there. */
+  remoteCopy: string | null;
+};
+
+export type SyncResult = {
+  updated: string[];
+  deleted: string[];
+  conflicts: SyncConflict[];
+  /** Local changes a pull left for the next push. */
+  pending: LocalChange[];
+};
+
+export type RemoteStatus = {
+  changes: LocalChange[];
+  conflicts: string[];
+};
+
+export async function remoteProjectAdd(input: {
+  name?: string;
+  host: string;
+  port?: number;
+  identityFile?: string;
+  remoteDir: string;
+  localDir: string;
+}): Promise<RemoteProject> {
+  return invoke<RemoteProject>("remote_project_add", {
+    name: input.name ?? null,
+    host: input.host,
+    port: input.port ?? null,
+    identityFile: input.identityFile ?? null,
+    remoteDir: input.remoteDir,
+    localDir: input.localDir,
+  });
+}
+
+export async function remoteProjectList(): Promise<RemoteProject[]> {
+  return invoke<RemoteProject[]>("remote_project_list");
+}
+
+export async function remoteProjectStatus(id: string): Promise<RemoteStatus> {
+  return invoke<RemoteStatus>("remote_project_status", { id });
+}
+
+export async function remoteProjectPull(id: string): Promise<SyncResult> {
+  return invoke<SyncResult>("remote_project_pull", { id });
+}
+
+export async function remoteProjectPush(id: string, confirmationId?: string): Promise<SyncResult> {
+  return invoke<SyncResult>("remote_project_push", { id, confirmationId: confirmationId ?? null });
+}
+
+export async function remoteProjectResolve(
+  id: string,
+  path: string,
+  keep: "local" | "remote",
+): Promise<RemoteStatus> {
+  return invoke<RemoteStatus>("remote_project_resolve", { id, path, keep });
+}
+
+export async function remoteProjectRemove(
+  id: string,
+  options?: { deleteMirror?: boolean; confirmationId?: string },
+): Promise<RemoteProject[]> {
+  return invoke<RemoteProject[]>("remote_project_remove", {
+    id,
+    deleteMirror: options?.deleteMirror ?? null,
+    confirmationId: options?.confirmationId ?? null,
+  });
+}
+
+export type InstructionFormat = "claude" | "cursor" | "copilot";
+
+export type InstructionsExport = {
+  format: InstructionFormat;
+  path: string;
+  /** The files merged, relative to the project where possible. */
+  sources: string[];
+  /** Null when the target is already up to date. */
+  change: { path: string; exists: boolean; diff: string } | null;
+  applied: boolean;
+  notes: string[];
+};
+
+export async function exportInstructions(
+  projectDir: string,
+  format: InstructionFormat,
+  options?: { apply?: boolean; confirmationId?: string },
+): Promise<InstructionsExport> {
+  return invoke<InstructionsExport>("export_instructions", {
+    projectDir,
+    format,
+    apply: options?.apply ?? null,
+    confirmationId: options?.confirmationId ?? null,
+  });
+}
+
+export type ProviderValidation = {
+  provider: string;
+  /** The provider accepted the key. */
+  valid: boolean;
+  keySource: "argument" | "auth" | "env";
+  status: number;
+  message: string;
+  /** Models the key can use, when the check lists them. */
+  models: string[];
+  /** Quota, credit and rate limit details the provider reported. */
+  hints: string[];
+};
+
+/** Without `key`, checks the key opencode would use (auth.json, then env). */
+export async function providerValidate(provider: string, key?: string): Promise<ProviderValidation> {
+  return invoke<ProviderValidation>("provider_validate", { provider, key: key ?? null });
+}
+
+export type ModelPricing = {
+  /** Dollars per million tokens. */
+  input: number;
+  output: number;
+  cacheRead: number | null;
+  cacheWrite: number | null;
+};
+
+export type ModelInfo = {
+  providerId: string;
+  providerName: string;
+  id: string;
+  name: string;
+  contextWindow: number | null;
+  outputLimit: number | null;
+  pricing: ModelPricing | null;
+  reasoning: boolean;
+  toolCall: boolean;
+  attachment: boolean;
+  /** The engine's default model for its provider. */
+  isDefault: boolean;
+};
+
+export type ModelCatalog = {
+  /** `providers` when no engine was running for the project. */
+  source: "engine" | "providers";
+  fetchedAt: number;
+  models: ModelInfo[];
+  notes: string[];
+};
+
+/** Cached for five minutes per project unless `refresh`. */
+export async function modelsList(projectDir: string, refresh?: boolean): Promise<ModelCatalog> {
+  return invoke<ModelCatalog>("models_list", { projectDir, refresh: refresh ?? null });
+}
+
+export type HookStage = "preStart" | "postReady" | "postStop";
+
+export type EngineHook = {
+  /** Generated when empty. */
+  id: string;
+  stage: HookStage;
+  /** A command line for the user's shell, run in the project directory. */
+  command: string;
+  timeoutSecs: number;
+  /** `preStart` only: a failure or timeout cancels the engine start. */
+  required: boolean;
+  enabled: boolean;
+};
+
+/** Emitted as `engine://hook` after each hook runs. */
+export type HookRun = {
+  projectDir: string;
+  hookId: string;
+  stage: HookStage;
+  command: string;
+  ok: boolean;
+  /** Null when the hook couldn't start or timed out. */
+  status: number | null;
+  durationMs: number;
+  stdout: string;
+  stderr: string;
+  error: string | null;
+};
+
+export async function engineHooksGet(projectDir: string): Promise<EngineHook[]> {
+  return invoke<EngineHook[]>("engine_hooks_get", { projectDir });
+}
+
+/** Replaces the project's hooks; new commands need confirmation. */
+export async function engineHooksSet(
+  projectDir: string,
+  hooks: EngineHook[],
+  confirmationId?: string,
+): Promise<EngineHook[]> {
+  return invoke<EngineHook[]>("engine_hooks_set", {
+    projectDir,
+    hooks,
+    confirmationId: confirmationId ?? null,
+  });
+}
+
+/** Runs one saved hook now and waits for it. */
+export async function engineHookRun(projectDir: string, id: string): Promise<HookRun> {
+  return invoke<HookRun>("engine_hook_run", { projectDir, id });
+}
+
+export type OpencodeSnapshot = {
+  id: string;
+  projectDir: string;
+  label: string;
+  createdAt: number;
+  files: number;
+  bytes: number;
+  /** Taken by a restore before it replaced the folder. */
+  automatic: boolean;
+};
+
+export type SnapshotRestore = {
+  snapshot: OpencodeSnapshot;
+  /** The folder as it was before the restore; null when there was none. */
+  previous: OpencodeSnapshot | null;
+  /** The project's engine only sees the change after a restart. */
+  restartNeeded: boolean;
+};
+
+/** Zips the project's `.opencode` folder, without `node_modules`. */
+export async function opencodeDirSnapshot(projectDir: string, label: string): Promise<OpencodeSnapshot> {
+  return invoke<OpencodeSnapshot>("opencode_dir_snapshot", { projectDir, label });
+}
+
+/** Newest first; only `projectDir`'s when given. */
+export async function opencodeDirSnapshots(projectDir?: string): Promise<OpencodeSnapshot[]> {
+  return invoke<OpencodeSnapshot[]>("opencode_dir_snapshots", { projectDir: projectDir ?? null });
+}
+
+export async function opencodeDirRestore(snapshotId: string, confirmationId?: string): Promise<SnapshotRestore> {
+  return invoke<SnapshotRestore>("opencode_dir_restore", {
+    snapshotId,
+    confirmationId: confirmationId ?? null,
+  });
+}
+
+export async function opencodeDirSnapshotDelete(snapshotId: string): Promise<void> {
+  return invoke<void>("opencode_dir_snapshot_delete", { snapshotId });
+}
+
+export type BackupScope = "app" | "global" | "project";
+
+export type BackupFile = {
+  /** Path inside the backup. */
+  name: string;
+  scope: BackupScope;
+  /** Where the file is restored to. */
+  source: string;
+  projectDir: string | null;
+  bytes: number;
+};
+
+export type BackupInfo = {
+  id: string;
+  createdAt: number;
+  files: BackupFile[];
+};
+
+export type BackupRestore = {
+  restored: string[];
+  /** A backup of the files as they were; null when the newest backup already matched them. */
+  previous: BackupInfo | null;
+  /** OpenWork settings were restored and apply after a restart. */
+  restartNeeded: boolean;
+};
+
+export type BackupSettings = {
+  /** The newest backups always kept. */
+  keepLatest: number;
+  /** Also keeps the newest backup of each of the last this many days. */
+  keepDays: number;
+};
+
+/** Newest first. */
+export async function backupsList(): Promise<BackupInfo[]> {
+  return invoke<BackupInfo[]>("backups_list");
+}
+
+/** Restores `files` (backup names) from the backup, or all of them. */
+export async function backupRestore(
+  backupId: string,
+  options?: { files?: string[]; confirmationId?: string },
+): Promise<BackupRestore> {
+  return invoke<BackupRestore>("backup_restore", {
+    backupId,
+    files: options?.files ?? null,
+    confirmationId: options?.confirmationId ?? null,
+  });
+}
+
+export async function backupSettingsGet(): Promise<BackupSettings> {
+  return invoke<BackupSettings>("backup_settings_get");
+}
+
+export async function backupSettingsSet(settings: BackupSettings): Promise<BackupSettings> {
+  return invoke<BackupSettings>("backup_settings_set", { settings });
+}
+
+export type Locale = "en" | "es" | "pt" | "fr" | "de";
+
+/** The locale backend errors are reported in. */
+export async function getLocale(): Promise<Locale> {
+  return invoke<Locale>("get_locale");
+}
+
+/** Accepts tags like `pt-BR`; without one, follows the system again. */
+export async function setLocale(locale?: string): Promise<Locale> {
+  return invoke<Locale>("set_locale", { locale: locale ?? null });
+}
+
+export type ExtensionCapability = "network" | "projectRead" | "projectWrite" | "runProcess" | "engine";
+
+export type ExtensionMethod = {
+  name: string;
+  description: string;
+  capabilities: ExtensionCapability[];
+};
+
+export type ExtensionInfo = {
+  manifest: {
+    id: string;
+    name: string;
+    version: string;
+    description: string;
+    capabilities: ExtensionCapability[];
+    methods: ExtensionMethod[];
+  };
+  enabled: boolean;
+  granted: ExtensionCapability[];
+  /** Capabilities the manifest asks for that aren't granted. */
+  missing: ExtensionCapability[];
+};
+
+/** Extensions compiled into this build. */
+export async function extensionsList(): Promise<ExtensionInfo[]> {
+  return invoke<ExtensionInfo[]>("extensions_list");
+}
+
+/** Sets what the extension may do; new grants need confirmation. */
+export async function extensionGrant(
+  id: string,
+  capabilities: ExtensionCapability[],
+  confirmationId?: string,
+): Promise<ExtensionInfo> {
+  return invoke<ExtensionInfo>("extension_grant", {
+    id,
+    capabilities,
+    confirmationId: confirmationId ?? null,
+  });
+}
+
+export async function extensionSetEnabled(id: string, enabled: boolean): Promise<ExtensionInfo> {
+  return invoke<ExtensionInfo>("extension_set_enabled", { id, enabled });
+}
+
+/** Fails with `NEEDS_APPROVAL` (missing capabilities in `details`) until granted. */
+export async function extensionCall<T = unknown>(
+  id: string,
+  method: string,
+  params?: unknown,
+  projectDir?: string,
+): Promise<T> {
+  return invoke<T>("extension_call", {
+    id,
+    method,
+    params: params ?? null,
+    projectDir: projectDir ?? null,
+  });
+}
+
+export const NETWORK_STATUS_EVENT = "network://status";
+
+/** Emitted as `network://status` when connectivity changes. */
+export type NetworkStatus = {
+  online: boolean;
+  /** Unix ms of the last probe; null before the first one. */
+  checkedAt: number | null;
+  changedAt: number | null;
+};
+
+/** The last known connectivity; `refresh` probes first. */
+export async function networkStatus(refresh?: boolean): Promise<NetworkStatus> {
+  return invoke<NetworkStatus>("network_status", { refresh: refresh ?? null });
+}
+
+export type EngineRelease = {
+  version: string;
+  name: string | null;
+  publishedAt: string | null;
+  /** Markdown. */
+  body: string;
+  url: string;
+  prerelease: boolean;
+};
+
+export type EngineReleaseNotes = {
+  fromVersion: string;
+  toVersion: string;
+  /** Newest first, after `fromVersion` up to and including `toVersion`. */
+  releases: EngineRelease[];
+  fetchedAt: number;
+  /** The list couldn't be refreshed and may miss recent releases. */
+  stale: boolean;
+};
+
+/** Defaults to the installed opencode version and the latest release. */
+export async function engineReleaseNotes(
+  fromVersion?: string,
+  toVersion?: string,
+): Promise<EngineReleaseNotes> {
+  return invoke<EngineReleaseNotes>("engine_release_notes", {
+    fromVersion: fromVersion ?? null,
+    toVersion: toVersion ?? null,
+  });
+}
+
+export type EngineEnvVar = {
+  name: string;
+  value: string;
+  masked: boolean;
+  /** `openWork` also covers variables OpenWork changed, like `PATH`. */
+  source: "inherited" | "dotenv" | "envProfile" | "openWork";
+};
+
+export type EngineEnvPreview = {
+  program: string;
+  args: string[];
+  cwd: string;
+  env: EngineEnvVar[];
+  /** Variables OpenWork has that the env policy keeps from the engine. */
+  removed: string[];
+  devcontainer: boolean;
+  permissionProfile: string | null;
+  envProfile: string | null;
+  configOverlay: unknown | null;
+  configFiles: { scope: string; path: string; exists: boolean }[];
+  notes: string[];
+};
+
+/** What an engine for the project would be started with; `profile` previews another permission profile. */
+export async function engineEnvPreview(projectDir: string, profile?: string): Promise<EngineEnvPreview> {
+  return invoke<EngineEnvPreview>("engine_env_preview", { projectDir, profile: profile ?? null });
 }